@@ -0,0 +1,170 @@
+//! The ranges of lines of `--between`, each running from a line matching a
+//! start pattern to the next line matching an end pattern, as sed's
+//! `/start/,/end/` addresses them.
+
+use crate::{expr::Expr, line_matches};
+
+/// Which lines of an input are inside ranges, decided a line at a time.
+///
+/// A range starts at a line matching the start pattern and takes in every
+/// line up to and including the next one matching the end pattern, or the
+/// rest of the input if none does. Ranges do not nest: a start inside a
+/// range is an ordinary line of it, and the first end closes it. The end is
+/// only looked for after the line starting the range, so that a start and
+/// an end that are the same pattern, such as `---`, enclose the lines
+/// between two of them.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::between::Between;
+///
+/// let mut between = Between::new("BEGIN", "END");
+/// let lines = ["a", "BEGIN", "b", "END", "c"];
+/// let inside: Vec<bool> = lines
+///     .iter()
+///     .map(|line| between.select(line, false))
+///     .collect();
+/// assert_eq!(inside, [false, true, true, true, false]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Between {
+    start: String,
+    end: String,
+    /// The two patterns as one expression, which is what is highlighted.
+    delimiters: Expr,
+    inside: bool,
+}
+
+impl Between {
+    /// Selects the lines from those matching `start` to those matching
+    /// `end`, with no range open yet.
+    pub fn new(start: &str, end: &str) -> Between {
+        Between {
+            start: start.to_string(),
+            end: end.to_string(),
+            delimiters: Expr::Or(
+                Box::new(Expr::Literal(start.to_string())),
+                Box::new(Expr::Literal(end.to_string())),
+            ),
+            inside: false,
+        }
+    }
+
+    /// The pattern starting a range.
+    pub fn start(&self) -> &str {
+        &self.start
+    }
+
+    /// The pattern ending a range.
+    pub fn end(&self) -> &str {
+        &self.end
+    }
+
+    /// The occurrences of either pattern, whether or not they start or end
+    /// a range, which is how the lines of ranges are highlighted.
+    pub fn delimiters(&self) -> &Expr {
+        &self.delimiters
+    }
+
+    /// Returns whether `line`, the next line of the input, is inside a
+    /// range, ignoring case if `ignore_case`.
+    pub fn select(&mut self, line: &str, ignore_case: bool) -> bool {
+        if self.inside {
+            self.inside = !line_matches(&self.end, line, ignore_case);
+            true
+        } else {
+            self.inside = line_matches(&self.start, line, ignore_case);
+            self.inside
+        }
+    }
+}
+
+/// Returns the lines of `contents` inside the ranges running from a line
+/// matching `start` to the next one matching `end`, as [`Between`] selects
+/// them.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::between::between;
+///
+/// let contents = "x\n[a]\n1\n[/a]\ny\n[a]\n2";
+/// let found: Vec<&str> = between("[a]", "[/a]", contents, false).collect();
+/// assert_eq!(found, ["[a]", "1", "[/a]", "[a]", "2"]);
+/// ```
+pub fn between<'a>(
+    start: &str,
+    end: &str,
+    contents: &'a str,
+    ignore_case: bool,
+) -> impl Iterator<Item = &'a str> {
+    let mut between = Between::new(start, end);
+    contents
+        .lines()
+        .filter(move |line| between.select(line, ignore_case))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Matcher;
+
+    fn found<'a>(start: &str, end: &str, contents: &'a str) -> Vec<&'a str> {
+        between(start, end, contents, false).collect()
+    }
+
+    #[test]
+    fn every_range_is_selected_and_the_last_may_run_to_the_end() {
+        let contents = "a\nBEGIN 1\nb\nEND 1\nc\nBEGIN 2\nd\nEND 2\ne\nBEGIN 3\nf";
+        assert_eq!(
+            found("BEGIN", "END", contents),
+            [
+                "BEGIN 1", "b", "END 1", "BEGIN 2", "d", "END 2", "BEGIN 3", "f"
+            ]
+        );
+        assert!(found("BEGIN", "END", "a\nEND\nb").is_empty());
+    }
+
+    #[test]
+    fn back_to_back_ranges_are_both_selected() {
+        assert_eq!(
+            found("BEGIN", "END", "BEGIN\nEND\nBEGIN\nEND\nx"),
+            ["BEGIN", "END", "BEGIN", "END"]
+        );
+        // An end immediately following a start closes the range at once.
+        assert_eq!(found("<", ">", "<\n>\n>\n"), ["<", ">"]);
+    }
+
+    #[test]
+    fn a_start_inside_a_range_does_not_nest() {
+        assert_eq!(
+            found("BEGIN", "END", "BEGIN a\nBEGIN b\nx\nEND\ny\nEND\n"),
+            ["BEGIN a", "BEGIN b", "x", "END"]
+        );
+    }
+
+    #[test]
+    fn the_end_is_only_looked_for_after_the_start() {
+        let contents = "title\n---\nlayout: post\n---\nbody\n";
+        assert_eq!(
+            found("---", "---", contents),
+            ["---", "layout: post", "---"]
+        );
+        assert_eq!(
+            found("[", "]", "[one]\ntwo\n]\nthree"),
+            ["[one]", "two", "]"]
+        );
+    }
+
+    #[test]
+    fn patterns_follow_the_case_setting() {
+        let mut between = Between::new("begin", "end");
+        assert!(!between.select("BEGIN", false));
+        assert!(between.select("BEGIN", true));
+        assert!(between.select("End", true));
+        assert!(!between.select("after", true));
+        assert_eq!(
+            between.delimiters().match_ranges("begin, end", false),
+            vec![0..5, 7..10]
+        );
+    }
+}
@@ -0,0 +1,147 @@
+//! Matching the query byte for byte, for `--no-unicode`: lines are never
+//! checked to be UTF-8, and `-i` folds ASCII letters only, so that large
+//! ASCII inputs are searched without the cost of either.
+//!
+//! On ASCII text, which is all that this is meant for, the results are
+//! those of the query matched as a string. Other text is matched as its
+//! bytes: a query only matches the same bytes, and letters outside ASCII
+//! match only themselves.
+
+use std::ops::Range;
+
+use crate::Matcher;
+
+/// Returns the offset of the first occurrence of `needle` in `haystack`,
+/// if there is one, ignoring the case of ASCII letters if `ignore_case`.
+/// An empty `needle` occurs at 0.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::bytes::find;
+///
+/// assert_eq!(find(b"a Rust crate", b"rust", true), Some(2));
+/// assert_eq!(find(b"a Rust crate", b"rust", false), None);
+/// assert_eq!(find("CAFÉ".as_bytes(), "café".as_bytes(), true), None);
+/// ```
+pub fn find(haystack: &[u8], needle: &[u8], ignore_case: bool) -> Option<usize> {
+    let Some(&first) = needle.first() else {
+        return Some(0);
+    };
+    let (lower, upper) = if ignore_case {
+        (first.to_ascii_lowercase(), first.to_ascii_uppercase())
+    } else {
+        (first, first)
+    };
+    let last = haystack.len().checked_sub(needle.len())?;
+    let mut start = 0;
+    while start <= last {
+        let at = start
+            + haystack[start..=last]
+                .iter()
+                .position(|&byte| byte == lower || byte == upper)?;
+        let candidate = &haystack[at..at + needle.len()];
+        let found = if ignore_case {
+            candidate.eq_ignore_ascii_case(needle)
+        } else {
+            candidate == needle
+        };
+        if found {
+            return Some(at);
+        }
+        start = at + 1;
+    }
+    None
+}
+
+/// Returns the ranges of every occurrence of `needle` in `haystack`, as
+/// [`find`] finds them, from left to right and never overlapping. An empty
+/// `needle` has none.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::bytes::match_ranges;
+///
+/// assert_eq!(match_ranges(b"aAaa", b"aa", true), vec![0..2, 2..4]);
+/// ```
+pub fn match_ranges(haystack: &[u8], needle: &[u8], ignore_case: bool) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    if needle.is_empty() {
+        return ranges;
+    }
+    let mut start = 0;
+    while let Some(at) = find(&haystack[start..], needle, ignore_case) {
+        ranges.push(start + at..start + at + needle.len());
+        start += at + needle.len();
+    }
+    ranges
+}
+
+/// A query matched by [`find`] against the bytes of each line.
+///
+/// The ranges found are on character boundaries of the line: the query is
+/// UTF-8 too, and folding ASCII letters leaves every other byte as it is.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::{Matcher, bytes::ByteQuery};
+///
+/// let query = ByteQuery::new("é");
+/// assert_eq!(query.match_ranges("É é", true), vec![3..5]);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct ByteQuery<'q> {
+    query: &'q [u8],
+}
+
+impl<'q> ByteQuery<'q> {
+    pub fn new(query: &'q str) -> ByteQuery<'q> {
+        ByteQuery {
+            query: query.as_bytes(),
+        }
+    }
+}
+
+impl Matcher for ByteQuery<'_> {
+    fn is_match(&self, line: &str, ignore_case: bool) -> bool {
+        find(line.as_bytes(), self.query, ignore_case).is_some()
+    }
+
+    fn match_ranges(&self, line: &str, ignore_case: bool) -> Vec<Range<usize>> {
+        match_ranges(line.as_bytes(), self.query, ignore_case)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn occurrences_are_found_byte_for_byte() {
+        assert_eq!(find(b"", b"", false), Some(0));
+        assert_eq!(find(b"ab", b"abc", false), None);
+        assert_eq!(find(b"xxERRor", b"error", true), Some(2));
+        assert_eq!(find(b"eeerror", b"error", false), Some(2));
+        assert_eq!(find(b"\xff\xfeerr\xff", b"err\xff", false), Some(2));
+        assert_eq!(
+            match_ranges(b"aaaa", b"", false),
+            Vec::<Range<usize>>::new()
+        );
+        assert_eq!(match_ranges(b"aaa", b"aa", false), vec![0..2]);
+        assert_eq!(
+            match_ranges(b"Error: ERROR error", b"error", true),
+            vec![0..5, 7..12, 13..18]
+        );
+    }
+
+    #[test]
+    fn only_ascii_letters_are_folded() {
+        let query = ByteQuery::new("straße");
+        assert!(query.is_match("STRAßE", true));
+        assert!(!query.is_match("STRASSE", true));
+        assert!(!ByteQuery::new("é").is_match("É", true));
+        assert_eq!(
+            ByteQuery::new("k").match_ranges("\u{212a} K k", true),
+            vec![4..5, 6..7]
+        );
+    }
+}
@@ -0,0 +1,2706 @@
+//! Running a search as a [`Config`] describes it: walking its inputs,
+//! searching each of them, and printing what was found, which is all the
+//! `minigrep` binary does once it has parsed its arguments.
+
+#[cfg(feature = "parallel")]
+use crate::parallel::{self, Batches, Part};
+#[cfg(feature = "tar")]
+use crate::tar::{self, TarArchive};
+#[cfg(feature = "zip")]
+use crate::zip::{self, ZipArchive};
+use crate::{
+    Matcher, ReadRecords,
+    between::Between,
+    bytes,
+    color::{self, Theme},
+    config::{Config, First, OutputFormat, Sort},
+    context::ContextUntil,
+    csv, decompress, dirs,
+    edit::{self, EditOptions},
+    encoding::{self, Decoder},
+    error::MinigrepError,
+    grep::{FileMatches, MatchedLine},
+    hex,
+    log::{self, Level},
+    mmap::{self, FileFacts, Mmap, ReadMethod},
+    output::Output,
+    pager::Pager,
+    preprocess::Preprocessed,
+    progress::Progress,
+    read_records,
+    render::{
+        Fields, LineKind, LineMatch, LineRuns, Palette, Plain, Prefix, format_run, render_match,
+    },
+    replace,
+    resume::{self, LineEnds, Start},
+    snippet::{self, ELLIPSIS},
+    stats::SearchStats,
+    strings,
+    tally::Tally,
+    types,
+    walk::{self, SkipReason, WalkOptions},
+    watch::{self, Event, Follower},
+    wc::{self, WordCount},
+};
+#[cfg(feature = "json")]
+use crate::{json, sarif};
+#[cfg(any(feature = "zip", feature = "tar"))]
+use std::mem;
+use std::{
+    borrow::Cow,
+    collections::{HashSet, VecDeque},
+    ffi::OsStr,
+    fmt,
+    fs::{self, File},
+    io::{self, BufRead, BufReader, BufWriter, IsTerminal, Read, Seek, SeekFrom, Write},
+    ops::Range,
+    path::{self, Path, PathBuf},
+    process::Stdio,
+    sync::{
+        Mutex, MutexGuard, PoisonError,
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+    },
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+/// Returns a function wrapping an I/O error on the input called `name`.
+fn input_error(name: &str) -> impl Fn(io::Error) -> MinigrepError + Copy + '_ {
+    move |error| MinigrepError::new(name, error)
+}
+
+/// What a run that was not cut short by an error found, from which the
+/// exit status is decided.
+///
+/// - `found`: Whether any line was selected, or with `--files`, any file
+///   listed, or with `--in-place`, any replacement made.
+/// - `matched_lines`: How many lines were selected in all inputs.
+/// - `failed_inputs`: How many inputs could not be searched for an error.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RunSummary {
+    pub found: bool,
+    pub matched_lines: u64,
+    pub failed_inputs: u64,
+}
+
+/// Executes the search process.
+///
+/// Reads each input indicated by the config, performs the search
+/// (case-sensitive or insensitive), and prints all matching lines,
+/// or only the file names when `-l`/`-L` is given. With `-r`, directories
+/// are searched recursively. With `--in-place`, each file is rewritten
+/// instead, and only its number of replacements is printed.
+///
+/// All output goes through a single locked handle on standard output:
+/// line-buffered when it is a terminal or `--line-buffered` is given,
+/// and block-buffered through a `BufWriter` otherwise, as
+/// [`run_with_writer`] describes. With `-j`, files are
+/// searched on several threads, each buffering a file's results until the
+/// file is done, and `--sort=path` decides whether the files are printed in
+/// order of their paths or as they finish. With `--stats` and `--timings`,
+/// summaries follow on standard error once the output has been flushed.
+///
+/// Inputs that cannot be walked, read, or rewritten are reported on standard
+/// error, unless `-s` is given, and skipped. With `-q`, nothing is printed
+/// to standard output at all.
+///
+/// With a `pager`, output to a terminal goes through the pager instead, as
+/// it would to the terminal, colors included, and without `--progress`;
+/// the run ends once the pager has been quit. A pager that cannot be
+/// started is warned about, and the output printed directly. Output that
+/// is not to a terminal is never paged.
+///
+/// # Returns
+/// What the search found, as [`RunSummary`] describes.
+///
+/// # Errors
+/// Returns a [`MinigrepError::Output`] if writing the output fails, or a
+/// [`MinigrepError`] naming the list of `--files-from` if it cannot be read.
+pub fn run(config: Config) -> Result<RunSummary, MinigrepError> {
+    let stdout = io::stdout();
+    let is_terminal = stdout.is_terminal();
+    // A lock on standard error could not be shared with the search threads,
+    // so each message takes its own.
+    let mut stderr = io::stderr();
+    let progress = config.progress && stderr.is_terminal();
+    if config.quiet {
+        return run_to(&config, io::sink(), false, &mut stderr, progress, None);
+    }
+    if is_terminal && let Some(command) = &config.pager {
+        match Pager::spawn(command, config.less.as_deref(), Stdio::inherit()) {
+            Ok(mut pager) => {
+                let searched = run_to(&config, &mut pager, true, &mut stderr, false, None);
+                pager.finish()?;
+                return searched;
+            }
+            Err(error) if !config.no_messages => {
+                // Nothing else is writing to standard error yet.
+                let _ = writeln!(stderr, "minigrep: {error}; printing directly");
+            }
+            Err(_) => {}
+        }
+    }
+    if is_terminal {
+        run_to(&config, stdout.lock(), true, &mut stderr, progress, None)
+    } else {
+        let stdout = BufWriter::new(stdout.lock());
+        run_to(&config, stdout, false, &mut stderr, progress, None)
+    }
+}
+
+/// Executes the search process like [`run`], printing the results to `out`
+/// and warnings and summaries to `err` instead.
+///
+/// Neither is taken to be a terminal: output is only colored with
+/// `--color=always` or with `CLICOLOR_FORCE` set in the environment
+/// `config` was built in, is not grouped under headings unless `--heading`
+/// is given, and is block-buffered unless `--line-buffered` is, and there
+/// is no `--progress` line. `err` is written from every thread searching, so it
+/// must be [`Send`]. With `-q`, nothing is written to `out`.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::{Config, run_with_writer};
+/// use std::fs;
+///
+/// let path = std::env::temp_dir().join("run_with_writer.txt");
+/// fs::write(&path, "Rust\nrust is fast\n").unwrap();
+/// let config = Config::new("rust", vec![path]);
+/// let (mut out, mut err) = (Vec::new(), Vec::new());
+/// let summary = run_with_writer(&config, &mut out, &mut err).unwrap();
+/// assert_eq!(out, b"rust is fast\n");
+/// assert_eq!(summary.matched_lines, 1);
+/// ```
+///
+/// # Errors
+/// Returns a [`MinigrepError::Output`] if writing the output fails, or a
+/// [`MinigrepError`] naming the list of `--files-from` if it cannot be read.
+pub fn run_with_writer(
+    config: &Config,
+    out: &mut impl Write,
+    err: &mut (impl Write + Send),
+) -> Result<RunSummary, MinigrepError> {
+    if config.quiet {
+        return run_to(config, io::sink(), false, err, false, None);
+    }
+    run_to(config, out, false, err, false, None)
+}
+
+/// Searches as [`run`] does, printing nothing, and returns what it
+/// gathered, for the [`grep`](crate::grep) module.
+///
+/// # Errors
+/// Returns a [`MinigrepError`] if the search cannot be run at all.
+pub(crate) fn collect(config: &Config) -> Result<Collected, MinigrepError> {
+    let mut collected = Collected::default();
+    run_to(
+        config,
+        io::sink(),
+        false,
+        &mut io::sink(),
+        false,
+        Some(&mut collected),
+    )?;
+    Ok(collected)
+}
+
+/// Searches the one file at `path` as [`run`] would, printing nothing, and
+/// returns its selected lines, for [`search_in_file`](crate::search_in_file).
+///
+/// # Errors
+/// Returns a [`MinigrepError`] if the file cannot be searched, or if it is
+/// skipped, for the reason it is.
+pub(crate) fn search_file(config: &Config, path: &Path) -> Result<FileMatches, MinigrepError> {
+    if let Some(metrics) = &config.metrics {
+        metrics.add_search();
+    }
+    let mut err = io::sink();
+    let messages = Messages::new(&mut err, false, false, config.columns);
+    let mut collected = Collected::default();
+    let mut printer = Printer::with_writer(config, io::sink(), false, &messages);
+    printer.collected = Some(&mut collected);
+    let mut stats = SearchStats::default();
+    let args = [path.to_path_buf()];
+    for entry in walk::resolve_inputs(&args, &walk_options(config)) {
+        let input = match entry? {
+            walk::Input::Stdin => Input::Stdin,
+            walk::Input::File(path) => file_input(config, path, true)?,
+            walk::Input::Skipped(path, reason) => {
+                let name = display_name(path.as_os_str());
+                return Err(MinigrepError::skipped(&name, reason));
+            }
+        };
+        printer.search_or_warn(&input, &mut stats)?;
+    }
+    drop(printer);
+    let name = display_name(path.as_os_str());
+    if let Some(error) = collected.failures.pop() {
+        return Err(error);
+    }
+    if stats.skipped_binary > 0 {
+        return Err(MinigrepError::skipped(&name, SkipReason::Binary));
+    }
+    collected
+        .matches
+        .pop()
+        .ok_or_else(|| MinigrepError::new(&name, io::ErrorKind::NotFound.into()))
+}
+
+/// The options the paths of a search are walked with.
+fn walk_options(config: &Config) -> WalkOptions {
+    let now = SystemTime::now();
+    WalkOptions {
+        recursive: config.recursive,
+        max_filesize: config.max_filesize,
+        min_filesize: config.min_filesize,
+        modified_after: config.newer_than.and_then(|age| now.checked_sub(age)),
+        modified_before: config.older_than.and_then(|age| now.checked_sub(age)),
+        max_depth: config.max_depth,
+        include: config.include.clone(),
+        exclude: config.exclude.clone(),
+        exclude_dir: config.exclude_dir.clone(),
+        hidden: config.hidden,
+        follow: config.follow,
+        ignore: !config.no_ignore,
+        custom_ignore: !config.no_ignore_files,
+        dedupe: !config.no_dedupe,
+        one_file_system: config.one_file_system,
+        read_devices: config.read_devices,
+    }
+}
+
+/// The input for the file at `path`, found by the walk, made absolute with
+/// `--absolute-path`, and decompressed if `decompress`.
+///
+/// # Errors
+/// Returns a [`MinigrepError`] if the path cannot be made absolute.
+fn file_input(config: &Config, path: PathBuf, decompress: bool) -> Result<Input, MinigrepError> {
+    let path = if config.absolute_path && path.is_relative() {
+        path::absolute(&path).map_err(input_error(&path.to_string_lossy()))?
+    } else {
+        path
+    };
+    let preprocess = config.pre.is_some()
+        && (config.pre_glob.is_empty()
+            || config.pre_glob.iter().any(|glob| {
+                let name = path.file_name().unwrap_or_default();
+                glob.is_match(&name.to_string_lossy())
+            }));
+    Ok(Input::File {
+        path,
+        decompress,
+        preprocess,
+    })
+}
+
+/// What a search run for [`collect`] gathers instead of printing.
+///
+/// - `matches`: The selected lines of every input searched, including
+///   those without any.
+/// - `failures`: Why each input that could not be searched was not.
+#[derive(Debug, Default)]
+pub(crate) struct Collected {
+    pub(crate) matches: Vec<FileMatches>,
+    pub(crate) failures: Vec<MinigrepError>,
+}
+
+/// Executes the search process for [`run`], [`run_with_writer`], and
+/// [`collect`], taking `out` to be a terminal if `is_terminal`, drawing a
+/// `--progress` line on `err` if `progress`, and gathering the results in
+/// `collected` instead of printing them, if given.
+fn run_to(
+    config: &Config,
+    out: impl Write,
+    is_terminal: bool,
+    err: &mut (dyn Write + Send),
+    progress: bool,
+    collected: Option<&mut Collected>,
+) -> Result<RunSummary, MinigrepError> {
+    let started = Instant::now();
+    if let Some(metrics) = &config.metrics {
+        metrics.add_search();
+    }
+    if log::enabled(Level::Debug) {
+        for line in config.describe() {
+            log::debug("config", format_args!("{line}"));
+        }
+    }
+    let mut stats = SearchStats::default();
+    let messages = Messages::new(err, progress, is_terminal, config.columns);
+    let limit = config.total_limit().map(|max| TotalLimit {
+        max,
+        claimed: AtomicU64::new(0),
+    });
+    let mut printer = Printer::with_writer(config, out, is_terminal, &messages);
+    printer.collected = collected;
+    printer.limit = limit.as_ref();
+    if config.query.contains('\n') && !config.null_data {
+        printer.warn(&concat!(
+            "the query contains a newline, so no line can match it; ",
+            "with -z, records may contain newlines",
+        ));
+    }
+    let found_input = || messages.update(|progress| progress.files_found += 1);
+    let walk_options = walk_options(config);
+    if config.type_list {
+        for (name, globs) in types::FILE_TYPES {
+            printer
+                .output
+                .write_line(&format!("{name}: {}", globs.join(", ")))?;
+        }
+        printer.finish()?;
+        return Ok(RunSummary {
+            found: true,
+            ..RunSummary::default()
+        });
+    }
+    if let Some(shell) = config.completions {
+        for line in shell.script().lines() {
+            printer.output.write_line(line)?;
+        }
+        printer.finish()?;
+        return Ok(RunSummary {
+            found: true,
+            ..RunSummary::default()
+        });
+    }
+    if let Some(interval) = config.watch {
+        return watch_file(config, &mut printer, interval);
+    }
+
+    let listed = match &config.files_from {
+        Some(list) => list.read()?,
+        None => Vec::new(),
+    };
+    // The files named, rather than found by `-r`, are decompressed.
+    let named: HashSet<PathBuf> = config
+        .file_paths
+        .iter()
+        .chain(&listed)
+        .flat_map(|path| walk::expand(path))
+        .collect();
+    let resolved = walk::resolve_inputs(&config.file_paths, &walk_options)
+        .chain(walk::resolve_inputs(&listed, &walk_options));
+    let mut inputs = Vec::new();
+    for entry in resolved {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(error) => {
+                printer.report(error)?;
+                stats.record_unreadable();
+                if let Some(metrics) = &config.metrics {
+                    metrics.add_input(0, 0, 1);
+                }
+                continue;
+            }
+        };
+        match entry {
+            walk::Input::Stdin => {
+                inputs.push(Input::Stdin);
+                found_input();
+            }
+            walk::Input::File(path) => {
+                let decompress = config.search_zip || named.contains(&path);
+                inputs.push(file_input(config, path, decompress)?);
+                found_input();
+            }
+            walk::Input::Skipped(path, reason) => {
+                if reason == SkipReason::TooLarge {
+                    let name = display_name(path.as_os_str());
+                    printer.print_error_event(&MinigrepError::skipped(&name, reason))?;
+                }
+                if !matches!(
+                    reason,
+                    SkipReason::TooLarge
+                        | SkipReason::TooSmall
+                        | SkipReason::TooOld
+                        | SkipReason::TooNew
+                        | SkipReason::Ignored
+                        | SkipReason::Duplicate
+                ) {
+                    printer.warn(&format!("{}: {reason}", path.display()));
+                }
+                stats.record_skip(reason);
+            }
+        }
+    }
+    if config.sort == Sort::Path {
+        inputs.sort_by(|a, b| a.path(&config.label).cmp(b.path(&config.label)));
+    }
+
+    if let Some(OutputFormat::Table(format)) = config.output_format
+        && !config.no_header
+    {
+        printer.output.write_line(&format.record(&csv::HEADER))?;
+    }
+    #[cfg(feature = "json")]
+    let mut report = sarif::Report::new(&config.patterns());
+    let sarif = config.output_format == Some(OutputFormat::Sarif);
+    let mut found = false;
+    if config.threads == 1 && !sarif && !messages.shows_progress() {
+        for input in &inputs {
+            found |= printer.search_or_warn(input, &mut stats)?;
+        }
+    } else {
+        let template = printer.buffered(Vec::new());
+        // For `--first` in order, each input selects its own first line,
+        // and only the inputs before the first found with one are searched
+        // on, so that whichever thread finishes first, the line printed is
+        // that of the first input with one.
+        let ordered_first = config.first == Some(First::Ordered);
+        let first_found = AtomicUsize::new(usize::MAX);
+        let search =
+            |(index, input): (usize, Input),
+             #[cfg(feature = "parallel")] batches: Option<&mut Batches<'_, Vec<u8>>>| {
+                if index > first_found.load(Ordering::Relaxed) {
+                    return Ok(None);
+                }
+                if messages.shows_progress() {
+                    let name = display_name(input.path(&config.label)).into_owned();
+                    messages.update(|progress| progress.current = name);
+                }
+                let own_limit = TotalLimit {
+                    max: 1,
+                    claimed: AtomicU64::new(0),
+                };
+                #[cfg(feature = "parallel")]
+                let mut buffer = template.buffered(Streamed {
+                    buffer: Vec::new(),
+                    batches,
+                });
+                #[cfg(not(feature = "parallel"))]
+                let mut buffer = template.buffered(Vec::new());
+                if ordered_first {
+                    buffer.limit = Some(&own_limit);
+                }
+                let mut stats = SearchStats::default();
+                let found = buffer.search_or_warn(&input, &mut stats)?;
+                if ordered_first && found {
+                    first_found.fetch_min(index, Ordering::Relaxed);
+                }
+                let tally = std::mem::take(&mut buffer.tally);
+                let counts = (
+                    std::mem::take(&mut buffer.file_counts),
+                    std::mem::take(&mut buffer.word_counts),
+                );
+                #[cfg(feature = "parallel")]
+                let bytes = buffer.output.into_inner().buffer;
+                #[cfg(not(feature = "parallel"))]
+                let bytes = buffer.output.into_inner();
+                Ok(Some((bytes, Some((stats, tally, counts, found)))))
+            };
+        // Whether some of the results of the input being printed have been,
+        // as they come in batches with `--sort=path`.
+        let mut continued = false;
+        // The output of each input comes with what else its search found,
+        // after any batches of it sent on with `--sort=path`, which come
+        // alone; an input left unsearched sends nothing.
+        let mut emit =
+            |result: Result<Option<(Vec<u8>, _)>, MinigrepError>| -> Result<(), MinigrepError> {
+                let Some((bytes, searched)) = result? else {
+                    return Ok(());
+                };
+                if ordered_first && found {
+                    return Ok(());
+                }
+                if sarif {
+                    #[cfg(feature = "json")]
+                    for result in String::from_utf8_lossy(&bytes).lines() {
+                        report.push(result.to_string());
+                    }
+                } else if messages.shares_terminal && !bytes.is_empty() {
+                    messages.interrupt(|_| printer.write_buffered(&bytes, continued))?
+                } else {
+                    printer.write_buffered(&bytes, continued)?
+                }
+                continued |= !bytes.is_empty();
+                let Some((file_stats, tally, (file_counts, word_counts), file_found)): Option<(
+                    SearchStats,
+                    _,
+                    _,
+                    _,
+                )> = searched
+                else {
+                    return Ok(());
+                };
+                continued = false;
+                printer.tally.merge(tally);
+                printer.file_counts.extend(file_counts);
+                printer.word_counts.extend(word_counts);
+                messages.update(|progress| {
+                    progress.files_done += 1;
+                    progress.matched_lines += file_stats.matched_lines;
+                });
+                stats.merge(&file_stats);
+                found |= file_found;
+                Ok(())
+            };
+        #[cfg(feature = "parallel")]
+        if config.sort == Sort::Path {
+            parallel::map_stream(
+                inputs.into_iter().enumerate().collect(),
+                config.threads,
+                config.sort_buffer,
+                |input, batches| search(input, Some(batches)),
+                |part| match part {
+                    Part::Batch(bytes) => emit(Ok(Some((bytes, None)))),
+                    Part::Done(result) => emit(result),
+                },
+            )?;
+        } else {
+            parallel::map_emit(
+                inputs.into_iter().enumerate().collect(),
+                config.threads,
+                false,
+                |input| search(input, None),
+                emit,
+            )?;
+        }
+        // Without threads, the inputs are searched one after another.
+        #[cfg(not(feature = "parallel"))]
+        for input in inputs.into_iter().enumerate() {
+            emit(search(input))?;
+        }
+    }
+    messages.clear();
+    #[cfg(feature = "json")]
+    if config.json {
+        printer.output.write_line(&json::summary_record(&stats))?;
+    }
+    if config.unique_counts {
+        printer.print_tally()?;
+    }
+    if config.count_by_file {
+        printer.print_file_counts()?;
+    }
+    if config.wc {
+        printer.print_word_counts()?;
+    }
+    if config.count_total {
+        let total = if config.count_matches {
+            stats.occurrences
+        } else {
+            stats.matched_lines
+        };
+        printer.output.write_line(&format!("total:{total}"))?;
+    }
+    #[cfg(feature = "json")]
+    if sarif {
+        if report.omitted() > 0 {
+            printer.warn(&format!(
+                "{} more matches were left out of the SARIF report, past the first {}",
+                report.omitted(),
+                sarif::MAX_RESULTS
+            ));
+        }
+        printer.output.write_line(&report.to_document())?;
+    }
+    printer.finish()?;
+
+    if config.stats {
+        stats.elapsed = started.elapsed();
+        messages.interrupt(|err| writeln!(err, "{stats}"))?;
+    }
+    if config.timings {
+        let report = stats.timings_report(config.timings_limit);
+        messages.interrupt(|err| writeln!(err, "{report}"))?;
+    }
+    Ok(RunSummary {
+        found,
+        matched_lines: stats.matched_lines,
+        failed_inputs: stats.unreadable,
+    })
+}
+
+/// Searches the one file given, then prints the selected lines appended to
+/// it, checking for them every `interval`, until interrupted, for `--watch`.
+///
+/// # Returns
+/// What the search found, as for [`run`]: a file that cannot be opened or
+/// stops being readable is warned about and counted as failed.
+///
+/// # Errors
+/// Returns a [`MinigrepError::Output`] if writing the output fails.
+fn watch_file(
+    config: &Config,
+    printer: &mut Printer<'_, '_, impl Write>,
+    interval: Duration,
+) -> Result<RunSummary, MinigrepError> {
+    watch::catch_interrupts();
+    let path = &config.file_paths[0];
+    let name = display_name(path.as_os_str());
+    let mut follower = match Follower::open(path, printer.terminator() as u8) {
+        Ok(follower) => follower,
+        Err(error) => {
+            printer.warn(&input_error(&name)(error));
+            return Ok(RunSummary {
+                failed_inputs: 1,
+                ..RunSummary::default()
+            });
+        }
+    };
+
+    let mut summary = RunSummary::default();
+    let mut selector = Selector::new(config, None);
+    let mut line_number = 0;
+    let mut byte_offset = 0;
+    let mut lossy = false;
+    let mut headed = false;
+    while !watch::interrupted() {
+        let events = match follower.poll() {
+            Ok(events) => events,
+            Err(error) => {
+                printer.warn(&input_error(&name)(error));
+                printer.finish()?;
+                summary.failed_inputs = 1;
+                return Ok(summary);
+            }
+        };
+        if events.is_empty() {
+            thread::sleep(interval);
+        }
+        for event in events {
+            let record = match event {
+                Event::Record(record) => record,
+                Event::Truncated => {
+                    printer.warn(&format!("{name}: file truncated, searching it again"));
+                    selector = Selector::new(config, None);
+                    line_number = 0;
+                    byte_offset = 0;
+                    continue;
+                }
+                Event::Replaced => {
+                    printer.warn(&format!("{name}: file replaced, searching the new one"));
+                    selector = Selector::new(config, None);
+                    line_number = 0;
+                    byte_offset = 0;
+                    continue;
+                }
+            };
+            let line = String::from_utf8_lossy(&record);
+            if matches!(line, Cow::Owned(_)) && !lossy {
+                printer.warn_if_lossy(&name, true);
+                lossy = true;
+            }
+            line_number += 1;
+            let offset = byte_offset;
+            byte_offset += record.len() as u64 + 1;
+            let selected = selector.is_selected(&line);
+            if selected {
+                summary.found = true;
+                summary.matched_lines += 1;
+            }
+            if !selected && !config.passthru {
+                continue;
+            }
+            if printer.heading && !headed {
+                printer.print_heading(&name)?;
+                headed = true;
+            }
+            let line = LineMatch::find(&config.matcher(), line_number, &line, config.ignore_case);
+            printer.print_record(&name, &line, offset, selected)?;
+        }
+    }
+    printer.finish()?;
+    Ok(summary)
+}
+
+/// Returns the name the input at `path` is printed under, which on Windows
+/// leaves out any verbatim prefix the path is opened with, as
+/// [`strip_verbatim`] does.
+fn display_name(path: &OsStr) -> Cow<'_, str> {
+    let name = path.to_string_lossy();
+    if cfg!(windows) {
+        strip_verbatim(name)
+    } else {
+        name
+    }
+}
+
+/// Turns a Windows verbatim path such as `\\?\C:\logs` or
+/// `\\?\UNC\server\share` back into the usual `C:\logs` or
+/// `\\server\share`; any other name is returned unchanged.
+fn strip_verbatim(name: Cow<'_, str>) -> Cow<'_, str> {
+    if let Some(rest) = name.strip_prefix(r"\\?\UNC\") {
+        return Cow::Owned(format!(r"\\{rest}"));
+    }
+    match name.strip_prefix(r"\\?\") {
+        Some(rest) if rest.as_bytes().get(1) == Some(&b':') => Cow::Owned(rest.to_string()),
+        _ => name,
+    }
+}
+
+/// A single input to search: standard input, or a file found by walking
+/// the paths given.
+///
+/// A file is decompressed when opened if `decompress` is set, as it is for
+/// files given on the command line and, with `--search-zip`, those found by
+/// `-r`. If `preprocess` is set, what `--pre` prints for it is searched
+/// instead, and `decompress` is ignored.
+enum Input {
+    Stdin,
+    File {
+        path: PathBuf,
+        decompress: bool,
+        preprocess: bool,
+    },
+}
+
+impl Input {
+    /// Opens the input for reading, through the `--pre` command or
+    /// decompressed if it should be, and transcoded to UTF-8 from
+    /// `--encoding` or, without it, from the encoding named by its byte
+    /// order mark. Also returns how it is read.
+    fn open(&self, config: &Config) -> io::Result<(Decoder<Box<dyn Read>>, ReadMethod)> {
+        let (reader, method) = self.open_bytes(config)?;
+        Ok((encoding::decode(reader, config.encoding), method))
+    }
+
+    /// Opens the input for reading its bytes as they are, through the
+    /// `--pre` command if it should be, and decompressed if it should be,
+    /// unless searching for `--hex` bytes. A file is read through a memory
+    /// map if `--read-strategy` chooses to and it can be mapped; either way
+    /// the method it is read with is returned too.
+    fn open_bytes(&self, config: &Config) -> io::Result<(Box<dyn Read>, ReadMethod)> {
+        let name = display_name(self.path(&config.label));
+        log::trace("search", format_args!("opening {name}"));
+        let reader: Box<dyn Read> = match self {
+            Input::Stdin => Box::new(io::stdin()),
+            Input::File {
+                path,
+                preprocess: true,
+                ..
+            } => {
+                let command = config.pre.as_deref().unwrap_or_default();
+                Box::new(Preprocessed::spawn(command, path)?)
+            }
+            Input::File {
+                path, decompress, ..
+            } => {
+                let file = File::open(path)?;
+                let decompress = *decompress && config.hex.is_none();
+                if let Some(map) = mapped(&file, path, config) {
+                    let map = io::Cursor::new(map);
+                    let reader = if decompress {
+                        decompress::decoded(path, map)?
+                    } else {
+                        Box::new(map)
+                    };
+                    return Ok((reader, ReadMethod::Mmap));
+                }
+                if decompress {
+                    decompress::decoded(path, BufReader::new(file))?
+                } else {
+                    Box::new(file)
+                }
+            }
+        };
+        Ok((reader, ReadMethod::Buffered))
+    }
+
+    /// Opens the input for `--start-byte`, its bytes read as they are, at
+    /// the first line starting at or after `start`, ended by `terminator`.
+    /// A file is seeked to just before `start` unless the lines before it
+    /// are counted too, with `count_lines`.
+    fn open_at(
+        &self,
+        start: u64,
+        terminator: u8,
+        count_lines: bool,
+    ) -> io::Result<(Box<dyn BufRead>, Start)> {
+        let (mut reader, at): (Box<dyn BufRead>, u64) = match self {
+            Input::Stdin => (Box::new(BufReader::new(io::stdin())), 0),
+            Input::File { path, .. } => {
+                let mut file = File::open(path)?;
+                let at = if count_lines {
+                    0
+                } else {
+                    start.saturating_sub(1)
+                };
+                file.seek(SeekFrom::Start(at))?;
+                (Box::new(BufReader::new(file)), at)
+            }
+        };
+        let start = resume::skip_to_line(&mut reader, at, start, terminator, count_lines)?;
+        Ok((reader, start))
+    }
+
+    /// The path the input is sorted by, which for standard input is its label.
+    fn path<'b>(&'b self, label: &'b str) -> &'b OsStr {
+        match self {
+            Input::Stdin => OsStr::new(label),
+            Input::File { path, .. } => path.as_os_str(),
+        }
+    }
+}
+
+/// Maps `file`, opened from `path`, into memory if `--read-strategy` was
+/// given and [`mmap::choose`] picks a memory map for it. A file that cannot
+/// be mapped is read into a buffer instead, as it would be without the
+/// option.
+fn mapped(file: &File, path: &Path, config: &Config) -> Option<Mmap> {
+    let strategy = config.read_strategy?;
+    let facts = file
+        .metadata()
+        .ok()
+        .map(|metadata| FileFacts::of(path, &metadata, strategy));
+    if mmap::choose(strategy, facts.as_ref()) != ReadMethod::Mmap {
+        return None;
+    }
+    Mmap::map(file)
+        .inspect_err(|error| {
+            let name = display_name(path.as_os_str());
+            log::debug("search", format_args!("not mapping {name}: {error}"));
+        })
+        .ok()
+}
+
+/// A reader counting the bytes read through it into `count`, for
+/// `--timings`.
+struct CountingReader<'c, R> {
+    inner: R,
+    count: &'c mut u64,
+}
+
+impl<R: Read> Read for CountingReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        *self.count += read as u64;
+        Ok(read)
+    }
+}
+
+impl<R: BufRead> BufRead for CountingReader<'_, R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amount: usize) {
+        *self.count += amount as u64;
+        self.inner.consume(amount);
+    }
+}
+
+/// How many bytes of output of an input searched on another thread are
+/// gathered before they are sent on as a batch, with `--sort=path`.
+#[cfg(feature = "parallel")]
+const STREAM_BATCH: usize = 64 << 10;
+
+/// Where an input searched on another thread prints, sending its lines on
+/// to `batches` once there are [`STREAM_BATCH`] bytes of them, if there is
+/// somewhere to send them, and otherwise keeping them until it is done.
+///
+/// Batches end with a whole line, so that none is split between two.
+#[cfg(feature = "parallel")]
+struct Streamed<'b, 's> {
+    buffer: Vec<u8>,
+    batches: Option<&'b mut Batches<'s, Vec<u8>>>,
+}
+
+#[cfg(feature = "parallel")]
+impl Write for Streamed<'_, '_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        if let Some(batches) = &mut self.batches
+            && self.buffer.len() >= STREAM_BATCH
+            && let Some(end) = self.buffer.iter().rposition(|&byte| byte == b'\n')
+        {
+            let rest = self.buffer.split_off(end + 1);
+            let batch = std::mem::replace(&mut self.buffer, rest);
+            let size = batch.len();
+            if !batches.send(batch, size) {
+                return Err(io::Error::other("the search has stopped"));
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Opens the file at `path` as a zip archive if it is one, as
+/// [`zip::is_zip`] tells from its name or first bytes.
+///
+/// # Errors
+/// Returns an error if the file cannot be opened, or is a zip archive whose
+/// directory cannot be read.
+#[cfg(feature = "zip")]
+fn open_zip(path: &Path) -> io::Result<Option<ZipArchive<BufReader<File>>>> {
+    let mut file = BufReader::new(File::open(path)?);
+    if !zip::is_zip(path, file.fill_buf()?) {
+        return Ok(None);
+    }
+    ZipArchive::new(file).map(Some)
+}
+
+/// Opens the file at `path` as a tar archive, decompressed, if it is one, as
+/// [`tar::is_tar`] tells from its name or first bytes.
+///
+/// # Errors
+/// Returns an error if the file cannot be opened or its first bytes read.
+#[cfg(feature = "tar")]
+fn open_tar(path: &Path) -> io::Result<Option<TarArchive<Box<dyn BufRead>>>> {
+    let mut reader = decompress::open_decoded(path)?;
+    if !tar::is_tar(path, reader.fill_buf()?) {
+        return Ok(None);
+    }
+    Ok(Some(TarArchive::new(reader)))
+}
+
+/// How many lines `--max-total` lets a run select in all its inputs,
+/// shared by every thread searching.
+struct TotalLimit {
+    max: u64,
+    claimed: AtomicU64,
+}
+
+impl TotalLimit {
+    /// Takes one of the lines left, returning whether there was one.
+    fn claim(&self) -> bool {
+        self.claimed.fetch_add(1, Ordering::Relaxed) < self.max
+    }
+
+    /// Whether every line has been taken, so that searching can stop.
+    fn reached(&self) -> bool {
+        self.claimed.load(Ordering::Relaxed) >= self.max
+    }
+}
+
+/// Decides which lines of an input are selected, one after the other:
+/// those the query matches or, with `--between`, those inside ranges,
+/// either way flipped by `-v`, among the lines inside the time window, and
+/// as long as `limit` has lines left.
+struct Selector<'c> {
+    config: &'c Config,
+    between: Option<Between>,
+    limit: Option<&'c TotalLimit>,
+}
+
+impl<'c> Selector<'c> {
+    /// Starts on the first line of an input, with no range open.
+    fn new(config: &'c Config, limit: Option<&'c TotalLimit>) -> Selector<'c> {
+        Selector {
+            config,
+            between: config.between.clone(),
+            limit,
+        }
+    }
+
+    /// Returns whether `line`, the next line of the input, is selected.
+    fn is_selected(&mut self, line: &str) -> bool {
+        let config = self.config;
+        if config
+            .time_window
+            .as_ref()
+            .is_some_and(|window| !window.contains(line))
+        {
+            return false;
+        }
+        let matched = match &mut self.between {
+            Some(between) => between.select(&config.normalized(line), config.ignore_case),
+            None => config.matcher().is_match(line, config.ignore_case),
+        };
+        matched != config.invert && self.limit.is_none_or(TotalLimit::claim)
+    }
+
+    /// Returns whether `line`, the next line of the input, is selected, its
+    /// bytes matched as `--no-unicode` has them, with nothing to decode.
+    fn is_selected_bytes(&mut self, line: &[u8]) -> bool {
+        let config = self.config;
+        let matched = bytes::find(line, config.query.as_bytes(), config.ignore_case).is_some();
+        matched != config.invert && self.limit.is_none_or(TotalLimit::claim)
+    }
+
+    /// Whether no more lines can be selected, in this input or any other.
+    fn done(&self) -> bool {
+        self.limit.is_some_and(TotalLimit::reached)
+    }
+}
+
+/// Prints search results according to the config.
+///
+/// With `heading`, each file's lines are grouped under its name instead of
+/// being prefixed with it, and `headed_files` counts the groups printed so
+/// far, so that all but the first are preceded by a blank line.
+struct Printer<'a, 'e, W: Write> {
+    config: &'a Config,
+    output: Output<W>,
+    colored: bool,
+    theme: Theme,
+    heading: bool,
+    headed_files: usize,
+    /// Whether lines are prefixed with the name of their file, as the
+    /// config asks and, for the members of an archive, always.
+    with_filename: bool,
+    /// The matches counted for `--unique-counts`, to be printed once the
+    /// search is over.
+    tally: Tally,
+    /// Where warnings are printed.
+    messages: &'a Messages<'e>,
+    /// How many files have had groups of context lines printed, so that
+    /// those of all but the first are preceded by a separator.
+    context_files: usize,
+    /// Where selected lines and failures are gathered instead of being
+    /// printed, for [`collect`].
+    collected: Option<&'a mut Collected>,
+    /// The lines left to select with `--max-total`.
+    limit: Option<&'a TotalLimit>,
+    /// The path and count of each file with selected lines, for
+    /// `--count-by-file` to print once the search is over.
+    file_counts: Vec<(String, u64)>,
+    /// The name and counts of each file searched, for `--wc` to print once
+    /// the search is over.
+    word_counts: Vec<(String, WordCount)>,
+    /// Where the input being searched starts with `--start-byte`, from
+    /// which its records are numbered and their offsets counted.
+    resumed: Option<Start>,
+}
+
+/// Where warnings and summaries are printed, standard error for [`run`],
+/// shared by every thread searching, along with the `--progress` line, if
+/// there is one, which is erased when dropped so that it is not left behind
+/// by an error.
+///
+/// Drawing the line, erasing it, and printing in its place are done while
+/// holding the lock, so that none of them can interleave.
+struct Messages<'e> {
+    state: Mutex<MessagesState<'e>>,
+    /// The width of the terminal, as given by `COLUMNS`, or 80.
+    width: usize,
+    /// Whether standard output is the terminal the line is drawn on too, so
+    /// that the line has to be erased before results are printed.
+    shares_terminal: bool,
+}
+
+/// What [`Messages`] guards: its writer and the progress shown, if any.
+struct MessagesState<'e> {
+    writer: &'e mut (dyn Write + Send),
+    progress: Option<Progress>,
+}
+
+impl<'e> Messages<'e> {
+    /// Creates `Messages` printed to `writer`, with a progress line if
+    /// `progress`, on a terminal `width` columns wide that standard output
+    /// is on too if `shares_terminal`.
+    fn new(
+        writer: &'e mut (dyn Write + Send),
+        progress: bool,
+        shares_terminal: bool,
+        width: usize,
+    ) -> Messages<'e> {
+        Messages {
+            state: Mutex::new(MessagesState {
+                writer,
+                progress: progress.then(Progress::default),
+            }),
+            width,
+            shares_terminal: progress && shares_terminal,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, MessagesState<'e>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Whether a progress line is kept.
+    fn shows_progress(&self) -> bool {
+        self.lock().progress.is_some()
+    }
+
+    /// Applies `change` to the progress, if there is a progress line, and
+    /// redraws it, unless it was drawn too recently.
+    fn update(&self, change: impl FnOnce(&mut Progress)) {
+        let state = &mut *self.lock();
+        let Some(progress) = &mut state.progress else {
+            return;
+        };
+        change(progress);
+        if let Some(line) = progress.redraw(Instant::now(), self.width) {
+            let _ = state.writer.write_all(line.as_bytes());
+        }
+    }
+
+    /// Erases the progress line, if shown, and runs `print` with the writer
+    /// before the line can be drawn again.
+    fn interrupt<T>(&self, print: impl FnOnce(&mut dyn Write) -> T) -> T {
+        let state = &mut *self.lock();
+        if let Some(erase) = state.progress.as_mut().and_then(Progress::erase) {
+            let _ = state.writer.write_all(erase.as_bytes());
+        }
+        print(state.writer)
+    }
+
+    /// Prints `message` on a line of its own, prefixed with the program's
+    /// name.
+    fn print(&self, message: &dyn fmt::Display) {
+        self.interrupt(|writer| {
+            let _ = writeln!(writer, "minigrep: {message}");
+        });
+    }
+
+    /// Erases the progress line, if shown.
+    fn clear(&self) {
+        self.interrupt(|_| ());
+    }
+}
+
+impl Drop for Messages<'_> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<'a, 'e, W: Write> Printer<'a, 'e, W> {
+    /// Writes the buffered results of a single input, as collected by a
+    /// [`Printer::buffered`] printer on another thread, or some of them,
+    /// `continued` if they follow others of the same input already written.
+    fn write_buffered(&mut self, bytes: &[u8], continued: bool) -> io::Result<()> {
+        let starts = !continued && !bytes.is_empty();
+        if self.heading && starts {
+            if self.headed_files > 0 {
+                self.output.write_raw(b"\n")?;
+            }
+            self.headed_files += 1;
+        } else if self.config.context && starts {
+            if self.context_files > 0 {
+                self.print_group_separator()?;
+            }
+            self.context_files += 1;
+        }
+        self.output.write_raw(bytes)
+    }
+
+    /// Creates a `Printer` writing to `writer`, which is taken to be a
+    /// terminal, for coloring, headings, and flushing, if `is_terminal`, and
+    /// warning through `messages`.
+    fn with_writer(
+        config: &'a Config,
+        writer: W,
+        is_terminal: bool,
+        messages: &'a Messages<'e>,
+    ) -> Printer<'a, 'e, W> {
+        let output = Output::new(writer, config.line_buffered || is_terminal);
+        let (colored, reason) =
+            if config.json || config.output_format.is_some() || config.format.is_some() {
+                (false, "the output is not plain lines")
+            } else {
+                color::color_decision(
+                    config.color,
+                    config.color_env.no_color.as_deref(),
+                    config.color_env.clicolor_force.as_deref(),
+                    is_terminal,
+                )
+            };
+        let on = if colored { "on" } else { "off" };
+        log::debug("config", format_args!("color: {on}, as {reason}"));
+        let theme = if colored {
+            let (variable, spec) = config.color_env.colors.clone().unwrap_or_default();
+            if !spec.is_empty() {
+                log::debug("config", format_args!("colors: {spec:?}, from {variable}"));
+            }
+            let (theme, warnings) = Theme::parse(&spec);
+            for warning in warnings {
+                messages.print(&warning);
+            }
+            theme
+        } else {
+            Theme::default()
+        };
+
+        let lines_printed = !(config.count
+            || config.files_with_matches
+            || config.files_without_match
+            || config.list_files
+            || config.in_place
+            || config.json
+            || config.output_format.is_some()
+            || config.format.is_some()
+            || config.unique_counts
+            || config.line_numbers_only
+            || config.vimgrep);
+        let heading = lines_printed
+            && config
+                .heading
+                .unwrap_or(is_terminal && config.with_filename);
+
+        Printer {
+            config,
+            output,
+            colored,
+            theme,
+            heading,
+            headed_files: 0,
+            tally: Tally::new(config.ignore_case),
+            with_filename: config.with_filename,
+            messages,
+            context_files: 0,
+            collected: None,
+            limit: None,
+            file_counts: Vec::new(),
+            word_counts: Vec::new(),
+            resumed: None,
+        }
+    }
+
+    /// Creates a `Printer` like this one, but collecting the results of a
+    /// single input in `writer`, to be written out once the input is done.
+    fn buffered<V: Write>(&self, writer: V) -> Printer<'a, 'e, V> {
+        Printer {
+            config: self.config,
+            output: Output::new(writer, false),
+            colored: self.colored,
+            theme: self.theme.clone(),
+            heading: self.heading,
+            headed_files: 0,
+            tally: Tally::new(self.config.ignore_case),
+            with_filename: self.with_filename,
+            messages: self.messages,
+            context_files: 0,
+            collected: None,
+            limit: self.limit,
+            file_counts: Vec::new(),
+            word_counts: Vec::new(),
+            resumed: None,
+        }
+    }
+
+    /// Prints a warning on standard error, unless `-s` was given.
+    fn warn(&self, warning: &dyn fmt::Display) {
+        if self.config.no_messages {
+            return;
+        }
+        self.messages.print(warning);
+    }
+
+    /// Warns about `error` like [`Printer::warn`] and, with `--json`, also
+    /// prints it as an `error` event, or gathers it for [`collect`].
+    fn report(&mut self, error: MinigrepError) -> io::Result<()> {
+        self.warn(&error);
+        log::debug("search", format_args!("skipping {error}"));
+        self.print_error_event(&error)?;
+        if let Some(collected) = &mut self.collected {
+            collected.failures.push(error);
+        }
+        Ok(())
+    }
+
+    /// With `--json`, prints an `error` event for `error`.
+    #[cfg_attr(not(feature = "json"), allow(unused_variables))]
+    fn print_error_event(&mut self, error: &MinigrepError) -> io::Result<()> {
+        #[cfg(feature = "json")]
+        if self.config.json {
+            return self.output.write_line(&json::error_record(error));
+        }
+        Ok(())
+    }
+
+    /// Warns that invalid UTF-8 in the input called `name` was replaced, if
+    /// `lossy`.
+    fn warn_if_lossy(&self, name: &str, lossy: bool) {
+        if lossy {
+            self.warn(&MinigrepError::invalid_utf8(name));
+        }
+    }
+
+    /// Searches `input` like [`Printer::search_input`], except that a
+    /// [`MinigrepError`] is printed as a warning and recorded in `stats`
+    /// instead of being returned. What it added to `stats` is added to the
+    /// config's metrics too, if any.
+    fn search_or_warn(
+        &mut self,
+        input: &Input,
+        stats: &mut SearchStats,
+    ) -> Result<bool, MinigrepError> {
+        if self.limit.is_some_and(TotalLimit::reached) {
+            return Ok(false);
+        }
+        let before = (stats.lines_scanned, stats.matched_lines, stats.unreadable);
+        let result = match self.search_input(input, stats) {
+            Err(error) => {
+                if let MinigrepError::Output { .. } = error {
+                    return Err(error);
+                }
+                self.report(error)?;
+                stats.record_unreadable();
+                Ok(false)
+            }
+            result => {
+                if log::enabled(Level::Debug) && !self.config.list_files {
+                    let name = display_name(input.path(&self.config.label));
+                    let selected = stats.matched_lines - before.1;
+                    let scanned = stats.lines_scanned - before.0;
+                    log::debug(
+                        "search",
+                        format_args!("{name}: {selected} of {scanned} lines selected"),
+                    );
+                }
+                result
+            }
+        };
+        if let Some(metrics) = &self.config.metrics {
+            metrics.add_input(
+                stats.lines_scanned - before.0,
+                stats.matched_lines - before.1,
+                stats.unreadable - before.2,
+            );
+        }
+        result
+    }
+
+    /// Searches, lists, or edits `input` as the config asks, recording it in
+    /// `stats`.
+    ///
+    /// # Returns
+    /// Whether any line was selected, the input was listed, or any
+    /// replacement was made.
+    ///
+    /// # Errors
+    /// Returns a [`MinigrepError`] if the input is a directory, which is
+    /// checked before it is opened, or cannot be read or, with
+    /// `--in-place`, rewritten, or an error if printing fails.
+    fn search_input(
+        &mut self,
+        input: &Input,
+        stats: &mut SearchStats,
+    ) -> Result<bool, MinigrepError> {
+        let config = self.config;
+        let name = display_name(input.path(&config.label));
+        if config.list_files {
+            self.print_name(&name)?;
+            return Ok(true);
+        }
+        if let Input::File { path, .. } = input
+            && fs::metadata(path).is_ok_and(|metadata| metadata.is_dir())
+        {
+            let name = name.into_owned();
+            return Err(MinigrepError::is_directory(&name));
+        }
+        if config.in_place
+            && let Input::File { path, .. } = input
+        {
+            let options = EditOptions {
+                query: &config.query,
+                ignore_case: config.ignore_case,
+                template: config.replace.as_deref().unwrap_or_default(),
+                backup: !config.no_backup,
+            };
+            let edited = edit::replace_in_file(path, &options).map_err(input_error(&name))?;
+            stats.record_file(edited.lines_scanned, edited.matched_lines);
+            self.print_replacements(&name, edited.replacements)?;
+            return Ok(edited.replacements > 0);
+        }
+
+        #[cfg(feature = "zip")]
+        if config.search_zip
+            && let Input::File {
+                path,
+                preprocess: false,
+                ..
+            } = input
+            && let Some(archive) = open_zip(path).map_err(input_error(&name))?
+        {
+            return self.search_zip(&name, archive, stats);
+        }
+        #[cfg(feature = "tar")]
+        if config.search_zip
+            && let Input::File {
+                path,
+                preprocess: false,
+                ..
+            } = input
+            && let Some(archive) = open_tar(path).map_err(input_error(&name))?
+        {
+            return self.search_tar(&name, archive, stats);
+        }
+
+        let matched_before = stats.matched_lines;
+        let method = if let Some(pattern) = &config.hex {
+            let (reader, method) = input.open_bytes(config).map_err(input_error(&name))?;
+            self.print_hex_file(&name, reader, pattern, stats)?;
+            method
+        } else if let Some(min) = config.strings {
+            let (reader, method) = input.open_bytes(config).map_err(input_error(&name))?;
+            self.print_strings_file(&name, reader, min, stats)?;
+            method
+        } else if let Some(start) = config.start_byte {
+            self.search_from(&name, input, start, stats)?;
+            ReadMethod::Buffered
+        } else {
+            let (reader, method) = input.open(config).map_err(input_error(&name))?;
+            self.search_reader(&name, BufReader::new(reader), stats)?;
+            method
+        };
+        if config.read_strategy.is_some() {
+            stats.record_read_method(&name, method);
+        }
+        Ok(stats.matched_lines > matched_before)
+    }
+
+    /// Searches the input called `name` as it is read from `reader`, as
+    /// JSON records with `--json`, as table rows with `--output-format`, and
+    /// as plain lines otherwise, unless its lines are gathered for
+    /// [`collect`], timing it for `--timings`.
+    ///
+    /// Unless records end with NUL, an input with a NUL byte in the first
+    /// block read of it is skipped as binary instead.
+    fn search_reader(
+        &mut self,
+        name: &str,
+        mut reader: impl BufRead,
+        stats: &mut SearchStats,
+    ) -> Result<(), MinigrepError> {
+        if !self.config.null_data && reader.fill_buf().map_err(input_error(name))?.contains(&0) {
+            let reason = SkipReason::Binary;
+            log::debug("search", format_args!("skipping {name}: {reason}"));
+            self.print_error_event(&MinigrepError::skipped(name, reason))?;
+            stats.record_skip(reason);
+            return Ok(());
+        }
+        let started = Instant::now();
+        let mut bytes = 0;
+        let reader = CountingReader {
+            inner: reader,
+            count: &mut bytes,
+        };
+        let searched = self.print_any_file(name, reader, stats);
+        if self.config.timings && searched.is_ok() {
+            stats.record_timing(name, bytes, started.elapsed());
+        }
+        searched
+    }
+
+    /// Searches the input called `name` as [`search_reader`](Self::search_reader)
+    /// does, printing it in the format the output is in.
+    fn print_any_file(
+        &mut self,
+        name: &str,
+        reader: impl BufRead,
+        stats: &mut SearchStats,
+    ) -> Result<(), MinigrepError> {
+        if self.collected.is_some() {
+            return self.collect_file(name, reader, stats);
+        }
+        #[cfg(feature = "json")]
+        if self.config.json {
+            return self.print_json_file(name, reader, stats);
+        }
+        #[cfg(feature = "json")]
+        if self.config.output_format == Some(OutputFormat::Sarif) {
+            return self.print_sarif_file(name, reader, stats);
+        }
+        if let Some(OutputFormat::Table(format)) = self.config.output_format {
+            return self.print_table_file(name, reader, format, stats);
+        }
+        self.print_file(name, reader, stats)
+    }
+
+    /// Searches the input called `name` from the line at or after the
+    /// `--start-byte` offset `start`, as [`search_reader`](Self::search_reader)
+    /// does, and records the offset it was read to in `stats`.
+    ///
+    /// A file shorter than `start` has been truncated or replaced since the
+    /// search that reported it, so it is searched from its start instead.
+    fn search_from(
+        &mut self,
+        name: &str,
+        input: &Input,
+        start: u64,
+        stats: &mut SearchStats,
+    ) -> Result<(), MinigrepError> {
+        let mut start = start;
+        if let Input::File { path, .. } = input
+            && fs::metadata(path).is_ok_and(|metadata| metadata.len() < start)
+        {
+            self.warn(&format!(
+                "{name}: shorter than --start-byte, so searched from its start"
+            ));
+            start = 0;
+        }
+        let terminator = self.terminator() as u8;
+        let (reader, resumed) = input
+            .open_at(start, terminator, self.config.count_from_start)
+            .map_err(input_error(name))?;
+        let mut end = resumed.offset;
+        self.resumed = Some(resumed);
+        let reader = LineEnds::new(reader, resumed.offset, terminator, &mut end);
+        let searched = self.search_reader(name, reader, stats);
+        self.resumed = None;
+        searched?;
+        // The lines before the start were numbered, not scanned.
+        stats.lines_scanned -= resumed.lines.unwrap_or(0);
+        stats.record_end_offset(name, end);
+        Ok(())
+    }
+
+    /// Reads the records of the input called `name` from `reader`, ended by
+    /// `terminator`, within the window of `--skip-lines` and `--head`.
+    ///
+    /// # Returns
+    /// The records after those skipped, and how many were skipped, along
+    /// with any counted before `--start-byte`, from which the rest are
+    /// numbered.
+    ///
+    /// # Errors
+    /// Returns an error naming the input if reading the skipped records
+    /// fails.
+    fn read_window<R: BufRead>(
+        &self,
+        name: &str,
+        reader: R,
+        terminator: char,
+    ) -> Result<(ReadRecords<R>, usize), MinigrepError> {
+        let mut records = read_records(reader, terminator);
+        let mut skipped = 0;
+        if let Some(start) = self.resumed {
+            records = records.starting_at(start.offset);
+            skipped = start.lines.unwrap_or(0) as usize;
+        }
+        if let Some(head) = self.config.head {
+            records = records.limit(head);
+        }
+        skipped += records
+            .skip_records(self.config.skip_lines)
+            .map_err(input_error(name))?;
+        Ok((records, skipped))
+    }
+
+    /// Searches the raw bytes of the input called `name`, read from
+    /// `reader`, for the `--hex` `pattern`, printing the offset of each
+    /// occurrence and, with `--hex-context`, the bytes around it; each
+    /// occurrence is recorded in `stats` as a selected line.
+    ///
+    /// # Errors
+    /// Returns an error naming the input if reading it fails partway, or if
+    /// printing fails.
+    fn print_hex_file(
+        &mut self,
+        name: &str,
+        reader: impl Read,
+        pattern: &[u8],
+        stats: &mut SearchStats,
+    ) -> Result<(), MinigrepError> {
+        let started = Instant::now();
+        let mut bytes = 0;
+        let reader = CountingReader {
+            inner: reader,
+            count: &mut bytes,
+        };
+        let context = self.config.hex_context;
+        let shown_name = self.paint(&self.config.quote.apply(name), &self.theme.filename);
+        let separator = if self.config.null { "\0" } else { ":" };
+        let mut occurrences = 0;
+        for occurrence in hex::Occurrences::new(reader, pattern.to_vec(), context) {
+            let occurrence = occurrence.map_err(input_error(name))?;
+            occurrences += 1;
+            let offset = format!("{:#010X}", occurrence.offset);
+            let mut line = format!(
+                "{shown_name}{separator}{}",
+                self.paint(&offset, &self.theme.byte_offset)
+            );
+            if context > 0 {
+                let before = hex::dump(&occurrence.before);
+                let matched = self.paint(&hex::dump(pattern), &self.theme.matched);
+                let after = hex::dump(&occurrence.after);
+                line.push_str(": ");
+                line.push_str([before, format!("[{matched}]"), after].join(" ").trim());
+            }
+            self.output.write_line(&line)?;
+        }
+        stats.record_file(0, occurrences);
+        stats.record_occurrences(occurrences);
+        if self.config.timings {
+            stats.record_timing(name, bytes, started.elapsed());
+        }
+        Ok(())
+    }
+
+    /// Searches the runs of at least `min` printable characters in the raw
+    /// bytes of the input called `name`, read from `reader`, as lines, for
+    /// `--strings`: each selected run is printed with its byte offset, and
+    /// recorded in `stats` as a selected line.
+    ///
+    /// # Errors
+    /// Returns an error naming the input if reading it fails partway, or if
+    /// printing fails.
+    fn print_strings_file(
+        &mut self,
+        name: &str,
+        reader: impl Read,
+        min: usize,
+        stats: &mut SearchStats,
+    ) -> Result<(), MinigrepError> {
+        let started = Instant::now();
+        let mut bytes = 0;
+        let reader = CountingReader {
+            inner: reader,
+            count: &mut bytes,
+        };
+        let config = self.config;
+        let mut selector = Selector::new(config, self.limit);
+        let mut runs = strings::Runs::new(reader, min);
+        let mut runs_scanned = 0;
+        let mut selected_runs = 0;
+        let mut occurrences = 0;
+        let mut headed = false;
+        while !selector.done()
+            && let Some(run) = runs.next()
+        {
+            let run = run.map_err(input_error(name))?;
+            runs_scanned += 1;
+            if !selector.is_selected(&run.text) {
+                continue;
+            }
+            selected_runs += 1;
+            if config.count {
+                if config.count_matches {
+                    occurrences += config.matcher().count(&run.text, config.ignore_case) as u64;
+                }
+                continue;
+            }
+            if self.heading && !headed {
+                self.print_heading(name)?;
+                headed = true;
+            }
+            let line = LineMatch::find(&config.matcher(), 0, &run.text, config.ignore_case);
+            self.print_record(name, &line, run.offset, true)?;
+        }
+        if config.count {
+            let count = if config.count_matches {
+                occurrences
+            } else {
+                selected_runs
+            };
+            self.print_count(name, count)?;
+        }
+        stats.record_occurrences(occurrences);
+        stats.record_file(runs_scanned, selected_runs);
+        if config.timings {
+            stats.record_timing(name, bytes, started.elapsed());
+        }
+        Ok(())
+    }
+
+    /// Searches each file in the zip archive called `name` as an input of
+    /// its own, called `name!member` and always printed with its name.
+    ///
+    /// Directories are passed over, archives within the archive are
+    /// skipped with a note, and members larger than `--max-filesize` are
+    /// skipped. A member that cannot be read, such as an encrypted one, is
+    /// warned about and recorded in `stats` as an input would be, and the
+    /// rest are still searched.
+    ///
+    /// # Returns
+    /// Whether any line of any member was selected.
+    #[cfg(feature = "zip")]
+    fn search_zip(
+        &mut self,
+        name: &str,
+        mut archive: ZipArchive<impl BufRead + Seek>,
+        stats: &mut SearchStats,
+    ) -> Result<bool, MinigrepError> {
+        let matched_before = stats.matched_lines;
+        let with_filename = mem::replace(&mut self.with_filename, true);
+        for index in 0..archive.members().len() {
+            let member = &archive.members()[index];
+            let member_name = format!("{name}!{}", member.name());
+            if member.is_dir() || !self.should_search_member(&member_name, member.size(), stats)? {
+                continue;
+            }
+            self.search_member(&member_name, archive.open(index), stats)?;
+        }
+        self.with_filename = with_filename;
+        Ok(stats.matched_lines > matched_before)
+    }
+
+    /// Searches each regular file in the tar archive called `name`, as it
+    /// streams past, like [`Printer::search_zip`]; links are passed over.
+    ///
+    /// An archive that turns out to be corrupt is warned about under its
+    /// own name once the members before the damage have been searched.
+    ///
+    /// # Returns
+    /// Whether any line of any member was selected.
+    #[cfg(feature = "tar")]
+    fn search_tar(
+        &mut self,
+        name: &str,
+        mut archive: TarArchive<impl Read>,
+        stats: &mut SearchStats,
+    ) -> Result<bool, MinigrepError> {
+        let matched_before = stats.matched_lines;
+        let with_filename = mem::replace(&mut self.with_filename, true);
+        loop {
+            let member = match archive.next_member() {
+                Ok(Some(member)) => member,
+                Ok(None) => break,
+                Err(error) => {
+                    self.report(input_error(name)(error))?;
+                    stats.record_unreadable();
+                    break;
+                }
+            };
+            let member_name = format!("{name}!{}", member.name());
+            if member.kind() != tar::Kind::File
+                || !self.should_search_member(&member_name, member.size(), stats)?
+            {
+                continue;
+            }
+            // The rest of a stream that fails partway cannot be read.
+            if !self.search_member(&member_name, Ok(archive.data()), stats)? {
+                break;
+            }
+        }
+        self.with_filename = with_filename;
+        Ok(stats.matched_lines > matched_before)
+    }
+
+    /// Returns whether the member of an archive called `name`, of `size`
+    /// bytes, should be searched: it is skipped with a note if it is an
+    /// archive itself, as told by its name, and recorded in `stats` as a
+    /// skipped file if it is larger than `--max-filesize`, which `--json`
+    /// also reports as an `error` event, or smaller than `--min-filesize`.
+    ///
+    /// # Errors
+    /// Returns an error if printing the event fails.
+    #[cfg(any(feature = "zip", feature = "tar"))]
+    fn should_search_member(
+        &mut self,
+        name: &str,
+        size: u64,
+        stats: &mut SearchStats,
+    ) -> io::Result<bool> {
+        let path = Path::new(name);
+        #[cfg(feature = "zip")]
+        let nested = zip::is_zip(path, b"");
+        #[cfg(not(feature = "zip"))]
+        let nested = false;
+        #[cfg(feature = "tar")]
+        let nested = nested || tar::is_tar(path, b"");
+        if nested {
+            self.warn(&format!("{name}: skipping nested archive"));
+            log::debug("search", format_args!("skipping {name}: nested archive"));
+            return Ok(false);
+        }
+        if self.config.max_filesize.is_some_and(|max| size > max) {
+            let reason = SkipReason::TooLarge;
+            log::debug("search", format_args!("skipping {name}: {reason}"));
+            self.print_error_event(&MinigrepError::skipped(name, reason))?;
+            stats.record_skip(reason);
+            return Ok(false);
+        }
+        if self.config.min_filesize.is_some_and(|min| size < min) {
+            let reason = SkipReason::TooSmall;
+            log::debug("search", format_args!("skipping {name}: {reason}"));
+            stats.record_skip(reason);
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    /// Searches the member of an archive called `name`, read from `reader`
+    /// if it could be opened, warning about and recording in `stats` an
+    /// error opening or reading it as [`Printer::search_or_warn`] does.
+    ///
+    /// # Returns
+    /// Whether the member could be searched.
+    #[cfg(any(feature = "zip", feature = "tar"))]
+    fn search_member(
+        &mut self,
+        name: &str,
+        reader: io::Result<impl Read>,
+        stats: &mut SearchStats,
+    ) -> Result<bool, MinigrepError> {
+        log::trace("search", format_args!("opening {name}"));
+        let searched = match reader {
+            Ok(reader) => {
+                let reader = encoding::decode(reader, self.config.encoding);
+                self.search_reader(name, BufReader::new(reader), stats)
+            }
+            Err(error) => Err(input_error(name)(error)),
+        };
+        match searched {
+            Ok(()) => Ok(true),
+            Err(error) => {
+                if let MinigrepError::Output { .. } = error {
+                    return Err(error);
+                }
+                self.report(error)?;
+                stats.record_unreadable();
+                Ok(false)
+            }
+        }
+    }
+
+    /// Colors `text` with `sgr` if coloring is enabled.
+    fn paint(&self, text: &str, sgr: &str) -> String {
+        if self.colored {
+            color::paint(text, sgr)
+        } else {
+            text.to_string()
+        }
+    }
+
+    /// Prints a file name on its own, as for `-l` and `--files`.
+    fn print_name(&mut self, name: &str) -> io::Result<()> {
+        let name = self.paint(&self.config.quote.apply(name), &self.theme.filename);
+        let terminator = if self.config.null { '\0' } else { '\n' };
+        self.output.write_terminated(&name, terminator)
+    }
+
+    /// Prints how many replacements `--in-place` made in the file `name`.
+    fn print_replacements(&mut self, name: &str, replacements: u64) -> io::Result<()> {
+        let name = self.paint(&self.config.quote.apply(name), &self.theme.filename);
+        let separator = if self.config.null { "\0" } else { ":" };
+        let noun = if replacements == 1 {
+            "replacement"
+        } else {
+            "replacements"
+        };
+        self.output
+            .write_line(&format!("{name}{separator} {replacements} {noun}"))
+    }
+
+    /// Prints each distinct match counted for `--unique-counts`, after its
+    /// count, most frequent first, as `uniq -c` would.
+    fn print_tally(&mut self) -> io::Result<()> {
+        let sorted = self.tally.sorted(self.config.top);
+        let width = sorted
+            .first()
+            .map_or(0, |(_, count)| count.to_string().len());
+        for (matched, count) in sorted {
+            let matched = self.paint(matched, &self.theme.matched);
+            self.output
+                .write_terminated(&format!("{count:>width$} {matched}"), self.terminator())?;
+        }
+        Ok(())
+    }
+
+    /// Prints the count of each file gathered for `--count-by-file`, a tab,
+    /// and its path: in the order the files were searched or, with
+    /// `--sort-count`, the largest counts first and equal ones by path.
+    fn print_file_counts(&mut self) -> io::Result<()> {
+        if self.config.group_by_dir {
+            return self.print_dir_counts();
+        }
+        let mut counts = std::mem::take(&mut self.file_counts);
+        if self.config.sort_count {
+            counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        }
+        if let Some(top) = self.config.top {
+            counts.truncate(top);
+        }
+        for (path, count) in counts {
+            let path = self.paint(&self.config.quote.apply(&path), &self.theme.filename);
+            self.output
+                .write_terminated(&format!("{count}\t{path}"), self.terminator())?;
+        }
+        Ok(())
+    }
+
+    /// Prints the counts of each file gathered for `--wc`, in the order the
+    /// files were searched, each followed by its path when file names are
+    /// printed, and then their total if there are several, all aligned to
+    /// the widest of them as `wc` aligns its columns.
+    fn print_word_counts(&mut self) -> io::Result<()> {
+        let counts = std::mem::take(&mut self.word_counts);
+        let mut total = WordCount::default();
+        for (_, count) in &counts {
+            total += *count;
+        }
+        let width = total.width();
+        let terminator = self.terminator();
+        for (path, count) in &counts {
+            let line = if self.with_filename {
+                let path = self.paint(&self.config.quote.apply(path), &self.theme.filename);
+                format!("{} {path}", count.render(width))
+            } else {
+                count.render(width)
+            };
+            self.output.write_terminated(&line, terminator)?;
+        }
+        if counts.len() > 1 {
+            self.output
+                .write_terminated(&format!("{} total", total.render(width)), terminator)?;
+        }
+        Ok(())
+    }
+
+    /// Prints the counts kept for `--group-by-dir` once the search is over:
+    /// a `COUNT<TAB>DIR` line for each directory, the largest count first
+    /// and only as many as `--top` allows, each followed by an indented
+    /// `COUNT<TAB>PATH` line for each of its files, the path relative to
+    /// the directory.
+    fn print_dir_counts(&mut self) -> io::Result<()> {
+        let counts = std::mem::take(&mut self.file_counts)
+            .into_iter()
+            .map(|(path, count)| (PathBuf::from(path), count));
+        let mut dirs = dirs::group_by_dir(
+            counts.collect(),
+            &self.config.file_paths,
+            self.config.group_depth,
+        );
+        if let Some(top) = self.config.top {
+            dirs.truncate(top);
+        }
+        let terminator = self.terminator();
+        for dir in dirs {
+            let name = dir.dir.to_string_lossy();
+            let name = if name.is_empty() {
+                Cow::from(".")
+            } else {
+                name
+            };
+            let name = self.paint(&self.config.quote.apply(&name), &self.theme.filename);
+            self.output
+                .write_terminated(&format!("{}\t{name}", dir.count), terminator)?;
+            for (path, count) in dir.files {
+                let path = path.to_string_lossy();
+                let path = self.paint(&self.config.quote.apply(&path), &self.theme.filename);
+                self.output
+                    .write_terminated(&format!("  {count}\t{path}"), terminator)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The character ending each record read and printed: NUL with `-z`,
+    /// a newline otherwise.
+    fn terminator(&self) -> char {
+        if self.config.null_data { '\0' } else { '\n' }
+    }
+
+    /// Prints the `--group-separator` line, unless there is none.
+    fn print_group_separator(&mut self) -> io::Result<()> {
+        let Some(separator) = &self.config.group_separator else {
+            return Ok(());
+        };
+        let separator = self.paint(separator, &self.theme.separator);
+        self.output.write_terminated(&separator, self.terminator())
+    }
+
+    /// Prints the heading grouping the lines of the file `name`, after a
+    /// blank line unless it is the first.
+    fn print_heading(&mut self, name: &str) -> io::Result<()> {
+        if self.headed_files > 0 {
+            self.output.write_line("")?;
+        }
+        self.headed_files += 1;
+        let name = self.paint(&self.config.quote.apply(name), &self.theme.filename);
+        self.output.write_line(&name)
+    }
+
+    /// Builds the prefix of a printed line: the file name, unless grouped
+    /// under a heading, then the line number and byte offset when enabled.
+    fn prefix<'n>(
+        &self,
+        name: &'n str,
+        line_number: usize,
+        byte_offset: u64,
+        kind: LineKind,
+    ) -> Prefix<'n> {
+        Prefix {
+            path: (self.with_filename && !self.heading).then_some(name),
+            line_number: self.config.line_number.then_some(line_number),
+            column: None,
+            byte_offset: self.config.byte_offset.then_some(byte_offset),
+            kind,
+        }
+    }
+
+    /// Returns the 1-based column of the byte at `start` of `text`, counted
+    /// in characters unless `--byte-column` asks for bytes.
+    fn column(&self, text: &str, start: usize) -> usize {
+        if self.config.byte_column {
+            start + 1
+        } else {
+            text[..start].chars().count() + 1
+        }
+    }
+
+    /// Renders `prefix` in this printer's colors, if any, with its path
+    /// quoted as `--quote` asks.
+    fn render_prefix(&self, prefix: &Prefix) -> String {
+        let path = prefix.path.map(|path| self.config.quote.apply(path));
+        let prefix = Prefix {
+            path: path.as_deref(),
+            ..*prefix
+        };
+        prefix.render(self.colored.then_some(&self.theme), self.config.null)
+    }
+
+    /// Searches the input called `name` as it is read from `reader`, prints
+    /// the results, and records the file in `stats`.
+    ///
+    /// Each line is decoded on its own, replacing invalid UTF-8 with U+FFFD,
+    /// so that a stray byte does not stop the rest of the file being
+    /// searched; a warning is printed once for each file where that happens.
+    ///
+    /// # Errors
+    /// Returns an error naming the input if reading it fails partway, or if
+    /// printing fails.
+    fn print_file(
+        &mut self,
+        name: &str,
+        reader: impl BufRead,
+        stats: &mut SearchStats,
+    ) -> Result<(), MinigrepError> {
+        let config = self.config;
+        let terminator = self.terminator();
+        let mut selector = Selector::new(config, self.limit);
+        let (mut lines, skipped) = self.read_window(name, reader, terminator)?;
+        let read_error = input_error(name);
+        let mut lossy = false;
+
+        if (config.files_with_matches || config.files_without_match) && !config.count {
+            let mut lines_scanned = 0;
+            let mut found = false;
+            while !selector.done()
+                && let Some(line) = lines.next_bytes()
+            {
+                let line = line.map_err(read_error)?;
+                lines_scanned += 1;
+                let selected = if config.no_unicode {
+                    selector.is_selected_bytes(line)
+                } else {
+                    let line = String::from_utf8_lossy(line);
+                    lossy |= matches!(line, Cow::Owned(_));
+                    selector.is_selected(&line)
+                };
+                if selected {
+                    found = true;
+                    break;
+                }
+            }
+            self.warn_if_lossy(name, lossy);
+            stats.record_file(lines_scanned, u64::from(found));
+            if found == config.files_with_matches {
+                self.print_name(name)?;
+            }
+            return Ok(());
+        }
+
+        let mut line_number = skipped;
+        let mut selected_lines = 0;
+        let mut occurrences = 0;
+        let mut word_count = WordCount::default();
+        let mut runs = LineRuns::default();
+        let mut headed = false;
+        let mut before = VecDeque::with_capacity(config.before_context);
+        let mut after_left: usize = 0;
+        let mut until = config.context_until.clone();
+        let mut last_printed = None;
+        loop {
+            // Past the limit, only the context after the last selected line
+            // is left to print.
+            let in_context = after_left > 0 || until.as_ref().is_some_and(ContextUntil::is_open);
+            if selector.done() && !in_context {
+                break;
+            }
+            let byte_offset = lines.offset();
+            let Some(line) = lines.next_bytes() else {
+                break;
+            };
+            let line = line.map_err(read_error)?;
+            line_number += 1;
+            let selected = if config.no_unicode {
+                // Only lines that are printed are decoded, and never
+                // warned about.
+                let selected = selector.is_selected_bytes(line);
+                let printed =
+                    selected || after_left > 0 || config.before_context > 0 || config.passthru;
+                if !printed {
+                    continue;
+                }
+                Some(selected)
+            } else {
+                None
+            };
+            let text = String::from_utf8_lossy(line);
+            lossy |= !config.no_unicode && matches!(text, Cow::Owned(_));
+            let line: &str = &text;
+            let selected = selected.unwrap_or_else(|| selector.is_selected(line));
+            let in_window = until.as_mut().is_some_and(|until| {
+                until.step(&config.normalized(line), selected, config.ignore_case)
+            });
+            if selected {
+                selected_lines += 1;
+            } else if after_left > 0 || in_window {
+                after_left = after_left.saturating_sub(1);
+                last_printed = Some(line_number);
+                let line =
+                    LineMatch::find(&config.matcher(), line_number, line, config.ignore_case);
+                self.print_record(name, &line, byte_offset, false)?;
+                continue;
+            } else if config.before_context > 0 {
+                if before.len() == config.before_context {
+                    before.pop_front();
+                }
+                before.push_back((line_number, byte_offset, text.into_owned()));
+                continue;
+            } else if !config.passthru {
+                continue;
+            }
+            if config.count {
+                if config.count_matches {
+                    occurrences += config.matcher().count(line, config.ignore_case) as u64;
+                } else if config.wc {
+                    word_count += WordCount {
+                        lines: 1,
+                        words: wc::words(line.as_bytes()),
+                        bytes: lines.offset() - byte_offset,
+                    };
+                }
+                continue;
+            }
+            if config.ranges {
+                if let Some(run) = runs.push(line_number) {
+                    self.print_line_number(name, &format_run(&run))?;
+                }
+                continue;
+            }
+            if self.heading && !headed {
+                self.print_heading(name)?;
+                headed = true;
+            }
+            if config.context {
+                let first = before.front().map_or(line_number, |(number, ..)| *number);
+                match last_printed {
+                    Some(last) if first > last + 1 => self.print_group_separator()?,
+                    Some(_) => {}
+                    None => {
+                        if self.context_files > 0 && !self.heading {
+                            self.print_group_separator()?;
+                        }
+                        self.context_files += 1;
+                    }
+                }
+                for (number, offset, text) in before.drain(..) {
+                    let line =
+                        LineMatch::find(&config.matcher(), number, &text, config.ignore_case);
+                    self.print_record(name, &line, offset, false)?;
+                }
+                after_left = config.after_context;
+                last_printed = Some(line_number);
+            }
+
+            let line = LineMatch::find(&config.matcher(), line_number, line, config.ignore_case);
+            self.print_record(name, &line, byte_offset, selected)?;
+        }
+        if let Some(run) = runs.finish() {
+            self.print_line_number(name, &format_run(&run))?;
+        }
+
+        let listed = if config.files_with_matches {
+            selected_lines > 0
+        } else {
+            !config.files_without_match || selected_lines == 0
+        };
+        if config.wc {
+            self.word_counts.push((name.to_string(), word_count));
+        } else if config.count && listed {
+            let count = if config.count_matches {
+                occurrences
+            } else {
+                selected_lines
+            };
+            self.print_count(name, count)?;
+        }
+        stats.record_occurrences(occurrences);
+        self.warn_if_lossy(name, lossy);
+        stats.record_file(line_number as u64, selected_lines);
+        Ok(())
+    }
+
+    /// Prints `count`, the lines or matches of the input called `name`
+    /// counted for `-c`, or keeps it for `--count-by-file` if it is not zero.
+    fn print_count(&mut self, name: &str, count: u64) -> io::Result<()> {
+        if self.config.count_by_file {
+            if count > 0 {
+                self.file_counts.push((name.to_string(), count));
+            }
+            return Ok(());
+        }
+        let prefix = Prefix {
+            path: self.with_filename.then_some(name),
+            line_number: None,
+            column: None,
+            byte_offset: None,
+            kind: LineKind::Selected,
+        };
+        let prefix = self.render_prefix(&prefix);
+        self.output.write_line(&format!("{prefix}{count}"))
+    }
+
+    /// Prints `line_number`, a line number or a range of them, of the input
+    /// called `name`, for `--line-numbers-only`.
+    fn print_line_number(&mut self, name: &str, line_number: &str) -> io::Result<()> {
+        let prefix = Prefix {
+            path: self.with_filename.then_some(name),
+            line_number: None,
+            column: None,
+            byte_offset: None,
+            kind: LineKind::Selected,
+        };
+        let prefix = self.render_prefix(&prefix);
+        self.output
+            .write_terminated(&format!("{prefix}{line_number}"), self.terminator())
+    }
+
+    /// Prints the matched `line` of the input called `name`, starting
+    /// `byte_offset` bytes into it, which is `selected` or else printed only
+    /// because of `--passthru`, as `-o`, `--vimgrep`, and `--replace` ask.
+    fn print_record(
+        &mut self,
+        name: &str,
+        line: &LineMatch,
+        byte_offset: u64,
+        selected: bool,
+    ) -> io::Result<()> {
+        let config = self.config;
+        if config.line_numbers_only {
+            return self.print_line_number(name, &line.line_number.to_string());
+        }
+        let kind = if selected {
+            LineKind::Selected
+        } else {
+            LineKind::Context
+        };
+        if let Some(template) = &config.format {
+            let path = config.quote.apply(name);
+            let mut fields = Fields {
+                path: &path,
+                line_number: line.line_number,
+                column: None,
+                byte_offset,
+                matched: None,
+                text: line.text,
+            };
+            if line.ranges.is_empty() {
+                return self
+                    .output
+                    .write_terminated(&template.render(&fields), self.terminator());
+            }
+            for range in &line.ranges {
+                fields.column = Some(line.text[..range.start].chars().count() + 1);
+                fields.byte_offset = byte_offset + range.start as u64;
+                fields.matched = Some(&line.text[range.clone()]);
+                self.output
+                    .write_terminated(&template.render(&fields), self.terminator())?;
+            }
+            return Ok(());
+        }
+        let mut prefix = self.prefix(name, line.line_number, byte_offset, kind);
+        if config.only_matching {
+            for (index, range) in line.ranges.iter().enumerate() {
+                prefix.byte_offset = prefix.byte_offset.map(|_| byte_offset + range.start as u64);
+                if config.column {
+                    prefix.column = Some(self.column(line.text, range.start));
+                }
+                let prefix = self.render_prefix(&prefix);
+                let text = match &config.replace {
+                    Some(template) => {
+                        replace::expand_replacement(template, &line.text[range.clone()])
+                    }
+                    None => line.text[range.clone()].to_string(),
+                };
+                if config.unique_counts {
+                    self.tally.add(&text);
+                    continue;
+                }
+                let text = self.paint(&text, self.theme.pattern(line.pattern(index)));
+                self.output
+                    .write_terminated(&format!("{prefix}{text}"), self.terminator())?;
+            }
+            return Ok(());
+        }
+        if config.vimgrep {
+            for range in &line.ranges {
+                prefix.column = Some(self.column(line.text, range.start));
+                self.print_line(&self.render_prefix(&prefix), line.clone())?;
+            }
+            return Ok(());
+        }
+        if let Some(context_chars) = config.char_context {
+            // The matches and columns are counted on from the last window,
+            // so that a long line with many matches is only gone through
+            // once.
+            let (mut next, mut counted, mut chars) = (0, 0, 0);
+            for window in snippet::context_windows(line.text, &line.ranges, context_chars) {
+                let matches = &line.ranges[next..];
+                let start = matches.first().map_or(window.start, |range| range.start);
+                next += matches
+                    .iter()
+                    .take_while(|range| range.start < window.end)
+                    .count();
+                prefix.byte_offset = prefix.byte_offset.map(|_| byte_offset + start as u64);
+                if config.column {
+                    chars += line.text[counted..start].chars().count();
+                    counted = start;
+                    prefix.column = Some(if config.byte_column {
+                        start + 1
+                    } else {
+                        chars + 1
+                    });
+                }
+                self.print_window(&self.render_prefix(&prefix), line.clone(), window)?;
+            }
+            return Ok(());
+        }
+        if config.column {
+            prefix.column = line
+                .ranges
+                .first()
+                .map(|range| self.column(line.text, range.start));
+        }
+        let prefix = self.render_prefix(&prefix);
+        match &config.replace {
+            Some(template) => {
+                let (text, ranges) = replace::replace_ranges(line.text, &line.ranges, template);
+                let replaced = LineMatch {
+                    line_number: line.line_number,
+                    text: &text,
+                    ranges,
+                    patterns: line.patterns.clone(),
+                };
+                self.print_line(&prefix, replaced)
+            }
+            None => self.print_line(&prefix, line.clone()),
+        }
+    }
+
+    /// Searches the input called `name` as it is read, raw, from `reader`,
+    /// printing a `match` event for every selected line, between a `begin`
+    /// and an `end` event if there is any, and records the file in `stats`.
+    ///
+    /// Lines are split on `\n`, dropping a trailing `\r`, and each one is
+    /// decoded on its own, so that invalid UTF-8 only makes its own line
+    /// lossy; an input with any lossy line is followed by an `error` event.
+    #[cfg(feature = "json")]
+    fn print_json_file(
+        &mut self,
+        name: &str,
+        reader: impl BufRead,
+        stats: &mut SearchStats,
+    ) -> Result<(), MinigrepError> {
+        let config = self.config;
+        let (mut lines, mut line_number) = self.read_window(name, reader, '\n')?;
+        let start = self.resumed.unwrap_or(Start {
+            offset: 0,
+            lines: Some(0),
+        });
+        let mut selector = Selector::new(config, self.limit);
+        let mut selected_lines = 0;
+        let mut lossy = false;
+        loop {
+            if selector.done() {
+                break;
+            }
+            let offset = lines.offset();
+            let Some(line) = lines.next_bytes() else {
+                break;
+            };
+            let line = line.map_err(input_error(name))?;
+            line_number += 1;
+            let text = String::from_utf8_lossy(line);
+            let line_lossy = matches!(text, Cow::Owned(_));
+            lossy |= line_lossy;
+            if !selector.is_selected(&text) {
+                continue;
+            }
+            if selected_lines == 0 {
+                self.output.write_line(&json::begin_record(name))?;
+            }
+            selected_lines += 1;
+            let ranges = if config.invert {
+                Vec::new()
+            } else {
+                config.matcher().match_ranges(&text, config.ignore_case)
+            };
+            let number = start.lines.map(|_| line_number);
+            let record = json::match_record(name, number, offset, &text, &ranges, line_lossy);
+            self.output.write_line(&record)?;
+        }
+        if selected_lines > 0 {
+            let record = json::end_record(
+                name,
+                line_number as u64 - start.lines.unwrap_or(0),
+                selected_lines,
+                lines.offset() - start.offset,
+            );
+            self.output.write_line(&record)?;
+        }
+        if lossy {
+            self.print_error_event(&MinigrepError::invalid_utf8(name))?;
+        }
+        stats.record_file(line_number as u64, selected_lines);
+        Ok(())
+    }
+
+    /// Searches the input called `name` as it is read from `reader`,
+    /// gathering its selected lines for [`collect`], and records the file in
+    /// `stats`.
+    fn collect_file(
+        &mut self,
+        name: &str,
+        reader: impl BufRead,
+        stats: &mut SearchStats,
+    ) -> Result<(), MinigrepError> {
+        let config = self.config;
+        let (mut records, mut line_number) = self.read_window(name, reader, self.terminator())?;
+        let mut selector = Selector::new(config, self.limit);
+        let mut lines = Vec::new();
+        while !selector.done()
+            && let Some(line) = records.next_bytes()
+        {
+            let text = String::from_utf8_lossy(line.map_err(input_error(name))?);
+            line_number += 1;
+            if !selector.is_selected(&text) {
+                continue;
+            }
+            let ranges = if config.invert {
+                Vec::new()
+            } else {
+                config.matcher().match_ranges(&text, config.ignore_case)
+            };
+            lines.push(MatchedLine {
+                line_number,
+                text: text.into_owned(),
+                ranges,
+            });
+        }
+        stats.record_file(line_number as u64, lines.len() as u64);
+        if let Some(collected) = &mut self.collected {
+            collected.matches.push(FileMatches {
+                path: name.to_string(),
+                lines,
+                searched_lines: line_number as u64,
+            });
+        }
+        Ok(())
+    }
+
+    /// Searches the input called `name` as it is read from `reader`,
+    /// printing a row of the table in `format` for every selected line, and
+    /// records the file in `stats`.
+    fn print_table_file(
+        &mut self,
+        name: &str,
+        reader: impl BufRead,
+        format: csv::Format,
+        stats: &mut SearchStats,
+    ) -> Result<(), MinigrepError> {
+        let config = self.config;
+        let (mut lines, mut line_number) = self.read_window(name, reader, self.terminator())?;
+        let mut selector = Selector::new(config, self.limit);
+        let mut selected_lines = 0;
+        while !selector.done()
+            && let Some(line) = lines.next_bytes()
+        {
+            let text = String::from_utf8_lossy(line.map_err(input_error(name))?);
+            line_number += 1;
+            if !selector.is_selected(&text) {
+                continue;
+            }
+            selected_lines += 1;
+            let column = config
+                .matcher()
+                .match_ranges(&text, config.ignore_case)
+                .first()
+                .map(|range| (text[..range.start].chars().count() + 1).to_string())
+                .unwrap_or_default();
+            let row = format.record(&[name, &line_number.to_string(), &column, &text]);
+            self.output.write_line(&row)?;
+        }
+        stats.record_file(line_number as u64, selected_lines);
+        Ok(())
+    }
+
+    /// Searches the input called `name` as it is read from `reader`,
+    /// printing the SARIF result of every match on a line of its own, to be
+    /// gathered into the report, and records the file in `stats`.
+    #[cfg(feature = "json")]
+    fn print_sarif_file(
+        &mut self,
+        name: &str,
+        reader: impl BufRead,
+        stats: &mut SearchStats,
+    ) -> Result<(), MinigrepError> {
+        let config = self.config;
+        let (mut lines, mut line_number) = self.read_window(name, reader, self.terminator())?;
+        let mut selected_lines = 0;
+        while let Some(line) = lines.next_bytes() {
+            let text = String::from_utf8_lossy(line.map_err(input_error(name))?);
+            line_number += 1;
+            let ranges = config.matcher().pattern_ranges(&text, config.ignore_case);
+            if ranges.is_empty() {
+                continue;
+            }
+            selected_lines += 1;
+            for (range, pattern) in ranges {
+                let start = text[..range.start].chars().count() + 1;
+                let matched = &text[range];
+                let end = start + matched.chars().count();
+                let result = sarif::result(pattern, name, line_number, start, end, matched);
+                self.output.write_line(&result)?;
+            }
+        }
+        stats.record_file(line_number as u64, selected_lines);
+        Ok(())
+    }
+
+    /// Prints `line` after `prefix`, rendered with its matches highlighted,
+    /// and applying `--max-columns` and `--snippet`.
+    fn print_line(&mut self, prefix: &str, line: LineMatch) -> io::Result<()> {
+        let config = self.config;
+        let mut window = None;
+        if let Some(max_columns) = config.max_columns
+            && line.text.chars().count() > max_columns
+        {
+            if !config.max_columns_preview {
+                let notice = format!("[Omitted long line with {} matches]", line.ranges.len());
+                return self
+                    .output
+                    .write_terminated(&format!("{prefix}{notice}"), self.terminator());
+            }
+            window = Some(snippet::preview_window(
+                line.text,
+                line.ranges.first().cloned(),
+                max_columns,
+            ));
+        }
+        if let Some(context_chars) = config.snippet
+            && let Some(first) = line.ranges.first()
+        {
+            window = Some(snippet::snippet_window(
+                line.text,
+                first.clone(),
+                context_chars,
+            ));
+        }
+        let window = window.unwrap_or(0..line.text.len());
+        self.print_window(prefix, line, window)
+    }
+
+    /// Prints the part `window` of `line` after `prefix`, rendered with its
+    /// matches highlighted, marking where it was cut with [`ELLIPSIS`].
+    fn print_window(
+        &mut self,
+        prefix: &str,
+        mut line: LineMatch,
+        window: Range<usize>,
+    ) -> io::Result<()> {
+        let before = if window.start > 0 { ELLIPSIS } else { "" };
+        let after = if window.end < line.text.len() {
+            ELLIPSIS
+        } else {
+            ""
+        };
+        if window != (0..line.text.len()) {
+            line.patterns = line
+                .ranges
+                .iter()
+                .zip(&line.patterns)
+                .filter(|(range, _)| range.start.max(window.start) < range.end.min(window.end))
+                .map(|(_, pattern)| *pattern)
+                .collect();
+            line.ranges = snippet::clip_ranges(&line.ranges, window.clone());
+            line.text = &line.text[window];
+        }
+
+        let text = if self.colored {
+            render_match(&line, &Palette(&self.theme))
+        } else {
+            render_match(&line, &Plain)
+        };
+        self.output
+            .write_terminated(&format!("{prefix}{before}{text}{after}"), self.terminator())
+    }
+
+    /// Flushes everything printed so far.
+    fn finish(&mut self) -> io::Result<()> {
+        self.output.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::OsString;
+
+    #[test]
+    fn verbatim_prefixes_are_stripped() {
+        let stripped = |name: &'static str| strip_verbatim(Cow::Borrowed(name)).into_owned();
+        assert_eq!(stripped(r"\\?\C:\logs\app.log"), r"C:\logs\app.log");
+        assert_eq!(
+            stripped(r"\\?\UNC\server\share\a.log"),
+            r"\\server\share\a.log"
+        );
+        assert_eq!(
+            stripped(r"\\?\Volume{1234}\a.log"),
+            r"\\?\Volume{1234}\a.log"
+        );
+        assert_eq!(stripped(r"C:\logs\app.log"), r"C:\logs\app.log");
+        assert_eq!(stripped("logs/app.log"), "logs/app.log");
+    }
+
+    fn build(args: &[&str]) -> Config {
+        let args = std::iter::once("minigrep").chain(args.iter().copied());
+        Config::build(args.map(OsString::from), true, |_| None).unwrap()
+    }
+
+    /// Searches `contents` as the file `poem.txt` with the options in `args`,
+    /// returning what was printed into memory.
+    fn printed(args: &[&str], contents: &str) -> String {
+        let args = [args, &["rust", "poem.txt"]].concat();
+        let config = build(&args);
+        let mut err = Vec::new();
+        let messages = Messages::new(&mut err, false, false, config.columns);
+        let mut printer = Printer::with_writer(&config, Vec::new(), false, &messages);
+        let mut stats = SearchStats::default();
+        printer
+            .print_file("poem.txt", contents.as_bytes(), &mut stats)
+            .unwrap();
+        printer.finish().unwrap();
+        String::from_utf8(printer.output.into_inner()).unwrap()
+    }
+
+    #[test]
+    fn byte_offsets_count_from_the_start_of_the_input() {
+        let contents = "a rust\r\nnothing\nrust, rust\n";
+        assert_eq!(printed(&["-b"], contents), "0:a rust\n16:rust, rust\n");
+        assert_eq!(
+            printed(&["-H", "-n", "-b"], contents),
+            "poem.txt:1:0:a rust\npoem.txt:3:16:rust, rust\n"
+        );
+        assert_eq!(
+            printed(&["-o", "-b"], contents),
+            "2:rust\n16:rust\n22:rust\n"
+        );
+        assert_eq!(
+            printed(&["--passthru", "-b"], contents),
+            "0:a rust\n8-nothing\n16:rust, rust\n"
+        );
+    }
+
+    #[test]
+    fn line_buffering_does_not_change_the_output() {
+        let contents = "rust and rust\nnothing\n".repeat(5_000) + "last rust";
+        for args in [
+            &[][..],
+            &["-n", "-H"],
+            &["-o"],
+            &["-c"],
+            &["--vimgrep"],
+            &["--replace=go"],
+            &["-v", "-n"],
+        ] {
+            let block = printed(args, &contents);
+            let line = printed(&[args, &["--line-buffered"]].concat(), &contents);
+            assert_eq!(block, line, "{args:?}");
+        }
+        assert_eq!(printed(&["-c"], &contents), "5001\n");
+        assert_eq!(printed(&["-n"], "a\nrust\n"), "2:rust\n");
+    }
+}
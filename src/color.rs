@@ -0,0 +1,444 @@
+//! Terminal colors for highlighting matches, file names, and line numbers.
+//!
+//! Colors are written as ANSI SGR escape sequences, e.g. `\x1b[01;31m`.
+//! Which sequences are used is described by a [`Theme`], which can be
+//! customized through a `GREP_COLORS`-style specification.
+
+use std::ops::Range;
+
+use crate::render::{Colored, LineMatch, render_match};
+
+/// The SGR parameters used for each part of the output.
+///
+/// An empty string leaves that part uncolored.
+///
+/// - `matched`: The matched text, bold red (`01;31`) by default.
+/// - `filename`: File name prefixes, magenta (`35`) by default.
+/// - `line_number`: Line number prefixes, green (`32`) by default.
+/// - `byte_offset`: Byte offset prefixes, green (`32`) by default.
+/// - `separator`: The `:` separators between prefixes, uncolored by default.
+/// - `patterns`: The matched text of the second pattern and those after it,
+///   when several are searched for, bold green, yellow, blue, magenta, and
+///   cyan by default.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    pub matched: String,
+    pub filename: String,
+    pub line_number: String,
+    pub byte_offset: String,
+    pub separator: String,
+    pub patterns: Vec<String>,
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme {
+            matched: String::from("01;31"),
+            filename: String::from("35"),
+            line_number: String::from("32"),
+            byte_offset: String::from("32"),
+            separator: String::new(),
+            patterns: ["01;32", "01;33", "01;34", "01;35", "01;36"]
+                .map(String::from)
+                .to_vec(),
+        }
+    }
+}
+
+/// `GREP_COLORS` capabilities that are accepted but have no effect here.
+const IGNORED_CAPABILITIES: [&str; 5] = ["mc", "sl", "cx", "rv", "ne"];
+
+impl Theme {
+    /// Parses a `GREP_COLORS`-style specification such as
+    /// `ms=01;32:fn=35:ln=32:se=36`, starting from the default theme.
+    ///
+    /// Supported capabilities are `ms` and `mt` (matched text), `fn`
+    /// (file names), `ln` (line numbers), `bn` (byte offsets), `se`
+    /// (separators), and `mp`, the matched text of the patterns after the
+    /// first, as SGR sequences separated by commas, e.g. `mp=01;32,01;34`.
+    /// The other
+    /// `GREP_COLORS` capabilities are accepted and ignored, unknown ones are
+    /// ignored with a warning, and a malformed value keeps the default for
+    /// its capability, also with a warning.
+    ///
+    /// # Returns
+    /// The parsed theme, along with any warnings to report to the user.
+    ///
+    /// # Examples
+    /// ```
+    /// use minigrep_cli_tool::color::Theme;
+    ///
+    /// let (theme, warnings) = Theme::parse("ms=01;32:fn=34");
+    /// assert_eq!(theme.matched, "01;32");
+    /// assert_eq!(theme.filename, "34");
+    /// assert!(warnings.is_empty());
+    /// ```
+    pub fn parse(spec: &str) -> (Theme, Vec<String>) {
+        let mut theme = Theme::default();
+        let mut warnings = Vec::new();
+
+        for entry in spec.split(':').filter(|entry| !entry.is_empty()) {
+            let (key, value) = entry.split_once('=').unwrap_or((entry, ""));
+            if key == "mp" {
+                if value.is_empty() {
+                    theme.patterns.clear();
+                } else if value.split(',').all(is_valid_sgr) {
+                    theme.patterns = value.split(',').map(String::from).collect();
+                } else {
+                    warnings.push(format!("ignoring malformed color value '{entry}'"));
+                }
+                continue;
+            }
+            let field = match key {
+                "ms" | "mt" => &mut theme.matched,
+                "fn" => &mut theme.filename,
+                "ln" => &mut theme.line_number,
+                "bn" => &mut theme.byte_offset,
+                "se" => &mut theme.separator,
+                _ if IGNORED_CAPABILITIES.contains(&key) => continue,
+                _ => {
+                    warnings.push(format!("ignoring unknown color capability '{key}'"));
+                    continue;
+                }
+            };
+            if is_valid_sgr(value) {
+                *field = value.to_string();
+            } else {
+                warnings.push(format!("ignoring malformed color value '{entry}'"));
+            }
+        }
+
+        (theme, warnings)
+    }
+
+    /// The SGR parameters of the matched text of the pattern numbered
+    /// `pattern`: `matched` for the first, and then each of `patterns` in
+    /// turn, starting over with `matched` once they run out.
+    ///
+    /// # Examples
+    /// ```
+    /// use minigrep_cli_tool::color::Theme;
+    ///
+    /// let (theme, _) = Theme::parse("ms=31:mp=32,34");
+    /// let colors: Vec<&str> = (0..4).map(|pattern| theme.pattern(pattern)).collect();
+    /// assert_eq!(colors, ["31", "32", "34", "31"]);
+    /// ```
+    pub fn pattern(&self, pattern: usize) -> &str {
+        match pattern % (self.patterns.len() + 1) {
+            0 => &self.matched,
+            index => &self.patterns[index - 1],
+        }
+    }
+}
+
+/// Returns whether `value` consists only of SGR parameters, e.g. `01;31`,
+/// or is empty.
+fn is_valid_sgr(value: &str) -> bool {
+    value.is_empty()
+        || value
+            .split(';')
+            .all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// When to color the output, as chosen with `--color`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Color only when standard output is a terminal.
+    Auto,
+    /// Always color.
+    Always,
+    /// Never color.
+    Never,
+}
+
+impl ColorChoice {
+    /// Parses a `--color` value: `auto`, `always`, or `never`.
+    ///
+    /// # Examples
+    /// ```
+    /// use minigrep_cli_tool::color::ColorChoice;
+    ///
+    /// assert_eq!(ColorChoice::parse("always"), Some(ColorChoice::Always));
+    /// assert_eq!(ColorChoice::parse("sometimes"), None);
+    /// ```
+    pub fn parse(value: &str) -> Option<ColorChoice> {
+        match value {
+            "auto" => Some(ColorChoice::Auto),
+            "always" => Some(ColorChoice::Always),
+            "never" => Some(ColorChoice::Never),
+            _ => None,
+        }
+    }
+}
+
+/// The environment variables coloring depends on, read once, when a
+/// [`Config`](crate::Config) is built.
+///
+/// - `no_color`: The value of `NO_COLOR`, if set.
+/// - `clicolor_force`: The value of `CLICOLOR_FORCE`, if set.
+/// - `colors`: The [`Theme`] specification, with the variable it was read
+///   from: `MINIGREP_COLORS` if set, and otherwise `GREP_COLORS`, if set.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ColorEnv {
+    pub no_color: Option<String>,
+    pub clicolor_force: Option<String>,
+    pub colors: Option<(&'static str, String)>,
+}
+
+impl ColorEnv {
+    /// Reads the variables through `env`, which returns the value of the
+    /// named variable, if set.
+    ///
+    /// # Examples
+    /// ```
+    /// use minigrep_cli_tool::color::ColorEnv;
+    ///
+    /// let env = |name: &str| (name == "GREP_COLORS").then(|| String::from("mt=01;32"));
+    /// let colors = ColorEnv::read(env).colors;
+    /// assert_eq!(colors, Some(("GREP_COLORS", String::from("mt=01;32"))));
+    /// ```
+    pub fn read(env: impl Fn(&str) -> Option<String>) -> ColorEnv {
+        let colors = ["MINIGREP_COLORS", "GREP_COLORS"]
+            .into_iter()
+            .find_map(|variable| Some((variable, env(variable)?)));
+        ColorEnv {
+            no_color: env("NO_COLOR"),
+            clicolor_force: env("CLICOLOR_FORCE"),
+            colors,
+        }
+    }
+}
+
+/// Decides whether output should be colored.
+///
+/// The precedence is:
+/// 1. An explicit `--color=always` or `--color=never`.
+/// 2. `NO_COLOR`: any non-empty value disables color.
+/// 3. `CLICOLOR_FORCE`: any non-empty value other than `0` enables color,
+///    so that it survives piping into e.g. `less -R`.
+/// 4. Whether standard output (not standard error) is a terminal.
+///
+/// # Arguments
+/// - `choice`: The `--color` setting.
+/// - `no_color`: The value of the `NO_COLOR` environment variable, if set.
+/// - `clicolor_force`: The value of the `CLICOLOR_FORCE` environment variable, if set.
+/// - `stdout_is_terminal`: Whether standard output is a terminal.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::color::{should_color, ColorChoice};
+///
+/// assert!(!should_color(ColorChoice::Auto, Some("1"), None, true));
+/// assert!(should_color(ColorChoice::Always, Some("1"), None, false));
+/// ```
+pub fn should_color(
+    choice: ColorChoice,
+    no_color: Option<&str>,
+    clicolor_force: Option<&str>,
+    stdout_is_terminal: bool,
+) -> bool {
+    color_decision(choice, no_color, clicolor_force, stdout_is_terminal).0
+}
+
+/// Decides whether output should be colored as [`should_color`] does, also
+/// returning the reason, for `--debug`.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::color::{color_decision, ColorChoice};
+///
+/// assert_eq!(
+///     color_decision(ColorChoice::Auto, Some("1"), Some("1"), true),
+///     (false, "NO_COLOR is set")
+/// );
+/// ```
+pub fn color_decision(
+    choice: ColorChoice,
+    no_color: Option<&str>,
+    clicolor_force: Option<&str>,
+    stdout_is_terminal: bool,
+) -> (bool, &'static str) {
+    match choice {
+        ColorChoice::Always => (true, "--color=always is given"),
+        ColorChoice::Never => (false, "--color=never is given"),
+        ColorChoice::Auto if no_color.is_some_and(|value| !value.is_empty()) => {
+            (false, "NO_COLOR is set")
+        }
+        ColorChoice::Auto
+            if clicolor_force.is_some_and(|value| !value.is_empty() && value != "0") =>
+        {
+            (true, "CLICOLOR_FORCE is set")
+        }
+        ColorChoice::Auto if stdout_is_terminal => (true, "standard output is a terminal"),
+        ColorChoice::Auto => (false, "standard output is not a terminal"),
+    }
+}
+
+/// Wraps `text` in the SGR sequence `sgr`, resetting afterwards.
+///
+/// An empty `sgr` leaves `text` unchanged.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::color::paint;
+///
+/// assert_eq!(paint("notes.txt", "35"), "\x1b[35mnotes.txt\x1b[0m");
+/// assert_eq!(paint("notes.txt", ""), "notes.txt");
+/// ```
+pub fn paint(text: &str, sgr: &str) -> String {
+    if sgr.is_empty() {
+        text.to_string()
+    } else {
+        format!("\x1b[{sgr}m{text}\x1b[0m")
+    }
+}
+
+/// Colors each of the byte `ranges` of `line` with `sgr`, leaving the rest
+/// of the line untouched.
+///
+/// The ranges must be sorted, non-overlapping, and lie on character
+/// boundaries, as returned by [`crate::match_ranges`].
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::color::highlight;
+///
+/// let line = highlight("a rust b rust", &[2..6, 9..13], "01;31");
+/// assert_eq!(line, "a \x1b[01;31mrust\x1b[0m b \x1b[01;31mrust\x1b[0m");
+/// ```
+pub fn highlight(line: &str, ranges: &[Range<usize>], sgr: &str) -> String {
+    let line = LineMatch {
+        line_number: 0,
+        text: line,
+        ranges: ranges.to_vec(),
+        patterns: Vec::new(),
+    };
+    render_match(&line, &Colored(sgr))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn explicit_choice_beats_everything() {
+        for no_color in [None, Some("1")] {
+            for force in [None, Some("1")] {
+                for tty in [false, true] {
+                    assert!(should_color(ColorChoice::Always, no_color, force, tty));
+                    assert!(!should_color(ColorChoice::Never, no_color, force, tty));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn auto_follows_terminal_without_env() {
+        assert!(should_color(ColorChoice::Auto, None, None, true));
+        assert!(!should_color(ColorChoice::Auto, None, None, false));
+    }
+
+    #[test]
+    fn no_color_disables_auto() {
+        assert!(!should_color(ColorChoice::Auto, Some("1"), None, true));
+        assert!(!should_color(
+            ColorChoice::Auto,
+            Some("1"),
+            Some("1"),
+            false
+        ));
+        assert!(should_color(ColorChoice::Auto, Some(""), None, true));
+    }
+
+    #[test]
+    fn clicolor_force_enables_auto_through_pipes() {
+        assert!(should_color(ColorChoice::Auto, None, Some("1"), false));
+        assert!(!should_color(ColorChoice::Auto, None, Some("0"), false));
+        assert!(!should_color(ColorChoice::Auto, None, Some(""), false));
+    }
+
+    #[test]
+    fn theme_defaults() {
+        let (theme, warnings) = Theme::parse("");
+        assert_eq!(theme, Theme::default());
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn theme_overrides_each_capability() {
+        let (theme, warnings) = Theme::parse("mt=01;32:fn=34:ln=33:bn=31:se=36:mp=1,2");
+        assert_eq!(
+            theme,
+            Theme {
+                matched: String::from("01;32"),
+                filename: String::from("34"),
+                line_number: String::from("33"),
+                byte_offset: String::from("31"),
+                separator: String::from("36"),
+                patterns: vec![String::from("1"), String::from("2")],
+            }
+        );
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn theme_cycles_through_the_pattern_colors() {
+        let theme = Theme::default();
+        let colors: Vec<&str> = (0..7).map(|pattern| theme.pattern(pattern)).collect();
+        assert_eq!(
+            colors,
+            [
+                "01;31", "01;32", "01;33", "01;34", "01;35", "01;36", "01;31"
+            ]
+        );
+        let (theme, warnings) = Theme::parse("ms=4:mp=1;35");
+        assert!(warnings.is_empty());
+        assert_eq!((theme.pattern(1), theme.pattern(2)), ("1;35", "4"));
+        let (theme, _) = Theme::parse("mp=");
+        assert_eq!((theme.pattern(0), theme.pattern(1)), ("01;31", "01;31"));
+        let (theme, warnings) = Theme::parse("mp=32,blue");
+        assert_eq!(theme.patterns, Theme::default().patterns);
+        assert_eq!(warnings, ["ignoring malformed color value 'mp=32,blue'"]);
+    }
+
+    #[test]
+    fn theme_empty_value_disables_color() {
+        let (theme, _) = Theme::parse("fn=");
+        assert_eq!(theme.filename, "");
+    }
+
+    #[test]
+    fn theme_ignores_grep_only_capabilities() {
+        let (theme, warnings) = Theme::parse("sl=:cx=:rv:ne:mc=01;31");
+        assert_eq!(theme, Theme::default());
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn theme_warns_on_unknown_and_malformed() {
+        let (theme, warnings) = Theme::parse("xx=1:ms=red:ln=;32:fn=36");
+        assert_eq!(theme.matched, "01;31");
+        assert_eq!(theme.line_number, "32");
+        assert_eq!(theme.filename, "36");
+        assert_eq!(warnings.len(), 3);
+        assert!(warnings[0].contains("'xx'"));
+        assert!(warnings[1].contains("ms=red"));
+    }
+
+    #[test]
+    fn highlight_without_ranges_is_unchanged() {
+        assert_eq!(highlight("plain line", &[], "01;31"), "plain line");
+    }
+
+    #[test]
+    fn highlight_adjacent_and_multibyte() {
+        assert_eq!(
+            highlight("rustrust", &[0..4, 4..8], "01;31"),
+            "\x1b[01;31mrust\x1b[0m\x1b[01;31mrust\x1b[0m"
+        );
+        assert_eq!(
+            highlight("héllo wörld", &[1..3, 7..13], "01;31"),
+            "h\x1b[01;31mé\x1b[0mllo \x1b[01;31mwörld\x1b[0m"
+        );
+    }
+}
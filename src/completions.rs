@@ -0,0 +1,320 @@
+//! The completion scripts of `--generate-completions`, made from the table
+//! of [`OPTIONS`] that the command line is parsed with, so that every
+//! option is completed without being listed again here.
+//!
+//! Each script completes the names of the options and, for those taking
+//! one of a few values, such as `--color`, the values; other values and
+//! the query are left to the shell's completion of file names.
+
+use crate::options::{OPTIONS, Opt, Value};
+
+/// The names of the shells, as `--generate-completions` takes them.
+pub const SHELLS: &[&str] = &["bash", "zsh", "fish", "powershell"];
+
+/// The names the binary is completed under: the name it goes by, and that
+/// of the package, which `cargo install` installs it as.
+const COMMANDS: &[&str] = &["minigrep", "minigrep-cli-tool"];
+
+/// A shell to write a completion script for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    PowerShell,
+}
+
+impl Shell {
+    /// Parses a `--generate-completions` value, one of [`SHELLS`].
+    ///
+    /// # Examples
+    /// ```
+    /// use minigrep_cli_tool::completions::Shell;
+    ///
+    /// assert_eq!(Shell::parse("zsh"), Some(Shell::Zsh));
+    /// assert_eq!(Shell::parse("tcsh"), None);
+    /// ```
+    pub fn parse(name: &str) -> Option<Shell> {
+        match name {
+            "bash" => Some(Shell::Bash),
+            "zsh" => Some(Shell::Zsh),
+            "fish" => Some(Shell::Fish),
+            "powershell" => Some(Shell::PowerShell),
+            _ => None,
+        }
+    }
+
+    /// Returns the completion script for this shell, to be sourced, or in
+    /// zsh's case put on `fpath` as `_minigrep`.
+    ///
+    /// # Examples
+    /// ```
+    /// use minigrep_cli_tool::completions::Shell;
+    ///
+    /// assert!(Shell::Bash.script().contains(" --line-number "));
+    /// assert!(Shell::Fish.script().contains("-s n -l line-number"));
+    /// ```
+    pub fn script(self) -> String {
+        match self {
+            Shell::Bash => bash(),
+            Shell::Zsh => zsh(),
+            Shell::Fish => fish(),
+            Shell::PowerShell => powershell(),
+        }
+    }
+}
+
+/// A script defining `_minigrep` for `complete -F`.
+///
+/// As bash splits `--name=value` at the `=`, the word before the one being
+/// completed may be `=`, in which case the option is the word before that.
+fn bash() -> String {
+    let mut cases = String::new();
+    for option in OPTIONS {
+        let complete = if option.choices.is_empty() {
+            String::from("return")
+        } else {
+            let choices = quoted(&option.choices.join(" "));
+            format!("COMPREPLY=($(compgen -W {choices} -- \"$cur\")); return")
+        };
+        let names = option.names().collect::<Vec<_>>().join("|");
+        match option.value {
+            Value::None => continue,
+            Value::Required(_) => cases.push_str(&format!("        {names}) {complete} ;;\n")),
+            Value::Optional(_) => cases.push_str(&format!(
+                "        {names}) if [[ $inline ]]; then {complete}; fi ;;\n"
+            )),
+        }
+    }
+    let words = OPTIONS.iter().flat_map(Opt::names).collect::<Vec<_>>();
+    let words = quoted(&words.join(" "));
+    format!(
+        "# bash completion for minigrep, from `minigrep --generate-completions bash`
+_minigrep() {{
+    local cur=${{COMP_WORDS[COMP_CWORD]}} prev=${{COMP_WORDS[COMP_CWORD-1]}} inline=
+    if [[ $cur == = ]]; then
+        cur= inline=1
+    elif [[ $prev == = ]]; then
+        prev=${{COMP_WORDS[COMP_CWORD-2]}} inline=1
+    fi
+    case $prev in
+{cases}    esac
+    if [[ $cur == -* ]]; then
+        COMPREPLY=($(compgen -W {words} -- \"$cur\"))
+    fi
+}}
+complete -o default -F _minigrep {}
+",
+        COMMANDS.join(" ")
+    )
+}
+
+/// A `#compdef` function handing every option to `_arguments`.
+fn zsh() -> String {
+    let mut specs = String::new();
+    for option in OPTIONS {
+        let escaped = option
+            .help
+            .replace('\\', "\\\\")
+            .replace('[', "\\[")
+            .replace(']', "\\]")
+            .replace(':', "\\:");
+        let action = if !option.choices.is_empty() {
+            format!("({})", option.choices.join(" "))
+        } else {
+            match option.value {
+                Value::Required("FILE") => String::from("_files"),
+                Value::Required("COMMAND") | Value::Optional("CMD") => {
+                    String::from("_command_names -e")
+                }
+                _ => String::from(" "),
+            }
+        };
+        let (suffix, rest) = match option.value {
+            Value::None => ("", format!("[{escaped}]")),
+            Value::Required(name) => ("=", format!("[{escaped}]:{name}:{action}")),
+            Value::Optional(name) => ("=-", format!("[{escaped}]::{name}:{action}")),
+        };
+        let long = option.long;
+        let spec = match option.short {
+            Some(short) => format!("'*'{{{short},{long}{suffix}}}{}", quoted(&rest)),
+            None => quoted(&format!("*{long}{suffix}{rest}")),
+        };
+        specs.push_str(&format!("    {spec} \\\n"));
+    }
+    format!(
+        "#compdef {}
+# zsh completion for minigrep, from `minigrep --generate-completions zsh`
+
+_arguments -S \\
+{specs}    '*:file:_files'
+",
+        COMMANDS.join(" ")
+    )
+}
+
+/// A `complete` command for every option, the other names wrapping the
+/// first.
+fn fish() -> String {
+    let quote = |text: &str| format!("'{}'", text.replace('\\', "\\\\").replace('\'', "\\'"));
+    let command = COMMANDS[0];
+    let mut script = String::from(
+        "# fish completion for minigrep, from `minigrep --generate-completions fish`\n",
+    );
+    for option in OPTIONS {
+        let mut line = format!("complete -c {command}");
+        if let Some(short) = option.short {
+            line.push_str(&format!(" -s {}", &short[1..]));
+        }
+        line.push_str(&format!(" -l {}", &option.long[2..]));
+        match (option.value, option.choices) {
+            (Value::None, _) => {}
+            (Value::Required(_), []) => line.push_str(" -r"),
+            (Value::Required(_), choices) => {
+                line.push_str(&format!(" -x -a {}", quote(&choices.join(" "))))
+            }
+            (Value::Optional(_), []) => {}
+            (Value::Optional(_), choices) => {
+                line.push_str(&format!(" -f -a {}", quote(&choices.join(" "))))
+            }
+        }
+        line.push_str(&format!(" -d {}\n", quote(option.help)));
+        script.push_str(&line);
+    }
+    for other in &COMMANDS[1..] {
+        script.push_str(&format!("complete -c {other} -w {command}\n"));
+    }
+    script
+}
+
+/// A native argument completer listing every name with its help, and the
+/// choices of `--name=` values.
+///
+/// The names are a list of pairs rather than a hashtable, whose keys would
+/// not tell `-l` from `-L`.
+fn powershell() -> String {
+    let quote = |text: &str| format!("'{}'", text.replace('\'', "''"));
+    let mut options = Vec::new();
+    let mut choices = String::new();
+    for option in OPTIONS {
+        for name in option.names() {
+            options.push(format!(
+                "        @({}, {})",
+                quote(name),
+                quote(option.help)
+            ));
+        }
+        if !option.choices.is_empty() {
+            let values = option.choices.iter().map(|choice| quote(choice));
+            choices.push_str(&format!(
+                "        {} = @({})\n",
+                quote(option.long),
+                values.collect::<Vec<_>>().join(", ")
+            ));
+        }
+    }
+    format!(
+        "# PowerShell completion for minigrep, from `minigrep --generate-completions powershell`
+Register-ArgumentCompleter -Native -CommandName {} -ScriptBlock {{
+    param($wordToComplete, $commandAst, $cursorPosition)
+    $options = @(
+{}
+    )
+    $choices = @{{
+{choices}    }}
+    if (-not $wordToComplete.StartsWith('-')) {{
+        return
+    }}
+    if ($wordToComplete -match '^(--[^=]+)=(.*)$' -and $choices.Contains($Matches[1])) {{
+        $name, $prefix = $Matches[1], $Matches[2]
+        $choices[$name] | Where-Object {{ $_ -clike \"$prefix*\" }} | ForEach-Object {{
+            [System.Management.Automation.CompletionResult]::new(\"$name=$_\", $_, 'ParameterValue', $_)
+        }}
+        return
+    }}
+    $options | Where-Object {{ $_[0] -clike \"$wordToComplete*\" }} | ForEach-Object {{
+        [System.Management.Automation.CompletionResult]::new($_[0], $_[0], 'ParameterName', $_[1])
+    }}
+}}
+",
+        COMMANDS.join(", "),
+        options.join(",\n")
+    )
+}
+
+/// Returns `text` between single quotes for bash or zsh, each single quote
+/// in it written as `'\''`.
+fn quoted(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_shell_named_is_parsed() {
+        for name in SHELLS {
+            assert!(Shell::parse(name).is_some(), "{name}");
+        }
+        assert_eq!(Shell::parse("PowerShell"), None);
+    }
+
+    #[test]
+    fn bash_completes_every_option_and_the_choices_of_values() {
+        let script = Shell::Bash.script();
+        let words = script
+            .lines()
+            .find_map(|line| line.trim().strip_prefix("COMPREPLY=($(compgen -W '"))
+            .and_then(|line| line.strip_suffix("' -- \"$cur\"))"))
+            .unwrap()
+            .split(' ')
+            .collect::<Vec<_>>();
+        for option in OPTIONS {
+            for name in option.names() {
+                assert!(words.contains(&name), "{name}");
+            }
+        }
+        assert!(script.contains(
+            "        --sort) COMPREPLY=($(compgen -W 'path none' -- \"$cur\")); return ;;\n"
+        ));
+        assert!(script.contains("        --color) if [[ $inline ]]; then "));
+        assert!(script.contains("        -A|--after-context) return ;;\n"));
+        assert!(script.ends_with("complete -o default -F _minigrep minigrep minigrep-cli-tool\n"));
+    }
+
+    #[test]
+    fn zsh_specs_describe_names_values_and_choices() {
+        let script = Shell::Zsh.script();
+        assert!(script.starts_with("#compdef minigrep minigrep-cli-tool\n"));
+        assert!(
+            script.contains(
+                "    '*'{-n,--line-number}'[prefix each line with its line number]' \\\n"
+            )
+        );
+        assert!(script.contains(
+            "    '*'{-A,--after-context=}'[also print the NUM lines after each match]:NUM: ' \\\n"
+        ));
+        assert!(script.contains("    '*--color=-[highlight matches, file names, and line numbers]::WHEN:(auto always never)' \\\n"));
+        assert!(script.contains("\\:LINE\\:COLUMN\\:TEXT"));
+        assert!(script.ends_with("    '*:file:_files'\n"));
+    }
+
+    #[test]
+    fn fish_and_powershell_list_every_option() {
+        let fish = Shell::Fish.script();
+        assert_eq!(fish.matches("complete -c minigrep ").count(), OPTIONS.len());
+        assert!(fish.contains("complete -c minigrep -l sort -x -a 'path none' -d "));
+        assert!(fish.contains("complete -c minigrep -s j -l threads -r -d "));
+        assert!(fish.ends_with("complete -c minigrep-cli-tool -w minigrep\n"));
+
+        let powershell = Shell::PowerShell.script();
+        for name in OPTIONS.iter().flat_map(Opt::names) {
+            assert!(
+                powershell.contains(&format!("        @('{name}', '")),
+                "{name}"
+            );
+        }
+        assert!(powershell.contains("        '--quote' = @('none', 'shell')\n"));
+    }
+}
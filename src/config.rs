@@ -0,0 +1,3284 @@
+//! The configuration of a search, as the command line asks for it.
+//!
+//! A [`Config`] is usually built from the arguments of the binary with
+//! [`Config::build`], which checks them as `minigrep` does, but it can also
+//! be made with [`Config::new`] and its fields set directly, before being
+//! passed to [`run`](crate::run).
+
+use crate::{
+    Matcher,
+    between::Between,
+    bytes::ByteQuery,
+    color::{ColorChoice, ColorEnv},
+    completions::Shell,
+    context::ContextUntil,
+    csv,
+    encoding::Encoding,
+    error::MinigrepError,
+    escape,
+    expr::Expr,
+    glob::{self, Glob},
+    hex,
+    metrics::Metrics,
+    mmap::ReadStrategy,
+    normalize::{self, Normalizing},
+    options::{self, Parse, Value},
+    pager::pager_command,
+    quote::Quoting,
+    render::{GROUP_SEPARATOR, Template},
+    timestamp::{self, TimeWindow, Untimed},
+};
+use std::{
+    borrow::Cow,
+    ffi::OsString,
+    fs::File,
+    io::{self, BufRead, BufReader},
+    ops::Range,
+    path::PathBuf,
+    sync::Arc,
+    thread,
+    time::Duration,
+};
+
+/// The name standard input is reported under when no `--label` is given.
+pub const DEFAULT_LABEL: &str = "(standard input)";
+
+/// How many bytes of output of the files after the one being printed are
+/// held back with `-j` and `--sort=path` when no `--sort-buffer` is given.
+pub const DEFAULT_SORT_BUFFER: usize = 16 << 20;
+
+/// How wide the terminal is taken to be when `COLUMNS` does not say.
+pub const DEFAULT_COLUMNS: usize = 80;
+
+/// Holds the command-line configuration for the program.
+///
+/// - `query`: The substring to search for, with the escapes of `--escapes`
+///   already interpreted; empty with `--files`, the text of `expr` with
+///   `--expr`, the first pattern with `-e` or `-f`, without any `(?i)` or
+///   `(?-i)`, and the start pattern with `--between`.
+/// - `expr`: The boolean expression of literals selecting lines instead of
+///   the query, if given with `--expr`, made of the patterns of `-e` and
+///   `-f` if there are several or one sets its own case, or made of the query and the patterns lines must
+///   not match, given with `--and-not`.
+/// - `between`: The start and end patterns of the ranges of lines selected
+///   instead of lines matching the query, if given with `--between`.
+/// - `time_window`: The window of time lines must start with a timestamp
+///   inside of to be searched, if given with `--since` or `--until`.
+/// - `file_paths`: Paths to the files to search, where `-` means standard input.
+/// - `ignore_case`: If `true`, performs a case-insensitive search.
+/// - `case_source`: What decided `ignore_case`, as `--debug` reports it.
+/// - `trim`: If `true`, lines are matched without their leading and
+///   trailing whitespace.
+/// - `squeeze_space`: If `true`, lines are matched with every run of
+///   whitespace made a single space, as the query and patterns already are.
+/// - `line_number`: If `true`, prefixes matching lines with their line number.
+/// - `column`: If `true`, prefixes matching lines with the column of their
+///   first match, and `-o` matches with theirs.
+/// - `byte_column`: If `true`, `column` counts bytes rather than characters.
+/// - `byte_offset`: If `true`, prefixes matching lines with the offset of
+///   their first byte, and `-o` matches with theirs, counted in the decoded
+///   line.
+/// - `hex`: The bytes to search for in the raw bytes of each input, printing
+///   the offset of each occurrence instead of lines, if the query was given
+///   in hex with `--hex`.
+/// - `hex_context`: How many bytes on each side of an occurrence `hex`
+///   prints in hex along with its offset.
+/// - `strings`: The fewest printable characters in a run of the raw bytes
+///   of each input searched as a line, instead of its lines, if asked for
+///   with `--strings`, which sets `byte_offset` so that each run is printed
+///   with its offset.
+/// - `with_filename`: If `true`, prefixes matching lines with the file name.
+/// - `recursive`: If `true`, searches directories recursively.
+/// - `max_filesize`: Files larger than this many bytes are skipped.
+/// - `min_filesize`: Files smaller than this many bytes are skipped.
+/// - `newer_than`, `older_than`: Files last modified longer ago than the
+///   first, or more recently than the second, are skipped.
+/// - `max_depth`: How many directory levels below each path `-r` descends.
+/// - `include`, `exclude`, `exclude_dir`: The globs choosing which files and
+///   directories found by `-r` are searched, including those of `--type`
+///   and `--type-not`.
+/// - `hidden`: If `true`, `-r` also searches hidden files and directories.
+/// - `follow`: If `true`, `-r` follows symbolic links.
+/// - `no_dedupe`: If `true`, `-r` searches a file found under several paths
+///   under each of them.
+/// - `one_file_system`: If `true`, `-r` stays on the file system of each
+///   path given.
+/// - `read_devices`: If `true`, devices, FIFOs, and sockets given as paths
+///   are read, as `--devices=read` asks, rather than skipped; those found
+///   by `-r` are skipped either way.
+/// - `no_ignore`: If `true`, `-r` does not skip the paths listed in
+///   `.gitignore` and `.ignore` files.
+/// - `no_ignore_files`: If `true`, `-r` does not skip the paths listed in
+///   any ignore file, including `.minigrepignore`.
+/// - `search_zip`: If `true`, compressed files found by `-r` are
+///   decompressed, as those given on the command line always are, and the
+///   members of zip and tar archives are searched.
+/// - `pre`: The command files are searched through, if any.
+/// - `pre_glob`: If not empty, only files whose names match one of these
+///   are searched through `pre`.
+/// - `max_columns`: Lines longer than this many characters are omitted or,
+///   with `max_columns_preview`, cut down around their first match.
+/// - `snippet`: How many characters to print on either side of the first
+///   match of each line, if lines are cut down around it.
+/// - `char_context`: How many characters to print on either side of every
+///   match, each match with its own, if lines are cut down around them.
+/// - `type_list`: If `true`, prints the file types known to `--type` instead
+///   of searching.
+/// - `completions`: The shell to print a completion script for instead of
+///   searching, if any.
+/// - `invert`: If `true`, selects the lines that do not match.
+/// - `count`: If `true`, prints only the number of selected lines per file.
+/// - `count_matches`: If `true`, the counts are of occurrences rather than
+///   lines.
+/// - `count_total`: If `true`, the counts are followed by their sum.
+/// - `count_by_file`: If `true`, the counts are printed once the search is
+///   over, each before its path and only for files with selected lines.
+/// - `sort_count`: If `true`, `count_by_file` prints the largest counts
+///   first, and equal ones by path.
+/// - `group_by_dir`: If `true`, `count_by_file` prints the counts added up
+///   by directory, each followed by those of its files, the largest first.
+/// - `group_depth`: How many levels below the path searched the directories
+///   `group_by_dir` adds up go, if not down to the directory of each file.
+/// - `wc`: If `true`, the counts are of the lines, words, and bytes
+///   selected, printed once the search is over as `wc` prints them, and
+///   followed by their total if there are several files.
+/// - `only_matching`: If `true`, prints each match instead of the whole line.
+/// - `unique_counts`: If `true`, `-o` prints each distinct match once, with
+///   how often it occurred, once the search is over.
+/// - `top`: How many of the most frequent matches `unique_counts` prints,
+///   or of the counts `count_by_file` prints, if not all of them.
+/// - `line_numbers_only`: If `true`, prints only the number of each selected
+///   line.
+/// - `ranges`: If `true`, `line_numbers_only` prints runs of consecutive
+///   line numbers as ranges.
+/// - `vimgrep`: If `true`, prints the line once per match, prefixed with the
+///   match's column.
+/// - `replace`: The text printed in place of each match, if any.
+/// - `in_place`: If `true`, files are rewritten with `replace` applied
+///   instead of being printed.
+/// - `no_backup`: If `true`, `in_place` keeps no `.bak` copy of each file.
+/// - `passthru`: If `true`, prints every line, not only the selected ones.
+/// - `context`: If `true`, selected lines are printed in groups with their
+///   context, even if that is no lines at all.
+/// - `before_context`, `after_context`: How many lines to print before and
+///   after each selected line.
+/// - `context_until`: The stop pattern up to which the lines after each
+///   selected line are printed too, if given with `--after-context-until`.
+/// - `group_separator`: The line printed between groups of context lines
+///   that are not adjacent, if any.
+/// - `files_with_matches`: If `true`, prints only the names of matching files.
+/// - `files_without_match`: If `true`, prints only the names of non-matching files.
+/// - `list_files`: If `true`, prints the files that would be searched instead
+///   of searching them.
+/// - `heading`: Whether to group lines under file name headings, if chosen
+///   with `--heading` or `--no-heading`.
+/// - `null_data`: If `true`, input and output records end with a NUL byte
+///   instead of a newline.
+/// - `null`: If `true`, file names are followed by a NUL byte.
+/// - `quote`: How file names are quoted.
+/// - `encoding`: The encoding inputs are read in, if not detected from a
+///   byte order mark.
+/// - `no_unicode`: If `true`, lines are matched as bytes, never checked to
+///   be UTF-8, and case is ignored for ASCII letters only; see
+///   [`crate::bytes`].
+/// - `absolute_path`: If `true`, relative file paths are made absolute
+///   before being searched and printed.
+/// - `label`: The name standard input is reported under.
+/// - `files_from`: The list further paths to search are read from, if any.
+/// - `line_buffered`: If `true`, flushes output after every line even when
+///   standard output is not a terminal, as `--watch` and `--json` also do.
+/// - `read_strategy`: How files are read, if `--read-strategy` was given;
+///   otherwise they are read into a buffer, as with
+///   [`ReadStrategy::Buffered`], and the method is not reported.
+/// - `threads`: How many files are searched at once.
+/// - `sort`: The order in which the results of different files are printed.
+/// - `sort_buffer`: With [`Sort::Path`], how many bytes of output of the
+///   files after the one being printed are held back at most.
+/// - `color`: When to color the output.
+/// - `color_env`: The environment variables that color the output with
+///   `--color=auto` or not, and choose its colors.
+/// - `pager`: The command output to a terminal is paged through, colored,
+///   if `--pager` was given or `MINIGREP_PAGER` is set, and `--no-pager`
+///   was not; see [`pager_command`](crate::pager::pager_command).
+/// - `less`: The value of `LESS`, if set, which the pager is run with
+///   instead of the options of [`Pager::spawn`](crate::pager::Pager::spawn).
+/// - `progress`: If `true`, keeps a status line on standard error while
+///   searching, if it is a terminal.
+/// - `columns`: The width of the terminal the `progress` line is kept
+///   within, from `COLUMNS`, or [`DEFAULT_COLUMNS`] without it.
+/// - `stats`: If `true`, prints a summary of the search to standard error.
+/// - `timings`: If `true`, prints how long each input took to search to
+///   standard error.
+/// - `timings_limit`: How many of the slowest inputs `timings` lists, if not
+///   all of them.
+/// - `no_messages`: If `true`, warnings about inputs that cannot be searched
+///   are not printed.
+/// - `quiet`: If `true`, nothing is printed to standard output, nor how many
+///   lines were found of those `require_count` asks for.
+/// - `skip_lines`: How many lines at the start of each input are read past
+///   without being searched; the rest keep their line numbers.
+/// - `head`: How many lines at the start of each input are read, skipped
+///   ones included, if not all of them.
+/// - `start_byte`: The offset each input is searched from, past the end of
+///   the line it falls within, if given with `--start-byte`.
+/// - `count_from_start`: If `true`, the lines before `start_byte` are
+///   counted, so that line numbers are known.
+/// - `max_total`: How many lines may be selected in all inputs together,
+///   after which the search stops, if limited with `--max-total`, or to 1
+///   by `first`.
+/// - `first`: Which line `--first` selects, the only one, if given.
+/// - `require_count`: How many lines must be selected in all inputs for the
+///   binary to succeed, if given with `--require-count` or
+///   `--require-match`; see
+///   [`ExitStatus::requiring`](crate::exit::ExitStatus::requiring).
+/// - `debug`: If `true`, why each file is skipped is logged to standard
+///   error; see the [`log`](crate::log) module.
+/// - `metrics`: The counters to add this search to, if any; see
+///   [`Metrics`].
+/// - `json`: If `true`, prints JSON Lines records instead of plain lines.
+/// - `output_format`: The format to print selected lines in instead of plain
+///   lines, if any.
+/// - `no_header`: If `true`, the table is printed without its header row.
+/// - `format`: The template of the line printed for each match instead of
+///   the usual one, if any.
+/// - `watch`: How often to check the file for appended lines, if it is
+///   followed with `--watch` after being searched.
+#[derive(Debug)]
+pub struct Config {
+    pub query: String,
+    pub expr: Option<Expr>,
+    pub between: Option<Between>,
+    pub time_window: Option<TimeWindow>,
+    pub file_paths: Vec<PathBuf>,
+    pub ignore_case: bool,
+    pub case_source: CaseSource,
+    pub trim: bool,
+    pub squeeze_space: bool,
+    pub line_number: bool,
+    pub column: bool,
+    pub byte_column: bool,
+    pub byte_offset: bool,
+    pub hex: Option<Vec<u8>>,
+    pub hex_context: usize,
+    pub strings: Option<usize>,
+    pub with_filename: bool,
+    pub recursive: bool,
+    pub max_filesize: Option<u64>,
+    pub min_filesize: Option<u64>,
+    pub newer_than: Option<Duration>,
+    pub older_than: Option<Duration>,
+    pub max_depth: Option<usize>,
+    pub include: Vec<Glob>,
+    pub exclude: Vec<Glob>,
+    pub exclude_dir: Vec<Glob>,
+    pub hidden: bool,
+    pub follow: bool,
+    pub no_dedupe: bool,
+    pub one_file_system: bool,
+    pub read_devices: bool,
+    pub no_ignore: bool,
+    pub no_ignore_files: bool,
+    pub search_zip: bool,
+    pub pre: Option<String>,
+    pub pre_glob: Vec<Glob>,
+    pub max_columns: Option<usize>,
+    pub max_columns_preview: bool,
+    pub snippet: Option<usize>,
+    pub char_context: Option<usize>,
+    pub type_list: bool,
+    pub completions: Option<Shell>,
+    pub invert: bool,
+    pub count: bool,
+    pub count_matches: bool,
+    pub count_total: bool,
+    pub count_by_file: bool,
+    pub sort_count: bool,
+    pub group_by_dir: bool,
+    pub group_depth: Option<usize>,
+    pub wc: bool,
+    pub only_matching: bool,
+    pub unique_counts: bool,
+    pub top: Option<usize>,
+    pub line_numbers_only: bool,
+    pub ranges: bool,
+    pub vimgrep: bool,
+    pub replace: Option<String>,
+    pub in_place: bool,
+    pub no_backup: bool,
+    pub passthru: bool,
+    pub context: bool,
+    pub before_context: usize,
+    pub after_context: usize,
+    pub context_until: Option<ContextUntil>,
+    pub group_separator: Option<String>,
+    pub files_with_matches: bool,
+    pub files_without_match: bool,
+    pub list_files: bool,
+    pub heading: Option<bool>,
+    pub null_data: bool,
+    pub null: bool,
+    pub quote: Quoting,
+    pub encoding: Option<Encoding>,
+    pub no_unicode: bool,
+    pub absolute_path: bool,
+    pub label: String,
+    pub files_from: Option<FileList>,
+    pub line_buffered: bool,
+    pub read_strategy: Option<ReadStrategy>,
+    pub threads: usize,
+    pub sort: Sort,
+    pub sort_buffer: usize,
+    pub color: ColorChoice,
+    pub color_env: ColorEnv,
+    pub pager: Option<String>,
+    pub less: Option<String>,
+    pub progress: bool,
+    pub columns: usize,
+    pub stats: bool,
+    pub timings: bool,
+    pub timings_limit: Option<usize>,
+    pub no_messages: bool,
+    pub quiet: bool,
+    pub skip_lines: usize,
+    pub head: Option<usize>,
+    pub start_byte: Option<u64>,
+    pub count_from_start: bool,
+    pub max_total: Option<u64>,
+    pub first: Option<First>,
+    pub require_count: Option<u64>,
+    pub debug: bool,
+    pub metrics: Option<Arc<Metrics>>,
+    pub json: bool,
+    pub output_format: Option<OutputFormat>,
+    pub no_header: bool,
+    pub format: Option<Template>,
+    pub watch: Option<Duration>,
+}
+
+impl Config {
+    /// Creates a `Config` searching `file_paths` for `query` with every
+    /// option at its default, as if none had been given on the command line.
+    /// Options are then enabled by setting the fields.
+    ///
+    /// Unlike [`Config::build`], this neither reads `IGNORE_CASE` nor checks
+    /// how the options combine, and setting one field does not set those it
+    /// would imply on the command line: `with_filename` is only set here, if
+    /// there is more than one path.
+    ///
+    /// # Examples
+    /// ```
+    /// use minigrep_cli_tool::Config;
+    ///
+    /// let mut config = Config::new("rust", vec!["poem.txt".into(), "notes.txt".into()]);
+    /// config.line_number = true;
+    /// assert!(config.with_filename);
+    /// assert_eq!(config.threads, 1);
+    /// ```
+    pub fn new(query: &str, file_paths: Vec<PathBuf>) -> Config {
+        Config {
+            query: query.to_string(),
+            expr: None,
+            between: None,
+            time_window: None,
+            with_filename: file_paths.len() > 1,
+            file_paths,
+            ignore_case: false,
+            case_source: CaseSource::Default,
+            trim: false,
+            squeeze_space: false,
+            line_number: false,
+            column: false,
+            byte_column: false,
+            byte_offset: false,
+            hex: None,
+            hex_context: 0,
+            strings: None,
+            recursive: false,
+            max_filesize: None,
+            min_filesize: None,
+            newer_than: None,
+            older_than: None,
+            max_depth: None,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            exclude_dir: Vec::new(),
+            hidden: false,
+            follow: false,
+            no_dedupe: false,
+            one_file_system: false,
+            read_devices: false,
+            no_ignore: false,
+            no_ignore_files: false,
+            search_zip: false,
+            pre: None,
+            pre_glob: Vec::new(),
+            max_columns: None,
+            max_columns_preview: false,
+            snippet: None,
+            char_context: None,
+            type_list: false,
+            completions: None,
+            invert: false,
+            count: false,
+            count_matches: false,
+            count_total: false,
+            count_by_file: false,
+            sort_count: false,
+            group_by_dir: false,
+            group_depth: None,
+            wc: false,
+            only_matching: false,
+            unique_counts: false,
+            top: None,
+            line_numbers_only: false,
+            ranges: false,
+            vimgrep: false,
+            replace: None,
+            in_place: false,
+            no_backup: false,
+            passthru: false,
+            context: false,
+            before_context: 0,
+            after_context: 0,
+            context_until: None,
+            group_separator: Some(String::from(GROUP_SEPARATOR)),
+            files_with_matches: false,
+            files_without_match: false,
+            list_files: false,
+            heading: None,
+            null_data: false,
+            null: false,
+            quote: Quoting::None,
+            encoding: None,
+            no_unicode: false,
+            absolute_path: false,
+            label: String::from(DEFAULT_LABEL),
+            files_from: None,
+            line_buffered: false,
+            read_strategy: None,
+            threads: 1,
+            sort: Sort::None,
+            sort_buffer: DEFAULT_SORT_BUFFER,
+            color: ColorChoice::Auto,
+            color_env: ColorEnv::default(),
+            pager: None,
+            less: None,
+            progress: false,
+            columns: DEFAULT_COLUMNS,
+            stats: false,
+            timings: false,
+            timings_limit: None,
+            no_messages: false,
+            quiet: false,
+            skip_lines: 0,
+            head: None,
+            start_byte: None,
+            count_from_start: false,
+            max_total: None,
+            first: None,
+            require_count: None,
+            debug: false,
+            metrics: None,
+            json: false,
+            output_format: None,
+            no_header: false,
+            format: None,
+            watch: None,
+        }
+    }
+
+    /// Builds a new `Config` instance from command-line arguments.
+    ///
+    /// Expected argument format:
+    /// ```text
+    /// minigrep [options] <query> [<file_path>...] [/i or /s]
+    /// ```
+    ///
+    /// - `/i` sets `ignore_case` to true
+    /// - `/s` sets `ignore_case` to false
+    /// - If no flag is provided, whether `env` finds the environment
+    ///   variable `IGNORE_CASE` determines behavior.
+    /// - Without a file path, standard input is searched, unless
+    ///   `stdin_is_terminal` says it is a terminal.
+    ///
+    /// Options may appear anywhere on the command line, and `--` ends
+    /// option parsing so that queries starting with `-` can be given.
+    /// With `--files` there is no query and every positional is a path.
+    /// With `--escapes`, the escapes of the query are interpreted as
+    /// [`unescape`](crate::escape::unescape) does. With `--expr`, there is
+    /// no query either: lines are selected by the expression instead, and
+    /// likewise with `--between`, by its start and end patterns.
+    ///
+    /// File paths may be any bytes the platform allows, but the query and
+    /// option values must be valid Unicode.
+    ///
+    /// The environment is only read through `env`, which returns the value
+    /// of the variable named, if it is set; the binary passes a lookup of
+    /// its own environment, and tests can pass a fixed one.
+    ///
+    /// # Errors
+    /// Returns an error if the query is missing or not valid Unicode, the
+    /// file path is missing while standard input is a terminal, an option
+    /// is unknown or lacks its value, with `--escapes`, the query has an
+    /// invalid escape, or the `--expr` expression cannot be parsed.
+    pub fn build(
+        mut args: impl Iterator<Item = OsString>,
+        stdin_is_terminal: bool,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Config, String> {
+        args.next();
+        let mut positionals = Vec::new();
+        let mut ignore_case_argument = None;
+        let mut parsed = Parsed::new();
+
+        while let Some(arg) = args.next() {
+            let Some(text) = arg.to_str() else {
+                positionals.push(arg);
+                continue;
+            };
+            let (flag, inline_value) = split_option(text);
+            match flag {
+                "/i" | "/s" => {
+                    ignore_case_argument = Some(flag == "/i");
+                    continue;
+                }
+                "--" => {
+                    positionals.extend(args.by_ref());
+                    break;
+                }
+                _ => {}
+            }
+            let Some(option) = options::find(flag) else {
+                if flag.starts_with('-') && flag != "-" {
+                    return Err(format!("Unknown option '{flag}'"));
+                }
+                positionals.push(arg);
+                continue;
+            };
+            let value = match option.value {
+                Value::None if inline_value.is_some() => {
+                    return Err(format!("Option '{flag}' does not take a value"));
+                }
+                Value::None => None,
+                Value::Required(_) => Some(option_value(flag, inline_value, &mut args)?),
+                Value::Optional(_) => inline_value.map(String::from),
+            };
+            match option.parse {
+                Parse::Flag(flag) => *flag(&mut parsed) = true,
+                Parse::With(parse) => parse(
+                    &mut parsed,
+                    &mut Arg {
+                        flag,
+                        value,
+                        rest: &mut args,
+                    },
+                )?,
+            }
+        }
+        let Parsed {
+            trim,
+            squeeze_space,
+            escapes,
+            expr,
+            between,
+            since,
+            until,
+            untimed,
+            and_not,
+            patterns,
+            pattern_option,
+            line_number,
+            mut column,
+            byte_column,
+            byte_offset,
+            hex,
+            hex_context,
+            strings,
+            with_filename,
+            recursive,
+            max_filesize,
+            min_filesize,
+            newer_than,
+            older_than,
+            max_depth,
+            include,
+            exclude,
+            exclude_dir,
+            type_list,
+            hidden,
+            follow,
+            no_dedupe,
+            one_file_system,
+            read_devices,
+            no_ignore,
+            no_ignore_files,
+            search_zip,
+            pre,
+            pre_glob,
+            max_columns,
+            max_columns_preview,
+            snippet,
+            char_context,
+            invert,
+            mut count,
+            count_matches,
+            count_total,
+            mut count_by_file,
+            sort_count,
+            group_by_dir,
+            group_depth,
+            wc,
+            only_matching,
+            unique_counts,
+            top,
+            line_numbers_only,
+            ranges,
+            vimgrep,
+            replace,
+            in_place,
+            no_backup,
+            passthru,
+            before_context,
+            after_context,
+            context,
+            context_flag,
+            context_until,
+            include_stop,
+            group_separator,
+            group_separator_given,
+            files_with_matches,
+            files_without_match,
+            list_files,
+            heading,
+            null_data,
+            null,
+            quote,
+            encoding,
+            no_unicode,
+            absolute_path,
+            label,
+            files_from,
+            line_buffered,
+            read_strategy,
+            mut threads,
+            mut sort,
+            sort_buffer,
+            color,
+            pager,
+            no_pager,
+            progress,
+            stats,
+            timings,
+            timings_limit,
+            no_messages,
+            quiet,
+            skip_lines,
+            head,
+            start_byte,
+            count_from_start,
+            mut max_total,
+            first,
+            mut require_count,
+            require_match,
+            debug,
+            json,
+            output_format,
+            no_header,
+            format,
+            watch,
+            watch_interval,
+            completions,
+        } = parsed;
+
+        let mut positionals = positionals.into_iter();
+        let query = if list_files || type_list || completions.is_some() {
+            String::new()
+        } else if let Some(expr) = &expr {
+            expr.clone()
+        } else if let Some(first) = patterns.first() {
+            Expr::pattern(first).literal_texts()[0].to_string()
+        } else if pattern_option.is_some() {
+            return Err(String::from("-f found no patterns"));
+        } else if let Some((start, _)) = &between {
+            start.clone()
+        } else {
+            match positionals.next() {
+                Some(arg) => arg
+                    .into_string()
+                    .map_err(|_| String::from("The query string is not valid Unicode"))?,
+                None => return Err(String::from("Didn't get a query string")),
+            }
+        };
+        let query = if escapes {
+            escape::unescape(&query)?
+        } else {
+            query
+        };
+        let squeezed = |text: String| {
+            if squeeze_space {
+                normalize::squeeze(&text)
+            } else {
+                text
+            }
+        };
+        let query = squeezed(query);
+        let between = between.map(|(start, end)| Between::new(&squeezed(start), &squeezed(end)));
+        let mut file_paths: Vec<PathBuf> = positionals.map(PathBuf::from).collect();
+        if file_paths.is_empty() && !type_list && completions.is_none() && files_from.is_none() {
+            if stdin_is_terminal {
+                return Err(String::from("Didn't get a file path "));
+            }
+            file_paths.push(PathBuf::from("-"));
+        }
+
+        if files_from.as_ref().is_some_and(FileList::is_stdin)
+            && file_paths.iter().any(|path| path.as_os_str() == "-")
+        {
+            return Err(String::from(
+                "the file list and an input cannot both be read from standard input",
+            ));
+        }
+
+        if max_depth.is_some() && !recursive {
+            return Err(String::from("--max-depth requires -r"));
+        }
+
+        if pre.is_none() && !pre_glob.is_empty() {
+            return Err(String::from("--pre-glob requires --pre"));
+        }
+
+        if group_depth.is_some() && !group_by_dir {
+            return Err(String::from("--group-depth requires --group-by-dir"));
+        }
+        let count_by_file_flag = if group_by_dir {
+            "--group-by-dir"
+        } else {
+            "--count-by-file"
+        };
+        count_by_file |= group_by_dir;
+        let count_flag = if count_matches {
+            "--count-matches"
+        } else if count_by_file && !count {
+            count_by_file_flag
+        } else if wc && !count {
+            "--wc"
+        } else {
+            "-c"
+        };
+        let count_alone = count;
+        count |= count_matches || count_by_file || wc;
+        let column_flag = if byte_column {
+            "--byte-column"
+        } else {
+            "--column"
+        };
+        column |= byte_column;
+
+        // Each option of CONFLICTS, with the name it is reported under and
+        // whether it was given.
+        let given = [
+            ("-v", "-v", invert),
+            ("-c", count_flag, count),
+            ("--count", "-c", count_alone),
+            ("--count-matches", "--count-matches", count_matches),
+            ("--count-by-file", count_by_file_flag, count_by_file),
+            ("--count-total", "--count-total", count_total),
+            ("--wc", "--wc", wc),
+            ("--column", column_flag, column),
+            ("-o", "-o", only_matching),
+            ("-l", "-l", files_with_matches),
+            ("-L", "-L", files_without_match),
+            ("--files", "--files", list_files),
+            (
+                "--line-numbers-only",
+                "--line-numbers-only",
+                line_numbers_only,
+            ),
+            ("--ranges", "--ranges", ranges),
+            ("--passthru", "--passthru", passthru),
+            ("--replace", "--replace", replace.is_some()),
+            ("--vimgrep", "--vimgrep", vimgrep),
+            ("--json", "--json", json),
+            (
+                "--output-format",
+                "--output-format",
+                output_format.is_some(),
+            ),
+            (
+                "--output-format=sarif",
+                "--output-format",
+                output_format == Some(OutputFormat::Sarif),
+            ),
+            ("--no-header", "--no-header", no_header),
+            ("--format", "--format", format.is_some()),
+            ("--snippet", "--snippet", snippet.is_some()),
+            ("--char-context", "--char-context", char_context.is_some()),
+            ("--max-columns", "--max-columns", max_columns.is_some()),
+            ("--color", "--color", color.is_some()),
+            ("-C", context_flag.unwrap_or("-C"), context_flag.is_some()),
+            ("--unique-counts", "--unique-counts", unique_counts),
+            ("--in-place", "--in-place", in_place),
+            ("--encoding", "--encoding", encoding.is_some()),
+            ("--no-unicode", "--no-unicode", no_unicode),
+            ("--pre", "--pre", pre.is_some()),
+            ("--quote=shell", "--quote=shell", quote == Quoting::Shell),
+            ("-Z", "-Z", null),
+            ("-z", "-z", null_data),
+            ("--watch", "--watch", watch),
+            ("-r", "-r", recursive),
+            ("--search-zip", "--search-zip", search_zip),
+            ("--files-from", "--files-from", files_from.is_some()),
+            ("--progress", "--progress", progress),
+            ("--stats", "--stats", stats),
+            ("--timings", "--timings", timings),
+            ("--hex", "--hex", hex),
+            ("--strings", "--strings", strings.is_some()),
+            ("-n", "-n", line_number),
+            ("--escapes", "--escapes", escapes),
+            ("--expr", "--expr", expr.is_some()),
+            ("--and-not", "--and-not", !and_not.is_empty()),
+            (
+                "-e",
+                pattern_option.unwrap_or("-e"),
+                pattern_option.is_some(),
+            ),
+            ("--trim", "--trim", trim),
+            ("--squeeze-space", "--squeeze-space", squeeze_space),
+            ("--between", "--between", between.is_some()),
+            ("--since", "--since", since.is_some()),
+            ("--until", "--until", until.is_some()),
+            (
+                "--after-context-until",
+                "--after-context-until",
+                context_until.is_some(),
+            ),
+            ("--skip-lines", "--skip-lines", skip_lines.is_some()),
+            ("--head", "--head", head.is_some()),
+            ("--start-byte", "--start-byte", start_byte.is_some()),
+            (
+                "--read-strategy",
+                "--read-strategy",
+                read_strategy.is_some(),
+            ),
+            ("--max-total", "--max-total", max_total.is_some()),
+            ("--first", "--first", first.is_some()),
+            (
+                "--require-count",
+                "--require-count",
+                require_count.is_some(),
+            ),
+            ("--require-match", "--require-match", require_match),
+            ("-q", "-q", quiet),
+        ];
+        let shown = |name: &str| {
+            let (_, shown, given) = given
+                .iter()
+                .find(|(option, ..)| *option == name)
+                .unwrap_or_else(|| panic!("{name} is not among the options given"));
+            given.then_some(*shown)
+        };
+        for (option, conflicts) in CONFLICTS {
+            if let Some(option) = shown(option)
+                && let Some(conflict) = conflicts.iter().find_map(|conflict| shown(conflict))
+            {
+                return Err(format!("{option} cannot be used with {conflict}"));
+            }
+        }
+
+        if count_total && !count {
+            return Err(String::from("--count-total requires -c"));
+        }
+
+        if unique_counts {
+            if !only_matching {
+                return Err(String::from("--unique-counts requires -o"));
+            }
+        } else if top.is_some() && !count_by_file {
+            return Err(String::from(
+                "--top requires --unique-counts or --count-by-file",
+            ));
+        }
+        if sort_count && !count_by_file {
+            return Err(String::from("--sort-count requires --count-by-file"));
+        }
+        if sort_buffer.is_some() && sort != Sort::Path {
+            return Err(String::from("--sort-buffer requires --sort=path"));
+        }
+
+        if start_byte.is_some() {
+            let numbered = [
+                "-n",
+                "--column",
+                "--vimgrep",
+                "--line-numbers-only",
+                "--output-format",
+                "--format",
+            ];
+            if !count_from_start && let Some(flag) = numbered.iter().find_map(|flag| shown(flag)) {
+                return Err(format!(
+                    "{flag} with --start-byte requires --count-from-start, \
+                     as line numbers are unknown without it"
+                ));
+            }
+        } else if count_from_start {
+            return Err(String::from("--count-from-start requires --start-byte"));
+        }
+
+        if ranges && !line_numbers_only {
+            return Err(String::from("--ranges requires --line-numbers-only"));
+        }
+
+        if untimed.is_some() && since.is_none() && until.is_none() {
+            return Err(String::from("--no-timestamp requires --since or --until"));
+        }
+        let time_window = if since.is_some() || until.is_some() {
+            let year = timestamp::current_year();
+            let bound = |flag: &str, value: Option<String>| {
+                value
+                    .map(|value| {
+                        timestamp::parse(&value, year).ok_or_else(|| {
+                            format!(
+                                "Invalid timestamp '{value}' for '{flag}': expected a form \
+                                 such as 2024-01-02T14:00:00Z, 2024-01-02 14:00, or \
+                                 Jan  2 14:00:00"
+                            )
+                        })
+                    })
+                    .transpose()
+            };
+            let since = bound("--since", since)?;
+            let until = bound("--until", until)?;
+            if let (Some(since), Some(until)) = (since, until)
+                && since >= until
+            {
+                return Err(String::from("--since must be earlier than --until"));
+            }
+            Some(TimeWindow {
+                since,
+                until,
+                untimed: untimed.unwrap_or(Untimed::Drop),
+                year,
+            })
+        } else {
+            None
+        };
+
+        let before_context = before_context.or(context).unwrap_or(0);
+        let after_context = after_context.or(context).unwrap_or(0);
+        if include_stop && context_until.is_none() {
+            return Err(String::from(
+                "--include-stop requires --after-context-until",
+            ));
+        }
+        let context_until =
+            context_until.map(|stop| ContextUntil::new(&squeezed(stop), include_stop));
+        if context_flag.is_none() && group_separator_given {
+            return Err(String::from(
+                "--group-separator and --no-group-separator require -A, -B, or -C",
+            ));
+        }
+
+        if in_place {
+            if replace.is_none() {
+                return Err(String::from("--in-place requires --replace"));
+            }
+            if file_paths.iter().any(|path| path.as_os_str() == "-") {
+                return Err(String::from(
+                    "--in-place cannot be used with standard input",
+                ));
+            }
+        } else if no_backup {
+            return Err(String::from("--no-backup requires --in-place"));
+        }
+
+        if no_header && output_format.is_none() {
+            return Err(String::from("--no-header requires --output-format"));
+        }
+
+        if watch {
+            if file_paths.len() != 1 || file_paths[0].as_os_str() == "-" {
+                return Err(String::from("--watch takes exactly one file"));
+            }
+        } else if watch_interval.is_some() {
+            return Err(String::from("--watch-interval requires --watch"));
+        }
+
+        if timings_limit.is_some() && !timings {
+            return Err(String::from("--timings-limit requires --timings"));
+        }
+
+        if require_match {
+            require_count = Some(1);
+        }
+
+        if let Some(first) = first {
+            max_total = Some(1);
+            if first == First::Ordered {
+                sort = Sort::Path;
+            }
+        }
+
+        if hex_context.is_some() && !hex {
+            return Err(String::from("--hex-context requires --hex"));
+        }
+        let hex = if hex {
+            Some(hex::parse_pattern(&query)?)
+        } else {
+            None
+        };
+        let expr = match expr {
+            Some(expr) => Some(Expr::parse(&squeezed(expr))?),
+            None if patterns.len() > 1
+                || patterns
+                    .iter()
+                    .any(|pattern| matches!(Expr::pattern(pattern), Expr::Cased(..)))
+                || !and_not.is_empty() =>
+            {
+                let query = patterns
+                    .into_iter()
+                    .map(|pattern| Expr::pattern(&squeezed(pattern)))
+                    .reduce(|left, right| Expr::Or(Box::new(left), Box::new(right)))
+                    .unwrap_or_else(|| Expr::Literal(query.clone()));
+                Some(and_not.into_iter().fold(query, |expr, pattern| {
+                    let not = Expr::Not(Box::new(Expr::Literal(squeezed(pattern))));
+                    Expr::And(Box::new(expr), Box::new(not))
+                }))
+            }
+            None => None,
+        };
+
+        // Checked after the conflicts, so that a combination no build
+        // accepts is explained the same way by every build.
+        let unavailable = [
+            ("--json", json && !cfg!(feature = "json"), "json"),
+            (
+                "--output-format=sarif",
+                output_format == Some(OutputFormat::Sarif) && !cfg!(feature = "json"),
+                "json",
+            ),
+            (
+                "-j",
+                threads != 1 && !cfg!(feature = "parallel"),
+                "parallel",
+            ),
+            (
+                "--search-zip",
+                search_zip
+                    && !cfg!(any(
+                        feature = "gzip",
+                        feature = "bzip2",
+                        feature = "xz",
+                        feature = "zip",
+                        feature = "tar"
+                    )),
+                "compress or archive",
+            ),
+        ];
+        if let Some((flag, _, feature)) = unavailable.iter().find(|(_, given, _)| *given) {
+            return Err(format!(
+                "{flag} is not available: minigrep was built without {feature} support"
+            ));
+        }
+
+        if threads == 0 {
+            threads = thread::available_parallelism().map_or(1, usize::from);
+        }
+
+        let ignore_case_env = env("IGNORE_CASE").is_some();
+        let (ignore_case, case_source) = match ignore_case_argument {
+            Some(value) => (
+                value,
+                CaseSource::Argument {
+                    overrides_env: ignore_case_env,
+                },
+            ),
+            None if ignore_case_env => (true, CaseSource::Environment),
+            None => (false, CaseSource::Default),
+        };
+
+        Ok(Config {
+            query,
+            expr,
+            between,
+            time_window,
+            with_filename: with_filename
+                || recursive
+                || vimgrep
+                || files_from.is_some()
+                || file_paths.len() > 1
+                // cmd.exe passes wildcards through, for one to stand for
+                // several files.
+                || cfg!(windows) && file_paths.iter().any(|path| glob::has_wildcards(path)),
+            file_paths,
+            ignore_case,
+            case_source,
+            trim,
+            squeeze_space,
+            line_number: line_number || column || vimgrep,
+            column,
+            byte_column: byte_column || no_unicode,
+            byte_offset: byte_offset || strings.is_some(),
+            hex,
+            hex_context: hex_context.unwrap_or(0),
+            strings,
+            recursive,
+            max_filesize,
+            min_filesize,
+            newer_than,
+            older_than,
+            max_depth,
+            include,
+            exclude,
+            exclude_dir,
+            hidden,
+            follow,
+            no_dedupe,
+            one_file_system,
+            read_devices,
+            no_ignore: no_ignore || no_ignore_files,
+            no_ignore_files,
+            search_zip,
+            pre,
+            pre_glob,
+            type_list,
+            completions,
+            max_columns,
+            max_columns_preview,
+            snippet,
+            char_context,
+            invert,
+            count,
+            count_matches,
+            count_total,
+            count_by_file,
+            sort_count,
+            group_by_dir,
+            group_depth,
+            wc,
+            only_matching,
+            unique_counts,
+            top,
+            line_numbers_only,
+            ranges,
+            vimgrep,
+            replace,
+            in_place,
+            no_backup,
+            passthru,
+            context: context_flag.is_some(),
+            before_context,
+            after_context,
+            context_until,
+            group_separator,
+            files_with_matches,
+            files_without_match,
+            list_files,
+            heading,
+            null_data,
+            null,
+            quote,
+            encoding,
+            no_unicode,
+            absolute_path,
+            label,
+            files_from,
+            line_buffered: line_buffered || watch || json,
+            read_strategy,
+            threads,
+            sort,
+            sort_buffer: sort_buffer.unwrap_or(DEFAULT_SORT_BUFFER),
+            color: color.unwrap_or(ColorChoice::Auto),
+            color_env: ColorEnv::read(&env),
+            pager: if no_pager {
+                None
+            } else {
+                pager_command(pager.as_ref().map(Option::as_deref), &env)
+            },
+            less: env("LESS"),
+            progress,
+            columns: env("COLUMNS")
+                .and_then(|columns| columns.parse().ok())
+                .unwrap_or(DEFAULT_COLUMNS),
+            stats,
+            timings,
+            timings_limit,
+            no_messages,
+            quiet,
+            skip_lines: skip_lines.unwrap_or(0),
+            head,
+            start_byte,
+            count_from_start,
+            max_total,
+            first,
+            require_count,
+            debug,
+            metrics: None,
+            json,
+            output_format,
+            no_header,
+            format,
+            watch: watch.then(|| Duration::from_millis(watch_interval.unwrap_or(250))),
+        })
+    }
+
+    /// Describes how the search was set up once every option and variable
+    /// was weighed against the others, as `--debug` logs it: a line
+    /// `NAME: VALUE` for each of what is looked for, whether case matters
+    /// and what decided it, how lines are matched, and which inputs are
+    /// searched.
+    ///
+    /// # Examples
+    /// ```
+    /// use minigrep_cli_tool::Config;
+    /// use std::ffi::OsString;
+    ///
+    /// let args = ["minigrep", "rust", "poem.txt", "/s"].map(OsString::from);
+    /// let env = |name: &str| (name == "IGNORE_CASE").then(|| String::from("1"));
+    /// let config = Config::build(args.into_iter(), true, env).unwrap();
+    /// let lines = config.describe();
+    /// assert_eq!(lines[0], "query: \"rust\"");
+    /// assert_eq!(lines[1], "case: sensitive, from /s over IGNORE_CASE");
+    /// ```
+    pub fn describe(&self) -> Vec<String> {
+        let sought = if let Some(hex) = &self.hex {
+            let bytes: Vec<String> = hex.iter().map(|byte| format!("{byte:02X}")).collect();
+            format!("hex: {}", bytes.join(" "))
+        } else if let Some(between) = &self.between {
+            format!("between: {:?} and {:?}", between.start(), between.end())
+        } else if let Some(expr) = &self.expr {
+            format!("expr: {expr}")
+        } else {
+            format!("query: {:?}", self.query)
+        };
+        let case = if self.ignore_case {
+            "insensitive"
+        } else {
+            "sensitive"
+        };
+        let source = match self.case_source {
+            CaseSource::Default => String::from("by default"),
+            CaseSource::Argument { overrides_env } => {
+                let flag = if self.ignore_case { "/i" } else { "/s" };
+                let over = if overrides_env {
+                    " over IGNORE_CASE"
+                } else {
+                    ""
+                };
+                format!("from {flag}{over}")
+            }
+            CaseSource::Environment => String::from("from IGNORE_CASE"),
+        };
+        let mut inputs: Vec<String> = self
+            .file_paths
+            .iter()
+            .map(|path| format!("{:?}", path.display().to_string()))
+            .collect();
+        if let Some(list) = &self.files_from {
+            inputs.push(format!(
+                "those listed in {:?}",
+                list.path.display().to_string()
+            ));
+        }
+        vec![
+            sought,
+            format!("case: {case}, {source}"),
+            format!("invert: {}", self.invert),
+            format!("trim: {}", self.trim),
+            format!("squeeze-space: {}", self.squeeze_space),
+            format!("inputs: {}", inputs.join(", ")),
+            format!("recursive: {}", self.recursive),
+            format!("threads: {}", self.threads),
+        ]
+    }
+
+    /// What is looked for in each line: the `--expr` expression if one was
+    /// given, the patterns of `--between`, or else the query, in lines
+    /// normalized by `--trim` and `--squeeze-space`, or matched as bytes
+    /// with `--no-unicode`.
+    ///
+    /// With `--between`, this only finds what to highlight: lines are
+    /// selected by the state of a [`Between`] instead.
+    pub(crate) fn matcher(&self) -> QueryMatcher<'_> {
+        let matcher: &dyn Matcher = match (&self.expr, &self.between) {
+            (Some(expr), _) => expr,
+            (None, Some(between)) => between.delimiters(),
+            (None, None) if self.no_unicode => {
+                return QueryMatcher::Bytes(ByteQuery::new(&self.query));
+            }
+            (None, None) => &self.query,
+        };
+        QueryMatcher::Text(Normalizing::new(matcher, self.trim, self.squeeze_space))
+    }
+
+    /// How many lines may be selected in all inputs together before the
+    /// search stops: `max_total`, or with `quiet`, no more than the exit
+    /// status needs, which is one, or `require_count`.
+    pub(crate) fn total_limit(&self) -> Option<u64> {
+        if !self.quiet {
+            return self.max_total;
+        }
+        let needed = self.require_count.unwrap_or(1).max(1);
+        Some(self.max_total.map_or(needed, |max| max.min(needed)))
+    }
+
+    /// The patterns the matches of [`Config::matcher`] are numbered by, in
+    /// order: the literals of its expression, or else the query alone.
+    #[cfg(feature = "json")]
+    pub(crate) fn patterns(&self) -> Vec<&str> {
+        match (&self.expr, &self.between) {
+            (Some(expr), _) => expr.literal_texts(),
+            (None, Some(between)) => between.delimiters().literal_texts(),
+            (None, None) => vec![&self.query],
+        }
+    }
+
+    /// `line` as `--trim` and `--squeeze-space` have it matched.
+    pub(crate) fn normalized<'l>(&self, line: &'l str) -> Cow<'l, str> {
+        if self.trim || self.squeeze_space {
+            Cow::Owned(normalize::normalize(line, self.trim, self.squeeze_space).into_text())
+        } else {
+            Cow::Borrowed(line)
+        }
+    }
+}
+
+/// What [`Config::matcher`] matches lines with.
+pub(crate) enum QueryMatcher<'c> {
+    Text(Normalizing<'c, dyn Matcher + 'c>),
+    Bytes(ByteQuery<'c>),
+}
+
+impl Matcher for QueryMatcher<'_> {
+    fn is_match(&self, line: &str, ignore_case: bool) -> bool {
+        match self {
+            QueryMatcher::Text(matcher) => matcher.is_match(line, ignore_case),
+            QueryMatcher::Bytes(matcher) => matcher.is_match(line, ignore_case),
+        }
+    }
+
+    fn match_ranges(&self, line: &str, ignore_case: bool) -> Vec<Range<usize>> {
+        match self {
+            QueryMatcher::Text(matcher) => matcher.match_ranges(line, ignore_case),
+            QueryMatcher::Bytes(matcher) => matcher.match_ranges(line, ignore_case),
+        }
+    }
+
+    fn count(&self, line: &str, ignore_case: bool) -> usize {
+        match self {
+            QueryMatcher::Text(matcher) => matcher.count(line, ignore_case),
+            QueryMatcher::Bytes(matcher) => matcher.count(line, ignore_case),
+        }
+    }
+
+    fn pattern_ranges(&self, line: &str, ignore_case: bool) -> Vec<(Range<usize>, usize)> {
+        match self {
+            QueryMatcher::Text(matcher) => matcher.pattern_ranges(line, ignore_case),
+            QueryMatcher::Bytes(matcher) => matcher.pattern_ranges(line, ignore_case),
+        }
+    }
+}
+
+/// What decided whether a search ignores case, for `--debug`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CaseSource {
+    /// Nothing did: a search is case-sensitive unless `ignore_case` is set
+    /// directly.
+    #[default]
+    Default,
+    /// The last of `/i` and `/s`, which take precedence over `IGNORE_CASE`,
+    /// whether or not it was set too.
+    Argument { overrides_env: bool },
+    /// `IGNORE_CASE` being set, to anything.
+    Environment,
+}
+
+/// How selected lines are printed instead of as plain lines, as chosen with
+/// `--output-format`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// A row of a CSV or TSV table for every selected line.
+    Table(csv::Format),
+    /// A SARIF result for every match, printed in a single document once
+    /// the search is over.
+    Sarif,
+}
+
+/// The options that cannot be given together: each option is rejected
+/// along with the first of those listed after it that is also given, as
+/// `OPTION cannot be used with CONFLICT`.
+///
+/// `-c` also stands for `--count-matches`, `--column` for `--byte-column`,
+/// `-e` for `-f`, and `-C` for `-A` and `-B`, each reported under the name
+/// given, while
+/// `--count` is only `-c` itself, and reported as `-c`.
+/// `--output-format=sarif` is only given with that format, and reported as
+/// `--output-format`.
+const CONFLICTS: &[(&str, &[&str])] = &[
+    ("--count-matches", &["-v", "--count"]),
+    ("--unique-counts", &["-c"]),
+    ("-l", &["-L"]),
+    (
+        "--column",
+        &[
+            "-c",
+            "-l",
+            "-L",
+            "--files",
+            "--line-numbers-only",
+            "--json",
+            "--output-format",
+            "--format",
+        ],
+    ),
+    (
+        "--line-numbers-only",
+        &[
+            "-c",
+            "-o",
+            "-l",
+            "-L",
+            "--passthru",
+            "--replace",
+            "--vimgrep",
+            "--json",
+            "--output-format",
+            "--format",
+        ],
+    ),
+    ("--ranges", &["--watch"]),
+    ("--passthru", &["-v", "-c", "-l", "-L", "-o"]),
+    (
+        "-C",
+        &[
+            "-c",
+            "-o",
+            "-l",
+            "-L",
+            "--files",
+            "--passthru",
+            "--vimgrep",
+            "--line-numbers-only",
+            "--json",
+            "--output-format",
+            "--format",
+            "--in-place",
+            "--watch",
+        ],
+    ),
+    (
+        "--in-place",
+        &[
+            "-v",
+            "-c",
+            "-o",
+            "-l",
+            "-L",
+            "--passthru",
+            "--files",
+            "--encoding",
+            "--pre",
+        ],
+    ),
+    (
+        "--json",
+        &[
+            "--color",
+            "-c",
+            "-o",
+            "-l",
+            "-L",
+            "--files",
+            "--passthru",
+            "--replace",
+            "--max-columns",
+        ],
+    ),
+    ("--output-format=sarif", &["-v", "--no-header"]),
+    (
+        "--output-format",
+        &[
+            "--color",
+            "-c",
+            "-o",
+            "-l",
+            "-L",
+            "--files",
+            "--passthru",
+            "--replace",
+            "--max-columns",
+            "--json",
+            "--vimgrep",
+            "--in-place",
+        ],
+    ),
+    (
+        "--format",
+        &[
+            "--color",
+            "-c",
+            "-o",
+            "-l",
+            "-L",
+            "--files",
+            "--passthru",
+            "--replace",
+            "--max-columns",
+            "--json",
+            "--output-format",
+            "--vimgrep",
+            "--in-place",
+        ],
+    ),
+    (
+        "--snippet",
+        &[
+            "--max-columns",
+            "-o",
+            "--json",
+            "--output-format",
+            "--format",
+        ],
+    ),
+    (
+        "--char-context",
+        &[
+            "-v",
+            "-o",
+            "--snippet",
+            "--max-columns",
+            "--passthru",
+            "--replace",
+            "--vimgrep",
+            "-C",
+            "--after-context-until",
+            "--json",
+            "--output-format",
+            "--format",
+            "--hex",
+        ],
+    ),
+    (
+        "--vimgrep",
+        &[
+            "-v",
+            "-c",
+            "-o",
+            "-l",
+            "-L",
+            "--files",
+            "--passthru",
+            "--replace",
+            "--json",
+        ],
+    ),
+    ("--quote=shell", &["-Z"]),
+    ("-z", &["--json", "--in-place"]),
+    (
+        "--watch",
+        &[
+            "-r",
+            "-c",
+            "-l",
+            "-L",
+            "--files",
+            "--in-place",
+            "--json",
+            "--output-format",
+            "--encoding",
+            "--search-zip",
+            "--files-from",
+            "--pre",
+            "--progress",
+            "--stats",
+            "--timings",
+        ],
+    ),
+    ("-c", &["-o"]),
+    (
+        "--start-byte",
+        &[
+            "--files",
+            "--in-place",
+            "--watch",
+            "--hex",
+            "--strings",
+            "--encoding",
+            "--pre",
+            "--search-zip",
+        ],
+    ),
+    (
+        "--no-unicode",
+        &[
+            "--encoding",
+            "--hex",
+            "--strings",
+            "--expr",
+            "--and-not",
+            "-e",
+            "--between",
+            "--since",
+            "--until",
+            "--trim",
+            "--squeeze-space",
+            "--after-context-until",
+            "--in-place",
+        ],
+    ),
+    ("--count-by-file", &["--count-total", "-l", "-L"]),
+    (
+        "--wc",
+        &[
+            "--count",
+            "--count-matches",
+            "--count-by-file",
+            "--count-total",
+            "-l",
+            "-L",
+            "--ranges",
+            "--strings",
+        ],
+    ),
+    (
+        "--hex",
+        &[
+            "-v",
+            "-c",
+            "-o",
+            "-l",
+            "-L",
+            "--files",
+            "--column",
+            "--line-numbers-only",
+            "--passthru",
+            "--replace",
+            "--vimgrep",
+            "--json",
+            "--output-format",
+            "--format",
+            "--snippet",
+            "--max-columns",
+            "-C",
+            "--encoding",
+            "--search-zip",
+            "-z",
+            "--watch",
+            "--escapes",
+        ],
+    ),
+    ("--expr", &["--files", "--hex", "--escapes", "--in-place"]),
+    (
+        "-e",
+        &[
+            "--expr",
+            "--between",
+            "--files",
+            "--hex",
+            "--escapes",
+            "--in-place",
+        ],
+    ),
+    (
+        "--and-not",
+        &["--expr", "--files", "--hex", "--escapes", "--in-place"],
+    ),
+    (
+        "--require-count",
+        &["--require-match", "--files", "--watch"],
+    ),
+    ("--require-match", &["--files", "--watch"]),
+    ("-q", &["-L", "--files", "--in-place", "--watch"]),
+    ("--skip-lines", &["--in-place", "--watch", "--hex"]),
+    ("--head", &["--in-place", "--watch", "--hex"]),
+    (
+        "--first",
+        &[
+            "--max-total",
+            "-c",
+            "-L",
+            "--files",
+            "--passthru",
+            "--in-place",
+            "--watch",
+            "--hex",
+            "--output-format=sarif",
+        ],
+    ),
+    (
+        "--max-total",
+        &[
+            "-L",
+            "--files",
+            "--passthru",
+            "--in-place",
+            "--watch",
+            "--hex",
+            "--output-format=sarif",
+        ],
+    ),
+    ("--trim", &["--files", "--hex", "--replace", "--in-place"]),
+    (
+        "--squeeze-space",
+        &["--files", "--hex", "--replace", "--in-place"],
+    ),
+    (
+        "--between",
+        &[
+            "--expr",
+            "--and-not",
+            "--files",
+            "--hex",
+            "--escapes",
+            "--replace",
+            "--in-place",
+            "-o",
+            "--count-matches",
+            "--output-format=sarif",
+        ],
+    ),
+    (
+        "--strings",
+        &[
+            "-n",
+            "-l",
+            "-L",
+            "--files",
+            "--line-numbers-only",
+            "--passthru",
+            "--vimgrep",
+            "--json",
+            "--output-format",
+            "--format",
+            "-C",
+            "--in-place",
+            "--encoding",
+            "-z",
+            "--hex",
+            "--skip-lines",
+            "--head",
+            "--watch",
+        ],
+    ),
+    (
+        "--since",
+        &["--files", "--hex", "--in-place", "--output-format=sarif"],
+    ),
+    (
+        "--until",
+        &["--files", "--hex", "--in-place", "--output-format=sarif"],
+    ),
+    (
+        "--read-strategy",
+        &["--watch", "--in-place", "--start-byte"],
+    ),
+];
+
+/// The order in which the results of different files are printed, as
+/// chosen with `--sort`.
+///
+/// The lines of each file are always printed together and in order. With
+/// [`Sort::Path`], the whole output is the same from one run to the next,
+/// however many threads search and whichever finishes first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Sort {
+    /// The order the files are walked in or, with `-j`, the order in which
+    /// they finish.
+    #[default]
+    None,
+    /// Byte-wise order of the paths, whatever the locale.
+    Path,
+}
+
+/// Which line `--first` selects: the first of all in order, or the first
+/// found by any thread.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum First {
+    /// The first line of the first input with one, the inputs sorted by
+    /// path, whatever the number of threads.
+    Ordered,
+    /// Whichever line is selected first, which with `-j` depends on which
+    /// thread gets to it first, but needs no input searched past it.
+    Any,
+}
+
+/// Where more paths to search are listed, for `--files-from` and
+/// `--files0-from`: the file at `path`, or standard input if it is `-`,
+/// with each path ended by `terminator`.
+#[derive(Debug)]
+pub struct FileList {
+    pub path: PathBuf,
+    pub terminator: u8,
+}
+
+impl FileList {
+    /// Whether the list is read from standard input.
+    pub fn is_stdin(&self) -> bool {
+        self.path.as_os_str() == "-"
+    }
+
+    /// Reads the paths listed, skipping empty entries and, in a list of
+    /// lines, dropping a `\r` before each newline.
+    ///
+    /// # Errors
+    /// Returns a [`MinigrepError`] naming the list if it cannot be read.
+    pub fn read(&self) -> Result<Vec<PathBuf>, MinigrepError> {
+        let name = if self.is_stdin() {
+            Cow::Borrowed(DEFAULT_LABEL)
+        } else {
+            self.path.to_string_lossy()
+        };
+        let error = |error| MinigrepError::new(&name, error);
+        let reader: Box<dyn BufRead> = if self.is_stdin() {
+            Box::new(io::stdin().lock())
+        } else {
+            Box::new(BufReader::new(File::open(&self.path).map_err(error)?))
+        };
+        let mut paths = Vec::new();
+        for entry in reader.split(self.terminator) {
+            let mut entry = entry.map_err(error)?;
+            if self.terminator == b'\n' && entry.last() == Some(&b'\r') {
+                entry.pop();
+            }
+            if !entry.is_empty() {
+                paths.push(path_from_bytes(entry));
+            }
+        }
+        Ok(paths)
+    }
+}
+
+/// Makes a path of the bytes read from a file list: any bytes on Unix,
+/// and elsewhere UTF-8, with invalid sequences replaced.
+fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStringExt;
+        PathBuf::from(OsString::from_vec(bytes))
+    }
+    #[cfg(not(unix))]
+    {
+        PathBuf::from(String::from_utf8_lossy(&bytes).into_owned())
+    }
+}
+
+/// The options of a command line as parsed so far, which the entries of
+/// [`OPTIONS`](options::OPTIONS) set, before [`Config::build`] checks
+/// them against each other.
+#[derive(Default)]
+pub(crate) struct Parsed {
+    pub(crate) trim: bool,
+    pub(crate) squeeze_space: bool,
+    pub(crate) escapes: bool,
+    pub(crate) expr: Option<String>,
+    pub(crate) between: Option<(String, String)>,
+    pub(crate) since: Option<String>,
+    pub(crate) until: Option<String>,
+    pub(crate) untimed: Option<Untimed>,
+    pub(crate) and_not: Vec<String>,
+    pub(crate) patterns: Vec<String>,
+    pub(crate) pattern_option: Option<&'static str>,
+    pub(crate) line_number: bool,
+    pub(crate) column: bool,
+    pub(crate) byte_column: bool,
+    pub(crate) byte_offset: bool,
+    pub(crate) hex: bool,
+    pub(crate) hex_context: Option<usize>,
+    pub(crate) strings: Option<usize>,
+    pub(crate) with_filename: bool,
+    pub(crate) recursive: bool,
+    pub(crate) max_filesize: Option<u64>,
+    pub(crate) min_filesize: Option<u64>,
+    pub(crate) newer_than: Option<Duration>,
+    pub(crate) older_than: Option<Duration>,
+    pub(crate) max_depth: Option<usize>,
+    pub(crate) include: Vec<Glob>,
+    pub(crate) exclude: Vec<Glob>,
+    pub(crate) exclude_dir: Vec<Glob>,
+    pub(crate) type_list: bool,
+    pub(crate) hidden: bool,
+    pub(crate) follow: bool,
+    pub(crate) no_dedupe: bool,
+    pub(crate) one_file_system: bool,
+    pub(crate) read_devices: bool,
+    pub(crate) no_ignore: bool,
+    pub(crate) no_ignore_files: bool,
+    pub(crate) search_zip: bool,
+    pub(crate) pre: Option<String>,
+    pub(crate) pre_glob: Vec<Glob>,
+    pub(crate) max_columns: Option<usize>,
+    pub(crate) max_columns_preview: bool,
+    pub(crate) snippet: Option<usize>,
+    pub(crate) char_context: Option<usize>,
+    pub(crate) invert: bool,
+    pub(crate) count: bool,
+    pub(crate) count_matches: bool,
+    pub(crate) count_total: bool,
+    pub(crate) count_by_file: bool,
+    pub(crate) sort_count: bool,
+    pub(crate) group_by_dir: bool,
+    pub(crate) group_depth: Option<usize>,
+    pub(crate) wc: bool,
+    pub(crate) only_matching: bool,
+    pub(crate) unique_counts: bool,
+    pub(crate) top: Option<usize>,
+    pub(crate) line_numbers_only: bool,
+    pub(crate) ranges: bool,
+    pub(crate) vimgrep: bool,
+    pub(crate) replace: Option<String>,
+    pub(crate) in_place: bool,
+    pub(crate) no_backup: bool,
+    pub(crate) passthru: bool,
+    pub(crate) before_context: Option<usize>,
+    pub(crate) after_context: Option<usize>,
+    pub(crate) context: Option<usize>,
+    pub(crate) context_flag: Option<&'static str>,
+    pub(crate) context_until: Option<String>,
+    pub(crate) include_stop: bool,
+    pub(crate) group_separator: Option<String>,
+    pub(crate) group_separator_given: bool,
+    pub(crate) files_with_matches: bool,
+    pub(crate) files_without_match: bool,
+    pub(crate) list_files: bool,
+    pub(crate) heading: Option<bool>,
+    pub(crate) null_data: bool,
+    pub(crate) null: bool,
+    pub(crate) quote: Quoting,
+    pub(crate) encoding: Option<Encoding>,
+    pub(crate) no_unicode: bool,
+    pub(crate) absolute_path: bool,
+    pub(crate) label: String,
+    pub(crate) files_from: Option<FileList>,
+    pub(crate) line_buffered: bool,
+    pub(crate) read_strategy: Option<ReadStrategy>,
+    pub(crate) threads: usize,
+    pub(crate) sort: Sort,
+    pub(crate) sort_buffer: Option<usize>,
+    pub(crate) color: Option<ColorChoice>,
+    pub(crate) pager: Option<Option<String>>,
+    pub(crate) no_pager: bool,
+    pub(crate) progress: bool,
+    pub(crate) stats: bool,
+    pub(crate) timings: bool,
+    pub(crate) timings_limit: Option<usize>,
+    pub(crate) no_messages: bool,
+    pub(crate) quiet: bool,
+    pub(crate) skip_lines: Option<usize>,
+    pub(crate) head: Option<usize>,
+    pub(crate) start_byte: Option<u64>,
+    pub(crate) count_from_start: bool,
+    pub(crate) max_total: Option<u64>,
+    pub(crate) first: Option<First>,
+    pub(crate) require_count: Option<u64>,
+    pub(crate) require_match: bool,
+    pub(crate) debug: bool,
+    pub(crate) json: bool,
+    pub(crate) output_format: Option<OutputFormat>,
+    pub(crate) no_header: bool,
+    pub(crate) format: Option<Template>,
+    pub(crate) watch: bool,
+    pub(crate) watch_interval: Option<u64>,
+    pub(crate) completions: Option<Shell>,
+}
+
+impl Parsed {
+    /// What is parsed of a command line without options.
+    fn new() -> Parsed {
+        Parsed {
+            group_separator: Some(String::from(GROUP_SEPARATOR)),
+            label: String::from(DEFAULT_LABEL),
+            threads: 1,
+            ..Parsed::default()
+        }
+    }
+
+    /// Takes in `--files-from` or `--files0-from`, whose paths are ended by
+    /// `terminator`.
+    ///
+    /// # Errors
+    /// Returns an error if a list of files is already given.
+    pub(crate) fn files_from(&mut self, arg: &mut Arg<'_>, terminator: u8) -> Result<(), String> {
+        if self.files_from.is_some() {
+            return Err(String::from(
+                "--files-from and --files0-from cannot be used together",
+            ));
+        }
+        self.files_from = Some(FileList {
+            path: PathBuf::from(arg.value()?),
+            terminator,
+        });
+        Ok(())
+    }
+
+    /// Takes in `--color` or `--colour`, which without a value is `auto`.
+    ///
+    /// # Errors
+    /// Returns an error if the value is not a [`ColorChoice`].
+    pub(crate) fn color(&mut self, arg: &mut Arg<'_>) -> Result<(), String> {
+        self.color = Some(match arg.optional_value() {
+            Some(value) => arg.parse(&value, ColorChoice::parse)?,
+            None => ColorChoice::Auto,
+        });
+        Ok(())
+    }
+}
+
+/// An option of the command line, as its entry in
+/// [`OPTIONS`](options::OPTIONS) takes it in.
+///
+/// - `flag`: The name it is given by, short or long.
+/// - `value`: Its value, if it takes one and one is given.
+/// - `rest`: The arguments after it.
+pub(crate) struct Arg<'a> {
+    pub(crate) flag: &'a str,
+    value: Option<String>,
+    rest: &'a mut dyn Iterator<Item = OsString>,
+}
+
+impl Arg<'_> {
+    /// Takes the value of an option that requires one.
+    ///
+    /// # Errors
+    /// Returns an error if there is no value, which `OPTIONS` only lets
+    /// happen to options that do not take one.
+    pub(crate) fn value(&mut self) -> Result<String, String> {
+        self.value
+            .take()
+            .ok_or_else(|| format!("Option '{}' requires a value", self.flag))
+    }
+
+    /// Takes the value of an option that may be given one.
+    pub(crate) fn optional_value(&mut self) -> Option<String> {
+        self.value.take()
+    }
+
+    /// Takes the value as a number.
+    ///
+    /// # Errors
+    /// Returns an error if there is no value or it is not a number.
+    pub(crate) fn number(&mut self) -> Result<usize, String> {
+        let value = self.value()?;
+        parse_number(self.flag, &value)
+    }
+
+    /// Takes the value as whichever of its choices `parse` returns.
+    ///
+    /// # Errors
+    /// Returns an error if there is no value or `parse` returns `None`.
+    pub(crate) fn choice<T>(&mut self, parse: impl Fn(&str) -> Option<T>) -> Result<T, String> {
+        let value = self.value()?;
+        self.parse(&value, parse)
+    }
+
+    /// Parses `value` of the option with `parse`.
+    ///
+    /// # Errors
+    /// Returns an error naming the option if `parse` returns `None`.
+    pub(crate) fn parse<T>(
+        &self,
+        value: &str,
+        parse: impl Fn(&str) -> Option<T>,
+    ) -> Result<T, String> {
+        parse(value).ok_or_else(|| format!("Invalid value '{value}' for '{}'", self.flag))
+    }
+
+    /// Takes the argument after the option, as `--between` does its end.
+    pub(crate) fn next(&mut self) -> Option<OsString> {
+        self.rest.next()
+    }
+}
+
+/// Splits a `--name=value` argument into its name and inline value.
+///
+/// Any other argument is returned unchanged with no value.
+fn split_option(arg: &str) -> (&str, Option<&str>) {
+    match arg.split_once('=') {
+        Some((flag, value)) if flag.starts_with("--") => (flag, Some(value)),
+        _ => (arg, None),
+    }
+}
+
+/// Returns the value of an option, taken either inline (`--name=value`)
+/// or from the next argument (`--name value`).
+///
+/// # Errors
+/// Returns an error if no value follows the option, or it is not valid
+/// Unicode.
+fn option_value(
+    flag: &str,
+    inline_value: Option<&str>,
+    args: &mut impl Iterator<Item = OsString>,
+) -> Result<String, String> {
+    match inline_value {
+        Some(value) => Ok(value.to_string()),
+        None => args
+            .next()
+            .ok_or_else(|| format!("Option '{flag}' requires a value"))?
+            .into_string()
+            .map_err(|_| format!("The value of '{flag}' is not valid Unicode")),
+    }
+}
+
+/// Reads the patterns of `-f` from the file at `path`, one per line, with
+/// the empty lines left out.
+///
+/// # Errors
+/// Returns an error naming the file if it cannot be read as UTF-8.
+pub(crate) fn read_patterns(path: &str) -> Result<Vec<String>, String> {
+    let text = std::fs::read_to_string(path).map_err(|error| format!("{path}: {error}"))?;
+    Ok(text
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+/// Parses the value of a numeric option.
+///
+/// # Errors
+/// Returns an error naming the option if `value` is not a non-negative number.
+pub(crate) fn parse_number(flag: &str, value: &str) -> Result<usize, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid number '{value}' for '{flag}'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strings;
+    use std::{ffi::OsStr, path::Path};
+
+    fn build(args: &[&str]) -> Result<Config, String> {
+        build_with_env(args, &[])
+    }
+
+    /// Builds a `Config` from `args` in an environment of only `vars`.
+    fn build_with_env(args: &[&str], vars: &[(&str, &str)]) -> Result<Config, String> {
+        let args = std::iter::once("minigrep").chain(args.iter().copied());
+        Config::build(args.map(OsString::from), true, fixed_env(vars))
+    }
+
+    /// Returns a lookup of the variables `vars`, and no others.
+    fn fixed_env<'v>(vars: &'v [(&str, &str)]) -> impl Fn(&str) -> Option<String> + 'v {
+        |name| {
+            vars.iter()
+                .find(|(var, _)| *var == name)
+                .map(|(_, value)| value.to_string())
+        }
+    }
+
+    #[test]
+    fn legacy_case_flag() {
+        let config = build(&["rust", "poem.txt", "/i"]).unwrap();
+        assert_eq!(config.query, "rust");
+        assert_eq!(config.file_paths, [Path::new("poem.txt")]);
+        assert!(config.ignore_case);
+        assert!(!config.with_filename);
+    }
+
+    #[test]
+    fn ignore_case_falls_back_to_the_environment() {
+        let ignore_case = |args: &[&str], vars| {
+            let args = [&["rust", "poem.txt"], args].concat();
+            build_with_env(&args, vars).unwrap().ignore_case
+        };
+        let set = &[("IGNORE_CASE", "1")][..];
+        assert!(!ignore_case(&[], &[]));
+        assert!(ignore_case(&[], set));
+        assert!(ignore_case(&[], &[("IGNORE_CASE", "")]));
+        assert!(!ignore_case(&[], &[("OTHER", "1")]));
+        assert!(ignore_case(&["/i"], &[]));
+        assert!(!ignore_case(&["/s"], set));
+        assert!(ignore_case(&["/s", "/i"], &[]));
+        assert!(!ignore_case(&["/i", "/s"], set));
+    }
+
+    #[test]
+    fn describe_names_what_decided_the_case() {
+        let case = |args: &[&str], vars| {
+            let args = [args, &["rust", "a.log"]].concat();
+            build_with_env(&args, vars).unwrap().describe()[1].clone()
+        };
+        let set: &[(&str, &str)] = &[("IGNORE_CASE", "1")];
+        assert_eq!(case(&[], &[]), "case: sensitive, by default");
+        assert_eq!(case(&[], set), "case: insensitive, from IGNORE_CASE");
+        assert_eq!(case(&["/i"], &[]), "case: insensitive, from /i");
+        assert_eq!(
+            case(&["/i", "/s"], set),
+            "case: sensitive, from /s over IGNORE_CASE"
+        );
+
+        let config = build(&["--expr", r#""a" | "b""#, "-v", "a.log", "-"]).unwrap();
+        let lines = config.describe();
+        assert_eq!(lines[0], r#"expr: "a" | "b""#);
+        assert!(lines.contains(&String::from("invert: true")));
+        assert!(lines.contains(&String::from(r#"inputs: "a.log", "-""#)));
+        let config = build(&["--between", "BEGIN", "END", "a.log"]).unwrap();
+        assert_eq!(config.describe()[0], r#"between: "BEGIN" and "END""#);
+        let config = build(&["--hex", "dead beef", "a.log"]).unwrap();
+        assert_eq!(config.describe()[0], "hex: DE AD BE EF");
+    }
+
+    #[test]
+    fn escapes_are_only_interpreted_when_asked() {
+        assert_eq!(build(&[r"a\tb", "poem.txt"]).unwrap().query, r"a\tb");
+        let config = build(&["--escapes", r"a\tb\\", "poem.txt"]).unwrap();
+        assert_eq!(config.query, "a\tb\\");
+        assert_eq!(
+            build(&["--escapes", r"a\x4", "poem.txt"]).unwrap_err(),
+            r"Incomplete escape '\x4' at character 2 of the query"
+        );
+    }
+
+    #[test]
+    fn hex_queries_are_parsed_as_bytes() {
+        let config = build(&["--hex", "--hex-context=2", "dead BEEF", "a.bin"]).unwrap();
+        assert_eq!(config.hex, Some(vec![0xde, 0xad, 0xbe, 0xef]));
+        assert_eq!(config.hex_context, 2);
+        for (query, error) in [
+            ("DE A", "Odd number of hex digits in 'A'"),
+            ("DX", "Invalid hex digit 'X' in 'DX'"),
+            (" ", "The hex pattern is empty"),
+        ] {
+            assert_eq!(build(&["--hex", query, "a.bin"]).unwrap_err(), error);
+        }
+        assert_eq!(
+            build(&["--hex-context=2", "DE", "a.bin"]).unwrap_err(),
+            "--hex-context requires --hex"
+        );
+    }
+
+    #[test]
+    fn strings_take_runs_of_a_minimum_length_with_their_offsets() {
+        let config = build(&["--strings", "libssl", "a.bin"]).unwrap();
+        assert_eq!(config.strings, Some(strings::DEFAULT_MIN));
+        assert!(config.byte_offset);
+        assert_eq!(build(&["--strings=8", "a", "x"]).unwrap().strings, Some(8));
+        let config = build(&["a", "x"]).unwrap();
+        assert!(config.strings.is_none() && !config.byte_offset);
+        for min in ["0", "four"] {
+            assert_eq!(
+                build(&[&format!("--strings={min}"), "a", "x"]).unwrap_err(),
+                format!("Invalid number '{min}' for '--strings'")
+            );
+        }
+    }
+
+    #[test]
+    fn expressions_take_the_place_of_the_query() {
+        let config = build(&["--expr", r#""a" & !"b""#, "x.log", "y.log"]).unwrap();
+        assert_eq!(config.query, r#""a" & !"b""#);
+        assert!(config.expr.is_some());
+        assert_eq!(config.file_paths, [Path::new("x.log"), Path::new("y.log")]);
+        assert_eq!(
+            build(&["--expr", r#""a" &"#, "x.log"]).unwrap_err(),
+            "Expected a quoted literal, '!', or '(' at the end of the expression"
+        );
+    }
+
+    #[test]
+    fn patterns_of_e_take_the_place_of_the_query() {
+        let config = build(&["-e", "err", "x.log"]).unwrap();
+        assert_eq!((config.query.as_str(), config.expr), ("err", None));
+        assert_eq!(config.file_paths, [Path::new("x.log")]);
+        let config = build(&["-e", "err", "--pattern=warn", "-e", "fail", "x.log"]).unwrap();
+        let literal = |text: &str| Box::new(Expr::Literal(text.to_string()));
+        let err_or_warn = Box::new(Expr::Or(literal("err"), literal("warn")));
+        assert_eq!(config.expr, Some(Expr::Or(err_or_warn, literal("fail"))));
+        assert_eq!(config.query, "err");
+        let config = build(&["-e", "a", "-e", "b", "--and-not=c", "x.log"]).unwrap();
+        let not_c = Box::new(Expr::Not(literal("c")));
+        let a_or_b = Box::new(Expr::Or(literal("a"), literal("b")));
+        assert_eq!(config.expr, Some(Expr::And(a_or_b, not_c)));
+    }
+
+    #[test]
+    fn patterns_may_set_their_own_case() {
+        let cased = |text: &str, ignore_case| Box::new(Expr::Cased(text.to_string(), ignore_case));
+        let config = build(&["-e", "(?i)err", "x.log"]).unwrap();
+        assert_eq!(config.query, "err");
+        assert_eq!(config.expr, Some(*cased("err", true)));
+
+        let file = std::env::temp_dir().join("minigrep-config-patterns.txt");
+        std::fs::write(&file, "(?-i)Warn\n\nfail\r\n").unwrap();
+        let file = file.to_str().unwrap();
+        let config = build(&["-e", "(?i)err", "-f", file, "x.log", "/i"]).unwrap();
+        let err_or_warn = Box::new(Expr::Or(cased("err", true), cased("Warn", false)));
+        let fail = Box::new(Expr::Literal(String::from("fail")));
+        assert_eq!(config.expr, Some(Expr::Or(err_or_warn, fail)));
+        assert_eq!(config.file_paths, [Path::new("x.log")]);
+
+        let empty = std::env::temp_dir().join("minigrep-config-no-patterns.txt");
+        std::fs::write(&empty, "\n").unwrap();
+        let err = build(&["-f", empty.to_str().unwrap(), "x.log"]).unwrap_err();
+        assert_eq!(err, "-f found no patterns");
+        let err = build(&["-f", file, "--expr", r#""a""#, "x.log"]).unwrap_err();
+        assert_eq!(err, "-f cannot be used with --expr");
+    }
+
+    #[test]
+    fn and_not_patterns_are_joined_to_the_query() {
+        let config = build(&["--and-not=b", "a", "--and-not", "c", "x.log"]).unwrap();
+        let not = |text: &str| Box::new(Expr::Not(Box::new(Expr::Literal(text.to_string()))));
+        let a_and_not_b = Expr::And(Box::new(Expr::Literal(String::from("a"))), not("b"));
+        assert_eq!(
+            config.expr,
+            Some(Expr::And(Box::new(a_and_not_b), not("c")))
+        );
+        assert_eq!(config.query, "a");
+        assert_eq!(build(&["a", "x.log"]).unwrap().expr, None);
+    }
+
+    #[test]
+    fn between_takes_the_place_of_the_query() {
+        let config = build(&["--between", "BEGIN", "END", "x.conf", "y.conf"]).unwrap();
+        assert_eq!(config.between, Some(Between::new("BEGIN", "END")));
+        assert_eq!(config.query, "BEGIN");
+        assert_eq!(
+            config.file_paths,
+            [Path::new("x.conf"), Path::new("y.conf")]
+        );
+        let config = build(&["--between=[", "]", "x.conf"]).unwrap();
+        assert_eq!(config.between, Some(Between::new("[", "]")));
+        assert_eq!(
+            build(&["x.conf", "--between", "BEGIN"]).unwrap_err(),
+            "Option '--between' requires an end pattern"
+        );
+    }
+
+    #[test]
+    fn since_and_until_bound_a_time_window() {
+        let config = build(&["--since=2024-01-02 14:00", "error", "app.log"]).unwrap();
+        let window = config.time_window.unwrap();
+        assert_eq!(window.since, timestamp::parse("2024-01-02T14:00Z", 0));
+        assert_eq!((window.until, window.untimed), (None, Untimed::Drop));
+        assert_eq!(window.year, timestamp::current_year());
+        let args = [
+            "--until",
+            "Jan  2 00:00:00",
+            "--no-timestamp=keep",
+            "a",
+            "x",
+        ];
+        let window = build(&args).unwrap().time_window.unwrap();
+        assert!(window.since.is_none() && window.until.is_some());
+        assert_eq!(window.untimed, Untimed::Keep);
+        assert_eq!(build(&["a", "x"]).unwrap().time_window, None);
+
+        assert_eq!(
+            build(&["--since=14:00", "a", "x"]).unwrap_err(),
+            "Invalid timestamp '14:00' for '--since': expected a form such as \
+             2024-01-02T14:00:00Z, 2024-01-02 14:00, or Jan  2 14:00:00"
+        );
+        assert_eq!(
+            build(&["--since=2024-01-02", "--until=2024-01-01", "a", "x"]).unwrap_err(),
+            "--since must be earlier than --until"
+        );
+        assert_eq!(
+            build(&["--since=2024-01-02", "--no-timestamp=skip", "a", "x"]).unwrap_err(),
+            "Invalid value 'skip' for '--no-timestamp'"
+        );
+        assert_eq!(
+            build(&["--no-timestamp=keep", "a", "x"]).unwrap_err(),
+            "--no-timestamp requires --since or --until"
+        );
+    }
+
+    #[test]
+    fn squeezing_applies_to_the_query_and_patterns() {
+        let config = build(&["--squeeze-space", "a \t b", "--and-not=c  d", "x"]).unwrap();
+        assert!(config.squeeze_space && !config.trim);
+        assert_eq!(config.query, "a b");
+        let not = Box::new(Expr::Not(Box::new(Expr::Literal(String::from("c d")))));
+        assert_eq!(
+            config.expr,
+            Some(Expr::And(Box::new(Expr::Literal(String::from("a b"))), not))
+        );
+        let config = build(&["--squeeze-space", "--between", "[  a", "b  ]", "x"]).unwrap();
+        assert_eq!(config.between, Some(Between::new("[ a", "b ]")));
+        let config = build(&["--trim", "a  b", "x"]).unwrap();
+        assert!(config.trim);
+        assert_eq!(config.query, "a  b");
+    }
+
+    #[test]
+    fn skip_lines_and_head_window_each_input() {
+        let config = build(&["--skip-lines=1", "--head", "100", "a", "x.csv"]).unwrap();
+        assert_eq!((config.skip_lines, config.head), (1, Some(100)));
+        let config = build(&["a", "x.csv"]).unwrap();
+        assert_eq!((config.skip_lines, config.head), (0, None));
+        assert!(build(&["--head=ten", "a", "x.csv"]).is_err());
+        assert_eq!(
+            build(&["--max-total=50", "a", "x"]).unwrap().max_total,
+            Some(50)
+        );
+        assert_eq!(build(&["a", "x"]).unwrap().max_total, None);
+    }
+
+    #[test]
+    fn first_selects_a_single_line_in_order_unless_any_will_do() {
+        let config = build(&["--first", "a", "x"]).unwrap();
+        assert_eq!(config.first, Some(First::Ordered));
+        assert_eq!((config.max_total, config.sort), (Some(1), Sort::Path));
+        let config = build(&["--first=any", "a", "x"]).unwrap();
+        assert_eq!(config.first, Some(First::Any));
+        assert_eq!((config.max_total, config.sort), (Some(1), Sort::None));
+        let err = build(&["--first=last", "a", "x"]).unwrap_err();
+        assert_eq!(err, "Invalid value 'last' for '--first'");
+    }
+
+    #[test]
+    fn line_numbers_from_a_start_byte_must_be_counted() {
+        let config = build(&["--start-byte=4096", "a", "x.log"]).unwrap();
+        assert_eq!(
+            (config.start_byte, config.count_from_start),
+            (Some(4096), false)
+        );
+        let err = build(&["--start-byte=4096", "--vimgrep", "a", "x.log"]).unwrap_err();
+        assert_eq!(
+            err,
+            "--vimgrep with --start-byte requires --count-from-start, \
+             as line numbers are unknown without it"
+        );
+        let config = build(&["--start-byte=1", "--count-from-start", "-n", "a", "x"]).unwrap();
+        assert!(config.count_from_start && config.line_number);
+        let err = build(&["--count-from-start", "a", "x"]).unwrap_err();
+        assert_eq!(err, "--count-from-start requires --start-byte");
+    }
+
+    #[test]
+    fn no_unicode_matches_bytes_with_columns_in_bytes() {
+        let config = build(&["--no-unicode", "--column", "K", "x.log", "/i"]).unwrap();
+        assert!(config.no_unicode && config.byte_column);
+        assert!(config.matcher().is_match("k", config.ignore_case));
+        assert!(!config.matcher().is_match("\u{212a}", config.ignore_case));
+        assert!(
+            Config::new("K", vec![])
+                .matcher()
+                .is_match("\u{212a}", true)
+        );
+        let err = build(&["--no-unicode", "--trim", "a", "x.log"]).unwrap_err();
+        assert_eq!(err, "--no-unicode cannot be used with --trim");
+    }
+
+    #[test]
+    fn pagers_come_from_the_flag_or_the_environment() {
+        let pager = |args: &[&str], vars: &[(&str, &str)]| {
+            build_with_env(&[args, &["a", "x"]].concat(), vars)
+                .unwrap()
+                .pager
+        };
+        assert_eq!(pager(&[], &[]), None);
+        assert_eq!(pager(&["--pager"], &[]), Some(String::from("less")));
+        assert_eq!(
+            pager(&["--pager=cat"], &[("MINIGREP_PAGER", "more")]),
+            Some(String::from("cat"))
+        );
+        assert_eq!(
+            pager(&[], &[("MINIGREP_PAGER", "less -S")]),
+            Some(String::from("less -S"))
+        );
+        assert_eq!(pager(&[], &[("PAGER", "more")]), None);
+        assert_eq!(
+            pager(&["--pager", "--no-pager"], &[("MINIGREP_PAGER", "more")]),
+            None
+        );
+    }
+
+    #[test]
+    fn the_environment_is_read_only_through_the_lookup() {
+        let config = build(&["a", "x"]).unwrap();
+        assert_eq!(config.color_env, ColorEnv::default());
+        assert_eq!((config.less, config.columns), (None, DEFAULT_COLUMNS));
+
+        let vars = [
+            ("NO_COLOR", ""),
+            ("CLICOLOR_FORCE", "1"),
+            ("MINIGREP_COLORS", "ms=35"),
+            ("GREP_COLORS", "ms=34"),
+            ("LESS", "R"),
+            ("COLUMNS", "120"),
+        ];
+        let config = build_with_env(&["a", "x"], &vars).unwrap();
+        assert_eq!(
+            config.color_env,
+            ColorEnv {
+                no_color: Some(String::new()),
+                clicolor_force: Some(String::from("1")),
+                colors: Some(("MINIGREP_COLORS", String::from("ms=35"))),
+            }
+        );
+        assert_eq!(config.less.as_deref(), Some("R"));
+        assert_eq!(config.columns, 120);
+        let config = build_with_env(&["a", "x"], &[("COLUMNS", "wide")]).unwrap();
+        assert_eq!(config.columns, DEFAULT_COLUMNS);
+    }
+
+    #[test]
+    fn options_and_label() {
+        let config = build(&["-n", "--label=log.gz", "error", "-", "/s"]).unwrap();
+        assert!(config.line_number);
+        assert!(!config.ignore_case);
+        assert_eq!(config.label, "log.gz");
+    }
+
+    #[test]
+    fn missing_file_path_reads_piped_standard_input() {
+        assert_eq!(build(&["error"]).unwrap_err(), "Didn't get a file path ");
+
+        let args = ["minigrep", "error", "/i"].map(OsString::from);
+        let config = Config::build(args.into_iter(), false, fixed_env(&[])).unwrap();
+        assert_eq!(config.file_paths, [Path::new("-")]);
+        assert!(config.ignore_case);
+        assert!(!config.with_filename);
+    }
+
+    #[test]
+    fn files_from_replaces_the_default_input() {
+        let config = build(&["--files-from=list.txt", "error"]).unwrap();
+        assert!(config.file_paths.is_empty());
+        assert!(config.with_filename);
+        let list = config.files_from.unwrap();
+        assert_eq!(
+            (list.path.as_path(), list.terminator),
+            (Path::new("list.txt"), b'\n')
+        );
+        let list = build(&["--files0-from", "-", "error"]).unwrap().files_from;
+        assert_eq!(list.map(|list| list.terminator), Some(b'\0'));
+
+        let err = build(&["--files-from=-", "error", "-"]).unwrap_err();
+        assert_eq!(
+            err,
+            "the file list and an input cannot both be read from standard input"
+        );
+        let err = build(&["--files-from=a", "--files0-from=b", "error"]).unwrap_err();
+        assert_eq!(
+            err,
+            "--files-from and --files0-from cannot be used together"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn only_paths_may_be_non_unicode() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let invalid = OsStr::from_bytes(b"caf\xe9");
+        let args = [OsStr::new("minigrep"), OsStr::new("error"), invalid];
+        let config =
+            Config::build(args.into_iter().map(OsString::from), true, fixed_env(&[])).unwrap();
+        assert_eq!(config.file_paths, [Path::new(invalid)]);
+
+        let args = [OsStr::new("minigrep"), invalid, OsStr::new("a.log")];
+        let err =
+            Config::build(args.into_iter().map(OsString::from), true, fixed_env(&[])).unwrap_err();
+        assert_eq!(err, "The query string is not valid Unicode");
+
+        let args = [OsStr::new("minigrep"), OsStr::new("--label"), invalid];
+        let err =
+            Config::build(args.into_iter().map(OsString::from), true, fixed_env(&[])).unwrap_err();
+        assert_eq!(err, "The value of '--label' is not valid Unicode");
+    }
+
+    #[test]
+    fn label_defaults_to_standard_input() {
+        let config = build(&["--label", "x", "error", "-"]).unwrap();
+        assert_eq!(config.label, "x");
+
+        let config = build(&["error", "-"]).unwrap();
+        assert_eq!(config.label, "(standard input)");
+    }
+
+    #[test]
+    fn multiple_files_enable_filename_prefix() {
+        let config = build(&["error", "a.log", "b.log"]).unwrap();
+        assert!(config.with_filename);
+    }
+
+    #[test]
+    fn line_buffered_flag() {
+        assert!(
+            build(&["--line-buffered", "error", "a.log"])
+                .unwrap()
+                .line_buffered
+        );
+        assert!(!build(&["error", "a.log"]).unwrap().line_buffered);
+    }
+
+    #[test]
+    fn read_strategy_flag() {
+        let strategy = |args: &[&str]| build(args).map(|config| config.read_strategy);
+        assert_eq!(strategy(&["error", "a.log"]), Ok(None));
+        assert_eq!(
+            strategy(&["--read-strategy=mmap", "error", "a.log"]),
+            Ok(Some(ReadStrategy::Mmap))
+        );
+        assert_eq!(
+            strategy(&["--read-strategy", "auto", "error", "a.log"]),
+            Ok(Some(ReadStrategy::Auto))
+        );
+        assert_eq!(
+            strategy(&["--read-strategy=mapped", "error", "a.log"]),
+            Err(String::from("Invalid value 'mapped' for '--read-strategy'"))
+        );
+    }
+
+    #[test]
+    fn color_choice() {
+        assert_eq!(build(&["error", "a.log"]).unwrap().color, ColorChoice::Auto);
+        assert_eq!(
+            build(&["--color", "error", "a.log"]).unwrap().color,
+            ColorChoice::Auto
+        );
+        assert_eq!(
+            build(&["--color=never", "error", "a.log"]).unwrap().color,
+            ColorChoice::Never
+        );
+        assert!(build(&["--color=sometimes", "error", "a.log"]).is_err());
+    }
+
+    #[test]
+    fn recursive_and_max_filesize() {
+        let config = build(&["-r", "--max-filesize=2M", "error", "logs"]).unwrap();
+        assert!(config.recursive);
+        assert!(config.with_filename);
+        assert_eq!(config.max_filesize, Some(2 * 1024 * 1024));
+
+        assert!(build(&["--max-filesize", "2Q", "error", "logs"]).is_err());
+    }
+
+    #[test]
+    fn max_depth_requires_recursion() {
+        assert_eq!(
+            build(&["-r", "--max-depth=2", "error", "."])
+                .unwrap()
+                .max_depth,
+            Some(2)
+        );
+        assert!(build(&["--max-depth=2", "error", "."]).is_err());
+        assert!(build(&["-r", "--max-depth=deep", "error", "."]).is_err());
+    }
+
+    #[test]
+    fn max_columns() {
+        let config = build(&[
+            "--max-columns=80",
+            "--max-columns-preview",
+            "error",
+            "a.log",
+        ])
+        .unwrap();
+        assert_eq!(config.max_columns, Some(80));
+        assert!(config.max_columns_preview);
+
+        assert!(build(&["--max-columns=-1", "error", "a.log"]).is_err());
+    }
+
+    #[test]
+    fn snippet_width_and_conflicts() {
+        assert_eq!(
+            build(&["--snippet=20", "error", "a.log"]).unwrap().snippet,
+            Some(20)
+        );
+        for conflict in ["--max-columns=80", "-o", "--json", "--format={text}"] {
+            let err = build(&["--snippet=20", conflict, "error", "a.log"]).unwrap_err();
+            let flag = conflict.split('=').next().unwrap();
+            assert_eq!(err, format!("--snippet cannot be used with {flag}"));
+        }
+
+        let config = build(&["--char-context=8", "-n", "--column", "error", "a.log"]).unwrap();
+        assert_eq!(config.char_context, Some(8));
+        for conflict in ["--snippet=20", "-v", "--after-context=2", "--passthru"] {
+            let err = build(&["--char-context=8", conflict, "error", "a.log"]).unwrap_err();
+            assert!(err.contains("--char-context"), "{err}");
+        }
+    }
+
+    #[test]
+    fn files_mode_takes_only_paths() {
+        let config = build(&["--files", "-Z", "src", "tests"]).unwrap();
+        assert!(config.list_files);
+        assert!(config.null);
+        assert_eq!(config.query, "");
+        assert_eq!(config.file_paths, [Path::new("src"), Path::new("tests")]);
+
+        assert!(build(&["--files"]).is_err());
+        assert_eq!(
+            build(&["error", "src"]).unwrap().file_paths,
+            [Path::new("src")]
+        );
+    }
+
+    #[test]
+    fn passthru_conflicts() {
+        assert!(build(&["--passthru", "error", "a.log"]).unwrap().passthru);
+        for conflict in ["-v", "-c", "-l", "-L", "-o"] {
+            let err = build(&["--passthru", conflict, "error", "a.log"]).unwrap_err();
+            assert_eq!(err, format!("--passthru cannot be used with {conflict}"));
+        }
+    }
+
+    #[test]
+    fn replace_takes_a_value() {
+        let config = build(&["--replace=[$0]", "error", "a.log"]).unwrap();
+        assert_eq!(config.replace.as_deref(), Some("[$0]"));
+        assert_eq!(
+            build(&["--replace", "", "error", "a.log"])
+                .unwrap()
+                .replace
+                .as_deref(),
+            Some("")
+        );
+        assert!(build(&["error", "a.log", "--replace"]).is_err());
+    }
+
+    #[test]
+    fn context_flags() {
+        let config = build(&["-C", "2", "-A", "1", "error", "a.log"]).unwrap();
+        assert!(config.context);
+        assert_eq!((config.before_context, config.after_context), (2, 1));
+        assert_eq!(config.group_separator.as_deref(), Some("--"));
+        let config = build(&["--after-context=0", "--no-group-separator", "e", "a"]).unwrap();
+        assert!(config.context);
+        assert_eq!((config.before_context, config.after_context), (0, 0));
+        assert_eq!(config.group_separator, None);
+        assert!(!build(&["error", "a.log"]).unwrap().context);
+
+        let err = build(&["-B", "1", "-o", "error", "a.log"]).unwrap_err();
+        assert_eq!(err, "-B cannot be used with -o");
+        let err = build(&["--group-separator=:", "error", "a.log"]).unwrap_err();
+        assert_eq!(
+            err,
+            "--group-separator and --no-group-separator require -A, -B, or -C"
+        );
+        assert!(build(&["-A", "x", "error", "a.log"]).is_err());
+    }
+
+    #[test]
+    fn in_place_conflicts() {
+        let config = build(&["--in-place", "--replace=new", "old", "a.txt"]).unwrap();
+        assert!(config.in_place);
+        assert!(!config.no_backup);
+        assert!(
+            build(&["--in-place", "--no-backup", "--replace=new", "old", "a.txt"])
+                .unwrap()
+                .no_backup
+        );
+
+        let err = build(&["--in-place", "old", "a.txt"]).unwrap_err();
+        assert_eq!(err, "--in-place requires --replace");
+        let err = build(&["--in-place", "--replace=new", "old", "a.txt", "-"]).unwrap_err();
+        assert_eq!(err, "--in-place cannot be used with standard input");
+        for conflict in ["-v", "-o", "-c", "-l", "--passthru"] {
+            let err =
+                build(&["--in-place", "--replace=new", conflict, "old", "a.txt"]).unwrap_err();
+            assert_eq!(err, format!("--in-place cannot be used with {conflict}"));
+        }
+        assert!(build(&["--no-backup", "--replace=new", "old", "a.txt"]).is_err());
+    }
+
+    #[test]
+    fn timings_and_their_limit() {
+        let config = build(&["--timings", "--timings-limit=5", "error", "a.log"]).unwrap();
+        assert!(config.timings);
+        assert_eq!(config.timings_limit, Some(5));
+        let err = build(&["--timings-limit=5", "error", "a.log"]).unwrap_err();
+        assert_eq!(err, "--timings-limit requires --timings");
+    }
+
+    #[test]
+    fn context_until_counts_as_context() {
+        let config = build(&["--after-context-until=--", "--include-stop", "e", "a"]).unwrap();
+        assert_eq!(config.context_until, Some(ContextUntil::new("--", true)));
+        assert!(config.context);
+        assert!(build(&["--after-context-until=x", "--no-group-separator", "e", "a"]).is_ok());
+        assert_eq!(
+            build(&["--include-stop", "e", "a"]).unwrap_err(),
+            "--include-stop requires --after-context-until"
+        );
+        assert_eq!(
+            build(&["-c", "--after-context-until=x", "e", "a"]).unwrap_err(),
+            "--after-context-until cannot be used with -c"
+        );
+    }
+
+    #[test]
+    fn threads_and_sort() {
+        let config = build(&["error", "a.log"]).unwrap();
+        assert_eq!(config.threads, 1);
+        assert_eq!(config.sort, Sort::None);
+
+        let config = build(&["--sort=path", "error", "a.log"]).unwrap();
+        assert_eq!(config.sort, Sort::Path);
+        if cfg!(feature = "parallel") {
+            let config = build(&["-j", "4", "error", "a.log"]).unwrap();
+            assert_eq!(config.threads, 4);
+            assert!(build(&["--threads=0", "error", "a.log"]).unwrap().threads >= 1);
+        }
+        assert!(build(&["--sort=size", "error", "a.log"]).is_err());
+
+        assert_eq!(config.sort_buffer, DEFAULT_SORT_BUFFER);
+        let config = build(&["--sort=path", "--sort-buffer=64K", "error", "a.log"]).unwrap();
+        assert_eq!(config.sort_buffer, 64 << 10);
+        let err = build(&["--sort-buffer=1M", "error", "a.log"]).unwrap_err();
+        assert_eq!(err, "--sort-buffer requires --sort=path");
+    }
+
+    #[test]
+    fn every_conflict_is_rejected_naming_both_options() {
+        let args = |option: &'static str| -> Vec<&'static str> {
+            match option {
+                "--output-format" => vec!["--output-format=csv"],
+                "--replace" => vec!["--replace=x"],
+                "--format" => vec!["--format={text}"],
+                "--snippet" => vec!["--snippet=10"],
+                "--char-context" => vec!["--char-context=10"],
+                "--read-strategy" => vec!["--read-strategy=mmap"],
+                "--max-columns" => vec!["--max-columns=80"],
+                "-C" => vec!["-C", "1"],
+                "--after-context-until" => vec!["--after-context-until=end"],
+                "--encoding" => vec!["--encoding=latin1"],
+                "--pre" => vec!["--pre=cat"],
+                "--files-from" => vec!["--files-from=list.txt"],
+                "--expr" => vec![r#"--expr="a""#],
+                "--and-not" => vec!["--and-not=b"],
+                "-e" => vec!["-e", "a"],
+                "--between" => vec!["--between", "a", "b"],
+                "--require-count" => vec!["--require-count=2"],
+                "--skip-lines" => vec!["--skip-lines=1"],
+                "--head" => vec!["--head=10"],
+                "--start-byte" => vec!["--start-byte=10"],
+                "--max-total" => vec!["--max-total=5"],
+                "--since" => vec!["--since=2024-01-02"],
+                "--until" => vec!["--until=2024-01-02"],
+                option => vec![option],
+            }
+        };
+        let shown = |option: &'static str| match option {
+            "--count" => "-c",
+            option => option.trim_end_matches("=sarif"),
+        };
+        for (option, conflicts) in CONFLICTS {
+            for conflict in *conflicts {
+                let given = [args(option), args(conflict), vec!["error", "a.log"]].concat();
+                let err = build(&given).unwrap_err();
+                let expected = format!("{} cannot be used with {}", shown(option), shown(conflict));
+                assert_eq!(err, expected, "{given:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn unusual_combinations_are_allowed() {
+        let combinations = [
+            &["-c", "-v"][..],
+            &["-o", "/i"],
+            &["-o", "-n", "-b"],
+            &["-c", "/i", "--count-total"],
+            &["-l", "-v"],
+            &["-L", "/i"],
+            &["--passthru", "-n", "--replace=x"],
+            &["--vimgrep", "-n", "/i"],
+            &["--column", "-o"],
+        ];
+        for combination in combinations {
+            let given = [combination, &["error", "a.log"]].concat();
+            assert!(build(&given).is_ok(), "{given:?}");
+        }
+    }
+
+    #[test]
+    fn flags_of_features_left_out_are_rejected() {
+        let search_zip = cfg!(any(
+            feature = "gzip",
+            feature = "bzip2",
+            feature = "xz",
+            feature = "zip",
+            feature = "tar"
+        ));
+        let flags = [
+            ("--json", "--json", cfg!(feature = "json"), "json"),
+            (
+                "--output-format=sarif",
+                "--output-format=sarif",
+                cfg!(feature = "json"),
+                "json",
+            ),
+            ("--threads=2", "-j", cfg!(feature = "parallel"), "parallel"),
+            (
+                "--search-zip",
+                "--search-zip",
+                search_zip,
+                "compress or archive",
+            ),
+        ];
+        for (flag, name, enabled, feature) in flags {
+            let result = build(&[flag, "error", "a.log"]);
+            if enabled {
+                assert!(result.is_ok(), "{flag}");
+            } else {
+                let expected = format!(
+                    "{name} is not available: minigrep was built without {feature} support"
+                );
+                assert_eq!(result.unwrap_err(), expected);
+            }
+        }
+        assert_eq!(build(&["-j", "1", "error", "a.log"]).unwrap().threads, 1);
+    }
+
+    #[test]
+    fn json_conflicts() {
+        if cfg!(feature = "json") {
+            assert!(
+                build(&["--json", "-v", "-n", "error", "a.log"])
+                    .unwrap()
+                    .json
+            );
+        }
+        for conflict in ["--color", "--color=never", "-c", "-o", "-l", "--replace=x"] {
+            let err = build(&["--json", conflict, "error", "a.log"]).unwrap_err();
+            let flag = conflict.split('=').next().unwrap();
+            assert_eq!(err, format!("--json cannot be used with {flag}"));
+        }
+    }
+
+    #[test]
+    fn output_format_values_and_conflicts() {
+        let config = build(&["--output-format=tsv", "--no-header", "error", "a.log"]).unwrap();
+        assert_eq!(
+            config.output_format,
+            Some(OutputFormat::Table(csv::Format::Tsv))
+        );
+        assert!(config.no_header);
+        assert_eq!(
+            build(&["--output-format", "csv", "error", "a.log"])
+                .unwrap()
+                .output_format,
+            Some(OutputFormat::Table(csv::Format::Csv))
+        );
+        if cfg!(feature = "json") {
+            assert_eq!(
+                build(&["--output-format=sarif", "error", "a.log"])
+                    .unwrap()
+                    .output_format,
+                Some(OutputFormat::Sarif)
+            );
+        }
+        for conflict in ["-v", "--no-header"] {
+            let err = build(&["--output-format=sarif", conflict, "error", "a.log"]).unwrap_err();
+            assert_eq!(
+                err,
+                format!("--output-format cannot be used with {conflict}")
+            );
+        }
+        assert!(build(&["--output-format=csv", "-v", "error", "a.log"]).is_ok());
+
+        let err = build(&["--output-format=xls", "error", "a.log"]).unwrap_err();
+        assert_eq!(err, "Invalid value 'xls' for '--output-format'");
+        let err = build(&["--no-header", "error", "a.log"]).unwrap_err();
+        assert_eq!(err, "--no-header requires --output-format");
+        for conflict in ["--json", "--vimgrep", "-c", "--color=always"] {
+            let err = build(&["--output-format=csv", conflict, "error", "a.log"]).unwrap_err();
+            let flag = conflict.split('=').next().unwrap();
+            assert_eq!(err, format!("--output-format cannot be used with {flag}"));
+        }
+    }
+
+    #[test]
+    fn format_is_parsed_at_startup() {
+        let config = build(&["--format={path}:{line}", "error", "a.log"]).unwrap();
+        assert_eq!(
+            config.format,
+            Some(Template::parse("{path}:{line}").unwrap())
+        );
+
+        let err = build(&["--format", "{file}", "error", "a.log"]).unwrap_err();
+        assert_eq!(
+            err,
+            "Invalid template '{file}': unknown placeholder '{file}'"
+        );
+        for conflict in ["-c", "--json", "--vimgrep", "--output-format=csv"] {
+            let err = build(&["--format={text}", conflict, "error", "a.log"]).unwrap_err();
+            let flag = conflict.split('=').next().unwrap();
+            assert_eq!(err, format!("--format cannot be used with {flag}"));
+        }
+    }
+
+    #[test]
+    fn count_matches_is_a_kind_of_count() {
+        let config = build(&["--count-matches", "error", "a.log"]).unwrap();
+        assert!(config.count && config.count_matches);
+        assert!(
+            build(&["--count-matches", "--count-total", "error", "a.log"])
+                .unwrap()
+                .count_total
+        );
+
+        let err = build(&["-c", "--count-matches", "error", "a.log"]).unwrap_err();
+        assert_eq!(err, "--count-matches cannot be used with -c");
+        let err = build(&["--count-matches", "-v", "error", "a.log"]).unwrap_err();
+        assert_eq!(err, "--count-matches cannot be used with -v");
+        let err = build(&["--count-matches", "--json", "error", "a.log"]).unwrap_err();
+        assert_eq!(err, "--json cannot be used with --count-matches");
+    }
+
+    #[test]
+    fn wc_is_a_kind_of_count() {
+        let config = build(&["--wc", "-v", "error", "a.log"]).unwrap();
+        assert!(config.count && config.wc && config.invert);
+
+        let err = build(&["-c", "--wc", "error", "a.log"]).unwrap_err();
+        assert_eq!(err, "--wc cannot be used with -c");
+        let err = build(&["--wc", "-o", "error", "a.log"]).unwrap_err();
+        assert_eq!(err, "--wc cannot be used with -o");
+        let err = build(&["--wc", "--count-total", "error", "a.log"]).unwrap_err();
+        assert_eq!(err, "--wc cannot be used with --count-total");
+    }
+
+    #[test]
+    fn unique_counts_and_top() {
+        let config = build(&["-o", "--unique-counts", "--top=3", "error", "a.log"]).unwrap();
+        assert!(config.unique_counts);
+        assert_eq!(config.top, Some(3));
+
+        let err = build(&["--unique-counts", "error", "a.log"]).unwrap_err();
+        assert_eq!(err, "--unique-counts requires -o");
+        let err = build(&["-o", "--top=3", "error", "a.log"]).unwrap_err();
+        assert_eq!(err, "--top requires --unique-counts or --count-by-file");
+        let err = build(&["-o", "--unique-counts", "--count-matches", "e", "a"]).unwrap_err();
+        assert_eq!(err, "--unique-counts cannot be used with --count-matches");
+
+        let config = build(&["--count-by-file", "--sort-count", "--top=3", "e", "a"]).unwrap();
+        assert!(config.count && config.count_by_file && config.sort_count);
+        assert_eq!(config.top, Some(3));
+        let err = build(&["--sort-count", "e", "a"]).unwrap_err();
+        assert_eq!(err, "--sort-count requires --count-by-file");
+        let err = build(&["--count-by-file", "-o", "e", "a"]).unwrap_err();
+        assert_eq!(err, "--count-by-file cannot be used with -o");
+
+        let config = build(&["--group-by-dir", "--group-depth=1", "e", "a"]).unwrap();
+        assert!(config.count_by_file && config.group_by_dir);
+        assert_eq!(config.group_depth, Some(1));
+        let err = build(&["--group-by-dir", "-l", "e", "a"]).unwrap_err();
+        assert_eq!(err, "--group-by-dir cannot be used with -l");
+        let err = build(&["--group-depth=1", "e", "a"]).unwrap_err();
+        assert_eq!(err, "--group-depth requires --group-by-dir");
+    }
+
+    #[test]
+    fn line_numbers_only_and_ranges() {
+        let config = build(&["--line-numbers-only", "--ranges", "rust", "poem.txt"]).unwrap();
+        assert!(config.line_numbers_only && config.ranges);
+
+        let err = build(&["--ranges", "rust", "poem.txt"]).unwrap_err();
+        assert_eq!(err, "--ranges requires --line-numbers-only");
+        let err = build(&["--line-numbers-only", "-o", "rust", "poem.txt"]).unwrap_err();
+        assert_eq!(err, "--line-numbers-only cannot be used with -o");
+        let err = build(&["--line-numbers-only", "--count-matches", "a", "b"]).unwrap_err();
+        assert_eq!(
+            err,
+            "--line-numbers-only cannot be used with --count-matches"
+        );
+        let args = [
+            "--line-numbers-only",
+            "--ranges",
+            "--watch",
+            "rust",
+            "poem.txt",
+        ];
+        let err = build(&args).unwrap_err();
+        assert_eq!(err, "--ranges cannot be used with --watch");
+    }
+
+    #[test]
+    fn count_total_requires_count() {
+        assert!(
+            build(&["-c", "--count-total", "error", "a.log"])
+                .unwrap()
+                .count_total
+        );
+        let err = build(&["--count-total", "error", "a.log"]).unwrap_err();
+        assert_eq!(err, "--count-total requires -c");
+    }
+
+    #[test]
+    fn pre_and_pre_glob() {
+        let config = build(&["--pre=pdftotext", "--pre-glob=*.pdf", "error", "a.pdf"]).unwrap();
+        assert_eq!(config.pre.as_deref(), Some("pdftotext"));
+        assert_eq!(config.pre_glob[0].as_str(), "*.pdf");
+
+        let err = build(&["--pre-glob=*.pdf", "error", "a.pdf"]).unwrap_err();
+        assert_eq!(err, "--pre-glob requires --pre");
+        let err = build(&["--pre=cat", "--in-place", "--replace=x", "error", "a"]).unwrap_err();
+        assert_eq!(err, "--in-place cannot be used with --pre");
+        let err = build(&["--pre=cat", "--watch", "error", "a"]).unwrap_err();
+        assert_eq!(err, "--watch cannot be used with --pre");
+    }
+
+    #[test]
+    fn watch_takes_one_file() {
+        let config = build(&["--watch", "error", "app.log"]).unwrap();
+        assert_eq!(config.watch, Some(Duration::from_millis(250)));
+        assert!(config.line_buffered);
+        let config = build(&["--watch", "--watch-interval=40", "error", "app.log"]).unwrap();
+        assert_eq!(config.watch, Some(Duration::from_millis(40)));
+
+        for args in [
+            &["--watch", "error", "a", "b"][..],
+            &["--watch", "error", "-"],
+        ] {
+            assert_eq!(build(args).unwrap_err(), "--watch takes exactly one file");
+        }
+        for conflict in ["-r", "-c", "-l", "--search-zip", "--progress"] {
+            let err = build(&["--watch", conflict, "error", "app.log"]).unwrap_err();
+            assert_eq!(err, format!("--watch cannot be used with {conflict}"));
+        }
+        let err = build(&["--watch-interval=40", "error", "app.log"]).unwrap_err();
+        assert_eq!(err, "--watch-interval requires --watch");
+    }
+
+    #[test]
+    fn column_implies_line_numbers() {
+        let config = build(&["--column", "error", "a.log"]).unwrap();
+        assert!(config.column && config.line_number);
+        assert!(!config.byte_column);
+        let config = build(&["--byte-column", "error", "a.log"]).unwrap();
+        assert!(config.column && config.byte_column && config.line_number);
+
+        let err = build(&["--column", "-c", "error", "a.log"]).unwrap_err();
+        assert_eq!(err, "--column cannot be used with -c");
+        let err = build(&["--byte-column", "--json", "error", "a.log"]).unwrap_err();
+        assert_eq!(err, "--byte-column cannot be used with --json");
+    }
+
+    #[test]
+    fn vimgrep_implies_prefixes() {
+        let config = build(&["--vimgrep", "error", "a.log"]).unwrap();
+        assert!(config.vimgrep);
+        assert!(config.with_filename);
+        assert!(config.line_number);
+
+        let err = build(&["--vimgrep", "-o", "error", "a.log"]).unwrap_err();
+        assert_eq!(err, "--vimgrep cannot be used with -o");
+    }
+
+    #[test]
+    fn heading_flags() {
+        assert_eq!(build(&["error", "a.log"]).unwrap().heading, None);
+        assert_eq!(
+            build(&["--heading", "error", "a.log"]).unwrap().heading,
+            Some(true)
+        );
+        assert_eq!(
+            build(&["--heading", "--no-heading", "error", "a.log"])
+                .unwrap()
+                .heading,
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn null_data_conflicts() {
+        assert!(build(&["-z", "error", "-"]).unwrap().null_data);
+        let err = build(&["-z", "--json", "error", "-"]).unwrap_err();
+        assert_eq!(err, "-z cannot be used with --json");
+    }
+
+    #[test]
+    fn quote_styles() {
+        assert_eq!(build(&["error", "-"]).unwrap().quote, Quoting::None);
+        let config = build(&["--quote=shell", "-l", "error", "-"]).unwrap();
+        assert_eq!(config.quote, Quoting::Shell);
+        assert!(build(&["--quote=none", "-Z", "error", "-"]).is_ok());
+
+        let err = build(&["--quote=shell", "-Z", "error", "-"]).unwrap_err();
+        assert_eq!(err, "--quote=shell cannot be used with -Z");
+        let err = build(&["--quote=c", "error", "-"]).unwrap_err();
+        assert_eq!(err, "Invalid value 'c' for '--quote'");
+    }
+
+    #[test]
+    fn encoding_names() {
+        assert_eq!(build(&["error", "a.log"]).unwrap().encoding, None);
+        assert_eq!(
+            build(&["--encoding=UTF-16BE", "error", "a.log"])
+                .unwrap()
+                .encoding,
+            Some(Encoding::Utf16Be)
+        );
+        let err = build(&["--encoding", "koi8-r", "error", "a.log"]).unwrap_err();
+        assert_eq!(err, "Unknown encoding 'koi8-r'");
+    }
+
+    #[test]
+    fn globs_and_types() {
+        let config = build(&[
+            "--include=*.rs",
+            "-t",
+            "toml",
+            "--type-not=md",
+            "--exclude-dir",
+            "target",
+            "error",
+            ".",
+        ])
+        .unwrap();
+        fn patterns(globs: &[Glob]) -> Vec<&str> {
+            globs.iter().map(Glob::as_str).collect()
+        }
+        assert_eq!(patterns(&config.include), vec!["*.rs", "*.toml"]);
+        assert_eq!(patterns(&config.exclude), vec!["*.md", "*.markdown"]);
+        assert_eq!(patterns(&config.exclude_dir), vec!["target"]);
+
+        let err = build(&["--type=rst", "error", "."]).unwrap_err();
+        assert_eq!(err, "Unknown file type 'rst'; did you mean 'rust'?");
+        assert!(build(&["--include=[a", "error", "."]).is_err());
+        assert!(build(&["--type-list"]).unwrap().type_list);
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        assert!(build(&["error"]).is_err());
+        assert!(build(&["--label"]).is_err());
+        assert!(build(&["--bogus", "error", "a.log"]).is_err());
+        assert!(build(&["-l", "-L", "error", "a.log"]).is_err());
+        assert_eq!(
+            build(&["--trim=yes", "error", "a.log"]).unwrap_err(),
+            "Option '--trim' does not take a value"
+        );
+    }
+
+    #[test]
+    fn every_option_of_the_table_is_parsed() {
+        for option in options::OPTIONS {
+            for name in option.names() {
+                let args = match option.value {
+                    Value::Required(_) => vec![name, "1", "error", "a.log"],
+                    _ => vec![name, "error", "a.log"],
+                };
+                if let Err(err) = build(&args) {
+                    assert!(!err.starts_with("Unknown option"), "{name}: {err}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn generate_completions_takes_a_shell_instead_of_a_query() {
+        let config = build(&["--generate-completions", "fish"]).unwrap();
+        assert_eq!(config.completions, Some(Shell::Fish));
+        assert_eq!(config.query, "");
+        assert_eq!(build(&["x", "a.log"]).unwrap().completions, None);
+        assert_eq!(
+            build(&["--generate-completions=tcsh"]).unwrap_err(),
+            "Invalid value 'tcsh' for '--generate-completions'"
+        );
+    }
+
+    #[test]
+    fn double_dash_ends_options() {
+        let config = build(&["--", "-n", "a.log"]).unwrap();
+        assert_eq!(config.query, "-n");
+        assert!(!config.line_number);
+    }
+}
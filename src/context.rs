@@ -0,0 +1,138 @@
+//! The context of `--after-context-until`, which follows each selected
+//! line up to a stop pattern rather than for a number of lines, so that
+//! the continuation lines of a multi-line log record are printed with it.
+
+use crate::line_matches;
+
+/// Which lines of an input are printed as context after selected lines,
+/// decided a line at a time.
+///
+/// A selected line opens a window, which takes in the lines after it until
+/// one matches the stop pattern. That line closes the window, and is only
+/// part of it with `include_stop`. A line selected inside a window is
+/// printed as a match and opens the window again, even if it also matches
+/// the stop pattern.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::context::ContextUntil;
+///
+/// let mut until = ContextUntil::new("2024-", false);
+/// let lines = [
+///     ("2024-01-01 error: failed", true),
+///     ("  at main.rs:3", false),
+///     ("2024-01-02 ok", false),
+///     ("  at main.rs:9", false),
+/// ];
+/// let context: Vec<bool> = lines
+///     .iter()
+///     .map(|(line, selected)| until.step(line, *selected, false))
+///     .collect();
+/// assert_eq!(context, [false, true, false, false]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContextUntil {
+    stop: String,
+    include_stop: bool,
+    open: bool,
+}
+
+impl ContextUntil {
+    /// Prints the lines after selected ones until one matching `stop`,
+    /// which is printed too if `include_stop`, with no window open yet.
+    pub fn new(stop: &str, include_stop: bool) -> ContextUntil {
+        ContextUntil {
+            stop: stop.to_string(),
+            include_stop,
+            open: false,
+        }
+    }
+
+    /// Whether the lines coming next are printed unless they close the
+    /// window.
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Moves on to `line`, the next line of the input, returning whether it
+    /// is printed as context: not if it is `selected`, which is printed as
+    /// such, and otherwise if a window is open, unless it is the line
+    /// closing it without `include_stop`. The stop pattern is matched
+    /// ignoring case if `ignore_case`.
+    pub fn step(&mut self, line: &str, selected: bool, ignore_case: bool) -> bool {
+        if selected {
+            self.open = true;
+            return false;
+        }
+        if !self.open {
+            return false;
+        }
+        if line_matches(&self.stop, line, ignore_case) {
+            self.open = false;
+            return self.include_stop;
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Steps through `lines`, each selected if it contains `*`, returning
+    /// what each of them is printed as: `M` for a match, `C` for context,
+    /// and `-` for nothing.
+    fn printed(until: &mut ContextUntil, lines: &[&str]) -> String {
+        lines
+            .iter()
+            .map(|line| {
+                let selected = line.contains('*');
+                match (selected, until.step(line, selected, false)) {
+                    (true, _) => 'M',
+                    (false, true) => 'C',
+                    (false, false) => '-',
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn windows_only_open_at_selected_lines() {
+        let mut until = ContextUntil::new("stop", false);
+        assert_eq!(printed(&mut until, &["a", "stop", "b"]), "---");
+        assert_eq!(printed(&mut until, &["*", "a", "b"]), "MCC");
+    }
+
+    #[test]
+    fn the_stop_line_closes_the_window_and_is_printed_on_request() {
+        let lines = ["*", "a", "stop", "b", "*", "stop", "stop"];
+        assert_eq!(
+            printed(&mut ContextUntil::new("stop", false), &lines),
+            "MC--M--"
+        );
+        assert_eq!(
+            printed(&mut ContextUntil::new("stop", true), &lines),
+            "MCC-MC-"
+        );
+    }
+
+    #[test]
+    fn a_match_inside_a_window_restarts_it() {
+        let mut until = ContextUntil::new("stop", false);
+        assert_eq!(
+            printed(&mut until, &["*", "a", "*", "b", "stop", "c"]),
+            "MCMC--"
+        );
+        // A selected line matching the stop pattern still opens a window.
+        assert_eq!(printed(&mut until, &["* stop", "a", "stop"]), "MC-");
+    }
+
+    #[test]
+    fn the_stop_pattern_follows_the_case_setting() {
+        let mut until = ContextUntil::new("stop", false);
+        until.step("*", true, true);
+        assert!(until.step("STOP", false, false));
+        assert!(!until.step("STOP", false, true));
+        assert!(!until.step("a", false, true));
+    }
+}
@@ -0,0 +1,155 @@
+//! The CSV and TSV tables printed by `--output-format`, for importing
+//! results into spreadsheets.
+//!
+//! Each selected line is a row of the fields in [`HEADER`]: the path of its
+//! input, its 1-based line number, the 1-based column, in characters, of its
+//! first match (empty when it has none, as with `-v`), and its text. The
+//! text of a line that is not valid UTF-8 has each invalid sequence replaced
+//! by U+FFFD.
+//!
+//! CSV fields are quoted as RFC 4180 describes when they contain a comma, a
+//! quote, or a line break, and also when they start or end with whitespace,
+//! which some readers would otherwise trim. TSV cannot quote, so tabs, line
+//! breaks, and backslashes are escaped as `\t`, `\n`, `\r`, and `\\`.
+
+use std::borrow::Cow;
+
+/// The names of the fields of each row, printed first unless `--no-header`
+/// is given.
+pub const HEADER: [&str; 4] = ["path", "line_number", "column", "text"];
+
+/// A table format, as chosen with `--output-format`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Comma-separated values.
+    Csv,
+    /// Tab-separated values.
+    Tsv,
+}
+
+impl Format {
+    /// Parses an `--output-format` value: `csv` or `tsv`.
+    ///
+    /// # Examples
+    /// ```
+    /// use minigrep_cli_tool::csv::Format;
+    ///
+    /// assert_eq!(Format::parse("tsv"), Some(Format::Tsv));
+    /// assert_eq!(Format::parse("xlsx"), None);
+    /// ```
+    pub fn parse(value: &str) -> Option<Format> {
+        match value {
+            "csv" => Some(Format::Csv),
+            "tsv" => Some(Format::Tsv),
+            _ => None,
+        }
+    }
+
+    /// Returns `value` quoted or escaped as a field of this format needs.
+    ///
+    /// # Examples
+    /// ```
+    /// use minigrep_cli_tool::csv::Format;
+    ///
+    /// assert_eq!(Format::Csv.field("plain"), "plain");
+    /// assert_eq!(Format::Csv.field("a, \"b\""), r#""a, ""b""""#);
+    /// assert_eq!(Format::Tsv.field("a\tb"), r"a\tb");
+    /// ```
+    pub fn field(self, value: &str) -> Cow<'_, str> {
+        match self {
+            Format::Csv => {
+                let needs_quotes = value.contains([',', '"', '\n', '\r'])
+                    || value.starts_with(char::is_whitespace)
+                    || value.ends_with(char::is_whitespace);
+                if needs_quotes {
+                    Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+                } else {
+                    Cow::Borrowed(value)
+                }
+            }
+            Format::Tsv => {
+                if !value.contains(['\t', '\n', '\r', '\\']) {
+                    return Cow::Borrowed(value);
+                }
+                let mut escaped = String::with_capacity(value.len() + 2);
+                for c in value.chars() {
+                    match c {
+                        '\t' => escaped.push_str("\\t"),
+                        '\n' => escaped.push_str("\\n"),
+                        '\r' => escaped.push_str("\\r"),
+                        '\\' => escaped.push_str("\\\\"),
+                        c => escaped.push(c),
+                    }
+                }
+                Cow::Owned(escaped)
+            }
+        }
+    }
+
+    /// Builds a row of `fields`, each quoted or escaped as needed, without
+    /// a line terminator.
+    ///
+    /// # Examples
+    /// ```
+    /// use minigrep_cli_tool::csv::{Format, HEADER};
+    ///
+    /// assert_eq!(Format::Csv.record(&HEADER), "path,line_number,column,text");
+    /// assert_eq!(
+    ///     Format::Tsv.record(&["poem.txt", "2", "3", "a rust"]),
+    ///     "poem.txt\t2\t3\ta rust"
+    /// );
+    /// ```
+    pub fn record(self, fields: &[&str]) -> String {
+        let delimiter = match self {
+            Format::Csv => ",",
+            Format::Tsv => "\t",
+        };
+        fields
+            .iter()
+            .map(|field| self.field(field))
+            .collect::<Vec<_>>()
+            .join(delimiter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_quotes_delimiters_quotes_and_line_breaks() {
+        assert_eq!(Format::Csv.field(""), "");
+        assert_eq!(Format::Csv.field("a,b"), r#""a,b""#);
+        assert_eq!(Format::Csv.field(r#"say "hi""#), r#""say ""hi""""#);
+        assert_eq!(Format::Csv.field(r#"""#), r#""""""#);
+        assert_eq!(Format::Csv.field("one\ntwo"), "\"one\ntwo\"");
+        assert_eq!(Format::Csv.field("cr\r"), "\"cr\r\"");
+        assert_eq!(Format::Csv.field("tab\tinside"), "tab\tinside");
+        assert_eq!(Format::Csv.field("it's"), "it's");
+    }
+
+    #[test]
+    fn csv_quotes_surrounding_whitespace() {
+        assert_eq!(Format::Csv.field("trailing "), r#""trailing ""#);
+        assert_eq!(Format::Csv.field("  indented"), r#""  indented""#);
+        assert_eq!(Format::Csv.field("\ttabbed"), "\"\ttabbed\"");
+        assert_eq!(Format::Csv.field("inner space"), "inner space");
+    }
+
+    #[test]
+    fn tsv_escapes_what_it_cannot_quote() {
+        assert_eq!(Format::Tsv.field("a,\"b\" "), "a,\"b\" ");
+        assert_eq!(Format::Tsv.field("a\tb\nc\rd"), r"a\tb\nc\rd");
+        assert_eq!(Format::Tsv.field(r"C:\logs"), r"C:\\logs");
+    }
+
+    #[test]
+    fn records_join_quoted_fields() {
+        assert_eq!(
+            Format::Csv.record(&["a.csv", "1", "", "x, \"y\" "]),
+            r#"a.csv,1,,"x, ""y"" ""#
+        );
+        assert_eq!(Format::Tsv.record(&["a", "", "b"]), "a\t\tb");
+        assert_eq!(Format::Csv.record(&[]), "");
+    }
+}
@@ -0,0 +1,256 @@
+//! Opening files through the decoder of the compression format they are
+//! in, so that compressed files are searched like any other.
+//!
+//! Each format has a feature of its own, `gzip`, `bzip2`, and `xz`, which
+//! the `compress` feature enables together, each decoding with a crate of
+//! its own: `flate2`, `bzip2`, and `xz2`. A file in a format whose feature
+//! is disabled, or in one not supported at all such as zstd, is read as it
+//! is.
+
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, Read},
+    path::Path,
+};
+
+/// A compression format files can be decompressed from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    #[cfg(feature = "gzip")]
+    Gzip,
+    #[cfg(feature = "bzip2")]
+    Bzip2,
+    #[cfg(feature = "xz")]
+    Xz,
+}
+
+impl Format {
+    /// The formats enabled in this build.
+    pub const ALL: &[Format] = &[
+        #[cfg(feature = "gzip")]
+        Format::Gzip,
+        #[cfg(feature = "bzip2")]
+        Format::Bzip2,
+        #[cfg(feature = "xz")]
+        Format::Xz,
+    ];
+
+    /// The extension of file names in this format, without its `.`.
+    pub fn extension(self) -> &'static str {
+        match self {
+            #[cfg(feature = "gzip")]
+            Format::Gzip => "gz",
+            #[cfg(feature = "bzip2")]
+            Format::Bzip2 => "bz2",
+            #[cfg(feature = "xz")]
+            Format::Xz => "xz",
+        }
+    }
+
+    /// The bytes files in this format start with, before the digit giving
+    /// the block size for bzip2.
+    pub fn magic(self) -> &'static [u8] {
+        match self {
+            #[cfg(feature = "gzip")]
+            Format::Gzip => &[0x1f, 0x8b],
+            #[cfg(feature = "bzip2")]
+            Format::Bzip2 => b"BZh",
+            #[cfg(feature = "xz")]
+            Format::Xz => &[0xfd, b'7', b'z', b'X', b'Z', 0x00],
+        }
+    }
+
+    /// The name of this format in the errors about corrupt data.
+    #[cfg(any(feature = "gzip", feature = "bzip2", feature = "xz"))]
+    fn name(self) -> &'static str {
+        match self {
+            #[cfg(feature = "gzip")]
+            Format::Gzip => "gzip",
+            #[cfg(feature = "bzip2")]
+            Format::Bzip2 => "bzip2",
+            #[cfg(feature = "xz")]
+            Format::Xz => "xz",
+        }
+    }
+
+    /// Detects the format of the file at `path`, which starts with `start`:
+    /// from the extension of its name or, failing that, from its first bytes.
+    ///
+    /// # Examples
+    /// ```
+    /// use minigrep_cli_tool::decompress::Format;
+    /// use std::path::Path;
+    ///
+    /// # #[cfg(all(feature = "gzip", feature = "bzip2"))] {
+    /// assert_eq!(Format::detect(Path::new("app.log.1.gz"), b""), Some(Format::Gzip));
+    /// assert_eq!(Format::detect(Path::new("app.log.2"), b"BZh9"), Some(Format::Bzip2));
+    /// # }
+    /// assert_eq!(Format::detect(Path::new("app.log.zst"), b"\x28\xb5\x2f\xfd"), None);
+    /// ```
+    pub fn detect(path: &Path, start: &[u8]) -> Option<Format> {
+        let extension = path.extension();
+        Format::ALL
+            .iter()
+            .find(|format| extension.is_some_and(|extension| extension == format.extension()))
+            .or_else(|| {
+                Format::ALL
+                    .iter()
+                    .find(|format| start.starts_with(format.magic()))
+            })
+            .copied()
+    }
+
+    /// Wraps `reader` in a decoder for this format.
+    ///
+    /// Several streams one after another, as `cat a.gz b.gz` makes, are
+    /// read as one. Reading returns an error of kind
+    /// [`InvalidData`](io::ErrorKind::InvalidData) if the data is corrupt,
+    /// as `corrupt gzip data: ...`, and of kind
+    /// [`UnexpectedEof`](io::ErrorKind::UnexpectedEof) if it stops early.
+    #[cfg_attr(
+        not(any(feature = "gzip", feature = "bzip2", feature = "xz")),
+        allow(unused_variables)
+    )]
+    pub fn decoder<'a>(self, reader: impl BufRead + 'a) -> Box<dyn Read + 'a> {
+        match self {
+            #[cfg(feature = "gzip")]
+            Format::Gzip => Box::new(Decoder {
+                inner: flate2::bufread::MultiGzDecoder::new(reader),
+                format: self,
+            }),
+            #[cfg(feature = "bzip2")]
+            Format::Bzip2 => Box::new(Decoder {
+                inner: bzip2::bufread::MultiBzDecoder::new(reader),
+                format: self,
+            }),
+            #[cfg(feature = "xz")]
+            Format::Xz => Box::new(Decoder {
+                inner: xz2::bufread::XzDecoder::new_multi_decoder(reader),
+                format: self,
+            }),
+        }
+    }
+}
+
+/// A decoder of one of the crates, its errors reported as [`Format::decoder`]
+/// describes.
+#[cfg(any(feature = "gzip", feature = "bzip2", feature = "xz"))]
+struct Decoder<R> {
+    inner: R,
+    format: Format,
+}
+
+#[cfg(any(feature = "gzip", feature = "bzip2", feature = "xz"))]
+impl<R: Read> Read for Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf).map_err(|error| match error.kind() {
+            io::ErrorKind::UnexpectedEof => truncated(),
+            io::ErrorKind::InvalidData | io::ErrorKind::InvalidInput => io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("corrupt {} data: {error}", self.format.name()),
+            ),
+            _ => error,
+        })
+    }
+}
+
+/// Opens the file at `path` for reading, decompressed if [`Format::detect`]
+/// finds it is in a format enabled in this build.
+///
+/// # Errors
+/// Returns an error if the file cannot be opened or its first bytes read.
+/// Once it is open, reading returns an error if the file is compressed but
+/// corrupt.
+pub fn open_decoded(path: &Path) -> io::Result<Box<dyn BufRead>> {
+    decoded(path, BufReader::new(File::open(path)?))
+}
+
+/// Wraps `reader`, the contents of the file at `path`, in a decoder if
+/// [`Format::detect`] finds it is in a format enabled in this build, as
+/// [`open_decoded`] does for a file it opens itself.
+///
+/// # Errors
+/// Returns an error if the first bytes of `reader` cannot be read.
+pub fn decoded<'a>(
+    path: &Path,
+    mut reader: impl BufRead + 'a,
+) -> io::Result<Box<dyn BufRead + 'a>> {
+    Ok(match Format::detect(path, reader.fill_buf()?) {
+        Some(format) => Box::new(BufReader::new(format.decoder(reader))),
+        None => Box::new(reader),
+    })
+}
+
+/// Returns an error for compressed data that stops before its end.
+#[cfg(any(feature = "gzip", feature = "bzip2", feature = "xz", feature = "zip"))]
+pub(crate) fn truncated() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::UnexpectedEof,
+        "unexpected end of compressed data",
+    )
+}
+
+/// The CRC-32 of every single byte, for [`crc32`].
+#[cfg(feature = "zip")]
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                0xedb8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// Continues the CRC-32 `crc` of some bytes with `bytes`, starting from 0,
+/// as zip computes it.
+#[cfg(feature = "zip")]
+pub(crate) fn crc32(crc: u32, bytes: &[u8]) -> u32 {
+    let mut crc = !crc;
+    for &byte in bytes {
+        crc = CRC_TABLE[((crc ^ u32::from(byte)) & 0xff) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "zip")]
+    #[test]
+    fn crc32_of_known_strings() {
+        assert_eq!(crc32(0, b""), 0);
+        assert_eq!(crc32(0, b"123456789"), 0xcbf4_3926);
+        assert_eq!(crc32(crc32(0, b"1234"), b"56789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn extensions_take_precedence_over_magic_bytes() {
+        for format in Format::ALL {
+            let name = format!("log.{}", format.extension());
+            assert_eq!(Format::detect(Path::new(&name), b""), Some(*format));
+            assert_eq!(
+                Format::detect(Path::new("log"), format.magic()),
+                Some(*format)
+            );
+        }
+        #[cfg(all(feature = "gzip", feature = "xz"))]
+        assert_eq!(
+            Format::detect(Path::new("log.xz"), Format::Gzip.magic()),
+            Some(Format::Xz)
+        );
+        assert_eq!(Format::detect(Path::new("log.zst"), b"plain"), None);
+        assert_eq!(Format::detect(Path::new("log"), b""), None);
+    }
+}
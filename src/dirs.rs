@@ -0,0 +1,160 @@
+//! Adding up the counts of `--count-by-file` by directory, for
+//! `--group-by-dir`, to show where in a tree the matches are.
+
+use std::path::{Path, PathBuf};
+
+/// The count of a directory, and those of the files it was made of.
+///
+/// - `dir`: The directory, as the paths searched are printed.
+/// - `count`: The sum of the counts of its files.
+/// - `files`: The path and count of each of its files, the path relative
+///   to `dir`, the largest counts first and equal ones by path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirCount {
+    pub dir: PathBuf,
+    pub count: u64,
+    pub files: Vec<(PathBuf, u64)>,
+}
+
+/// Groups `counts`, each the path of a file and its count, by the directory
+/// each file is in relative to the first of `roots` its path starts with or,
+/// if `depth` is given, by the directory no more than `depth` levels below
+/// that root that it is in. A file outside every root is grouped by the
+/// directory its path names.
+///
+/// # Returns
+/// The directories with the largest counts first, and equal ones by path.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::dirs::group_by_dir;
+/// use std::path::{Path, PathBuf};
+///
+/// let counts = [("src/a/x.rs", 2), ("src/a/b/y.rs", 3), ("src/z.rs", 1)]
+///     .map(|(path, count)| (PathBuf::from(path), count));
+/// let roots = [PathBuf::from("src")];
+/// let dirs = group_by_dir(counts.to_vec(), &roots, None);
+/// assert_eq!(dirs[0].dir, Path::new("src/a/b"));
+/// let dirs = group_by_dir(counts.to_vec(), &roots, Some(1));
+/// assert_eq!((dirs[0].dir.as_path(), dirs[0].count), (Path::new("src/a"), 5));
+/// assert_eq!(dirs[0].files[0], (PathBuf::from("b/y.rs"), 3));
+/// ```
+pub fn group_by_dir(
+    counts: Vec<(PathBuf, u64)>,
+    roots: &[PathBuf],
+    depth: Option<usize>,
+) -> Vec<DirCount> {
+    let mut dirs: Vec<DirCount> = Vec::new();
+    for (path, count) in counts {
+        let dir = dir_of(&path, roots, depth);
+        let file = path.strip_prefix(&dir).unwrap_or(&path).to_path_buf();
+        match dirs.iter_mut().find(|group| group.dir == dir) {
+            Some(group) => {
+                group.count += count;
+                group.files.push((file, count));
+            }
+            None => dirs.push(DirCount {
+                dir,
+                count,
+                files: vec![(file, count)],
+            }),
+        }
+    }
+    for group in &mut dirs {
+        group
+            .files
+            .sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    }
+    dirs.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.dir.cmp(&b.dir)));
+    dirs
+}
+
+/// The directory the file at `path` is counted in, as [`group_by_dir`]
+/// groups them.
+fn dir_of(path: &Path, roots: &[PathBuf], depth: Option<usize>) -> PathBuf {
+    let parent = path.parent().unwrap_or(Path::new(""));
+    let Some(root) = roots.iter().find(|root| parent.starts_with(root)) else {
+        return parent.to_path_buf();
+    };
+    let relative = parent.strip_prefix(root).unwrap_or(Path::new(""));
+    match depth {
+        Some(depth) => {
+            let mut dir = root.clone();
+            dir.extend(relative.components().take(depth));
+            dir
+        }
+        None => parent.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts(counts: &[(&str, u64)]) -> Vec<(PathBuf, u64)> {
+        counts
+            .iter()
+            .map(|(path, count)| (PathBuf::from(path), *count))
+            .collect()
+    }
+
+    fn summary(dirs: &[DirCount]) -> Vec<(String, u64, Vec<String>)> {
+        dirs.iter()
+            .map(|group| {
+                let files = group.files.iter();
+                let files = files.map(|(file, count)| format!("{count} {}", file.display()));
+                (
+                    group.dir.display().to_string(),
+                    group.count,
+                    files.collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn files_are_grouped_by_their_directory_largest_first() {
+        let given = counts(&[
+            ("tree/a/one.rs", 2),
+            ("tree/b/two.rs", 4),
+            ("tree/a/three.rs", 3),
+            ("tree/top.rs", 1),
+        ]);
+        let dirs = group_by_dir(given, &[PathBuf::from("tree")], None);
+        assert_eq!(
+            summary(&dirs),
+            [
+                (
+                    String::from("tree/a"),
+                    5,
+                    vec![String::from("3 three.rs"), String::from("2 one.rs")]
+                ),
+                (String::from("tree/b"), 4, vec![String::from("4 two.rs")]),
+                (String::from("tree"), 1, vec![String::from("1 top.rs")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_depth_rolls_directories_up_below_the_root() {
+        let given = counts(&[
+            ("tree/a/x/one.rs", 2),
+            ("tree/a/y/two.rs", 2),
+            ("tree/b/three.rs", 5),
+            ("tree/top.rs", 1),
+            ("other/four.rs", 1),
+        ]);
+        let roots = [PathBuf::from("tree"), PathBuf::from("other/four.rs")];
+        let dirs = group_by_dir(given.clone(), &roots, Some(0));
+        assert_eq!(
+            summary(&dirs)
+                .iter()
+                .map(|(dir, count, _)| (dir.as_str(), *count))
+                .collect::<Vec<_>>(),
+            [("tree", 10), ("other", 1)]
+        );
+        let dirs = group_by_dir(given, &roots, Some(1));
+        assert_eq!(summary(&dirs)[1].0, "tree/a");
+        assert_eq!(summary(&dirs)[1].2, ["2 x/one.rs", "2 y/two.rs"]);
+    }
+}
@@ -0,0 +1,218 @@
+//! Rewriting files with their matches replaced, as done by `--in-place`.
+
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process,
+};
+
+use crate::{match_ranges, replace::replace_ranges};
+
+/// How [`replace_in_file`] finds and replaces matches.
+///
+/// - `query`: The substring to replace.
+/// - `ignore_case`: If `true`, occurrences are found ignoring case.
+/// - `template`: The replacement, where `$0` stands for the matched text.
+/// - `backup`: If `true`, the original file is kept as `<path>.bak`.
+#[derive(Debug, Clone)]
+pub struct EditOptions<'a> {
+    pub query: &'a str,
+    pub ignore_case: bool,
+    pub template: &'a str,
+    pub backup: bool,
+}
+
+/// What [`replace_in_file`] found in a file.
+///
+/// - `lines_scanned`: The number of lines in the file.
+/// - `matched_lines`: Lines containing at least one occurrence.
+/// - `replacements`: Occurrences replaced.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Edited {
+    pub lines_scanned: u64,
+    pub matched_lines: u64,
+    pub replacements: u64,
+}
+
+/// Replaces every occurrence of the query in the file at `path`.
+///
+/// Line endings and everything other than the occurrences are preserved.
+/// The new contents are written to a temporary file in the same directory,
+/// which is given the original's permissions, synced to disk, and then
+/// renamed over it, so the original is never left truncated, even by a
+/// crash. A symbolic link is followed: the file it points to is the one
+/// rewritten and backed up, and the link is left as it is. A file without
+/// occurrences is not rewritten, and no backup is made of it.
+///
+/// # Returns
+/// How many lines were scanned and matched, and how many replacements made.
+///
+/// # Errors
+/// Returns any error reading, backing up, or rewriting the file.
+pub fn replace_in_file(path: &Path, options: &EditOptions) -> io::Result<Edited> {
+    let path = &fs::canonicalize(path)?;
+    let contents = fs::read_to_string(path)?;
+    let mut edited = String::with_capacity(contents.len());
+    let mut summary = Edited::default();
+    for line in contents.split_inclusive('\n') {
+        let text = line.strip_suffix('\n').unwrap_or(line);
+        let ranges = match_ranges(options.query, text, options.ignore_case);
+        summary.lines_scanned += 1;
+        if !ranges.is_empty() {
+            summary.matched_lines += 1;
+            summary.replacements += ranges.len() as u64;
+        }
+        edited.push_str(&replace_ranges(text, &ranges, options.template).0);
+        edited.push_str(&line[text.len()..]);
+    }
+    if summary.replacements == 0 {
+        return Ok(summary);
+    }
+
+    if options.backup {
+        fs::copy(path, backup_path(path))?;
+    }
+    let temp_path = sibling_path(path, &format!(".minigrep-{}.tmp", process::id()));
+    let written = File::create(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(edited.as_bytes())?;
+            file.set_permissions(fs::metadata(path)?.permissions())?;
+            file.sync_all()
+        })
+        .and_then(|()| fs::rename(&temp_path, path));
+    if written.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    written.map(|()| summary)
+}
+
+/// Returns where the backup of the file at `path` is kept: `<path>.bak`.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::edit::backup_path;
+/// use std::path::{Path, PathBuf};
+///
+/// assert_eq!(backup_path(Path::new("notes/file.txt")), PathBuf::from("notes/file.txt.bak"));
+/// ```
+pub fn backup_path(path: &Path) -> PathBuf {
+    sibling_path(path, ".bak")
+}
+
+/// Returns `path` with `suffix` appended to its file name.
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates an empty directory for a test, removing any earlier copy.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("minigrep-edit-{name}"));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn options(backup: bool) -> EditOptions<'static> {
+        EditOptions {
+            query: "old",
+            ignore_case: true,
+            template: "new",
+            backup,
+        }
+    }
+
+    #[test]
+    fn replaces_and_keeps_a_backup() {
+        let dir = temp_dir("backup");
+        let path = dir.join("file.txt");
+        fs::write(&path, "old OLD\r\nkeep\nold").unwrap();
+
+        assert_eq!(
+            replace_in_file(&path, &options(true)).unwrap(),
+            Edited {
+                lines_scanned: 3,
+                matched_lines: 2,
+                replacements: 3,
+            }
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new new\r\nkeep\nnew");
+        assert_eq!(
+            fs::read_to_string(dir.join("file.txt.bak")).unwrap(),
+            "old OLD\r\nkeep\nold"
+        );
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn no_backup_and_untouched_files() {
+        let dir = temp_dir("no-backup");
+        let path = dir.join("file.txt");
+        fs::write(&path, "old\n").unwrap();
+        assert_eq!(
+            replace_in_file(&path, &options(false))
+                .unwrap()
+                .replacements,
+            1
+        );
+        assert!(!dir.join("file.txt.bak").exists());
+
+        let unchanged = dir.join("unchanged.txt");
+        fs::write(&unchanged, "nothing here\n").unwrap();
+        let edited = replace_in_file(&unchanged, &options(true)).unwrap();
+        assert_eq!(edited.lines_scanned, 1);
+        assert_eq!(edited.replacements, 0);
+        assert!(!dir.join("unchanged.txt.bak").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn preserves_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = temp_dir("permissions");
+        let path = dir.join("script.sh");
+        fs::write(&path, "echo old\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o750)).unwrap();
+
+        replace_in_file(&path, &options(false)).unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o750);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn edits_the_target_of_a_symlink() {
+        let dir = temp_dir("symlink");
+        let target = dir.join("target.txt");
+        let link = dir.join("link.txt");
+        fs::write(&target, "old\n").unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        replace_in_file(&link, &options(true)).unwrap();
+        assert!(
+            fs::symlink_metadata(&link)
+                .unwrap()
+                .file_type()
+                .is_symlink()
+        );
+        assert_eq!(fs::read_to_string(&target).unwrap(), "new\n");
+        assert_eq!(
+            fs::read_to_string(dir.join("target.txt.bak")).unwrap(),
+            "old\n"
+        );
+        assert!(!dir.join("link.txt.bak").exists());
+    }
+
+    #[test]
+    fn missing_file_is_an_error() {
+        let dir = temp_dir("missing");
+        assert!(replace_in_file(&dir.join("missing.txt"), &options(true)).is_err());
+    }
+}
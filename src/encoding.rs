@@ -0,0 +1,272 @@
+//! Transcoding inputs in other encodings to UTF-8 before they are searched.
+
+use std::io::{self, Read};
+
+/// A text encoding that inputs can be read in, as chosen with `--encoding`
+/// or detected from a byte order mark.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// UTF-8, which is passed through unchanged.
+    Utf8,
+    /// UTF-16, little-endian.
+    Utf16Le,
+    /// UTF-16, big-endian.
+    Utf16Be,
+    /// ISO-8859-1, where every byte is the code point of the same value.
+    Latin1,
+}
+
+impl Encoding {
+    /// Parses an `--encoding` value such as `utf-8`, `utf-16le`, `utf-16be`,
+    /// or `latin-1`, ignoring case and allowing the dash to be left out.
+    ///
+    /// # Examples
+    /// ```
+    /// use minigrep_cli_tool::encoding::Encoding;
+    ///
+    /// assert_eq!(Encoding::parse("UTF-16LE"), Some(Encoding::Utf16Le));
+    /// assert_eq!(Encoding::parse("iso-8859-1"), Some(Encoding::Latin1));
+    /// assert_eq!(Encoding::parse("ebcdic"), None);
+    /// ```
+    pub fn parse(name: &str) -> Option<Encoding> {
+        match name.to_ascii_lowercase().as_str() {
+            "utf-8" | "utf8" => Some(Encoding::Utf8),
+            "utf-16le" | "utf16le" => Some(Encoding::Utf16Le),
+            "utf-16be" | "utf16be" => Some(Encoding::Utf16Be),
+            "latin-1" | "latin1" | "iso-8859-1" => Some(Encoding::Latin1),
+            _ => None,
+        }
+    }
+
+    /// The byte order mark that may start text in this encoding, if any.
+    fn bom(self) -> &'static [u8] {
+        match self {
+            Encoding::Utf8 => b"\xef\xbb\xbf",
+            Encoding::Utf16Le => b"\xff\xfe",
+            Encoding::Utf16Be => b"\xfe\xff",
+            Encoding::Latin1 => b"",
+        }
+    }
+}
+
+/// Detects the encoding of `bytes` from the byte order mark they start with.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::encoding::{sniff_bom, Encoding};
+///
+/// assert_eq!(sniff_bom(b"\xff\xfer\0"), Some(Encoding::Utf16Le));
+/// assert_eq!(sniff_bom(b"rust"), None);
+/// ```
+pub fn sniff_bom(bytes: &[u8]) -> Option<Encoding> {
+    [Encoding::Utf8, Encoding::Utf16Le, Encoding::Utf16Be]
+        .into_iter()
+        .find(|encoding| bytes.starts_with(encoding.bom()))
+}
+
+/// How many bytes of input [`Decoder`] reads at a time.
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// Reads text from `inner`, yielding it transcoded to UTF-8.
+///
+/// Without an `encoding`, it is detected from a byte order mark, falling
+/// back to UTF-8. Any byte order mark of the encoding used is dropped. Input
+/// is transcoded a chunk at a time as it is read, never all at once.
+///
+/// UTF-8 input is passed through unchanged, invalid or not, so that reading
+/// it with [`io::read_to_string`] still reports invalid UTF-8 as an error.
+/// In UTF-16 input, unpaired surrogates and a dangling odd byte are replaced
+/// by U+FFFD.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::encoding::decode;
+/// use std::io;
+///
+/// let utf16 = b"\xff\xfer\0u\0s\0t\0".as_slice();
+/// assert_eq!(io::read_to_string(decode(utf16, None)).unwrap(), "rust");
+/// ```
+pub fn decode<R: Read>(inner: R, encoding: Option<Encoding>) -> Decoder<R> {
+    Decoder {
+        inner,
+        encoding,
+        bom_checked: false,
+        pending: Vec::new(),
+        decoded: Vec::new(),
+        position: 0,
+        eof: false,
+    }
+}
+
+/// The reader returned by [`decode`].
+pub struct Decoder<R> {
+    inner: R,
+    /// The encoding of the input, once known.
+    encoding: Option<Encoding>,
+    /// Whether a byte order mark has been looked for and dropped.
+    bom_checked: bool,
+    /// Input read but not yet decoded, such as half a UTF-16 code unit.
+    pending: Vec<u8>,
+    /// Decoded UTF-8 not yet handed out, starting at `position`.
+    decoded: Vec<u8>,
+    position: usize,
+    /// Whether `inner` has been read to the end.
+    eof: bool,
+}
+
+impl<R: Read> Decoder<R> {
+    /// Reads the next chunk of input and decodes as much of the pending
+    /// input as can be decoded.
+    fn fill(&mut self) -> io::Result<()> {
+        let start = self.pending.len();
+        self.pending.resize(start + CHUNK_SIZE, 0);
+        let read = match self.inner.read(&mut self.pending[start..]) {
+            Ok(read) => read,
+            Err(error) => {
+                self.pending.truncate(start);
+                return Err(error);
+            }
+        };
+        self.pending.truncate(start + read);
+        self.eof = read == 0;
+
+        if !self.bom_checked {
+            // The longest byte order mark is three bytes long.
+            if self.pending.len() < 3 && !self.eof {
+                return Ok(());
+            }
+            let encoding = *self
+                .encoding
+                .get_or_insert_with(|| sniff_bom(&self.pending).unwrap_or(Encoding::Utf8));
+            if self.pending.starts_with(encoding.bom()) {
+                self.pending.drain(..encoding.bom().len());
+            }
+            self.bom_checked = true;
+        }
+
+        self.decoded.clear();
+        self.position = 0;
+        match self.encoding.unwrap_or(Encoding::Utf8) {
+            Encoding::Utf8 => self.decoded.append(&mut self.pending),
+            Encoding::Latin1 => {
+                let text: String = self.pending.drain(..).map(char::from).collect();
+                self.decoded = text.into_bytes();
+            }
+            Encoding::Utf16Le => self.decode_utf16(u16::from_le_bytes),
+            Encoding::Utf16Be => self.decode_utf16(u16::from_be_bytes),
+        }
+        Ok(())
+    }
+
+    /// Decodes the complete UTF-16 code units of the pending input, keeping
+    /// back a trailing odd byte or high surrogate until more input arrives.
+    fn decode_utf16(&mut self, unit: fn([u8; 2]) -> u16) {
+        let mut units: Vec<u16> = self
+            .pending
+            .chunks_exact(2)
+            .map(|pair| unit([pair[0], pair[1]]))
+            .collect();
+        let mut kept = self.pending.len() % 2;
+        if !self.eof
+            && units
+                .last()
+                .is_some_and(|unit| (0xd800..0xdc00).contains(unit))
+        {
+            units.pop();
+            kept += 2;
+        }
+
+        let mut text: String = char::decode_utf16(units)
+            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect();
+        self.pending.drain(..self.pending.len() - kept);
+        if self.eof && !self.pending.is_empty() {
+            text.push(char::REPLACEMENT_CHARACTER);
+            self.pending.clear();
+        }
+        self.decoded = text.into_bytes();
+    }
+}
+
+impl<R: Read> Read for Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.decoded.len() && !self.eof {
+            self.fill()?;
+        }
+        let available = &self.decoded[self.position..];
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.position += len;
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Hands out its bytes one at a time, to split every sequence.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.0.split_first() {
+                Some((&byte, rest)) if !buf.is_empty() => {
+                    buf[0] = byte;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    fn utf16le(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+    }
+
+    fn read(bytes: &[u8], encoding: Option<Encoding>) -> String {
+        io::read_to_string(decode(Trickle(bytes), encoding)).unwrap()
+    }
+
+    #[test]
+    fn utf16_with_bom_in_either_byte_order() {
+        let text = "Rust 🦀 is fast\r\nünïcode\n";
+        let mut le = vec![0xff, 0xfe];
+        le.extend(utf16le(text));
+        assert_eq!(read(&le, None), text);
+
+        let mut be = vec![0xfe, 0xff];
+        be.extend(text.encode_utf16().flat_map(u16::to_be_bytes));
+        assert_eq!(read(&be, None), text);
+    }
+
+    #[test]
+    fn explicit_encoding_without_bom() {
+        assert_eq!(read(&utf16le("no bom"), Some(Encoding::Utf16Le)), "no bom");
+        assert_eq!(read(b"caf\xe9", Some(Encoding::Latin1)), "café");
+        assert_eq!(read(b"\xef\xbb\xbfplain", Some(Encoding::Utf8)), "plain");
+    }
+
+    #[test]
+    fn utf8_passes_through() {
+        assert_eq!(read(b"\xef\xbb\xbfwith bom", None), "with bom");
+        assert_eq!(read(b"ab", None), "ab");
+        assert_eq!(read(b"", None), "");
+
+        let invalid = io::read_to_string(decode(b"bad \xff".as_slice(), None));
+        assert_eq!(invalid.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn broken_utf16_is_replaced() {
+        let mut lone_surrogate = utf16le("a");
+        lone_surrogate.extend([0x00, 0xd8]);
+        lone_surrogate.extend(utf16le("b"));
+        assert_eq!(read(&lone_surrogate, Some(Encoding::Utf16Le)), "a\u{fffd}b");
+
+        let mut odd = utf16le("ab");
+        odd.push(b'c');
+        assert_eq!(read(&odd, Some(Encoding::Utf16Le)), "ab\u{fffd}");
+    }
+}
@@ -0,0 +1,265 @@
+//! The errors that keep an input from being searched, or searched whole,
+//! or that end a run, and their kinds, as warnings,
+//! [`search_in_file`](crate::search_in_file), and the `error` events of
+//! `--json` tell them apart.
+
+use std::{error::Error, fmt, io};
+
+use crate::walk::SkipReason;
+
+/// Why an input could not be searched, or only in part, naming it as the
+/// command line does: by its path, as `(standard input)`, or as
+/// `archive!member` for a member of an archive.
+///
+/// Every variant holds the name of the input as `path`, and the I/O error
+/// underneath as `source`, which [`Error::source`] returns as well.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::error::{ErrorKind, MinigrepError};
+/// use std::io;
+///
+/// let error = MinigrepError::new("app.log", io::Error::from(io::ErrorKind::NotFound));
+/// assert!(matches!(error, MinigrepError::NotFound { .. }));
+/// assert_eq!((error.path(), error.kind()), ("app.log", ErrorKind::NotFound));
+/// assert!(error.to_string().starts_with("app.log: "));
+/// ```
+#[derive(Debug)]
+pub enum MinigrepError {
+    /// The path does not exist.
+    NotFound { path: String, source: io::Error },
+    /// The path could not be read for lack of permission.
+    PermissionDenied { path: String, source: io::Error },
+    /// The path is a directory, but `-r` was not given.
+    IsDirectory { path: String, source: io::Error },
+    /// The input contains invalid UTF-8, which was replaced by U+FFFD.
+    InvalidUtf8 { path: String, source: io::Error },
+    /// The file is larger than `--max-filesize`, so it was skipped.
+    TooLarge { path: String, source: io::Error },
+    /// The input has a NUL byte in its first block, so it was skipped.
+    Binary { path: String, source: io::Error },
+    /// Any other failure to read or rewrite the input.
+    Io { path: String, source: io::Error },
+    /// Writing the output failed, which ends the run, with `path` naming
+    /// the output as `(output)`.
+    Output { path: String, source: io::Error },
+}
+
+impl MinigrepError {
+    /// Wraps the I/O error `source` on the input called `path`, in the
+    /// variant its kind stands for.
+    pub fn new(path: &str, source: io::Error) -> MinigrepError {
+        let path = path.to_string();
+        match source.kind() {
+            io::ErrorKind::NotFound => MinigrepError::NotFound { path, source },
+            io::ErrorKind::PermissionDenied => MinigrepError::PermissionDenied { path, source },
+            io::ErrorKind::IsADirectory => MinigrepError::IsDirectory { path, source },
+            io::ErrorKind::FileTooLarge => MinigrepError::TooLarge { path, source },
+            _ => MinigrepError::Io { path, source },
+        }
+    }
+
+    /// The error for the directory called `path`, given without `-r`.
+    pub(crate) fn is_directory(path: &str) -> MinigrepError {
+        let source = io::Error::new(
+            io::ErrorKind::IsADirectory,
+            "is a directory (use -r to search recursively)",
+        );
+        MinigrepError::new(path, source)
+    }
+
+    /// The error for the input called `path`, in which invalid UTF-8 was
+    /// replaced.
+    pub(crate) fn invalid_utf8(path: &str) -> MinigrepError {
+        MinigrepError::InvalidUtf8 {
+            path: path.to_string(),
+            source: io::Error::new(
+                io::ErrorKind::InvalidData,
+                "invalid UTF-8 was replaced with U+FFFD",
+            ),
+        }
+    }
+
+    /// The error for the input called `path`, skipped for `reason`: a
+    /// [`MinigrepError::TooLarge`] or [`MinigrepError::Binary`] one if that
+    /// is the reason, and a [`MinigrepError::Io`] one otherwise.
+    pub(crate) fn skipped(path: &str, reason: SkipReason) -> MinigrepError {
+        let path = path.to_string();
+        match reason {
+            SkipReason::TooLarge => MinigrepError::TooLarge {
+                path,
+                source: io::Error::new(io::ErrorKind::FileTooLarge, reason),
+            },
+            SkipReason::Binary => MinigrepError::Binary {
+                path,
+                source: io::Error::new(io::ErrorKind::InvalidData, reason),
+            },
+            _ => MinigrepError::Io {
+                path,
+                source: io::Error::other(reason),
+            },
+        }
+    }
+
+    /// The name of the input.
+    pub fn path(&self) -> &str {
+        self.parts().0
+    }
+
+    /// What went wrong, without the name of the input.
+    pub fn reason(&self) -> String {
+        self.parts().1.to_string()
+    }
+
+    /// The kind of error, as an `error` event of `--json` reports it.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            MinigrepError::NotFound { .. } => ErrorKind::NotFound,
+            MinigrepError::PermissionDenied { .. } => ErrorKind::PermissionDenied,
+            MinigrepError::IsDirectory { .. } => ErrorKind::IsDirectory,
+            MinigrepError::InvalidUtf8 { .. } => ErrorKind::InvalidUtf8,
+            MinigrepError::TooLarge { .. } => ErrorKind::TooLarge,
+            MinigrepError::Binary { .. } => ErrorKind::BinarySkipped,
+            MinigrepError::Io { .. } | MinigrepError::Output { .. } => ErrorKind::Io,
+        }
+    }
+
+    /// The name of the input and the I/O error underneath.
+    fn parts(&self) -> (&str, &io::Error) {
+        match self {
+            MinigrepError::NotFound { path, source }
+            | MinigrepError::PermissionDenied { path, source }
+            | MinigrepError::IsDirectory { path, source }
+            | MinigrepError::InvalidUtf8 { path, source }
+            | MinigrepError::TooLarge { path, source }
+            | MinigrepError::Binary { path, source }
+            | MinigrepError::Io { path, source }
+            | MinigrepError::Output { path, source } => (path, source),
+        }
+    }
+}
+
+/// Writes the error as the command line warns about it, as `path: reason`.
+impl fmt::Display for MinigrepError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (path, source) = self.parts();
+        write!(f, "{path}: {source}")
+    }
+}
+
+/// Wraps an error writing the output, as a [`MinigrepError::Output`].
+impl From<io::Error> for MinigrepError {
+    fn from(source: io::Error) -> MinigrepError {
+        MinigrepError::Output {
+            path: "(output)".to_string(),
+            source,
+        }
+    }
+}
+
+impl Error for MinigrepError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(self.parts().1)
+    }
+}
+
+/// Why an input could not be searched, or only in part, as an `error` event
+/// reports it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The path does not exist.
+    NotFound,
+    /// The path could not be read for lack of permission.
+    PermissionDenied,
+    /// The path is a directory, but `-r` was not given.
+    IsDirectory,
+    /// The input contains invalid UTF-8, which was replaced by U+FFFD.
+    InvalidUtf8,
+    /// The file is larger than `--max-filesize`, so it was skipped.
+    TooLarge,
+    /// The input has a NUL byte in its first block, so it was skipped as
+    /// binary.
+    BinarySkipped,
+    /// Any other failure to read the input.
+    Io,
+}
+
+impl ErrorKind {
+    /// Classifies an I/O error.
+    ///
+    /// # Examples
+    /// ```
+    /// use minigrep_cli_tool::error::ErrorKind;
+    /// use std::io;
+    ///
+    /// let error = io::Error::from(io::ErrorKind::PermissionDenied);
+    /// assert_eq!(ErrorKind::of(&error), ErrorKind::PermissionDenied);
+    /// ```
+    pub fn of(error: &io::Error) -> ErrorKind {
+        match error.kind() {
+            io::ErrorKind::NotFound => ErrorKind::NotFound,
+            io::ErrorKind::PermissionDenied => ErrorKind::PermissionDenied,
+            io::ErrorKind::IsADirectory => ErrorKind::IsDirectory,
+            _ => ErrorKind::Io,
+        }
+    }
+
+    /// The name of the kind in `error` events, in snake case.
+    pub fn name(self) -> &'static str {
+        match self {
+            ErrorKind::NotFound => "not_found",
+            ErrorKind::PermissionDenied => "permission_denied",
+            ErrorKind::IsDirectory => "is_directory",
+            ErrorKind::InvalidUtf8 => "invalid_utf8",
+            ErrorKind::TooLarge => "too_large",
+            ErrorKind::BinarySkipped => "binary_skipped",
+            ErrorKind::Io => "io",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn io_errors_are_classified_by_kind() {
+        let kind = |kind: io::ErrorKind| ErrorKind::of(&io::Error::from(kind));
+        assert_eq!(kind(io::ErrorKind::NotFound), ErrorKind::NotFound);
+        assert_eq!(
+            kind(io::ErrorKind::PermissionDenied),
+            ErrorKind::PermissionDenied
+        );
+        assert_eq!(kind(io::ErrorKind::IsADirectory), ErrorKind::IsDirectory);
+        assert_eq!(kind(io::ErrorKind::UnexpectedEof), ErrorKind::Io);
+    }
+
+    #[test]
+    fn errors_are_wrapped_in_the_variant_of_their_kind() {
+        let error = |kind: io::ErrorKind| MinigrepError::new("a.log", io::Error::from(kind));
+        let kinds = [
+            (io::ErrorKind::NotFound, ErrorKind::NotFound),
+            (io::ErrorKind::PermissionDenied, ErrorKind::PermissionDenied),
+            (io::ErrorKind::IsADirectory, ErrorKind::IsDirectory),
+            (io::ErrorKind::FileTooLarge, ErrorKind::TooLarge),
+            (io::ErrorKind::UnexpectedEof, ErrorKind::Io),
+        ];
+        for (io_kind, kind) in kinds {
+            assert_eq!(error(io_kind).kind(), kind, "{io_kind:?}");
+        }
+        assert_eq!(error(io::ErrorKind::NotFound).path(), "a.log");
+
+        let skipped = |reason| MinigrepError::skipped("a.bin", reason);
+        assert_eq!(skipped(SkipReason::Binary).kind(), ErrorKind::BinarySkipped);
+        assert_eq!(skipped(SkipReason::TooLarge).kind(), ErrorKind::TooLarge);
+        assert_eq!(skipped(SkipReason::Device).kind(), ErrorKind::Io);
+        assert_eq!(
+            skipped(SkipReason::Binary).to_string(),
+            "a.bin: binary file"
+        );
+        assert_eq!(
+            MinigrepError::is_directory("src").to_string(),
+            "src: is a directory (use -r to search recursively)"
+        );
+    }
+}
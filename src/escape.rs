@@ -0,0 +1,132 @@
+//! The escape sequences `--escapes` interprets in the query, so that a tab
+//! or a newline can be searched for without typing one into the shell.
+
+/// Replaces the escape sequences of `query` by the characters they stand
+/// for: `\t` for a tab, `\n` for a newline, `\r` for a carriage return,
+/// `\\` for a backslash, and `\xNN`, with two hex digits, for the ASCII
+/// character numbered `NN`.
+///
+/// # Errors
+/// Returns an error naming the 1-based character position of the
+/// backslash if an escape is unknown, such as `\q`, cut short, such as
+/// `\x4` or a lone `\` at the end, or stands for a character beyond ASCII.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::escape::unescape;
+///
+/// assert_eq!(unescape(r"a\tb\x21"), Ok(String::from("a\tb!")));
+/// assert_eq!(
+///     unescape(r"ab\q"),
+///     Err(String::from(r"Invalid escape '\q' at character 3 of the query"))
+/// );
+/// ```
+pub fn unescape(query: &str) -> Result<String, String> {
+    let mut unescaped = String::with_capacity(query.len());
+    let mut chars = query.chars().enumerate().peekable();
+    while let Some((index, c)) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        let position = index + 1;
+        let escaped = match chars.next() {
+            Some((_, 't')) => '\t',
+            Some((_, 'n')) => '\n',
+            Some((_, 'r')) => '\r',
+            Some((_, '\\')) => '\\',
+            Some((_, 'x')) => {
+                let mut digits = String::new();
+                while digits.len() < 2
+                    && let Some((_, digit)) = chars.next_if(|(_, c)| c.is_ascii_hexdigit())
+                {
+                    digits.push(digit);
+                }
+                if digits.len() < 2 {
+                    return Err(format!(
+                        "Incomplete escape '\\x{digits}' at character {position} of the query"
+                    ));
+                }
+                let code = u8::from_str_radix(&digits, 16).expect("two hex digits");
+                if !code.is_ascii() {
+                    return Err(format!(
+                        "Escape '\\x{digits}' at character {position} of the query is not ASCII"
+                    ));
+                }
+                char::from(code)
+            }
+            Some((_, other)) => {
+                return Err(format!(
+                    "Invalid escape '\\{other}' at character {position} of the query"
+                ));
+            }
+            None => {
+                return Err(format!(
+                    "Incomplete escape '\\' at character {position} of the query"
+                ));
+            }
+        };
+        unescaped.push(escaped);
+    }
+    Ok(unescaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_sequence_is_replaced() {
+        let cases = [
+            (r"a\tb", "a\tb"),
+            (r"a\nb", "a\nb"),
+            (r"a\rb", "a\rb"),
+            (r"a\\b", "a\\b"),
+            (r"\x41\x7e\x7F", "A~\x7f"),
+            (r"\\n", "\\n"),
+            (r"\x410", "A0"),
+            ("plain text", "plain text"),
+            ("", ""),
+            (r"é\t", "é\t"),
+        ];
+        for (query, expected) in cases {
+            assert_eq!(unescape(query).as_deref(), Ok(expected), "{query}");
+        }
+    }
+
+    #[test]
+    fn bad_escapes_name_their_position() {
+        let cases = [
+            (r"\q", r"Invalid escape '\q' at character 1 of the query"),
+            (r"ab\ ", r"Invalid escape '\ ' at character 3 of the query"),
+            (r"é\é", r"Invalid escape '\é' at character 2 of the query"),
+            (
+                r"tab\",
+                r"Incomplete escape '\' at character 4 of the query",
+            ),
+            (
+                r"\x4",
+                r"Incomplete escape '\x4' at character 1 of the query",
+            ),
+            (
+                r"a\x",
+                r"Incomplete escape '\x' at character 2 of the query",
+            ),
+            (
+                r"\x4g",
+                r"Incomplete escape '\x4' at character 1 of the query",
+            ),
+            (
+                r"\x80",
+                r"Escape '\x80' at character 1 of the query is not ASCII",
+            ),
+            (
+                r"\t\xff",
+                r"Escape '\xff' at character 3 of the query is not ASCII",
+            ),
+        ];
+        for (query, expected) in cases {
+            assert_eq!(unescape(query), Err(String::from(expected)), "{query}");
+        }
+    }
+}
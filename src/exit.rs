@@ -0,0 +1,227 @@
+//! The exit statuses of the binary, which scripts rely on, decided in one
+//! place.
+
+use std::io;
+
+use crate::{cli::RunSummary, error::MinigrepError};
+
+/// How a run of the binary ended, as its exit status reports it.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::{RunSummary, exit::ExitStatus};
+///
+/// let summary = RunSummary { found: true, matched_lines: 2, failed_inputs: 1 };
+/// assert_eq!(ExitStatus::from(summary).to_code(), 0);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitStatus {
+    /// Something was selected, listed, or replaced.
+    MatchesFound,
+    /// Nothing was, and every input could be searched.
+    NoMatches,
+    /// The arguments could not be parsed.
+    UsageError,
+    /// Nothing was found and some inputs could not be searched, or the
+    /// search could not go on.
+    IoError,
+    /// Fewer lines were selected than `--require-count` or
+    /// `--require-match` asked for, though every input could be searched.
+    TooFewMatches,
+    /// Standard output was closed early, e.g. by `head`, so the search was
+    /// cut short.
+    Interrupted,
+}
+
+impl ExitStatus {
+    /// The exit code: 0 when something was found, 1 when nothing was, 2 for
+    /// errors, 3 when too few lines were, and for
+    /// [`ExitStatus::Interrupted`], 141, which is 128 plus the number of
+    /// `SIGPIPE`, as shells report for a process it killed.
+    pub fn to_code(self) -> i32 {
+        match self {
+            ExitStatus::MatchesFound => 0,
+            ExitStatus::NoMatches => 1,
+            ExitStatus::UsageError | ExitStatus::IoError => 2,
+            ExitStatus::TooFewMatches => 3,
+            ExitStatus::Interrupted => 141,
+        }
+    }
+
+    /// The status of a run that had to select at least `required` lines:
+    /// [`ExitStatus::TooFewMatches`] if it selected fewer, unless some inputs
+    /// could not be searched, which may be why, making it
+    /// [`ExitStatus::IoError`]; otherwise [`ExitStatus::MatchesFound`].
+    ///
+    /// # Examples
+    /// ```
+    /// use minigrep_cli_tool::{RunSummary, exit::ExitStatus};
+    ///
+    /// let summary = RunSummary { found: true, matched_lines: 2, failed_inputs: 0 };
+    /// assert_eq!(ExitStatus::requiring(summary, 2), ExitStatus::MatchesFound);
+    /// assert_eq!(ExitStatus::requiring(summary, 3).to_code(), 3);
+    /// ```
+    pub fn requiring(summary: RunSummary, required: u64) -> ExitStatus {
+        if summary.matched_lines >= required {
+            ExitStatus::MatchesFound
+        } else if summary.failed_inputs > 0 {
+            ExitStatus::IoError
+        } else {
+            ExitStatus::TooFewMatches
+        }
+    }
+}
+
+impl From<RunSummary> for ExitStatus {
+    /// Whether anything was found, and if not, whether any input failed.
+    fn from(summary: RunSummary) -> ExitStatus {
+        if summary.found {
+            ExitStatus::MatchesFound
+        } else if summary.failed_inputs > 0 {
+            ExitStatus::IoError
+        } else {
+            ExitStatus::NoMatches
+        }
+    }
+}
+
+impl From<&MinigrepError> for ExitStatus {
+    /// [`ExitStatus::Interrupted`] for a write to a pipe whose reader has
+    /// gone away, and [`ExitStatus::IoError`] for any other error ending a
+    /// run, whether writing the output or reading an input failed.
+    fn from(error: &MinigrepError) -> ExitStatus {
+        match error {
+            MinigrepError::Output { source, .. } if source.kind() == io::ErrorKind::BrokenPipe => {
+                ExitStatus::Interrupted
+            }
+            MinigrepError::Output { .. }
+            | MinigrepError::NotFound { .. }
+            | MinigrepError::PermissionDenied { .. }
+            | MinigrepError::IsDirectory { .. }
+            | MinigrepError::InvalidUtf8 { .. }
+            | MinigrepError::TooLarge { .. }
+            | MinigrepError::Binary { .. }
+            | MinigrepError::Io { .. } => ExitStatus::IoError,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::walk::SkipReason;
+
+    #[test]
+    fn each_status_keeps_its_code() {
+        let codes = [
+            (ExitStatus::MatchesFound, 0),
+            (ExitStatus::NoMatches, 1),
+            (ExitStatus::UsageError, 2),
+            (ExitStatus::IoError, 2),
+            (ExitStatus::TooFewMatches, 3),
+            (ExitStatus::Interrupted, 141),
+        ];
+        for (status, code) in codes {
+            assert_eq!(status.to_code(), code, "{status:?}");
+        }
+    }
+
+    #[test]
+    fn summaries_decide_between_found_missing_and_failed() {
+        let summary = |found, failed_inputs| RunSummary {
+            found,
+            matched_lines: 0,
+            failed_inputs,
+        };
+        assert_eq!(ExitStatus::from(summary(true, 0)), ExitStatus::MatchesFound);
+        assert_eq!(ExitStatus::from(summary(true, 3)), ExitStatus::MatchesFound);
+        assert_eq!(ExitStatus::from(summary(false, 0)), ExitStatus::NoMatches);
+        assert_eq!(ExitStatus::from(summary(false, 1)), ExitStatus::IoError);
+    }
+
+    #[test]
+    fn requirements_are_met_by_enough_selected_lines() {
+        let summary = |matched_lines, failed_inputs| RunSummary {
+            found: matched_lines > 0,
+            matched_lines,
+            failed_inputs,
+        };
+        let status = ExitStatus::requiring;
+        assert_eq!(status(summary(3, 0), 3), ExitStatus::MatchesFound);
+        assert_eq!(status(summary(3, 1), 3), ExitStatus::MatchesFound);
+        assert_eq!(status(summary(2, 0), 3), ExitStatus::TooFewMatches);
+        assert_eq!(status(summary(0, 0), 1), ExitStatus::TooFewMatches);
+        assert_eq!(status(summary(2, 1), 3), ExitStatus::IoError);
+        assert_eq!(status(summary(0, 0), 0), ExitStatus::MatchesFound);
+    }
+
+    fn status(error: MinigrepError) -> ExitStatus {
+        ExitStatus::from(&error)
+    }
+
+    fn input_error(kind: io::ErrorKind) -> MinigrepError {
+        MinigrepError::new("list", io::Error::from(kind))
+    }
+
+    #[test]
+    fn broken_pipes_are_interruptions() {
+        let pipe = io::Error::from(io::ErrorKind::BrokenPipe);
+        assert_eq!(status(pipe.into()), ExitStatus::Interrupted);
+    }
+
+    #[test]
+    fn other_output_errors_are_io_errors() {
+        let full = io::Error::from(io::ErrorKind::StorageFull);
+        assert_eq!(status(full.into()), ExitStatus::IoError);
+    }
+
+    #[test]
+    fn missing_inputs_are_io_errors() {
+        let error = input_error(io::ErrorKind::NotFound);
+        assert!(matches!(error, MinigrepError::NotFound { .. }));
+        assert_eq!(status(error), ExitStatus::IoError);
+    }
+
+    #[test]
+    fn denied_inputs_are_io_errors() {
+        let error = input_error(io::ErrorKind::PermissionDenied);
+        assert!(matches!(error, MinigrepError::PermissionDenied { .. }));
+        assert_eq!(status(error), ExitStatus::IoError);
+    }
+
+    #[test]
+    fn directories_are_io_errors() {
+        assert_eq!(
+            status(MinigrepError::is_directory("src")),
+            ExitStatus::IoError
+        );
+    }
+
+    #[test]
+    fn invalid_utf8_is_an_io_error() {
+        let error = MinigrepError::invalid_utf8("a.log");
+        assert_eq!(status(error), ExitStatus::IoError);
+    }
+
+    #[test]
+    fn inputs_too_large_are_io_errors() {
+        let error = MinigrepError::skipped("a.log", SkipReason::TooLarge);
+        assert!(matches!(error, MinigrepError::TooLarge { .. }));
+        assert_eq!(status(error), ExitStatus::IoError);
+    }
+
+    #[test]
+    fn binary_inputs_are_io_errors() {
+        let error = MinigrepError::skipped("a.bin", SkipReason::Binary);
+        assert!(matches!(error, MinigrepError::Binary { .. }));
+        assert_eq!(status(error), ExitStatus::IoError);
+    }
+
+    #[test]
+    fn other_input_errors_are_io_errors() {
+        // A broken pipe on an input is no interruption: only the output's is.
+        let error = input_error(io::ErrorKind::BrokenPipe);
+        assert!(matches!(error, MinigrepError::Io { .. }));
+        assert_eq!(status(error), ExitStatus::IoError);
+    }
+}
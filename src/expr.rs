@@ -0,0 +1,613 @@
+//! The boolean expressions of `--expr`, which select a line by which
+//! literals it contains, as in `("timeout" | "timed out") & !"retry"`.
+//!
+//! An expression is built from quoted literals, each matched as the query
+//! is, and these operators, from the tightest binding to the loosest:
+//!
+//! 1. `!e`: the line does not match `e`.
+//! 2. `a & b`: it matches both.
+//! 3. `a | b`: it matches either.
+//!
+//! So `!"a" & "b" | "c"` is `((!"a") & "b") | "c"`, and parentheses group
+//! otherwise. `&` and `|` group from the left. Outside literals, whitespace
+//! is ignored. Inside them, every character stands for itself, operators
+//! included, except that `\"` stands for a quote and `\\` for a backslash.
+//! A literal after `(?i)` is matched ignoring case, and one after `(?-i)`
+//! with it, whatever the case setting of the search.
+
+use std::{fmt, ops::Range};
+
+use crate::{Matcher, line_matches, match_ranges};
+
+/// A parsed `--expr` expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    /// A quoted literal, matched as a query.
+    Literal(String),
+    /// A quoted literal after `(?i)`, if the flag is set, or `(?-i)`,
+    /// matched ignoring case if the flag is set, whatever the case of the
+    /// search.
+    Cased(String, bool),
+    /// `!e`.
+    Not(Box<Expr>),
+    /// `a & b`.
+    And(Box<Expr>, Box<Expr>),
+    /// `a | b`.
+    Or(Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    /// Parses `text` as an expression.
+    ///
+    /// # Errors
+    /// Returns an error naming the 1-based character position at fault if
+    /// `text` is not an expression: an operator is missing an operand, a
+    /// literal or a parenthesis is left open, a literal is empty or has an
+    /// invalid escape, or something follows a whole expression.
+    ///
+    /// # Examples
+    /// ```
+    /// use minigrep_cli_tool::expr::Expr;
+    ///
+    /// let literal = |text: &str| Box::new(Expr::Literal(text.to_string()));
+    /// assert_eq!(
+    ///     Expr::parse(r#"!"a" | "b""#),
+    ///     Ok(Expr::Or(Box::new(Expr::Not(literal("a"))), literal("b")))
+    /// );
+    /// assert_eq!(
+    ///     Expr::parse(r#""a" &"#),
+    ///     Err(String::from(
+    ///         "Expected a quoted literal, '!', or '(' at the end of the expression"
+    ///     ))
+    /// );
+    /// ```
+    pub fn parse(text: &str) -> Result<Expr, String> {
+        let mut parser = Parser {
+            chars: text.chars().collect(),
+            index: 0,
+        };
+        let expr = parser.or()?;
+        match parser.peek() {
+            None => Ok(expr),
+            Some(c) => Err(format!(
+                "Unexpected '{c}' at character {} of the expression",
+                parser.index + 1
+            )),
+        }
+    }
+
+    /// The literal a pattern of `-e` or `-f` stands for: a
+    /// [`Expr::Cased`] one if the pattern starts with `(?i)` or `(?-i)`,
+    /// which is then left out, and a plain one otherwise.
+    ///
+    /// # Examples
+    /// ```
+    /// use minigrep_cli_tool::expr::Expr;
+    ///
+    /// assert_eq!(Expr::pattern("(?i)err"), Expr::Cased(String::from("err"), true));
+    /// assert_eq!(Expr::pattern("(?-i)Err"), Expr::Cased(String::from("Err"), false));
+    /// assert_eq!(Expr::pattern("(?x)"), Expr::Literal(String::from("(?x)")));
+    /// ```
+    pub fn pattern(pattern: &str) -> Expr {
+        match [("(?i)", true), ("(?-i)", false)]
+            .into_iter()
+            .find_map(|(flag, ignore_case)| Some((pattern.strip_prefix(flag)?, ignore_case)))
+        {
+            Some((literal, ignore_case)) => Expr::Cased(literal.to_string(), ignore_case),
+            None => Expr::Literal(pattern.to_string()),
+        }
+    }
+
+    /// The literals of this expression, in the order they are numbered in
+    /// by [`Matcher::pattern_ranges`], those under a `!` included.
+    ///
+    /// # Examples
+    /// ```
+    /// use minigrep_cli_tool::expr::Expr;
+    ///
+    /// let expr = Expr::parse(r#"("a" | "b") & !"c""#).unwrap();
+    /// assert_eq!(expr.literal_texts(), ["a", "b", "c"]);
+    /// ```
+    pub fn literal_texts(&self) -> Vec<&str> {
+        match self {
+            Expr::Literal(literal) | Expr::Cased(literal, _) => vec![literal],
+            Expr::Not(expr) => expr.literal_texts(),
+            Expr::And(left, right) | Expr::Or(left, right) => {
+                let mut texts = left.literal_texts();
+                texts.extend(right.literal_texts());
+                texts
+            }
+        }
+    }
+
+    /// How many literals there are in this expression, which those after
+    /// it are numbered from.
+    fn literals(&self) -> usize {
+        match self {
+            Expr::Literal(_) | Expr::Cased(..) => 1,
+            Expr::Not(expr) => expr.literals(),
+            Expr::And(left, right) | Expr::Or(left, right) => left.literals() + right.literals(),
+        }
+    }
+
+    /// Appends to `ranges` the occurrences of the literals that made this
+    /// expression, which matches `line`, do so, each with the position of
+    /// its literal counting from `first`: none under a `!`, and only those
+    /// of the sides of a `|` that match.
+    fn push_ranges(
+        &self,
+        line: &str,
+        ignore_case: bool,
+        first: usize,
+        ranges: &mut Vec<(Range<usize>, usize)>,
+    ) {
+        match self {
+            Expr::Literal(literal) => ranges.extend(
+                match_ranges(literal, line, ignore_case)
+                    .into_iter()
+                    .map(|range| (range, first)),
+            ),
+            Expr::Cased(literal, ignore_case) => ranges.extend(
+                match_ranges(literal, line, *ignore_case)
+                    .into_iter()
+                    .map(|range| (range, first)),
+            ),
+            Expr::Not(_) => {}
+            Expr::And(left, right) => {
+                left.push_ranges(line, ignore_case, first, ranges);
+                right.push_ranges(line, ignore_case, first + left.literals(), ranges);
+            }
+            Expr::Or(left, right) => {
+                let firsts = [first, first + left.literals()];
+                for (side, first) in [left, right].into_iter().zip(firsts) {
+                    if side.is_match(line, ignore_case) {
+                        side.push_ranges(line, ignore_case, first, ranges);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Expr {
+    /// How tightly the operator at the top of the expression binds, a
+    /// literal binding tightest.
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Or(..) => 0,
+            Expr::And(..) => 1,
+            Expr::Not(_) => 2,
+            Expr::Literal(_) | Expr::Cased(..) => 3,
+        }
+    }
+
+    /// Writes `self` as an operand binding at least as tightly as
+    /// `precedence`, in parentheses if it does not.
+    fn write_operand(&self, f: &mut fmt::Formatter<'_>, precedence: u8) -> fmt::Result {
+        if self.precedence() < precedence {
+            write!(f, "({self})")
+        } else {
+            write!(f, "{self}")
+        }
+    }
+}
+
+/// Writes the expression as [`Expr::parse`] reads it, with only the
+/// parentheses its grouping needs.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::expr::Expr;
+///
+/// let expr = Expr::parse(r#"(("a"|"b")) & !"say \"c\"""#).unwrap();
+/// assert_eq!(expr.to_string(), r#"("a" | "b") & !"say \"c\"""#);
+/// ```
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Literal(literal) => write_literal(f, literal),
+            Expr::Cased(literal, ignore_case) => {
+                f.write_str(if *ignore_case { "(?i)" } else { "(?-i)" })?;
+                write_literal(f, literal)
+            }
+            Expr::Not(expr) => {
+                f.write_str("!")?;
+                expr.write_operand(f, 2)
+            }
+            Expr::And(left, right) | Expr::Or(left, right) => {
+                let precedence = self.precedence();
+                left.write_operand(f, precedence)?;
+                f.write_str(if precedence == 0 { " | " } else { " & " })?;
+                // As operators group from the left, a right operand of the
+                // same operator is grouped.
+                right.write_operand(f, precedence + 1)
+            }
+        }
+    }
+}
+
+/// Writes `literal` quoted, as [`Expr::parse`] reads it.
+fn write_literal(f: &mut fmt::Formatter<'_>, literal: &str) -> fmt::Result {
+    write!(
+        f,
+        "\"{}\"",
+        literal.replace('\\', "\\\\").replace('"', "\\\"")
+    )
+}
+
+impl Matcher for Expr {
+    fn is_match(&self, line: &str, ignore_case: bool) -> bool {
+        match self {
+            Expr::Literal(literal) => line_matches(literal, line, ignore_case),
+            Expr::Cased(literal, ignore_case) => line_matches(literal, line, *ignore_case),
+            Expr::Not(expr) => !expr.is_match(line, ignore_case),
+            Expr::And(left, right) => {
+                left.is_match(line, ignore_case) && right.is_match(line, ignore_case)
+            }
+            Expr::Or(left, right) => {
+                left.is_match(line, ignore_case) || right.is_match(line, ignore_case)
+            }
+        }
+    }
+
+    /// The occurrences of the literals that make the line match, where
+    /// those of different literals overlapping are kept from the first
+    /// and longest.
+    fn match_ranges(&self, line: &str, ignore_case: bool) -> Vec<Range<usize>> {
+        self.pattern_ranges(line, ignore_case)
+            .into_iter()
+            .map(|(range, _)| range)
+            .collect()
+    }
+
+    /// The ranges of [`Expr::match_ranges`], each with the position of its
+    /// literal in the expression, from 0; of literals matching the same
+    /// text, the earlier one is kept.
+    fn pattern_ranges(&self, line: &str, ignore_case: bool) -> Vec<(Range<usize>, usize)> {
+        if !self.is_match(line, ignore_case) {
+            return Vec::new();
+        }
+        let mut ranges = Vec::new();
+        self.push_ranges(line, ignore_case, 0, &mut ranges);
+        ranges.sort_by_key(|(range, pattern)| (range.start, usize::MAX - range.end, *pattern));
+        let mut end = 0;
+        ranges.retain(|(range, _)| {
+            let kept = range.start >= end;
+            if kept {
+                end = range.end;
+            }
+            kept
+        });
+        ranges
+    }
+}
+
+/// The state of [`Expr::parse`]: the characters of the expression and how
+/// far they have been read.
+struct Parser {
+    chars: Vec<char>,
+    index: usize,
+}
+
+impl Parser {
+    /// Skips whitespace and returns the next character, if any.
+    fn peek(&mut self) -> Option<char> {
+        while self
+            .chars
+            .get(self.index)
+            .is_some_and(|c| c.is_whitespace())
+        {
+            self.index += 1;
+        }
+        self.chars.get(self.index).copied()
+    }
+
+    /// Parses operands joined by `|`.
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.peek() == Some('|') {
+            self.index += 1;
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    /// Parses operands joined by `&`.
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.not()?;
+        while self.peek() == Some('&') {
+            self.index += 1;
+            expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+        }
+        Ok(expr)
+    }
+
+    /// Parses an operand, negated by any number of `!`.
+    fn not(&mut self) -> Result<Expr, String> {
+        if self.peek() == Some('!') {
+            self.index += 1;
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        match self.peek() {
+            Some('"') => self.quoted().map(Expr::Literal),
+            Some('(') if self.chars.get(self.index + 1) == Some(&'?') => self.cased(),
+            Some('(') => {
+                let open = self.index;
+                self.index += 1;
+                let expr = self.or()?;
+                match self.peek() {
+                    Some(')') => {
+                        self.index += 1;
+                        Ok(expr)
+                    }
+                    Some(c) => Err(format!(
+                        "Expected ')' but found '{c}' at character {} of the expression",
+                        self.index + 1
+                    )),
+                    None => Err(format!(
+                        "Unclosed '(' at character {} of the expression",
+                        open + 1
+                    )),
+                }
+            }
+            Some(c) => Err(format!(
+                "Expected a quoted literal, '!', or '(' but found '{c}' at character {} of the \
+                 expression",
+                self.index + 1
+            )),
+            None => Err(String::from(
+                "Expected a quoted literal, '!', or '(' at the end of the expression",
+            )),
+        }
+    }
+
+    /// Parses the `(?i)` or `(?-i)` starting at the current character, and
+    /// the quoted literal after it.
+    fn cased(&mut self) -> Result<Expr, String> {
+        let start = self.index;
+        let rest = self.chars[start..].iter().take(5).collect::<String>();
+        let (flag, ignore_case) = [("(?i)", true), ("(?-i)", false)]
+            .into_iter()
+            .find(|(flag, _)| rest.starts_with(flag))
+            .ok_or_else(|| {
+                format!(
+                    "Expected '(?i)' or '(?-i)' at character {} of the expression",
+                    start + 1
+                )
+            })?;
+        self.index += flag.len();
+        match self.peek() {
+            Some('"') => Ok(Expr::Cased(self.quoted()?, ignore_case)),
+            _ => Err(format!(
+                "Expected a quoted literal after '{flag}' at character {} of the expression",
+                start + 1
+            )),
+        }
+    }
+
+    /// Parses the quoted literal starting at the current character,
+    /// returning its text.
+    fn quoted(&mut self) -> Result<String, String> {
+        let start = self.index;
+        self.index += 1;
+        let mut literal = String::new();
+        loop {
+            let Some(&c) = self.chars.get(self.index) else {
+                return Err(format!(
+                    "Unterminated literal starting at character {} of the expression",
+                    start + 1
+                ));
+            };
+            self.index += 1;
+            match c {
+                '"' => break,
+                '\\' => match self.chars.get(self.index) {
+                    Some(&escaped @ ('"' | '\\')) => {
+                        literal.push(escaped);
+                        self.index += 1;
+                    }
+                    Some(other) => {
+                        return Err(format!(
+                            "Invalid escape '\\{other}' at character {} of the expression",
+                            self.index
+                        ));
+                    }
+                    None => {
+                        return Err(format!(
+                            "Unterminated literal starting at character {} of the expression",
+                            start + 1
+                        ));
+                    }
+                },
+                c => literal.push(c),
+            }
+        }
+        if literal.is_empty() {
+            return Err(format!(
+                "Empty literal at character {} of the expression",
+                start + 1
+            ));
+        }
+        Ok(literal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn literal(text: &str) -> Box<Expr> {
+        Box::new(Expr::Literal(text.to_string()))
+    }
+
+    fn matches(expr: &str, line: &str) -> bool {
+        Expr::parse(expr).unwrap().is_match(line, false)
+    }
+
+    #[test]
+    fn expressions_are_written_as_they_are_parsed() {
+        for text in [
+            r#""a""#,
+            r#"!"a" & "b" | "c""#,
+            r#""a" | "b" & "c""#,
+            r#"!("a" | "b")"#,
+            r#""a" & ("b" & "c")"#,
+            r#"("a" | "b") & !!"c""#,
+            r#""a" | ("b" | "c") | "d""#,
+            r#""back\\slash" & "\"quoted\"""#,
+            r#"(?i)"a" | !(?-i)"B""#,
+        ] {
+            let expr = Expr::parse(text).unwrap();
+            assert_eq!(expr.to_string(), text);
+            assert_eq!(Expr::parse(&expr.to_string()), Ok(expr));
+        }
+    }
+
+    #[test]
+    fn not_binds_tighter_than_and_than_or() {
+        use Expr::{And, Not, Or};
+        assert_eq!(
+            Expr::parse(r#"!"a" & "b" | "c""#),
+            Ok(Or(
+                Box::new(And(Box::new(Not(literal("a"))), literal("b"))),
+                literal("c")
+            ))
+        );
+        assert_eq!(
+            Expr::parse(r#""a" | "b" & "c""#),
+            Ok(Or(literal("a"), Box::new(And(literal("b"), literal("c")))))
+        );
+        assert_eq!(
+            Expr::parse(r#"!("a" | "b")"#),
+            Ok(Not(Box::new(Or(literal("a"), literal("b")))))
+        );
+        assert_eq!(
+            Expr::parse(r#""a" & "b" & "c""#),
+            Ok(And(Box::new(And(literal("a"), literal("b"))), literal("c")))
+        );
+        assert_eq!(
+            Expr::parse(r#"!!"a""#),
+            Ok(Not(Box::new(Not(literal("a")))))
+        );
+
+        // With `|` binding tighter, this would select "c".
+        assert!(!matches(r#""a" | "b" & "c""#, "c"));
+        assert!(matches(r#"!"a" & "b" | "c""#, "a c"));
+        assert!(!matches(r#"!("a" | "b") & "c""#, "a c"));
+    }
+
+    #[test]
+    fn literals_keep_operators_and_escapes() {
+        assert_eq!(
+            Expr::parse(r#""a & !(b | c)""#),
+            Ok(*literal("a & !(b | c)"))
+        );
+        assert_eq!(
+            Expr::parse(r#""say \"hi\" \\ bye""#),
+            Ok(*literal(r#"say "hi" \ bye"#))
+        );
+        assert_eq!(Expr::parse("\t( \"x\" )\n"), Ok(*literal("x")));
+        assert!(matches(r#""a | b" & !"c""#, "a | b"));
+        assert!(!matches(r#""a | b""#, "a"));
+    }
+
+    #[test]
+    fn cased_literals_keep_their_case_whatever_the_search() {
+        let expr = Expr::parse(r#"(?i)"err" | (?-i)"Warn" | "Info""#).unwrap();
+        for ignore_case in [false, true] {
+            assert!(expr.is_match("ERR", ignore_case));
+            assert!(expr.is_match("Warn", ignore_case));
+            assert!(!expr.is_match("WARN", ignore_case));
+            assert_eq!(expr.is_match("INFO", ignore_case), ignore_case);
+        }
+        assert_eq!(expr.match_ranges("Err and warn", false), vec![0..3]);
+    }
+
+    #[test]
+    fn parse_errors_point_at_the_fault() {
+        let errors = [
+            (
+                "",
+                "Expected a quoted literal, '!', or '(' at the end of the expression",
+            ),
+            (
+                r#""a" & b"#,
+                "Expected a quoted literal, '!', or '(' but found 'b' at character 7 of the \
+                 expression",
+            ),
+            (
+                r#"| "a""#,
+                "Expected a quoted literal, '!', or '(' but found '|' at character 1 of the expression",
+            ),
+            (
+                r#""a" "b""#,
+                "Unexpected '\"' at character 5 of the expression",
+            ),
+            (r#""a")"#, "Unexpected ')' at character 4 of the expression"),
+            (r#"("a""#, "Unclosed '(' at character 1 of the expression"),
+            (
+                r#"("a" x"#,
+                "Expected ')' but found 'x' at character 6 of the expression",
+            ),
+            (
+                r#""a" | "b"#,
+                "Unterminated literal starting at character 7 of the expression",
+            ),
+            (
+                r#""a\"#,
+                "Unterminated literal starting at character 1 of the expression",
+            ),
+            (
+                r#""a\q""#,
+                r"Invalid escape '\q' at character 3 of the expression",
+            ),
+            (r#"!"""#, "Empty literal at character 2 of the expression"),
+            (
+                r#"(?x)"a""#,
+                "Expected '(?i)' or '(?-i)' at character 1 of the expression",
+            ),
+            (
+                r#""a" | (?i)("b")"#,
+                "Expected a quoted literal after '(?i)' at character 7 of the expression",
+            ),
+        ];
+        for (expr, error) in errors {
+            assert_eq!(Expr::parse(expr), Err(String::from(error)), "{expr}");
+        }
+    }
+
+    #[test]
+    fn ranges_are_those_of_the_literals_making_the_match() {
+        let ranges = |expr: &str, line: &str| Expr::parse(expr).unwrap().match_ranges(line, true);
+        assert_eq!(ranges(r#""b" & !"z""#, "abcb"), vec![1..2, 3..4]);
+        assert_eq!(ranges(r#"("a" & "zz") | "c""#, "a c"), vec![2..3]);
+        assert_eq!(ranges(r#""ab" | "abc" | "bc""#, "abcd"), vec![0..3]);
+        assert_eq!(ranges(r#""A" & !"b""#, "ab"), Vec::new());
+        assert_eq!(ranges(r#"!"b""#, "a"), Vec::new());
+        assert_eq!(
+            Expr::parse(r#""a" | "b""#).unwrap().count("a b a", false),
+            3
+        );
+    }
+
+    #[test]
+    fn ranges_carry_the_position_of_their_literal() {
+        let ranges = |expr: &str, line: &str| {
+            Expr::parse(expr)
+                .unwrap()
+                .pattern_ranges(line, false)
+                .into_iter()
+                .map(|(range, pattern)| (range.start, pattern))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ranges(r#""b" | "a""#, "a b a"), [(0, 1), (2, 0), (4, 1)]);
+        // Literals under `!` and sides of `|` not matching are still counted.
+        assert_eq!(
+            ranges(r#"!"x" & ("y" | "z") & "a""#, "z a"),
+            [(0, 2), (2, 3)]
+        );
+        // Of two literals matching the same text, the earlier one is kept.
+        assert_eq!(ranges(r#""b" | "a" | "a""#, "a"), [(0, 1)]);
+        assert_eq!(ranges(r#""warn" | "warning""#, "warning"), [(0, 1)]);
+    }
+}
@@ -0,0 +1,286 @@
+//! Shell-style wildcard patterns for choosing files by name, as used by
+//! `--include`, `--exclude`, and `--exclude-dir`, and for expanding the
+//! paths given on the command line where no shell does, as on Windows.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// A single piece of a parsed [`Glob`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    /// A character matching only itself.
+    Literal(char),
+    /// `?`, matching any single character.
+    AnyChar,
+    /// `*`, matching any run of characters, including none.
+    AnyRun,
+    /// `[...]`, matching one character in (or, negated, not in) the ranges.
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+impl Token {
+    /// Returns whether this single-character token matches `c`.
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Token::Literal(literal) => *literal == c,
+            Token::AnyChar => true,
+            Token::AnyRun => false,
+            Token::Class { negated, ranges } => {
+                ranges.iter().any(|&(low, high)| (low..=high).contains(&c)) != *negated
+            }
+        }
+    }
+}
+
+/// A wildcard pattern matched against file names.
+///
+/// `*` matches any run of characters, `?` any single character, and
+/// `[abc]`, `[a-z]`, or `[!a-z]` one character of (or not of) a set. A
+/// backslash makes the character after it literal. Unlike in a shell, `*`
+/// and `?` also match a leading `.`.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::glob::Glob;
+///
+/// let glob = Glob::new("*.[ch]").unwrap();
+/// assert!(glob.is_match("main.c"));
+/// assert!(glob.is_match("lib.h"));
+/// assert!(!glob.is_match("main.rs"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glob {
+    pattern: String,
+    tokens: Vec<Token>,
+}
+
+impl Glob {
+    /// Parses `pattern`.
+    ///
+    /// # Errors
+    /// Returns an error if a `[` is never closed or the pattern ends in a
+    /// lone backslash.
+    pub fn new(pattern: &str) -> Result<Glob, String> {
+        let mut tokens = Vec::new();
+        let mut chars = pattern.chars();
+        while let Some(c) = chars.next() {
+            let token = match c {
+                '?' => Token::AnyChar,
+                '*' => Token::AnyRun,
+                '\\' => Token::Literal(
+                    chars
+                        .next()
+                        .ok_or_else(|| format!("Invalid glob '{pattern}': trailing backslash"))?,
+                ),
+                '[' => parse_class(&mut chars)
+                    .ok_or_else(|| format!("Invalid glob '{pattern}': unclosed '['"))?,
+                c => Token::Literal(c),
+            };
+            tokens.push(token);
+        }
+        Ok(Glob {
+            pattern: pattern.to_string(),
+            tokens,
+        })
+    }
+
+    /// The pattern this glob was parsed from.
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Returns whether the whole of `name` matches the pattern.
+    pub fn is_match(&self, name: &str) -> bool {
+        let text: Vec<char> = name.chars().collect();
+        let tokens = &self.tokens;
+        let (mut t, mut p) = (0, 0);
+        // Where the last `*` was, and how much text it has swallowed so far.
+        let mut last_run: Option<(usize, usize)> = None;
+        while t < text.len() {
+            if p < tokens.len() && tokens[p] == Token::AnyRun {
+                last_run = Some((p, t));
+                p += 1;
+            } else if p < tokens.len() && tokens[p].matches(text[t]) {
+                p += 1;
+                t += 1;
+            } else if let Some((run, swallowed)) = last_run {
+                last_run = Some((run, swallowed + 1));
+                p = run + 1;
+                t = swallowed + 1;
+            } else {
+                return false;
+            }
+        }
+        tokens[p..].iter().all(|token| *token == Token::AnyRun)
+    }
+}
+
+/// Expands `pattern`, a path whose file name may be a glob such as `*.log`,
+/// into the paths of the entries of its directory that match, in order of
+/// their names, as a shell would before running a command.
+///
+/// Only the file name may contain wildcards. A pattern without any, or one
+/// that is invalid, matches nothing, or names a directory that cannot be
+/// read, is returned as it is, so that searching it reports the error.
+///
+/// # Examples
+/// ```no_run
+/// use minigrep_cli_tool::glob::expand;
+/// use std::path::Path;
+///
+/// for path in expand(Path::new("logs/*.log")) {
+///     println!("{}", path.display());
+/// }
+/// ```
+pub fn expand(pattern: &Path) -> Vec<PathBuf> {
+    let unchanged = || vec![pattern.to_path_buf()];
+    let Some(name) = pattern.file_name().and_then(|name| name.to_str()) else {
+        return unchanged();
+    };
+    if !has_wildcards(pattern) {
+        return unchanged();
+    }
+    let Ok(glob) = Glob::new(name) else {
+        return unchanged();
+    };
+    let dir = pattern.parent().unwrap_or(Path::new(""));
+    let listed = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    let Ok(entries) = fs::read_dir(listed) else {
+        return unchanged();
+    };
+    let mut matched: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .filter(|entry| glob.is_match(&entry.file_name().to_string_lossy()))
+        .map(|entry| dir.join(entry.file_name()))
+        .collect();
+    if matched.is_empty() {
+        return unchanged();
+    }
+    matched.sort();
+    matched
+}
+
+/// Returns whether the file name of `pattern` contains a wildcard, so that
+/// [`expand`] may expand it into several paths.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::glob::has_wildcards;
+/// use std::path::Path;
+///
+/// assert!(has_wildcards(Path::new("logs/*.log")));
+/// assert!(!has_wildcards(Path::new("lo?s/app.log")));
+/// ```
+pub fn has_wildcards(pattern: &Path) -> bool {
+    pattern
+        .file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.contains(['*', '?', '[']))
+}
+
+/// Parses the rest of a `[...]` class after its opening `[`.
+///
+/// A `]` right after the opening (or after `!`) is part of the set. Returns
+/// `None` if the class is never closed.
+fn parse_class(chars: &mut std::str::Chars) -> Option<Token> {
+    let negated = chars.as_str().starts_with('!');
+    if negated {
+        chars.next();
+    }
+    let mut ranges = Vec::new();
+    let mut first = true;
+    loop {
+        let low = match chars.next()? {
+            ']' if !first => break,
+            '\\' => chars.next()?,
+            c => c,
+        };
+        first = false;
+        let rest = chars.as_str();
+        let high = if rest.starts_with('-') && !rest.starts_with("-]") && rest.len() > 1 {
+            chars.next();
+            chars.next()?
+        } else {
+            low
+        };
+        ranges.push((low, high));
+    }
+    Some(Token::Class { negated, ranges })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, name: &str) -> bool {
+        Glob::new(pattern).unwrap().is_match(name)
+    }
+
+    #[test]
+    fn stars_and_question_marks() {
+        assert!(matches("*.rs", "main.rs"));
+        assert!(matches("*.rs", ".rs"));
+        assert!(!matches("*.rs", "main.rs.bak"));
+        assert!(matches("*test*", "a_test_b"));
+        assert!(matches("a*b*c", "abbbc"));
+        assert!(!matches("a*b*c", "abbbcd"));
+        assert!(matches("?.txt", "a.txt"));
+        assert!(!matches("?.txt", "ab.txt"));
+        assert!(matches("*", ""));
+        assert!(matches("Cargo.toml", "Cargo.toml"));
+    }
+
+    #[test]
+    fn classes() {
+        assert!(matches("[abc].md", "b.md"));
+        assert!(!matches("[abc].md", "d.md"));
+        assert!(matches("file[0-9]", "file7"));
+        assert!(!matches("file[!0-9]", "file7"));
+        assert!(matches("[]]", "]"));
+        assert!(matches("[a-]", "-"));
+    }
+
+    #[test]
+    fn escapes_and_errors() {
+        assert!(matches("\\*.txt", "*.txt"));
+        assert!(!matches("\\*.txt", "a.txt"));
+        assert!(Glob::new("[abc").is_err());
+        assert!(Glob::new("abc\\").is_err());
+        assert_eq!(Glob::new("*.rs").unwrap().as_str(), "*.rs");
+    }
+
+    #[test]
+    fn non_ascii_names() {
+        assert!(matches("é?*.txt", "éàbc.txt"));
+    }
+
+    #[test]
+    fn expand_lists_matching_entries_in_order() {
+        let dir = std::env::temp_dir().join("minigrep-glob-expand");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for name in ["b.log", "a.log", "c.txt"] {
+            fs::write(dir.join(name), "").unwrap();
+        }
+
+        assert_eq!(
+            expand(&dir.join("*.log")),
+            [dir.join("a.log"), dir.join("b.log")]
+        );
+        assert_eq!(expand(&dir.join("?.txt")), [dir.join("c.txt")]);
+        for unchanged in ["c.txt", "*.md", "[abc"] {
+            assert_eq!(expand(&dir.join(unchanged)), [dir.join(unchanged)]);
+        }
+        let missing = dir.join("missing").join("*.log");
+        assert_eq!(expand(&missing), vec![missing]);
+    }
+}
@@ -0,0 +1,266 @@
+//! A single call searching files as the command line would, returning the
+//! matches instead of printing them.
+//!
+//! [`grep`] runs the very search [`run`](crate::run) does: paths are walked
+//! with the same rules for recursion, ignore files, hidden files, and sizes,
+//! compressed files and archive members are decompressed and decoded the
+//! same way, and an input that cannot be searched is passed over while the
+//! rest still are, to be returned as a [`MinigrepError`] after the search.
+//! Only the printing is left out. [`search_in_file`] does the same for a
+//! single file.
+
+use std::{io, ops::Range, path::Path, sync::Arc};
+
+use crate::{cli, config::Config, error::MinigrepError, escape, glob::Glob, metrics::Metrics};
+
+/// The options of a [`grep`] call, set by chaining, all off by default.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::grep::Options;
+///
+/// let options = Options::default().recursive(true).ignore_case(true);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    ignore_case: bool,
+    escapes: bool,
+    invert: bool,
+    recursive: bool,
+    max_depth: Option<usize>,
+    hidden: bool,
+    follow: bool,
+    no_ignore: bool,
+    include: Vec<Glob>,
+    exclude: Vec<Glob>,
+    max_filesize: Option<u64>,
+    search_zip: bool,
+    metrics: Option<Arc<Metrics>>,
+}
+
+impl Options {
+    /// Ignores case, as `/i` does.
+    pub fn ignore_case(mut self, ignore_case: bool) -> Options {
+        self.ignore_case = ignore_case;
+        self
+    }
+
+    /// Interprets the escape sequences of the query, as `--escapes` does;
+    /// see [`unescape`](crate::escape::unescape).
+    pub fn escapes(mut self, escapes: bool) -> Options {
+        self.escapes = escapes;
+        self
+    }
+
+    /// Selects the lines that do not match, as `-v` does.
+    pub fn invert(mut self, invert: bool) -> Options {
+        self.invert = invert;
+        self
+    }
+
+    /// Searches directories recursively, as `-r` does.
+    pub fn recursive(mut self, recursive: bool) -> Options {
+        self.recursive = recursive;
+        self
+    }
+
+    /// Descends at most `depth` directory levels below each path, as
+    /// `--max-depth` does.
+    pub fn max_depth(mut self, depth: usize) -> Options {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Also searches hidden files and directories, as `--hidden` does.
+    pub fn hidden(mut self, hidden: bool) -> Options {
+        self.hidden = hidden;
+        self
+    }
+
+    /// Follows symbolic links, as `--follow` does.
+    pub fn follow(mut self, follow: bool) -> Options {
+        self.follow = follow;
+        self
+    }
+
+    /// Searches the paths listed in ignore files too, as `--no-ignore`
+    /// does.
+    pub fn no_ignore(mut self, no_ignore: bool) -> Options {
+        self.no_ignore = no_ignore;
+        self
+    }
+
+    /// Only searches the files found by recursion whose names match
+    /// `glob`, or one of the others given, as `--include` does.
+    pub fn include(mut self, glob: Glob) -> Options {
+        self.include.push(glob);
+        self
+    }
+
+    /// Skips the files found by recursion whose names match `glob`, as
+    /// `--exclude` does.
+    pub fn exclude(mut self, glob: Glob) -> Options {
+        self.exclude.push(glob);
+        self
+    }
+
+    /// Skips files larger than `bytes`, as `--max-filesize` does.
+    pub fn max_filesize(mut self, bytes: u64) -> Options {
+        self.max_filesize = Some(bytes);
+        self
+    }
+
+    /// Decompresses the files found by recursion and searches the members
+    /// of archives, as `--search-zip` does.
+    pub fn search_zip(mut self, search_zip: bool) -> Options {
+        self.search_zip = search_zip;
+        self
+    }
+
+    /// Adds the search, and the lines it reads and selects, to `metrics`,
+    /// which can be shared with other searches.
+    pub fn metrics(mut self, metrics: Arc<Metrics>) -> Options {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Builds the configuration of a search of `paths` for `query` with
+    /// these options, or returns why the query is invalid.
+    fn config(self, query: &str, paths: &[impl AsRef<Path>]) -> Result<Config, String> {
+        let query = if self.escapes {
+            escape::unescape(query)?
+        } else {
+            query.to_string()
+        };
+        let file_paths = paths.iter().map(|path| path.as_ref().to_path_buf());
+        // cmd.exe passes wildcards through, and the command line expands
+        // them, so a path written for it does the same here.
+        #[cfg(windows)]
+        let file_paths = file_paths.flat_map(|path| crate::glob::expand(&path));
+        let mut config = Config::new(&query, file_paths.collect());
+        config.ignore_case = self.ignore_case;
+        config.invert = self.invert;
+        config.recursive = self.recursive;
+        config.max_depth = self.max_depth;
+        config.hidden = self.hidden;
+        config.follow = self.follow;
+        config.no_ignore = self.no_ignore;
+        config.include = self.include;
+        config.exclude = self.exclude;
+        config.max_filesize = self.max_filesize;
+        config.search_zip = self.search_zip;
+        config.metrics = self.metrics;
+        Ok(config)
+    }
+}
+
+/// The selected lines of one input.
+///
+/// - `path`: The name of the input, as the command line prints it; a
+///   member of an archive is called `archive!member`.
+/// - `lines`: The lines selected, in order.
+/// - `searched_lines`: How many lines the input has, selected or not.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileMatches {
+    pub path: String,
+    pub lines: Vec<MatchedLine>,
+    pub searched_lines: u64,
+}
+
+/// A selected line.
+///
+/// - `line_number`: The 1-based number of the line in its input.
+/// - `text`: The line, without its terminator, with invalid UTF-8 replaced
+///   by U+FFFD.
+/// - `ranges`: The byte ranges of `text` the query occurs at, which are
+///   none when lines that do not match are selected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchedLine {
+    pub line_number: usize,
+    pub text: String,
+    pub ranges: Vec<Range<usize>>,
+}
+
+/// Searches `paths` for `query` as `minigrep` would with `options`, and
+/// returns the selected lines of each input that has any, in the order the
+/// command line prints them.
+///
+/// # Errors
+/// Returns a [`MinigrepError`] for each input that could not be searched,
+/// in the order the command line warns about them, or with
+/// [`Options::escapes`], a single one if the query has an invalid escape.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::grep::{grep, Options};
+/// use std::fs;
+///
+/// let dir = std::env::temp_dir().join("minigrep-grep-example");
+/// fs::create_dir_all(&dir).unwrap();
+/// fs::write(dir.join("notes.txt"), "todo: tests\ndone\n").unwrap();
+///
+/// let found = grep(&[&dir], "TODO", Options::default().recursive(true).ignore_case(true)).unwrap();
+/// assert_eq!(found[0].lines[0].text, "todo: tests");
+/// assert_eq!(found[0].lines[0].ranges, vec![0..4]);
+/// ```
+pub fn grep(
+    paths: &[impl AsRef<Path>],
+    query: &str,
+    options: Options,
+) -> Result<Vec<FileMatches>, Vec<MinigrepError>> {
+    let config = options
+        .config(query, paths)
+        .map_err(|message| vec![invalid_query("(query)", message)])?;
+    let collected = cli::collect(&config).map_err(|error| vec![error])?;
+    if !collected.failures.is_empty() {
+        return Err(collected.failures);
+    }
+    let matches = collected.matches.into_iter();
+    Ok(matches.filter(|file| !file.lines.is_empty()).collect())
+}
+
+/// Searches the file at `path` for `query` as `minigrep` would with
+/// `options`, reading it a line at a time, and returns its selected lines,
+/// which may be none.
+///
+/// The file is decompressed if it is compressed, as on the command line,
+/// but an archive is searched as it is; the recursion options do not
+/// apply, and [`grep`] searches the members of archives.
+///
+/// # Errors
+/// Returns a [`MinigrepError`] if the file cannot be searched: it does not
+/// exist, cannot be read, or is a directory, or it is skipped for being
+/// larger than [`Options::max_filesize`], a device, or binary, the error
+/// naming the reason, or with [`Options::escapes`], if the query has an
+/// invalid escape.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::{grep::Options, search_in_file};
+///
+/// # let path = std::env::temp_dir().join("minigrep-search-in-file.txt");
+/// # std::fs::write(&path, "fn main() {}\n// TODO: tests\n").unwrap();
+/// let found = search_in_file("TODO", &path, &Options::default())?;
+/// for line in &found.lines {
+///     println!("{}: {}", line.line_number, line.text);
+/// }
+/// assert_eq!((found.lines[0].line_number, found.searched_lines), (2, 2));
+/// # Ok::<(), minigrep_cli_tool::error::MinigrepError>(())
+/// ```
+pub fn search_in_file(
+    query: &str,
+    path: impl AsRef<Path>,
+    options: &Options,
+) -> Result<FileMatches, MinigrepError> {
+    let path = path.as_ref();
+    let options = options.clone().recursive(false).search_zip(false);
+    let config = options
+        .config(query, &[path])
+        .map_err(|message| invalid_query(&path.display().to_string(), message))?;
+    cli::search_file(&config, path)
+}
+
+/// The error for a query with an invalid escape, naming it as `name`.
+fn invalid_query(name: &str, message: String) -> MinigrepError {
+    MinigrepError::new(name, io::Error::new(io::ErrorKind::InvalidInput, message))
+}
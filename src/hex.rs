@@ -0,0 +1,286 @@
+//! The byte patterns of `--hex`, searched for in the raw bytes of inputs
+//! whose lines mean nothing, such as binaries.
+
+use std::{
+    fmt::Write as _,
+    io::{self, Read},
+};
+
+/// How many bytes are read from the input at a time.
+const CHUNK: usize = 64 * 1024;
+
+/// Parses a `--hex` query: bytes of two hex digits each, in either case,
+/// separated by whitespace or not, as in `DE AD BE EF` or `deadbeef`.
+///
+/// # Errors
+/// Returns an error if the pattern is empty, has a character that is not a
+/// hex digit, or a group of an odd number of digits.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::hex::parse_pattern;
+///
+/// assert_eq!(parse_pattern("DE AD beef"), Ok(vec![0xde, 0xad, 0xbe, 0xef]));
+/// assert!(parse_pattern("DE A").is_err());
+/// ```
+pub fn parse_pattern(query: &str) -> Result<Vec<u8>, String> {
+    let mut pattern = Vec::new();
+    for group in query.split_whitespace() {
+        if let Some(invalid) = group.chars().find(|c| !c.is_ascii_hexdigit()) {
+            return Err(format!("Invalid hex digit '{invalid}' in '{group}'"));
+        }
+        if group.len() % 2 != 0 {
+            return Err(format!("Odd number of hex digits in '{group}'"));
+        }
+        for index in (0..group.len()).step_by(2) {
+            let byte = u8::from_str_radix(&group[index..index + 2], 16).expect("two hex digits");
+            pattern.push(byte);
+        }
+    }
+    if pattern.is_empty() {
+        return Err(String::from("The hex pattern is empty"));
+    }
+    Ok(pattern)
+}
+
+/// Formats `bytes` as uppercase hex with a space between bytes, as in
+/// `DE AD BE EF`.
+pub fn dump(bytes: &[u8]) -> String {
+    let mut dump = String::with_capacity(bytes.len() * 3);
+    for (index, byte) in bytes.iter().enumerate() {
+        if index > 0 {
+            dump.push(' ');
+        }
+        let _ = write!(dump, "{byte:02X}");
+    }
+    dump
+}
+
+/// An occurrence of a pattern found by [`Occurrences`].
+///
+/// - `offset`: The offset of its first byte in the input.
+/// - `before`: Up to the context's length of bytes just before it.
+/// - `after`: Up to the context's length of bytes just after it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Occurrence {
+    pub offset: u64,
+    pub before: Vec<u8>,
+    pub after: Vec<u8>,
+}
+
+/// The occurrences of a pattern in the bytes read from a reader, from left
+/// to right and never overlapping, as [`crate::match_ranges`] finds them in
+/// a line.
+///
+/// The input is read a chunk at a time, keeping only what an occurrence
+/// spanning chunks, or its context, needs.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::hex::Occurrences;
+///
+/// let input = b"\x00\xde\xad\x01\xde\xad".as_slice();
+/// let offsets: Vec<u64> = Occurrences::new(input, vec![0xde, 0xad], 0)
+///     .map(|occurrence| occurrence.unwrap().offset)
+///     .collect();
+/// assert_eq!(offsets, [1, 4]);
+/// ```
+pub struct Occurrences<R> {
+    reader: R,
+    pattern: Vec<u8>,
+    context: usize,
+    /// The bytes read and still needed, starting at `base` in the input.
+    window: Vec<u8>,
+    base: u64,
+    /// Where in `window` to look for the next occurrence.
+    position: usize,
+    /// Whether `reader` has been read to the end, or has failed.
+    eof: bool,
+}
+
+impl<R: Read> Occurrences<R> {
+    /// Finds the occurrences of `pattern`, which must not be empty, in the
+    /// bytes of `reader`, along with `context` bytes on each side.
+    pub fn new(reader: R, pattern: Vec<u8>, context: usize) -> Occurrences<R> {
+        assert!(!pattern.is_empty(), "the pattern is empty");
+        Occurrences {
+            reader,
+            pattern,
+            context,
+            window: Vec::new(),
+            base: 0,
+            position: 0,
+            eof: false,
+        }
+    }
+}
+
+impl<R: Read> Iterator for Occurrences<R> {
+    type Item = io::Result<Occurrence>;
+
+    fn next(&mut self) -> Option<io::Result<Occurrence>> {
+        let length = self.pattern.len();
+        loop {
+            // Until the end, an occurrence is only reported once its context
+            // after it has been read.
+            let limit = if self.eof {
+                self.window.len()
+            } else {
+                self.window.len().saturating_sub(self.context)
+            };
+            let found = self
+                .window
+                .get(self.position..limit)
+                .and_then(|unsearched| {
+                    unsearched
+                        .windows(length)
+                        .position(|bytes| bytes == self.pattern)
+                });
+            if let Some(found) = found {
+                let start = self.position + found;
+                let end = start + length;
+                self.position = end;
+                let after_end = (end + self.context).min(self.window.len());
+                return Some(Ok(Occurrence {
+                    offset: self.base + start as u64,
+                    before: self.window[start.saturating_sub(self.context)..start].to_vec(),
+                    after: self.window[end..after_end].to_vec(),
+                }));
+            }
+            if self.eof {
+                return None;
+            }
+            // An occurrence may still start in the last bytes searched.
+            self.position = self.position.max((limit + 1).saturating_sub(length));
+            let unneeded = self.position.saturating_sub(self.context);
+            self.window.drain(..unneeded);
+            self.base += unneeded as u64;
+            self.position -= unneeded;
+
+            let filled = self.window.len();
+            self.window.resize(filled + CHUNK, 0);
+            let read = loop {
+                match self.reader.read(&mut self.window[filled..]) {
+                    Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                    read => break read,
+                }
+            };
+            match read {
+                Ok(read) => {
+                    self.window.truncate(filled + read);
+                    self.eof = read == 0;
+                }
+                Err(error) => {
+                    self.window.truncate(filled);
+                    self.eof = true;
+                    return Some(Err(error));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A reader handing out at most one byte at a time, so that every
+    /// occurrence spans reads.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let Some((first, rest)) = self.0.split_first() else {
+                return Ok(0);
+            };
+            buf[0] = *first;
+            self.0 = rest;
+            Ok(1)
+        }
+    }
+
+    fn occurrences(reader: impl Read, pattern: &[u8], context: usize) -> Vec<Occurrence> {
+        Occurrences::new(reader, pattern.to_vec(), context)
+            .collect::<io::Result<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn patterns_are_parsed_or_rejected() {
+        assert_eq!(parse_pattern(" de\tAD\n"), Ok(vec![0xde, 0xad]));
+        assert_eq!(parse_pattern("00ff 7F"), Ok(vec![0, 0xff, 0x7f]));
+        let errors = [
+            ("", "The hex pattern is empty"),
+            ("  ", "The hex pattern is empty"),
+            ("DE A", "Odd number of hex digits in 'A'"),
+            ("DEA", "Odd number of hex digits in 'DEA'"),
+            ("DG", "Invalid hex digit 'G' in 'DG'"),
+            ("0xDE", "Invalid hex digit 'x' in '0xDE'"),
+        ];
+        for (query, error) in errors {
+            assert_eq!(parse_pattern(query), Err(String::from(error)), "{query:?}");
+        }
+    }
+
+    #[test]
+    fn bytes_are_dumped_in_uppercase() {
+        assert_eq!(dump(&[0xde, 0xad, 0x0a]), "DE AD 0A");
+        assert_eq!(dump(&[]), "");
+    }
+
+    #[test]
+    fn occurrences_never_overlap_and_span_reads() {
+        let input = b"\xaa\xaa\xaa\x00\xaa\xaa";
+        let offsets = |reader| -> Vec<u64> {
+            occurrences(reader, &[0xaa, 0xaa], 0)
+                .iter()
+                .map(|occurrence| occurrence.offset)
+                .collect()
+        };
+        assert_eq!(offsets(Box::new(input.as_slice()) as Box<dyn Read>), [0, 4]);
+        assert_eq!(offsets(Box::new(Trickle(input))), [0, 4]);
+        assert!(occurrences(b"\xaa".as_slice(), &[0xaa, 0xaa], 0).is_empty());
+        assert!(occurrences(b"".as_slice(), &[0xaa], 3).is_empty());
+    }
+
+    #[test]
+    fn context_is_cut_short_at_the_ends() {
+        let input = b"\x01\xde\xad\x02\x03\x04\xde\xad";
+        for found in [
+            occurrences(input.as_slice(), &[0xde, 0xad], 2),
+            occurrences(Trickle(input), &[0xde, 0xad], 2),
+        ] {
+            assert_eq!(
+                found,
+                [
+                    Occurrence {
+                        offset: 1,
+                        before: vec![0x01],
+                        after: vec![0x02, 0x03],
+                    },
+                    Occurrence {
+                        offset: 6,
+                        before: vec![0x03, 0x04],
+                        after: vec![],
+                    },
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn offsets_count_past_the_first_chunk() {
+        let mut input = vec![0; CHUNK * 3 + 5];
+        input[CHUNK - 1] = 0xde;
+        input[CHUNK] = 0xad;
+        input[CHUNK * 3 + 3] = 0xde;
+        input[CHUNK * 3 + 4] = 0xad;
+        let found = occurrences(input.as_slice(), &[0xde, 0xad], 1);
+        let offsets: Vec<u64> = found.iter().map(|occurrence| occurrence.offset).collect();
+        assert_eq!(offsets, [CHUNK as u64 - 1, CHUNK as u64 * 3 + 3]);
+        assert_eq!(
+            (found[0].before.as_slice(), found[0].after.as_slice()),
+            (&[0][..], &[0][..])
+        );
+    }
+}
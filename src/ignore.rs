@@ -0,0 +1,243 @@
+//! Ignore files in the style of `.gitignore`, which `-r` uses to skip the
+//! paths they list.
+//!
+//! A useful subset of the gitignore syntax is supported:
+//!
+//! - Blank lines match nothing, and lines starting with `#` are comments.
+//!   Trailing spaces are dropped unless escaped with a backslash.
+//! - Each line is a [`Glob`] matched against path components, so `*` never
+//!   matches a `/`. A component that is exactly `**` matches any number of
+//!   directories, including none.
+//! - A pattern containing a `/` anywhere except at its end is matched
+//!   against the whole path relative to the directory of the ignore file.
+//!   One without is matched against the name of a file at any depth.
+//! - A trailing `/` makes the pattern match only directories.
+//! - A leading `!` re-includes paths an earlier pattern ignored. A path
+//!   inside an ignored directory cannot be re-included, since the directory
+//!   is never walked. Use `\!` or `\#` for a pattern starting with that
+//!   character.
+//!
+//! Within one file, the last matching pattern decides.
+
+use std::path::{Component, Path, PathBuf};
+
+use crate::glob::Glob;
+
+/// A component of a [`Pattern`].
+#[derive(Debug, Clone)]
+enum Part {
+    /// A glob matching a single file or directory name.
+    Name(Glob),
+    /// `**`, matching any number of directories.
+    AnyDirs,
+}
+
+/// A single line of an ignore file.
+#[derive(Debug, Clone)]
+struct Pattern {
+    /// Whether the line started with `!`.
+    negated: bool,
+    /// Whether the line ended with `/`.
+    dir_only: bool,
+    parts: Vec<Part>,
+}
+
+impl Pattern {
+    /// Parses one line of an ignore file, returning `None` for blank lines,
+    /// comments, and lines that are not valid globs.
+    fn parse(line: &str) -> Option<Pattern> {
+        let trimmed = line.trim_end_matches(' ');
+        // Keep an escaped space, which the glob then reads as a literal one.
+        let line = if trimmed.ends_with('\\') && trimmed.len() < line.len() {
+            &line[..trimmed.len() + 1]
+        } else {
+            trimmed
+        };
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let (dir_only, line) = match line.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        if line.is_empty() {
+            return None;
+        }
+
+        let mut parts = Vec::new();
+        let line = match line.strip_prefix('/') {
+            Some(rest) => rest,
+            None if !line.contains('/') => {
+                parts.push(Part::AnyDirs);
+                line
+            }
+            None => line,
+        };
+        for component in line.split('/') {
+            parts.push(match component {
+                "**" => Part::AnyDirs,
+                component => Part::Name(Glob::new(component).ok()?),
+            });
+        }
+        Some(Pattern {
+            negated,
+            dir_only,
+            parts,
+        })
+    }
+}
+
+/// Returns whether `parts` match the whole of `names`.
+fn matches(parts: &[Part], names: &[String]) -> bool {
+    match parts.split_first() {
+        None => names.is_empty(),
+        // A trailing `**` matches everything inside, but not the directory.
+        Some((Part::AnyDirs, [])) => !names.is_empty(),
+        Some((Part::AnyDirs, rest)) => (0..=names.len()).any(|skip| matches(rest, &names[skip..])),
+        Some((Part::Name(glob), rest)) => names
+            .split_first()
+            .is_some_and(|(name, names)| glob.is_match(name) && matches(rest, names)),
+    }
+}
+
+/// The patterns of one ignore file, which apply to the paths below the
+/// directory it is in.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::ignore::Ignore;
+/// use std::path::Path;
+///
+/// let ignore = Ignore::parse(Path::new("repo"), "target/\n*.log\n!keep.log\n");
+/// assert_eq!(ignore.matched(Path::new("repo/target"), true), Some(true));
+/// assert_eq!(ignore.matched(Path::new("repo/src/debug.log"), false), Some(true));
+/// assert_eq!(ignore.matched(Path::new("repo/keep.log"), false), Some(false));
+/// assert_eq!(ignore.matched(Path::new("repo/src/main.rs"), false), None);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Ignore {
+    dir: PathBuf,
+    patterns: Vec<Pattern>,
+}
+
+impl Ignore {
+    /// Parses the `contents` of an ignore file in the directory `dir`.
+    ///
+    /// Lines that are not valid globs, such as one with an unclosed `[`, are
+    /// skipped, as git does.
+    pub fn parse(dir: &Path, contents: &str) -> Ignore {
+        Ignore {
+            dir: dir.to_path_buf(),
+            patterns: contents.lines().filter_map(Pattern::parse).collect(),
+        }
+    }
+
+    /// Returns whether the file or directory at `path` is ignored: `Some(true)`
+    /// if the last pattern matching it ignores it, `Some(false)` if that is a
+    /// negated pattern, and `None` if no pattern matches or `path` is not
+    /// below the directory of this file.
+    pub fn matched(&self, path: &Path, is_dir: bool) -> Option<bool> {
+        let relative = path.strip_prefix(&self.dir).ok()?;
+        let names: Vec<String> = relative
+            .components()
+            .map(|component| match component {
+                Component::Normal(name) => Some(name.to_string_lossy().into_owned()),
+                _ => None,
+            })
+            .collect::<Option<_>>()?;
+        if names.is_empty() {
+            return None;
+        }
+        self.patterns
+            .iter()
+            .rev()
+            .find(|pattern| (is_dir || !pattern.dir_only) && matches(&pattern.parts, &names))
+            .map(|pattern| !pattern.negated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ignored(contents: &str, path: &str, is_dir: bool) -> Option<bool> {
+        Ignore::parse(Path::new("root"), contents).matched(&Path::new("root").join(path), is_dir)
+    }
+
+    #[test]
+    fn blank_lines_and_comments_match_nothing() {
+        let contents = "\n# a comment\n   \n\\#literal\n";
+        assert_eq!(ignored(contents, "# a comment", false), None);
+        assert_eq!(ignored(contents, "#literal", false), Some(true));
+        assert_eq!(Ignore::parse(Path::new("root"), contents).patterns.len(), 1);
+    }
+
+    #[test]
+    fn unanchored_patterns_match_names_at_any_depth() {
+        assert_eq!(ignored("*.o", "main.o", false), Some(true));
+        assert_eq!(ignored("*.o", "src/deep/main.o", false), Some(true));
+        assert_eq!(ignored("*.o", "main.c", false), None);
+        assert_eq!(ignored("build", "a/build", true), Some(true));
+        assert_eq!(ignored("build", "a/build", false), Some(true));
+    }
+
+    #[test]
+    fn slashes_anchor_patterns() {
+        assert_eq!(ignored("/build", "build", true), Some(true));
+        assert_eq!(ignored("/build", "src/build", true), None);
+        assert_eq!(ignored("doc/*.html", "doc/index.html", false), Some(true));
+        assert_eq!(ignored("doc/*.html", "doc/api/index.html", false), None);
+        assert_eq!(ignored("doc/*.html", "src/doc/index.html", false), None);
+        assert_eq!(ignored("*/gen", "src/gen", true), Some(true));
+        assert_eq!(ignored("*/gen", "gen", true), None);
+    }
+
+    #[test]
+    fn trailing_slashes_match_only_directories() {
+        assert_eq!(ignored("target/", "target", true), Some(true));
+        assert_eq!(ignored("target/", "target", false), None);
+        assert_eq!(ignored("target/", "nested/target", true), Some(true));
+    }
+
+    #[test]
+    fn double_stars_match_any_number_of_directories() {
+        assert_eq!(ignored("**/logs", "logs", true), Some(true));
+        assert_eq!(ignored("**/logs", "a/b/logs", true), Some(true));
+        assert_eq!(ignored("a/**/b", "a/b", true), Some(true));
+        assert_eq!(ignored("a/**/b", "a/x/y/b", true), Some(true));
+        assert_eq!(ignored("a/**", "a/x/y", false), Some(true));
+        assert_eq!(ignored("a/**", "a", true), None);
+    }
+
+    #[test]
+    fn the_last_matching_pattern_wins() {
+        let contents = "*.log\n!important.log\n";
+        assert_eq!(ignored(contents, "debug.log", false), Some(true));
+        assert_eq!(ignored(contents, "important.log", false), Some(false));
+        assert_eq!(
+            ignored("!important.log\n*.log", "important.log", false),
+            Some(true)
+        );
+        assert_eq!(ignored("\\!bang", "!bang", false), Some(true));
+    }
+
+    #[test]
+    fn trailing_spaces_are_trimmed_unless_escaped() {
+        assert_eq!(ignored("notes.txt   ", "notes.txt", false), Some(true));
+        assert_eq!(ignored("odd\\ ", "odd ", false), Some(true));
+        assert_eq!(ignored("odd\\ ", "odd", false), None);
+    }
+
+    #[test]
+    fn paths_outside_the_directory_are_not_matched() {
+        let ignore = Ignore::parse(Path::new("root/sub"), "*");
+        assert_eq!(ignore.matched(Path::new("root/other.txt"), false), None);
+        assert_eq!(ignore.matched(Path::new("root/sub"), true), None);
+        assert_eq!(ignore.matched(Path::new("root/sub/x"), false), Some(true));
+        assert!(Pattern::parse("[oops").is_none());
+    }
+}
@@ -0,0 +1,248 @@
+//! The JSON Lines events printed by `--json`, modelled on ripgrep's so
+//! that editor integrations can consume either.
+//!
+//! Every event is a single-line JSON object with a `type` field, printed as
+//! soon as it happens. The events of each input with a selected line come
+//! together, as a `begin`, its `match` events, and an `end`; inputs with no
+//! selected line print none. A `summary` comes last.
+//!
+//! - `begin`: An input is about to print its first match, with its `path`.
+//! - `match`: A selected line, with `path`, `line_number` (1-based, or
+//!   `null` past a `--start-byte` without `--count-from-start`),
+//!   `absolute_offset` (of the line's first byte in the input), `text` (the
+//!   line without its terminator), and `submatches`, an array of
+//!   `{"start": S, "end": E}` byte offsets of each occurrence within `text`.
+//!   When the line is not valid UTF-8, each invalid sequence is replaced by
+//!   U+FFFD in `text`, the offsets within it refer to that replaced text,
+//!   and the event carries `"lossy": true`; the field is absent otherwise.
+//! - `end`: The input is finished, with its `path` and its own
+//!   `lines_scanned`, `matched_lines`, and `bytes_searched`.
+//! - `error`: An input could not be searched, or only in part, with its
+//!   `path`, the `kind` of error as the [`ErrorKind`](crate::error::ErrorKind) of its
+//!   [`MinigrepError`] names it, and a human-readable `message`. Errors
+//!   come where they happen: before the first `begin` for paths that cannot
+//!   be walked or are too large, in place of the `begin` of an input
+//!   skipped as binary, and after an input's `end`, if it has one, for
+//!   invalid UTF-8.
+//! - `summary`: The last event, with the totals `files_searched`,
+//!   `files_skipped`, `lines_scanned`, and `matched_lines`, and with
+//!   `--start-byte`, `end_offsets`, an array of `{"path": P, "offset": N}`
+//!   giving the offset each input was read to, where the next search of
+//!   it can start.
+
+use std::{fmt::Write, ops::Range};
+
+use crate::{error::MinigrepError, stats::SearchStats};
+
+/// Returns `value` as a quoted JSON string.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::json::string;
+///
+/// assert_eq!(string("say \"hi\"\n"), r#""say \"hi\"\n""#);
+/// ```
+pub fn string(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c < ' ' || c == '\u{7f}' => {
+                let _ = write!(quoted, "\\u{:04x}", c as u32);
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Builds the `begin` event of the input called `path`.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::json::begin_record;
+///
+/// assert_eq!(begin_record("poem.txt"), r#"{"type":"begin","path":"poem.txt"}"#);
+/// ```
+pub fn begin_record(path: &str) -> String {
+    format!(r#"{{"type":"begin","path":{}}}"#, string(path))
+}
+
+/// Builds the `match` event of a selected line.
+///
+/// # Arguments
+/// - `path`: The name of the input the line is in.
+/// - `line_number`: The 1-based number of the line, if known.
+/// - `absolute_offset`: The offset of the line's first byte in the input.
+/// - `text`: The line, without its terminator.
+/// - `submatches`: The byte ranges of each occurrence within `text`.
+/// - `lossy`: Whether `text` was decoded lossily from invalid UTF-8.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::json::match_record;
+///
+/// assert_eq!(
+///     match_record("poem.txt", Some(2), 8, "a rust rust", &[2..6, 7..11], false),
+///     r#"{"type":"match","path":"poem.txt","line_number":2,"absolute_offset":8,"text":"a rust rust","submatches":[{"start":2,"end":6},{"start":7,"end":11}]}"#
+/// );
+/// ```
+pub fn match_record(
+    path: &str,
+    line_number: Option<usize>,
+    absolute_offset: u64,
+    text: &str,
+    submatches: &[Range<usize>],
+    lossy: bool,
+) -> String {
+    let submatches = submatches
+        .iter()
+        .map(|range| format!(r#"{{"start":{},"end":{}}}"#, range.start, range.end))
+        .collect::<Vec<_>>()
+        .join(",");
+    let line_number = line_number.map_or(String::from("null"), |number| number.to_string());
+    let mut record = format!(
+        r#"{{"type":"match","path":{},"line_number":{line_number},"absolute_offset":{absolute_offset},"text":{},"submatches":[{submatches}]"#,
+        string(path),
+        string(text),
+    );
+    if lossy {
+        record.push_str(r#","lossy":true"#);
+    }
+    record.push('}');
+    record
+}
+
+/// Builds the `end` event of the input called `path`, with the number of
+/// lines, selected lines, and bytes searched in it.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::json::end_record;
+///
+/// assert_eq!(
+///     end_record("poem.txt", 10, 2, 120),
+///     r#"{"type":"end","path":"poem.txt","lines_scanned":10,"matched_lines":2,"bytes_searched":120}"#
+/// );
+/// ```
+pub fn end_record(
+    path: &str,
+    lines_scanned: u64,
+    matched_lines: u64,
+    bytes_searched: u64,
+) -> String {
+    format!(
+        r#"{{"type":"end","path":{},"lines_scanned":{lines_scanned},"matched_lines":{matched_lines},"bytes_searched":{bytes_searched}}}"#,
+        string(path),
+    )
+}
+
+/// Builds the `error` event of `error`, with the kind its variant stands
+/// for.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::{error::MinigrepError, json::error_record};
+/// use std::io;
+///
+/// let source = io::Error::new(io::ErrorKind::FileTooLarge, "larger than the maximum file size");
+/// assert_eq!(
+///     error_record(&MinigrepError::new("big.log", source)),
+///     r#"{"type":"error","path":"big.log","kind":"too_large","message":"larger than the maximum file size"}"#
+/// );
+/// ```
+pub fn error_record(error: &MinigrepError) -> String {
+    format!(
+        r#"{{"type":"error","path":{},"kind":{},"message":{}}}"#,
+        string(error.path()),
+        string(error.kind().name()),
+        string(&error.reason()),
+    )
+}
+
+/// Builds the `summary` event ending the output.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::{json::summary_record, stats::SearchStats};
+///
+/// let mut stats = SearchStats::default();
+/// stats.record_file(10, 2);
+/// assert_eq!(
+///     summary_record(&stats),
+///     r#"{"type":"summary","files_searched":1,"files_skipped":0,"lines_scanned":10,"matched_lines":2}"#
+/// );
+/// ```
+pub fn summary_record(stats: &SearchStats) -> String {
+    let mut record = format!(
+        r#"{{"type":"summary","files_searched":{},"files_skipped":{},"lines_scanned":{},"matched_lines":{}"#,
+        stats.files_searched,
+        stats.files_skipped(),
+        stats.lines_scanned,
+        stats.matched_lines,
+    );
+    if !stats.end_offsets.is_empty() {
+        let offsets = stats
+            .end_offsets
+            .iter()
+            .map(|(path, offset)| format!(r#"{{"path":{},"offset":{offset}}}"#, string(path)))
+            .collect::<Vec<_>>()
+            .join(",");
+        record.push_str(&format!(r#","end_offsets":[{offsets}]"#));
+    }
+    record.push('}');
+    record
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+
+    #[test]
+    fn strings_escape_control_characters() {
+        assert_eq!(string(""), r#""""#);
+        assert_eq!(string("a\\b\tc"), r#""a\\b\tc""#);
+        assert_eq!(string("\u{1}\u{1b}[0m\u{7f}"), r#""\u0001\u001b[0m\u007f""#);
+        assert_eq!(string("héllo ✓"), "\"héllo ✓\"");
+    }
+
+    #[test]
+    fn lossy_records_are_flagged() {
+        assert_eq!(
+            match_record("-", Some(1), 0, "\u{fffd}x", &[], true),
+            r#"{"type":"match","path":"-","line_number":1,"absolute_offset":0,"text":"�x","submatches":[],"lossy":true}"#
+        );
+    }
+
+    #[test]
+    fn unknown_line_numbers_and_end_offsets_are_written() {
+        assert!(
+            match_record("a.log", None, 4096, "x", &[], false)
+                .contains(r#""line_number":null,"absolute_offset":4096,"#)
+        );
+        let mut stats = SearchStats::default();
+        stats.record_end_offset("a.log", 4100);
+        assert!(
+            summary_record(&stats)
+                .ends_with(r#""matched_lines":0,"end_offsets":[{"path":"a.log","offset":4100}]}"#)
+        );
+    }
+
+    #[test]
+    fn error_records_name_their_kind() {
+        assert_eq!(
+            error_record(&MinigrepError::new(
+                "a \"b\"",
+                io::Error::new(io::ErrorKind::PermissionDenied, "denied")
+            )),
+            r#"{"type":"error","path":"a \"b\"","kind":"permission_denied","message":"denied"}"#
+        );
+    }
+}
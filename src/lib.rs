@@ -2,9 +2,25 @@
 //!
 //! A lightweight library module that powers the MiniGrep CLI tool.
 //!
-//! It provides two main functions for searching within text:
+//! It provides the following functions for searching within text:
 //! - `search` (case-sensitive)
 //! - `search_case_insensitive` (case-insensitive)
+//! - `search_with_line_numbers` (either, reporting line numbers)
+//! - `records` (splitting text into lines, or records with another terminator)
+//! - `read_records` (the same, reading the records from a stream one at a time)
+//! - `line_matches` (whether a single line contains the query)
+//! - `match_ranges` (the byte ranges of each occurrence within a line)
+//! - `count_matches` (how many occurrences there are within a line)
+//!
+//! The [`Matcher`] trait wraps the last three, for a query string or an
+//! [`expr::Expr`] alike.
+//!
+//! It also provides the whole search the binary runs: a [`Config`], built
+//! from command-line arguments or field by field, is handed to [`run`], which
+//! searches its inputs and prints the results to standard output, or to
+//! [`run_with_writer`], which prints them wherever it is given. For scripts,
+//! [`grep()`] runs the same search in a single call and returns the matches
+//! instead of printing them.
 //!
 //! # Examples
 //! ```
@@ -20,6 +36,68 @@
 //! let matches_insensitive: Vec<&str> = search_case_insensitive(query, contents).collect();
 //! ```
 
+pub mod between;
+pub mod bytes;
+pub mod cli;
+pub mod color;
+pub mod completions;
+pub mod config;
+pub mod context;
+pub mod csv;
+pub mod decompress;
+pub mod dirs;
+pub mod edit;
+pub mod encoding;
+pub mod error;
+pub mod escape;
+pub mod exit;
+pub mod expr;
+pub mod glob;
+pub mod grep;
+pub mod hex;
+pub mod ignore;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod log;
+pub mod metrics;
+pub mod mmap;
+pub mod normalize;
+pub mod options;
+pub mod output;
+pub mod pager;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+pub mod preprocess;
+pub mod progress;
+pub mod quote;
+pub mod render;
+pub mod replace;
+pub mod resume;
+#[cfg(feature = "json")]
+pub mod sarif;
+pub mod snippet;
+pub mod stats;
+pub mod strings;
+pub mod tally;
+#[cfg(feature = "tar")]
+pub mod tar;
+pub mod timestamp;
+pub mod types;
+pub mod walk;
+pub mod watch;
+pub mod wc;
+#[cfg(feature = "zip")]
+pub mod zip;
+
+pub use cli::{RunSummary, run, run_with_writer};
+pub use config::Config;
+pub use grep::{grep, search_in_file};
+
+use std::{
+    io::{self, BufRead},
+    ops::Range,
+};
+
 /// Searches for lines containing the query string in the provided text.
 ///
 /// This function performs a **case-sensitive** search.
@@ -41,11 +119,8 @@
 /// let results: Vec<&str> = search(query, contents).collect();
 /// assert_eq!(results, vec!["Rust is safe."]);
 /// ```
-
 pub fn search<'a>(query: &str, contents: &'a str) -> impl Iterator<Item = &'a str> {
-    contents
-        .lines()
-        .filter(move |line| line.contains(query))
+    contents.lines().filter(move |line| line.contains(query))
 }
 
 /// Searches for lines containing the query string, ignoring case.
@@ -67,13 +142,427 @@ pub fn search<'a>(query: &str, contents: &'a str) -> impl Iterator<Item = &'a st
 /// let results: Vec<&str> = search_case_insensitive(query, contents).collect();
 /// assert_eq!(results, vec!["Rust:", "Trust in rust."]);
 /// ```
-
-pub fn search_case_insensitive<'a>(query: &str, contents: &'a str) -> impl Iterator<Item = &'a str> {
+pub fn search_case_insensitive<'a>(
+    query: &str,
+    contents: &'a str,
+) -> impl Iterator<Item = &'a str> {
     contents
         .lines()
         .filter(|line| line.to_lowercase().contains(&query.to_lowercase()))
 }
 
+/// Searches for lines containing the query string, keeping track of where each
+/// matching line was found.
+///
+/// # Arguments
+/// - `query`: The substring to look for.
+/// - `contents`: The text to search within.
+/// - `ignore_case`: If `true`, the search ignores case.
+///
+/// # Returns
+/// An iterator over `(line_number, line)` pairs, where line numbers start at 1.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::search_with_line_numbers;
+///
+/// let contents = "Rust:\nReally productive.\nTrust in rust.";
+///
+/// let results: Vec<(usize, &str)> = search_with_line_numbers("rust", contents, true).collect();
+/// assert_eq!(results, vec![(1, "Rust:"), (3, "Trust in rust.")]);
+/// ```
+pub fn search_with_line_numbers<'a>(
+    query: &'a str,
+    contents: &'a str,
+    ignore_case: bool,
+) -> impl Iterator<Item = (usize, &'a str)> {
+    contents
+        .lines()
+        .enumerate()
+        .filter(move |(_, line)| line_matches(query, line, ignore_case))
+        .map(|(index, line)| (index + 1, line))
+}
+
+/// Splits `contents` into records, each ended by `terminator`.
+///
+/// The terminators are not part of the records, and a terminator at the very
+/// end of `contents` does not start another, empty, record. With `'\n'` this
+/// is the same as [`str::lines`], so a `'\r'` before the terminator is
+/// dropped too; with any other terminator, records are returned verbatim,
+/// including any newlines they contain.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::records;
+///
+/// let contents = "first\nrecord\0second\0";
+/// let found: Vec<&str> = records(contents, '\0').collect();
+/// assert_eq!(found, vec!["first\nrecord", "second"]);
+/// ```
+pub fn records(contents: &str, terminator: char) -> impl Iterator<Item = &str> {
+    contents.split_terminator(terminator).map(move |record| {
+        if terminator == '\n' {
+            record.strip_suffix('\r').unwrap_or(record)
+        } else {
+            record
+        }
+    })
+}
+
+/// Reads records ended by `terminator` from `reader` one at a time, split
+/// the same way as by [`records`], so that a stream is searched as it
+/// arrives instead of being read whole first.
+///
+/// `terminator` must be an ASCII character. Iterating allocates a string
+/// per record; [`ReadRecords::next_record`] instead reuses one buffer, so
+/// that memory use stays proportional to the longest record.
+///
+/// # Errors
+/// Each record is an error if reading it fails or it is not valid UTF-8.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::read_records;
+///
+/// let input = "first\r\nsecond\n".as_bytes();
+/// let found: Vec<String> = read_records(input, '\n').map(Result::unwrap).collect();
+/// assert_eq!(found, vec!["first", "second"]);
+/// ```
+pub fn read_records<R: BufRead>(reader: R, terminator: char) -> ReadRecords<R> {
+    debug_assert!(terminator.is_ascii(), "terminator must be ASCII");
+    ReadRecords {
+        reader,
+        terminator,
+        buffer: Vec::new(),
+        offset: 0,
+        remaining: None,
+    }
+}
+
+/// The reader returned by [`read_records`].
+pub struct ReadRecords<R> {
+    reader: R,
+    terminator: char,
+    /// The last record read, reused for the next one.
+    buffer: Vec<u8>,
+    /// How many bytes have been read, terminators included.
+    offset: u64,
+    /// How many more records are read before the end is reported, if
+    /// limited by [`ReadRecords::limit`].
+    remaining: Option<usize>,
+}
+
+impl<R: BufRead> ReadRecords<R> {
+    /// The offset of the next record in the input: the number of bytes
+    /// read so far, including terminators and any `'\r'` dropped.
+    ///
+    /// # Examples
+    /// ```
+    /// use minigrep_cli_tool::read_records;
+    ///
+    /// let mut records = read_records("one\r\ntwo\n".as_bytes(), '\n');
+    /// records.next_bytes();
+    /// assert_eq!(records.offset(), 5);
+    /// ```
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Counts offsets from `offset` rather than 0, for a reader that starts
+    /// that far into its input.
+    ///
+    /// # Examples
+    /// ```
+    /// use minigrep_cli_tool::read_records;
+    ///
+    /// let mut records = read_records("two\n".as_bytes(), '\n').starting_at(4);
+    /// records.next_bytes();
+    /// assert_eq!(records.offset(), 8);
+    /// ```
+    pub fn starting_at(mut self, offset: u64) -> ReadRecords<R> {
+        self.offset = offset;
+        self
+    }
+
+    /// Reads no more than `records` records in all, as if the input ended
+    /// there, so that the rest of it is never read.
+    ///
+    /// # Examples
+    /// ```
+    /// use minigrep_cli_tool::read_records;
+    ///
+    /// let records = read_records("one\ntwo\nthree\n".as_bytes(), '\n').limit(2);
+    /// assert_eq!(records.count(), 2);
+    /// ```
+    pub fn limit(mut self, records: usize) -> ReadRecords<R> {
+        self.remaining = Some(records);
+        self
+    }
+
+    /// Reads past the next `records` records, which count towards any
+    /// [`ReadRecords::limit`], and returns how many there were before the
+    /// end, so that the rest can still be numbered from the start of the
+    /// input.
+    ///
+    /// # Errors
+    /// Returns an error if reading fails.
+    ///
+    /// # Examples
+    /// ```
+    /// use minigrep_cli_tool::read_records;
+    ///
+    /// let mut records = read_records("header\nrow\n".as_bytes(), '\n');
+    /// assert_eq!(records.skip_records(3).unwrap(), 2);
+    /// assert!(records.next_bytes().is_none());
+    /// ```
+    pub fn skip_records(&mut self, records: usize) -> io::Result<usize> {
+        for skipped in 0..records {
+            match self.next_bytes() {
+                Some(Ok(_)) => {}
+                Some(Err(error)) => return Err(error),
+                None => return Ok(skipped),
+            }
+        }
+        Ok(records)
+    }
+
+    /// Reads the next record as raw bytes, without its terminator, or
+    /// returns `None` at the end of the input.
+    ///
+    /// # Examples
+    /// ```
+    /// use minigrep_cli_tool::read_records;
+    ///
+    /// let mut records = read_records(b"ok\n\xff\n".as_slice(), '\n');
+    /// assert_eq!(records.next_bytes().unwrap().unwrap(), b"ok");
+    /// assert_eq!(records.next_bytes().unwrap().unwrap(), b"\xff");
+    /// assert!(records.next_bytes().is_none());
+    /// ```
+    pub fn next_bytes(&mut self) -> Option<io::Result<&[u8]>> {
+        self.buffer.clear();
+        if let Some(remaining) = &mut self.remaining {
+            *remaining = remaining.checked_sub(1)?;
+        }
+        match self
+            .reader
+            .read_until(self.terminator as u8, &mut self.buffer)
+        {
+            Ok(0) => return None,
+            Ok(read) => self.offset += read as u64,
+            Err(error) => return Some(Err(error)),
+        }
+        if self.buffer.last() == Some(&(self.terminator as u8)) {
+            self.buffer.pop();
+        }
+        if self.terminator == '\n' && self.buffer.last() == Some(&b'\r') {
+            self.buffer.pop();
+        }
+        Some(Ok(&self.buffer))
+    }
+
+    /// Reads the next record, without its terminator, or returns `None` at
+    /// the end of the input.
+    ///
+    /// # Errors
+    /// Returns an error if reading fails or the record is not valid UTF-8.
+    pub fn next_record(&mut self) -> Option<io::Result<&str>> {
+        Some(self.next_bytes()?.and_then(|bytes| {
+            std::str::from_utf8(bytes).map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "stream did not contain valid UTF-8",
+                )
+            })
+        }))
+    }
+}
+
+impl<R: BufRead> Iterator for ReadRecords<R> {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_record().map(|record| record.map(str::to_string))
+    }
+}
+
+/// Checks whether a single line contains the query string.
+///
+/// # Arguments
+/// - `query`: The substring to look for.
+/// - `line`: The line to search within.
+/// - `ignore_case`: If `true`, the search ignores case.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::line_matches;
+///
+/// assert!(line_matches("RUST", "Trust in rust.", true));
+/// assert!(!line_matches("RUST", "Trust in rust.", false));
+/// ```
+pub fn line_matches(query: &str, line: &str, ignore_case: bool) -> bool {
+    if ignore_case {
+        line.to_lowercase().contains(&query.to_lowercase())
+    } else {
+        line.contains(query)
+    }
+}
+
+/// Finds every occurrence of the query within a single line.
+///
+/// Occurrences are found from left to right and never overlap. With
+/// `ignore_case`, characters are compared by their lowercase forms while
+/// the returned ranges still refer to the original line, so they can be
+/// used to slice it even when case folding changes the byte length.
+///
+/// An empty query has no occurrences, even though it matches every line.
+///
+/// # Arguments
+/// - `query`: The substring to look for.
+/// - `line`: The line to search within.
+/// - `ignore_case`: If `true`, the search ignores case.
+///
+/// # Returns
+/// The byte ranges of each occurrence within `line`.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::match_ranges;
+///
+/// let ranges = match_ranges("rust", "Trust in Rust.", true);
+/// assert_eq!(ranges, vec![1..5, 9..13]);
+/// ```
+pub fn match_ranges(query: &str, line: &str, ignore_case: bool) -> Vec<Range<usize>> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+    if !ignore_case {
+        return line
+            .match_indices(query)
+            .map(|(start, found)| start..start + found.len())
+            .collect();
+    }
+
+    let folded_query: Vec<char> = query.chars().flat_map(char::to_lowercase).collect();
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    while let Some(next) = line[start..].chars().next() {
+        match folded_match_len(&folded_query, &line[start..]) {
+            Some(len) => {
+                ranges.push(start..start + len);
+                start += len;
+            }
+            None => start += next.len_utf8(),
+        }
+    }
+    ranges
+}
+
+/// Counts the occurrences of the query within a single line, as
+/// [`match_ranges`] finds them: from left to right and never overlapping,
+/// so that `aa` occurs twice in `aaaa`, not three times.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::count_matches;
+///
+/// assert_eq!(count_matches("aa", "aaaa aaa", false), 3);
+/// assert_eq!(count_matches("RUST", "Trust in Rust.", true), 2);
+/// assert_eq!(count_matches("", "anything", false), 0);
+/// ```
+pub fn count_matches(query: &str, line: &str, ignore_case: bool) -> usize {
+    if query.is_empty() {
+        0
+    } else if ignore_case {
+        match_ranges(query, line, ignore_case).len()
+    } else {
+        line.matches(query).count()
+    }
+}
+
+/// What a search looks for in each line: a query string, matched by
+/// [`line_matches`] and found by [`match_ranges`], or an `--expr`
+/// [`expr::Expr`].
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::{Matcher, expr::Expr};
+///
+/// assert!("rust".is_match("Trust", false));
+/// let expr = Expr::parse(r#""rust" & !"go""#).unwrap();
+/// assert!(expr.is_match("Rust", true));
+/// assert_eq!(expr.match_ranges("rust, rust", false), vec![0..4, 6..10]);
+/// ```
+pub trait Matcher {
+    /// Returns whether `line` is matched, ignoring case if `ignore_case`.
+    fn is_match(&self, line: &str, ignore_case: bool) -> bool;
+
+    /// Returns the byte ranges of `line` to mark as matched: sorted,
+    /// non-overlapping, and on character boundaries.
+    fn match_ranges(&self, line: &str, ignore_case: bool) -> Vec<Range<usize>>;
+
+    /// Counts the occurrences in `line`, which are those of
+    /// [`Matcher::match_ranges`].
+    fn count(&self, line: &str, ignore_case: bool) -> usize {
+        self.match_ranges(line, ignore_case).len()
+    }
+
+    /// Returns the ranges of [`Matcher::match_ranges`], each with the index
+    /// of the pattern it is an occurrence of, by which it is colored: always
+    /// 0 for a query, and the position of the literal in an expression.
+    fn pattern_ranges(&self, line: &str, ignore_case: bool) -> Vec<(Range<usize>, usize)> {
+        self.match_ranges(line, ignore_case)
+            .into_iter()
+            .map(|range| (range, 0))
+            .collect()
+    }
+}
+
+impl Matcher for str {
+    fn is_match(&self, line: &str, ignore_case: bool) -> bool {
+        line_matches(self, line, ignore_case)
+    }
+
+    fn match_ranges(&self, line: &str, ignore_case: bool) -> Vec<Range<usize>> {
+        match_ranges(self, line, ignore_case)
+    }
+
+    fn count(&self, line: &str, ignore_case: bool) -> usize {
+        count_matches(self, line, ignore_case)
+    }
+}
+
+impl Matcher for String {
+    fn is_match(&self, line: &str, ignore_case: bool) -> bool {
+        self.as_str().is_match(line, ignore_case)
+    }
+
+    fn match_ranges(&self, line: &str, ignore_case: bool) -> Vec<Range<usize>> {
+        self.as_str().match_ranges(line, ignore_case)
+    }
+
+    fn count(&self, line: &str, ignore_case: bool) -> usize {
+        self.as_str().count(line, ignore_case)
+    }
+}
+
+/// Returns the byte length of the prefix of `text` whose lowercase form
+/// equals `folded_query`, if there is one.
+fn folded_match_len(folded_query: &[char], text: &str) -> Option<usize> {
+    let mut matched = 0;
+    for (offset, c) in text.char_indices() {
+        for folded in c.to_lowercase() {
+            if folded_query.get(matched) != Some(&folded) {
+                return None;
+            }
+            matched += 1;
+        }
+        if matched == folded_query.len() {
+            return Some(offset + c.len_utf8());
+        }
+    }
+    None
+}
+
 #[cfg(test)]
 mod tests {
     use std::vec;
@@ -180,4 +669,141 @@ Come dive into the world of rust.";
         let expected: Vec<&str> = Vec::new();
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn line_numbers() {
+        let query = "ive";
+        let contents = "\
+Rust:
+really productive.
+also passive.
+probably problamatic.
+Come dive into the world of rust.";
+
+        let result: Vec<(usize, &str)> = search_with_line_numbers(query, contents, false).collect();
+        assert_eq!(
+            result,
+            vec![
+                (2, "really productive."),
+                (3, "also passive."),
+                (5, "Come dive into the world of rust.")
+            ]
+        );
+    }
+
+    #[test]
+    fn ranges_of_multiple_matches() {
+        let line = "abcabc abc";
+
+        assert_eq!(match_ranges("abc", line, false), vec![0..3, 3..6, 7..10]);
+        assert_eq!(match_ranges("aa", "aaa", false), vec![0..2]);
+        assert_eq!(match_ranges("", line, false), Vec::new());
+    }
+
+    #[test]
+    fn ranges_ignoring_case() {
+        let line = "Straße STRASSE straße";
+
+        assert_eq!(match_ranges("STRAßE", line, true), vec![0..7, 16..23]);
+        assert_eq!(match_ranges("İ", "aİb", true), vec![1..3]);
+        assert_eq!(match_ranges("rUsT", "Rust: rust.", true), vec![0..4, 6..10]);
+    }
+
+    #[test]
+    fn counted_matches_agree_with_ranges() {
+        for (query, line, ignore_case) in [
+            ("abc", "abcabc abc", false),
+            ("aa", "aaaaa", false),
+            ("STRAßE", "Straße STRASSE straße", true),
+            ("x", "none", true),
+        ] {
+            assert_eq!(
+                count_matches(query, line, ignore_case),
+                match_ranges(query, line, ignore_case).len()
+            );
+        }
+        assert_eq!(count_matches("aa", "aaaaa", false), 2);
+    }
+
+    #[test]
+    fn records_with_newlines_are_lines() {
+        let contents = "one\r\ntwo\n\nthree";
+        assert_eq!(
+            records(contents, '\n').collect::<Vec<_>>(),
+            contents.lines().collect::<Vec<_>>()
+        );
+        assert_eq!(records("", '\0').count(), 0);
+        assert_eq!(
+            records("a\r\0\0", '\0').collect::<Vec<_>>(),
+            vec!["a\r", ""]
+        );
+    }
+
+    #[test]
+    fn read_records_split_like_records() {
+        let read = |contents: &str, terminator| {
+            read_records(contents.as_bytes(), terminator)
+                .collect::<io::Result<Vec<String>>>()
+                .unwrap()
+        };
+        for contents in ["one\r\ntwo\n\nthree", "", "\n", "a\r\0\0", "last\r"] {
+            for terminator in ['\n', '\0'] {
+                assert_eq!(
+                    read(contents, terminator),
+                    records(contents, terminator).collect::<Vec<_>>(),
+                    "{contents:?}"
+                );
+            }
+        }
+
+        let mut invalid = read_records(b"ok\n\xff\n".as_slice(), '\n');
+        assert_eq!(invalid.next().unwrap().unwrap(), "ok");
+        let error = invalid.next().unwrap().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    /// Yields `lines` copies of a short line without holding them anywhere.
+    struct Generated {
+        lines: usize,
+        offset: usize,
+    }
+
+    impl io::Read for Generated {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            const LINE: &[u8] = b"a generated line\n";
+            let mut written = 0;
+            while written < buf.len() && self.lines > 0 {
+                let rest = &LINE[self.offset..];
+                let len = rest.len().min(buf.len() - written);
+                buf[written..written + len].copy_from_slice(&rest[..len]);
+                written += len;
+                self.offset += len;
+                if self.offset == LINE.len() {
+                    self.offset = 0;
+                    self.lines -= 1;
+                }
+            }
+            Ok(written)
+        }
+    }
+
+    #[test]
+    fn next_record_reuses_one_buffer() {
+        let input = io::BufReader::new(Generated {
+            lines: 1_000_000,
+            offset: 0,
+        });
+        let mut records = read_records(input, '\n');
+        let mut count = 0;
+        while let Some(record) = records.next_record() {
+            assert_eq!(record.unwrap(), "a generated line");
+            count += 1;
+        }
+        assert_eq!(count, 1_000_000);
+        assert!(
+            records.buffer.capacity() < 64,
+            "{}",
+            records.buffer.capacity()
+        );
+    }
 }
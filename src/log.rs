@@ -0,0 +1,163 @@
+//! Hooks for following the decisions a search makes about its inputs: why
+//! a file was passed over, and which files were opened.
+//!
+//! The interface follows that of the `log` crate, which this crate does not
+//! depend on by default: an embedder installs a [`Log`] with [`set_logger`],
+//! and is handed a [`Record`] for every message. With the `log` feature,
+//! every message is also logged through the `log` crate, with a target such
+//! as `minigrep_cli_tool::walk`, so that a logger installed for it, such as
+//! `env_logger`, receives them too. At [`Level::Debug`], the search
+//! logs how it was set up, such as whether case matters and what decided
+//! it, under the target `config`, the walker every file it skips, with the
+//! reason, and the search how many lines of each input it searched were
+//! selected; at [`Level::Trace`], the search logs every input it opens.
+//! Nothing is formatted unless a logger is installed that wants the level.
+
+use std::{
+    fmt,
+    io::{self, Write},
+    sync::OnceLock,
+};
+
+/// How detailed a message is, from the least to the most.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    /// A decision about an input, such as skipping it.
+    Debug,
+    /// A step taken for every input, such as opening it.
+    Trace,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Level::Debug => "debug",
+            Level::Trace => "trace",
+        })
+    }
+}
+
+/// A message handed to the installed [`Log`].
+///
+/// - `level`: How detailed the message is.
+/// - `target`: The module it comes from, such as `"walk"`.
+/// - `args`: The message itself.
+#[derive(Debug, Clone, Copy)]
+pub struct Record<'a> {
+    pub level: Level,
+    pub target: &'static str,
+    pub args: fmt::Arguments<'a>,
+}
+
+/// Where messages go once installed with [`set_logger`].
+pub trait Log: Send + Sync {
+    /// Returns whether messages at `level` are wanted, so that the others
+    /// are not even formatted.
+    fn enabled(&self, level: Level) -> bool;
+
+    /// Handles a message at a level it wants.
+    fn log(&self, record: &Record<'_>);
+}
+
+/// A [`Log`] writing every message at `level` or below to standard error,
+/// as `minigrep: [debug] walk: ...`. `--debug` installs one at
+/// [`Level::Debug`].
+#[derive(Debug, Clone, Copy)]
+pub struct StderrLogger {
+    pub level: Level,
+}
+
+impl Log for StderrLogger {
+    fn enabled(&self, level: Level) -> bool {
+        level <= self.level
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        let _ = writeln!(
+            io::stderr(),
+            "minigrep: [{}] {}: {}",
+            record.level,
+            record.target,
+            record.args
+        );
+    }
+}
+
+/// The error of [`set_logger`] when a logger was already installed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SetLoggerError;
+
+impl fmt::Display for SetLoggerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a logger is already installed")
+    }
+}
+
+impl std::error::Error for SetLoggerError {}
+
+static LOGGER: OnceLock<&'static dyn Log> = OnceLock::new();
+
+/// Installs `logger` for the rest of the process.
+///
+/// # Errors
+/// Returns an error if a logger was already installed, which is kept.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::log::{set_logger, Level, StderrLogger};
+///
+/// static LOGGER: StderrLogger = StderrLogger { level: Level::Debug };
+/// set_logger(&LOGGER).unwrap();
+/// assert!(set_logger(&LOGGER).is_err());
+/// ```
+pub fn set_logger(logger: &'static dyn Log) -> Result<(), SetLoggerError> {
+    LOGGER.set(logger).map_err(|_| SetLoggerError)
+}
+
+/// Logs the message made by `args` at [`Level::Debug`].
+pub(crate) fn debug(target: &'static str, args: fmt::Arguments<'_>) {
+    log(Level::Debug, target, args);
+}
+
+/// Logs the message made by `args` at [`Level::Trace`].
+pub(crate) fn trace(target: &'static str, args: fmt::Arguments<'_>) {
+    log(Level::Trace, target, args);
+}
+
+/// Returns whether a logger is installed that wants messages at `level`,
+/// for messages that take work to make even before they are formatted.
+pub(crate) fn enabled(level: Level) -> bool {
+    #[cfg(feature = "log")]
+    if log_crate_level(level) <= ::log::max_level() {
+        return true;
+    }
+    LOGGER.get().is_some_and(|logger| logger.enabled(level))
+}
+
+/// Hands the message made by `args` to the installed logger, if it wants
+/// messages at `level`.
+fn log(level: Level, target: &'static str, args: fmt::Arguments<'_>) {
+    #[cfg(feature = "log")]
+    if log_crate_level(level) <= ::log::max_level() {
+        let target = format!("{}::{target}", env!("CARGO_CRATE_NAME"));
+        ::log::log!(target: &target, log_crate_level(level), "{args}");
+    }
+    if let Some(logger) = LOGGER.get()
+        && logger.enabled(level)
+    {
+        logger.log(&Record {
+            level,
+            target,
+            args,
+        });
+    }
+}
+
+/// The level of the `log` crate that `level` stands for.
+#[cfg(feature = "log")]
+fn log_crate_level(level: Level) -> ::log::Level {
+    match level {
+        Level::Debug => ::log::Level::Debug,
+        Level::Trace => ::log::Level::Trace,
+    }
+}
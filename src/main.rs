@@ -1,125 +1,608 @@
 //! # Minigrep CLI Tool
-//! 
-//! A command-line utility similar to classic 'grep' tool. 
+//!
+//! A command-line utility similar to classic 'grep' tool.
 //! It searches for lines containing a specified query string withing a text file.
-//! 
+//!
 //! This crate supports both **case-sensitive** and **case-insensitive** searches,
 //! controlled via a command-line flag or an environment variable.
-//! 
-//! 
+//!
+//!
 //! # Usage
 //! ```
-//! cargo run -- <query> <file_path> <flag> [/i or /s]
+//! cargo run -- [options] <query> [<file_path>...] [/i or /s]
 //! ```
-//! 
+//!
 //! - '/i' enables case-insensitive search
 //! - '/s' enables case-sensitive search
-//! 
+//!
 //! Alternatively, you can enable case-insensitive search using the environment variable
 //! ```
 //! IGNORE_CASE=1 cargo run -- <query> <file_path>
 //! ```
-//! 
+//!
+//! A file path of `-` reads from standard input, as does giving no file path
+//! at all when standard input is not a terminal, e.g. `cat poem.txt |
+//! minigrep rust`. Input is searched as it is read, a line at a time.
+//! Invalid UTF-8 in a line is replaced by U+FFFD, with a warning on standard
+//! error, and the rest of the input is still searched.
+//!
+//! On Windows, whose shells pass wildcards through, a file path whose name
+//! contains `*`, `?`, or `[` is expanded into the files it matches, as an
+//! `--include` glob matches names, and files are printed without the
+//! `\\?\` prefix of verbatim paths, though they are still opened with it.
+//!
+//! Output is deterministic given the same inputs and options, with one
+//! exception: with `-j` and without `--sort=path`, the files are printed in
+//! the order they finish. Each file's lines are always printed together and
+//! in order. Warnings on standard error may interleave differently between
+//! runs.
+//!
+//! The exit status is 0 if any line was selected, 1 if none was, and 2 if an
+//! error occurred. With `--require-count=N`, it is 0 if at least N lines
+//! were, and 3 if fewer were, with a line on standard error saying how many.
+//! If standard output is closed early, e.g. by `head`, the search stops
+//! quietly with status 141, as if killed by `SIGPIPE`.
+//!
+//! Some options need a cargo feature, all of which are enabled by default:
+//! `--search-zip` needs `compress` (gzip, bzip2, and xz) or `archive` (zip
+//! and tar), `--json` and `--output-format=sarif` need `json`, and `-j`
+//! with more than one thread needs `parallel`. A build without the feature
+//! rejects the option, with status 2.
+//!
+//! Options that contradict each other are rejected before anything is
+//! searched, naming both, as in `-l cannot be used with -L`, as is a value
+//! given to an option that takes none, as in `--trim=yes`.
+//!
+//! # Options
+//! - `-n`, `--line-number`: prefix each matching line with its line number
+//! - `--column`: prefix each matching line, after its line number, with the
+//!   1-based column of its first match in characters, or with `-o`, each
+//!   match with its own column; implies `-n`; cannot be combined with `-c`,
+//!   `-l`, `-L`, `--files`, `--line-numbers-only`, `--json`,
+//!   `--output-format`, or `--format`
+//! - `--byte-column`: like `--column`, counting the column in bytes
+//! - `-b`, `--byte-offset`: prefix each matching line with the offset of its
+//!   first byte in the input, or with `-o`, each match with its own offset
+//! - `--hex`: take the query as hex bytes, separated by whitespace or not,
+//!   as in `DE AD BE EF`, and search the raw bytes of each input for them,
+//!   neither decoded nor decompressed, printing the offset of each
+//!   occurrence as `path:0x0000ABCD` instead of lines; cannot be combined
+//!   with options about lines or their matches, such as `-v`, `-c`, `-o`,
+//!   `-l`, `-C`, or `--json`
+//! - `--hex-context=N`: with `--hex`, also print the N bytes on each side of
+//!   each occurrence in hex, with the occurrence in brackets, as in
+//!   `path:0x0000ABCD: 01 02 [DE AD BE EF] 03 04`
+//! - `--strings[=MIN]`: search each input as `strings FILE | minigrep`
+//!   would, taking every run of at least MIN (4 by default) printable
+//!   characters in its raw bytes, ASCII or UTF-8, as a line, and printing
+//!   the byte offset of each selected run instead of a line number, as in
+//!   `1032:/usr/lib/libssl.so`; this applies to every input, not
+//!   only binary ones, which are not told apart; `-v`, `-c`, and `-o` work
+//!   on runs as on lines, while options about line numbers or neighboring
+//!   lines, such as `-n`, `-l`, `-C`, or `--json`, cannot be combined with
+//!   it
+//! - `-H`, `--with-filename`: prefix each matching line with its file name
+//!   (the default when more than one file is searched)
+//! - `-v`, `--invert-match`: select the lines that do not match
+//! - `--expr=EXPR`: select lines by a boolean expression of quoted literals
+//!   instead of a query, which is then not given, e.g.
+//!   `minigrep --expr '("timeout" | "timed out") & !"retry"' app.log`;
+//!   `!` binds tighter than `&`, which binds tighter than `|`, and
+//!   parentheses group; operators inside quotes are literal, and `\"` and
+//!   `\\` stand for a quote and a backslash; each literal is matched as a
+//!   query would be, and those making a line match are the ones
+//!   highlighted; an expression that cannot be parsed is an error naming
+//!   the position at fault
+//! - `-e PATTERN`, `--pattern=PATTERN`: search for PATTERN instead of a
+//!   query, which is then not given; given more than once, select the
+//!   lines with any of the patterns, as `--expr '"err" | "warn"'` would,
+//!   e.g. `minigrep -e err -e warn app.log`; with `--color`, the matches of
+//!   each pattern are highlighted in a color of their own; a pattern
+//!   starting with `(?i)` is matched ignoring case, and one starting with
+//!   `(?-i)` with it, whatever `/i` and `/s` say
+//! - `-f FILE`, `--file=FILE`: search for the patterns on the lines of
+//!   FILE, empty lines left out, as if each were given with `-e`
+//! - `--and-not=PATTERN`: select only the lines that match the query and
+//!   not PATTERN, matched with the same case setting; may be given more
+//!   than once, for lines matching none of the patterns; `-v` inverts the
+//!   whole, selecting the lines either without the query or with one of
+//!   the patterns; cannot be combined with `--expr`, of which it is the
+//!   shorthand `"QUERY" & !"PATTERN"`
+//! - `--between START END`: select the ranges of lines from each one
+//!   containing START to the next one containing END, both included,
+//!   instead of lines matching a query, which is then not given, e.g.
+//!   `minigrep --between 'BEGIN CONFIG' 'END CONFIG' app.conf`; every
+//!   range of a file is selected, one without an end runs to the end of
+//!   the file, and a START inside a range does not nest, the first END
+//!   closing it; END is only looked for after the line starting the range,
+//!   so `--between --- ---` selects a block fenced by `---` lines; `-n`
+//!   and `-c` number and count the lines of ranges, and `-v` selects the
+//!   lines outside them
+//! - `--since=TS` / `--until=TS`: only search the lines starting with a
+//!   timestamp at or after `--since` and before `--until`, either of which
+//!   may be left out, e.g. `minigrep --since='2024-01-02 14:00'
+//!   --until='2024-01-02 14:10' error app.log`; a timestamp is ISO 8601,
+//!   `2024-01-02`, `2024-01-02T14:00:05.123`, or `2024-01-02 14:00`,
+//!   optionally with a zone such as `Z` or `+01:00`, or syslog's
+//!   `Jan  2 14:00:05`, and may be in brackets at the start of a line; one
+//!   without a zone is taken to be UTC, not local time, and a syslog one
+//!   to be in the current year; `-v` only flips the query, selecting the
+//!   lines of the window without it
+//! - `--no-timestamp=keep|drop`: with `--since` or `--until`, whether the
+//!   lines not starting with a timestamp, such as the continuation lines
+//!   of a record, are searched or left out; `drop` by default
+//! - `--escapes`: interpret `\t`, `\n`, `\r`, `\\`, and `\xNN` (an ASCII
+//!   character in hex) in the query, which is otherwise taken literally,
+//!   e.g. `minigrep --escapes 'a\tb'` for a tab; an invalid escape is an
+//!   error naming its position; as lines end at a newline, a query with
+//!   `\n` only matches with `-z`, and is warned about otherwise
+//! - `--trim`: match lines without their leading and trailing whitespace,
+//!   still printing them whole
+//! - `--squeeze-space`: match with every run of whitespace, tabs included,
+//!   made a single space, in the query and patterns as in lines, e.g.
+//!   `minigrep --squeeze-space 'port = 80'` finds `port\t=   80`; what is
+//!   printed and highlighted is the line as it was read, a space of the
+//!   query highlighting its whole run; neither option can be combined with
+//!   `--replace` or `--hex`
+//! - `-c`, `--count`: print only the number of selected lines per file,
+//!   including files with none; with `-l`, only for the files with some,
+//!   and with `-L`, only for the files with none; with `-q`, counting stops
+//!   at the first selected line; cannot be combined with `-o`
+//! - `--count-matches`: like `-c`, and with the same options, but count
+//!   every occurrence of the query, without overlaps, rather than each line
+//!   it occurs in; cannot be combined with `-c` or `-v`
+//! - `--count-total`: with `-c` or `--count-matches`, also print the number
+//!   of selected lines or occurrences in all files, as a last line `total:N`
+//! - `-o`, `--only-matching`: print each match on its own line instead of
+//!   the whole line
+//! - `--unique-counts`: with `-o`, print each distinct match once the
+//!   search is over, after the number of times it occurred, most frequent
+//!   first and equally frequent ones in lexical order, as
+//!   `grep -o | sort | uniq -c | sort -rn` would; ignoring case, matches that
+//!   differ only in case are counted together and shown as first seen;
+//!   cannot be combined with `-c` or `--count-matches`
+//! - `--count-by-file`: like `-c`, and with the same options, but print
+//!   the counts once the search is over, as `COUNT<TAB>PATH` lines, leaving
+//!   out files without selected lines and, as ever, files that cannot be
+//!   read, which are warned about; cannot be combined with
+//!   `--count-total`, `-l`, or `-L`
+//! - `--sort-count`: with `--count-by-file`, print the largest counts
+//!   first, and equal ones in order of their paths, e.g.
+//!   `minigrep -r --count-by-file --sort-count --top=10 old_api src` for
+//!   the files using a deprecated function most
+//! - `--group-by-dir`: like `--count-by-file`, but add the counts up by
+//!   the directory each file is in, printing a `COUNT<TAB>DIR` line for
+//!   each directory, the largest first and equal ones in order of their
+//!   paths, followed by an indented `COUNT<TAB>PATH` line for each of its
+//!   files, the path relative to the directory and again the largest first
+//! - `--group-depth=N`: with `--group-by-dir`, add up the directories no
+//!   more than N levels below the path searched, so that `--group-depth=1`
+//!   counts `src/parser/lexer/` as `src/parser/` when searching `src`, and
+//!   `--group-depth=0` counts a whole tree together
+//! - `--wc`: print, instead of the selected lines, how many there are and
+//!   how many words and bytes are in them, as `grep PATTERN FILE | wc`
+//!   would, e.g. with `-v` to size the rest: it counts words as runs of
+//!   bytes other than ASCII whitespace and bytes with the line terminators
+//!   read, aligning the counts, followed by the path when file names are
+//!   printed, and ending with a `total` line for several files, once the
+//!   search is over; like `-c`, it cannot be combined with the options `-c`
+//!   cannot, nor with `-c`, `--count-matches`, `--count-by-file`,
+//!   `--count-total`, `-l`, `-L`, `--ranges`, or `--strings`
+//! - `--top=N`: with `--unique-counts`, print only the `N` most frequent
+//!   matches; with `--count-by-file`, only the first `N` counts; with
+//!   `--group-by-dir`, only the first `N` directories
+//! - `--line-numbers-only`: print only the number of each selected line,
+//!   after its file name when that would be printed, e.g. for `sed -n`;
+//!   cannot be combined with `-c`, `-o`, `-l`, `-L`, `--passthru`,
+//!   `--replace`, `--vimgrep`, `--json`, `--output-format`, or `--format`
+//! - `--ranges`: with `--line-numbers-only`, print consecutive line numbers
+//!   as one range, such as `12-15`; cannot be combined with `--watch`
+//! - `--vimgrep`: print `PATH:LINE:COLUMN:TEXT` once for every match, for
+//!   editors' quickfix lists; `COLUMN` is the 1-based position of the match
+//!   in characters, or with `--byte-column` in bytes; cannot be combined
+//!   with `-v`, `-c`, `-o`, `-l`, `-L`, `--files`, `--passthru`,
+//!   `--replace`, or `--json`
+//! - `--replace=TEXT`: print matches replaced by `TEXT`, where `$0` stands
+//!   for the matched text and `$$` for a literal `$`; files are not modified
+//!   unless `--in-place` is given
+//! - `--in-place`: with `--replace`, rewrite each file with its matches
+//!   replaced, keeping the original as `FILE.bak`, and print the number of
+//!   replacements per file; a symbolic link is followed, and the file it
+//!   points to rewritten and backed up; cannot be used on standard input,
+//!   or combined with `-v`, `-c`, `-o`, `-l`, `-L`, `--passthru`,
+//!   `--files`, `--encoding`, or `--pre`
+//! - `--no-backup`: with `--in-place`, do not keep a `.bak` copy
+//! - `--passthru`: print every line, highlighting the matches; cannot be
+//!   combined with `-v`, `-c`, `-l`, `-L`, or `-o`
+//! - `-A NUM`, `--after-context=NUM`: also print the `NUM` lines after each
+//!   selected line, marked by `-` instead of `:` after their prefixes;
+//!   groups of lines that are not adjacent are separated by a `--` line,
+//!   and groups that touch or overlap are printed as one; cannot be
+//!   combined with `-c`, `-o`, `-l`, `-L`, `--files`, `--passthru`,
+//!   `--vimgrep`, `--line-numbers-only`, `--json`, `--output-format`,
+//!   `--format`, `--in-place`, or `--watch`
+//! - `-B NUM`, `--before-context=NUM`: likewise for the `NUM` lines before
+//! - `-C NUM`, `--context=NUM`: both, unless `-A` or `-B` is given too
+//! - `--after-context-until=STOP`: also print the lines after each
+//!   selected line until one containing `STOP`, which is left out, as in
+//!   `minigrep --after-context-until=2024- error app.log` for the
+//!   continuation lines of log records; a line selected among them is
+//!   printed as a match and starts the lines after it again; combines with
+//!   `-A`, `-B`, and `-C` as they do with each other, and cannot be
+//!   combined with what they cannot
+//! - `--include-stop`: with `--after-context-until`, print the line
+//!   containing `STOP` too
+//! - `--group-separator=SEP`: with context, separate the groups by a `SEP`
+//!   line instead of `--`
+//! - `--no-group-separator`: with context, print the groups one after the
+//!   other
+//! - `-l`, `--files-with-matches`: print only the names of files containing a match
+//! - `-L`, `--files-without-match`: print only the names of files without a match
+//! - `--encoding=ENC`: read inputs as `utf-8`, `utf-16le`, `utf-16be`, or
+//!   `latin-1`; by default UTF-16 is detected from a byte order mark and
+//!   everything else is read as UTF-8
+//! - `--no-unicode`: match the query byte for byte without checking that
+//!   lines are UTF-8, with `-i` ignoring the case of ASCII letters only,
+//!   which is faster on large ASCII inputs; other text is printed with
+//!   invalid bytes replaced, and columns count bytes
+//! - `--absolute-path`: print the names of files given or found by `-r` as
+//!   absolute paths, in every output mode; relative paths are joined to the
+//!   current directory without resolving symbolic links or `..`, and paths
+//!   given as absolute ones are printed unchanged
+//! - `--label=NAME`: the name standard input is reported under
+//!   (defaults to `(standard input)`)
+//! - `--files-from=FILE`: also search the paths listed in `FILE`, one per
+//!   line, as if they were given on the command line, always printing file
+//!   names; empty lines are skipped, a listed `-` is a file of that name,
+//!   and `FILE` may be `-` to read the list from standard input, which can
+//!   then not be searched too; a list that cannot be read is an error
+//! - `--files0-from=FILE`: like `--files-from`, with the paths ended by NUL
+//!   bytes, e.g. for the output of `find -print0`
+//! - `--line-buffered`: flush output after every line; this is the default
+//!   when standard output is a terminal, otherwise output is block-buffered
+//! - `--read-strategy=STRATEGY`: read files with `read` calls into a buffer
+//!   (`buffered`, how they are read without the option), through a memory
+//!   map (`mmap`), or (`auto`) through a memory map only for regular files
+//!   of at least 1 MiB that are not on a network file system, which is only
+//!   checked on Linux; standard input, `--pre` output, and files that
+//!   cannot be mapped are always read into a buffer, and the output is the
+//!   same either way, except that a mapped file truncated while it is
+//!   searched may end the search with `SIGBUS`; `--stats` reports how each
+//!   file was read; cannot be used with `--watch`, `--in-place`, or
+//!   `--start-byte`
+//! - `-r`, `--recursive`: search directories recursively, skipping hidden
+//!   files and directories (those whose names start with `.`, or on Windows
+//!   that have the hidden attribute), paths listed in `.gitignore`,
+//!   `.ignore`, and `.minigrepignore` files, and files over `--max-filesize`;
+//!   paths given on the command line are always searched
+//! - `--hidden`: with `-r`, search hidden files and directories too
+//! - `--no-ignore`: with `-r`, search paths listed in `.gitignore` and
+//!   `.ignore` files too; `.minigrepignore` files still apply, and take
+//!   precedence over the others otherwise. `--stats` reports how many paths
+//!   were ignored
+//! - `--no-ignore-files`: with `-r`, read no ignore files at all, including
+//!   `.minigrepignore`
+//! - `--search-zip`: with `-r`, decompress the gzip, bzip2, and xz files
+//!   found, as files given on the command line always are; they are
+//!   recognized by a `.gz`, `.bz2`, or `.xz` extension or else by their
+//!   first bytes, and line numbers count the decompressed lines; also
+//!   search each file in a zip or tar archive, compressed or not, named or
+//!   found, as `bundle.zip!inner/path.log`, skipping encrypted members with
+//!   a warning, archives within archives with a note, and links and members
+//!   larger than `--max-filesize` or smaller than `--min-filesize`
+//!   silently; each format has a feature, enabled together by the default
+//!   `compress` and `archive` features
+//! - `--pre=COMMAND`: search what `COMMAND` prints when run with each file's
+//!   path as its argument, e.g. `--pre=pdftotext`, instead of the file
+//!   itself; the command's error output is passed through, and a command
+//!   that fails makes its file an error, warned about like one that cannot
+//!   be read; standard input is never preprocessed, and the output is
+//!   neither decompressed nor searched as an archive; cannot be combined
+//!   with `--in-place` or `--watch`
+//! - `--pre-glob=GLOB`: with `--pre`, preprocess only files whose names
+//!   match `GLOB`, searching the others as they are; may be given more than
+//!   once
+//! - `--follow`: with `-r`, follow symbolic links, which are skipped
+//!   otherwise; broken links and links back to a directory already searched
+//!   are reported on standard error and skipped (`-L` is taken by
+//!   `--files-without-match`)
+//! - `--no-dedupe`: with `-r`, search a file each time it is found, under
+//!   every path; by default a file reached again through a hard link or,
+//!   with `--follow`, a symbolic link, within the same path given, is
+//!   searched only under the path it was first found by
+//! - `--one-file-system`: with `-r`, do not descend into directories on
+//!   another file system than the path given, such as network mounts, as
+//!   with `find -xdev`; only has an effect on Unix
+//! - `--devices=ACTION`: `read` devices, FIFOs, and sockets given as paths,
+//!   so that `minigrep error /dev/stdin` reads what is piped in, or `skip`
+//!   them (the default), with a warning and counted by `--stats`, since
+//!   reading one may never end; those found by `-r` are always skipped.
+//!   Only told apart from regular files on Unix
+//! - `--watch`: search the one file given, then keep it open and print the
+//!   selected lines appended to it, like `tail -F | grep`, until Ctrl-C
+//!   ends the search with the usual exit status; a file that shrinks, or
+//!   on Unix is replaced, as when logs are rotated, is read again from its
+//!   start; cannot be combined with `-r`, `-c`, `-l`, `-L`, `--files`,
+//!   `--in-place`, `--json`, `--encoding`, `--search-zip`, `--files-from`,
+//!   `--pre`, or `--stats` (`--follow` is taken by symbolic links)
+//! - `--watch-interval=MS`: with `--watch`, check the file for new lines
+//!   every `MS` milliseconds instead of every 250
+//! - `--max-depth=N`: with `-r`, descend at most `N` directory levels below
+//!   each path given; `0` searches only the paths themselves
+//! - `--include=GLOB`: with `-r`, search only files whose names match `GLOB`,
+//!   e.g. `'*.rs'`; may be given more than once
+//! - `--exclude=GLOB`: with `-r`, skip files whose names match `GLOB`
+//! - `--exclude-dir=GLOB`: with `-r`, skip directories whose names match `GLOB`
+//! - `-t TYPE`, `--type=TYPE`: with `-r`, search only files of the built-in
+//!   type `TYPE`, e.g. `rust` or `py`, as if its globs were given to `--include`
+//! - `-T TYPE`, `--type-not=TYPE`: with `-r`, skip files of the type `TYPE`
+//! - `--type-list`: print the built-in file types and their globs, and exit
+//! - `--max-filesize=SIZE`: skip files larger than `SIZE` bytes; `SIZE` may
+//!   end in `K`, `M`, or `G`
+//! - `--min-filesize=SIZE`: skip files smaller than `SIZE` bytes
+//! - `--newer-than=DURATION`: skip files last modified longer ago than
+//!   `DURATION`, a number followed by `s`, `m`, `h`, or `d`, e.g. `36h`;
+//!   files whose modification time cannot be read are searched
+//! - `--older-than=DURATION`: skip files last modified more recently than
+//!   `DURATION` ago; like the size limits, the times are checked before
+//!   files are opened, and `--stats` counts the files they filter out
+//! - `-j N`, `--threads=N`: search up to `N` files at once; `0` uses one
+//!   thread per CPU, and the default is `1`
+//! - `--sort=ORDER`: print the results of each file in the order `ORDER`,
+//!   either `path` (byte-wise by path) or `none` (the default: walk order,
+//!   or with `-j`, whichever file finishes first); lines within a file are
+//!   always in order, and with `path` the output is the same on every run,
+//!   whatever `-j` is
+//! - `--sort-buffer=SIZE`: with `-j` and `--sort=path`, print each file's
+//!   lines as they are found, once the files before it are done, holding
+//!   back no more than `SIZE` (e.g. `512K`; the default is `16M`) of the
+//!   lines of the files after it, whose threads wait once it is full
+//! - `--max-columns=N`: omit lines longer than `N` characters, printing
+//!   `[Omitted long line with K matches]` instead
+//! - `--max-columns-preview`: with `--max-columns`, print the `N` characters
+//!   around the first match of a long line, marking the cuts with `…`
+//! - `--snippet=N`: print only the `N` characters before and after the
+//!   first match of each line, marking the cuts with `…`; later matches are
+//!   highlighted if they fall within that, and cuts inside a word move up
+//!   to 5 characters towards the match to fall at whitespace instead;
+//!   cannot be combined with `--max-columns`, `-o`, `--json`,
+//!   `--output-format`, or `--format`
+//! - `--char-context=N`: print the `N` characters before and after every
+//!   match instead of whole lines, each on its own line with its line
+//!   number, column, and byte offset in the whole line when asked for,
+//!   marking the cuts with `…`; matches whose characters would overlap or
+//!   touch share a line, e.g. for minified files or JSON logs on one long
+//!   line; cannot be combined with `-v`, `-o`, `--snippet`,
+//!   `--max-columns`, `--passthru`, `--replace`, `--vimgrep`, `-A`, `-B`,
+//!   `-C`, `--after-context-until`, `--json`, `--output-format`,
+//!   `--format`, or `--hex`
+//! - `--files`: print the files that would be searched, one per line,
+//!   without searching them; no query is given in this mode
+//! - `--heading`: print each file's name once, above its lines, with a blank
+//!   line between files; the default when standard output is a terminal and
+//!   file names are shown
+//! - `--no-heading`: prefix every line with its file name instead
+//! - `-z`, `--null-data`: read and print records ended by a NUL byte instead
+//!   of lines, e.g. for the output of `find -print0`; records may contain
+//!   newlines, and `-n` counts records; cannot be combined with `--json` or
+//!   `--in-place`; without it, an input with a NUL byte in the first block
+//!   read of it is taken to be binary and skipped
+//! - `-Z`, `--null`: terminate file names with a NUL byte instead of the
+//!   character that normally follows them
+//! - `--quote=STYLE`: print file names `shell`-quoted, wrapped in single
+//!   quotes unless they are made only of characters no shell treats
+//!   specially, so that the names `-l` and `--files` print can be pasted
+//!   into a command; the default is `none`; `-Z` is the more robust choice
+//!   for `xargs -0`, and cannot be combined with it
+//! - `-s`, `--no-messages`: do not warn about files that cannot be read;
+//!   such files are skipped either way, and make the exit status 2 if
+//!   nothing else matched
+//! - `-q`, `--quiet`: print nothing to standard output, leaving the exit
+//!   status to tell whether anything matched, and stop reading at the
+//!   first selected line, or with `--require-count=N`, the Nth, as
+//!   `--max-total` would; warnings are still printed unless `-s` is given;
+//!   cannot be combined with `-L`, `--files`, `--in-place`, or `--watch`
+//! - `--skip-lines=N`: do not search the first N lines of each input, such
+//!   as a CSV header or a license banner; the lines after them keep their
+//!   numbers, so a match on the first line searched after
+//!   `--skip-lines=3` is line 4
+//! - `--head=N`: stop reading each input after its first N lines, skipped
+//!   ones included, so that `--skip-lines=1 --head=100` searches lines 2
+//!   to 100 and the rest of a large file is never read
+//! - `--start-byte=N`: start searching each input at byte N, or past the
+//!   end of the line byte N falls within, seeking a file there rather than
+//!   reading what comes before; inputs are read as raw bytes, neither
+//!   decompressed nor decoded. `--stats` and the `--json` summary report
+//!   the offset just past the last whole line read in each input, for the
+//!   next search of a growing log to start from, so that no line is
+//!   searched twice or missed; a last line still being written is only
+//!   searched once whole, and a file shorter than N, as after it is
+//!   rotated, is searched from its start. Line numbers are unknown, and
+//!   `null` in JSON, unless counted with `--count-from-start`
+//! - `--count-from-start`: with `--start-byte`, read the input from its
+//!   start to number its lines, as `-n`, `--column`, `--vimgrep`,
+//!   `--line-numbers-only`, `--output-format`, and `--format` need
+//! - `--max-total=N`: stop the whole search once N lines are selected in
+//!   all inputs together, leaving the rest of the input and the inputs
+//!   after it unread, though the context of the last line is still
+//!   printed; with `-j`, the threads stop together and exactly N lines are
+//!   printed, in the order asked for with `--sort`, but which N lines they
+//!   are depends on which threads got to them first, so it may change
+//!   from one run to the next; cannot be combined with `-L`
+//! - `--first[=ORDER]`: print the first selected line of all the inputs,
+//!   and nothing after it but its context, then stop, with the exit
+//!   status of a match. With `ordered`, the default, that is the first line
+//!   of the first input with one, the inputs sorted by path, however many
+//!   threads search; with `-j`, inputs after one found with a line are
+//!   left unsearched, but those before it are searched on. With `any`,
+//!   that is whichever line a thread finds first, as with `--max-total=1`,
+//!   which may change from one run to the next; cannot be combined with
+//!   `--max-total`, `-c`, or `-L`
+//! - `--require-count=N`: exit with status 3 unless at least N lines are
+//!   selected in all inputs, e.g. to assert in CI that output has the
+//!   markers it should, printing how many were found to standard error
+//!   unless `-s` or `-q` is given, so that
+//!   `minigrep -q --require-count=3 MARKER out.txt` asserts silently; with
+//!   `-l`, at most one line counts per file; status 2, if some inputs could
+//!   not be searched, takes precedence
+//! - `--require-match`: the same as `--require-count=1`
+//! - `--debug`: log to standard error how the search was set up once
+//!   options and environment variables were weighed, as lines such as
+//!   `minigrep: [debug] config: case: sensitive, from /s over IGNORE_CASE`
+//!   for what is looked for, whether case matters and what decided it,
+//!   how lines are matched, the inputs, and whether output is colored and
+//!   why; why each file is skipped, be it hidden, excluded, ignored, too
+//!   large, or unreadable, as
+//!   `minigrep: [debug] walk: skipping target: listed in an ignore file`;
+//!   and how many lines of each input searched were selected, as
+//!   `minigrep: [debug] search: notes.txt: 2 of 40 lines selected`
+//! - `--progress`: while searching, keep a status line on standard error,
+//!   when it is a terminal, with how many of the files found have been
+//!   searched, how many lines matched, and the file being searched; it is
+//!   redrawn at most ten times a second, and erased before anything else is
+//!   printed to the terminal and once the search is over; cannot be
+//!   combined with `--watch`
+//! - `--stats`: after searching, print to standard error how many files were
+//!   searched and skipped, and why, such as being binary or too large, how
+//!   many lines were scanned and matched, and how long it took
+//! - `--timings`: after searching, print to standard error how long reading
+//!   and searching each input took and how many megabytes a second that
+//!   was, slowest first, then the totals; time spent queued for a thread is
+//!   not counted, and archive members are timed one by one
+//! - `--timings-limit=N`: with `--timings`, list only the `N` slowest inputs
+//! - `--json`: print a stream of JSON events, one per line and each as soon
+//!   as it happens: `begin` and `end` around the `match` events of each file
+//!   with a selected line, an `error` event, with the path, kind, and
+//!   message, for each input that could not be searched, was too large,
+//!   was skipped as binary, or held invalid UTF-8, as well as the usual
+//!   warning, then a `summary`; see the library's `json` module for the
+//!   schema; cannot be combined with `--color`, `-c`, `-o`, `-l`, `-L`,
+//!   `--files`, `--passthru`, `--replace`, or `--max-columns`
+//! - `--output-format=FORMAT`: print each selected line as a row of a
+//!   table, `csv` or `tsv`, with the fields `path,line_number,column,text`,
+//!   after a header row; `column` is that of the first match, in
+//!   characters; see the library's `csv` module for the quoting; with
+//!   `sarif`, print a SARIF 2.1.0 document with a result for every match
+//!   once the search is over, keeping at most the first 25,000 (see the
+//!   library's `sarif` module); cannot be combined with the options
+//!   `--json` cannot, or `--json`, `--vimgrep`, or `--in-place`, nor
+//!   `sarif` with `-v`
+//! - `--no-header`: with `--output-format`, leave out the header row
+//! - `--format=TEMPLATE`: print a line made from `TEMPLATE` for every
+//!   match instead of the matching line, such as `{path}:{line}: {text}`;
+//!   the placeholders are `{path}`, `{line}`, `{column}` (in characters),
+//!   `{byte}` (the offset of the match in the input), `{match}`, and
+//!   `{text}` (the whole line), and `{{` and `}}` print braces; with `-v`,
+//!   one line is printed for each selected line, with `{column}` and
+//!   `{match}` empty; cannot be combined with the options
+//!   `--output-format` cannot, or `--output-format`
+//! - `--color[=WHEN]`: highlight matches, file names, and line numbers;
+//!   `WHEN` is `auto` (the default, only when standard output is a terminal),
+//!   `always`, or `never`; in `auto` mode a non-empty `NO_COLOR` disables
+//!   color and `CLICOLOR_FORCE` forces it even when piped
+//! - `--pager[=CMD]`: when standard output is a terminal, page the output
+//!   through CMD, colored as it would be on the terminal, and wait for the
+//!   pager to be quit; without CMD, the pager is `MINIGREP_PAGER`, `PAGER`,
+//!   or `less`, run with `LESS=FRX` unless `LESS` is set; setting
+//!   `MINIGREP_PAGER` pages without the flag, but `PAGER` alone does not;
+//!   CMD is split on whitespace, without shell quoting; quitting the pager
+//!   early ends the search quietly, a pager that cannot be started is
+//!   warned about and the output printed directly, and output that is not
+//!   to a terminal is never paged
+//! - `--no-pager`: print directly even if `--pager` or `MINIGREP_PAGER`
+//!   asks for a pager
+//! - `--generate-completions=SHELL`: print a script completing the options
+//!   of minigrep, and the values of those taking one of a few, for `SHELL`,
+//!   one of `bash`, `zsh`, `fish`, or `powershell`, and exit, e.g.
+//!   `minigrep --generate-completions bash > ~/.local/share/bash-completion/completions/minigrep`;
+//!   the script is made from the table of options the command line is
+//!   parsed with, so it always lists every option
+//!
+//! The colors can be customized with `MINIGREP_COLORS` (or, if unset,
+//! `GREP_COLORS`), e.g. `MINIGREP_COLORS='ms=01;32:fn=35:ln=32:se=36'` for
+//! matches, file names, line numbers, and separators. When several patterns
+//! are searched for, with `-e`, `--expr`, or `--between`, the matches of
+//! the first are colored with `ms` and those of the next ones with each
+//! color of `mp` in turn, e.g. `mp=01;32,01;34`, bold green, yellow, blue,
+//! magenta, and cyan by default, starting over with `ms` after the last;
+//! where matches of two patterns overlap, the one starting first, or else
+//! the longer, is highlighted, and of two matching the same text, the
+//! earlier pattern's.
+//!
 //! Example:
 //! ```
 //! cargo run -- rust docs.txt /i
+//! zcat log.gz | cargo run -- --label=log.gz -n error -
+//! cargo run -- --in-place --replace=colour color notes.txt
 //! ```
 
-
-use minigrep_cli_tool::{search, search_case_insensitive};
-use std::{env, error::Error, fs, process};
+use minigrep_cli_tool::{
+    Config,
+    exit::ExitStatus,
+    log::{self, Level, StderrLogger},
+    run,
+};
+use std::{
+    env,
+    io::{self, IsTerminal},
+    process,
+};
 
 ///The entry point of the Minigrep CLI Tool.
-/// 
-/// Parses command-line arguments, builds the configuration,
-/// and runs the main search routine. Any errors during argument
-/// parsing or execution display a message and terminate the process
-/// with a non-zero exit code.
-
+///
+/// Runs the command line and exits with the code of its [`ExitStatus`].
 fn main() {
-    let config = Config::build(env::args()).unwrap_or_else(|err| {
-        eprintln!("Problem parsing arguments: {err}");
-        process::exit(1)
-    });
-
-    if let Err(e) = run(config) {
-        eprintln!("Applciation error: {e}");
-        process::exit(1)
-    }
+    process::exit(run_command_line().to_code())
 }
 
-/// Holds the command-line configuration for the program.
+/// Parses command-line arguments, builds the configuration, and runs the
+/// main search routine.
 ///
-/// - `query`: The substring to search for.
-/// - `file_path`: Path to the file to search.
-/// - `ignore_case`: If `true`, performs a case-insensitive search.
-
-struct Config {
-    query: String,
-    file_path: String,
-    ignore_case: bool,
-}
-
-impl Config {
-    /// Builds a new `Config` instance from command-line arguments.
-    ///
-    /// Expected argument format:
-    /// ```
-    /// minigrep <query> <file_path> [/i or /s]
-    /// ```
-    ///
-    /// - `/i` sets `ignore_case` to true
-    /// - `/s` sets `ignore_case` to false
-    /// - If no flag is provided, the environment variable `IGNORE_CASE`
-    ///   determines behavior.
-    ///
-    /// # Errors
-    /// Returns an error if either query or file path is missing.
-    fn build(mut args: impl Iterator<Item = String>) -> Result<Config, &'static str> {
-        args.next();
-        let query = match args.next() {
-            Some(arg) => arg,
-            None => return Err("Didn't get a query string"),
-        };
-        let file_path = match args.next() {
-            Some(arg) => arg,
-            None => return Err("Didn't get a file path "),
-        };
-
-        let ignore_case_argument = match args.next() {
-            Some(value) if value == "/i" => Some(true),
-            Some(value) if value == "/s" => Some(false),
-            _ => None,
-        };
-
-        let ignore_case = match ignore_case_argument {
-            Some(value) => value,
-            None => env::var("IGNORE_CASE").is_ok()
+/// Any errors during argument parsing or execution display a message and
+/// end the run with [`ExitStatus::UsageError`] or [`ExitStatus::IoError`];
+/// otherwise the status tells whether any line was selected, and if none
+/// was, whether some inputs could not be searched, or with
+/// `--require-count`, whether enough were, as [`ExitStatus::requiring`]
+/// decides. If standard output is closed early, the run ends quietly with
+/// [`ExitStatus::Interrupted`].
+fn run_command_line() -> ExitStatus {
+    let config = match Config::build(env::args_os(), io::stdin().is_terminal(), |name| {
+        env::var(name).ok()
+    }) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Problem parsing arguments: {err}");
+            return ExitStatus::UsageError;
+        }
+    };
+    if config.debug {
+        static LOGGER: StderrLogger = StderrLogger {
+            level: Level::Debug,
         };
-
-        Ok(Config {
-            query,
-            file_path,
-            ignore_case,
-        })
+        // Nothing else installs a logger, so this cannot fail.
+        let _ = log::set_logger(&LOGGER);
     }
-}
 
-/// Executes the search process.
-///
-/// Reads the file indicated by the config, performs the search
-/// (case-sensitive or insensitive), and prints all matching lines.
-///
-/// # Errors
-/// Returns a boxed error if the file cannot be read.
-
-fn run(config: Config) -> Result<(), Box<dyn Error>> {
-    let contents = fs::read_to_string(config.file_path)?;
-
-    if config.ignore_case {
-        search_case_insensitive(&config.query, &contents).for_each(|line| println!("{line}"));
-    } else {
-        search(&config.query, &contents).for_each(|line| println!("{line}"));
-    };
-    Ok(())
+    let (required, silent) = (config.require_count, config.no_messages || config.quiet);
+    match run(config) {
+        Ok(summary) => {
+            let Some(required) = required else {
+                return ExitStatus::from(summary);
+            };
+            let status = ExitStatus::requiring(summary, required);
+            if status == ExitStatus::TooFewMatches && !silent {
+                let found = summary.matched_lines;
+                let noun = if found == 1 { "line" } else { "lines" };
+                eprintln!("minigrep: found {found} matching {noun}, {required} required");
+            }
+            status
+        }
+        Err(e) => {
+            let status = ExitStatus::from(&e);
+            if status != ExitStatus::Interrupted {
+                eprintln!("Applciation error: {e}");
+            }
+            status
+        }
+    }
 }
@@ -0,0 +1,74 @@
+//! Counters kept across many searches, for a program that embeds the
+//! search and reports how much it has done.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Totals over every search run with these metrics, as given by
+/// [`Options::metrics`](crate::grep::Options::metrics) or
+/// [`Config::metrics`](crate::Config).
+///
+/// Unlike the [`SearchStats`](crate::stats::SearchStats) of a single run,
+/// the counts add up across runs, and can be read from any thread while
+/// searches are still going on: they are updated as each input is
+/// searched. A search run without metrics counts nothing.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::{grep::{grep, Options}, metrics::Metrics};
+/// use std::sync::Arc;
+///
+/// let path = std::env::temp_dir().join("minigrep-metrics-example.txt");
+/// std::fs::write(&path, "rust\ngo\nrust again\n").unwrap();
+///
+/// let metrics = Arc::new(Metrics::default());
+/// for query in ["rust", "go"] {
+///     grep(&[&path], query, Options::default().metrics(metrics.clone())).unwrap();
+/// }
+/// assert_eq!(metrics.searches(), 2);
+/// assert_eq!(metrics.lines_scanned(), 6);
+/// assert_eq!(metrics.matched_lines(), 3);
+/// ```
+#[derive(Debug, Default)]
+pub struct Metrics {
+    searches: AtomicU64,
+    lines_scanned: AtomicU64,
+    matched_lines: AtomicU64,
+    failed_inputs: AtomicU64,
+}
+
+impl Metrics {
+    /// How many searches have been run.
+    pub fn searches(&self) -> u64 {
+        self.searches.load(Ordering::Relaxed)
+    }
+
+    /// How many lines have been read from the inputs searched.
+    pub fn lines_scanned(&self) -> u64 {
+        self.lines_scanned.load(Ordering::Relaxed)
+    }
+
+    /// How many lines have been selected.
+    pub fn matched_lines(&self) -> u64 {
+        self.matched_lines.load(Ordering::Relaxed)
+    }
+
+    /// How many inputs could not be walked, read, or rewritten.
+    pub fn failed_inputs(&self) -> u64 {
+        self.failed_inputs.load(Ordering::Relaxed)
+    }
+
+    /// Counts one more search.
+    pub(crate) fn add_search(&self) {
+        self.searches.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Counts what searching an input found.
+    pub(crate) fn add_input(&self, lines_scanned: u64, matched_lines: u64, failed_inputs: u64) {
+        self.lines_scanned
+            .fetch_add(lines_scanned, Ordering::Relaxed);
+        self.matched_lines
+            .fetch_add(matched_lines, Ordering::Relaxed);
+        self.failed_inputs
+            .fetch_add(failed_inputs, Ordering::Relaxed);
+    }
+}
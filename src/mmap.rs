@@ -0,0 +1,361 @@
+//! Reading files through a memory map instead of with `read` calls, for
+//! `--read-strategy`, and choosing which of the two to read a file with.
+//!
+//! Either way the same bytes are searched: a mapped file is read as a
+//! slice of memory, through the same line reader. Only how they reach it,
+//! and so how fast, depends on the file system and the disk underneath.
+
+use std::{
+    fmt,
+    fs::{self, File},
+    io,
+    ops::Deref,
+    path::Path,
+    sync::OnceLock,
+};
+
+/// How `--read-strategy` asks for files to be read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadStrategy {
+    /// Always with `read` calls into a buffer.
+    Buffered,
+    /// Through a memory map, for every regular file.
+    Mmap,
+    /// Through a memory map for regular files of at least
+    /// [`MMAP_MIN_LEN`] bytes that are not on a network file system, and
+    /// with `read` calls otherwise.
+    Auto,
+}
+
+/// How a file was read: see [`choose`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadMethod {
+    Buffered,
+    Mmap,
+}
+
+impl fmt::Display for ReadMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ReadMethod::Buffered => "buffered",
+            ReadMethod::Mmap => "mmap",
+        })
+    }
+}
+
+/// The size from which [`ReadStrategy::Auto`] maps a file: below it,
+/// setting a map up costs more than the copies it saves.
+pub const MMAP_MIN_LEN: u64 = 1 << 20;
+
+/// What [`choose`] goes by, as read from a file's metadata.
+///
+/// - `regular`: Whether it is a regular file, which alone can be mapped.
+/// - `len`: Its size in bytes.
+/// - `network`: Whether it is on a network file system, as far as can be
+///   told: see [`on_network_mount`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileFacts {
+    pub regular: bool,
+    pub len: u64,
+    pub network: bool,
+}
+
+impl FileFacts {
+    /// Reads the facts about the file at `path`, whose metadata is
+    /// `metadata`. Whether it is on a network file system is only looked
+    /// up if `strategy` goes by it.
+    pub fn of(path: &Path, metadata: &fs::Metadata, strategy: ReadStrategy) -> FileFacts {
+        let regular = metadata.is_file();
+        let len = metadata.len();
+        let network = strategy == ReadStrategy::Auto
+            && regular
+            && len >= MMAP_MIN_LEN
+            && on_network_mount(path);
+        FileFacts {
+            regular,
+            len,
+            network,
+        }
+    }
+}
+
+/// Chooses how to read an input: `file` is what is known about it, or
+/// `None` for standard input, which is never mapped.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::mmap::{FileFacts, ReadMethod, ReadStrategy, choose};
+///
+/// let big = FileFacts { regular: true, len: 64 << 20, network: false };
+/// let small = FileFacts { len: 512, ..big };
+/// assert_eq!(choose(ReadStrategy::Auto, Some(&big)), ReadMethod::Mmap);
+/// assert_eq!(choose(ReadStrategy::Auto, Some(&small)), ReadMethod::Buffered);
+/// assert_eq!(choose(ReadStrategy::Mmap, Some(&small)), ReadMethod::Mmap);
+/// assert_eq!(choose(ReadStrategy::Mmap, None), ReadMethod::Buffered);
+/// ```
+pub fn choose(strategy: ReadStrategy, file: Option<&FileFacts>) -> ReadMethod {
+    let mapped = match strategy {
+        ReadStrategy::Buffered => false,
+        ReadStrategy::Mmap => file.is_some_and(|file| file.regular),
+        ReadStrategy::Auto => {
+            file.is_some_and(|file| file.regular && file.len >= MMAP_MIN_LEN && !file.network)
+        }
+    };
+    if mapped {
+        ReadMethod::Mmap
+    } else {
+        ReadMethod::Buffered
+    }
+}
+
+/// The file system types that are mounted over a network, as Linux names
+/// them in `/proc/self/mounts`.
+const NETWORK_FILE_SYSTEMS: &[&str] = &[
+    "nfs",
+    "nfs4",
+    "cifs",
+    "smb3",
+    "smbfs",
+    "ncpfs",
+    "afs",
+    "9p",
+    "ceph",
+    "glusterfs",
+    "lustre",
+    "fuse.sshfs",
+    "fuse.rclone",
+    "davfs",
+];
+
+/// Returns whether the file at `path` is on a network file system, as
+/// listed in `/proc/self/mounts` on Linux. Elsewhere this cannot be told,
+/// and no file is.
+pub fn on_network_mount(path: &Path) -> bool {
+    static MOUNTS: OnceLock<String> = OnceLock::new();
+    if !cfg!(target_os = "linux") {
+        return false;
+    }
+    let mounts = MOUNTS.get_or_init(|| fs::read_to_string("/proc/self/mounts").unwrap_or_default());
+    let Ok(path) = fs::canonicalize(path) else {
+        return false;
+    };
+    mount_type(mounts, &path).is_some_and(|fs_type| NETWORK_FILE_SYSTEMS.contains(&fs_type))
+}
+
+/// Returns the type of the file system that the absolute `path` is on, as
+/// listed in `mounts`, in the format of `/proc/self/mounts`: that of the
+/// longest mount point it is under, the last one mounted there if there
+/// are several.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::mmap::mount_type;
+/// use std::path::Path;
+///
+/// let mounts = "/dev/sda1 / ext4 rw 0 0\nserver:/export /mnt/logs nfs4 rw 0 0\n";
+/// assert_eq!(mount_type(mounts, Path::new("/mnt/logs/app.log")), Some("nfs4"));
+/// assert_eq!(mount_type(mounts, Path::new("/mnt/logsets/a")), Some("ext4"));
+/// ```
+pub fn mount_type<'m>(mounts: &'m str, path: &Path) -> Option<&'m str> {
+    let mut found: Option<(usize, &str)> = None;
+    for line in mounts.lines() {
+        let mut fields = line.split(' ');
+        let (Some(_), Some(point), Some(fs_type)) = (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        // Spaces and other separators in mount points are octal escapes.
+        let point = point.replace("\\040", " ").replace("\\011", "\t");
+        let depth = Path::new(&point).components().count();
+        if path.starts_with(&point) && found.is_none_or(|(deepest, _)| depth >= deepest) {
+            found = Some((depth, fs_type));
+        }
+    }
+    found.map(|(_, fs_type)| fs_type)
+}
+
+/// A file mapped into memory to read, unmapped when dropped.
+///
+/// The map is private and read-only, but it is not a copy: if another
+/// process changes the file while it is mapped, the change may be seen,
+/// and if the file is cut short, reading past its new end may kill the
+/// process with `SIGBUS`.
+pub struct Mmap {
+    ptr: *mut u8,
+    len: usize,
+}
+
+impl Mmap {
+    /// Maps the whole of `file`, as long as it is when mapped.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be mapped, such as one that is
+    /// not a regular file, and on platforms other than Unix, where files
+    /// are never mapped, one of kind [`io::ErrorKind::Unsupported`].
+    pub fn map(file: &File) -> io::Result<Mmap> {
+        let len = usize::try_from(file.metadata()?.len())
+            .map_err(|_| io::Error::other("too large to map"))?;
+        if len == 0 {
+            // An empty map is an error, and there is nothing to read anyway.
+            return Ok(Mmap {
+                ptr: std::ptr::null_mut(),
+                len,
+            });
+        }
+        map(file, len).map(|ptr| Mmap { ptr, len })
+    }
+}
+
+#[cfg(unix)]
+fn map(file: &File, len: usize) -> io::Result<*mut u8> {
+    use std::{
+        ffi::{c_int, c_long, c_void},
+        os::fd::AsRawFd,
+    };
+
+    const PROT_READ: c_int = 1;
+    const MAP_PRIVATE: c_int = 2;
+    unsafe extern "C" {
+        fn mmap(
+            addr: *mut c_void,
+            len: usize,
+            prot: c_int,
+            flags: c_int,
+            fd: c_int,
+            offset: c_long,
+        ) -> *mut c_void;
+    }
+    // SAFETY: the map is of `len` bytes of an open file, from its start,
+    // and left for the kernel to place; failure is checked for below.
+    let ptr = unsafe {
+        mmap(
+            std::ptr::null_mut(),
+            len,
+            PROT_READ,
+            MAP_PRIVATE,
+            file.as_raw_fd(),
+            0,
+        )
+    };
+    // `MAP_FAILED` is the address -1.
+    if ptr as usize == usize::MAX {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(ptr.cast())
+}
+
+#[cfg(not(unix))]
+fn map(_file: &File, _len: usize) -> io::Result<*mut u8> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "memory maps are only used on Unix",
+    ))
+}
+
+impl Deref for Mmap {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        if self.len == 0 {
+            return &[];
+        }
+        // SAFETY: `ptr` points to the `len` readable bytes mapped, which
+        // stay mapped until this value is dropped.
+        unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+impl AsRef<[u8]> for Mmap {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl Drop for Mmap {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if self.len > 0 {
+            use std::ffi::{c_int, c_void};
+
+            unsafe extern "C" {
+                fn munmap(addr: *mut c_void, len: usize) -> c_int;
+            }
+            // SAFETY: this is the map made in `Mmap::map`, unmapped once.
+            unsafe {
+                munmap(self.ptr.cast(), self.len);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn facts(regular: bool, len: u64, network: bool) -> FileFacts {
+        FileFacts {
+            regular,
+            len,
+            network,
+        }
+    }
+
+    #[test]
+    fn auto_maps_only_large_regular_local_files() {
+        let auto = |file: FileFacts| choose(ReadStrategy::Auto, Some(&file));
+        assert_eq!(auto(facts(true, MMAP_MIN_LEN, false)), ReadMethod::Mmap);
+        assert_eq!(
+            auto(facts(true, MMAP_MIN_LEN - 1, false)),
+            ReadMethod::Buffered
+        );
+        assert_eq!(auto(facts(true, 1 << 30, true)), ReadMethod::Buffered);
+        assert_eq!(auto(facts(false, 1 << 30, false)), ReadMethod::Buffered);
+        assert_eq!(choose(ReadStrategy::Auto, None), ReadMethod::Buffered);
+    }
+
+    #[test]
+    fn explicit_strategies_are_followed_where_they_can_be() {
+        let network = facts(true, 10, true);
+        assert_eq!(choose(ReadStrategy::Mmap, Some(&network)), ReadMethod::Mmap);
+        assert_eq!(
+            choose(ReadStrategy::Mmap, Some(&facts(false, 10, false))),
+            ReadMethod::Buffered
+        );
+        assert_eq!(
+            choose(ReadStrategy::Buffered, Some(&facts(true, 1 << 30, false))),
+            ReadMethod::Buffered
+        );
+    }
+
+    #[test]
+    fn mount_types_come_from_the_deepest_mount_point() {
+        let mounts = "\
+            /dev/sda1 / ext4 rw 0 0\n\
+            tmpfs /mnt tmpfs rw 0 0\n\
+            server:/share /mnt/team\\040logs cifs rw 0 0\n\
+            server:/share /mnt/team\\040logs nfs rw 0 0\n\
+            malformed\n";
+        let fs_type = |path: &str| mount_type(mounts, Path::new(path));
+        assert_eq!(fs_type("/home/a.log"), Some("ext4"));
+        assert_eq!(fs_type("/mnt/a.log"), Some("tmpfs"));
+        assert_eq!(fs_type("/mnt/team logs/a.log"), Some("nfs"));
+        assert_eq!(mount_type("", Path::new("/a.log")), None);
+    }
+
+    #[test]
+    fn mapped_files_read_as_their_bytes() {
+        let dir = std::env::temp_dir().join("minigrep-mmap");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("mapped.txt");
+        fs::write(&path, "one\ntwo\n").unwrap();
+        let file = File::open(&path).unwrap();
+        match Mmap::map(&file) {
+            Ok(map) => assert_eq!(&map[..], b"one\ntwo\n"),
+            Err(error) => assert_eq!(error.kind(), io::ErrorKind::Unsupported),
+        }
+
+        fs::write(&path, "").unwrap();
+        let file = File::open(&path).unwrap();
+        assert!(Mmap::map(&file).unwrap().is_empty());
+    }
+}
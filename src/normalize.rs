@@ -0,0 +1,242 @@
+//! The whitespace normalization of `--trim` and `--squeeze-space`, under
+//! which a line is matched as if its padding were gone, while what is
+//! printed, and highlighted, is still the line as it was read.
+
+use std::ops::Range;
+
+use crate::Matcher;
+
+/// A line as it is matched once normalized, along with where each of its
+/// bytes came from in the original line.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::normalize::normalize;
+///
+/// let line = "  key \t =  value ";
+/// let normalized = normalize(line, true, true);
+/// assert_eq!(normalized.text(), "key = value");
+/// // The space between "key" and "=" stands for the whole run.
+/// let range = normalized.original(3..5);
+/// assert_eq!(&line[range], " \t =");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Normalized {
+    text: String,
+    /// For each byte of `text`, the range of the original line it stands
+    /// for: the byte it was copied from, or a whole run of whitespace.
+    starts: Vec<usize>,
+    ends: Vec<usize>,
+}
+
+impl Normalized {
+    /// The normalized text.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The normalized text, leaving the rest behind.
+    pub fn into_text(self) -> String {
+        self.text
+    }
+
+    /// Maps `range`, a non-empty range of the normalized text on character
+    /// boundaries, back to the range of the original line it came from.
+    pub fn original(&self, range: Range<usize>) -> Range<usize> {
+        self.starts[range.start]..self.ends[range.end - 1]
+    }
+
+    /// Appends `c`, which stands for `original` in the original line.
+    fn push(&mut self, c: char, original: Range<usize>) {
+        self.text.push(c);
+        let last = c.len_utf8() - 1;
+        for index in 0..=last {
+            self.starts.push(original.start + index);
+            self.ends.push(if index == last {
+                original.end
+            } else {
+                original.start + index + 1
+            });
+        }
+    }
+}
+
+/// Normalizes `line`: without its leading and trailing whitespace if
+/// `trim`, and with every run of whitespace, tabs included, made a single
+/// space if `squeeze_space`.
+pub fn normalize(line: &str, trim: bool, squeeze_space: bool) -> Normalized {
+    let (offset, line) = if trim {
+        let trimmed = line.trim_start();
+        (line.len() - trimmed.len(), trimmed.trim_end())
+    } else {
+        (0, line)
+    };
+    let mut normalized = Normalized {
+        text: String::with_capacity(line.len()),
+        starts: Vec::with_capacity(line.len()),
+        ends: Vec::with_capacity(line.len()),
+    };
+    let mut chars = line.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        let start = offset + index;
+        let mut end = start + c.len_utf8();
+        if squeeze_space && c.is_whitespace() {
+            while let Some((index, c)) = chars.next_if(|(_, c)| c.is_whitespace()) {
+                end = offset + index + c.len_utf8();
+            }
+            normalized.push(' ', start..end);
+        } else {
+            normalized.push(c, start..end);
+        }
+    }
+    normalized
+}
+
+/// Makes every run of whitespace in `query` a single space, as
+/// `--squeeze-space` does to lines, so that it matches them.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::normalize::squeeze;
+///
+/// assert_eq!(squeeze("key\t  = value"), "key = value");
+/// ```
+pub fn squeeze(query: &str) -> String {
+    normalize(query, false, true).into_text()
+}
+
+/// A matcher looking at lines once normalized, whose ranges refer to the
+/// original lines; with neither `trim` nor `squeeze_space`, it is the
+/// matcher itself.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::{Matcher, normalize::Normalizing};
+///
+/// let matcher = Normalizing::new("a b", false, true);
+/// assert!(matcher.is_match("a \t b", false));
+/// assert_eq!(matcher.match_ranges("x a  b", false), vec![2..6]);
+/// ```
+pub struct Normalizing<'m, M: Matcher + ?Sized> {
+    matcher: &'m M,
+    trim: bool,
+    squeeze_space: bool,
+}
+
+impl<'m, M: Matcher + ?Sized> Normalizing<'m, M> {
+    /// Matches `matcher` against lines normalized as [`normalize`] does.
+    pub fn new(matcher: &'m M, trim: bool, squeeze_space: bool) -> Normalizing<'m, M> {
+        Normalizing {
+            matcher,
+            trim,
+            squeeze_space,
+        }
+    }
+
+    fn normalizes(&self) -> bool {
+        self.trim || self.squeeze_space
+    }
+}
+
+impl<M: Matcher + ?Sized> Matcher for Normalizing<'_, M> {
+    fn is_match(&self, line: &str, ignore_case: bool) -> bool {
+        if !self.normalizes() {
+            return self.matcher.is_match(line, ignore_case);
+        }
+        let normalized = normalize(line, self.trim, self.squeeze_space);
+        self.matcher.is_match(normalized.text(), ignore_case)
+    }
+
+    fn match_ranges(&self, line: &str, ignore_case: bool) -> Vec<Range<usize>> {
+        if !self.normalizes() {
+            return self.matcher.match_ranges(line, ignore_case);
+        }
+        let normalized = normalize(line, self.trim, self.squeeze_space);
+        self.matcher
+            .match_ranges(normalized.text(), ignore_case)
+            .into_iter()
+            .map(|range| normalized.original(range))
+            .collect()
+    }
+
+    fn count(&self, line: &str, ignore_case: bool) -> usize {
+        if !self.normalizes() {
+            return self.matcher.count(line, ignore_case);
+        }
+        let normalized = normalize(line, self.trim, self.squeeze_space);
+        self.matcher.count(normalized.text(), ignore_case)
+    }
+
+    fn pattern_ranges(&self, line: &str, ignore_case: bool) -> Vec<(Range<usize>, usize)> {
+        if !self.normalizes() {
+            return self.matcher.pattern_ranges(line, ignore_case);
+        }
+        let normalized = normalize(line, self.trim, self.squeeze_space);
+        self.matcher
+            .pattern_ranges(normalized.text(), ignore_case)
+            .into_iter()
+            .map(|(range, pattern)| (normalized.original(range), pattern))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn highlighted<'l>(
+        matcher: &str,
+        trim: bool,
+        squeeze_space: bool,
+        line: &'l str,
+    ) -> Vec<&'l str> {
+        let matcher = Normalizing::new(matcher, trim, squeeze_space);
+        matcher
+            .match_ranges(line, false)
+            .into_iter()
+            .map(|range| &line[range])
+            .collect()
+    }
+
+    #[test]
+    fn trimming_drops_the_padding_at_both_ends() {
+        assert_eq!(normalize(" \t a  b \t", true, false).text(), "a  b");
+        assert_eq!(normalize("   ", true, false).text(), "");
+        assert_eq!(normalize(" a ", false, false).text(), " a ");
+        assert!(Normalizing::new("a  b", true, false).is_match("\t a  b  ", false));
+        assert!(!Normalizing::new(" a", true, false).is_match("  a", false));
+    }
+
+    #[test]
+    fn squeezing_makes_runs_of_tabs_and_spaces_one_space() {
+        assert_eq!(normalize("a\tb", false, true).text(), "a b");
+        assert_eq!(normalize("a \t  b   c", false, true).text(), "a b c");
+        assert_eq!(normalize("  a  ", false, true).text(), " a ");
+        assert_eq!(normalize("  a  ", true, true).text(), "a");
+        assert_eq!(squeeze(" key\t\t= 1 "), " key = 1 ");
+    }
+
+    #[test]
+    fn highlights_cover_the_original_text_of_matches() {
+        assert_eq!(
+            highlighted("key = value", false, true, "\tkey  =\t\tvalue;"),
+            ["key  =\t\tvalue"]
+        );
+        assert_eq!(
+            highlighted("a ", false, true, "a   b a\tc"),
+            ["a   ", "a\t"]
+        );
+        assert_eq!(highlighted("x", true, false, "  x y x  "), ["x", "x"]);
+        assert_eq!(highlighted("é ü", true, true, " é \t ü "), ["é \t ü"]);
+        let matcher = Normalizing::new("b c", true, true);
+        assert_eq!(matcher.match_ranges("  a   b \t c", false), vec![6..11]);
+        assert_eq!(matcher.count("b c b  c", false), 2);
+    }
+
+    #[test]
+    fn without_normalization_lines_are_matched_as_they_are() {
+        let matcher = Normalizing::new("a b", false, false);
+        assert!(!matcher.is_match("a  b", false));
+        assert_eq!(matcher.match_ranges(" a b", false), vec![1..4]);
+    }
+}
@@ -0,0 +1,1070 @@
+//! The options minigrep takes, as data: their names, whether they take a
+//! value, and what they do.
+//!
+//! [`Config::build`](crate::Config::build) looks each argument up in
+//! [`OPTIONS`] to know whether it is an option, what value to take for it,
+//! and what to do with the value, and the scripts of
+//! `--generate-completions` are made from the same table, so that an option
+//! added here is both parsed and completed.
+
+use crate::{
+    completions::{self, Shell},
+    config::{Arg, First, OutputFormat, Parsed, Sort, parse_number, read_patterns},
+    csv,
+    encoding::Encoding,
+    glob::Glob,
+    mmap::ReadStrategy,
+    quote::Quoting,
+    render::Template,
+    strings,
+    timestamp::Untimed,
+    types,
+    walk::{parse_duration, parse_size},
+};
+
+/// Whether an option takes a value, and how it is named in help.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Value {
+    /// No value, as for `--trim`.
+    None,
+    /// A value, given inline as `--name=VALUE` or as the next argument.
+    Required(&'static str),
+    /// A value that may be left out, and is only given inline, as
+    /// `--color=WHEN`.
+    Optional(&'static str),
+}
+
+/// What an option does to the command line being parsed.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Parse {
+    /// Turns on the flag the function returns.
+    Flag(fn(&mut Parsed) -> &mut bool),
+    /// Takes the argument in, with its value if it has one.
+    With(fn(&mut Parsed, &mut Arg<'_>) -> Result<(), String>),
+}
+
+/// An option of the command line.
+///
+/// - `long`: Its name, such as `--line-number`.
+/// - `short`: Its one-letter name, such as `-n`, if it has one.
+/// - `value`: Whether it takes a value.
+/// - `choices`: The values it takes, if it only takes some; empty
+///   otherwise.
+/// - `help`: What it does, in a line.
+///
+/// Options are equal if they have the same long name, which names one
+/// option only.
+#[derive(Debug, Clone, Copy)]
+pub struct Opt {
+    pub long: &'static str,
+    pub short: Option<&'static str>,
+    pub value: Value,
+    pub choices: &'static [&'static str],
+    pub help: &'static str,
+    pub(crate) parse: Parse,
+}
+
+impl PartialEq for Opt {
+    fn eq(&self, other: &Opt) -> bool {
+        self.long == other.long
+    }
+}
+
+impl Eq for Opt {}
+
+impl Opt {
+    /// An option without a short name, value, or choices, which `parse`
+    /// takes in.
+    const fn new(
+        long: &'static str,
+        help: &'static str,
+        parse: fn(&mut Parsed, &mut Arg<'_>) -> Result<(), String>,
+    ) -> Opt {
+        Opt {
+            long,
+            short: None,
+            value: Value::None,
+            choices: &[],
+            help,
+            parse: Parse::With(parse),
+        }
+    }
+
+    /// An option turning on the flag `flag` returns, without a short name.
+    const fn flag(
+        long: &'static str,
+        help: &'static str,
+        flag: fn(&mut Parsed) -> &mut bool,
+    ) -> Opt {
+        Opt {
+            long,
+            short: None,
+            value: Value::None,
+            choices: &[],
+            help,
+            parse: Parse::Flag(flag),
+        }
+    }
+
+    const fn short(self, short: &'static str) -> Opt {
+        Opt {
+            short: Some(short),
+            ..self
+        }
+    }
+
+    const fn value(self, name: &'static str) -> Opt {
+        Opt {
+            value: Value::Required(name),
+            ..self
+        }
+    }
+
+    const fn optional_value(self, name: &'static str) -> Opt {
+        Opt {
+            value: Value::Optional(name),
+            ..self
+        }
+    }
+
+    const fn choices(self, choices: &'static [&'static str]) -> Opt {
+        Opt { choices, ..self }
+    }
+
+    /// Returns the names of the option, its short one first.
+    pub fn names(&self) -> impl Iterator<Item = &'static str> {
+        self.short.into_iter().chain([self.long])
+    }
+}
+
+/// Every option, in the order `Config::build` parses them.
+pub const OPTIONS: &[Opt] = &[
+    Opt::flag(
+        "--trim",
+        "match lines without their leading and trailing whitespace",
+        |parsed| &mut parsed.trim,
+    ),
+    Opt::flag(
+        "--squeeze-space",
+        "match with every run of whitespace made a single space",
+        |parsed| &mut parsed.squeeze_space,
+    ),
+    Opt::flag(
+        "--escapes",
+        "interpret backslash escapes in the query",
+        |parsed| &mut parsed.escapes,
+    ),
+    Opt::new(
+        "--expr",
+        "select lines by a boolean expression of quoted literals",
+        |parsed, arg| {
+            parsed.expr = Some(arg.value()?);
+            Ok(())
+        },
+    )
+    .value("EXPR"),
+    Opt::new(
+        "--and-not",
+        "select only the lines with the query and not PATTERN",
+        |parsed, arg| {
+            parsed.and_not.push(arg.value()?);
+            Ok(())
+        },
+    )
+    .value("PATTERN"),
+    Opt::new(
+        "--pattern",
+        "search for PATTERN, which may be given again",
+        |parsed, arg| {
+            parsed.patterns.push(arg.value()?);
+            parsed.pattern_option.get_or_insert("-e");
+            Ok(())
+        },
+    )
+    .short("-e")
+    .value("PATTERN"),
+    Opt::new(
+        "--file",
+        "search for the patterns in FILE, one per line",
+        |parsed, arg| {
+            parsed.patterns.extend(read_patterns(&arg.value()?)?);
+            parsed.pattern_option.get_or_insert("-f");
+            Ok(())
+        },
+    )
+    .short("-f")
+    .value("FILE"),
+    Opt::new(
+        "--between",
+        "select the ranges of lines from START to the next END",
+        |parsed, arg| {
+            let start = arg.value()?;
+            let flag = arg.flag;
+            let end = arg
+                .next()
+                .ok_or_else(|| format!("Option '{flag}' requires an end pattern"))?
+                .into_string()
+                .map_err(|_| format!("The end pattern of '{flag}' is not valid Unicode"))?;
+            parsed.between = Some((start, end));
+            Ok(())
+        },
+    )
+    .value("START"),
+    Opt::new(
+        "--since",
+        "only search the lines stamped at or after TS",
+        |parsed, arg| {
+            parsed.since = Some(arg.value()?);
+            Ok(())
+        },
+    )
+    .value("TS"),
+    Opt::new(
+        "--until",
+        "only search the lines stamped before TS",
+        |parsed, arg| {
+            parsed.until = Some(arg.value()?);
+            Ok(())
+        },
+    )
+    .value("TS"),
+    Opt::new(
+        "--no-timestamp",
+        "whether to keep the lines without a timestamp in a window",
+        |parsed, arg| {
+            parsed.untimed = Some(arg.choice(Untimed::parse)?);
+            Ok(())
+        },
+    )
+    .value("WHAT")
+    .choices(&["keep", "drop"]),
+    Opt::flag(
+        "--line-number",
+        "prefix each line with its line number",
+        |parsed| &mut parsed.line_number,
+    )
+    .short("-n"),
+    Opt::flag(
+        "--column",
+        "prefix each line with the column of its match",
+        |parsed| &mut parsed.column,
+    ),
+    Opt::flag(
+        "--byte-column",
+        "like --column, counting in bytes",
+        |parsed| &mut parsed.byte_column,
+    ),
+    Opt::flag(
+        "--byte-offset",
+        "prefix each line with its byte offset",
+        |parsed| &mut parsed.byte_offset,
+    )
+    .short("-b"),
+    Opt::flag(
+        "--hex",
+        "search the raw bytes of inputs for the hex query",
+        |parsed| &mut parsed.hex,
+    ),
+    Opt::new(
+        "--hex-context",
+        "with --hex, print the NUM bytes around each occurrence",
+        |parsed, arg| {
+            parsed.hex_context = Some(arg.number()?);
+            Ok(())
+        },
+    )
+    .value("NUM"),
+    Opt::new(
+        "--strings",
+        "search the runs of at least MIN printable characters",
+        |parsed, arg| {
+            parsed.strings = Some(match arg.optional_value() {
+                Some(value) => match parse_number(arg.flag, &value)? {
+                    0 => return Err(format!("Invalid number '{value}' for '{}'", arg.flag)),
+                    min => min,
+                },
+                None => strings::DEFAULT_MIN,
+            });
+            Ok(())
+        },
+    )
+    .optional_value("MIN"),
+    Opt::flag(
+        "--with-filename",
+        "prefix each line with its file name",
+        |parsed| &mut parsed.with_filename,
+    )
+    .short("-H"),
+    Opt::flag("--recursive", "search directories recursively", |parsed| {
+        &mut parsed.recursive
+    })
+    .short("-r"),
+    Opt::new(
+        "--max-depth",
+        "with -r, descend at most NUM directory levels",
+        |parsed, arg| {
+            parsed.max_depth = Some(arg.number()?);
+            Ok(())
+        },
+    )
+    .value("NUM"),
+    Opt::new(
+        "--include",
+        "with -r, search only files matching GLOB",
+        |parsed, arg| {
+            parsed.include.push(Glob::new(&arg.value()?)?);
+            Ok(())
+        },
+    )
+    .value("GLOB"),
+    Opt::new(
+        "--exclude",
+        "with -r, skip files matching GLOB",
+        |parsed, arg| {
+            parsed.exclude.push(Glob::new(&arg.value()?)?);
+            Ok(())
+        },
+    )
+    .value("GLOB"),
+    Opt::new(
+        "--exclude-dir",
+        "with -r, skip directories matching GLOB",
+        |parsed, arg| {
+            parsed.exclude_dir.push(Glob::new(&arg.value()?)?);
+            Ok(())
+        },
+    )
+    .value("GLOB"),
+    Opt::new(
+        "--type",
+        "with -r, search only files of the type TYPE",
+        |parsed, arg| {
+            for glob in types::globs(&arg.value()?)? {
+                parsed.include.push(Glob::new(glob)?);
+            }
+            Ok(())
+        },
+    )
+    .short("-t")
+    .value("TYPE"),
+    Opt::new(
+        "--type-not",
+        "with -r, skip files of the type TYPE",
+        |parsed, arg| {
+            for glob in types::globs(&arg.value()?)? {
+                parsed.exclude.push(Glob::new(glob)?);
+            }
+            Ok(())
+        },
+    )
+    .short("-T")
+    .value("TYPE"),
+    Opt::flag(
+        "--type-list",
+        "print the built-in file types and exit",
+        |parsed| &mut parsed.type_list,
+    ),
+    Opt::flag(
+        "--hidden",
+        "with -r, search hidden files and directories",
+        |parsed| &mut parsed.hidden,
+    ),
+    Opt::flag("--follow", "with -r, follow symbolic links", |parsed| {
+        &mut parsed.follow
+    }),
+    Opt::flag(
+        "--no-dedupe",
+        "with -r, search a file under every path",
+        |parsed| &mut parsed.no_dedupe,
+    ),
+    Opt::flag(
+        "--one-file-system",
+        "with -r, stay on the file system of each path",
+        |parsed| &mut parsed.one_file_system,
+    ),
+    Opt::new(
+        "--devices",
+        "read or skip devices, FIFOs, and sockets named",
+        |parsed, arg| {
+            parsed.read_devices = arg.choice(|value| match value {
+                "read" => Some(true),
+                "skip" => Some(false),
+                _ => None,
+            })?;
+            Ok(())
+        },
+    )
+    .value("ACTION")
+    .choices(&["read", "skip"]),
+    Opt::flag(
+        "--no-ignore",
+        "with -r, search paths listed in .gitignore and .ignore files",
+        |parsed| &mut parsed.no_ignore,
+    ),
+    Opt::flag(
+        "--no-ignore-files",
+        "with -r, read no ignore files at all",
+        |parsed| &mut parsed.no_ignore_files,
+    ),
+    Opt::flag(
+        "--search-zip",
+        "with -r, search compressed files and archives",
+        |parsed| &mut parsed.search_zip,
+    ),
+    Opt::new(
+        "--pre",
+        "search what COMMAND prints for each file",
+        |parsed, arg| {
+            parsed.pre = Some(arg.value()?);
+            Ok(())
+        },
+    )
+    .value("COMMAND"),
+    Opt::new(
+        "--pre-glob",
+        "with --pre, preprocess only files matching GLOB",
+        |parsed, arg| {
+            parsed.pre_glob.push(Glob::new(&arg.value()?)?);
+            Ok(())
+        },
+    )
+    .value("GLOB"),
+    Opt::new(
+        "--max-columns",
+        "omit lines longer than NUM characters",
+        |parsed, arg| {
+            parsed.max_columns = Some(arg.number()?);
+            Ok(())
+        },
+    )
+    .value("NUM"),
+    Opt::flag(
+        "--max-columns-preview",
+        "with --max-columns, print the start of long lines",
+        |parsed| &mut parsed.max_columns_preview,
+    ),
+    Opt::new(
+        "--snippet",
+        "print only the NUM characters around the first match",
+        |parsed, arg| {
+            parsed.snippet = Some(arg.number()?);
+            Ok(())
+        },
+    )
+    .value("NUM"),
+    Opt::new(
+        "--char-context",
+        "print the NUM characters around each match on its own line",
+        |parsed, arg| {
+            parsed.char_context = Some(arg.number()?);
+            Ok(())
+        },
+    )
+    .value("NUM"),
+    Opt::new(
+        "--max-filesize",
+        "skip files larger than SIZE",
+        |parsed, arg| {
+            parsed.max_filesize = Some(parse_size(&arg.value()?)?);
+            Ok(())
+        },
+    )
+    .value("SIZE"),
+    Opt::new(
+        "--min-filesize",
+        "skip files smaller than SIZE",
+        |parsed, arg| {
+            parsed.min_filesize = Some(parse_size(&arg.value()?)?);
+            Ok(())
+        },
+    )
+    .value("SIZE"),
+    Opt::new(
+        "--newer-than",
+        "skip files last modified longer ago than DURATION",
+        |parsed, arg| {
+            parsed.newer_than = Some(parse_duration(&arg.value()?)?);
+            Ok(())
+        },
+    )
+    .value("DURATION"),
+    Opt::new(
+        "--older-than",
+        "skip files last modified within DURATION",
+        |parsed, arg| {
+            parsed.older_than = Some(parse_duration(&arg.value()?)?);
+            Ok(())
+        },
+    )
+    .value("DURATION"),
+    Opt::flag(
+        "--invert-match",
+        "select the lines that do not match",
+        |parsed| &mut parsed.invert,
+    )
+    .short("-v"),
+    Opt::flag(
+        "--count",
+        "print only the number of selected lines",
+        |parsed| &mut parsed.count,
+    )
+    .short("-c"),
+    Opt::flag(
+        "--count-matches",
+        "print only the number of matches",
+        |parsed| &mut parsed.count_matches,
+    ),
+    Opt::flag(
+        "--count-total",
+        "with -c, also print the total of all files",
+        |parsed| &mut parsed.count_total,
+    ),
+    Opt::flag(
+        "--count-by-file",
+        "print the counts of the files with matches once done",
+        |parsed| &mut parsed.count_by_file,
+    ),
+    Opt::flag(
+        "--sort-count",
+        "with --count-by-file, print the largest counts first",
+        |parsed| &mut parsed.sort_count,
+    ),
+    Opt::flag(
+        "--group-by-dir",
+        "print the counts of each directory once done",
+        |parsed| &mut parsed.group_by_dir,
+    ),
+    Opt::new(
+        "--group-depth",
+        "with --group-by-dir, add up directories NUM levels down",
+        |parsed, arg| {
+            parsed.group_depth = Some(arg.number()?);
+            Ok(())
+        },
+    )
+    .value("NUM"),
+    Opt::flag(
+        "--wc",
+        "print the lines, words, and bytes selected in each file",
+        |parsed| &mut parsed.wc,
+    ),
+    Opt::flag("--only-matching", "print only the matches", |parsed| {
+        &mut parsed.only_matching
+    })
+    .short("-o"),
+    Opt::flag(
+        "--unique-counts",
+        "with -o, print each distinct match once with its count",
+        |parsed| &mut parsed.unique_counts,
+    ),
+    Opt::new(
+        "--top",
+        "print only the NUM largest counts",
+        |parsed, arg| {
+            parsed.top = Some(arg.number()?);
+            Ok(())
+        },
+    )
+    .value("NUM"),
+    Opt::flag(
+        "--line-numbers-only",
+        "print only the numbers of the selected lines",
+        |parsed| &mut parsed.line_numbers_only,
+    ),
+    Opt::flag(
+        "--ranges",
+        "with --line-numbers-only, print ranges of numbers",
+        |parsed| &mut parsed.ranges,
+    ),
+    Opt::flag(
+        "--vimgrep",
+        "print PATH:LINE:COLUMN:TEXT for every match",
+        |parsed| &mut parsed.vimgrep,
+    ),
+    Opt::new(
+        "--replace",
+        "print the matches replaced by TEXT",
+        |parsed, arg| {
+            parsed.replace = Some(arg.value()?);
+            Ok(())
+        },
+    )
+    .value("TEXT"),
+    Opt::flag(
+        "--in-place",
+        "with --replace, rewrite the files",
+        |parsed| &mut parsed.in_place,
+    ),
+    Opt::flag(
+        "--no-backup",
+        "with --in-place, keep no .bak copy",
+        |parsed| &mut parsed.no_backup,
+    ),
+    Opt::flag(
+        "--passthru",
+        "print every line, highlighting the matches",
+        |parsed| &mut parsed.passthru,
+    ),
+    Opt::new(
+        "--after-context",
+        "also print the NUM lines after each match",
+        |parsed, arg| {
+            parsed.after_context = Some(arg.number()?);
+            parsed.context_flag = Some("-A");
+            Ok(())
+        },
+    )
+    .short("-A")
+    .value("NUM"),
+    Opt::new(
+        "--before-context",
+        "also print the NUM lines before each match",
+        |parsed, arg| {
+            parsed.before_context = Some(arg.number()?);
+            parsed.context_flag = Some("-B");
+            Ok(())
+        },
+    )
+    .short("-B")
+    .value("NUM"),
+    Opt::new(
+        "--context",
+        "also print the NUM lines around each match",
+        |parsed, arg| {
+            parsed.context = Some(arg.number()?);
+            parsed.context_flag = Some("-C");
+            Ok(())
+        },
+    )
+    .short("-C")
+    .value("NUM"),
+    Opt::new(
+        "--after-context-until",
+        "also print the lines after each match until STOP",
+        |parsed, arg| {
+            parsed.context_until = Some(arg.value()?);
+            parsed.context_flag = Some("--after-context-until");
+            Ok(())
+        },
+    )
+    .value("STOP"),
+    Opt::flag(
+        "--include-stop",
+        "with --after-context-until, print the STOP line too",
+        |parsed| &mut parsed.include_stop,
+    ),
+    Opt::new(
+        "--group-separator",
+        "separate the groups of context by a SEP line",
+        |parsed, arg| {
+            parsed.group_separator = Some(arg.value()?);
+            parsed.group_separator_given = true;
+            Ok(())
+        },
+    )
+    .value("SEP"),
+    Opt::new(
+        "--no-group-separator",
+        "print the groups of context without separators",
+        |parsed, _| {
+            parsed.group_separator = None;
+            parsed.group_separator_given = true;
+            Ok(())
+        },
+    ),
+    Opt::flag(
+        "--files-with-matches",
+        "print only the names of files with a match",
+        |parsed| &mut parsed.files_with_matches,
+    )
+    .short("-l"),
+    Opt::flag(
+        "--files-without-match",
+        "print only the names of files without a match",
+        |parsed| &mut parsed.files_without_match,
+    )
+    .short("-L"),
+    Opt::flag(
+        "--files",
+        "print the files that would be searched",
+        |parsed| &mut parsed.list_files,
+    ),
+    Opt::new(
+        "--heading",
+        "print each file name once, above its lines",
+        |parsed, _| {
+            parsed.heading = Some(true);
+            Ok(())
+        },
+    ),
+    Opt::new(
+        "--no-heading",
+        "prefix every line with its file name",
+        |parsed, _| {
+            parsed.heading = Some(false);
+            Ok(())
+        },
+    ),
+    Opt::flag(
+        "--null-data",
+        "read and print records ended by NUL bytes",
+        |parsed| &mut parsed.null_data,
+    )
+    .short("-z"),
+    Opt::flag("--null", "end file names with a NUL byte", |parsed| {
+        &mut parsed.null
+    })
+    .short("-Z"),
+    Opt::new(
+        "--quote",
+        "print file names quoted in STYLE",
+        |parsed, arg| {
+            parsed.quote = arg.choice(Quoting::parse)?;
+            Ok(())
+        },
+    )
+    .value("STYLE")
+    .choices(&["none", "shell"]),
+    Opt::new(
+        "--encoding",
+        "read inputs in the encoding ENC",
+        |parsed, arg| {
+            let value = arg.value()?;
+            let encoding =
+                Encoding::parse(&value).ok_or_else(|| format!("Unknown encoding '{value}'"))?;
+            parsed.encoding = Some(encoding);
+            Ok(())
+        },
+    )
+    .value("ENC")
+    .choices(&["utf-8", "utf-16le", "utf-16be", "latin-1"]),
+    Opt::flag(
+        "--no-unicode",
+        "match bytes, ignoring case for ASCII letters only",
+        |parsed| &mut parsed.no_unicode,
+    ),
+    Opt::flag(
+        "--absolute-path",
+        "print the names of files as absolute paths",
+        |parsed| &mut parsed.absolute_path,
+    ),
+    Opt::new(
+        "--label",
+        "report standard input under NAME",
+        |parsed, arg| {
+            parsed.label = arg.value()?;
+            Ok(())
+        },
+    )
+    .value("NAME"),
+    Opt::new(
+        "--files-from",
+        "also search the paths listed in FILE",
+        |parsed, arg| parsed.files_from(arg, b'\n'),
+    )
+    .value("FILE"),
+    Opt::new(
+        "--files0-from",
+        "also search the NUL-separated paths listed in FILE",
+        |parsed, arg| parsed.files_from(arg, b'\0'),
+    )
+    .value("FILE"),
+    Opt::flag(
+        "--line-buffered",
+        "flush the output after every line",
+        |parsed| &mut parsed.line_buffered,
+    ),
+    Opt::new(
+        "--read-strategy",
+        "read files with STRATEGY",
+        |parsed, arg| {
+            parsed.read_strategy = Some(arg.choice(|value| match value {
+                "buffered" => Some(ReadStrategy::Buffered),
+                "mmap" => Some(ReadStrategy::Mmap),
+                "auto" => Some(ReadStrategy::Auto),
+                _ => None,
+            })?);
+            Ok(())
+        },
+    )
+    .value("STRATEGY")
+    .choices(&["buffered", "mmap", "auto"]),
+    Opt::new(
+        "--threads",
+        "search up to NUM files at once",
+        |parsed, arg| {
+            parsed.threads = arg.number()?;
+            Ok(())
+        },
+    )
+    .short("-j")
+    .value("NUM"),
+    Opt::new(
+        "--sort",
+        "print the results of each file in ORDER",
+        |parsed, arg| {
+            parsed.sort = arg.choice(|value| match value {
+                "path" => Some(Sort::Path),
+                "none" => Some(Sort::None),
+                _ => None,
+            })?;
+            Ok(())
+        },
+    )
+    .value("ORDER")
+    .choices(&["path", "none"]),
+    Opt::new(
+        "--sort-buffer",
+        "with --sort=path, hold back up to SIZE of later files",
+        |parsed, arg| {
+            let size = parse_size(&arg.value()?)?;
+            parsed.sort_buffer = Some(usize::try_from(size).unwrap_or(usize::MAX));
+            Ok(())
+        },
+    )
+    .value("SIZE"),
+    Opt::flag(
+        "--progress",
+        "keep a status line on standard error",
+        |parsed| &mut parsed.progress,
+    ),
+    Opt::flag("--stats", "print statistics once done", |parsed| {
+        &mut parsed.stats
+    }),
+    Opt::flag(
+        "--timings",
+        "print how long each input took once done",
+        |parsed| &mut parsed.timings,
+    ),
+    Opt::new(
+        "--timings-limit",
+        "with --timings, list only the NUM slowest inputs",
+        |parsed, arg| {
+            parsed.timings_limit = Some(arg.number()?);
+            Ok(())
+        },
+    )
+    .value("NUM"),
+    Opt::flag(
+        "--no-messages",
+        "do not warn about files that cannot be read",
+        |parsed| &mut parsed.no_messages,
+    )
+    .short("-s"),
+    Opt::flag(
+        "--quiet",
+        "print nothing, only exiting with the status",
+        |parsed| &mut parsed.quiet,
+    )
+    .short("-q"),
+    Opt::new(
+        "--skip-lines",
+        "do not search the first NUM lines of each input",
+        |parsed, arg| {
+            parsed.skip_lines = Some(arg.number()?);
+            Ok(())
+        },
+    )
+    .value("NUM"),
+    Opt::new(
+        "--head",
+        "stop reading each input after NUM lines",
+        |parsed, arg| {
+            parsed.head = Some(arg.number()?);
+            Ok(())
+        },
+    )
+    .value("NUM"),
+    Opt::new(
+        "--start-byte",
+        "start searching each input at byte NUM",
+        |parsed, arg| {
+            parsed.start_byte = Some(arg.number()? as u64);
+            Ok(())
+        },
+    )
+    .value("NUM"),
+    Opt::flag(
+        "--count-from-start",
+        "with --start-byte, number lines from the start",
+        |parsed| &mut parsed.count_from_start,
+    ),
+    Opt::new(
+        "--max-total",
+        "stop once NUM lines are selected in all",
+        |parsed, arg| {
+            parsed.max_total = Some(arg.number()? as u64);
+            Ok(())
+        },
+    )
+    .value("NUM"),
+    Opt::new(
+        "--first",
+        "print the first selected line of all, then stop",
+        |parsed, arg| {
+            parsed.first = Some(match arg.optional_value() {
+                Some(value) => arg.parse(&value, |value| match value {
+                    "ordered" => Some(First::Ordered),
+                    "any" => Some(First::Any),
+                    _ => None,
+                })?,
+                None => First::Ordered,
+            });
+            Ok(())
+        },
+    )
+    .optional_value("ORDER")
+    .choices(&["ordered", "any"]),
+    Opt::new(
+        "--require-count",
+        "exit with status 3 unless NUM lines are selected",
+        |parsed, arg| {
+            parsed.require_count = Some(arg.number()? as u64);
+            Ok(())
+        },
+    )
+    .value("NUM"),
+    Opt::flag(
+        "--require-match",
+        "the same as --require-count=1",
+        |parsed| &mut parsed.require_match,
+    ),
+    Opt::flag("--debug", "log why each file is skipped", |parsed| {
+        &mut parsed.debug
+    }),
+    Opt::flag("--json", "print a stream of JSON events", |parsed| {
+        &mut parsed.json
+    }),
+    Opt::new(
+        "--output-format",
+        "print the matches as a table or SARIF",
+        |parsed, arg| {
+            parsed.output_format = Some(arg.choice(|value| match value {
+                "sarif" => Some(OutputFormat::Sarif),
+                _ => csv::Format::parse(value).map(OutputFormat::Table),
+            })?);
+            Ok(())
+        },
+    )
+    .value("FORMAT")
+    .choices(&["csv", "tsv", "sarif"]),
+    Opt::flag(
+        "--no-header",
+        "with --output-format, leave out the header",
+        |parsed| &mut parsed.no_header,
+    ),
+    Opt::new(
+        "--format",
+        "print a line made from TEMPLATE for every match",
+        |parsed, arg| {
+            parsed.format = Some(Template::parse(&arg.value()?)?);
+            Ok(())
+        },
+    )
+    .value("TEMPLATE"),
+    Opt::flag(
+        "--watch",
+        "keep printing the lines appended to the file",
+        |parsed| &mut parsed.watch,
+    ),
+    Opt::new(
+        "--watch-interval",
+        "with --watch, check the file every MS milliseconds",
+        |parsed, arg| {
+            parsed.watch_interval = Some(arg.number()? as u64);
+            Ok(())
+        },
+    )
+    .value("MS"),
+    Opt::new(
+        "--pager",
+        "page the output through CMD on a terminal",
+        |parsed, arg| {
+            parsed.pager = Some(arg.optional_value());
+            Ok(())
+        },
+    )
+    .optional_value("CMD"),
+    Opt::flag("--no-pager", "print directly, without a pager", |parsed| {
+        &mut parsed.no_pager
+    }),
+    Opt::new(
+        "--color",
+        "highlight matches, file names, and line numbers",
+        |parsed, arg| parsed.color(arg),
+    )
+    .optional_value("WHEN")
+    .choices(&["auto", "always", "never"]),
+    Opt::new("--colour", "the same as --color", |parsed, arg| {
+        parsed.color(arg)
+    })
+    .optional_value("WHEN")
+    .choices(&["auto", "always", "never"]),
+    Opt::new(
+        "--generate-completions",
+        "print a completion script for SHELL and exit",
+        |parsed, arg| {
+            parsed.completions = Some(arg.choice(Shell::parse)?);
+            Ok(())
+        },
+    )
+    .value("SHELL")
+    .choices(completions::SHELLS),
+];
+
+/// Returns the option named `name`, long or short, if there is one.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::options::{self, Value};
+///
+/// let option = options::find("-A").unwrap();
+/// assert_eq!(option.long, "--after-context");
+/// assert_eq!(option.value, Value::Required("NUM"));
+/// assert_eq!(options::find("--no-such-option"), None);
+/// ```
+pub fn find(name: &str) -> Option<&'static Opt> {
+    OPTIONS
+        .iter()
+        .find(|option| option.names().any(|n| n == name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_are_unique_and_well_formed() {
+        let mut names: Vec<&str> = OPTIONS.iter().flat_map(Opt::names).collect();
+        for name in &names {
+            let letters = name.trim_start_matches('-');
+            match name.len() - letters.len() {
+                1 => assert_eq!(letters.chars().count(), 1, "{name}"),
+                2 => assert!(letters.len() > 1, "{name}"),
+                _ => panic!("{name} starts with neither - nor --"),
+            }
+        }
+        let count = names.len();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), count);
+    }
+
+    #[test]
+    fn only_options_taking_a_value_have_choices() {
+        for option in OPTIONS {
+            if option.value == Value::None {
+                assert!(option.choices.is_empty(), "{}", option.long);
+            }
+        }
+    }
+
+    #[test]
+    fn flags_take_no_value() {
+        for option in OPTIONS {
+            if let Parse::Flag(_) = option.parse {
+                assert_eq!(option.value, Value::None, "{}", option.long);
+            }
+        }
+    }
+}
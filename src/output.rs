@@ -0,0 +1,143 @@
+//! The single handle all of the tool's printed output flows through.
+
+use std::io::{self, Write};
+
+/// Wraps the destination of printed lines and decides when they are flushed.
+///
+/// When `line_buffered` is `true`, every line is flushed as soon as it is
+/// written, so that results show up immediately when piped into another
+/// program. Otherwise flushing is left to the wrapped writer (typically a
+/// `BufWriter`) and to the final call to [`Output::flush`].
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::output::Output;
+///
+/// let mut output = Output::new(Vec::new(), true);
+/// output.write_line("Rust is safe.").unwrap();
+///
+/// assert_eq!(output.into_inner(), b"Rust is safe.\n");
+/// ```
+pub struct Output<W: Write> {
+    inner: W,
+    line_buffered: bool,
+}
+
+impl<W: Write> Output<W> {
+    /// Creates a new `Output` writing into `inner`.
+    pub fn new(inner: W, line_buffered: bool) -> Output<W> {
+        Output {
+            inner,
+            line_buffered,
+        }
+    }
+
+    /// Writes `line` followed by a newline, flushing if line-buffered.
+    ///
+    /// # Errors
+    /// Returns any error from the underlying writer.
+    pub fn write_line(&mut self, line: &str) -> io::Result<()> {
+        self.write_terminated(line, '\n')
+    }
+
+    /// Writes `record` followed by `terminator`, flushing if line-buffered.
+    ///
+    /// This is how NUL-terminated file names are written for `-Z`.
+    ///
+    /// # Errors
+    /// Returns any error from the underlying writer.
+    pub fn write_terminated(&mut self, record: &str, terminator: char) -> io::Result<()> {
+        write!(self.inner, "{record}{terminator}")?;
+        if self.line_buffered {
+            self.inner.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Writes output that was formatted elsewhere, such as the buffered
+    /// results of a file searched on another thread, flushing if
+    /// line-buffered.
+    ///
+    /// # Errors
+    /// Returns any error from the underlying writer.
+    pub fn write_raw(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.inner.write_all(bytes)?;
+        if self.line_buffered {
+            self.inner.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Flushes everything written so far.
+    ///
+    /// # Errors
+    /// Returns any error from the underlying writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    /// Consumes the `Output`, returning the wrapped writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Records every write and flush it receives.
+    #[derive(Default)]
+    struct RecordingWriter {
+        written: Vec<u8>,
+        flushes: usize,
+    }
+
+    impl Write for RecordingWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.written.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.flushes += 1;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn line_buffered_flushes_every_line() {
+        let mut output = Output::new(RecordingWriter::default(), true);
+        output.write_line("one").unwrap();
+        output.write_line("two").unwrap();
+        output.write_line("three").unwrap();
+
+        let writer = output.into_inner();
+        assert_eq!(writer.written, b"one\ntwo\nthree\n");
+        assert_eq!(writer.flushes, 3);
+    }
+
+    #[test]
+    fn records_can_be_nul_terminated() {
+        let mut output = Output::new(RecordingWriter::default(), true);
+        output.write_terminated("a.txt", '\0').unwrap();
+        output.write_terminated("b.txt", '\0').unwrap();
+
+        let writer = output.into_inner();
+        assert_eq!(writer.written, b"a.txt\0b.txt\0");
+        assert_eq!(writer.flushes, 2);
+    }
+
+    #[test]
+    fn block_buffered_flushes_only_on_request() {
+        let mut output = Output::new(RecordingWriter::default(), false);
+        output.write_line("one").unwrap();
+        output.write_line("two").unwrap();
+        assert_eq!(output.inner.flushes, 0);
+
+        output.flush().unwrap();
+        let writer = output.into_inner();
+        assert_eq!(writer.written, b"one\ntwo\n");
+        assert_eq!(writer.flushes, 1);
+    }
+}
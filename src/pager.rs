@@ -0,0 +1,210 @@
+//! Paging the output through a command such as `less`, for `--pager`, so
+//! that a long search can be read a screen at a time.
+//!
+//! The command is split on whitespace, without any shell quoting, and run
+//! with the output written to its standard input, while its own output
+//! goes to the terminal. Once the search is done, the input is closed and
+//! the pager waited for, so that the prompt only comes back once it has
+//! been quit. A pager quit early makes the next write fail with a broken
+//! pipe, which ends the search quietly.
+
+use std::{
+    io::{self, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+};
+
+/// The command pages are shown with unless `--pager`, `MINIGREP_PAGER`, or
+/// `PAGER` names another.
+pub const DEFAULT_PAGER: &str = "less";
+
+/// The options `less` is run with unless `LESS` is set: exit at once if
+/// the output fits on one screen, show colors as they are, and leave the
+/// screen as it was.
+const LESS_OPTIONS: &str = "FRX";
+
+/// A pager running on the output written to it.
+///
+/// # Examples
+/// ```no_run
+/// use minigrep_cli_tool::pager::Pager;
+/// use std::{io::Write, process::Stdio};
+///
+/// let mut pager = Pager::spawn("less -R", None, Stdio::inherit())?;
+/// writeln!(pager, "a line to read")?;
+/// pager.finish()?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct Pager {
+    child: Child,
+    input: Option<ChildStdin>,
+}
+
+impl Pager {
+    /// Starts `command`, showing what is written to it on `output`, which
+    /// is the terminal, [`Stdio::inherit`], outside of tests.
+    ///
+    /// `LESS` is set to `less`, the value it has in the environment the
+    /// [`Config`](crate::Config) was built in, or to `FRX` if it has none.
+    ///
+    /// # Errors
+    /// Returns an error naming `command` if it is empty or cannot be
+    /// started.
+    pub fn spawn(command: &str, less: Option<&str>, output: Stdio) -> io::Result<Pager> {
+        let cannot_run = |error: io::Error| {
+            io::Error::new(error.kind(), format!("cannot run pager {command}: {error}"))
+        };
+        let mut words = command.split_whitespace();
+        let program = words
+            .next()
+            .ok_or_else(|| cannot_run(io::Error::from(io::ErrorKind::InvalidInput)))?;
+        let mut pager = Command::new(program);
+        pager
+            .args(words)
+            .stdin(Stdio::piped())
+            .stdout(output)
+            .stderr(Stdio::inherit());
+        pager.env("LESS", less.unwrap_or(LESS_OPTIONS));
+        let mut child = pager.spawn().map_err(cannot_run)?;
+        let input = child.stdin.take();
+        Ok(Pager { child, input })
+    }
+
+    /// Closes the pager's input, so that it knows the output has ended, and
+    /// waits for it to be quit.
+    ///
+    /// # Errors
+    /// Returns an error if the pager cannot be waited for. How it exits is
+    /// not an error, since quitting early is how a pager is used.
+    pub fn finish(mut self) -> io::Result<()> {
+        drop(self.input.take());
+        self.child.wait().map(drop)
+    }
+
+    fn input(&mut self) -> &mut ChildStdin {
+        self.input
+            .as_mut()
+            .expect("the input is only closed by finish")
+    }
+}
+
+impl Write for Pager {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.input().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.input().flush()
+    }
+}
+
+/// Returns the pager command to run on output to a terminal, if any:
+/// `flag`, the value of `--pager`, which is `Some(None)` if it was given
+/// without one, `MINIGREP_PAGER`, or, with a bare `--pager`, `PAGER` or
+/// else [`DEFAULT_PAGER`]; `PAGER` alone, which other programs use too,
+/// does not turn paging on. Variables are looked up with `env`, and empty
+/// ones are taken to be unset.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::pager::pager_command;
+///
+/// let env = |name: &str| (name == "PAGER").then(|| String::from("more"));
+/// assert_eq!(pager_command(None, env), None);
+/// assert_eq!(pager_command(Some(None), env), Some(String::from("more")));
+/// assert_eq!(
+///     pager_command(Some(Some("less -S")), env),
+///     Some(String::from("less -S"))
+/// );
+/// ```
+pub fn pager_command(
+    flag: Option<Option<&str>>,
+    env: impl Fn(&str) -> Option<String>,
+) -> Option<String> {
+    let var = |name: &str| env(name).filter(|value| !value.trim().is_empty());
+    match flag {
+        Some(Some(command)) => Some(command.to_string()),
+        Some(None) => Some(
+            var("MINIGREP_PAGER")
+                .or_else(|| var("PAGER"))
+                .unwrap_or_else(|| String::from(DEFAULT_PAGER)),
+        ),
+        None => var("MINIGREP_PAGER"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env<'v>(vars: &'v [(&str, &str)]) -> impl Fn(&str) -> Option<String> + 'v {
+        |name| {
+            vars.iter()
+                .find(|(var, _)| *var == name)
+                .map(|(_, value)| value.to_string())
+        }
+    }
+
+    #[test]
+    fn the_flag_or_minigrep_pager_turns_paging_on() {
+        let both = [("MINIGREP_PAGER", "most"), ("PAGER", "more")];
+        assert_eq!(pager_command(None, env(&both)), Some(String::from("most")));
+        assert_eq!(
+            pager_command(Some(None), env(&both)),
+            Some(String::from("most"))
+        );
+        assert_eq!(
+            pager_command(Some(Some("cat")), env(&both)),
+            Some(String::from("cat"))
+        );
+        assert_eq!(pager_command(None, env(&[("PAGER", "more")])), None);
+        assert_eq!(
+            pager_command(Some(None), env(&[("MINIGREP_PAGER", " ")])),
+            Some(String::from(DEFAULT_PAGER))
+        );
+        assert_eq!(pager_command(None, env(&[("MINIGREP_PAGER", "")])), None);
+    }
+
+    #[test]
+    fn pagers_that_cannot_start_are_errors_naming_them() {
+        let error = Pager::spawn("minigrep-no-such-pager -R", None, Stdio::null()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+        assert!(
+            error
+                .to_string()
+                .starts_with("cannot run pager minigrep-no-such-pager -R: "),
+            "{error}"
+        );
+        let error = Pager::spawn(" ", None, Stdio::null()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn output_reaches_the_pager_and_is_waited_for() {
+        let path = std::env::temp_dir().join("minigrep_pager_cat.txt");
+        let file = std::fs::File::create(&path).unwrap();
+        let mut pager = Pager::spawn("cat -u", None, Stdio::from(file)).unwrap();
+        pager.write_all(b"first\n").unwrap();
+        writeln!(pager, "second").unwrap();
+        pager.flush().unwrap();
+        pager.finish().unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "first\nsecond\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_pager_quit_early_breaks_the_pipe() {
+        let mut pager = Pager::spawn("head -n 1", None, Stdio::null()).unwrap();
+        let line = [b'x'; 1023]
+            .iter()
+            .chain(b"\n")
+            .copied()
+            .collect::<Vec<u8>>();
+        let error = (0..10_000)
+            .find_map(|_| pager.write_all(&line).err())
+            .expect("writing fails once head has exited");
+        assert_eq!(error.kind(), io::ErrorKind::BrokenPipe);
+        pager.finish().unwrap();
+    }
+}
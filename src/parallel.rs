@@ -0,0 +1,440 @@
+//! Searching several inputs at once on a pool of threads.
+
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::{
+        Condvar, Mutex, PoisonError,
+        atomic::{AtomicBool, Ordering},
+        mpsc,
+    },
+    thread,
+};
+
+/// Runs `work` on each of `items` using up to `threads` threads, handing
+/// every result to `emit` on the calling thread.
+///
+/// If `ordered` is `true`, results are emitted in the order of `items`, each
+/// as soon as all the ones before it are done; otherwise they are emitted in
+/// order of completion. With a single thread, or a single item, no threads
+/// are spawned and the results are always in order.
+///
+/// # Errors
+/// Stops at the first error returned by `emit`, abandoning the items not yet
+/// started, and returns that error.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::parallel::map_emit;
+///
+/// let mut squares = Vec::new();
+/// map_emit(vec![1, 2, 3, 4], 3, true, |n| n * n, |square| {
+///     squares.push(square);
+///     Ok::<(), ()>(())
+/// })
+/// .unwrap();
+/// assert_eq!(squares, vec![1, 4, 9, 16]);
+/// ```
+pub fn map_emit<T, R, E>(
+    items: Vec<T>,
+    threads: usize,
+    ordered: bool,
+    work: impl Fn(T) -> R + Sync,
+    mut emit: impl FnMut(R) -> Result<(), E>,
+) -> Result<(), E>
+where
+    T: Send,
+    R: Send,
+{
+    let threads = threads.min(items.len());
+    if threads <= 1 {
+        return items.into_iter().try_for_each(|item| emit(work(item)));
+    }
+
+    let queue = Mutex::new(items.into_iter().enumerate());
+    let stopped = AtomicBool::new(false);
+    let (sender, receiver) = mpsc::channel();
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            let sender = sender.clone();
+            let (queue, stopped, work) = (&queue, &stopped, &work);
+            scope.spawn(move || {
+                while !stopped.load(Ordering::Relaxed) {
+                    let Some((index, item)) = queue.lock().unwrap().next() else {
+                        break;
+                    };
+                    if sender.send((index, work(item))).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(sender);
+
+        let mut pending = BTreeMap::new();
+        let mut next = 0;
+        let result = receiver.iter().try_for_each(|(index, result)| {
+            if !ordered {
+                return emit(result);
+            }
+            pending.insert(index, result);
+            while let Some(result) = pending.remove(&next) {
+                emit(result)?;
+                next += 1;
+            }
+            Ok(())
+        });
+        if result.is_err() {
+            stopped.store(true, Ordering::Relaxed);
+        }
+        result
+    })
+}
+
+/// What [`map_stream`] hands to `emit` for each item: the batches its work
+/// sent, then its result.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Part<B, R> {
+    Batch(B),
+    Done(R),
+}
+
+/// Where the work of [`map_stream`] on an item sends its batches.
+pub struct Batches<'s, B> {
+    send: &'s mut dyn FnMut(B, usize) -> bool,
+}
+
+impl<B> Batches<'_, B> {
+    /// Sends `batch`, taking `size` of the budget until it is emitted, and
+    /// waiting for the budget to be enough if the batch is held back behind
+    /// those of earlier items.
+    ///
+    /// # Returns
+    /// `false` if the run has stopped, so that nothing more is emitted and
+    /// the work can be abandoned.
+    pub fn send(&mut self, batch: B, size: usize) -> bool {
+        (self.send)(batch, size)
+    }
+}
+
+/// How much of the budget of [`map_stream`] is taken by the batches held
+/// back, and which item is the one being emitted.
+struct Budget {
+    next: usize,
+    held: usize,
+}
+
+/// Runs `work` on each of `items` using up to `threads` threads, like
+/// [`map_emit`] in order, but with the work sending its results in batches
+/// as it goes, the ones of each item emitted as soon as those of all the
+/// items before it have been, ahead of its result.
+///
+/// The batches of the item being emitted are emitted as they arrive. Those
+/// of later items are held back, to no more than `budget` in all, as the
+/// sizes sent have it: an item whose batch does not fit waits until the
+/// budget is freed by earlier batches being emitted, or until it is the
+/// item being emitted. So output starts before a large first item is done,
+/// without the items after it, however large, being kept whole in memory.
+///
+/// # Errors
+/// Stops at the first error returned by `emit`, abandoning the items not yet
+/// started and failing every [`Batches::send`] after, and returns that
+/// error.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::parallel::{Part, map_stream};
+///
+/// let mut parts = Vec::new();
+/// map_stream(
+///     vec![2, 3],
+///     2,
+///     1,
+///     |n, batches| {
+///         for i in 0..n {
+///             batches.send(n * 10 + i, 1);
+///         }
+///         n
+///     },
+///     |part| {
+///         parts.push(part);
+///         Ok::<(), ()>(())
+///     },
+/// )
+/// .unwrap();
+/// let expected = [20, 21].map(Part::Batch).into_iter().chain([Part::Done(2)]);
+/// let expected = expected.chain([30, 31, 32].map(Part::Batch)).chain([Part::Done(3)]);
+/// assert_eq!(parts, expected.collect::<Vec<_>>());
+/// ```
+pub fn map_stream<T, B, R, E>(
+    items: Vec<T>,
+    threads: usize,
+    budget: usize,
+    work: impl Fn(T, &mut Batches<'_, B>) -> R + Sync,
+    mut emit: impl FnMut(Part<B, R>) -> Result<(), E>,
+) -> Result<(), E>
+where
+    T: Send,
+    B: Send,
+    R: Send,
+{
+    let threads = threads.min(items.len());
+    if threads <= 1 {
+        for item in items {
+            let mut failed = None;
+            let mut send = |batch, _| {
+                if failed.is_none() {
+                    failed = emit(Part::Batch(batch)).err();
+                }
+                failed.is_none()
+            };
+            let result = work(item, &mut Batches { send: &mut send });
+            if let Some(error) = failed {
+                return Err(error);
+            }
+            emit(Part::Done(result))?;
+        }
+        return Ok(());
+    }
+
+    let queue = Mutex::new(items.into_iter().enumerate());
+    let stopped = AtomicBool::new(false);
+    let state = Mutex::new(Budget { next: 0, held: 0 });
+    let freed = Condvar::new();
+    let lock = || state.lock().unwrap_or_else(PoisonError::into_inner);
+    // Each part with its item and how much of the budget it takes.
+    let (sender, receiver) = mpsc::channel::<(usize, Part<B, R>, usize)>();
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            let sender = sender.clone();
+            let (queue, stopped, work, lock, freed) = (&queue, &stopped, &work, &lock, &freed);
+            scope.spawn(move || {
+                while !stopped.load(Ordering::Relaxed) {
+                    let Some((index, item)) = queue.lock().unwrap().next() else {
+                        break;
+                    };
+                    let mut send = |batch, size| {
+                        let mut state = lock();
+                        let taken = loop {
+                            if stopped.load(Ordering::Relaxed) {
+                                return false;
+                            }
+                            if state.next == index {
+                                break 0;
+                            }
+                            if state.held + size <= budget {
+                                state.held += size;
+                                break size;
+                            }
+                            state = freed.wait(state).unwrap_or_else(PoisonError::into_inner);
+                        };
+                        drop(state);
+                        sender.send((index, Part::Batch(batch), taken)).is_ok()
+                    };
+                    let result = work(item, &mut Batches { send: &mut send });
+                    if sender.send((index, Part::Done(result), 0)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(sender);
+
+        // The parts of the items after the one being emitted, in the order
+        // each item sent them.
+        let mut pending = BTreeMap::<usize, VecDeque<_>>::new();
+        let mut next = 0;
+        let mut emit_part = |part: Part<B, R>, taken: usize| -> Result<bool, E> {
+            if taken > 0 {
+                lock().held -= taken;
+                freed.notify_all();
+            }
+            let done = matches!(part, Part::Done(_));
+            emit(part)?;
+            Ok(done)
+        };
+        let result = receiver.iter().try_for_each(|(index, part, taken)| {
+            if index != next {
+                pending.entry(index).or_default().push_back((part, taken));
+                return Ok(());
+            }
+            let mut done = emit_part(part, taken)?;
+            while done {
+                next += 1;
+                lock().next = next;
+                freed.notify_all();
+                done = false;
+                let Some(parts) = pending.remove(&next) else {
+                    break;
+                };
+                for (part, taken) in parts {
+                    done = emit_part(part, taken)?;
+                }
+            }
+            Ok(())
+        });
+        if result.is_err() {
+            stopped.store(true, Ordering::Relaxed);
+            // Under the lock, so that no item waiting misses the wakeup.
+            let _state = lock();
+            freed.notify_all();
+        }
+        result
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{sync::atomic::AtomicUsize, time::Duration};
+
+    #[test]
+    fn ordered_results_follow_the_items() {
+        let mut emitted = Vec::new();
+        let items: Vec<u64> = (0..20).collect();
+        let work = |n: u64| {
+            thread::sleep(Duration::from_millis((20 - n) % 5));
+            n
+        };
+        map_emit(items, 4, true, work, |n| {
+            emitted.push(n);
+            Ok::<(), ()>(())
+        })
+        .unwrap();
+        assert_eq!(emitted, (0..20).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn unordered_results_are_all_emitted() {
+        let mut emitted = Vec::new();
+        map_emit(
+            (0..50).collect(),
+            8,
+            false,
+            |n: u32| n * 2,
+            |n| {
+                emitted.push(n);
+                Ok::<(), ()>(())
+            },
+        )
+        .unwrap();
+        emitted.sort();
+        assert_eq!(emitted, (0..50).map(|n| n * 2).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn emit_errors_stop_the_run() {
+        let mut emitted = 0;
+        let result = map_emit(
+            (0..1000).collect(),
+            4,
+            true,
+            |n: u32| n,
+            |n| {
+                emitted += 1;
+                if n == 3 { Err(n) } else { Ok(()) }
+            },
+        );
+        assert_eq!(result, Err(3));
+        assert_eq!(emitted, 4);
+    }
+
+    #[test]
+    fn streamed_batches_follow_the_items_whatever_their_sizes() {
+        // The first items are the largest and the slowest, so that the
+        // later ones finish long before them.
+        let sizes: Vec<u64> = (0..24).map(|n| [400, 1, 0, 90, 3, 40][n % 6]).collect();
+        let mut parts = Vec::new();
+        map_stream(
+            sizes.iter().copied().enumerate().collect(),
+            6,
+            64,
+            |(item, size), batches| {
+                for batch in 0..size {
+                    if item < 6 {
+                        thread::sleep(Duration::from_micros(50));
+                    }
+                    batches.send((item, batch), 1);
+                }
+                item
+            },
+            |part| {
+                parts.push(part);
+                Ok::<(), ()>(())
+            },
+        )
+        .unwrap();
+        let expected = sizes.iter().enumerate().flat_map(|(item, &size)| {
+            let batches = (0..size).map(move |batch| Part::Batch((item, batch)));
+            batches.chain([Part::Done(item)])
+        });
+        assert_eq!(parts, expected.collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn batches_held_back_behind_an_enormous_item_stay_within_the_budget() {
+        const BATCH: usize = 100;
+        const BUDGET: usize = 1_000;
+        const THREADS: usize = 4;
+        // The batches of the items after the first sent, and emitted.
+        let sent = AtomicUsize::new(0);
+        let mut emitted = 0;
+        let mut most_held = 0;
+        let mut first_done = false;
+        map_stream(
+            (0..40).collect(),
+            THREADS,
+            BUDGET,
+            |item: usize, batches| {
+                let count = if item == 0 { 2_000 } else { 50 };
+                for _ in 0..count {
+                    if item == 0 {
+                        thread::sleep(Duration::from_micros(20));
+                    } else {
+                        sent.fetch_add(BATCH, Ordering::SeqCst);
+                    }
+                    if !batches.send(item, BATCH) {
+                        break;
+                    }
+                }
+            },
+            |part| {
+                match part {
+                    Part::Batch(0) => {
+                        let held = sent.load(Ordering::SeqCst) - emitted;
+                        most_held = most_held.max(held);
+                    }
+                    Part::Batch(_) => emitted += BATCH,
+                    Part::Done(()) => first_done = true,
+                }
+                assert!(first_done || emitted == 0);
+                Ok::<(), ()>(())
+            },
+        )
+        .unwrap();
+        // Each thread waiting has counted the batch that it waits to send.
+        assert!(most_held <= BUDGET + THREADS * BATCH, "{most_held}");
+        assert!(most_held >= BUDGET / 2, "{most_held}");
+        assert_eq!(emitted, 39 * 50 * BATCH);
+    }
+
+    #[test]
+    fn emit_errors_stop_a_stream_and_its_waiting_items() {
+        let result = map_stream(
+            (0..100).collect(),
+            4,
+            10,
+            |item: u32, batches| {
+                let mut sent = 0;
+                while sent < 1_000 && batches.send(item, 5) {
+                    sent += 1;
+                }
+                sent
+            },
+            |part| match part {
+                Part::Batch(0) => Err("stopped"),
+                _ => Ok(()),
+            },
+        );
+        assert_eq!(result, Err("stopped"));
+    }
+}
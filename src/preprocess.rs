@@ -0,0 +1,144 @@
+//! Running a preprocessor over files, for `--pre`, so that formats such as
+//! PDF can be searched as the text a command like `pdftotext` makes of
+//! them.
+//!
+//! The command is run with the file's path as its only argument, its
+//! standard output is read as the file's contents while it runs, and its
+//! standard error goes straight to ours. A command that exits with a
+//! failure makes reading fail once its output has been read.
+
+use std::{
+    io::{self, Read},
+    path::Path,
+    process::{Child, ChildStdout, Command, ExitStatus, Stdio},
+};
+
+/// The output of a preprocessor run on one file, read as it is written.
+///
+/// The process is waited for once its output ends, and killed if the
+/// output is dropped before then.
+///
+/// # Examples
+/// ```no_run
+/// use minigrep_cli_tool::preprocess::Preprocessed;
+/// use std::{io::Read, path::Path};
+///
+/// let mut text = String::new();
+/// Preprocessed::spawn("pdftotext", Path::new("manual.pdf"))?.read_to_string(&mut text)?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct Preprocessed {
+    command: String,
+    child: Child,
+    stdout: ChildStdout,
+    status: Option<ExitStatus>,
+}
+
+impl Preprocessed {
+    /// Starts `command` on the file at `path`, with no standard input.
+    ///
+    /// # Errors
+    /// Returns an error naming `command` if it cannot be started.
+    pub fn spawn(command: &str, path: &Path) -> io::Result<Preprocessed> {
+        let mut child = Command::new(command)
+            .arg(path)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(|error| {
+                io::Error::new(
+                    error.kind(),
+                    format!("cannot run preprocessor {command}: {error}"),
+                )
+            })?;
+        let stdout = child.stdout.take().expect("standard output is piped");
+        Ok(Preprocessed {
+            command: command.to_string(),
+            child,
+            stdout,
+            status: None,
+        })
+    }
+}
+
+impl Read for Preprocessed {
+    /// Reads the preprocessor's output and, at its end, waits for it.
+    ///
+    /// # Errors
+    /// Returns an error if reading fails or, once the output has ended,
+    /// if the preprocessor did not exit successfully.
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.stdout.read(buf)?;
+        if read > 0 || buf.is_empty() || self.status.is_some() {
+            return Ok(read);
+        }
+        let status = self.child.wait()?;
+        self.status = Some(status);
+        if !status.success() {
+            return Err(io::Error::other(format!(
+                "preprocessor {} failed with {status}",
+                self.command
+            )));
+        }
+        Ok(0)
+    }
+}
+
+impl Drop for Preprocessed {
+    fn drop(&mut self) {
+        if self.status.is_none() {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::{fs, path::PathBuf};
+
+    fn temp_file(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("minigrep-preprocess-{name}"));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn reads_the_output_of_the_command() {
+        let path = temp_file("cat", "first\nsecond\n");
+        let mut output = String::new();
+        Preprocessed::spawn("cat", &path)
+            .unwrap()
+            .read_to_string(&mut output)
+            .unwrap();
+        assert_eq!(output, "first\nsecond\n");
+    }
+
+    #[test]
+    fn a_failing_command_fails_the_read() {
+        let path = temp_file("false", "");
+        let error = Preprocessed::spawn("false", &path)
+            .unwrap()
+            .read_to_end(&mut Vec::new())
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "preprocessor false failed with exit status: 1"
+        );
+    }
+
+    #[test]
+    fn a_missing_command_cannot_be_spawned() {
+        let path = temp_file("missing", "");
+        let error = Preprocessed::spawn("minigrep-no-such-preprocessor", &path).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+        assert!(
+            error
+                .to_string()
+                .starts_with("cannot run preprocessor minigrep-no-such-preprocessor: ")
+        );
+    }
+}
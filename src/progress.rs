@@ -0,0 +1,163 @@
+//! The status line `--progress` keeps redrawing on standard error while a
+//! search runs.
+//!
+//! A [`Progress`] holds the counts shown and decides when the line is
+//! drawn: at most once every [`INTERVAL`], by going back to the start of the
+//! line and clearing what follows the new status. Before anything else is
+//! printed to the same terminal, the line is erased the same way, and it is
+//! drawn again with the next update.
+
+use std::time::{Duration, Instant};
+
+/// The shortest time between two redraws of the line.
+pub const INTERVAL: Duration = Duration::from_millis(100);
+
+/// Returns the cursor to the start of the line and clears it.
+const ERASE: &str = "\r\x1b[K";
+
+/// The state of a search, as the status line shows it.
+///
+/// - `files_found`: Inputs found to search so far.
+/// - `files_done`: Inputs searched so far.
+/// - `matched_lines`: Lines selected in those inputs.
+/// - `current`: The name of the input being searched, if any.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::progress::Progress;
+///
+/// let mut progress = Progress::default();
+/// progress.files_found = 340;
+/// progress.files_done = 12;
+/// progress.matched_lines = 5;
+/// progress.current = String::from("src/main.rs");
+/// assert_eq!(
+///     progress.status(80),
+///     "12/340 files, 5 matched lines: src/main.rs"
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Progress {
+    pub files_found: u64,
+    pub files_done: u64,
+    pub matched_lines: u64,
+    pub current: String,
+    drawn_at: Option<Instant>,
+    shown: bool,
+}
+
+impl Progress {
+    /// Formats the status, cut down to fewer than `width` characters so that
+    /// it never wraps: the start of the current input's name is left out
+    /// first, marked with `…`, then the end of the counts.
+    pub fn status(&self, width: usize) -> String {
+        let counts = format!(
+            "{}/{} files, {} matched lines",
+            self.files_done, self.files_found, self.matched_lines
+        );
+        let room = width.saturating_sub(1);
+        let counts_len = counts.chars().count();
+        if counts_len >= room {
+            return counts.chars().take(room).collect();
+        }
+        if self.current.is_empty() {
+            return counts;
+        }
+        let room = room - counts_len - ": ".len();
+        let name_len = self.current.chars().count();
+        if name_len <= room {
+            return format!("{counts}: {}", self.current);
+        }
+        if room < 2 {
+            return counts;
+        }
+        let tail: String = self.current.chars().skip(name_len - (room - 1)).collect();
+        format!("{counts}: …{tail}")
+    }
+
+    /// Returns what redraws the line with the current status, for a
+    /// terminal `width` characters wide, unless it was drawn less than
+    /// [`INTERVAL`] before `now`.
+    pub fn redraw(&mut self, now: Instant, width: usize) -> Option<String> {
+        if self
+            .drawn_at
+            .is_some_and(|drawn_at| now.duration_since(drawn_at) < INTERVAL)
+        {
+            return None;
+        }
+        self.drawn_at = Some(now);
+        self.shown = true;
+        Some(format!("\r{}\x1b[K", self.status(width)))
+    }
+
+    /// Returns what erases the line, if it is shown.
+    pub fn erase(&mut self) -> Option<&'static str> {
+        self.shown.then(|| {
+            self.shown = false;
+            ERASE
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progress(current: &str) -> Progress {
+        Progress {
+            files_found: 20,
+            files_done: 3,
+            matched_lines: 7,
+            current: current.to_string(),
+            ..Progress::default()
+        }
+    }
+
+    #[test]
+    fn status_fits_the_width() {
+        assert_eq!(progress("").status(80), "3/20 files, 7 matched lines");
+        assert_eq!(
+            progress("src/walk.rs").status(41),
+            "3/20 files, 7 matched lines: src/walk.rs"
+        );
+        assert_eq!(
+            progress("src/walk.rs").status(36),
+            "3/20 files, 7 matched lines: …lk.rs"
+        );
+        assert_eq!(
+            progress("src/é/日本.rs").status(36),
+            "3/20 files, 7 matched lines: …日本.rs"
+        );
+        assert_eq!(
+            progress("src/walk.rs").status(30),
+            "3/20 files, 7 matched lines"
+        );
+        assert_eq!(progress("src/walk.rs").status(11), "3/20 files");
+        assert_eq!(progress("src/walk.rs").status(0), "");
+    }
+
+    #[test]
+    fn redraws_are_throttled() {
+        let mut progress = progress("a.txt");
+        let start = Instant::now();
+        assert_eq!(
+            progress.redraw(start, 80).as_deref(),
+            Some("\r3/20 files, 7 matched lines: a.txt\x1b[K")
+        );
+        assert_eq!(progress.redraw(start + INTERVAL / 2, 80), None);
+        progress.files_done += 1;
+        assert_eq!(
+            progress.redraw(start + INTERVAL, 80).as_deref(),
+            Some("\r4/20 files, 7 matched lines: a.txt\x1b[K")
+        );
+    }
+
+    #[test]
+    fn only_a_shown_line_is_erased() {
+        let mut progress = Progress::default();
+        assert_eq!(progress.erase(), None);
+        progress.redraw(Instant::now(), 80);
+        assert_eq!(progress.erase(), Some("\r\x1b[K"));
+        assert_eq!(progress.erase(), None);
+    }
+}
@@ -0,0 +1,100 @@
+//! Quoting the file names printed with `--quote`, so that they can be
+//! pasted into a shell command or passed through `xargs` whatever they
+//! contain.
+
+use std::borrow::Cow;
+
+/// How printed file names are quoted, as chosen with `--quote`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Quoting {
+    /// File names are printed as they are.
+    #[default]
+    None,
+    /// File names are quoted for POSIX shells by [`shell`].
+    Shell,
+}
+
+impl Quoting {
+    /// Parses a `--quote` value: `none` or `shell`.
+    ///
+    /// # Examples
+    /// ```
+    /// use minigrep_cli_tool::quote::Quoting;
+    ///
+    /// assert_eq!(Quoting::parse("shell"), Some(Quoting::Shell));
+    /// assert_eq!(Quoting::parse("c"), None);
+    /// ```
+    pub fn parse(value: &str) -> Option<Quoting> {
+        match value {
+            "none" => Some(Quoting::None),
+            "shell" => Some(Quoting::Shell),
+            _ => None,
+        }
+    }
+
+    /// Returns `text` quoted this way.
+    pub fn apply(self, text: &str) -> Cow<'_, str> {
+        match self {
+            Quoting::None => Cow::Borrowed(text),
+            Quoting::Shell => shell(text),
+        }
+    }
+}
+
+/// Returns `text` as a single word for a POSIX shell: unchanged if it is
+/// made only of characters no shell treats specially, otherwise wrapped in
+/// single quotes, with each single quote in it written as `'\''`.
+///
+/// Everything else, including newlines and non-ASCII characters, stands
+/// for itself between single quotes.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::quote::shell;
+///
+/// assert_eq!(shell("src/main.rs"), "src/main.rs");
+/// assert_eq!(shell("my notes.txt"), "'my notes.txt'");
+/// assert_eq!(shell("it's"), r"'it'\''s'");
+/// ```
+pub fn shell(text: &str) -> Cow<'_, str> {
+    let is_plain = |byte: u8| byte.is_ascii_alphanumeric() || b"%+,-./:=@_".contains(&byte);
+    if !text.is_empty() && text.bytes().all(is_plain) {
+        return Cow::Borrowed(text);
+    }
+    Cow::Owned(format!("'{}'", text.replace('\'', r"'\''")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_words_are_left_alone() {
+        assert_eq!(shell("a-b_c.d/e,f:g=h@i%j+k"), "a-b_c.d/e,f:g=h@i%j+k");
+        assert!(matches!(shell("poem.txt"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn shell_syntax_is_quoted() {
+        assert_eq!(shell(""), "''");
+        assert_eq!(shell("a b"), "'a b'");
+        assert_eq!(shell("$HOME/*.rs"), "'$HOME/*.rs'");
+        assert_eq!(shell("~user"), "'~user'");
+        assert_eq!(shell("a\\b"), "'a\\b'");
+        assert_eq!(shell("-n;rm"), "'-n;rm'");
+    }
+
+    #[test]
+    fn embedded_quotes_close_and_reopen_the_quoting() {
+        assert_eq!(shell("'"), r"''\'''");
+        assert_eq!(shell("don't 'x'"), r"'don'\''t '\''x'\'''");
+        assert_eq!(shell(r#"say "hi""#), r#"'say "hi"'"#);
+    }
+
+    #[test]
+    fn newlines_and_non_ascii_are_quoted_as_they_are() {
+        assert_eq!(shell("one\ntwo"), "'one\ntwo'");
+        assert_eq!(shell("café.txt"), "'café.txt'");
+        assert_eq!(shell("日本"), "'日本'");
+    }
+}
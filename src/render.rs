@@ -0,0 +1,626 @@
+//! Rendering matched lines for printing.
+//!
+//! The search describes each line it found as a [`LineMatch`]: its number,
+//! its text, and where in the text the query occurs. [`render_match`] turns
+//! that into the text printed, marking each occurrence the way a
+//! [`Renderer`] chooses, so that plain and colored output differ only in
+//! the renderer used. The fields printed before it are rendered by
+//! [`Prefix::render`], which follows GNU grep's order and separators, or
+//! the whole output line by a [`Template`] given with `--format`. With
+//! `--line-numbers-only --ranges`, [`LineRuns`] merges the numbers of
+//! consecutive lines into the runs printed instead.
+
+use std::ops::{Range, RangeInclusive};
+
+use crate::{
+    Matcher,
+    color::{self, Theme},
+};
+
+/// A line found by a search, with the occurrences of the query in it.
+///
+/// The `ranges` are byte ranges of `text`, sorted, non-overlapping, and on
+/// character boundaries, as returned by [`match_ranges`](crate::match_ranges).
+/// Alongside them, `patterns` holds the index of the pattern each is an
+/// occurrence of, as [`Matcher::pattern_ranges`] gives it; a range without
+/// one is taken to be of the first pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineMatch<'a> {
+    pub line_number: usize,
+    pub text: &'a str,
+    pub ranges: Vec<Range<usize>>,
+    pub patterns: Vec<usize>,
+}
+
+impl<'a> LineMatch<'a> {
+    /// Finds the occurrences of `query`, a query string or any other
+    /// [`Matcher`], in `text`, line `line_number` of its input, ignoring
+    /// case if `ignore_case`.
+    ///
+    /// # Examples
+    /// ```
+    /// use minigrep_cli_tool::render::LineMatch;
+    ///
+    /// let line = LineMatch::find("rust", 3, "Trust in Rust.", true);
+    /// assert_eq!(line.ranges, vec![1..5, 9..13]);
+    /// assert_eq!(line.patterns, [0, 0]);
+    /// ```
+    pub fn find(
+        query: &(impl Matcher + ?Sized),
+        line_number: usize,
+        text: &'a str,
+        ignore_case: bool,
+    ) -> LineMatch<'a> {
+        let (ranges, patterns) = query.pattern_ranges(text, ignore_case).into_iter().unzip();
+        LineMatch {
+            line_number,
+            text,
+            ranges,
+            patterns,
+        }
+    }
+
+    /// The index of the pattern the `index`th range is an occurrence of.
+    pub fn pattern(&self, index: usize) -> usize {
+        self.patterns.get(index).copied().unwrap_or(0)
+    }
+}
+
+/// How the occurrences of the query are marked in a rendered line.
+pub trait Renderer {
+    /// Appends `text`, an occurrence of the pattern numbered `pattern`, to
+    /// `rendered`.
+    fn matched(&self, text: &str, pattern: usize, rendered: &mut String);
+}
+
+/// Renders lines as they are, for output without color.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Plain;
+
+impl Renderer for Plain {
+    fn matched(&self, text: &str, _pattern: usize, rendered: &mut String) {
+        rendered.push_str(text);
+    }
+}
+
+/// Colors each occurrence with an SGR sequence such as `01;31`, as
+/// [`color::paint`] does, whichever pattern it is of.
+#[derive(Debug, Clone, Copy)]
+pub struct Colored<'a>(pub &'a str);
+
+impl Renderer for Colored<'_> {
+    fn matched(&self, text: &str, _pattern: usize, rendered: &mut String) {
+        rendered.push_str(&color::paint(text, self.0));
+    }
+}
+
+/// Colors the occurrences of each pattern as the theme does, cycling
+/// through its colors as [`Theme::pattern`] does.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::{
+///     color::Theme,
+///     expr::Expr,
+///     render::{LineMatch, Palette, render_match},
+/// };
+///
+/// let expr = Expr::parse(r#""err" | "warn""#).unwrap();
+/// let line = LineMatch::find(&expr, 1, "warn, err", false);
+/// assert_eq!(
+///     render_match(&line, &Palette(&Theme::default())),
+///     "\x1b[01;32mwarn\x1b[0m, \x1b[01;31merr\x1b[0m"
+/// );
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Palette<'a>(pub &'a Theme);
+
+impl Renderer for Palette<'_> {
+    fn matched(&self, text: &str, pattern: usize, rendered: &mut String) {
+        rendered.push_str(&color::paint(text, self.0.pattern(pattern)));
+    }
+}
+
+/// Renders the text of `line`, with each of its occurrences of the query
+/// marked by `renderer`.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::render::{render_match, Colored, LineMatch, Plain};
+///
+/// let line = LineMatch::find("rust", 1, "a rust b", false);
+/// assert_eq!(render_match(&line, &Plain), "a rust b");
+/// assert_eq!(render_match(&line, &Colored("01;31")), "a \x1b[01;31mrust\x1b[0m b");
+/// ```
+pub fn render_match(line: &LineMatch, renderer: &impl Renderer) -> String {
+    let mut rendered = String::with_capacity(line.text.len());
+    let mut end = 0;
+    for (index, range) in line.ranges.iter().enumerate() {
+        rendered.push_str(&line.text[end..range.start]);
+        renderer.matched(
+            &line.text[range.clone()],
+            line.pattern(index),
+            &mut rendered,
+        );
+        end = range.end;
+    }
+    rendered.push_str(&line.text[end..]);
+    rendered
+}
+
+/// Whether a printed line was selected, or is printed around the selected
+/// ones, as with `-A`, `-B`, `-C`, or `--passthru`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineKind {
+    Selected,
+    Context,
+}
+
+impl LineKind {
+    /// The separator following each field of the prefix: `:` on a selected
+    /// line and `-` on a context line.
+    pub fn separator(self) -> &'static str {
+        match self {
+            LineKind::Selected => ":",
+            LineKind::Context => "-",
+        }
+    }
+}
+
+/// The line printed by default between groups of context lines that are
+/// not adjacent, unless `--group-separator` chooses another.
+pub const GROUP_SEPARATOR: &str = "--";
+
+/// The fields printed before a line, each followed by the separator of its
+/// [`LineKind`], in the order `path:line:column:byte:`; fields that are
+/// `None` are left out.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::render::{LineKind, Prefix};
+///
+/// let prefix = Prefix {
+///     path: Some("src/main.rs"),
+///     line_number: Some(12),
+///     column: None,
+///     byte_offset: Some(340),
+///     kind: LineKind::Context,
+/// };
+/// assert_eq!(prefix.render(None, false), "src/main.rs-12-340-");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Prefix<'a> {
+    pub path: Option<&'a str>,
+    pub line_number: Option<usize>,
+    pub column: Option<usize>,
+    pub byte_offset: Option<u64>,
+    pub kind: LineKind,
+}
+
+impl Prefix<'_> {
+    /// Renders the prefix, coloring each field and separator with `theme`
+    /// if one is given. With `null`, as `-Z` asks, the path is followed by a
+    /// NUL byte instead of a separator.
+    pub fn render(&self, theme: Option<&Theme>, null: bool) -> String {
+        let paint = |text: &str, sgr: fn(&Theme) -> &str| match theme {
+            Some(theme) => color::paint(text, sgr(theme)),
+            None => text.to_string(),
+        };
+        let separator = paint(self.kind.separator(), |theme| &theme.separator);
+        let mut rendered = String::new();
+        if let Some(path) = self.path {
+            rendered.push_str(&paint(path, |theme| &theme.filename));
+            if null {
+                rendered.push('\0');
+            } else {
+                rendered.push_str(&separator);
+            }
+        }
+        let mut field = |text: String, sgr: fn(&Theme) -> &str| {
+            rendered.push_str(&paint(&text, sgr));
+            rendered.push_str(&separator);
+        };
+        if let Some(line_number) = self.line_number {
+            field(line_number.to_string(), |theme| &theme.line_number);
+        }
+        if let Some(column) = self.column {
+            field(column.to_string(), |theme| &theme.line_number);
+        }
+        if let Some(byte_offset) = self.byte_offset {
+            field(byte_offset.to_string(), |theme| &theme.byte_offset);
+        }
+        rendered
+    }
+}
+
+/// A piece of a [`Template`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Path,
+    Line,
+    Column,
+    Byte,
+    Match,
+    Text,
+}
+
+/// A template for the line printed for each match, as given with
+/// `--format`, such as `{path}:{line}: {text}`.
+///
+/// The placeholders are `{path}`, `{line}` (the 1-based line number),
+/// `{column}` (the 1-based column of the match, in characters), `{byte}`
+/// (the offset of the match in the input), `{match}` (the matched text),
+/// and `{text}` (the whole line). `{{` and `}}` stand for literal braces.
+/// For a line selected without a match, as with `-v`, `{column}` and
+/// `{match}` are empty and `{byte}` is the offset of the line.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::render::{Fields, Template};
+///
+/// let template = Template::parse("| {path} | {line} | `{match}` |")?;
+/// let fields = Fields {
+///     path: "src/lib.rs",
+///     line_number: 12,
+///     column: Some(5),
+///     byte_offset: 340,
+///     matched: Some("rust"),
+///     text: "fn rust() {}",
+/// };
+/// assert_eq!(template.render(&fields), "| src/lib.rs | 12 | `rust` |");
+/// # Ok::<(), String>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+/// What a [`Template`] fills its placeholders with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fields<'a> {
+    pub path: &'a str,
+    pub line_number: usize,
+    pub column: Option<usize>,
+    pub byte_offset: u64,
+    pub matched: Option<&'a str>,
+    pub text: &'a str,
+}
+
+impl Template {
+    /// Parses `template`.
+    ///
+    /// # Errors
+    /// Returns an error if the template names an unknown placeholder, or
+    /// has a `{` never closed or a `}` never opened.
+    pub fn parse(template: &str) -> Result<Template, String> {
+        let invalid = |reason: String| format!("Invalid template '{template}': {reason}");
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut rest = template;
+        while let Some(i) = rest.find(['{', '}']) {
+            literal.push_str(&rest[..i]);
+            let brace = &rest[i..i + 1];
+            rest = &rest[i + 1..];
+            if let Some(after) = rest.strip_prefix(brace) {
+                literal.push_str(brace);
+                rest = after;
+                continue;
+            }
+            if brace == "}" {
+                return Err(invalid(String::from("unmatched '}'")));
+            }
+            let end = rest
+                .find('}')
+                .ok_or_else(|| invalid(String::from("unclosed '{'")))?;
+            let part = match &rest[..end] {
+                "path" => Part::Path,
+                "line" => Part::Line,
+                "column" => Part::Column,
+                "byte" => Part::Byte,
+                "match" => Part::Match,
+                "text" => Part::Text,
+                name => return Err(invalid(format!("unknown placeholder '{{{name}}}'"))),
+            };
+            if !literal.is_empty() {
+                parts.push(Part::Literal(std::mem::take(&mut literal)));
+            }
+            parts.push(part);
+            rest = &rest[end + 1..];
+        }
+        literal.push_str(rest);
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+        Ok(Template { parts })
+    }
+
+    /// Renders the template with its placeholders replaced by `fields`.
+    pub fn render(&self, fields: &Fields) -> String {
+        let mut rendered = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(text) => rendered.push_str(text),
+                Part::Path => rendered.push_str(fields.path),
+                Part::Line => rendered.push_str(&fields.line_number.to_string()),
+                Part::Column => {
+                    if let Some(column) = fields.column {
+                        rendered.push_str(&column.to_string());
+                    }
+                }
+                Part::Byte => rendered.push_str(&fields.byte_offset.to_string()),
+                Part::Match => rendered.push_str(fields.matched.unwrap_or_default()),
+                Part::Text => rendered.push_str(fields.text),
+            }
+        }
+        rendered
+    }
+}
+
+/// Merges the numbers of the lines selected in an input, given in
+/// increasing order, into runs of consecutive ones.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::render::{LineRuns, format_run};
+///
+/// let mut runs = LineRuns::default();
+/// let mut printed = Vec::new();
+/// for line_number in [3, 12, 13, 14, 15] {
+///     printed.extend(runs.push(line_number).as_ref().map(format_run));
+/// }
+/// printed.extend(runs.finish().as_ref().map(format_run));
+/// assert_eq!(printed, ["3", "12-15"]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LineRuns {
+    current: Option<RangeInclusive<usize>>,
+}
+
+impl LineRuns {
+    /// Adds `line_number`, which must be greater than those added before,
+    /// returning the run it ends if it does not follow on from it.
+    pub fn push(&mut self, line_number: usize) -> Option<RangeInclusive<usize>> {
+        match &mut self.current {
+            Some(run) if *run.end() + 1 == line_number => {
+                *run = *run.start()..=line_number;
+                None
+            }
+            current => current.replace(line_number..=line_number),
+        }
+    }
+
+    /// Returns the run still open, if any, leaving none.
+    pub fn finish(&mut self) -> Option<RangeInclusive<usize>> {
+        self.current.take()
+    }
+}
+
+/// Renders `run` as its only line number, or as its first and last ones
+/// joined by `-`.
+pub fn format_run(run: &RangeInclusive<usize>) -> String {
+    if run.start() == run.end() {
+        run.start().to_string()
+    } else {
+        format!("{}-{}", run.start(), run.end())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Marks occurrences with brackets, to show where the renderer is used.
+    struct Brackets;
+
+    impl Renderer for Brackets {
+        fn matched(&self, text: &str, _pattern: usize, rendered: &mut String) {
+            rendered.push('[');
+            rendered.push_str(text);
+            rendered.push(']');
+        }
+    }
+
+    #[test]
+    fn each_range_goes_through_the_renderer() {
+        let line = LineMatch::find("ab", 1, "ab cab abab", false);
+        assert_eq!(line.ranges, vec![0..2, 4..6, 7..9, 9..11]);
+        assert_eq!(render_match(&line, &Brackets), "[ab] c[ab] [ab][ab]");
+    }
+
+    #[test]
+    fn plain_renders_the_text_unchanged() {
+        let line = LineMatch::find("É", 7, "café CAFÉ", true);
+        assert_eq!(line.line_number, 7);
+        assert_eq!(render_match(&line, &Plain), "café CAFÉ");
+        assert_eq!(render_match(&line, &Brackets), "caf[é] CAF[É]");
+    }
+
+    #[test]
+    fn colored_paints_only_the_matches() {
+        let line = LineMatch::find("x", 1, "x-y-x", false);
+        assert_eq!(
+            render_match(&line, &Colored("32")),
+            "\x1b[32mx\x1b[0m-y-\x1b[32mx\x1b[0m"
+        );
+        assert_eq!(render_match(&line, &Colored("")), "x-y-x");
+    }
+
+    /// Every combination of a path, `-n`, `-b`, and the kind of line, with
+    /// the prefix GNU grep prints for it.
+    #[test]
+    fn prefixes_follow_grep() {
+        let golden = [
+            (false, false, false, LineKind::Selected, ""),
+            (false, false, false, LineKind::Context, ""),
+            (false, false, true, LineKind::Selected, "120:"),
+            (false, false, true, LineKind::Context, "120-"),
+            (false, true, false, LineKind::Selected, "7:"),
+            (false, true, false, LineKind::Context, "7-"),
+            (false, true, true, LineKind::Selected, "7:120:"),
+            (false, true, true, LineKind::Context, "7-120-"),
+            (true, false, false, LineKind::Selected, "a.log:"),
+            (true, false, false, LineKind::Context, "a.log-"),
+            (true, false, true, LineKind::Selected, "a.log:120:"),
+            (true, false, true, LineKind::Context, "a.log-120-"),
+            (true, true, false, LineKind::Selected, "a.log:7:"),
+            (true, true, false, LineKind::Context, "a.log-7-"),
+            (true, true, true, LineKind::Selected, "a.log:7:120:"),
+            (true, true, true, LineKind::Context, "a.log-7-120-"),
+        ];
+        for (path, line_number, byte_offset, kind, expected) in golden {
+            let prefix = Prefix {
+                path: path.then_some("a.log"),
+                line_number: line_number.then_some(7),
+                column: None,
+                byte_offset: byte_offset.then_some(120),
+                kind,
+            };
+            assert_eq!(
+                prefix.render(None, false),
+                expected,
+                "path: {path}, -n: {line_number}, -b: {byte_offset}, {kind:?}"
+            );
+        }
+        assert_eq!(GROUP_SEPARATOR, "--");
+    }
+
+    #[test]
+    fn prefixes_with_columns_nul_and_color() {
+        let prefix = Prefix {
+            path: Some("a.log"),
+            line_number: Some(7),
+            column: Some(3),
+            byte_offset: None,
+            kind: LineKind::Selected,
+        };
+        assert_eq!(prefix.render(None, false), "a.log:7:3:");
+        assert_eq!(prefix.render(None, true), "a.log\x007:3:");
+
+        let theme = Theme {
+            separator: String::from("36"),
+            ..Theme::default()
+        };
+        let prefix = Prefix {
+            column: None,
+            byte_offset: Some(120),
+            kind: LineKind::Context,
+            ..prefix
+        };
+        assert_eq!(
+            prefix.render(Some(&theme), false),
+            "\x1b[35ma.log\x1b[0m\x1b[36m-\x1b[0m\x1b[32m7\x1b[0m\x1b[36m-\x1b[0m\
+             \x1b[32m120\x1b[0m\x1b[36m-\x1b[0m"
+        );
+    }
+
+    /// The fields of a match in a fixed line, `FIXME: see é and FIXME`, as
+    /// the 4th line of `notes.md`.
+    fn fields() -> Fields<'static> {
+        Fields {
+            path: "notes.md",
+            line_number: 4,
+            column: Some(19),
+            byte_offset: 120,
+            matched: Some("FIXME"),
+            text: "FIXME: see é and FIXME",
+        }
+    }
+
+    #[test]
+    fn templates_render_each_placeholder() {
+        let templates = [
+            (
+                "{path}:{line}: {text}",
+                "notes.md:4: FIXME: see é and FIXME",
+            ),
+            ("{path}:{line}:{column}:{byte}", "notes.md:4:19:120"),
+            (
+                "| {path} | {line} | `{match}` |",
+                "| notes.md | 4 | `FIXME` |",
+            ),
+            (
+                "[[file:{path}::{line}][{match}]]",
+                "[[file:notes.md::4][FIXME]]",
+            ),
+            ("{match}{match}", "FIXMEFIXME"),
+            ("no placeholders", "no placeholders"),
+            ("", ""),
+        ];
+        for (template, expected) in templates {
+            let template = Template::parse(template).unwrap();
+            assert_eq!(template.render(&fields()), expected);
+        }
+    }
+
+    #[test]
+    fn doubled_braces_are_literal() {
+        let template = Template::parse("{{{line}}} {{path}} }}{{").unwrap();
+        assert_eq!(template.render(&fields()), "{4} {path} }{");
+    }
+
+    #[test]
+    fn missing_fields_are_empty() {
+        let fields = Fields {
+            column: None,
+            matched: None,
+            ..fields()
+        };
+        let template = Template::parse("{line}:{column}:{byte}:[{match}]").unwrap();
+        assert_eq!(template.render(&fields), "4::120:[]");
+    }
+
+    #[test]
+    fn invalid_templates_are_rejected() {
+        let errors = [
+            ("{file}", "unknown placeholder '{file}'"),
+            ("{Path}", "unknown placeholder '{Path}'"),
+            ("{}", "unknown placeholder '{}'"),
+            ("{line", "unclosed '{'"),
+            ("line}", "unmatched '}'"),
+        ];
+        for (template, reason) in errors {
+            assert_eq!(
+                Template::parse(template),
+                Err(format!("Invalid template '{template}': {reason}"))
+            );
+        }
+    }
+
+    #[test]
+    fn lines_without_matches_are_rendered_whole() {
+        let line = LineMatch::find("zzz", 2, "no match here", false);
+        assert!(line.ranges.is_empty());
+        assert_eq!(render_match(&line, &Brackets), "no match here");
+    }
+
+    fn runs(line_numbers: &[usize]) -> Vec<String> {
+        let mut runs = LineRuns::default();
+        let mut printed: Vec<String> = line_numbers
+            .iter()
+            .filter_map(|&line_number| runs.push(line_number))
+            .map(|run| format_run(&run))
+            .collect();
+        printed.extend(runs.finish().map(|run| format_run(&run)));
+        printed
+    }
+
+    #[test]
+    fn consecutive_lines_merge_into_runs() {
+        assert_eq!(runs(&[1, 2, 3, 7, 8, 10]), ["1-3", "7-8", "10"]);
+        assert_eq!(runs(&[4, 6, 8]), ["4", "6", "8"]);
+        assert_eq!(runs(&[5]), ["5"]);
+        assert!(runs(&[]).is_empty());
+    }
+
+    #[test]
+    fn finishing_leaves_no_run_open() {
+        let mut runs = LineRuns::default();
+        assert_eq!(runs.push(1), None);
+        assert_eq!(runs.push(2), None);
+        assert_eq!(runs.finish(), Some(1..=2));
+        assert_eq!(runs.finish(), None);
+        assert_eq!(runs.push(3), None);
+        assert_eq!(runs.finish(), Some(3..=3));
+    }
+}
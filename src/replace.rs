@@ -0,0 +1,106 @@
+//! Substituting matches in printed output, as done by `--replace`.
+//!
+//! Replacement only ever changes what is printed; files are left untouched.
+
+use std::ops::Range;
+
+/// Expands a replacement template for a single `matched` occurrence.
+///
+/// `$0` stands for the matched text, which keeps its original casing during
+/// a case-insensitive search, and `$$` for a literal `$`. Any other `$` is
+/// kept as is.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::replace::expand_replacement;
+///
+/// assert_eq!(expand_replacement("[$0]", "Rust"), "[Rust]");
+/// assert_eq!(expand_replacement("$$0 is $1", "Rust"), "$0 is $1");
+/// ```
+pub fn expand_replacement(template: &str, matched: &str) -> String {
+    let mut expanded = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(index) = rest.find('$') {
+        expanded.push_str(&rest[..index]);
+        rest = &rest[index + 1..];
+        if let Some(after) = rest.strip_prefix('0') {
+            expanded.push_str(matched);
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix('$') {
+            expanded.push('$');
+            rest = after;
+        } else {
+            expanded.push('$');
+        }
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+/// Replaces each of the byte `ranges` of `line` with the expanded `template`.
+///
+/// # Returns
+/// The new line, along with the byte ranges the replacements occupy in it,
+/// so that they can be highlighted.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::replace::replace_ranges;
+///
+/// let (line, ranges) = replace_ranges("a rust b rust", &[2..6, 9..13], "<$0>");
+/// assert_eq!(line, "a <rust> b <rust>");
+/// assert_eq!(ranges, vec![2..8, 11..17]);
+/// ```
+pub fn replace_ranges(
+    line: &str,
+    ranges: &[Range<usize>],
+    template: &str,
+) -> (String, Vec<Range<usize>>) {
+    let mut replaced = String::with_capacity(line.len());
+    let mut replaced_ranges = Vec::with_capacity(ranges.len());
+    let mut end = 0;
+    for range in ranges {
+        replaced.push_str(&line[end..range.start]);
+        let start = replaced.len();
+        replaced.push_str(&expand_replacement(template, &line[range.clone()]));
+        replaced_ranges.push(start..replaced.len());
+        end = range.end;
+    }
+    replaced.push_str(&line[end..]);
+    (replaced, replaced_ranges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_references_and_escapes() {
+        assert_eq!(expand_replacement("plain", "x"), "plain");
+        assert_eq!(expand_replacement("$0$0", "ab"), "abab");
+        assert_eq!(expand_replacement("$$", "x"), "$");
+        assert_eq!(expand_replacement("$$$0", "x"), "$x");
+        assert_eq!(expand_replacement("cost: $5", "x"), "cost: $5");
+        assert_eq!(expand_replacement("trailing $", "x"), "trailing $");
+        assert_eq!(expand_replacement("", "x"), "");
+    }
+
+    #[test]
+    fn replaces_with_different_lengths() {
+        let (line, ranges) = replace_ranges("Rust and rust", &[0..4, 9..13], "");
+        assert_eq!(line, " and ");
+        assert_eq!(ranges, vec![0..0, 5..5]);
+
+        let (line, ranges) = replace_ranges("héllo wörld", &[1..3, 7..13], "$0!");
+        assert_eq!(line, "hé!llo wörld!");
+        assert_eq!(ranges, vec![1..4, 8..15]);
+    }
+
+    #[test]
+    fn no_ranges_leave_the_line() {
+        assert_eq!(
+            replace_ranges("line", &[], "x"),
+            (String::from("line"), Vec::new())
+        );
+    }
+}
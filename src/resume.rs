@@ -0,0 +1,245 @@
+//! Searching a growing file from where an earlier search stopped, for
+//! `--start-byte`, so that a log can be searched again and again without
+//! its lines being searched twice or missed.
+//!
+//! A search starts at the given offset, or past the end of the line the
+//! offset falls within, so that a partial line is never searched, and
+//! reports the offset just past the last whole line it read, where the next
+//! search starts. A last line without its terminator, perhaps still being
+//! written, is neither searched nor counted as read, so that the next
+//! search reads it once it is whole.
+
+use std::io::{self, BufRead, Read};
+
+/// Where the search of an input starts.
+///
+/// - `offset`: The offset of the first byte searched, which starts a line.
+/// - `lines`: How many lines come before it, if they were counted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Start {
+    pub offset: u64,
+    pub lines: Option<u64>,
+}
+
+/// Reads `reader`, which is `at` bytes into its input, up to `start`, then
+/// on through the end of the line `start` falls within, unless it is where
+/// a line starts.
+///
+/// Whether a line starts at `start` is told by the byte before it, so `at`
+/// is no later than `start - 1`; a file can be seeked there rather than
+/// read from 0. The lines before are only counted if `count_lines`, which
+/// takes reading from 0. An input ending before `start` is read to its end.
+///
+/// # Errors
+/// Returns an error if reading fails.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::resume::{Start, skip_to_line};
+///
+/// let mut input = "one\ntwo\nthree\n".as_bytes();
+/// let start = skip_to_line(&mut input, 0, 5, b'\n', true)?;
+/// assert_eq!(start, Start { offset: 8, lines: Some(2) });
+/// assert_eq!(input, b"three\n");
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn skip_to_line(
+    reader: &mut impl BufRead,
+    at: u64,
+    start: u64,
+    terminator: u8,
+    count_lines: bool,
+) -> io::Result<Start> {
+    debug_assert!(at < start || at == 0, "the byte before {start} is read");
+    debug_assert!(at == 0 || !count_lines, "lines are counted from 0");
+    let mut offset = at;
+    let mut lines = 0;
+    let mut previous = terminator;
+    while offset < start {
+        let buffer = reader.fill_buf()?;
+        if buffer.is_empty() {
+            break;
+        }
+        let read = buffer.len().min((start - offset) as usize);
+        lines += buffer[..read]
+            .iter()
+            .filter(|&&byte| byte == terminator)
+            .count() as u64;
+        previous = buffer[read - 1];
+        reader.consume(read);
+        offset += read as u64;
+    }
+    if previous != terminator {
+        loop {
+            let buffer = reader.fill_buf()?;
+            if buffer.is_empty() {
+                break;
+            }
+            let (read, ended) = match buffer.iter().position(|&byte| byte == terminator) {
+                Some(end) => (end + 1, true),
+                None => (buffer.len(), false),
+            };
+            reader.consume(read);
+            offset += read as u64;
+            if ended {
+                lines += 1;
+                break;
+            }
+        }
+    }
+    Ok(Start {
+        offset,
+        lines: count_lines.then_some(lines),
+    })
+}
+
+/// A reader of the whole lines of its input, noting in `end` the offset
+/// just past the last terminator read through it, where a search resuming
+/// after this one starts.
+///
+/// A last line without its terminator is held back, and read as if the
+/// input ended before it.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::resume::LineEnds;
+/// use std::io::BufRead;
+///
+/// let mut end = 10;
+/// let reader = LineEnds::new("one\ntwo\nthr".as_bytes(), 10, b'\n', &mut end);
+/// let lines: Vec<String> = reader.lines().map(Result::unwrap).collect();
+/// assert_eq!(lines, ["one", "two"]);
+/// assert_eq!(end, 18);
+/// ```
+pub struct LineEnds<'e, R> {
+    inner: R,
+    /// The offset in the input of the next byte read.
+    offset: u64,
+    terminator: u8,
+    end: &'e mut u64,
+    /// Whole lines read from `inner`, of which those before `read` have
+    /// been read through this reader.
+    lines: Vec<u8>,
+    read: usize,
+}
+
+impl<'e, R> LineEnds<'e, R> {
+    /// Wraps `inner`, which is `offset` bytes into its input, at the start
+    /// of a line, where `end` is set to.
+    pub fn new(inner: R, offset: u64, terminator: u8, end: &'e mut u64) -> LineEnds<'e, R> {
+        *end = offset;
+        LineEnds {
+            inner,
+            offset,
+            terminator,
+            end,
+            lines: Vec::new(),
+            read: 0,
+        }
+    }
+}
+
+impl<R: BufRead> Read for LineEnds<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.fill_buf()?.read(buf)?;
+        self.consume(read);
+        Ok(read)
+    }
+}
+
+impl<R: BufRead> BufRead for LineEnds<'_, R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.read == self.lines.len() {
+            self.lines.clear();
+            self.read = 0;
+            // Read on through the next terminator, dropping what comes
+            // after the last one at the end of the input.
+            loop {
+                let buffer = self.inner.fill_buf()?;
+                if buffer.is_empty() {
+                    self.lines.clear();
+                    break;
+                }
+                match buffer.iter().rposition(|&byte| byte == self.terminator) {
+                    Some(last) => {
+                        self.lines.extend_from_slice(&buffer[..=last]);
+                        self.inner.consume(last + 1);
+                        break;
+                    }
+                    None => {
+                        let read = buffer.len();
+                        self.lines.extend_from_slice(buffer);
+                        self.inner.consume(read);
+                    }
+                }
+            }
+        }
+        Ok(&self.lines[self.read..])
+    }
+
+    fn consume(&mut self, amount: usize) {
+        let consumed = &self.lines[self.read..self.read + amount];
+        if let Some(end) = line_end(consumed, self.offset, self.terminator) {
+            *self.end = end;
+        }
+        self.offset += amount as u64;
+        self.read += amount;
+    }
+}
+
+/// The offset just past the last `terminator` in `bytes`, which start at
+/// `offset`, if there is one.
+fn line_end(bytes: &[u8], offset: u64, terminator: u8) -> Option<u64> {
+    let last = bytes.iter().rposition(|&byte| byte == terminator)?;
+    Some(offset + last as u64 + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_start_within_a_line_skips_the_rest_of_it() {
+        let input = b"one\ntwo\nthree\n";
+        for (start, expected) in [(0, 0), (1, 4), (3, 4), (4, 4), (5, 8), (13, 14)] {
+            let mut reader = &input[..];
+            let at = if start == 0 { 0 } else { start - 1 };
+            let mut seeked = &input[at as usize..];
+            let counted = skip_to_line(&mut reader, 0, start, b'\n', true).unwrap();
+            let sought = skip_to_line(&mut seeked, at, start, b'\n', false).unwrap();
+            assert_eq!(counted.offset, expected, "{start}");
+            assert_eq!(sought.offset, expected, "{start}");
+            assert_eq!(sought.lines, None);
+            assert_eq!(reader, &input[expected as usize..], "{start}");
+            assert_eq!(seeked, &input[expected as usize..], "{start}");
+        }
+        let mut reader = &input[..];
+        let start = skip_to_line(&mut reader, 0, 9, b'\n', true).unwrap();
+        assert_eq!(start.lines, Some(3));
+        let mut reader = &input[..];
+        let start = skip_to_line(&mut reader, 0, 20, b'\n', true).unwrap();
+        assert_eq!(
+            start,
+            Start {
+                offset: 14,
+                lines: Some(3)
+            }
+        );
+    }
+
+    #[test]
+    fn the_end_is_past_the_last_whole_line() {
+        let mut end = 0;
+        let mut reader = LineEnds::new(&b"a\0b\0partial"[..], 4, b'\0', &mut end);
+        let mut read = Vec::new();
+        reader.read_to_end(&mut read).unwrap();
+        assert_eq!(end, 8);
+
+        assert_eq!(read, b"a\0b\0");
+
+        let mut end = 0;
+        let reader = LineEnds::new(&b"no terminator yet"[..], 7, b'\n', &mut end);
+        assert_eq!(reader.lines().count(), 0);
+        assert_eq!(end, 7);
+    }
+}
@@ -0,0 +1,233 @@
+//! The SARIF 2.1.0 document printed by `--output-format=sarif`, for
+//! uploading matches to code-scanning services.
+//!
+//! The document has a single run, with a rule for each pattern searched
+//! for and a result for each match, located by the URI of its file and the
+//! line and columns it spans. Columns count Unicode code points, as the run
+//! declares with `columnKind`, rather than SARIF's default of UTF-16 code
+//! units. The matched text is the result's message.
+//!
+//! SARIF cannot be streamed: the document is a single JSON object, printed
+//! once the search is over. To bound the memory that takes, a [`Report`]
+//! keeps at most [`MAX_RESULTS`] results, and records how many more it left
+//! out in a warning notification of the run's invocation.
+
+use std::fmt::Write;
+
+use crate::json::string;
+
+/// The most results a [`Report`] keeps, which is also the most GitHub code
+/// scanning accepts in one run.
+pub const MAX_RESULTS: usize = 25_000;
+
+/// The `ruleId` of the pattern at `rule_index`.
+fn rule_id(rule_index: usize) -> String {
+    format!("pattern-{}", rule_index + 1)
+}
+
+/// Returns `path` as the URI of an artifact: relative paths stay relative,
+/// absolute ones become `file:` URIs, and everything outside the characters
+/// URIs allow as they are is percent-encoded. On Windows, backslashes are
+/// taken as separators.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::sarif::uri;
+///
+/// assert_eq!(uri("src/main.rs"), "src/main.rs");
+/// assert_eq!(uri("/srv/my notes.txt"), "file:///srv/my%20notes.txt");
+/// ```
+pub fn uri(path: &str) -> String {
+    let path = if cfg!(windows) {
+        path.replace('\\', "/")
+    } else {
+        path.to_string()
+    };
+    let bytes = path.as_bytes();
+    let (mut uri, rest) = if path.starts_with('/') {
+        (String::from("file://"), path.as_str())
+    } else if bytes.len() > 2 && bytes[0].is_ascii_alphabetic() && &bytes[1..3] == b":/" {
+        (format!("file:///{}", &path[..2]), &path[2..])
+    } else {
+        (String::new(), path.as_str())
+    };
+    for byte in rest.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~/".contains(&byte) {
+            uri.push(byte as char);
+        } else {
+            let _ = write!(uri, "%{byte:02X}");
+        }
+    }
+    uri
+}
+
+/// Builds the result of one match.
+///
+/// # Arguments
+/// - `rule_index`: The index of the pattern matched among the rules.
+/// - `path`: The name of the file the match is in.
+/// - `line_number`: The 1-based number of its line.
+/// - `start_column`, `end_column`: The 1-based columns, in characters, of
+///   its first character and of the one after its last.
+/// - `text`: The matched text.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::sarif::result;
+///
+/// assert_eq!(
+///     result(0, "a.rs", 3, 5, 9, "TODO"),
+///     r#"{"ruleId":"pattern-1","ruleIndex":0,"level":"warning","message":{"text":"TODO"},"locations":[{"physicalLocation":{"artifactLocation":{"uri":"a.rs"},"region":{"startLine":3,"startColumn":5,"endColumn":9}}}]}"#
+/// );
+/// ```
+pub fn result(
+    rule_index: usize,
+    path: &str,
+    line_number: usize,
+    start_column: usize,
+    end_column: usize,
+    text: &str,
+) -> String {
+    format!(
+        concat!(
+            r#"{{"ruleId":{},"ruleIndex":{},"level":"warning","message":{{"text":{}}},"#,
+            r#""locations":[{{"physicalLocation":{{"artifactLocation":{{"uri":{}}},"#,
+            r#""region":{{"startLine":{},"startColumn":{},"endColumn":{}}}}}}}]}}"#,
+        ),
+        string(&rule_id(rule_index)),
+        rule_index,
+        string(text),
+        string(&uri(path)),
+        line_number,
+        start_column,
+        end_column,
+    )
+}
+
+/// The results of a search, gathered to be printed as a SARIF document.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::sarif::{result, Report};
+///
+/// let mut report = Report::new(&["TODO"]);
+/// report.push(result(0, "a.rs", 3, 5, 9, "TODO"));
+/// let document = report.to_document();
+/// assert!(document.starts_with(r#"{"$schema":"#));
+/// assert!(document.contains(r#""results":[{"ruleId":"pattern-1""#));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report {
+    patterns: Vec<String>,
+    results: Vec<String>,
+    omitted: u64,
+}
+
+impl Report {
+    /// Creates a report with no results, with a rule for each of
+    /// `patterns`.
+    pub fn new(patterns: &[&str]) -> Report {
+        Report {
+            patterns: patterns.iter().map(|pattern| pattern.to_string()).collect(),
+            ..Report::default()
+        }
+    }
+
+    /// Adds `result`, as built by [`result`], unless the report already
+    /// holds [`MAX_RESULTS`], in which case it is only counted.
+    pub fn push(&mut self, result: String) {
+        if self.results.len() < MAX_RESULTS {
+            self.results.push(result);
+        } else {
+            self.omitted += 1;
+        }
+    }
+
+    /// How many results were left out for exceeding [`MAX_RESULTS`].
+    pub fn omitted(&self) -> u64 {
+        self.omitted
+    }
+
+    /// Builds the SARIF document, as a single line of JSON.
+    pub fn to_document(&self) -> String {
+        let rules = self
+            .patterns
+            .iter()
+            .enumerate()
+            .map(|(index, pattern)| {
+                format!(
+                    r#"{{"id":{},"shortDescription":{{"text":{}}}}}"#,
+                    string(&rule_id(index)),
+                    string(&format!("Matches of {pattern:?}")),
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        let mut document = format!(
+            concat!(
+                r#"{{"$schema":"https://json.schemastore.org/sarif-2.1.0.json","version":"2.1.0","#,
+                r#""runs":[{{"tool":{{"driver":{{"name":"minigrep","version":{},"rules":[{}]}}}},"#,
+                r#""columnKind":"unicodeCodePoints","results":[{}]"#,
+            ),
+            string(env!("CARGO_PKG_VERSION")),
+            rules,
+            self.results.join(","),
+        );
+        if self.omitted > 0 {
+            let _ = write!(
+                document,
+                concat!(
+                    r#","invocations":[{{"executionSuccessful":true,"#,
+                    r#""toolExecutionNotifications":[{{"level":"warning","message":{{"text":{}}}}}]}}]"#,
+                ),
+                string(&format!(
+                    "{} more results were left out, past the first {MAX_RESULTS}",
+                    self.omitted
+                )),
+            );
+        }
+        document.push_str("}]}");
+        document
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uris_are_percent_encoded() {
+        assert_eq!(uri("./a b/c#d.rs"), "./a%20b/c%23d.rs");
+        assert_eq!(uri("caf\u{e9}:1.txt"), "caf%C3%A9%3A1.txt");
+        assert_eq!(uri("C:/src/lib.rs"), "file:///C:/src/lib.rs");
+        assert_eq!(uri("/"), "file:///");
+    }
+
+    #[test]
+    fn a_report_without_results_is_complete() {
+        assert_eq!(
+            Report::new(&["say \"hi\""]).to_document(),
+            concat!(
+                r#"{"$schema":"https://json.schemastore.org/sarif-2.1.0.json","version":"2.1.0","#,
+                r#""runs":[{"tool":{"driver":{"name":"minigrep","version":""#,
+                env!("CARGO_PKG_VERSION"),
+                r#"","rules":[{"id":"pattern-1","shortDescription":{"text":"Matches of \"say \\\"hi\\\"\""}}]}},"#,
+                r#""columnKind":"unicodeCodePoints","results":[]}]}"#,
+            )
+        );
+    }
+
+    #[test]
+    fn results_past_the_limit_are_counted() {
+        let mut report = Report::new(&["x"]);
+        for line in 0..MAX_RESULTS + 2 {
+            report.push(result(0, "a", line + 1, 1, 2, "x"));
+        }
+        assert_eq!(report.omitted(), 2);
+        let document = report.to_document();
+        assert_eq!(document.matches(r#""ruleId""#).count(), MAX_RESULTS);
+        assert!(document.ends_with(
+            r#""invocations":[{"executionSuccessful":true,"toolExecutionNotifications":[{"level":"warning","message":{"text":"2 more results were left out, past the first 25000"}}]}]}]}"#
+        ));
+    }
+}
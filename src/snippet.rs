@@ -0,0 +1,312 @@
+//! Cutting long lines down to a window around their matches.
+
+use std::ops::Range;
+
+/// The marker printed where a line was cut.
+pub const ELLIPSIS: &str = "…";
+
+/// Chooses which part of a long `line` to show, at most `max_chars`
+/// characters wide.
+///
+/// The window starts at the beginning of the line when the first match fits
+/// there; otherwise it is moved so that the first match sits in the middle,
+/// without running past the end of the line. It always starts and ends on
+/// character boundaries.
+///
+/// # Arguments
+/// - `line`: The line to cut.
+/// - `first_match`: The byte range of the first match in `line`, if any.
+/// - `max_chars`: The width of the window, in characters.
+///
+/// # Returns
+/// The byte range of `line` to show.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::snippet::preview_window;
+///
+/// let line = "0123456789 rust 0123456789";
+/// assert_eq!(preview_window(line, Some(11..15), 6), 10..16);
+/// assert_eq!(preview_window(line, Some(11..15), 100), 0..line.len());
+/// ```
+pub fn preview_window(
+    line: &str,
+    first_match: Option<Range<usize>>,
+    max_chars: usize,
+) -> Range<usize> {
+    let total = line.chars().count();
+    if total <= max_chars {
+        return 0..line.len();
+    }
+
+    let start = match first_match {
+        Some(found) => {
+            let match_start = line[..found.start].chars().count();
+            let match_end = match_start + line[found].chars().count();
+            if match_end <= max_chars {
+                0
+            } else {
+                let lead = max_chars.saturating_sub(match_end - match_start) / 2;
+                match_start.saturating_sub(lead).min(total - max_chars)
+            }
+        }
+        None => 0,
+    };
+
+    let byte_offset = |chars: usize| {
+        line.char_indices()
+            .nth(chars)
+            .map_or(line.len(), |(offset, _)| offset)
+    };
+    byte_offset(start)..byte_offset(start + max_chars)
+}
+
+/// How many characters a cut made by [`snippet_window`] may move inwards
+/// to land just past whitespace instead of inside a word, unless that is
+/// more than half of the context kept.
+const WORD_SLACK: usize = 5;
+
+/// Chooses which part of `line` to show around its first match: at most
+/// `context_chars` characters before it and as many after it.
+///
+/// Only the first match is taken into account; later ones are shown only
+/// if they fall inside the window. Where a cut would fall inside a word,
+/// it is moved inwards, by at most [`WORD_SLACK`] characters or half of
+/// `context_chars` and never into the match, to the nearest whitespace,
+/// which is left out. The window
+/// always starts and ends on character boundaries.
+///
+/// # Arguments
+/// - `line`: The line to cut.
+/// - `first_match`: The byte range of the first match in `line`.
+/// - `context_chars`: How many characters to keep on either side of it.
+///
+/// # Returns
+/// The byte range of `line` to show.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::snippet::snippet_window;
+///
+/// let line = "a long preamble then rust and some tail";
+/// let window = snippet_window(line, 21..25, 8);
+/// assert_eq!(&line[window], "then rust and");
+/// ```
+pub fn snippet_window(line: &str, first_match: Range<usize>, context_chars: usize) -> Range<usize> {
+    let slack = WORD_SLACK.min(context_chars / 2);
+    let before: Vec<(usize, char)> = line[..first_match.start].char_indices().collect();
+    let mut start = 0;
+    if before.len() > context_chars {
+        let cut = before.len() - context_chars;
+        start = before
+            .get(cut)
+            .map_or(first_match.start, |&(offset, _)| offset);
+        if !before[cut - 1].1.is_whitespace() {
+            let mut inwards = (cut..before.len()).take(slack);
+            if let Some(space) = inwards.find(|&i| before[i].1.is_whitespace()) {
+                start = before[space].0 + before[space].1.len_utf8();
+            }
+        }
+    }
+
+    let after: Vec<(usize, char)> = line[first_match.end..]
+        .char_indices()
+        .map(|(offset, c)| (first_match.end + offset, c))
+        .collect();
+    let mut end = line.len();
+    if after.len() > context_chars {
+        end = after[context_chars].0;
+        if !after[context_chars].1.is_whitespace() {
+            let mut inwards = (0..context_chars).rev().take(slack);
+            if let Some(space) = inwards.find(|&i| after[i].1.is_whitespace()) {
+                end = after[space].0;
+            }
+        }
+    }
+    start..end
+}
+
+/// Chooses the parts of `line` to show around each of its matches: exactly
+/// `context_chars` characters before and after each, or as many as there
+/// are, the windows of matches that overlap or touch merged into one, so
+/// that no character is shown twice.
+///
+/// Only the characters around each match are looked at, so that the cost
+/// does not grow with the length of the line. The windows always start and
+/// end on character boundaries.
+///
+/// # Arguments
+/// - `line`: The line to cut.
+/// - `ranges`: The byte ranges of the matches in `line`, in order.
+/// - `context_chars`: How many characters to keep on either side of each.
+///
+/// # Returns
+/// The byte ranges of `line` to show, in order.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::snippet::context_windows;
+///
+/// let line = r#"{"id":1,"err":"x"},{"id":2,"err":"y"},{"id":3}"#;
+/// let windows = context_windows(line, &[8..13, 27..32], 2);
+/// let shown: Vec<&str> = windows.into_iter().map(|window| &line[window]).collect();
+/// assert_eq!(shown, [r#"1,"err":""#, r#"2,"err":""#]);
+/// assert_eq!(context_windows(line, &[8..13, 27..32], 7), vec![1..39]);
+/// ```
+pub fn context_windows(
+    line: &str,
+    ranges: &[Range<usize>],
+    context_chars: usize,
+) -> Vec<Range<usize>> {
+    let mut windows: Vec<Range<usize>> = Vec::new();
+    for range in ranges {
+        let start = line[..range.start]
+            .char_indices()
+            .rev()
+            .take(context_chars)
+            .last()
+            .map_or(range.start, |(offset, _)| offset);
+        let end = line[range.end..]
+            .char_indices()
+            .nth(context_chars)
+            .map_or(line.len(), |(offset, _)| range.end + offset);
+        match windows.last_mut() {
+            Some(last) if start <= last.end => last.end = last.end.max(end),
+            _ => windows.push(start..end),
+        }
+    }
+    windows
+}
+
+/// Restricts `ranges` to those parts lying inside `window`, making them
+/// relative to the start of the window.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::snippet::clip_ranges;
+///
+/// assert_eq!(clip_ranges(&[2..4, 8..12, 20..22], 3..10), vec![0..1, 5..7]);
+/// ```
+pub fn clip_ranges(ranges: &[Range<usize>], window: Range<usize>) -> Vec<Range<usize>> {
+    ranges
+        .iter()
+        .map(|range| range.start.max(window.start)..range.end.min(window.end))
+        .filter(|range| range.start < range.end)
+        .map(|range| range.start - window.start..range.end - window.start)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_lines_are_whole() {
+        assert_eq!(preview_window("short", Some(0..2), 5), 0..5);
+        assert_eq!(preview_window("", None, 0), 0..0);
+    }
+
+    #[test]
+    fn match_near_start_keeps_head() {
+        let line = "rust and a lot of other text";
+        assert_eq!(preview_window(line, Some(0..4), 8), 0..8);
+        assert_eq!(preview_window(line, None, 8), 0..8);
+    }
+
+    #[test]
+    fn match_far_out_is_centered() {
+        let line = format!("{}rust{}", "a".repeat(50), "b".repeat(50));
+        let window = preview_window(&line, Some(50..54), 10);
+        assert_eq!(window, 47..57);
+        assert_eq!(&line[window], "aaarustbbb");
+    }
+
+    #[test]
+    fn window_never_runs_past_the_end() {
+        let line = format!("{}rust", "a".repeat(50));
+        assert_eq!(preview_window(&line, Some(50..54), 10), 44..54);
+    }
+
+    #[test]
+    fn window_lands_on_char_boundaries() {
+        let line = format!("{}rust{}", "é".repeat(20), "ö".repeat(20));
+        let window = preview_window(&line, Some(40..44), 8);
+        assert_eq!(&line[window], "éérustöö");
+    }
+
+    #[test]
+    fn snippets_keep_context_on_both_sides() {
+        let line = format!("{}rust{}", "a".repeat(20), "b".repeat(20));
+        let window = snippet_window(&line, 20..24, 3);
+        assert_eq!(&line[window], "aaarustbbb");
+        assert_eq!(snippet_window(&line, 20..24, 30), 0..line.len());
+        assert_eq!(&line[snippet_window(&line, 20..24, 0)], "rust");
+    }
+
+    #[test]
+    fn snippets_prefer_cutting_at_whitespace() {
+        let line = "alpha beta gamma rust delta epsilon zeta";
+        assert_eq!(&line[snippet_window(line, 17..21, 8)], "gamma rust delta");
+        assert_eq!(
+            &line[snippet_window(line, 17..21, 11)],
+            "beta gamma rust delta"
+        );
+        assert_eq!(&line[snippet_window(line, 17..21, 6)], "gamma rust delta");
+        let words = "abcdefghijklmnop rust qrstuvwxyzabcdef";
+        assert_eq!(
+            &words[snippet_window(words, 17..21, 8)],
+            "jklmnop rust qrstuvw"
+        );
+    }
+
+    #[test]
+    fn snippet_cuts_never_reach_into_the_match() {
+        let line = "xxxxxxxx rust yyyyyyyy";
+        assert_eq!(&line[snippet_window(line, 9..13, 2)], "x rust y");
+        let line = "xxxxxxxxru styyyyyy";
+        assert_eq!(&line[snippet_window(line, 8..13, 2)], "xxru styy");
+    }
+
+    #[test]
+    fn snippet_cuts_land_on_char_boundaries() {
+        let line = format!("{}rust{}", "é".repeat(20), "ö".repeat(20));
+        let window = snippet_window(&line, 40..44, 2);
+        assert_eq!(&line[window], "éérustöö");
+    }
+
+    #[test]
+    fn context_windows_keep_exactly_the_characters_asked_for() {
+        let line = "aaaaaaaaaa rust bbbbbbbbbb rust cccccccccc";
+        let shown = |context_chars| -> Vec<&str> {
+            context_windows(line, &[11..15, 27..31], context_chars)
+                .into_iter()
+                .map(|window| &line[window])
+                .collect()
+        };
+        assert_eq!(shown(0), ["rust", "rust"]);
+        assert_eq!(shown(3), ["aa rust bb", "bb rust cc"]);
+        assert_eq!(shown(6), ["aaaaa rust bbbbbbbbbb rust ccccc"]);
+        assert_eq!(shown(100), [line]);
+        assert!(context_windows(line, &[], 3).is_empty());
+    }
+
+    #[test]
+    fn context_windows_that_touch_are_merged() {
+        let line = "xrustyyrustx";
+        assert_eq!(context_windows(line, &[1..5, 7..11], 1), vec![0..12]);
+        assert_eq!(context_windows(line, &[1..5, 7..11], 0), vec![1..5, 7..11]);
+    }
+
+    #[test]
+    fn context_windows_land_on_char_boundaries() {
+        let line = format!("{}rust{}", "é".repeat(20), "ö".repeat(20));
+        let windows = context_windows(&line, std::slice::from_ref(&(40..44)), 3);
+        assert_eq!(&line[windows[0].clone()], "ééérustööö");
+    }
+
+    #[test]
+    fn clip_keeps_partial_overlaps() {
+        assert_eq!(clip_ranges(&[0..5, 6..7], 2..4), vec![0..2]);
+        assert!(clip_ranges(&[0..2, 4..6], 2..4).is_empty());
+    }
+}
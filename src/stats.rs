@@ -0,0 +1,440 @@
+//! Totals gathered over a whole search, as reported by `--stats`, and the
+//! time each input took, as reported by `--timings`.
+
+use std::{cmp::Reverse, fmt, fmt::Write, time::Duration};
+
+use crate::{mmap::ReadMethod, walk::SkipReason};
+
+/// Counts of what a search looked at and found.
+///
+/// - `files_searched`: Inputs whose contents were searched.
+/// - `skipped_too_large`: Files skipped for exceeding the maximum file size.
+/// - `filtered_out`: Files skipped for being under the minimum file size or
+///   modified outside the times allowed.
+/// - `skipped_devices`: Devices, FIFOs, and sockets skipped instead of read.
+/// - `skipped_binary`: Inputs skipped for holding a NUL byte in their first
+///   block.
+/// - `unreadable`: Inputs skipped because they could not be walked, read, or
+///   rewritten.
+/// - `ignored`: Files and directories skipped for being listed in an ignore
+///   file.
+/// - `lines_scanned`: Lines examined across all searched inputs.
+/// - `matched_lines`: Lines that matched the query.
+/// - `occurrences`: Occurrences of the query in those lines, counted only
+///   for `--count-matches`.
+/// - `elapsed`: Wall-clock time the whole search took.
+/// - `timings`: How long each input took to search, if timed.
+/// - `end_offsets`: The name of each input searched from `--start-byte`,
+///   with the offset just past the last whole line read in it, where the
+///   next search of it can start.
+/// - `read_methods`: The name of each input read with a `--read-strategy`,
+///   with how it was read.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::stats::SearchStats;
+///
+/// let mut stats = SearchStats::default();
+/// stats.record_file(10, 2);
+/// stats.record_file(5, 0);
+///
+/// assert_eq!(stats.files_searched, 2);
+/// assert_eq!(stats.lines_scanned, 15);
+/// assert_eq!(stats.matched_lines, 2);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    pub files_searched: u64,
+    pub skipped_too_large: u64,
+    pub filtered_out: u64,
+    pub skipped_devices: u64,
+    pub skipped_binary: u64,
+    pub unreadable: u64,
+    pub ignored: u64,
+    pub lines_scanned: u64,
+    pub matched_lines: u64,
+    pub occurrences: u64,
+    pub elapsed: Duration,
+    pub timings: Vec<FileTiming>,
+    pub end_offsets: Vec<(String, u64)>,
+    pub read_methods: Vec<(String, ReadMethod)>,
+}
+
+/// How long reading and searching one input took, as reported by
+/// `--timings`.
+///
+/// - `path`: The name the input is printed under.
+/// - `bytes`: How many bytes were searched, after any decompression or
+///   decoding.
+/// - `elapsed`: How long reading and searching them took, not counting any
+///   time spent waiting for a thread to search on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileTiming {
+    pub path: String,
+    pub bytes: u64,
+    pub elapsed: Duration,
+}
+
+impl FileTiming {
+    /// The number of megabytes (10^6 bytes) searched per second, unless
+    /// the time taken was too short to measure.
+    ///
+    /// # Examples
+    /// ```
+    /// use minigrep_cli_tool::stats::FileTiming;
+    /// use std::time::Duration;
+    ///
+    /// let timing = FileTiming {
+    ///     path: String::from("big.log"),
+    ///     bytes: 3_000_000,
+    ///     elapsed: Duration::from_millis(20),
+    /// };
+    /// assert_eq!(timing.throughput(), Some(150.0));
+    /// ```
+    pub fn throughput(&self) -> Option<f64> {
+        throughput(self.bytes, self.elapsed)
+    }
+}
+
+/// Megabytes per second for `bytes` searched in `elapsed`, if not zero.
+fn throughput(bytes: u64, elapsed: Duration) -> Option<f64> {
+    (!elapsed.is_zero()).then(|| bytes as f64 / 1e6 / elapsed.as_secs_f64())
+}
+
+impl SearchStats {
+    /// Records a searched input with its number of scanned and matched lines.
+    pub fn record_file(&mut self, lines_scanned: u64, matched_lines: u64) {
+        self.files_searched += 1;
+        self.lines_scanned += lines_scanned;
+        self.matched_lines += matched_lines;
+    }
+
+    /// Records `occurrences` more occurrences of the query.
+    pub fn record_occurrences(&mut self, occurrences: u64) {
+        self.occurrences += occurrences;
+    }
+
+    /// Records how long the input called `path`, of `bytes`, took to search.
+    pub fn record_timing(&mut self, path: &str, bytes: u64, elapsed: Duration) {
+        self.timings.push(FileTiming {
+            path: path.to_string(),
+            bytes,
+            elapsed,
+        });
+    }
+
+    /// Records that the input called `path`, searched from `--start-byte`,
+    /// was read up to `offset`.
+    pub fn record_end_offset(&mut self, path: &str, offset: u64) {
+        self.end_offsets.push((path.to_string(), offset));
+    }
+
+    /// Records that the input called `path` was read with `method`.
+    pub fn record_read_method(&mut self, path: &str, method: ReadMethod) {
+        self.read_methods.push((path.to_string(), method));
+    }
+
+    /// Records a file that was skipped instead of searched.
+    ///
+    /// Skipped symbolic links are not files, so they are reported as
+    /// warnings instead of being counted, and duplicates are not counted
+    /// either, having been searched under another path.
+    pub fn record_skip(&mut self, reason: SkipReason) {
+        match reason {
+            SkipReason::TooLarge => self.skipped_too_large += 1,
+            SkipReason::TooSmall | SkipReason::TooOld | SkipReason::TooNew => {
+                self.filtered_out += 1
+            }
+            SkipReason::Ignored => self.ignored += 1,
+            SkipReason::Device => self.skipped_devices += 1,
+            SkipReason::Binary => self.skipped_binary += 1,
+            SkipReason::BrokenLink | SkipReason::Cycle | SkipReason::Duplicate => {}
+        }
+    }
+
+    /// Records an input that was skipped because it could not be searched.
+    pub fn record_unreadable(&mut self) {
+        self.unreadable += 1;
+    }
+
+    /// Adds the counts and timings gathered in `other`, e.g. by another
+    /// thread.
+    ///
+    /// The elapsed time is left unchanged, since it covers the whole search.
+    pub fn merge(&mut self, other: &SearchStats) {
+        self.files_searched += other.files_searched;
+        self.skipped_too_large += other.skipped_too_large;
+        self.filtered_out += other.filtered_out;
+        self.skipped_devices += other.skipped_devices;
+        self.skipped_binary += other.skipped_binary;
+        self.unreadable += other.unreadable;
+        self.ignored += other.ignored;
+        self.lines_scanned += other.lines_scanned;
+        self.matched_lines += other.matched_lines;
+        self.occurrences += other.occurrences;
+        self.timings.extend_from_slice(&other.timings);
+        self.end_offsets.extend_from_slice(&other.end_offsets);
+        self.read_methods.extend_from_slice(&other.read_methods);
+    }
+
+    /// The number of files skipped for any reason other than being ignored.
+    pub fn files_skipped(&self) -> u64 {
+        self.skipped_too_large
+            + self.filtered_out
+            + self.skipped_devices
+            + self.skipped_binary
+            + self.unreadable
+    }
+
+    /// Formats the timings as the report printed by `--timings`: a line for
+    /// each input, slowest first and at most `limit` of them, with how long
+    /// it took, its throughput, and its name, then a line with the totals
+    /// for every input timed.
+    ///
+    /// # Examples
+    /// ```
+    /// use minigrep_cli_tool::stats::SearchStats;
+    /// use std::time::Duration;
+    ///
+    /// let mut stats = SearchStats::default();
+    /// stats.record_timing("fast.txt", 1_000_000, Duration::from_millis(10));
+    /// stats.record_timing("slow.txt", 2_000_000, Duration::from_millis(40));
+    /// assert_eq!(
+    ///     stats.timings_report(None),
+    ///     "0.040s     50.0 MB/s  slow.txt\n\
+    ///      0.010s    100.0 MB/s  fast.txt\n\
+    ///      total: 2 files, 3.0 MB in 0.050s, 60.0 MB/s"
+    /// );
+    /// ```
+    pub fn timings_report(&self, limit: Option<usize>) -> String {
+        let mut timings: Vec<&FileTiming> = self.timings.iter().collect();
+        timings.sort_by_key(|timing| (Reverse(timing.elapsed), &timing.path));
+        let mut report = String::new();
+        for timing in timings.iter().take(limit.unwrap_or(usize::MAX)) {
+            let _ = writeln!(
+                report,
+                "{:.3}s {} MB/s  {}",
+                timing.elapsed.as_secs_f64(),
+                format_throughput(timing.throughput()),
+                timing.path
+            );
+        }
+        let bytes: u64 = timings.iter().map(|timing| timing.bytes).sum();
+        let elapsed: Duration = timings.iter().map(|timing| timing.elapsed).sum();
+        let _ = write!(
+            report,
+            "total: {} files, {:.1} MB in {:.3}s, {} MB/s",
+            timings.len(),
+            bytes as f64 / 1e6,
+            elapsed.as_secs_f64(),
+            format_throughput(throughput(bytes, elapsed)).trim_start()
+        );
+        report
+    }
+}
+
+/// Right-aligns a throughput for the columns of the `--timings` report.
+fn format_throughput(throughput: Option<f64>) -> String {
+    match throughput {
+        Some(throughput) => format!("{throughput:>8.1}"),
+        None => format!("{:>8}", "-"),
+    }
+}
+
+/// Formats the stats as the human-readable summary printed by `--stats`,
+/// with the devices and binary files skipped among the files skipped if
+/// there were any, a
+/// line `PATH read to byte N` for each input searched from `--start-byte`,
+/// and a line `PATH read with METHOD` for each input read with a
+/// `--read-strategy`.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::stats::SearchStats;
+/// use std::time::Duration;
+///
+/// let stats = SearchStats {
+///     files_searched: 2,
+///     skipped_too_large: 1,
+///     filtered_out: 3,
+///     skipped_devices: 0,
+///     skipped_binary: 0,
+///     unreadable: 1,
+///     ignored: 4,
+///     lines_scanned: 40,
+///     matched_lines: 3,
+///     occurrences: 0,
+///     elapsed: Duration::from_millis(12),
+///     timings: Vec::new(),
+///     end_offsets: Vec::new(),
+///     read_methods: Vec::new(),
+/// };
+/// assert_eq!(
+///     stats.to_string(),
+///     "2 files searched\n\
+///      5 files skipped (1 too large, 3 filtered out, 1 unreadable)\n\
+///      4 entries ignored\n\
+///      40 lines scanned\n\
+///      3 matched lines\n\
+///      0.012 seconds elapsed"
+/// );
+/// ```
+impl fmt::Display for SearchStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} files searched", self.files_searched)?;
+        let devices = match self.skipped_devices {
+            0 => String::new(),
+            devices => format!(", {devices} devices"),
+        };
+        let binary = match self.skipped_binary {
+            0 => String::new(),
+            binary => format!(", {binary} binary"),
+        };
+        writeln!(
+            f,
+            "{} files skipped ({} too large, {} filtered out{devices}{binary}, {} unreadable)",
+            self.files_skipped(),
+            self.skipped_too_large,
+            self.filtered_out,
+            self.unreadable
+        )?;
+        writeln!(f, "{} entries ignored", self.ignored)?;
+        writeln!(f, "{} lines scanned", self.lines_scanned)?;
+        writeln!(f, "{} matched lines", self.matched_lines)?;
+        let mut end_offsets = self.end_offsets.clone();
+        end_offsets.sort();
+        for (path, offset) in end_offsets {
+            writeln!(f, "{path} read to byte {offset}")?;
+        }
+        let mut read_methods = self.read_methods.clone();
+        read_methods.sort_by(|a, b| a.0.cmp(&b.0));
+        for (path, method) in read_methods {
+            writeln!(f, "{path} read with {method}")?;
+        }
+        write!(f, "{:.3} seconds elapsed", self.elapsed.as_secs_f64())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_are_counted_by_reason() {
+        let mut stats = SearchStats::default();
+        stats.record_skip(SkipReason::TooLarge);
+        stats.record_skip(SkipReason::TooLarge);
+        stats.record_skip(SkipReason::Ignored);
+        stats.record_skip(SkipReason::TooOld);
+        stats.record_skip(SkipReason::TooSmall);
+        stats.record_skip(SkipReason::Device);
+        stats.record_skip(SkipReason::Binary);
+
+        assert_eq!(stats.skipped_too_large, 2);
+        assert_eq!(stats.filtered_out, 2);
+        assert_eq!(stats.skipped_devices, 1);
+        assert_eq!(stats.skipped_binary, 1);
+        assert_eq!(stats.ignored, 1);
+        assert_eq!(stats.files_skipped(), 6);
+        assert_eq!(stats.files_searched, 0);
+        let summary = stats.to_string();
+        assert!(summary.contains(
+            "6 files skipped (2 too large, 2 filtered out, 1 devices, 1 binary, 0 unreadable)"
+        ));
+    }
+
+    #[test]
+    fn merge_adds_counts() {
+        let mut total = SearchStats::default();
+        total.record_file(10, 2);
+        let mut other = SearchStats::default();
+        other.record_file(5, 1);
+        other.record_skip(SkipReason::TooLarge);
+        other.record_skip(SkipReason::Ignored);
+        other.record_skip(SkipReason::TooNew);
+        other.record_unreadable();
+        other.elapsed = Duration::from_secs(1);
+        other.record_timing("a.txt", 100, Duration::from_millis(2));
+
+        total.merge(&other);
+        assert_eq!(total.files_searched, 2);
+        assert_eq!(total.skipped_too_large, 1);
+        assert_eq!(total.filtered_out, 1);
+        assert_eq!(total.ignored, 1);
+        assert_eq!(total.unreadable, 1);
+        assert_eq!(total.files_skipped(), 3);
+        assert_eq!(total.lines_scanned, 15);
+        assert_eq!(total.matched_lines, 3);
+        assert_eq!(total.elapsed, Duration::ZERO);
+        assert_eq!(total.timings, other.timings);
+    }
+
+    #[test]
+    fn empty_summary() {
+        assert_eq!(
+            SearchStats::default().to_string(),
+            "0 files searched\n\
+             0 files skipped (0 too large, 0 filtered out, 0 unreadable)\n\
+             0 entries ignored\n\
+             0 lines scanned\n\
+             0 matched lines\n\
+             0.000 seconds elapsed"
+        );
+    }
+
+    #[test]
+    fn end_offsets_are_reported_by_path() {
+        let mut stats = SearchStats::default();
+        stats.record_end_offset("b.log", 120);
+        let mut other = SearchStats::default();
+        other.record_end_offset("a.log", 4096);
+        stats.merge(&other);
+        stats.elapsed = Duration::from_millis(5);
+        assert!(stats.to_string().ends_with(
+            "0 matched lines\n\
+             a.log read to byte 4096\n\
+             b.log read to byte 120\n\
+             0.005 seconds elapsed"
+        ));
+    }
+
+    #[test]
+    fn read_methods_are_reported_by_path() {
+        let mut stats = SearchStats::default();
+        stats.record_read_method("small.log", ReadMethod::Buffered);
+        let mut other = SearchStats::default();
+        other.record_read_method("big.log", ReadMethod::Mmap);
+        stats.merge(&other);
+        assert!(stats.to_string().ends_with(
+            "0 matched lines\n\
+             big.log read with mmap\n\
+             small.log read with buffered\n\
+             0.000 seconds elapsed"
+        ));
+    }
+
+    #[test]
+    fn timings_are_reported_slowest_first_up_to_the_limit() {
+        let mut stats = SearchStats::default();
+        stats.record_timing("b.txt", 500_000, Duration::from_millis(5));
+        stats.record_timing("slow.txt", 1_000_000, Duration::from_millis(100));
+        stats.record_timing("a.txt", 500_000, Duration::from_millis(5));
+        stats.record_timing("empty.txt", 0, Duration::ZERO);
+        assert_eq!(
+            stats.timings_report(Some(3)),
+            "0.100s     10.0 MB/s  slow.txt\n\
+             0.005s    100.0 MB/s  a.txt\n\
+             0.005s    100.0 MB/s  b.txt\n\
+             total: 4 files, 2.0 MB in 0.110s, 18.2 MB/s"
+        );
+        assert!(
+            stats
+                .timings_report(None)
+                .contains("0.000s        - MB/s  empty.txt\n")
+        );
+        assert_eq!(
+            SearchStats::default().timings_report(None),
+            "total: 0 files, 0.0 MB in 0.000s, - MB/s"
+        );
+    }
+}
@@ -0,0 +1,256 @@
+//! The runs of printable text of `--strings`, pulled out of the raw bytes
+//! of inputs such as binaries and searched as lines, as `strings file |
+//! grep pattern` does in two steps.
+
+use std::io::{self, Read};
+
+/// How many bytes are read from the input at a time.
+const CHUNK: usize = 64 * 1024;
+
+/// How many characters a run has at least unless `--strings` says.
+pub const DEFAULT_MIN: usize = 4;
+
+/// A run of printable characters found by [`Runs`].
+///
+/// - `offset`: The offset of its first byte in the input.
+/// - `text`: The characters of the run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Run {
+    pub offset: u64,
+    pub text: String,
+}
+
+/// The runs of at least a minimum of printable characters in the bytes read
+/// from a reader, in order.
+///
+/// Printable characters are tabs and the characters of valid UTF-8 that
+/// are not control characters, so that ASCII text and UTF-8 text are both
+/// found; every other byte ends a run. The input is read a chunk at a time,
+/// a character spanning two chunks being read whole.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::strings::Runs;
+///
+/// let input = b"\x7fELF\x02\x01\x00/lib/ld.so\x00\x01\x02ok\x00".as_slice();
+/// let runs: Vec<(u64, String)> = Runs::new(input, 3)
+///     .map(|run| run.unwrap())
+///     .map(|run| (run.offset, run.text))
+///     .collect();
+/// assert_eq!(runs, [(1, String::from("ELF")), (7, String::from("/lib/ld.so"))]);
+/// ```
+pub struct Runs<R> {
+    reader: R,
+    min: usize,
+    /// The bytes read and not yet looked at from `position` on, starting at
+    /// `base` in the input.
+    buffer: Vec<u8>,
+    base: u64,
+    position: usize,
+    /// The run being read, starting at `start` in the input, and how many
+    /// characters it has.
+    run: String,
+    start: u64,
+    chars: usize,
+    /// Whether `reader` has been read to the end, or has failed.
+    eof: bool,
+}
+
+impl<R: Read> Runs<R> {
+    /// Finds the runs of at least `min` printable characters in the bytes
+    /// of `reader`.
+    pub fn new(reader: R, min: usize) -> Runs<R> {
+        Runs {
+            reader,
+            min,
+            buffer: Vec::new(),
+            base: 0,
+            position: 0,
+            run: String::new(),
+            start: 0,
+            chars: 0,
+            eof: false,
+        }
+    }
+
+    /// Drops the bytes already looked at and reads the next chunk after the
+    /// rest.
+    fn fill(&mut self) -> io::Result<()> {
+        self.buffer.drain(..self.position);
+        self.base += self.position as u64;
+        self.position = 0;
+        let filled = self.buffer.len();
+        self.buffer.resize(filled + CHUNK, 0);
+        let read = loop {
+            match self.reader.read(&mut self.buffer[filled..]) {
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                read => break read,
+            }
+        };
+        match read {
+            Ok(read) => {
+                self.buffer.truncate(filled + read);
+                self.eof = read == 0;
+                Ok(())
+            }
+            Err(error) => {
+                self.buffer.truncate(filled);
+                self.eof = true;
+                Err(error)
+            }
+        }
+    }
+
+    /// Ends the run being read, returning it if it is long enough.
+    fn finish(&mut self) -> Option<Run> {
+        let chars = std::mem::take(&mut self.chars);
+        let text = std::mem::take(&mut self.run);
+        (chars >= self.min).then_some(Run {
+            offset: self.start,
+            text,
+        })
+    }
+}
+
+impl<R: Read> Iterator for Runs<R> {
+    type Item = io::Result<Run>;
+
+    fn next(&mut self) -> Option<io::Result<Run>> {
+        loop {
+            let left = self.buffer.len() - self.position;
+            let width = self
+                .buffer
+                .get(self.position)
+                .map_or(1, |&byte| width(byte));
+            if left < width && !self.eof {
+                if let Err(error) = self.fill() {
+                    return Some(Err(error));
+                }
+                continue;
+            }
+            if left == 0 {
+                return self.finish().map(Ok);
+            }
+            let bytes = self.buffer.get(self.position..self.position + width);
+            let printable = bytes
+                .and_then(|bytes| std::str::from_utf8(bytes).ok())
+                .and_then(|text| text.chars().next())
+                .filter(|&c| c == '\t' || !c.is_control());
+            match printable {
+                Some(c) => {
+                    if self.chars == 0 {
+                        self.start = self.base + self.position as u64;
+                    }
+                    self.run.push(c);
+                    self.chars += 1;
+                    self.position += width;
+                }
+                None => {
+                    self.position += 1;
+                    if let Some(run) = self.finish() {
+                        return Some(Ok(run));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The length of the UTF-8 character starting with `byte`, or one if it
+/// cannot start one.
+fn width(byte: u8) -> usize {
+    match byte {
+        0xc2..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf4 => 4,
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A reader handing out at most one byte at a time, so that every
+    /// character spans reads.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let Some((first, rest)) = self.0.split_first() else {
+                return Ok(0);
+            };
+            buf[0] = *first;
+            self.0 = rest;
+            Ok(1)
+        }
+    }
+
+    fn runs(input: impl Read, min: usize) -> Vec<(u64, String)> {
+        Runs::new(input, min)
+            .map(|run| run.unwrap())
+            .map(|run| (run.offset, run.text))
+            .collect()
+    }
+
+    fn texts(input: &[u8], min: usize) -> Vec<String> {
+        runs(input, min).into_iter().map(|(_, text)| text).collect()
+    }
+
+    #[test]
+    fn runs_shorter_than_the_minimum_are_left_out() {
+        let input = b"ab\x00abc\x00abcd\x01abcde";
+        assert_eq!(texts(input, 4), ["abcd", "abcde"]);
+        assert_eq!(texts(input, 3), ["abc", "abcd", "abcde"]);
+        assert_eq!(texts(input, 1).len(), 4);
+        assert!(texts(b"", 1).is_empty());
+        assert!(texts(b"\x00\x01\xff", 1).is_empty());
+    }
+
+    #[test]
+    fn offsets_are_of_the_first_byte_of_each_run() {
+        assert_eq!(
+            runs(b"\x00\x00hello\xffworld!".as_slice(), 4),
+            [(2, String::from("hello")), (8, String::from("world!"))]
+        );
+    }
+
+    #[test]
+    fn tabs_and_spaces_are_printable_but_newlines_are_not() {
+        assert_eq!(
+            texts(b"key =\tvalue\nnext line\r\n", 4),
+            ["key =\tvalue", "next line"]
+        );
+        assert_eq!(texts(b"abc\x7fdef", 3), ["abc", "def"]);
+    }
+
+    #[test]
+    fn utf8_characters_count_once_and_invalid_bytes_end_runs() {
+        let input = "\u{0}caf\u{e9} \u{2603}\u{0}".as_bytes();
+        assert_eq!(texts(input, 6), ["caf\u{e9} \u{2603}"]);
+        assert!(texts("\u{e9}\u{e9}\u{e9}".as_bytes(), 4).is_empty());
+        // A lone lead byte, and a truncated sequence at the end.
+        assert_eq!(texts(b"abcd\xc3efgh\xe2\x98", 4), ["abcd", "efgh"]);
+        // An encoded control character is not printable.
+        assert_eq!(texts(b"abcd\xc2\x85efgh", 4), ["abcd", "efgh"]);
+    }
+
+    #[test]
+    fn runs_and_characters_spanning_reads_are_read_whole() {
+        let input = "\u{1}\u{2}gr\u{fc}\u{df} dich\u{0}\u{1f600}\u{1f600}!!".as_bytes();
+        let expected = runs(input, 4);
+        assert_eq!(
+            expected,
+            [
+                (2, String::from("gr\u{fc}\u{df} dich")),
+                (14, String::from("\u{1f600}\u{1f600}!!"))
+            ]
+        );
+        assert_eq!(runs(Trickle(input), 4), expected);
+        let long = [b"\x00".as_slice(), &[b'x'; CHUNK + 10], b"\x00tail"].concat();
+        let found = runs(long.as_slice(), 4);
+        assert_eq!(found.len(), 2);
+        assert_eq!((found[0].0, found[0].1.len()), (1, CHUNK + 10));
+        assert_eq!(found[1], (CHUNK as u64 + 12, String::from("tail")));
+    }
+}
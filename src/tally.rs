@@ -0,0 +1,122 @@
+//! Counting the distinct strings matched, for `--unique-counts`, as
+//! `grep -o | sort | uniq -c | sort -rn` would.
+
+use std::collections::HashMap;
+
+/// How many times each distinct string was matched.
+///
+/// When case is ignored, strings are grouped by their lowercase forms and
+/// shown as they were first seen.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::tally::Tally;
+///
+/// let mut tally = Tally::new(true);
+/// for matched in ["Rust", "rust", "RUST", "go"] {
+///     tally.add(matched);
+/// }
+/// assert_eq!(tally.sorted(None), vec![("Rust", 3), ("go", 1)]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Tally {
+    ignore_case: bool,
+    /// The first form seen and the count of each string, by its key.
+    counts: HashMap<String, (String, u64)>,
+}
+
+impl Tally {
+    /// Creates an empty tally, grouping strings that differ only in case if
+    /// `ignore_case`.
+    pub fn new(ignore_case: bool) -> Tally {
+        Tally {
+            ignore_case,
+            counts: HashMap::new(),
+        }
+    }
+
+    /// Counts one more match of `matched`.
+    pub fn add(&mut self, matched: &str) {
+        self.add_count(matched, 1);
+    }
+
+    fn add_count(&mut self, matched: &str, count: u64) {
+        let key = if self.ignore_case {
+            matched.to_lowercase()
+        } else {
+            matched.to_string()
+        };
+        self.counts
+            .entry(key)
+            .or_insert_with(|| (matched.to_string(), 0))
+            .1 += count;
+    }
+
+    /// Adds the counts gathered in `other`, e.g. by another thread, which
+    /// are taken to have been seen after those of this tally.
+    pub fn merge(&mut self, other: Tally) {
+        for (shown, count) in other.counts.into_values() {
+            self.add_count(&shown, count);
+        }
+    }
+
+    /// Returns each distinct string with its count, most frequent first and
+    /// equally frequent ones in lexical order, keeping at most `top`.
+    pub fn sorted(&self, top: Option<usize>) -> Vec<(&str, u64)> {
+        let mut sorted: Vec<(&str, u64)> = self
+            .counts
+            .values()
+            .map(|(shown, count)| (shown.as_str(), *count))
+            .collect();
+        sorted.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        if let Some(top) = top {
+            sorted.truncate(top);
+        }
+        sorted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tally(ignore_case: bool, matches: &[&str]) -> Tally {
+        let mut tally = Tally::new(ignore_case);
+        for matched in matches {
+            tally.add(matched);
+        }
+        tally
+    }
+
+    #[test]
+    fn sorted_by_count_then_lexically() {
+        let tally = tally(false, &["b", "a", "c", "b", "c", "d", "c"]);
+        assert_eq!(
+            tally.sorted(None),
+            vec![("c", 3), ("b", 2), ("a", 1), ("d", 1)]
+        );
+        assert_eq!(tally.sorted(Some(2)), vec![("c", 3), ("b", 2)]);
+        assert_eq!(tally.sorted(Some(0)), vec![]);
+    }
+
+    #[test]
+    fn case_is_kept_apart_unless_ignored() {
+        let matches = ["Error", "ERROR", "error", "ERROR"];
+        assert_eq!(
+            tally(false, &matches).sorted(None),
+            vec![("ERROR", 2), ("Error", 1), ("error", 1)]
+        );
+        assert_eq!(tally(true, &matches).sorted(None), vec![("Error", 4)]);
+    }
+
+    #[test]
+    fn folded_groups_keep_the_first_casing_across_merges() {
+        let mut first = tally(true, &["Straße", "x"]);
+        let second = tally(true, &["STRASSE", "straße", "X", "X"]);
+        first.merge(second);
+        assert_eq!(
+            first.sorted(None),
+            vec![("x", 3), ("Straße", 2), ("STRASSE", 1)]
+        );
+    }
+}
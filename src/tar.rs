@@ -0,0 +1,474 @@
+//! Reading the members of tar archives as they stream past, so that
+//! bundles of files can be searched without unpacking them first.
+//!
+//! The ustar, pax, and GNU formats are read, including the long names the
+//! latter two store in entries of their own. A compressed archive, such as
+//! a `.tar.gz`, is read through its decoder first.
+
+use std::{
+    borrow::Cow,
+    io::{self, Read},
+    path::Path,
+};
+
+/// The size of a header, and the unit the data of members is padded to.
+const BLOCK: usize = 512;
+
+/// The longest name stored in an entry of its own that is read.
+const MAX_LONG_NAME: u64 = 1 << 20;
+
+/// The names archives are recognized by, compressed or not.
+const EXTENSIONS: &[&str] = &[
+    ".tar", ".tar.gz", ".tgz", ".tar.bz2", ".tbz", ".tbz2", ".tar.xz", ".txz",
+];
+
+/// Returns an error for an archive that stops before its end.
+fn truncated() -> io::Error {
+    io::Error::new(io::ErrorKind::UnexpectedEof, "unexpected end of tar data")
+}
+
+/// Returns an error for data that is not a valid tar archive.
+fn corrupt(reason: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("corrupt tar data: {reason}"),
+    )
+}
+
+/// Returns whether the file at `path` is a tar archive, compressed or not:
+/// from its name or, failing that, from its first bytes once decompressed,
+/// `start`.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::tar;
+/// use std::path::Path;
+///
+/// assert!(tar::is_tar(Path::new("logs.tar"), b""));
+/// assert!(tar::is_tar(Path::new("logs.TAR.GZ"), b""));
+/// assert!(!tar::is_tar(Path::new("logs.gz"), b""));
+/// ```
+pub fn is_tar(path: &Path, start: &[u8]) -> bool {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    EXTENSIONS.iter().any(|extension| name.ends_with(extension))
+        || start.get(257..262) == Some(b"ustar")
+}
+
+/// What kind of file a member of an archive is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    File,
+    Directory,
+    HardLink,
+    Symlink,
+    /// A device, a FIFO, or an entry of a type not known.
+    Other,
+}
+
+/// A member of a tar archive, as its header describes it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Member {
+    name: Vec<u8>,
+    kind: Kind,
+    size: u64,
+}
+
+impl Member {
+    /// The member's path within the archive, with any bytes that are not
+    /// UTF-8 replaced by U+FFFD.
+    pub fn name(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.name)
+    }
+
+    /// What kind of file the member is.
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    /// The size of the member's data.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+/// A tar archive read from `R`, whose members are read one after another.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::tar::{Kind, TarArchive};
+/// use std::io::Read;
+///
+/// let mut bytes = vec![0; 2048];
+/// bytes[..5].copy_from_slice(b"a.log");
+/// bytes[124..135].copy_from_slice(b"00000000005");
+/// bytes[148..156].copy_from_slice(b"0002426\0");
+/// bytes[156] = b'0';
+/// bytes[512..517].copy_from_slice(b"okay\n");
+///
+/// let mut archive = TarArchive::new(bytes.as_slice());
+/// let member = archive.next_member().unwrap().unwrap();
+/// assert_eq!((member.name(), member.kind()), ("a.log".into(), Kind::File));
+/// let mut text = String::new();
+/// archive.data().read_to_string(&mut text).unwrap();
+/// assert_eq!(text, "okay\n");
+/// assert!(archive.next_member().unwrap().is_none());
+/// ```
+#[derive(Debug)]
+pub struct TarArchive<R> {
+    reader: R,
+    /// How many bytes of the current member's data are left to read, and
+    /// how many bytes of padding follow them.
+    left: u64,
+    padding: u64,
+    done: bool,
+}
+
+impl<R: Read> TarArchive<R> {
+    /// Creates an archive reading its members from `reader`.
+    pub fn new(reader: R) -> TarArchive<R> {
+        TarArchive {
+            reader,
+            left: 0,
+            padding: 0,
+            done: false,
+        }
+    }
+
+    /// Reads the header of the next member, skipping whatever is left of
+    /// the current one, or returns `None` at the end of the archive.
+    ///
+    /// # Errors
+    /// Returns an error if reading fails, or one of kind
+    /// [`InvalidData`](io::ErrorKind::InvalidData) if the data is not a
+    /// tar archive or is corrupt.
+    pub fn next_member(&mut self) -> io::Result<Option<Member>> {
+        let mut long_name = None;
+        let mut pax_size = None;
+        loop {
+            self.skip(self.left + self.padding)?;
+            self.left = 0;
+            self.padding = 0;
+            if self.done {
+                return Ok(None);
+            }
+            let mut header = [0; BLOCK];
+            if !self.read_block(&mut header)? || header.iter().all(|&byte| byte == 0) {
+                self.done = true;
+                return Ok(None);
+            }
+            check_sum(&header)?;
+
+            let kind = header[156];
+            let size = match pax_size.take() {
+                Some(size) => size,
+                None => number(&header[124..136])?,
+            };
+            self.left = match kind {
+                b'1' | b'2' => 0,
+                _ => size,
+            };
+            self.padding = (BLOCK as u64 - self.left % BLOCK as u64) % BLOCK as u64;
+            match kind {
+                b'L' => {
+                    let mut name = self.read_long(size)?;
+                    name.truncate(
+                        name.iter()
+                            .position(|&byte| byte == 0)
+                            .unwrap_or(name.len()),
+                    );
+                    long_name = Some(name);
+                    continue;
+                }
+                b'x' => {
+                    let records = self.read_long(size)?;
+                    for (key, value) in pax_records(&records)? {
+                        match key {
+                            b"path" => long_name = Some(value.to_vec()),
+                            b"size" => {
+                                let size = std::str::from_utf8(value).ok();
+                                let size = size.and_then(|size| size.parse().ok());
+                                pax_size = Some(size.ok_or_else(|| corrupt("bad pax size"))?);
+                            }
+                            _ => {}
+                        }
+                    }
+                    continue;
+                }
+                b'g' | b'K' => continue,
+                _ => {}
+            }
+
+            let name = long_name.take().unwrap_or_else(|| header_name(&header));
+            let kind = match kind {
+                b'0' | b'\0' | b'7' if name.ends_with(b"/") => Kind::Directory,
+                b'0' | b'\0' | b'7' => Kind::File,
+                b'1' => Kind::HardLink,
+                b'2' => Kind::Symlink,
+                b'5' => Kind::Directory,
+                _ => Kind::Other,
+            };
+            return Ok(Some(Member { name, kind, size }));
+        }
+    }
+
+    /// A reader of the data of the member last returned by
+    /// [`TarArchive::next_member`].
+    ///
+    /// Reading returns an error of kind
+    /// [`UnexpectedEof`](io::ErrorKind::UnexpectedEof) if the archive ends
+    /// before the data does.
+    pub fn data(&mut self) -> impl Read + '_ {
+        Data { archive: self }
+    }
+
+    /// Reads a whole block, or returns `false` if the archive ends before
+    /// it starts.
+    fn read_block(&mut self, block: &mut [u8; BLOCK]) -> io::Result<bool> {
+        let mut filled = 0;
+        while filled < BLOCK {
+            match self.reader.read(&mut block[filled..]) {
+                Ok(0) if filled == 0 => return Ok(false),
+                Ok(0) => return Err(truncated()),
+                Ok(n) => filled += n,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(true)
+    }
+
+    /// Reads the data of an entry holding a long name or pax records.
+    fn read_long(&mut self, size: u64) -> io::Result<Vec<u8>> {
+        if size > MAX_LONG_NAME {
+            return Err(corrupt("extended header too long"));
+        }
+        let mut data = Vec::new();
+        self.data().read_to_end(&mut data)?;
+        Ok(data)
+    }
+
+    /// Skips `n` bytes of the archive.
+    fn skip(&mut self, n: u64) -> io::Result<()> {
+        if io::copy(&mut (&mut self.reader).take(n), &mut io::sink())? < n {
+            return Err(truncated());
+        }
+        Ok(())
+    }
+}
+
+/// The data of a member of a [`TarArchive`], as it is read.
+struct Data<'a, R> {
+    archive: &'a mut TarArchive<R>,
+}
+
+impl<R: Read> Read for Data<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let archive = &mut *self.archive;
+        let len = buf
+            .len()
+            .min(usize::try_from(archive.left).unwrap_or(usize::MAX));
+        if len == 0 {
+            return Ok(0);
+        }
+        let n = archive.reader.read(&mut buf[..len])?;
+        if n == 0 {
+            return Err(truncated());
+        }
+        archive.left -= n as u64;
+        Ok(n)
+    }
+}
+
+/// Checks the checksum of `header`, the sum of its bytes with those of the
+/// checksum itself taken as spaces.
+fn check_sum(header: &[u8; BLOCK]) -> io::Result<()> {
+    let expected = number(&header[148..156])?;
+    let field = 148..156;
+    let (mut unsigned, mut signed) = (0u64, 0i64);
+    for (i, &byte) in header.iter().enumerate() {
+        let byte = if field.contains(&i) { b' ' } else { byte };
+        unsigned += u64::from(byte);
+        signed += i64::from(byte as i8);
+    }
+    // Some old archivers summed the bytes as signed.
+    if expected != unsigned && i64::try_from(expected) != Ok(signed) {
+        return Err(corrupt("checksum mismatch"));
+    }
+    Ok(())
+}
+
+/// Parses a numeric field of a header: octal digits padded with spaces or
+/// NULs or, for numbers too large for them, base-256 after a set high bit.
+fn number(field: &[u8]) -> io::Result<u64> {
+    if field[0] & 0x80 != 0 {
+        let mut number = u64::from(field[0] & 0x7f);
+        for &byte in &field[1..] {
+            number = number
+                .checked_mul(256)
+                .ok_or_else(|| corrupt("number too large"))?
+                | u64::from(byte);
+        }
+        return Ok(number);
+    }
+    let digits = field
+        .iter()
+        .skip_while(|&&byte| byte == b' ')
+        .take_while(|&&byte| byte != b' ' && byte != 0);
+    let mut number = 0u64;
+    for &digit in digits {
+        if !(b'0'..=b'7').contains(&digit) {
+            return Err(corrupt("bad number"));
+        }
+        number = (number << 3) | u64::from(digit - b'0');
+    }
+    Ok(number)
+}
+
+/// The name of the member `header` describes, joined to the prefix ustar
+/// archives keep for long paths.
+fn header_name(header: &[u8; BLOCK]) -> Vec<u8> {
+    let field = |range: std::ops::Range<usize>| {
+        let field = &header[range];
+        &field[..field
+            .iter()
+            .position(|&byte| byte == 0)
+            .unwrap_or(field.len())]
+    };
+    let name = field(0..100);
+    let prefix = field(345..500);
+    if &header[257..263] != b"ustar\0" || prefix.is_empty() {
+        return name.to_vec();
+    }
+    [prefix, b"/", name].concat()
+}
+
+/// Splits the records of a pax extended header, each `"<len> <key>=<value>\n"`,
+/// into their keys and values.
+fn pax_records(mut records: &[u8]) -> io::Result<Vec<(&[u8], &[u8])>> {
+    let mut pairs = Vec::new();
+    while !records.is_empty() {
+        let bad = || corrupt("bad pax record");
+        let space = records
+            .iter()
+            .position(|&byte| byte == b' ')
+            .ok_or_else(bad)?;
+        let len: usize = std::str::from_utf8(&records[..space])
+            .ok()
+            .and_then(|len| len.parse().ok())
+            .ok_or_else(bad)?;
+        let record = records.get(space + 1..len).ok_or_else(bad)?;
+        let record = record.strip_suffix(b"\n").ok_or_else(bad)?;
+        let equals = record
+            .iter()
+            .position(|&byte| byte == b'=')
+            .ok_or_else(bad)?;
+        pairs.push((&record[..equals], &record[equals + 1..]));
+        records = &records[len..];
+    }
+    Ok(pairs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds the header of a member called `name`, of type `kind`, with
+    /// `size` bytes of data.
+    fn header(name: &[u8], kind: u8, size: usize) -> Vec<u8> {
+        let mut header = vec![0; BLOCK];
+        header[..name.len()].copy_from_slice(name);
+        header[124..135].copy_from_slice(format!("{size:011o}").as_bytes());
+        header[156] = kind;
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[148..156].fill(b' ');
+        let sum: u32 = header.iter().map(|&byte| u32::from(byte)).sum();
+        header[148..156].copy_from_slice(format!("{sum:06o}\0 ").as_bytes());
+        header
+    }
+
+    /// Builds an archive of `(name, kind, data)` members.
+    fn archive(members: &[(&[u8], u8, &[u8])]) -> Vec<u8> {
+        let mut archive = Vec::new();
+        for &(name, kind, data) in members {
+            archive.extend_from_slice(&header(name, kind, data.len()));
+            archive.extend_from_slice(data);
+            archive.resize(archive.len().next_multiple_of(BLOCK), 0);
+        }
+        archive.extend_from_slice(&[0; 2 * BLOCK]);
+        archive
+    }
+
+    /// Reads every member of `archive`, with the data of those read.
+    fn members(archive: &[u8], read: bool) -> io::Result<Vec<(String, Kind, String)>> {
+        let mut archive = TarArchive::new(archive);
+        let mut members = Vec::new();
+        while let Some(member) = archive.next_member()? {
+            let mut data = String::new();
+            if read {
+                archive.data().read_to_string(&mut data)?;
+            }
+            members.push((member.name().into_owned(), member.kind(), data));
+        }
+        Ok(members)
+    }
+
+    #[test]
+    fn members_of_each_kind() {
+        let bytes = archive(&[
+            (b"logs/", b'5', b""),
+            (b"logs/a.log", b'0', b"first\n"),
+            (b"logs/link", b'2', b""),
+            (b"logs/b.log", b'0', &[b'x'; 600]),
+        ]);
+        for read in [true, false] {
+            let members = members(&bytes, read).unwrap();
+            let kinds: Vec<_> = members
+                .iter()
+                .map(|(name, kind, _)| (&**name, *kind))
+                .collect();
+            assert_eq!(
+                kinds,
+                [
+                    ("logs/", Kind::Directory),
+                    ("logs/a.log", Kind::File),
+                    ("logs/link", Kind::Symlink),
+                    ("logs/b.log", Kind::File),
+                ]
+            );
+            if read {
+                assert_eq!(members[1].2, "first\n");
+                assert_eq!(members[3].2.len(), 600);
+            }
+        }
+    }
+
+    #[test]
+    fn long_names() {
+        let long = "d/".repeat(80) + "end.log";
+        let pax = format!("{} path={long}\n", long.len() + 10);
+        let bytes = archive(&[
+            (b"././@LongLink", b'L', long.as_bytes()),
+            (b"short", b'0', b"gnu\n"),
+            (b"PaxHeaders/x", b'x', pax.as_bytes()),
+            (b"short", b'0', b"pax\n"),
+        ]);
+        let members = members(&bytes, true).unwrap();
+        assert_eq!(members[0], (long.clone(), Kind::File, "gnu\n".into()));
+        assert_eq!(members[1], (long, Kind::File, "pax\n".into()));
+    }
+
+    #[test]
+    fn corrupt_archives_are_errors() {
+        let mut bytes = archive(&[(b"a.log", b'0', b"some text\n")]);
+        bytes[0] ^= 1;
+        let error = members(&bytes, true).unwrap_err();
+        assert_eq!(error.to_string(), "corrupt tar data: checksum mismatch");
+
+        let bytes = archive(&[(b"a.log", b'0', b"some text\n")]);
+        let error = members(&bytes[..BLOCK + 4], true).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
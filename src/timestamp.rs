@@ -0,0 +1,490 @@
+//! The timestamps starting log lines, which `--since` and `--until`
+//! compare against a window of time.
+//!
+//! Two forms are recognized, optionally after a `[`:
+//!
+//! - ISO 8601: a date, `2024-01-02`, optionally followed by `T` or a space
+//!   and a time, `14:00`, `14:00:05`, or `14:00:05.123`, and then
+//!   optionally by a zone, `Z`, `+01:00`, `+0100`, or `+01`.
+//! - syslog: `Jan  2 14:00:05`, with the day padded by a space or not.
+//!
+//! Timestamps are compared as instants. One without a zone is taken to be
+//! in UTC, rather than in the local zone, so that a search gives the same
+//! lines on every machine; and a syslog timestamp, which has no year, is
+//! taken to be in the year the window was given in, the current one.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// An instant, as the seconds and nanoseconds since the Unix epoch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Timestamp {
+    seconds: i64,
+    nanos: u32,
+}
+
+impl Timestamp {
+    /// The seconds since the Unix epoch, rounded down.
+    pub fn seconds(self) -> i64 {
+        self.seconds
+    }
+}
+
+/// What becomes of a line that does not start with a timestamp, as chosen
+/// with `--no-timestamp`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Untimed {
+    /// The line is searched as if it were in the window.
+    Keep,
+    /// The line is left out, as if it were outside the window.
+    Drop,
+}
+
+impl Untimed {
+    /// Parses the value of `--no-timestamp`.
+    pub fn parse(value: &str) -> Option<Untimed> {
+        match value {
+            "keep" => Some(Untimed::Keep),
+            "drop" => Some(Untimed::Drop),
+            _ => None,
+        }
+    }
+}
+
+/// The window of time of `--since` and `--until`, which only lines
+/// starting with a timestamp inside it are searched in.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::timestamp::{TimeWindow, Untimed, parse};
+///
+/// let window = TimeWindow {
+///     since: Some(parse("2024-01-02T14:00", 2024).unwrap()),
+///     until: Some(parse("2024-01-02T14:10", 2024).unwrap()),
+///     untimed: Untimed::Drop,
+///     year: 2024,
+/// };
+/// assert!(window.contains("2024-01-02T14:05:00Z error: disk full"));
+/// assert!(window.contains("Jan  2 14:00:00 host app: error"));
+/// assert!(!window.contains("2024-01-02 14:10:00 error: too late"));
+/// assert!(!window.contains("  at main.rs:3"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeWindow {
+    /// The first instant of the window, if it has one.
+    pub since: Option<Timestamp>,
+    /// The first instant after the window, if it has one.
+    pub until: Option<Timestamp>,
+    /// What becomes of lines without a timestamp.
+    pub untimed: Untimed,
+    /// The year syslog timestamps are taken to be in.
+    pub year: i64,
+}
+
+impl TimeWindow {
+    /// Returns whether `line` is searched: if it starts with a timestamp,
+    /// whether that is at or after `since` and before `until`, and
+    /// otherwise as `untimed` says.
+    pub fn contains(&self, line: &str) -> bool {
+        match parse_prefix(line, self.year) {
+            Some(timestamp) => {
+                self.since.is_none_or(|since| timestamp >= since)
+                    && self.until.is_none_or(|until| timestamp < until)
+            }
+            None => self.untimed == Untimed::Keep,
+        }
+    }
+}
+
+/// Parses `text`, all of it, as a timestamp, taking one in the syslog form
+/// to be in `year`.
+///
+/// Returns `None` if `text`, leading and trailing whitespace aside, is not
+/// a timestamp of a recognized form, or names a date or time that does not
+/// exist, such as February 30th or 24:00.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::timestamp::parse;
+///
+/// let utc = parse("2024-01-02T14:00:00", 2000).unwrap();
+/// assert_eq!(parse("2024-01-02 15:00+01:00", 2000), Some(utc));
+/// assert_eq!(parse("Jan  2 14:00:00", 2024), Some(utc));
+/// assert_eq!(parse("2024-02-30", 2024), None);
+/// ```
+pub fn parse(text: &str, year: i64) -> Option<Timestamp> {
+    let text = text.trim();
+    let (timestamp, length) = scan(text, year)?;
+    (length == text.len()).then_some(timestamp)
+}
+
+/// Returns the timestamp `line` starts with, if any, taking one in the
+/// syslog form to be in `year`.
+///
+/// The timestamp may be enclosed in brackets, and must not run into a
+/// letter or digit, so that `2024-01-02` starts `2024-01-02: ok` but not
+/// `2024-01-023`.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::timestamp::{parse, parse_prefix};
+///
+/// let timestamp = parse_prefix("[2024-01-02 14:00:00] ready", 2024);
+/// assert_eq!(timestamp, parse("2024-01-02T14:00:00Z", 2024));
+/// assert_eq!(parse_prefix("ready at 2024-01-02", 2024), None);
+/// ```
+pub fn parse_prefix(line: &str, year: i64) -> Option<Timestamp> {
+    let text = line.strip_prefix('[').unwrap_or(line);
+    let (timestamp, length) = scan(text, year)?;
+    match text[length..].chars().next() {
+        Some(next) if next.is_alphanumeric() => None,
+        _ => Some(timestamp),
+    }
+}
+
+/// The year it is now, in UTC, which syslog timestamps are taken to be in.
+pub fn current_year() -> i64 {
+    let seconds = match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(since) => since.as_secs() as i64,
+        Err(before) => -(before.duration().as_secs() as i64),
+    };
+    civil_year(seconds.div_euclid(86_400))
+}
+
+/// Reads the timestamp at the start of `text`, returning it with its
+/// length in bytes.
+fn scan(text: &str, year: i64) -> Option<(Timestamp, usize)> {
+    let mut cursor = Cursor {
+        bytes: text.as_bytes(),
+        position: 0,
+    };
+    let timestamp = if cursor.peek().is_some_and(|byte| byte.is_ascii_digit()) {
+        cursor.iso()?
+    } else {
+        cursor.syslog(year)?
+    };
+    Some((timestamp, cursor.position))
+}
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// The bytes of a timestamp and how far they have been read.
+struct Cursor<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Cursor<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.position).copied()
+    }
+
+    /// Reads `byte` if it comes next.
+    fn eat(&mut self, byte: u8) -> bool {
+        let next = self.peek() == Some(byte);
+        if next {
+            self.position += 1;
+        }
+        next
+    }
+
+    /// Reads exactly `count` digits as a number.
+    fn number(&mut self, count: usize) -> Option<u32> {
+        let digits = self.bytes.get(self.position..self.position + count)?;
+        if !digits.iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        self.position += count;
+        Some(
+            digits
+                .iter()
+                .fold(0, |number, digit| number * 10 + u32::from(digit - b'0')),
+        )
+    }
+
+    /// Whether a time, `HH:`, starts `offset` bytes ahead.
+    fn time_ahead(&self, offset: usize) -> bool {
+        let ahead = self
+            .bytes
+            .get(self.position + offset..self.position + offset + 3);
+        matches!(ahead, Some([a, b, b':']) if a.is_ascii_digit() && b.is_ascii_digit())
+    }
+
+    /// Reads an ISO 8601 timestamp.
+    fn iso(&mut self) -> Option<Timestamp> {
+        let year = self.number(4)?;
+        let month = self.eat(b'-').then(|| self.number(2))??;
+        let day = self.eat(b'-').then(|| self.number(2))??;
+        let days = days_from_civil(i64::from(year), month, day)?;
+        if !matches!(self.peek(), Some(b'T' | b' ')) || !self.time_ahead(1) {
+            return Some(Timestamp {
+                seconds: days * 86_400,
+                nanos: 0,
+            });
+        }
+        self.position += 1;
+        let (seconds, nanos) = self.time(true)?;
+        let offset = self.zone()?;
+        Some(Timestamp {
+            seconds: days * 86_400 + seconds - offset,
+            nanos,
+        })
+    }
+
+    /// Reads a syslog timestamp, taking it to be in `year`.
+    fn syslog(&mut self, year: i64) -> Option<Timestamp> {
+        let name = self.bytes.get(self.position..self.position + 3)?;
+        let month = MONTHS.iter().position(|month| month.as_bytes() == name)? as u32 + 1;
+        self.position += 3;
+        if !self.eat(b' ') {
+            return None;
+        }
+        self.eat(b' ');
+        let day = match self.number(2) {
+            Some(day) => day,
+            None => self.number(1)?,
+        };
+        if !self.eat(b' ') {
+            return None;
+        }
+        let days = days_from_civil(year, month, day)?;
+        let (seconds, nanos) = self.time(false)?;
+        Some(Timestamp {
+            seconds: days * 86_400 + seconds,
+            nanos,
+        })
+    }
+
+    /// Reads `HH:MM:SS`, with the seconds optional if `optional_seconds`,
+    /// and any fraction of a second after them, returning the seconds into
+    /// the day and the nanoseconds.
+    fn time(&mut self, optional_seconds: bool) -> Option<(i64, u32)> {
+        let hour = self.number(2)?;
+        let minute = self.eat(b':').then(|| self.number(2))??;
+        let second = if self.peek() == Some(b':') || !optional_seconds {
+            self.eat(b':').then(|| self.number(2))??
+        } else {
+            0
+        };
+        if hour > 23 || minute > 59 || second > 59 {
+            return None;
+        }
+        let mut nanos = 0;
+        if matches!(self.peek(), Some(b'.' | b','))
+            && self
+                .bytes
+                .get(self.position + 1)
+                .is_some_and(u8::is_ascii_digit)
+        {
+            self.position += 1;
+            let mut scale = 100_000_000;
+            while let Some(digit) = self.peek().filter(u8::is_ascii_digit) {
+                nanos += u32::from(digit - b'0') * scale;
+                scale /= 10;
+                self.position += 1;
+            }
+        }
+        let seconds = i64::from(hour) * 3600 + i64::from(minute) * 60 + i64::from(second);
+        Some((seconds, nanos))
+    }
+
+    /// Reads the zone after an ISO 8601 time, if any, returning how many
+    /// seconds it is ahead of UTC: none without a zone.
+    fn zone(&mut self) -> Option<i64> {
+        let sign = match self.peek() {
+            Some(b'Z') => {
+                self.position += 1;
+                return Some(0);
+            }
+            Some(b'+') => 1,
+            Some(b'-') => -1,
+            _ => return Some(0),
+        };
+        self.position += 1;
+        let hours = self.number(2)?;
+        let minutes = if self.eat(b':') {
+            self.number(2)?
+        } else {
+            self.number(2).unwrap_or(0)
+        };
+        if hours > 23 || minutes > 59 {
+            return None;
+        }
+        Some(sign * (i64::from(hours) * 3600 + i64::from(minutes) * 60))
+    }
+}
+
+/// The number of days in `month` of `year`.
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// The days from the Unix epoch to the date given, if it exists.
+fn days_from_civil(year: i64, month: u32, day: u32) -> Option<i64> {
+    if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+        return None;
+    }
+    // Counting from March, so that the leap day ends the year.
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month_from_march = i64::from((month + 9) % 12);
+    let day_of_year = (153 * month_from_march + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    Some(era * 146_097 + day_of_era - 719_468)
+}
+
+/// The year of the day `days` after the Unix epoch.
+fn civil_year(days: i64) -> i64 {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let year = era * 400 + year_of_era;
+    if month_from_march < 10 {
+        year
+    } else {
+        year + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The timestamp of a UTC date and time, for comparison.
+    fn utc(year: i64, month: u32, day: u32, hour: i64, minute: i64, second: i64) -> Timestamp {
+        let days = days_from_civil(year, month, day).unwrap();
+        Timestamp {
+            seconds: days * 86_400 + hour * 3600 + minute * 60 + second,
+            nanos: 0,
+        }
+    }
+
+    #[test]
+    fn civil_dates_count_days_from_the_epoch() {
+        assert_eq!(days_from_civil(1970, 1, 1), Some(0));
+        assert_eq!(days_from_civil(2000, 3, 1), Some(11_017));
+        assert_eq!(days_from_civil(1969, 12, 31), Some(-1));
+        assert_eq!(days_from_civil(2024, 2, 29), Some(19_782));
+        assert_eq!(days_from_civil(2023, 2, 29), None);
+        assert_eq!(days_from_civil(1900, 2, 29), None);
+        assert_eq!(days_from_civil(2024, 13, 1), None);
+        assert_eq!(days_from_civil(2024, 4, 31), None);
+        for (days, year) in [(0, 1970), (-1, 1969), (11_016, 2000), (19_782, 2024)] {
+            assert_eq!(civil_year(days), year, "{days}");
+        }
+        assert_eq!(civil_year(days_from_civil(2024, 12, 31).unwrap()), 2024);
+        assert_eq!(civil_year(days_from_civil(2025, 1, 1).unwrap()), 2025);
+    }
+
+    #[test]
+    fn iso_forms_are_recognized() {
+        let at = utc(2024, 1, 2, 14, 0, 5);
+        for text in [
+            "2024-01-02T14:00:05",
+            "2024-01-02 14:00:05",
+            "2024-01-02T14:00:05Z",
+            "2024-01-02T15:00:05+01:00",
+            "2024-01-02T15:30:05+0130",
+            "2024-01-02T13:00:05-01",
+        ] {
+            assert_eq!(parse(text, 1999), Some(at), "{text}");
+        }
+        assert_eq!(parse("2024-01-02", 1999), Some(utc(2024, 1, 2, 0, 0, 0)));
+        assert_eq!(
+            parse("2024-01-02T14:00", 1999),
+            Some(utc(2024, 1, 2, 14, 0, 0))
+        );
+        let fraction = parse("2024-01-02T14:00:05.25Z", 1999).unwrap();
+        assert_eq!(fraction.seconds(), at.seconds());
+        assert!(fraction > at && fraction < utc(2024, 1, 2, 14, 0, 6));
+        assert_eq!(parse("2024-01-02T14:00:05,25", 1999), Some(fraction));
+    }
+
+    #[test]
+    fn syslog_timestamps_take_the_year_given() {
+        let at = utc(2024, 3, 5, 9, 7, 1);
+        assert_eq!(parse("Mar  5 09:07:01", 2024), Some(at));
+        assert_eq!(parse("Mar 5 09:07:01", 2024), Some(at));
+        assert_eq!(parse("Mar 05 09:07:01", 2024), Some(at));
+        assert!(parse("Mar  5 09:07:01", 2025).unwrap() > at);
+        assert!(parse("Feb 29 00:00:00", 2023).is_none());
+        assert!(parse("mar  5 09:07:01", 2024).is_none());
+        assert!(parse("Mar  5 09:07", 2024).is_none());
+    }
+
+    #[test]
+    fn impossible_and_unknown_timestamps_are_rejected() {
+        for text in [
+            "",
+            "today",
+            "2024-1-02",
+            "2024-01-02T24:00",
+            "2024-01-02T14:60",
+            "2024-01-02T14:00:61",
+            "2024-02-30",
+            "2024-01-02T14:00+25:00",
+            "2024-01-02T14:00 extra",
+            "14:00",
+        ] {
+            assert_eq!(parse(text, 2024), None, "{text}");
+        }
+        assert_eq!(parse("  2024-01-02 ", 2024), parse("2024-01-02", 2024));
+    }
+
+    #[test]
+    fn line_prefixes_stop_at_the_timestamp() {
+        let at = Some(utc(2024, 1, 2, 14, 0, 0));
+        assert_eq!(parse_prefix("2024-01-02T14:00:00Z error", 2024), at);
+        assert_eq!(parse_prefix("2024-01-02 14:00:00,000 INFO", 2024), at);
+        assert_eq!(parse_prefix("[2024-01-02T14:00:00] ok", 2024), at);
+        assert_eq!(parse_prefix("Jan  2 14:00:00 host sshd[1]: ok", 2024), at);
+        assert_eq!(
+            parse_prefix("2024-01-02 booted", 2024),
+            Some(utc(2024, 1, 2, 0, 0, 0))
+        );
+        assert_eq!(parse_prefix("2024-01-023", 2024), None);
+        assert_eq!(parse_prefix("2024-01-02T14:00:00Zulu", 2024), None);
+        assert_eq!(parse_prefix(" 2024-01-02", 2024), None);
+        assert_eq!(parse_prefix("  at main.rs:3", 2024), None);
+    }
+
+    #[test]
+    fn windows_include_their_start_but_not_their_end() {
+        let window = |since: Option<&str>, until: Option<&str>, untimed| TimeWindow {
+            since: since.map(|since| parse(since, 2024).unwrap()),
+            until: until.map(|until| parse(until, 2024).unwrap()),
+            untimed,
+            year: 2024,
+        };
+        let both = window(
+            Some("2024-01-02T14:00"),
+            Some("2024-01-02T14:10"),
+            Untimed::Drop,
+        );
+        assert!(both.contains("2024-01-02T14:00:00 a"));
+        assert!(both.contains("2024-01-02T14:09:59.999 a"));
+        assert!(!both.contains("2024-01-02T14:10:00 a"));
+        assert!(!both.contains("2024-01-02T13:59:59 a"));
+        assert!(both.contains("2024-01-02T15:05:00+01:00 a"));
+        assert!(!both.contains("continued"));
+
+        let since = window(Some("Jan  2 14:00:00"), None, Untimed::Keep);
+        assert!(since.contains("2030-01-01 a"));
+        assert!(!since.contains("Jan  2 13:59:59 a"));
+        assert!(since.contains("continued"));
+        let until = window(None, Some("2024-01-02"), Untimed::Drop);
+        assert!(until.contains("2024-01-01T23:59:59 a"));
+        assert!(!until.contains("2024-01-02T00:00:00 a"));
+    }
+}
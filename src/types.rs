@@ -0,0 +1,120 @@
+//! The built-in table of file types selected with `--type` and `--type-not`.
+
+/// Each file type's name, along with the globs matching the names of its
+/// files, in order of the type names.
+pub const FILE_TYPES: &[(&str, &[&str])] = &[
+    ("c", &["*.c", "*.h"]),
+    ("cpp", &["*.cc", "*.cpp", "*.cxx", "*.hh", "*.hpp", "*.hxx"]),
+    ("go", &["*.go"]),
+    ("java", &["*.java"]),
+    ("js", &["*.js", "*.cjs", "*.mjs", "*.jsx"]),
+    ("json", &["*.json"]),
+    ("md", &["*.md", "*.markdown"]),
+    ("py", &["*.py", "*.pyi"]),
+    ("rust", &["*.rs"]),
+    ("toml", &["*.toml"]),
+    ("ts", &["*.ts", "*.cts", "*.mts", "*.tsx"]),
+    ("yaml", &["*.yaml", "*.yml"]),
+];
+
+/// Returns the globs of the file type called `name`.
+///
+/// # Errors
+/// Returns an error if there is no such type, suggesting the names of
+/// similar ones: those sharing a prefix with `name`, or only a few typos
+/// away from it.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::types::globs;
+///
+/// assert_eq!(globs("rust"), Ok(&["*.rs"][..]));
+/// assert_eq!(
+///     globs("rst").unwrap_err(),
+///     "Unknown file type 'rst'; did you mean 'rust'?"
+/// );
+/// ```
+pub fn globs(name: &str) -> Result<&'static [&'static str], String> {
+    if let Some((_, globs)) = FILE_TYPES.iter().find(|(type_name, _)| *type_name == name) {
+        return Ok(globs);
+    }
+
+    let suggestions: Vec<String> = FILE_TYPES
+        .iter()
+        .map(|(type_name, _)| *type_name)
+        .filter(|type_name| {
+            type_name.starts_with(name)
+                || name.starts_with(type_name)
+                || edit_distance(name, type_name) <= name.chars().count() / 3
+        })
+        .map(|type_name| format!("'{type_name}'"))
+        .collect();
+    match suggestions.as_slice() {
+        [] => Err(format!(
+            "Unknown file type '{name}'; see --type-list for the known types"
+        )),
+        [suggestion] => Err(format!(
+            "Unknown file type '{name}'; did you mean {suggestion}?"
+        )),
+        _ => Err(format!(
+            "Unknown file type '{name}'; did you mean one of {}?",
+            suggestions.join(", ")
+        )),
+    }
+}
+
+/// The number of single-character insertions, deletions, and substitutions
+/// turning `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::glob::Glob;
+
+    #[test]
+    fn table_is_sorted_and_valid() {
+        let names: Vec<&str> = FILE_TYPES.iter().map(|(name, _)| *name).collect();
+        let mut sorted = names.clone();
+        sorted.sort();
+        assert_eq!(names, sorted);
+        for (_, globs) in FILE_TYPES {
+            for glob in *globs {
+                assert!(Glob::new(glob).is_ok(), "{glob}");
+            }
+        }
+    }
+
+    #[test]
+    fn suggestions_for_unknown_types() {
+        assert_eq!(
+            globs("pyton").unwrap_err(),
+            "Unknown file type 'pyton'; did you mean 'py'?"
+        );
+        assert_eq!(
+            globs("j").unwrap_err(),
+            "Unknown file type 'j'; did you mean one of 'java', 'js', 'json'?"
+        );
+        assert!(globs("haskell").unwrap_err().contains("--type-list"));
+    }
+
+    #[test]
+    fn edit_distances() {
+        assert_eq!(edit_distance("rust", "rust"), 0);
+        assert_eq!(edit_distance("rst", "rust"), 1);
+        assert_eq!(edit_distance("", "go"), 2);
+        assert_eq!(edit_distance("yml", "yaml"), 1);
+    }
+}
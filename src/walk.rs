@@ -0,0 +1,1251 @@
+//! Expanding the paths given on the command line into the files to search.
+
+use std::{
+    collections::HashSet,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use crate::{error::MinigrepError, glob::Glob, ignore::Ignore, log};
+
+/// The ignore files `-r` reads in each directory when
+/// [`WalkOptions::ignore`] is set, in increasing order of precedence.
+pub const IGNORE_FILES: &[&str] = &[".gitignore", ".ignore"];
+
+/// The tool's own ignore file, read in each directory when
+/// [`WalkOptions::custom_ignore`] is set. It takes precedence over the
+/// [`IGNORE_FILES`], as `.rgignore` does in ripgrep.
+pub const CUSTOM_IGNORE_FILE: &str = ".minigrepignore";
+
+/// Controls which files [`walk`] yields.
+///
+/// - `recursive`: If `true`, directories are descended into; otherwise
+///   every path is yielded as a file to search.
+/// - `max_filesize`: Files larger than this many bytes are skipped, whether
+///   found during recursion or named directly.
+/// - `min_filesize`: Files smaller than this many bytes are skipped, in the
+///   same way.
+/// - `modified_after`, `modified_before`: Files last modified before the
+///   first or after the second of these times are skipped, in the same way;
+///   files whose modification time cannot be read are kept.
+/// - `max_depth`: How many directory levels below each root to descend;
+///   `0` only yields the roots themselves, `1` also their immediate children.
+/// - `include`: If not empty, only files whose names match one of these
+///   globs are yielded.
+/// - `exclude`: Files whose names match one of these globs are not yielded.
+/// - `exclude_dir`: Directories whose names match one of these globs are not
+///   descended into.
+/// - `hidden`: If `true`, hidden files and directories are walked too;
+///   otherwise they are skipped.
+/// - `follow`: If `true`, symbolic links found during recursion are followed;
+///   otherwise they are skipped. Each directory is descended into at most
+///   once, so that links pointing back up the tree cannot loop forever.
+/// - `ignore`: If `true`, paths listed in the [`IGNORE_FILES`] of the
+///   directories walked are skipped; see the [`ignore`](crate::ignore)
+///   module for the syntax. The files of deeper directories take precedence
+///   over those of their parents. Ignore files above the roots are not read.
+/// - `read_devices`: If `true`, devices, FIFOs, and sockets named directly
+///   are yielded to be read; otherwise they are skipped, as those found
+///   during recursion always are, since reading one may never end.
+/// - `custom_ignore`: If `true`, the [`CUSTOM_IGNORE_FILE`] of each directory
+///   walked is read in the same way, whether or not `ignore` is set.
+/// - `dedupe`: If `true`, a file found during recursion that was already
+///   yielded under another path, through a hard link or, with `follow`, a
+///   symbolic link, is skipped, so that each file is searched once, under
+///   the first path it was found by.
+/// - `one_file_system`: If `true`, directories on another device than the
+///   root, such as the mount points of other file systems, are not
+///   descended into, as with `find -xdev`. Devices are only told apart on
+///   Unix; elsewhere this has no effect.
+///
+/// The globs, hiding, and ignore files only apply to what is found during recursion; the
+/// roots themselves are always walked.
+#[derive(Debug, Clone, Default)]
+pub struct WalkOptions {
+    pub recursive: bool,
+    pub max_filesize: Option<u64>,
+    pub min_filesize: Option<u64>,
+    pub modified_after: Option<SystemTime>,
+    pub modified_before: Option<SystemTime>,
+    pub max_depth: Option<usize>,
+    pub include: Vec<Glob>,
+    pub exclude: Vec<Glob>,
+    pub exclude_dir: Vec<Glob>,
+    pub hidden: bool,
+    pub follow: bool,
+    pub ignore: bool,
+    pub custom_ignore: bool,
+    pub dedupe: bool,
+    pub one_file_system: bool,
+    pub read_devices: bool,
+}
+
+impl WalkOptions {
+    /// Returns whether any file is skipped for its size or modification
+    /// time, which must then be read even when not recursing.
+    fn filters_metadata(&self) -> bool {
+        self.max_filesize.is_some()
+            || self.min_filesize.is_some()
+            || self.modified_after.is_some()
+            || self.modified_before.is_some()
+    }
+
+    /// Returns why the file with `metadata` is skipped for its size or
+    /// modification time, if it is.
+    fn filtered_out(&self, metadata: &fs::Metadata) -> Option<SkipReason> {
+        let len = metadata.len();
+        if self.max_filesize.is_some_and(|max| len > max) {
+            return Some(SkipReason::TooLarge);
+        }
+        if self.min_filesize.is_some_and(|min| len < min) {
+            return Some(SkipReason::TooSmall);
+        }
+        let modified = metadata.modified().ok()?;
+        if self.modified_after.is_some_and(|after| modified < after) {
+            return Some(SkipReason::TooOld);
+        }
+        if self.modified_before.is_some_and(|before| modified > before) {
+            return Some(SkipReason::TooNew);
+        }
+        None
+    }
+
+    /// Returns why a file or directory found during recursion is passed
+    /// over, if it is: it is hidden, unless `hidden` is set, or it does not
+    /// pass the include and exclude globs.
+    fn passed_over(&self, path: &Path, metadata: &fs::Metadata) -> Option<&'static str> {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if !self.hidden && is_hidden(&name, metadata) {
+            return Some("hidden");
+        }
+        let any_match = |globs: &[Glob]| globs.iter().any(|glob| glob.is_match(&name));
+        if metadata.is_dir() {
+            any_match(&self.exclude_dir).then_some("matched by an excluded directory glob")
+        } else if !self.include.is_empty() && !any_match(&self.include) {
+            Some("matched by no included glob")
+        } else {
+            any_match(&self.exclude).then_some("matched by an excluded glob")
+        }
+    }
+}
+
+/// Returns whether the file called `name` is hidden: its name starts with a
+/// `.` or, on Windows, it has the hidden attribute.
+fn is_hidden(name: &str, metadata: &fs::Metadata) -> bool {
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::MetadataExt;
+
+        const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+        if metadata.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0 {
+            return true;
+        }
+    }
+    #[cfg(not(windows))]
+    let _ = metadata;
+    name.starts_with('.')
+}
+
+/// Returns whether the file with `metadata` is a device, FIFO, or socket
+/// rather than a regular file. Only these are told apart on Unix, so that
+/// elsewhere no file is one.
+fn is_device(metadata: &fs::Metadata) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+
+        let file_type = metadata.file_type();
+        file_type.is_block_device()
+            || file_type.is_char_device()
+            || file_type.is_fifo()
+            || file_type.is_socket()
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = metadata;
+        false
+    }
+}
+
+/// Why [`walk`] passed over a file instead of yielding it for searching.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    /// The file is larger than `max_filesize`.
+    TooLarge,
+    /// The file is smaller than `min_filesize`.
+    TooSmall,
+    /// The file was last modified before `modified_after`.
+    TooOld,
+    /// The file was last modified after `modified_before`.
+    TooNew,
+    /// With `follow`, a symbolic link whose target does not exist.
+    BrokenLink,
+    /// With `follow`, a directory that was already walked, reached again
+    /// through a symbolic link.
+    Cycle,
+    /// With `ignore` or `custom_ignore`, a file or directory listed in an
+    /// ignore file.
+    Ignored,
+    /// With `dedupe`, a file that was already yielded under another path.
+    Duplicate,
+    /// A device, FIFO, or socket, found during recursion or, unless
+    /// `read_devices`, named directly.
+    Device,
+    /// A file with a NUL byte in the first block read of it, found once it
+    /// is opened rather than by [`walk`], unless records end with NUL.
+    Binary,
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SkipReason::TooLarge => "larger than the maximum file size",
+            SkipReason::TooSmall => "smaller than the minimum file size",
+            SkipReason::TooOld => "modified before the oldest time allowed",
+            SkipReason::TooNew => "modified after the newest time allowed",
+            SkipReason::BrokenLink => "broken symbolic link",
+            SkipReason::Cycle => "symbolic link cycle, already searched",
+            SkipReason::Ignored => "listed in an ignore file",
+            SkipReason::Duplicate => "already searched through another path",
+            SkipReason::Device => "device, FIFO, or socket",
+            SkipReason::Binary => "binary file",
+        })
+    }
+}
+
+/// A single result of walking a path.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Walked {
+    /// A file that should be searched.
+    File(PathBuf),
+    /// A file that was skipped, along with the reason.
+    Skipped(PathBuf, SkipReason),
+}
+
+impl std::error::Error for SkipReason {}
+
+/// The error reading the metadata or entries of the path at `path`.
+fn walk_error(path: &Path, error: io::Error) -> MinigrepError {
+    MinigrepError::new(&path.to_string_lossy(), error)
+}
+
+/// One of the inputs that the paths given on the command line stand for,
+/// as [`resolve_inputs`] yields them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Input {
+    /// The standard input, named by `-`.
+    Stdin,
+    /// A file that should be searched.
+    File(PathBuf),
+    /// A file that was skipped, along with the reason.
+    Skipped(PathBuf, SkipReason),
+}
+
+/// Expands `args`, the paths given on the command line, into the inputs to
+/// search, in order: `-` is the standard input, and every other path is
+/// walked with `options`. On Windows, where the shell leaves wildcards to
+/// the program, each path is first expanded as a [glob](crate::glob).
+///
+/// Without [`WalkOptions::recursive`], a path that does not exist or that
+/// names a directory is an error, a [`MinigrepError::NotFound`] or
+/// [`MinigrepError::IsDirectory`] one.
+///
+/// # Examples
+/// ```no_run
+/// use minigrep_cli_tool::walk::{Input, WalkOptions, resolve_inputs};
+/// use std::path::PathBuf;
+///
+/// let args = [PathBuf::from("src"), PathBuf::from("-")];
+/// let options = WalkOptions { recursive: true, ..WalkOptions::default() };
+/// for input in resolve_inputs(&args, &options) {
+///     match input {
+///         Ok(Input::Stdin) => println!("<stdin>"),
+///         Ok(Input::File(path)) => println!("{}", path.display()),
+///         Ok(Input::Skipped(..)) => {}
+///         Err(error) => eprintln!("{error}"),
+///     }
+/// }
+/// ```
+pub fn resolve_inputs<'a>(
+    args: &'a [PathBuf],
+    options: &'a WalkOptions,
+) -> impl Iterator<Item = Result<Input, MinigrepError>> + 'a {
+    // One walk serves every path, so what it has seen under one is known
+    // under the next.
+    let mut walk = walk_with(Vec::new(), options, device);
+    let mut paths = args.iter().flat_map(|arg| expand(arg));
+    std::iter::from_fn(move || {
+        loop {
+            if let Some(walked) = walk.next() {
+                return Some(walked.map(|walked| match walked {
+                    Walked::File(path) => Input::File(path),
+                    Walked::Skipped(path, reason) => Input::Skipped(path, reason),
+                }));
+            }
+            let path = paths.next()?;
+            if path.as_os_str() == "-" {
+                return Some(Ok(Input::Stdin));
+            }
+            if !options.recursive
+                && let Some(error) = named_error(&path)
+            {
+                return Some(Err(error));
+            }
+            walk.pending.push((path, 0));
+        }
+    })
+}
+
+/// The paths `arg` stands for on the command line: those its glob matches
+/// on Windows, and `arg` itself elsewhere.
+pub(crate) fn expand(arg: &Path) -> Vec<PathBuf> {
+    #[cfg(windows)]
+    {
+        crate::glob::expand(arg)
+    }
+    #[cfg(not(windows))]
+    {
+        vec![arg.to_path_buf()]
+    }
+}
+
+/// Returns why the path named at `path` cannot be searched without `-r`, if
+/// it cannot: it does not exist, or it is a directory.
+fn named_error(path: &Path) -> Option<MinigrepError> {
+    match fs::metadata(path) {
+        Ok(metadata) if metadata.is_dir() => {
+            Some(MinigrepError::is_directory(&path.to_string_lossy()))
+        }
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Some(walk_error(path, error)),
+        _ => None,
+    }
+}
+
+/// Walks `root`, yielding the files to search.
+///
+/// Directory entries are visited in order of their names, so the output of a
+/// recursive search is the same on every run. Sizes are checked from the
+/// metadata, before any file is opened.
+///
+/// # Examples
+/// ```no_run
+/// use minigrep_cli_tool::walk::{walk, WalkOptions, Walked};
+/// use std::path::Path;
+///
+/// let options = WalkOptions { recursive: true, ..WalkOptions::default() };
+/// for entry in walk(Path::new("src"), &options) {
+///     if let Ok(Walked::File(path)) = entry {
+///         println!("{}", path.display());
+///     }
+/// }
+/// ```
+pub fn walk<'a>(root: &Path, options: &'a WalkOptions) -> Walk<'a> {
+    walk_with(vec![root.to_path_buf()], options, device)
+}
+
+/// Walks each of `roots` in turn like [`walk`], telling the device of each
+/// path from its metadata with `device`.
+fn walk_with<'a>(roots: Vec<PathBuf>, options: &'a WalkOptions, device: DeviceOf) -> Walk<'a> {
+    Walk {
+        options,
+        pending: roots.into_iter().rev().map(|root| (root, 0)).collect(),
+        visited: HashSet::new(),
+        yielded: HashSet::new(),
+        ignores: Vec::new(),
+        device,
+        root_device: None,
+    }
+}
+
+/// Tells the device the file or directory at a path is on from its
+/// metadata, if devices can be told apart.
+type DeviceOf = fn(&Path, &fs::Metadata) -> Option<u64>;
+
+/// Returns the device the file or directory with `metadata` is on, on Unix.
+fn device(_path: &Path, metadata: &fs::Metadata) -> Option<u64> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        Some(metadata.dev())
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = metadata;
+        None
+    }
+}
+
+/// What identifies a file or directory however it is reached: its device
+/// and inode on Unix, and its canonical path elsewhere.
+#[cfg(unix)]
+type FileId = (u64, u64);
+#[cfg(not(unix))]
+type FileId = PathBuf;
+
+/// Returns the [`FileId`] of the file or directory at `path`.
+#[cfg(unix)]
+fn file_id(_path: &Path, metadata: &fs::Metadata) -> io::Result<FileId> {
+    use std::os::unix::fs::MetadataExt;
+
+    Ok((metadata.dev(), metadata.ino()))
+}
+
+/// Returns the [`FileId`] of the file or directory at `path`.
+#[cfg(not(unix))]
+fn file_id(path: &Path, _metadata: &fs::Metadata) -> io::Result<FileId> {
+    fs::canonicalize(path)
+}
+
+/// The iterator returned by [`walk`].
+pub struct Walk<'a> {
+    options: &'a WalkOptions,
+    /// The paths still to visit, each with its depth below the root.
+    pending: Vec<(PathBuf, usize)>,
+    /// With `follow`, the directories descended into so far.
+    visited: HashSet<FileId>,
+    /// With `dedupe`, the files yielded so far.
+    yielded: HashSet<FileId>,
+    /// The ignore files read from the directories above the next
+    /// pending path, each with the depth of its directory.
+    ignores: Vec<(usize, Ignore)>,
+    device: DeviceOf,
+    /// With `one_file_system`, the device the root is on.
+    root_device: Option<u64>,
+}
+
+impl Walk<'_> {
+    /// Queues the entries of the directory at `path`, found at `depth`, to
+    /// be visited in order of their names, and reads the directory's ignore
+    /// files.
+    fn queue_children(&mut self, path: &Path, depth: usize) -> Result<(), MinigrepError> {
+        let mut children = fs::read_dir(path)
+            .and_then(|entries| {
+                entries
+                    .map(|entry| entry.map(|entry| entry.path()))
+                    .collect::<io::Result<Vec<PathBuf>>>()
+            })
+            .map_err(|error| walk_error(path, error))?;
+        children.sort();
+        self.pending
+            .extend(children.into_iter().rev().map(|child| (child, depth + 1)));
+
+        let names = IGNORE_FILES
+            .iter()
+            .filter(|_| self.options.ignore)
+            .chain(Some(&CUSTOM_IGNORE_FILE).filter(|_| self.options.custom_ignore));
+        for name in names {
+            let file = path.join(name);
+            match fs::read_to_string(&file) {
+                Ok(contents) => self.ignores.push((depth, Ignore::parse(path, &contents))),
+                Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+                Err(error) => return Err(walk_error(&file, error)),
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns whether the path found during recursion is listed in the
+    /// ignore files above it.
+    fn is_ignored(&self, path: &Path, metadata: &fs::Metadata) -> bool {
+        self.ignores
+            .iter()
+            .rev()
+            .find_map(|(_, ignore)| ignore.matched(path, metadata.is_dir()))
+            .unwrap_or(false)
+    }
+}
+
+impl Iterator for Walk<'_> {
+    type Item = Result<Walked, MinigrepError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((path, depth)) = self.pending.pop() {
+            if !self.options.recursive && !self.options.filters_metadata() {
+                // Whatever cannot be read is left to fail when searched.
+                if !self.options.read_devices
+                    && fs::metadata(&path).is_ok_and(|metadata| is_device(&metadata))
+                {
+                    return Some(Ok(skipped(path, SkipReason::Device)));
+                }
+                return Some(Ok(Walked::File(path)));
+            }
+            // Drop the ignore files of directories this path is not inside.
+            while self
+                .ignores
+                .last()
+                .is_some_and(|(dir_depth, _)| *dir_depth >= depth)
+            {
+                self.ignores.pop();
+            }
+
+            // Links named on the command line are always followed.
+            let metadata = if depth > 0 && !self.options.follow {
+                fs::symlink_metadata(&path)
+            } else {
+                fs::metadata(&path)
+            };
+            let metadata = match metadata {
+                Ok(metadata) => metadata,
+                Err(_) if depth > 0 && path.is_symlink() => {
+                    return Some(Ok(skipped(path, SkipReason::BrokenLink)));
+                }
+                Err(error) => return Some(Err(walk_error(&path, error))),
+            };
+            if metadata.is_symlink() {
+                passed_over(&path, "symbolic link, not followed");
+                continue;
+            }
+            if depth > 0
+                && let Some(reason) = self.options.passed_over(&path, &metadata)
+            {
+                passed_over(&path, reason);
+                continue;
+            }
+            if depth > 0 && self.is_ignored(&path, &metadata) {
+                return Some(Ok(skipped(path, SkipReason::Ignored)));
+            }
+            if metadata.is_dir() && self.options.recursive {
+                if self.options.one_file_system {
+                    let device = (self.device)(&path, &metadata);
+                    if depth == 0 {
+                        self.root_device = device;
+                    } else if device != self.root_device {
+                        passed_over(&path, "on another file system");
+                        continue;
+                    }
+                }
+                if self.options.follow {
+                    match file_id(&path, &metadata) {
+                        Ok(id) if !self.visited.insert(id) => {
+                            return Some(Ok(skipped(path, SkipReason::Cycle)));
+                        }
+                        Ok(_) => {}
+                        Err(error) => return Some(Err(walk_error(&path, error))),
+                    }
+                }
+                let within_depth = self.options.max_depth.is_none_or(|max| depth < max);
+                if within_depth && let Err(error) = self.queue_children(&path, depth) {
+                    return Some(Err(error));
+                }
+                continue;
+            }
+            if is_device(&metadata) && (depth > 0 || !self.options.read_devices) {
+                return Some(Ok(skipped(path, SkipReason::Device)));
+            }
+            if let Some(reason) = self.options.filtered_out(&metadata) {
+                return Some(Ok(skipped(path, reason)));
+            }
+            if self.options.recursive && self.options.dedupe {
+                match file_id(&path, &metadata) {
+                    Ok(id) if !self.yielded.insert(id) => {
+                        return Some(Ok(skipped(path, SkipReason::Duplicate)));
+                    }
+                    Ok(_) => {}
+                    Err(error) => return Some(Err(walk_error(&path, error))),
+                }
+            }
+            return Some(Ok(Walked::File(path)));
+        }
+        None
+    }
+}
+
+/// Logs that `path` is skipped for `reason`, and returns it as skipped.
+fn skipped(path: PathBuf, reason: SkipReason) -> Walked {
+    passed_over(&path, reason);
+    Walked::Skipped(path, reason)
+}
+
+/// Logs that `path` is passed over for `reason`.
+fn passed_over(path: &Path, reason: impl fmt::Display) {
+    log::debug(
+        "walk",
+        format_args!("skipping {}: {reason}", path.display()),
+    );
+}
+
+/// Parses a file size such as `512`, `100K`, `10M`, or `1G`.
+///
+/// Suffixes are powers of 1024 and may be written in either case.
+///
+/// # Errors
+/// Returns an error if the number is missing or invalid, the suffix is
+/// unknown, or the size does not fit in a `u64`.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::walk::parse_size;
+///
+/// assert_eq!(parse_size("512"), Ok(512));
+/// assert_eq!(parse_size("2K"), Ok(2048));
+/// assert!(parse_size("2X").is_err());
+/// ```
+pub fn parse_size(size: &str) -> Result<u64, String> {
+    let (digits, multiplier) = match size.char_indices().last() {
+        Some((index, 'k' | 'K')) => (&size[..index], 1 << 10),
+        Some((index, 'm' | 'M')) => (&size[..index], 1 << 20),
+        Some((index, 'g' | 'G')) => (&size[..index], 1 << 30),
+        _ => (size, 1),
+    };
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(format!("Invalid size '{size}'"));
+    }
+    digits
+        .parse::<u64>()
+        .ok()
+        .and_then(|value| value.checked_mul(multiplier))
+        .ok_or_else(|| format!("Size '{size}' is too large"))
+}
+
+/// Parses a duration such as `90s`, `15m`, `36h`, or `2d`; a bare number
+/// is a number of seconds.
+///
+/// # Errors
+/// Returns an error if the number is missing or invalid, the suffix is
+/// unknown, or the duration is too long to represent.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::walk::parse_duration;
+/// use std::time::Duration;
+///
+/// assert_eq!(parse_duration("36h"), Ok(Duration::from_secs(36 * 60 * 60)));
+/// assert!(parse_duration("2w").is_err());
+/// ```
+pub fn parse_duration(duration: &str) -> Result<Duration, String> {
+    let (digits, multiplier) = match duration.char_indices().last() {
+        Some((index, 's')) => (&duration[..index], 1),
+        Some((index, 'm')) => (&duration[..index], 60),
+        Some((index, 'h')) => (&duration[..index], 60 * 60),
+        Some((index, 'd')) => (&duration[..index], 24 * 60 * 60),
+        _ => (duration, 1),
+    };
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(format!("Invalid duration '{duration}'"));
+    }
+    digits
+        .parse::<u64>()
+        .ok()
+        .and_then(|value| value.checked_mul(multiplier))
+        .map(Duration::from_secs)
+        .ok_or_else(|| format!("Duration '{duration}' is too long"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    /// Creates an empty directory for a test, removing any earlier copy.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("minigrep-walk-{name}"));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn files(root: &Path, options: &WalkOptions) -> Vec<Walked> {
+        walk(root, options).map(Result::unwrap).collect()
+    }
+
+    #[test]
+    fn parse_size_bare_bytes_and_suffixes() {
+        assert_eq!(parse_size("0"), Ok(0));
+        assert_eq!(parse_size("1234"), Ok(1234));
+        assert_eq!(parse_size("1k"), Ok(1024));
+        assert_eq!(parse_size("3M"), Ok(3 * 1024 * 1024));
+        assert_eq!(parse_size("2g"), Ok(2 * 1024 * 1024 * 1024));
+    }
+
+    #[test]
+    fn parse_size_rejects_invalid_input() {
+        for size in ["", "K", "-1", "1.5M", "10KB", "ten", " 10", "1T"] {
+            assert!(parse_size(size).is_err(), "{size:?} should be rejected");
+        }
+        assert!(parse_size("99999999999999999999").is_err());
+        assert!(parse_size("17179869184G").is_err());
+    }
+
+    #[test]
+    fn parse_duration_suffixes() {
+        assert_eq!(parse_duration("0"), Ok(Duration::ZERO));
+        assert_eq!(parse_duration("45"), Ok(Duration::from_secs(45)));
+        assert_eq!(parse_duration("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("15m"), Ok(Duration::from_secs(900)));
+        assert_eq!(parse_duration("36h"), Ok(Duration::from_secs(129_600)));
+        assert_eq!(parse_duration("2d"), Ok(Duration::from_secs(172_800)));
+    }
+
+    #[test]
+    fn parse_duration_rejects_invalid_input() {
+        for duration in [
+            "",
+            "d",
+            "-1d",
+            "1.5h",
+            "2w",
+            "10 m",
+            "1D",
+            "99999999999999999999d",
+        ] {
+            assert!(
+                parse_duration(duration).is_err(),
+                "{duration:?} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn walks_directories_in_name_order() {
+        let dir = temp_dir("order");
+        fs::create_dir(dir.join("b")).unwrap();
+        fs::write(dir.join("b/inner.txt"), "x").unwrap();
+        fs::write(dir.join("c.txt"), "x").unwrap();
+        fs::write(dir.join("a.txt"), "x").unwrap();
+
+        let options = WalkOptions {
+            recursive: true,
+            ..WalkOptions::default()
+        };
+        assert_eq!(
+            files(&dir, &options),
+            vec![
+                Walked::File(dir.join("a.txt")),
+                Walked::File(dir.join("b/inner.txt")),
+                Walked::File(dir.join("c.txt")),
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_files_over_max_filesize() {
+        let dir = temp_dir("max-filesize");
+        fs::write(dir.join("big.txt"), "x".repeat(11)).unwrap();
+        fs::write(dir.join("small.txt"), "x".repeat(10)).unwrap();
+
+        let options = WalkOptions {
+            recursive: true,
+            max_filesize: Some(10),
+            ..WalkOptions::default()
+        };
+        assert_eq!(
+            files(&dir, &options),
+            vec![
+                Walked::Skipped(dir.join("big.txt"), SkipReason::TooLarge),
+                Walked::File(dir.join("small.txt")),
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_files_by_minimum_size_and_modification_time() {
+        let dir = temp_dir("metadata-filters");
+        fs::write(dir.join("empty.txt"), "").unwrap();
+        fs::write(dir.join("old.txt"), "x").unwrap();
+        fs::write(dir.join("new.txt"), "x").unwrap();
+        let now = SystemTime::now();
+        let day = Duration::from_secs(24 * 60 * 60);
+        fs::File::options()
+            .write(true)
+            .open(dir.join("old.txt"))
+            .unwrap()
+            .set_modified(now - 3 * day)
+            .unwrap();
+
+        let mut options = WalkOptions {
+            recursive: true,
+            min_filesize: Some(1),
+            modified_after: Some(now - day),
+            ..WalkOptions::default()
+        };
+        assert_eq!(
+            files(&dir, &options),
+            vec![
+                Walked::Skipped(dir.join("empty.txt"), SkipReason::TooSmall),
+                Walked::File(dir.join("new.txt")),
+                Walked::Skipped(dir.join("old.txt"), SkipReason::TooOld),
+            ]
+        );
+
+        options.modified_after = None;
+        options.modified_before = Some(now - day);
+        let old = dir.join("old.txt");
+        let new = dir.join("new.txt");
+        assert_eq!(files(&old, &options), vec![Walked::File(old.clone())]);
+        assert_eq!(
+            files(&new, &options),
+            vec![Walked::Skipped(new.clone(), SkipReason::TooNew)]
+        );
+    }
+
+    #[test]
+    fn max_depth_cuts_off_exactly() {
+        let dir = temp_dir("max-depth");
+        fs::create_dir_all(dir.join("one/two/three")).unwrap();
+        fs::write(dir.join("top.txt"), "x").unwrap();
+        fs::write(dir.join("one/first.txt"), "x").unwrap();
+        fs::write(dir.join("one/two/second.txt"), "x").unwrap();
+        fs::write(dir.join("one/two/three/third.txt"), "x").unwrap();
+
+        let found = |max_depth| {
+            let options = WalkOptions {
+                recursive: true,
+                max_depth: Some(max_depth),
+                ..WalkOptions::default()
+            };
+            files(&dir, &options)
+                .into_iter()
+                .map(|walked| match walked {
+                    Walked::File(path) => path.strip_prefix(&dir).unwrap().to_path_buf(),
+                    Walked::Skipped(path, _) => panic!("unexpected skip of {path:?}"),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(found(0), Vec::<PathBuf>::new());
+        assert_eq!(found(1), vec![PathBuf::from("top.txt")]);
+        assert_eq!(
+            found(2),
+            vec![PathBuf::from("one/first.txt"), PathBuf::from("top.txt")]
+        );
+        assert_eq!(found(3).len(), 3);
+        assert_eq!(found(4).len(), 4);
+
+        let file = dir.join("top.txt");
+        let options = WalkOptions {
+            recursive: true,
+            max_depth: Some(0),
+            ..WalkOptions::default()
+        };
+        assert_eq!(files(&file, &options), vec![Walked::File(file.clone())]);
+    }
+
+    #[test]
+    fn include_and_exclude_globs() {
+        let dir = temp_dir("globs");
+        fs::create_dir_all(dir.join("src/target")).unwrap();
+        fs::create_dir_all(dir.join("target")).unwrap();
+        fs::write(dir.join("src/main.rs"), "x").unwrap();
+        fs::write(dir.join("src/main_test.rs"), "x").unwrap();
+        fs::write(dir.join("src/notes.md"), "x").unwrap();
+        fs::write(dir.join("src/target/gen.rs"), "x").unwrap();
+        fs::write(dir.join("target/out.rs"), "x").unwrap();
+
+        let globs = |patterns: &[&str]| {
+            patterns
+                .iter()
+                .map(|pattern| Glob::new(pattern).unwrap())
+                .collect::<Vec<_>>()
+        };
+        let options = WalkOptions {
+            recursive: true,
+            include: globs(&["*.rs", "*.toml"]),
+            exclude: globs(&["*_test.rs"]),
+            exclude_dir: globs(&["target"]),
+            ..WalkOptions::default()
+        };
+        assert_eq!(
+            files(&dir, &options),
+            vec![Walked::File(dir.join("src/main.rs"))]
+        );
+
+        let notes = dir.join("src/notes.md");
+        assert_eq!(files(&notes, &options), vec![Walked::File(notes.clone())]);
+        let target = dir.join("target");
+        assert_eq!(
+            files(&target, &options),
+            vec![Walked::File(target.join("out.rs"))]
+        );
+    }
+
+    #[test]
+    fn hidden_entries_are_skipped_unless_asked_for() {
+        let dir = temp_dir("hidden");
+        fs::create_dir_all(dir.join(".git/objects")).unwrap();
+        fs::create_dir(dir.join("src")).unwrap();
+        fs::write(dir.join(".git/objects/pack"), "x").unwrap();
+        fs::write(dir.join(".env"), "x").unwrap();
+        fs::write(dir.join("src/.hidden.rs"), "x").unwrap();
+        fs::write(dir.join("src/main.rs"), "x").unwrap();
+
+        let mut options = WalkOptions {
+            recursive: true,
+            ..WalkOptions::default()
+        };
+        assert_eq!(
+            files(&dir, &options),
+            vec![Walked::File(dir.join("src/main.rs"))]
+        );
+
+        let git = dir.join(".git");
+        assert_eq!(
+            files(&git, &options),
+            vec![Walked::File(git.join("objects/pack"))]
+        );
+
+        options.hidden = true;
+        assert_eq!(files(&dir, &options).len(), 4);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symbolic_links_are_followed_only_when_asked() {
+        use std::os::unix::fs::symlink;
+
+        let dir = temp_dir("follow");
+        fs::create_dir_all(dir.join("real")).unwrap();
+        fs::write(dir.join("real/file.txt"), "x").unwrap();
+        symlink(dir.join("real"), dir.join("linked")).unwrap();
+        symlink(&dir, dir.join("real/loop")).unwrap();
+        symlink(dir.join("missing"), dir.join("broken")).unwrap();
+
+        let mut options = WalkOptions {
+            recursive: true,
+            ..WalkOptions::default()
+        };
+        assert_eq!(
+            files(&dir, &options),
+            vec![Walked::File(dir.join("real/file.txt"))]
+        );
+
+        options.follow = true;
+        assert_eq!(
+            files(&dir, &options),
+            vec![
+                Walked::Skipped(dir.join("broken"), SkipReason::BrokenLink),
+                Walked::File(dir.join("linked/file.txt")),
+                Walked::Skipped(dir.join("linked/loop"), SkipReason::Cycle),
+                Walked::Skipped(dir.join("real"), SkipReason::Cycle),
+            ]
+        );
+
+        options.follow = false;
+        let linked = dir.join("linked");
+        assert_eq!(
+            files(&linked, &options),
+            vec![Walked::File(linked.join("file.txt"))]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn files_reached_twice_are_yielded_once() {
+        use std::os::unix::fs::symlink;
+
+        let dir = temp_dir("dedupe");
+        fs::write(dir.join("a.txt"), "x").unwrap();
+        fs::hard_link(dir.join("a.txt"), dir.join("b.txt")).unwrap();
+        symlink(dir.join("a.txt"), dir.join("c.txt")).unwrap();
+        fs::write(dir.join("d.txt"), "x").unwrap();
+
+        let mut options = WalkOptions {
+            recursive: true,
+            follow: true,
+            dedupe: true,
+            ..WalkOptions::default()
+        };
+        assert_eq!(
+            files(&dir, &options),
+            vec![
+                Walked::File(dir.join("a.txt")),
+                Walked::Skipped(dir.join("b.txt"), SkipReason::Duplicate),
+                Walked::Skipped(dir.join("c.txt"), SkipReason::Duplicate),
+                Walked::File(dir.join("d.txt")),
+            ]
+        );
+
+        options.dedupe = false;
+        assert_eq!(files(&dir, &options).len(), 4);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn files_reached_under_two_roots_are_yielded_once() {
+        let dir = temp_dir("dedupe-roots");
+        fs::create_dir_all(dir.join("x")).unwrap();
+        fs::create_dir_all(dir.join("y")).unwrap();
+        fs::write(dir.join("x/a.txt"), "x").unwrap();
+        fs::hard_link(dir.join("x/a.txt"), dir.join("y/b.txt")).unwrap();
+
+        let options = WalkOptions {
+            recursive: true,
+            dedupe: true,
+            ..WalkOptions::default()
+        };
+        let args = [dir.join("x"), dir.join("y")];
+        assert_eq!(
+            resolve_inputs(&args, &options)
+                .map(Result::unwrap)
+                .collect::<Vec<_>>(),
+            vec![
+                Input::File(dir.join("x/a.txt")),
+                Input::Skipped(dir.join("y/b.txt"), SkipReason::Duplicate),
+            ]
+        );
+    }
+
+    #[test]
+    fn one_file_system_stays_on_the_root_device() {
+        let dir = temp_dir("one-file-system");
+        fs::create_dir_all(dir.join("mnt/nested")).unwrap();
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join("mnt/remote.txt"), "x").unwrap();
+        fs::write(dir.join("mnt/nested/deep.txt"), "x").unwrap();
+        fs::write(dir.join("src/local.txt"), "x").unwrap();
+        fs::write(dir.join("top.txt"), "x").unwrap();
+
+        // Everything under a directory called `mnt` is on another device.
+        fn device(path: &Path, _metadata: &fs::Metadata) -> Option<u64> {
+            Some(if path.iter().any(|part| part == "mnt") {
+                2
+            } else {
+                1
+            })
+        }
+        let found = |root: &Path, options: &WalkOptions| {
+            walk_with(vec![root.to_path_buf()], options, device)
+                .map(Result::unwrap)
+                .collect::<Vec<_>>()
+        };
+
+        let mut options = WalkOptions {
+            recursive: true,
+            one_file_system: true,
+            ..WalkOptions::default()
+        };
+        assert_eq!(
+            found(&dir, &options),
+            vec![
+                Walked::File(dir.join("src/local.txt")),
+                Walked::File(dir.join("top.txt")),
+            ]
+        );
+        let mnt = dir.join("mnt");
+        assert_eq!(
+            found(&mnt, &options),
+            vec![
+                Walked::File(mnt.join("nested/deep.txt")),
+                Walked::File(mnt.join("remote.txt")),
+            ]
+        );
+
+        options.one_file_system = false;
+        assert_eq!(found(&dir, &options).len(), 4);
+    }
+
+    #[test]
+    fn ignore_files_nest_and_override() {
+        let dir = temp_dir("ignore");
+        fs::create_dir_all(dir.join("src/gen")).unwrap();
+        fs::create_dir_all(dir.join("target")).unwrap();
+        fs::write(dir.join(".gitignore"), "target/\n*.log\n").unwrap();
+        fs::write(dir.join(".ignore"), "!keep.log\n").unwrap();
+        fs::write(dir.join("src/.gitignore"), "gen/\n!debug.log\n").unwrap();
+        fs::write(dir.join("target/out.rs"), "x").unwrap();
+        fs::write(dir.join("build.log"), "x").unwrap();
+        fs::write(dir.join("keep.log"), "x").unwrap();
+        fs::write(dir.join("src/main.rs"), "x").unwrap();
+        fs::write(dir.join("src/debug.log"), "x").unwrap();
+        fs::write(dir.join("src/gen/code.rs"), "x").unwrap();
+        fs::write(dir.join("gen"), "x").unwrap();
+
+        let mut options = WalkOptions {
+            recursive: true,
+            ignore: true,
+            ..WalkOptions::default()
+        };
+        assert_eq!(
+            files(&dir, &options),
+            vec![
+                Walked::Skipped(dir.join("build.log"), SkipReason::Ignored),
+                Walked::File(dir.join("gen")),
+                Walked::File(dir.join("keep.log")),
+                Walked::File(dir.join("src/debug.log")),
+                Walked::Skipped(dir.join("src/gen"), SkipReason::Ignored),
+                Walked::File(dir.join("src/main.rs")),
+                Walked::Skipped(dir.join("target"), SkipReason::Ignored),
+            ]
+        );
+
+        let target = dir.join("target");
+        assert_eq!(
+            files(&target, &options),
+            vec![Walked::File(target.join("out.rs"))]
+        );
+
+        options.ignore = false;
+        assert_eq!(files(&dir, &options).len(), 7);
+    }
+
+    #[test]
+    fn custom_ignore_file_takes_precedence() {
+        let dir = temp_dir("custom-ignore");
+        fs::create_dir(dir.join("data")).unwrap();
+        fs::write(dir.join(".gitignore"), "*.csv\n*.json\n").unwrap();
+        fs::write(dir.join(".ignore"), "!*.csv\n!*.json\n").unwrap();
+        fs::write(dir.join(".minigrepignore"), "data/\n*.json\n").unwrap();
+        fs::write(dir.join("data/big.csv"), "x").unwrap();
+        fs::write(dir.join("table.csv"), "x").unwrap();
+        fs::write(dir.join("dump.json"), "x").unwrap();
+
+        let found = |ignore, custom_ignore| {
+            let options = WalkOptions {
+                recursive: true,
+                ignore,
+                custom_ignore,
+                ..WalkOptions::default()
+            };
+            files(&dir, &options)
+                .into_iter()
+                .filter_map(|walked| match walked {
+                    Walked::File(path) => Some(path.strip_prefix(&dir).unwrap().to_path_buf()),
+                    Walked::Skipped(..) => None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(found(true, true), vec![PathBuf::from("table.csv")]);
+        assert_eq!(
+            found(true, false),
+            vec![
+                PathBuf::from("data/big.csv"),
+                PathBuf::from("dump.json"),
+                PathBuf::from("table.csv"),
+            ]
+        );
+        assert_eq!(found(false, true), vec![PathBuf::from("table.csv")]);
+        assert_eq!(found(false, false).len(), 3);
+    }
+
+    #[test]
+    fn non_recursive_yields_paths_unchanged() {
+        let options = WalkOptions::default();
+        assert_eq!(
+            files(Path::new("missing.txt"), &options),
+            vec![Walked::File(PathBuf::from("missing.txt"))]
+        );
+    }
+
+    #[test]
+    fn resolve_inputs_applies_every_filter_in_argument_order() {
+        let dir = temp_dir("resolve-inputs");
+        fs::create_dir_all(dir.join("sub/deep")).unwrap();
+        fs::create_dir(dir.join("target")).unwrap();
+        fs::write(dir.join(".gitignore"), "ignored.rs\n").unwrap();
+        fs::write(dir.join(CUSTOM_IGNORE_FILE), "custom.rs\n").unwrap();
+        for name in [
+            "a.rs",
+            "ignored.rs",
+            "custom.rs",
+            ".hidden.rs",
+            "notes.txt",
+            "skip.rs",
+            "old.rs",
+            "sub/s.rs",
+            "sub/deep/d.rs",
+            "target/t.rs",
+        ] {
+            fs::write(dir.join(name), "fn main() {}\n").unwrap();
+        }
+        fs::write(dir.join("big.rs"), "x".repeat(100)).unwrap();
+        fs::write(dir.join("empty.rs"), "").unwrap();
+        fs::hard_link(dir.join("a.rs"), dir.join("link.rs")).unwrap();
+        let now = SystemTime::now();
+        let day = Duration::from_secs(24 * 60 * 60);
+        fs::File::options()
+            .write(true)
+            .open(dir.join("old.rs"))
+            .unwrap()
+            .set_modified(now - 3 * day)
+            .unwrap();
+
+        let globs = |patterns: &[&str]| -> Vec<Glob> {
+            patterns.iter().map(|p| Glob::new(p).unwrap()).collect()
+        };
+        let mut options = WalkOptions {
+            recursive: true,
+            max_filesize: Some(50),
+            min_filesize: Some(1),
+            modified_after: Some(now - day),
+            max_depth: Some(2),
+            include: globs(&["*.rs"]),
+            exclude: globs(&["skip*"]),
+            exclude_dir: globs(&["target"]),
+            ignore: true,
+            custom_ignore: true,
+            dedupe: true,
+            ..WalkOptions::default()
+        };
+        let resolved = |args: &[PathBuf], options: &WalkOptions| -> Vec<Result<Input, ErrorKind>> {
+            resolve_inputs(args, options)
+                .map(|input| input.map_err(|error| error.kind()))
+                .collect()
+        };
+        let missing = dir.join("missing.rs");
+        let args = [dir.clone(), PathBuf::from("-"), missing.clone()];
+        assert_eq!(
+            resolved(&args, &options),
+            vec![
+                Ok(Input::File(dir.join("a.rs"))),
+                Ok(Input::Skipped(dir.join("big.rs"), SkipReason::TooLarge)),
+                Ok(Input::Skipped(dir.join("custom.rs"), SkipReason::Ignored)),
+                Ok(Input::Skipped(dir.join("empty.rs"), SkipReason::TooSmall)),
+                Ok(Input::Skipped(dir.join("ignored.rs"), SkipReason::Ignored)),
+                Ok(Input::Skipped(dir.join("link.rs"), SkipReason::Duplicate)),
+                Ok(Input::Skipped(dir.join("old.rs"), SkipReason::TooOld)),
+                Ok(Input::File(dir.join("sub/s.rs"))),
+                Ok(Input::Stdin),
+                Err(ErrorKind::NotFound),
+            ]
+        );
+
+        options.hidden = true;
+        options.max_depth = None;
+        let found = resolved(std::slice::from_ref(&dir), &options);
+        assert!(found.contains(&Ok(Input::File(dir.join(".hidden.rs")))));
+        assert!(found.contains(&Ok(Input::File(dir.join("sub/deep/d.rs")))));
+
+        options.recursive = false;
+        let args = [
+            dir.join("notes.txt"),
+            dir.clone(),
+            missing,
+            dir.join("big.rs"),
+        ];
+        assert_eq!(
+            resolved(&args, &options),
+            vec![
+                Ok(Input::File(dir.join("notes.txt"))),
+                Err(ErrorKind::IsDirectory),
+                Err(ErrorKind::NotFound),
+                Ok(Input::Skipped(dir.join("big.rs"), SkipReason::TooLarge)),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn devices_are_skipped_unless_named_and_read() {
+        use std::os::unix::net::UnixListener;
+
+        let dir = temp_dir("devices");
+        let socket = dir.join("app.sock");
+        let _listener = UnixListener::bind(&socket).unwrap();
+        fs::write(dir.join("app.log"), "x").unwrap();
+
+        let mut options = WalkOptions::default();
+        let skipped = || Walked::Skipped(socket.clone(), SkipReason::Device);
+        assert_eq!(files(&socket, &options), vec![skipped()]);
+        options.max_filesize = Some(10);
+        assert_eq!(files(&socket, &options), vec![skipped()]);
+        options.read_devices = true;
+        assert_eq!(files(&socket, &options), vec![Walked::File(socket.clone())]);
+
+        options.recursive = true;
+        assert_eq!(
+            files(&dir, &options),
+            vec![Walked::File(dir.join("app.log")), skipped()]
+        );
+    }
+}
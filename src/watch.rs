@@ -0,0 +1,251 @@
+//! Following a file as it grows, as `tail -F` does, so that `--watch` can
+//! search the lines appended to a log while it is being written.
+//!
+//! The file is polled rather than watched through the operating system:
+//! each [`Follower::poll`] reads whatever was appended since the last one,
+//! and starts over if the file shrank or, on Unix, if another file took its
+//! place, as happens when logs are rotated.
+
+use std::{
+    fs::{self, File},
+    io::{self, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// The most a single poll reads, so that a long file is searched a piece at
+/// a time rather than read whole into memory.
+const CHUNK: u64 = 1 << 16;
+
+/// What a [`Follower`] found when it polled its file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// A complete record, without its terminator.
+    Record(Vec<u8>),
+    /// The file became shorter than what was already read, and is being
+    /// read again from its start.
+    Truncated,
+    /// Another file was put in place of the one being read, which is now
+    /// read from its start.
+    Replaced,
+}
+
+/// A file being followed as records are appended to it.
+///
+/// # Examples
+/// ```no_run
+/// use minigrep_cli_tool::watch::{Event, Follower};
+/// use std::{path::Path, thread, time::Duration};
+///
+/// let mut follower = Follower::open(Path::new("app.log"), b'\n')?;
+/// loop {
+///     for event in follower.poll()? {
+///         if let Event::Record(line) = event {
+///             println!("{}", String::from_utf8_lossy(&line));
+///         }
+///     }
+///     thread::sleep(Duration::from_millis(250));
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct Follower {
+    path: PathBuf,
+    file: File,
+    identity: Option<(u64, u64)>,
+    /// How many bytes of the file have been read.
+    position: u64,
+    /// The start of a record whose terminator has not been written yet.
+    pending: Vec<u8>,
+    terminator: u8,
+}
+
+impl Follower {
+    /// Opens the file at `path` to be followed from its start, splitting it
+    /// into records ended by `terminator`.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be opened.
+    pub fn open(path: &Path, terminator: u8) -> io::Result<Follower> {
+        let file = File::open(path)?;
+        let identity = identity(&file.metadata()?);
+        Ok(Follower {
+            path: path.to_path_buf(),
+            file,
+            identity,
+            position: 0,
+            pending: Vec::new(),
+            terminator,
+        })
+    }
+
+    /// Reads the records appended since the last poll, then checks whether
+    /// the file was truncated or replaced and, if so, starts over.
+    ///
+    /// At most [`CHUNK`] bytes are read at once, so the file may not have
+    /// been read to its end unless no events are returned. A record still
+    /// missing its terminator is held back until it is complete, unless the
+    /// file is replaced first. While the path names no
+    /// file, as between the steps of a rotation, the file already open is
+    /// still read.
+    ///
+    /// # Errors
+    /// Returns an error if reading the file fails.
+    pub fn poll(&mut self) -> io::Result<Vec<Event>> {
+        let mut events = Vec::new();
+        if !self.read_records(&mut events)? {
+            return Ok(events);
+        }
+        let Ok(metadata) = fs::metadata(&self.path) else {
+            return Ok(events);
+        };
+        if identity(&metadata) != self.identity {
+            if !self.pending.is_empty() {
+                events.push(Event::Record(std::mem::take(&mut self.pending)));
+            }
+            *self = Follower::open(&self.path, self.terminator)?;
+            events.push(Event::Replaced);
+            self.read_records(&mut events)?;
+        } else if metadata.len() < self.position {
+            self.file.seek(SeekFrom::Start(0))?;
+            self.position = 0;
+            self.pending.clear();
+            events.push(Event::Truncated);
+            self.read_records(&mut events)?;
+        }
+        Ok(events)
+    }
+
+    /// Reads up to [`CHUNK`] bytes of the file, adding each record completed
+    /// to `events`, and returns whether its current end was reached.
+    fn read_records(&mut self, events: &mut Vec<Event>) -> io::Result<bool> {
+        let mut appended = Vec::new();
+        (&mut self.file).take(CHUNK).read_to_end(&mut appended)?;
+        self.position += appended.len() as u64;
+        let mut rest = appended.as_slice();
+        while let Some(end) = rest.iter().position(|&byte| byte == self.terminator) {
+            self.pending.extend_from_slice(&rest[..end]);
+            events.push(Event::Record(std::mem::take(&mut self.pending)));
+            rest = &rest[end + 1..];
+        }
+        self.pending.extend_from_slice(rest);
+        Ok((appended.len() as u64) < CHUNK)
+    }
+}
+
+/// What tells one file from another at the same path: its device and inode
+/// on Unix, and nothing, so that only truncation is noticed, elsewhere.
+fn identity(metadata: &fs::Metadata) -> Option<(u64, u64)> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        Some((metadata.dev(), metadata.ino()))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = metadata;
+        None
+    }
+}
+
+/// Set once an interrupt has been caught by [`catch_interrupts`].
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Makes Ctrl-C, on Unix, set [`interrupted`] instead of killing the
+/// process, so that a search that never ends by itself can still finish
+/// cleanly. Elsewhere this does nothing.
+pub fn catch_interrupts() {
+    #[cfg(unix)]
+    {
+        use std::ffi::c_int;
+
+        const SIGINT: c_int = 2;
+        unsafe extern "C" {
+            fn signal(signum: c_int, handler: extern "C" fn(c_int)) -> usize;
+        }
+        extern "C" fn on_interrupt(_: c_int) {
+            INTERRUPTED.store(true, Ordering::Relaxed);
+        }
+        // SAFETY: `signal` is given a valid signal number and a handler that
+        // only stores to an atomic, which is safe to do in a signal handler.
+        unsafe {
+            signal(SIGINT, on_interrupt);
+        }
+    }
+}
+
+/// Whether an interrupt has been caught since [`catch_interrupts`].
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::Relaxed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs::OpenOptions, io::Write};
+
+    fn records(events: &[Event]) -> Vec<&str> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                Event::Record(record) => Some(std::str::from_utf8(record).unwrap()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Creates a file for a test in a directory of its own, removing any
+    /// earlier copy.
+    fn temp_file(name: &str, contents: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("minigrep-watch-{name}"));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("app.log");
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn appended_records_are_read_once_complete() {
+        let path = temp_file("appended", "one\ntw");
+        let mut follower = Follower::open(&path, b'\n').unwrap();
+        assert_eq!(records(&follower.poll().unwrap()), ["one"]);
+        assert_eq!(follower.poll().unwrap(), []);
+
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"o\nthree\n").unwrap();
+        assert_eq!(records(&follower.poll().unwrap()), ["two", "three"]);
+    }
+
+    #[test]
+    fn truncated_files_are_read_again() {
+        let path = temp_file("truncated", "old line\nanother\n");
+        let mut follower = Follower::open(&path, b'\n').unwrap();
+        follower.poll().unwrap();
+
+        fs::write(&path, "new\n").unwrap();
+        let events = follower.poll().unwrap();
+        assert_eq!(events, [Event::Truncated, Event::Record(b"new".to_vec())]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn replaced_files_are_followed() {
+        let path = temp_file("rotated", "first\nhalf");
+        let mut follower = Follower::open(&path, b'\n').unwrap();
+        follower.poll().unwrap();
+
+        fs::rename(&path, path.with_extension("log.1")).unwrap();
+        assert_eq!(follower.poll().unwrap(), []);
+        fs::write(&path, "second\n").unwrap();
+        let events = follower.poll().unwrap();
+        assert_eq!(
+            events,
+            [
+                Event::Record(b"half".to_vec()),
+                Event::Replaced,
+                Event::Record(b"second".to_vec()),
+            ]
+        );
+    }
+}
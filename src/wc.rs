@@ -0,0 +1,141 @@
+//! Counting the lines, words, and bytes of the selected lines, for `--wc`,
+//! as `grep PATTERN FILE | wc` would.
+
+use std::ops::AddAssign;
+
+/// The lines, words, and bytes counted in some selected lines.
+///
+/// - `lines`: How many lines there were.
+/// - `words`: How many words they had, as [`words`] counts them.
+/// - `bytes`: How many bytes they were read as, terminators included, as
+///   `wc -c` counts them: a line ending in `\r\n` has two more than its
+///   text, and a last line with no terminator none.
+///
+/// Counts are added up with `+=`.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::wc::{WordCount, words};
+///
+/// let mut count = WordCount::default();
+/// for line in ["two words\n", "\n"] {
+///     let text = line.trim_end_matches('\n').as_bytes();
+///     count += WordCount { lines: 1, words: words(text), bytes: line.len() as u64 };
+/// }
+/// assert_eq!(count, WordCount { lines: 2, words: 2, bytes: 11 });
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WordCount {
+    pub lines: u64,
+    pub words: u64,
+    pub bytes: u64,
+}
+
+impl WordCount {
+    /// Renders the three counts in that order, each right-aligned to
+    /// `width` and separated by a space, as `wc` prints them.
+    ///
+    /// # Examples
+    /// ```
+    /// use minigrep_cli_tool::wc::WordCount;
+    ///
+    /// let count = WordCount { lines: 2, words: 5, bytes: 31 };
+    /// assert_eq!(count.render(count.width()), " 2  5 31");
+    /// ```
+    pub fn render(&self, width: usize) -> String {
+        format!(
+            "{:>width$} {:>width$} {:>width$}",
+            self.lines, self.words, self.bytes
+        )
+    }
+
+    /// The number of digits of the largest of the counts, for
+    /// [`WordCount::render`] to align them all to: those of a total are as
+    /// wide as any of the counts added to it.
+    pub fn width(&self) -> usize {
+        let largest = self.lines.max(self.words).max(self.bytes);
+        largest.to_string().len()
+    }
+}
+
+impl AddAssign for WordCount {
+    fn add_assign(&mut self, other: WordCount) {
+        self.lines += other.lines;
+        self.words += other.words;
+        self.bytes += other.bytes;
+    }
+}
+
+/// Counts the words in `text`, the runs of bytes other than whitespace, as
+/// `wc -w` does in the C locale: space, tab, newline, vertical tab, form
+/// feed, and carriage return separate words, and every other byte, invalid
+/// UTF-8 included, is part of one.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::wc::words;
+///
+/// assert_eq!(words(b"  fn main() {}\t"), 3);
+/// assert_eq!(words(b""), 0);
+/// ```
+pub fn words(text: &[u8]) -> u64 {
+    let mut words = 0;
+    let mut in_word = false;
+    for &byte in text {
+        let space = matches!(byte, b' ' | b'\t' | b'\n' | b'\x0b' | b'\x0c' | b'\r');
+        if !space && !in_word {
+            words += 1;
+        }
+        in_word = !space;
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn words_are_runs_of_non_whitespace() {
+        assert_eq!(words(b"one"), 1);
+        assert_eq!(words(b" \t\x0b\x0c\r "), 0);
+        assert_eq!(words(b"a\x0bb\x0cc\rd"), 4);
+        assert_eq!(words("café au lait".as_bytes()), 3);
+        assert_eq!(words(b"\xff\xfe x"), 2);
+        // No-break spaces are not whitespace in the C locale.
+        assert_eq!(words("a\u{a0}b".as_bytes()), 1);
+    }
+
+    /// Counts `lines`, each ended by a newline, as the lines of one file.
+    fn counted(lines: &[&str]) -> WordCount {
+        let mut count = WordCount::default();
+        for line in lines {
+            count += WordCount {
+                lines: 1,
+                words: words(line.as_bytes()),
+                bytes: line.len() as u64 + 1,
+            };
+        }
+        count
+    }
+
+    #[test]
+    fn totals_align_to_their_widest_count() {
+        let first = counted(&["error: disk full"]);
+        let second = counted(&["warning: retrying in 10 seconds"; 3]);
+        let mut total = WordCount::default();
+        total += first;
+        total += second;
+        assert_eq!(
+            total,
+            WordCount {
+                lines: 4,
+                words: 18,
+                bytes: 113
+            }
+        );
+        let width = total.width();
+        assert_eq!(first.render(width), "  1   3  17");
+        assert_eq!(total.render(width), "  4  18 113");
+    }
+}
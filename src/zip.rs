@@ -0,0 +1,448 @@
+//! Reading the members of zip archives (APPNOTE.TXT), so that bundles of
+//! files can be searched without unpacking them first.
+//!
+//! Members may be stored or deflated, and archives may use the zip64
+//! extensions. Encrypted members, and those compressed any other way, are
+//! listed but cannot be read.
+
+use std::{
+    borrow::Cow,
+    io::{self, BufRead, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+use crate::decompress::{crc32, truncated};
+
+/// The bytes zip archives start with: the signature of a local header.
+pub const MAGIC: [u8; 4] = *b"PK\x03\x04";
+
+/// The signatures of the records of an archive other than local headers.
+const CENTRAL_HEADER: [u8; 4] = *b"PK\x01\x02";
+const END_OF_DIRECTORY: [u8; 4] = *b"PK\x05\x06";
+const ZIP64_END_OF_DIRECTORY: [u8; 4] = *b"PK\x06\x06";
+const ZIP64_LOCATOR: [u8; 4] = *b"PK\x06\x07";
+
+/// The lengths of the fixed parts of the records read.
+const CENTRAL_HEADER_LEN: usize = 46;
+const LOCAL_HEADER_LEN: usize = 30;
+const END_OF_DIRECTORY_LEN: usize = 22;
+const ZIP64_LOCATOR_LEN: usize = 20;
+const ZIP64_END_OF_DIRECTORY_LEN: usize = 56;
+
+/// The flag of a member that is encrypted.
+const FLAG_ENCRYPTED: u16 = 1;
+
+/// The compression methods members can be read in.
+const METHOD_STORED: u16 = 0;
+const METHOD_DEFLATED: u16 = 8;
+
+/// Returns an error for data that is not a valid zip archive.
+fn corrupt(reason: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("corrupt zip data: {reason}"),
+    )
+}
+
+/// Reads a little-endian 16, 32, or 64-bit number from `bytes` at `at`.
+fn u16_at(bytes: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([bytes[at], bytes[at + 1]])
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+}
+
+fn u64_at(bytes: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap())
+}
+
+/// Reads exactly `buf.len()` bytes, reporting the end of the input as
+/// truncated data.
+fn read_exact(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<()> {
+    reader.read_exact(buf).map_err(|error| match error.kind() {
+        io::ErrorKind::UnexpectedEof => truncated(),
+        _ => error,
+    })
+}
+
+/// Returns whether the file at `path` is a zip archive: from the extension
+/// of its name or, failing that, from its first bytes, `start`.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::zip;
+/// use std::path::Path;
+///
+/// assert!(zip::is_zip(Path::new("logs.ZIP"), b""));
+/// assert!(zip::is_zip(Path::new("logs.bundle"), b"PK\x03\x04\x14\x00"));
+/// assert!(!zip::is_zip(Path::new("logs.txt"), b"PK"));
+/// ```
+pub fn is_zip(path: &Path, start: &[u8]) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("zip"))
+        || start.starts_with(&MAGIC)
+}
+
+/// A member of a zip archive, as its central directory lists it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Member {
+    name: Vec<u8>,
+    flags: u16,
+    method: u16,
+    crc: u32,
+    compressed_size: u64,
+    size: u64,
+    /// Where the member's local header starts.
+    offset: u64,
+}
+
+impl Member {
+    /// The member's path within the archive, with any bytes that are not
+    /// UTF-8 replaced by U+FFFD.
+    pub fn name(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.name)
+    }
+
+    /// Whether the member is a directory rather than a file.
+    pub fn is_dir(&self) -> bool {
+        self.name.ends_with(b"/")
+    }
+
+    /// Whether the member is encrypted, and so cannot be read.
+    pub fn is_encrypted(&self) -> bool {
+        self.flags & FLAG_ENCRYPTED != 0
+    }
+
+    /// The size of the member once decompressed.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+/// A zip archive read from `R`, whose members can each be read in turn.
+///
+/// # Examples
+/// ```
+/// use minigrep_cli_tool::zip::ZipArchive;
+/// use std::io::{Cursor, Read};
+///
+/// let archive: &[u8] = &[
+///     0x50, 0x4b, 0x03, 0x04, 0x14, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x21, 0x00,
+///     0x35, 0xed, 0x5a, 0x66, 0x05, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x05, 0x00,
+///     0x00, 0x00, 0x61, 0x2e, 0x6c, 0x6f, 0x67, 0x6f, 0x6b, 0x61, 0x79, 0x0a, 0x50, 0x4b,
+///     0x01, 0x02, 0x14, 0x03, 0x14, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x21, 0x00,
+///     0x35, 0xed, 0x5a, 0x66, 0x05, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x05, 0x00,
+///     0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80, 0x01, 0x00, 0x00,
+///     0x00, 0x00, 0x61, 0x2e, 0x6c, 0x6f, 0x67, 0x50, 0x4b, 0x05, 0x06, 0x00, 0x00, 0x00,
+///     0x00, 0x01, 0x00, 0x01, 0x00, 0x33, 0x00, 0x00, 0x00, 0x28, 0x00, 0x00, 0x00, 0x00,
+///     0x00,
+/// ];
+/// let mut archive = ZipArchive::new(Cursor::new(archive)).unwrap();
+/// assert_eq!(archive.members()[0].name(), "a.log");
+///
+/// let mut text = String::new();
+/// archive.open(0).unwrap().read_to_string(&mut text).unwrap();
+/// assert_eq!(text, "okay\n");
+/// ```
+#[derive(Debug)]
+pub struct ZipArchive<R> {
+    reader: R,
+    members: Vec<Member>,
+}
+
+impl<R: BufRead + Seek> ZipArchive<R> {
+    /// Reads the central directory of the archive read from `reader`.
+    ///
+    /// # Errors
+    /// Returns an error if reading fails, or one of kind
+    /// [`InvalidData`](io::ErrorKind::InvalidData) if the data is not a zip
+    /// archive or its directory is corrupt.
+    pub fn new(mut reader: R) -> io::Result<ZipArchive<R>> {
+        let (count, offset) = read_end_of_directory(&mut reader)?;
+        reader.seek(SeekFrom::Start(offset))?;
+        let mut members = Vec::new();
+        for _ in 0..count {
+            let mut header = [0; CENTRAL_HEADER_LEN];
+            read_exact(&mut reader, &mut header)?;
+            if header[..4] != CENTRAL_HEADER {
+                return Err(corrupt("bad central directory"));
+            }
+            let name_len = usize::from(u16_at(&header, 28));
+            let extra_len = usize::from(u16_at(&header, 30));
+            let comment_len = u64::from(u16_at(&header, 32));
+            let mut name = vec![0; name_len];
+            read_exact(&mut reader, &mut name)?;
+            let mut extra = vec![0; extra_len];
+            read_exact(&mut reader, &mut extra)?;
+            io::copy(&mut (&mut reader).take(comment_len), &mut io::sink())?;
+
+            let mut member = Member {
+                name,
+                flags: u16_at(&header, 8),
+                method: u16_at(&header, 10),
+                crc: u32_at(&header, 16),
+                compressed_size: u64::from(u32_at(&header, 20)),
+                size: u64::from(u32_at(&header, 24)),
+                offset: u64::from(u32_at(&header, 42)),
+            };
+            read_zip64_sizes(&mut member, &extra)?;
+            members.push(member);
+        }
+        Ok(ZipArchive { reader, members })
+    }
+
+    /// The members of the archive, in the order of its directory.
+    pub fn members(&self) -> &[Member] {
+        &self.members
+    }
+
+    /// Opens the member at `index` in [`ZipArchive::members`] for reading.
+    ///
+    /// Its checksum and size are checked once it has been read to its end.
+    ///
+    /// # Errors
+    /// Returns an error of kind [`Unsupported`](io::ErrorKind::Unsupported)
+    /// if the member is encrypted or compressed in a way that cannot be
+    /// read, or if its local header cannot be read. Reading returns an error
+    /// if the member's data is corrupt.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn open(&mut self, index: usize) -> io::Result<Box<dyn Read + '_>> {
+        let member = &self.members[index];
+        if member.is_encrypted() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "encrypted zip members are not supported",
+            ));
+        }
+        self.reader.seek(SeekFrom::Start(member.offset))?;
+        let mut header = [0; LOCAL_HEADER_LEN];
+        read_exact(&mut self.reader, &mut header)?;
+        if header[..4] != MAGIC {
+            return Err(corrupt("bad local header"));
+        }
+        let skipped = i64::from(u16_at(&header, 26)) + i64::from(u16_at(&header, 28));
+        self.reader.seek(SeekFrom::Current(skipped))?;
+
+        let data = (&mut self.reader).take(member.compressed_size);
+        let data: Box<dyn Read> = match member.method {
+            METHOD_STORED => Box::new(data),
+            METHOD_DEFLATED => Box::new(flate2::bufread::DeflateDecoder::new(data)),
+            method => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    format!("zip compression method {method} is not supported"),
+                ));
+            }
+        };
+        Ok(Box::new(Checked {
+            data,
+            expected_crc: member.crc,
+            expected_size: member.size,
+            crc: 0,
+            size: 0,
+        }))
+    }
+}
+
+/// Finds the end of central directory record of the archive read from
+/// `reader`, and returns how many members the directory lists and where it
+/// starts.
+fn read_end_of_directory(reader: &mut (impl Read + Seek)) -> io::Result<(u64, u64)> {
+    let length = reader.seek(SeekFrom::End(0))?;
+    // The record ends the archive, followed only by a comment of up to
+    // 65535 bytes.
+    let tail_len = length.min((END_OF_DIRECTORY_LEN + usize::from(u16::MAX)) as u64);
+    reader.seek(SeekFrom::Start(length - tail_len))?;
+    let mut tail = vec![0; tail_len as usize];
+    read_exact(reader, &mut tail)?;
+    let at = (0..tail.len().saturating_sub(END_OF_DIRECTORY_LEN - 1))
+        .rev()
+        .find(|&at| tail[at..].starts_with(&END_OF_DIRECTORY))
+        .ok_or_else(|| corrupt("no central directory"))?;
+    let record = &tail[at..];
+    let count = u16_at(record, 10);
+    let offset = u32_at(record, 16);
+    if count != u16::MAX && offset != u32::MAX {
+        return Ok((u64::from(count), u64::from(offset)));
+    }
+
+    let record_start = length - tail_len + at as u64;
+    let locator_start = record_start
+        .checked_sub(ZIP64_LOCATOR_LEN as u64)
+        .ok_or_else(|| corrupt("missing zip64 locator"))?;
+    reader.seek(SeekFrom::Start(locator_start))?;
+    let mut locator = [0; ZIP64_LOCATOR_LEN];
+    read_exact(reader, &mut locator)?;
+    if locator[..4] != ZIP64_LOCATOR {
+        return Err(corrupt("missing zip64 locator"));
+    }
+    reader.seek(SeekFrom::Start(u64_at(&locator, 8)))?;
+    let mut record = [0; ZIP64_END_OF_DIRECTORY_LEN];
+    read_exact(reader, &mut record)?;
+    if record[..4] != ZIP64_END_OF_DIRECTORY {
+        return Err(corrupt("bad zip64 end of central directory"));
+    }
+    Ok((u64_at(&record, 32), u64_at(&record, 48)))
+}
+
+/// Replaces the sizes and offset of `member` that did not fit in 32 bits
+/// with those of the zip64 field in its `extra` fields.
+fn read_zip64_sizes(member: &mut Member, mut extra: &[u8]) -> io::Result<()> {
+    while extra.len() >= 4 {
+        let (id, len) = (u16_at(extra, 0), usize::from(u16_at(extra, 2)));
+        let field = extra
+            .get(4..4 + len)
+            .ok_or_else(|| corrupt("bad extra field"))?;
+        if id == 0x0001 {
+            let mut values = field.chunks_exact(8).map(|value| u64_at(value, 0));
+            let sizes = [
+                &mut member.size,
+                &mut member.compressed_size,
+                &mut member.offset,
+            ];
+            for size in sizes {
+                if *size == u64::from(u32::MAX) {
+                    *size = values.next().ok_or_else(|| corrupt("bad zip64 field"))?;
+                }
+            }
+        }
+        extra = &extra[4 + len..];
+    }
+    Ok(())
+}
+
+/// A member's data as it is read, checked against the checksum and size
+/// the directory lists once its end is reached.
+struct Checked<'a> {
+    data: Box<dyn Read + 'a>,
+    expected_crc: u32,
+    expected_size: u64,
+    crc: u32,
+    size: u64,
+}
+
+impl Read for Checked<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.data.read(buf)?;
+        self.crc = crc32(self.crc, &buf[..n]);
+        self.size += n as u64;
+        if n == 0 && !buf.is_empty() {
+            if self.size != self.expected_size {
+                return Err(if self.size < self.expected_size {
+                    truncated()
+                } else {
+                    corrupt("length mismatch")
+                });
+            }
+            if self.crc != self.expected_crc {
+                return Err(corrupt("checksum mismatch"));
+            }
+        }
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// Builds an archive of stored members from `(name, flags, data)`.
+    fn archive(members: &[(&str, u16, &[u8])]) -> Vec<u8> {
+        let mut archive = Vec::new();
+        let mut directory = Vec::new();
+        for &(name, flags, data) in members {
+            let offset = archive.len() as u32;
+            let mut fields = Vec::new();
+            fields.extend_from_slice(&20u16.to_le_bytes());
+            fields.extend_from_slice(&flags.to_le_bytes());
+            fields.extend_from_slice(&METHOD_STORED.to_le_bytes());
+            fields.extend_from_slice(&[0; 4]);
+            fields.extend_from_slice(&crc32(0, data).to_le_bytes());
+            fields.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            fields.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            fields.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            fields.extend_from_slice(&[0; 2]);
+
+            archive.extend_from_slice(&MAGIC);
+            archive.extend_from_slice(&fields);
+            archive.extend_from_slice(name.as_bytes());
+            archive.extend_from_slice(data);
+
+            directory.extend_from_slice(&CENTRAL_HEADER);
+            directory.extend_from_slice(&20u16.to_le_bytes());
+            directory.extend_from_slice(&fields);
+            directory.extend_from_slice(&[0; 10]);
+            directory.extend_from_slice(&offset.to_le_bytes());
+            directory.extend_from_slice(name.as_bytes());
+        }
+        let offset = archive.len() as u32;
+        let count = members.len() as u16;
+        archive.extend_from_slice(&directory);
+        archive.extend_from_slice(&END_OF_DIRECTORY);
+        archive.extend_from_slice(&[0; 4]);
+        archive.extend_from_slice(&count.to_le_bytes());
+        archive.extend_from_slice(&count.to_le_bytes());
+        archive.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+        archive.extend_from_slice(&offset.to_le_bytes());
+        archive.extend_from_slice(&[0; 2]);
+        archive
+    }
+
+    fn read(archive: &mut ZipArchive<Cursor<Vec<u8>>>, index: usize) -> io::Result<String> {
+        let mut text = String::new();
+        archive.open(index)?.read_to_string(&mut text)?;
+        Ok(text)
+    }
+
+    #[test]
+    fn members_are_listed_and_read() {
+        let bytes = archive(&[
+            ("logs/", 0, b""),
+            ("logs/a.log", 0, b"first\n"),
+            ("logs/b.log", FLAG_ENCRYPTED, b"secret\n"),
+        ]);
+        let mut archive = ZipArchive::new(Cursor::new(bytes)).unwrap();
+        let names: Vec<_> = archive.members().iter().map(Member::name).collect();
+        assert_eq!(names, ["logs/", "logs/a.log", "logs/b.log"]);
+        assert!(archive.members()[0].is_dir());
+        assert!(archive.members()[2].is_encrypted());
+
+        assert_eq!(read(&mut archive, 1).unwrap(), "first\n");
+        let error = read(&mut archive, 2).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn deflated_members() {
+        // As made by Python's zipfile, with one deflated member "d.log".
+        let bytes = vec![
+            0x50, 0x4b, 0x03, 0x04, 0x14, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x21, 0x00,
+            0xc9, 0x8e, 0x9b, 0x7c, 0x0c, 0x00, 0x00, 0x00, 0x0f, 0x00, 0x00, 0x00, 0x05, 0x00,
+            0x00, 0x00, 0x64, 0x2e, 0x6c, 0x6f, 0x67, 0xcb, 0xcd, 0x2c, 0x2e, 0xc9, 0xcf, 0x4b,
+            0xcd, 0x85, 0x50, 0x5c, 0x00, 0x50, 0x4b, 0x01, 0x02, 0x14, 0x03, 0x14, 0x00, 0x00,
+            0x00, 0x08, 0x00, 0x00, 0x00, 0x21, 0x00, 0xc9, 0x8e, 0x9b, 0x7c, 0x0c, 0x00, 0x00,
+            0x00, 0x0f, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x80, 0x01, 0x00, 0x00, 0x00, 0x00, 0x64, 0x2e, 0x6c, 0x6f, 0x67,
+            0x50, 0x4b, 0x05, 0x06, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x33, 0x00,
+            0x00, 0x00, 0x2f, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        let mut archive = ZipArchive::new(Cursor::new(bytes)).unwrap();
+        assert_eq!(read(&mut archive, 0).unwrap(), "mistonemistone\n");
+    }
+
+    #[test]
+    fn corrupt_archives_are_errors() {
+        let error = ZipArchive::new(Cursor::new(b"plain text".to_vec())).unwrap_err();
+        assert_eq!(error.to_string(), "corrupt zip data: no central directory");
+
+        let mut bytes = archive(&[("a.log", 0, b"some text\n")]);
+        bytes[LOCAL_HEADER_LEN + 5] ^= 1;
+        let mut archive = ZipArchive::new(Cursor::new(bytes)).unwrap();
+        let error = read(&mut archive, 0).unwrap_err();
+        assert_eq!(error.to_string(), "corrupt zip data: checksum mismatch");
+    }
+}
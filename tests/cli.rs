@@ -0,0 +1,2554 @@
+//! End-to-end tests running the compiled `minigrep-cli-tool` binary.
+
+use minigrep_cli_tool::{
+    error,
+    grep::{Options, grep},
+    options::{OPTIONS, Opt},
+    search_in_file,
+};
+use std::{
+    fs,
+    io::{ErrorKind, Write},
+    path::PathBuf,
+    process::{Command, Output, Stdio},
+};
+
+/// Writes `contents` to a fixture file named `name` and returns its path.
+fn fixture(name: &str, contents: &str) -> PathBuf {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+/// Creates an empty fixture directory named `name` and returns its path.
+fn fixture_dir(name: &str) -> PathBuf {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = fs::remove_dir_all(&path);
+    fs::create_dir_all(&path).unwrap();
+    path
+}
+
+/// Builds a command running the binary with `args` in a clean environment.
+fn command(args: &[&str]) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_minigrep-cli-tool"));
+    command
+        .args(args)
+        .env_remove("IGNORE_CASE")
+        .env_remove("NO_COLOR")
+        .env_remove("CLICOLOR_FORCE")
+        .env_remove("MINIGREP_COLORS")
+        .env_remove("GREP_COLORS");
+    command
+}
+
+/// Runs the binary with `args`, feeding `stdin` to it.
+fn minigrep(args: &[&str], stdin: &str) -> Output {
+    run(command(args), stdin)
+}
+
+/// Runs `command` to completion, feeding `stdin` to it.
+///
+/// The binary may exit before reading its input, e.g. on a usage error, so
+/// a broken pipe while feeding it is not a failure.
+fn run(mut command: Command, stdin: &str) -> Output {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let written = child.stdin.take().unwrap().write_all(stdin.as_bytes());
+    if let Err(error) = written {
+        assert_eq!(error.kind(), ErrorKind::BrokenPipe, "{error}");
+    }
+    child.wait_with_output().unwrap()
+}
+
+/// Returns the standard output of a run as a string.
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+/// Returns the standard error of a run as a string.
+fn stderr(output: &Output) -> String {
+    String::from_utf8(output.stderr.clone()).unwrap()
+}
+
+/// The `--threads` options among `options` that this build accepts: all of
+/// them with the `parallel` feature, and only `--threads=1` without it.
+fn supported_threads<'a>(options: &[&'a str]) -> Vec<&'a str> {
+    options
+        .iter()
+        .copied()
+        .filter(|option| cfg!(feature = "parallel") || *option == "--threads=1")
+        .collect()
+}
+
+#[test]
+fn color_always_highlights_each_match() {
+    let output = minigrep(&["--color=always", "rust", "-"], "rust and rust\nnothing\n");
+
+    assert_eq!(
+        stdout(&output),
+        "\x1b[01;31mrust\x1b[0m and \x1b[01;31mrust\x1b[0m\n"
+    );
+}
+
+#[test]
+fn color_always_paints_prefixes() {
+    let path = fixture("color_prefixes.txt", "Rust:\nsafe, fast.\nTrust in rust.\n");
+    let path = path.to_str().unwrap();
+    let output = minigrep(&["--color=always", "-n", "-H", "RUST", path, "/i"], "");
+
+    assert_eq!(
+        stdout(&output),
+        format!(
+            "\x1b[35m{path}\x1b[0m:\x1b[32m1\x1b[0m:\x1b[01;31mRust\x1b[0m:\n\
+             \x1b[35m{path}\x1b[0m:\x1b[32m3\x1b[0m:T\x1b[01;31mrust\x1b[0m in \x1b[01;31mrust\x1b[0m.\n"
+        )
+    );
+}
+
+#[test]
+fn color_always_highlights_each_pattern_in_its_own_color() {
+    let output = minigrep(
+        &["--color=always", "-e", "err", "-e", "warn", "-"],
+        "warn: err\nok\nerror warned\n",
+    );
+    assert_eq!(
+        stdout(&output),
+        "\x1b[01;32mwarn\x1b[0m: \x1b[01;31merr\x1b[0m\n\
+         \x1b[01;31merr\x1b[0mor \x1b[01;32mwarn\x1b[0med\n"
+    );
+
+    // Overlapping matches keep the longer, and the same text the earlier pattern.
+    let output = minigrep(
+        &["--color=always", "-e", "warn", "-e", "warning", "-"],
+        "warning\n",
+    );
+    assert_eq!(stdout(&output), "\x1b[01;32mwarning\x1b[0m\n");
+    let output = minigrep(
+        &["--color=always", "-e", "err", "-e", "ERR", "-", "/i"],
+        "Err\n",
+    );
+    assert_eq!(stdout(&output), "\x1b[01;31mErr\x1b[0m\n");
+
+    let output = minigrep(&["--color=always", "-o", "-e", "a", "-e", "b", "-"], "ba\n");
+    assert_eq!(
+        stdout(&output),
+        "\x1b[01;32mb\x1b[0m\n\x1b[01;31ma\x1b[0m\n"
+    );
+
+    let mut command = command(&["--color=always", "-e", "a", "-e", "b", "-e", "c", "-"]);
+    command.env("MINIGREP_COLORS", "ms=35:mp=34");
+    assert_eq!(
+        stdout(&run(command, "abc\n")),
+        "\x1b[35ma\x1b[0m\x1b[34mb\x1b[0m\x1b[35mc\x1b[0m\n"
+    );
+}
+
+#[test]
+fn color_never_and_auto_through_pipe_are_plain() {
+    for color in ["--color=never", "--color=auto"] {
+        let output = minigrep(&[color, "rust", "-"], "rust and rust\n");
+        assert_eq!(stdout(&output), "rust and rust\n");
+    }
+}
+
+#[test]
+fn debug_logs_the_setup_and_what_decided_it() {
+    let path = fixture("debug_setup.txt", "Rust\nrust\n");
+    let path = path.to_str().unwrap();
+    let mut debug = command(&["--debug", "rust", path, "/s"]);
+    debug.env("IGNORE_CASE", "1");
+    let output = run(debug, "");
+    assert_eq!(stdout(&output), "rust\n");
+    let expected = [
+        String::from("minigrep: [debug] config: query: \"rust\""),
+        String::from("minigrep: [debug] config: case: sensitive, from /s over IGNORE_CASE"),
+        format!("minigrep: [debug] config: inputs: {path:?}"),
+        String::from("minigrep: [debug] config: color: off, as standard output is not a terminal"),
+        format!("minigrep: [debug] search: {path}: 1 of 2 lines selected"),
+    ];
+    let stderr = stderr(&output);
+    let lines: Vec<&str> = stderr.lines().collect();
+    for line in &expected {
+        assert!(lines.contains(&line.as_str()), "{line} in {stderr}");
+    }
+}
+
+#[test]
+fn generate_completions_writes_a_bash_script_naming_every_option() {
+    let output = minigrep(&["--generate-completions", "bash"], "");
+    assert_eq!(output.status.code(), Some(0));
+    let script = stdout(&output);
+    assert!(script.contains("complete -o default -F _minigrep minigrep"));
+    let words: Vec<&str> = script
+        .split(|c: char| c.is_whitespace() || "'|)".contains(c))
+        .collect();
+    for name in OPTIONS.iter().flat_map(Opt::names) {
+        assert!(words.contains(&name), "{name}");
+    }
+
+    let output = minigrep(&["--generate-completions", "csh"], "");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stdout(&output).is_empty());
+}
+
+#[test]
+fn pagers_are_bypassed_when_output_is_not_a_terminal() {
+    let output = minigrep(&["--pager=minigrep-no-such-pager", "rust", "-"], "rust\n");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        (stdout(&output), stderr(&output)),
+        ("rust\n".into(), "".into())
+    );
+
+    let mut paged = command(&["rust", "-"]);
+    paged.env("MINIGREP_PAGER", "minigrep-no-such-pager");
+    let output = run(paged, "rust\n");
+    assert_eq!(
+        (stdout(&output), stderr(&output)),
+        ("rust\n".into(), "".into())
+    );
+}
+
+#[test]
+fn clicolor_force_colors_through_pipe() {
+    let mut command = command(&["rust", "-"]);
+    command.env("CLICOLOR_FORCE", "1");
+    let output = run(command, "rust\n");
+
+    assert_eq!(stdout(&output), "\x1b[01;31mrust\x1b[0m\n");
+}
+
+#[test]
+fn no_color_yields_to_explicit_flag() {
+    let mut plain = command(&["rust", "-"]);
+    plain.env("NO_COLOR", "1").env("CLICOLOR_FORCE", "1");
+    assert_eq!(stdout(&run(plain, "rust\n")), "rust\n");
+
+    let mut forced = command(&["--color=always", "rust", "-"]);
+    forced.env("NO_COLOR", "1");
+    assert_eq!(stdout(&run(forced, "rust\n")), "\x1b[01;31mrust\x1b[0m\n");
+}
+
+#[test]
+fn color_theme_from_environment() {
+    let mut command = command(&["--color=always", "-n", "rust", "-"]);
+    command.env("MINIGREP_COLORS", "ms=01;32:ln=33:se=36:zz=1");
+    command.env("GREP_COLORS", "ms=01;34");
+    let output = run(command, "a rust\n");
+
+    assert_eq!(
+        stdout(&output),
+        "\x1b[33m1\x1b[0m\x1b[36m:\x1b[0ma \x1b[01;32mrust\x1b[0m\n"
+    );
+    assert!(stderr(&output).contains("'zz'"));
+}
+
+#[test]
+fn recursive_search_skips_files_over_max_filesize() {
+    let dir = fixture_dir("max_filesize");
+    fs::create_dir(dir.join("nested")).unwrap();
+    fs::write(dir.join("nested/small.txt"), "match\n").unwrap();
+    fs::write(
+        dir.join("big.txt"),
+        format!("match\n{}\n", "x".repeat(2048)),
+    )
+    .unwrap();
+    let root = dir.to_str().unwrap();
+
+    let output = minigrep(&["-r", "--max-filesize=1K", "match", root], "");
+    assert_eq!(stdout(&output), format!("{root}/nested/small.txt:match\n"));
+
+    let output = minigrep(&["-r", "match", root], "");
+    assert_eq!(
+        stdout(&output),
+        format!("{root}/big.txt:match\n{root}/nested/small.txt:match\n")
+    );
+}
+
+#[test]
+fn max_depth_limits_recursion_and_requires_it() {
+    let dir = fixture_dir("max_depth");
+    fs::create_dir_all(dir.join("vendor/deep")).unwrap();
+    fs::write(dir.join("top.txt"), "match\n").unwrap();
+    fs::write(dir.join("vendor/lib.txt"), "match\n").unwrap();
+    fs::write(dir.join("vendor/deep/lib.txt"), "match\n").unwrap();
+    let root = dir.to_str().unwrap();
+
+    let output = minigrep(&["-r", "--max-depth=2", "-l", "match", root], "");
+    assert_eq!(
+        stdout(&output),
+        format!("{root}/top.txt\n{root}/vendor/lib.txt\n")
+    );
+
+    let output = minigrep(&["--max-depth=2", "match", root], "");
+    assert_eq!(output.status.code(), Some(2));
+}
+
+/// A fixture with one short line and one long line holding three matches.
+fn long_line_fixture() -> String {
+    format!(
+        "short rust\n{}rust{}rust rust\n",
+        "a".repeat(30),
+        "b".repeat(30)
+    )
+}
+
+#[test]
+fn max_columns_omits_long_lines() {
+    let output = minigrep(
+        &["--max-columns=20", "-n", "rust", "-"],
+        &long_line_fixture(),
+    );
+
+    assert_eq!(
+        stdout(&output),
+        "1:short rust\n2:[Omitted long line with 3 matches]\n"
+    );
+}
+
+#[test]
+fn max_columns_preview_cuts_around_first_match() {
+    let args = [
+        "--max-columns=10",
+        "--max-columns-preview",
+        "--color=always",
+        "rust",
+        "-",
+    ];
+    let output = minigrep(&args, &long_line_fixture());
+
+    assert_eq!(
+        stdout(&output),
+        "short \x1b[01;31mrust\x1b[0m\n…aaa\x1b[01;31mrust\x1b[0mbbb…\n"
+    );
+}
+
+#[test]
+fn snippet_trims_around_the_first_match() {
+    let input = "short rust\n\
+                 a long preamble before the rust call and rust again at the end\n\
+                 plain line\n";
+
+    let output = minigrep(&["--snippet=12", "-n", "rust", "-"], input);
+    assert_eq!(
+        stdout(&output),
+        "1:short rust\n2:…before the rust call and…\n"
+    );
+
+    let output = minigrep(&["--snippet=25", "--color=always", "rust", "-"], input);
+    assert_eq!(
+        stdout(&output),
+        "short \x1b[01;31mrust\x1b[0m\n\
+         …long preamble before the \x1b[01;31mrust\x1b[0m call and \x1b[01;31mrust\x1b[0m again at…\n"
+    );
+
+    let output = minigrep(&["--snippet=3", "--passthru", "rust", "-"], input);
+    assert_eq!(stdout(&output), "…rt rust\n…he rust ca…\nplain line\n");
+
+    let output = minigrep(&["--snippet=3", "--max-columns=10", "rust", "-"], input);
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn char_context_prints_each_match_of_a_long_line_on_its_own() {
+    let input = "ok\n{\"ü\":\"café error\",\"n\":1},{\"n\":2,\"e\":\"error error\"}\n";
+
+    // Windows that overlap are merged, and positions are in the whole line.
+    let output = minigrep(&["--char-context=3", "-n", "--column", "error", "-"], input);
+    assert_eq!(
+        stdout(&output),
+        "2:12:…fé error\",\"…\n2:38:…\":\"error error\"}\n"
+    );
+    let output = minigrep(
+        &["--char-context=3", "-b", "--byte-column", "error", "-"],
+        input,
+    );
+    assert_eq!(
+        stdout(&output),
+        "2:14:16:…fé error\",\"…\n2:40:42:…\":\"error error\"}\n"
+    );
+    let output = minigrep(&["--char-context=0", "--color=always", "error", "-"], input);
+    assert_eq!(stdout(&output), "…\x1b[01;31merror\x1b[0m…\n".repeat(3));
+
+    let filler = "é".repeat(1 << 20);
+    let input = format!("{filler}error{filler}error{filler}\n");
+    let output = minigrep(&["--char-context=2", "--column", "error", "-"], &input);
+    assert_eq!(
+        stdout(&output),
+        format!(
+            "1:{}:…ééerroréé…\n1:{}:…ééerroréé…\n",
+            (1 << 20) + 1,
+            (2 << 20) + 6
+        )
+    );
+
+    let output = minigrep(&["--char-context=3", "-v", "error", "-"], "error\n");
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn timings_list_each_file_then_the_totals() {
+    let dir = fixture_dir("timings");
+    fs::write(dir.join("a.txt"), "match\n".repeat(1000)).unwrap();
+    fs::write(dir.join("b.txt"), "nothing\n").unwrap();
+    fs::write(dir.join("c.txt"), "match\n").unwrap();
+    let root = dir.to_str().unwrap();
+
+    for threads in supported_threads(&["--threads=1", "--threads=3"]) {
+        let output = minigrep(&["-r", "-c", "--timings", threads, "match", root], "");
+        assert_eq!(stdout(&output).lines().count(), 3);
+        let stderr = stderr(&output);
+        let lines: Vec<&str> = stderr.lines().collect();
+        assert_eq!(lines.len(), 4, "{stderr}");
+        for line in &lines[..3] {
+            assert!(line.contains(" MB/s  "), "{line}");
+        }
+        let mut names: Vec<&str> = lines[..3]
+            .iter()
+            .map(|line| line.rsplit('/').next().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["a.txt", "b.txt", "c.txt"]);
+        assert!(
+            lines[3].starts_with("total: 3 files, 0.0 MB in "),
+            "{stderr}"
+        );
+    }
+
+    let output = minigrep(&["-r", "--timings", "--timings-limit=1", "match", root], "");
+    assert_eq!(stderr(&output).lines().count(), 2);
+}
+
+#[test]
+fn progress_is_not_drawn_unless_stderr_is_a_terminal() {
+    let dir = fixture_dir("progress");
+    fs::write(dir.join("a.txt"), "match\n").unwrap();
+    fs::write(dir.join("b.txt"), "other\nmatch\n").unwrap();
+    let root = dir.to_str().unwrap();
+
+    let plain = minigrep(&["-r", "--sort=path", "match", root], "");
+    let output = minigrep(&["-r", "--sort=path", "--progress", "match", root], "");
+    assert_eq!(stdout(&output), stdout(&plain));
+    assert_eq!(stderr(&output), "");
+}
+
+/// `grep`'s output for each set of context options, on the lines of
+/// [`context_groups_merge_like_grep`], with the lines joined by `|`.
+const CONTEXT_CASES: [(&[&str], &str); 12] = [
+    // m1's after-context touches m2's before-context exactly.
+    (&["-C", "1"], "m1|a|b|m2|c|--|e|m3|m4|f|--|i|m5|j|"),
+    // m1's after-context overlaps m2's before-context by one line.
+    (&["-C", "2"], "m1|a|b|m2|c|d|e|m3|m4|f|g|h|i|m5|j|"),
+    // m4 falls inside m3's after-context.
+    (&["-A", "1"], "m1|a|--|m2|c|--|m3|m4|f|--|m5|j|"),
+    (&["-A", "2"], "m1|a|b|m2|c|d|--|m3|m4|f|g|--|m5|j|"),
+    (&["-B", "1"], "m1|--|b|m2|--|e|m3|m4|--|i|m5|"),
+    (&["-B", "3"], "m1|a|b|m2|c|d|e|m3|m4|--|g|h|i|m5|"),
+    (
+        &["-A", "1", "-B", "2"],
+        "m1|a|b|m2|c|d|e|m3|m4|f|--|h|i|m5|j|",
+    ),
+    (&["-C", "1", "-A", "0"], "m1|--|b|m2|--|e|m3|m4|--|i|m5|"),
+    (&["-A", "0"], "m1|--|m2|--|m3|m4|--|m5|"),
+    (&["-v", "-A", "1"], "a|b|m2|c|d|e|m3|--|f|g|h|i|m5|j|"),
+    (
+        &["-A", "1", "--group-separator=##"],
+        "m1|a|##|m2|c|##|m3|m4|f|##|m5|j|",
+    ),
+    (
+        &["-A", "1", "--no-group-separator"],
+        "m1|a|m2|c|m3|m4|f|m5|j|",
+    ),
+];
+
+#[test]
+fn context_groups_merge_like_grep() {
+    let input = "m1\na\nb\nm2\nc\nd\ne\nm3\nm4\nf\ng\nh\ni\nm5\nj\n";
+    for (options, expected) in CONTEXT_CASES {
+        let args = [options, &["m", "-"]].concat();
+        let output = minigrep(&args, input);
+        assert_eq!(stdout(&output).replace('\n', "|"), expected, "{options:?}");
+    }
+}
+
+#[test]
+fn context_lines_are_marked_and_groups_separated_across_files() {
+    let dir = fixture_dir("context_files");
+    fs::write(dir.join("a.txt"), "x\nm\ny\n").unwrap();
+    fs::write(dir.join("b.txt"), "m\nz\n").unwrap();
+    let expected = "a.txt-1-x\na.txt:2:m\na.txt-3-y\n--\nb.txt:1:m\nb.txt-2-z\n";
+    for threads in supported_threads(&["--threads=1", "--threads=2"]) {
+        let args = [
+            "-n",
+            "-C",
+            "1",
+            threads,
+            "--sort=path",
+            "m",
+            "a.txt",
+            "b.txt",
+        ];
+        let mut command = command(&args);
+        command.current_dir(&dir);
+        assert_eq!(stdout(&run(command, "")), expected);
+    }
+
+    let mut command = command(&["--heading", "-B", "1", "m", "a.txt", "b.txt"]);
+    command.current_dir(&dir);
+    assert_eq!(stdout(&run(command, "")), "a.txt\nx\nm\n\nb.txt\nm\n");
+}
+
+#[test]
+fn stats_summary_goes_to_stderr() {
+    let dir = fixture_dir("stats");
+    fs::write(dir.join("a.txt"), "match\nother\nmatch again\n").unwrap();
+    fs::write(dir.join("b.txt"), "nothing\n").unwrap();
+    fs::write(dir.join("huge.txt"), "match\n".repeat(100)).unwrap();
+    let root = dir.to_str().unwrap();
+
+    let output = minigrep(&["-r", "--stats", "--max-filesize=100", "match", root], "");
+    let stderr = stderr(&output);
+
+    assert_eq!(stdout(&output).lines().count(), 2);
+    assert!(stderr.starts_with(
+        "2 files searched\n\
+         1 files skipped (1 too large, 0 filtered out, 0 unreadable)\n\
+         0 entries ignored\n\
+         4 lines scanned\n\
+         2 matched lines\n"
+    ));
+    assert!(stderr.trim_end().ends_with("seconds elapsed"));
+}
+
+#[test]
+fn stats_count_binary_files_skipped() {
+    let dir = fixture_dir("stats_binary");
+    fs::write(dir.join("a.txt"), "match\n").unwrap();
+    fs::write(dir.join("b.bin"), b"match\0\x7fELF\n").unwrap();
+    fs::write(dir.join("c.bin"), b"\0\0\0\0match\n").unwrap();
+    let root = dir.to_str().unwrap();
+
+    for threads in supported_threads(&["--threads=1", "--threads=3"]) {
+        let output = minigrep(&["-r", "--stats", threads, "match", root], "");
+        assert_eq!(stdout(&output), format!("{root}/a.txt:match\n"));
+        let stderr = stderr(&output);
+        assert!(
+            stderr.starts_with(
+                "1 files searched\n\
+                 2 files skipped (0 too large, 0 filtered out, 2 binary, 0 unreadable)\n\
+                 0 entries ignored\n\
+                 1 lines scanned\n\
+                 1 matched lines\n"
+            ),
+            "{threads}: {stderr}"
+        );
+    }
+}
+
+#[test]
+fn files_lists_what_would_be_searched() {
+    let dir = fixture_dir("files_mode");
+    fs::create_dir(dir.join("sub")).unwrap();
+    fs::write(dir.join("a.txt"), "a").unwrap();
+    fs::write(dir.join("sub/b.txt"), "b").unwrap();
+    fs::write(dir.join("big.txt"), "x".repeat(100)).unwrap();
+    let root = dir.to_str().unwrap();
+
+    let output = minigrep(&["--files", "-r", "--max-filesize=10", root], "");
+    assert_eq!(stdout(&output), format!("{root}/a.txt\n{root}/sub/b.txt\n"));
+
+    let output = minigrep(&["--files", "-r", "-Z", "--max-depth=1", root], "");
+    assert_eq!(stdout(&output), format!("{root}/a.txt\0{root}/big.txt\0"));
+}
+
+#[test]
+fn null_terminates_file_names() {
+    let path = fixture("null_names.txt", "match\n");
+    let path = path.to_str().unwrap();
+
+    let output = minigrep(&["-l", "-Z", "match", path], "");
+    assert_eq!(stdout(&output), format!("{path}\0"));
+
+    let output = minigrep(&["-H", "-n", "-Z", "match", path], "");
+    assert_eq!(stdout(&output), format!("{path}\01:match\n"));
+}
+
+#[test]
+fn quote_shell_escapes_file_names() {
+    let dir = fixture_dir("quote_shell");
+    fs::write(dir.join("plain.txt"), "match\n").unwrap();
+    fs::write(dir.join("it's here.txt"), "match\n").unwrap();
+    let quoted = |args: &[&str]| {
+        let args = [
+            &["--quote=shell"],
+            args,
+            &["match", "plain.txt", "it's here.txt"],
+        ]
+        .concat();
+        let mut command = command(&args);
+        command.current_dir(&dir);
+        stdout(&run(command, ""))
+    };
+
+    assert_eq!(quoted(&["-l"]), "plain.txt\n'it'\\''s here.txt'\n");
+    assert_eq!(
+        quoted(&["-n"]),
+        "plain.txt:1:match\n'it'\\''s here.txt':1:match\n"
+    );
+    assert_eq!(quoted(&["-c"]), "plain.txt:1\n'it'\\''s here.txt':1\n");
+}
+
+#[test]
+fn byte_offsets_follow_the_file_name_and_line_number() {
+    let path = fixture("byte_offsets.txt", "one\nmatch here\nmatch\n");
+    let path = path.to_str().unwrap();
+
+    let output = minigrep(&["-H", "-n", "-b", "match", path], "");
+    assert_eq!(
+        stdout(&output),
+        format!("{path}:2:4:match here\n{path}:3:15:match\n")
+    );
+
+    let output = minigrep(&["--color=always", "--byte-offset", "match", path], "");
+    assert_eq!(
+        stdout(&output),
+        "\x1b[32m4\x1b[0m:\x1b[01;31mmatch\x1b[0m here\n\
+         \x1b[32m15\x1b[0m:\x1b[01;31mmatch\x1b[0m\n"
+    );
+}
+
+#[test]
+fn passthru_prints_every_line_and_highlights_matches() {
+    let input = "start\nerror: disk\nok\n";
+    let output = minigrep(&["--passthru", "--color=always", "-n", "error", "-"], input);
+
+    assert_eq!(
+        stdout(&output),
+        "\x1b[32m1\x1b[0m-start\n\
+         \x1b[32m2\x1b[0m:\x1b[01;31merror\x1b[0m: disk\n\
+         \x1b[32m3\x1b[0m-ok\n"
+    );
+    assert_eq!(output.status.code(), Some(0));
+
+    let output = minigrep(&["--passthru", "missing", "-"], input);
+    assert_eq!(stdout(&output), input);
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn passthru_rejects_contradicting_modes() {
+    for flag in ["-v", "-c", "-l", "-o"] {
+        let output = minigrep(&["--passthru", flag, "error", "-"], "");
+        assert_eq!(output.status.code(), Some(2), "{flag}");
+    }
+}
+
+#[test]
+fn counts_cover_every_file_with_a_total() {
+    let dir = fixture_dir("counts");
+    fs::write(dir.join("a.log"), "error one\nok\nerror two\nerror three\n").unwrap();
+    fs::write(dir.join("b.log"), "ok\nfine\n").unwrap();
+    fs::write(dir.join("c.log"), "error: error twice\n").unwrap();
+    let counts = |args: &[&str]| {
+        let args = [args, &["error", "a.log", "b.log", "c.log"]].concat();
+        let mut command = command(&args);
+        command.current_dir(&dir);
+        stdout(&run(command, ""))
+    };
+
+    let golden: [(&[&str], &str); 5] = [
+        (&["-c"], "a.log:3\nb.log:0\nc.log:1\n"),
+        (
+            &["-c", "--count-total"],
+            "a.log:3\nb.log:0\nc.log:1\ntotal:4\n",
+        ),
+        (&["-c", "-v"], "a.log:1\nb.log:2\nc.log:0\n"),
+        (&["-c", "-l"], "a.log:3\nc.log:1\n"),
+        (&["-c", "-L", "--count-total"], "b.log:0\ntotal:4\n"),
+    ];
+    for (args, expected) in golden {
+        assert_eq!(counts(args), expected, "{args:?}");
+    }
+    if cfg!(feature = "parallel") {
+        assert_eq!(
+            counts(&["-c", "--threads=3", "--sort=path"]),
+            "a.log:3\nb.log:0\nc.log:1\n"
+        );
+    }
+}
+
+#[test]
+fn count_matches_totals_cover_every_file() {
+    let dir = fixture_dir("count_matches");
+    fs::write(dir.join("a.log"), "error error error\nok\n").unwrap();
+    fs::write(dir.join("b.log"), "ok\n").unwrap();
+    let mut command = command(&[
+        "--count-matches",
+        "--count-total",
+        "error",
+        "a.log",
+        "b.log",
+    ]);
+    command.current_dir(&dir);
+    assert_eq!(stdout(&run(command, "")), "a.log:3\nb.log:0\ntotal:3\n");
+}
+
+#[test]
+fn line_numbers_only_prints_numbers_or_ranges() {
+    let input = "rust\nrust\nno\nrust\nrust\nrust\nno\nrust\n";
+    let output = minigrep(&["--line-numbers-only", "rust", "-"], input);
+    assert_eq!(stdout(&output), "1\n2\n4\n5\n6\n8\n");
+    let output = minigrep(&["--line-numbers-only", "--ranges", "rust", "-"], input);
+    assert_eq!(stdout(&output), "1-2\n4-6\n8\n");
+    let output = minigrep(
+        &["--line-numbers-only", "--ranges", "-v", "rust", "-"],
+        input,
+    );
+    assert_eq!(stdout(&output), "3\n7\n");
+
+    let dir = fixture_dir("line_numbers_only");
+    fs::write(dir.join("a.txt"), "rust\nrust\n").unwrap();
+    fs::write(dir.join("b.txt"), "no\nrust\n").unwrap();
+    let mut command = command(&["--line-numbers-only", "--ranges", "rust", "a.txt", "b.txt"]);
+    command.current_dir(&dir);
+    assert_eq!(stdout(&run(command, "")), "a.txt:1-2\nb.txt:2\n");
+}
+
+#[test]
+fn unique_counts_aggregate_matches_across_files() {
+    let dir = fixture_dir("unique_counts");
+    fs::write(dir.join("a.log"), "Error: disk\nERROR ERROR\nok\n").unwrap();
+    fs::write(dir.join("b.log"), "error again\nwarn\nerror\n").unwrap();
+    let tally = |args: &[&str], case: &[&str]| {
+        let args = [&["-o", "--unique-counts"], args, &["a.log", "b.log"], case].concat();
+        let mut command = command(&args);
+        command.current_dir(&dir);
+        stdout(&run(command, ""))
+    };
+
+    assert_eq!(tally(&["error"], &["/i"]), "5 Error\n");
+    if cfg!(feature = "parallel") {
+        assert_eq!(
+            tally(&["--threads=2", "--sort=path", "error"], &["/i"]),
+            "5 Error\n"
+        );
+    }
+    assert_eq!(tally(&["rror"], &["/i"]), "5 rror\n");
+    assert_eq!(tally(&["r"], &[]), "10 r\n");
+    assert_eq!(tally(&["E"], &[]), "3 E\n");
+
+    let input = "b a c b c d c\n".repeat(4);
+    let output = minigrep(
+        &["-o", "--unique-counts", "--replace=[$0]", " ", "-"],
+        &input,
+    );
+    assert_eq!(stdout(&output), "24 [ ]\n");
+    let output = minigrep(&["-o", "--unique-counts", "c", "-", "--top=1"], &input);
+    assert_eq!(stdout(&output), "12 c\n");
+}
+
+#[test]
+fn exit_status_reflects_matches() {
+    assert_eq!(minigrep(&["rust", "-"], "rust\n").status.code(), Some(0));
+    assert_eq!(minigrep(&["rust", "-"], "go\n").status.code(), Some(1));
+    assert_eq!(
+        minigrep(&["rust", "/no/such/file"], "").status.code(),
+        Some(2)
+    );
+}
+
+#[test]
+fn required_counts_decide_the_exit_status() {
+    let input = "marker one\nmarker two\nother\n";
+    let status = |args: &[&str]| minigrep(args, input).status.code();
+
+    assert_eq!(status(&["--require-count=2", "marker", "-"]), Some(0));
+    assert_eq!(status(&["--require-match", "marker", "-"]), Some(0));
+    let output = minigrep(&["--require-count=3", "marker", "-"], input);
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(stdout(&output), "marker one\nmarker two\n");
+    assert_eq!(
+        stderr(&output),
+        "minigrep: found 2 matching lines, 3 required\n"
+    );
+    let output = minigrep(&["--require-match", "missing", "-"], input);
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(
+        stderr(&output),
+        "minigrep: found 0 matching lines, 1 required\n"
+    );
+
+    // Silent assertions print nothing at all.
+    for (args, code) in [
+        (&["-q", "--require-count=5", "marker", "-"][..], 3),
+        (&["-q", "--require-count=2", "marker", "-"], 0),
+        (&["-q", "--require-match", "missing", "-"], 3),
+        (&["-q", "-c", "--require-match", "marker", "-"], 0),
+        (&["--quiet", "marker", "-"], 0),
+        (&["-q", "missing", "-"], 1),
+    ] {
+        let output = minigrep(args, input);
+        assert_eq!(output.status.code(), Some(code), "{args:?}");
+        assert_eq!(
+            (stdout(&output).as_str(), stderr(&output).as_str()),
+            ("", ""),
+            "{args:?}"
+        );
+    }
+
+    // Inputs that cannot be searched may be why too few lines were found.
+    let missing = ["--require-match", "marker", "/no/such/file"];
+    assert_eq!(status(&missing), Some(2));
+    let output = minigrep(&["-q", "--require-match", "marker", "/no/such/file"], input);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).starts_with("minigrep: /no/such/file:"));
+}
+
+#[test]
+fn replace_substitutes_in_output_only() {
+    let path = fixture("replace.txt", "Rust and rust\nplain\n");
+    let path = path.to_str().unwrap();
+
+    let output = minigrep(&["--replace=[$0]", "-n", "rust", path, "/i"], "");
+    assert_eq!(stdout(&output), "1:[Rust] and [rust]\n");
+    assert_eq!(fs::read_to_string(path).unwrap(), "Rust and rust\nplain\n");
+
+    let output = minigrep(&["-o", "--replace=<$0|$$>", "rust", path, "/i"], "");
+    assert_eq!(stdout(&output), "<Rust|$>\n<rust|$>\n");
+}
+
+#[test]
+fn replace_highlights_the_replacement() {
+    let output = minigrep(
+        &["--color=always", "--replace=go", "rust", "-"],
+        "a rust b\n",
+    );
+    assert_eq!(stdout(&output), "a \x1b[01;31mgo\x1b[0m b\n");
+}
+
+#[test]
+fn in_place_rewrites_files_with_backups() {
+    let dir = fixture_dir("in-place");
+    fs::write(dir.join("a.txt"), "rust and rust\nplain\n").unwrap();
+    fs::write(dir.join("b.txt"), "plain\n").unwrap();
+    let a = dir.join("a.txt");
+    let b = dir.join("b.txt");
+
+    let output = minigrep(
+        &[
+            "--in-place",
+            "--replace=go",
+            "rust",
+            a.to_str().unwrap(),
+            b.to_str().unwrap(),
+        ],
+        "",
+    );
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        stdout(&output),
+        format!(
+            "{}: 2 replacements\n{}: 0 replacements\n",
+            a.display(),
+            b.display()
+        )
+    );
+    assert_eq!(fs::read_to_string(&a).unwrap(), "go and go\nplain\n");
+    assert_eq!(
+        fs::read_to_string(dir.join("a.txt.bak")).unwrap(),
+        "rust and rust\nplain\n"
+    );
+    assert!(!dir.join("b.txt.bak").exists());
+
+    let output = minigrep(
+        &[
+            "--in-place",
+            "--no-backup",
+            "--replace=c",
+            "go",
+            "-r",
+            dir.to_str().unwrap(),
+        ],
+        "",
+    );
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(fs::read_to_string(&a).unwrap(), "c and c\nplain\n");
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 3);
+}
+
+#[test]
+fn in_place_refusals() {
+    let dir = fixture_dir("in-place-refusals");
+    let dir = dir.to_str().unwrap();
+
+    let output = minigrep(&["--in-place", "--replace=go", "rust", dir], "");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).contains("is a directory (use -r"));
+
+    let output = minigrep(&["--in-place", "--replace=go", "rust", "-"], "rust\n");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).contains("--in-place cannot be used with standard input"));
+
+    for conflict in ["-v", "-o"] {
+        let output = minigrep(&["--in-place", "--replace=go", conflict, "rust", dir], "");
+        assert_eq!(output.status.code(), Some(2));
+        assert!(stderr(&output).contains(&format!("--in-place cannot be used with {conflict}")));
+    }
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn sort_by_path_is_deterministic_in_parallel() {
+    let dir = fixture_dir("sort");
+    for name in ["b.txt", "a.txt", "C.txt", "a-b.txt", "c.txt"] {
+        fs::write(dir.join(name), "rust\n".repeat(100)).unwrap();
+    }
+    let b = dir.join("b.txt");
+    let a = dir.join("a.txt");
+    let dir = dir.to_str().unwrap();
+
+    let output = minigrep(&["-c", "-j", "4", "--sort=path", "rust", "-r", dir], "");
+    let names: Vec<String> = stdout(&output)
+        .lines()
+        .map(|line| line.rsplit_once(':').unwrap().0.to_string())
+        .collect();
+    let expected: Vec<String> = ["C.txt", "a-b.txt", "a.txt", "b.txt", "c.txt"]
+        .iter()
+        .map(|name| format!("{dir}/{name}"))
+        .collect();
+    assert_eq!(names, expected);
+
+    let args = [
+        "-n",
+        "--sort=path",
+        "rust",
+        b.to_str().unwrap(),
+        a.to_str().unwrap(),
+    ];
+    let output = minigrep(&args, "");
+    let lines: Vec<&str> = std::str::from_utf8(&output.stdout)
+        .unwrap()
+        .lines()
+        .collect();
+    assert_eq!(lines.len(), 200);
+    assert!(lines[0].ends_with("a.txt:1:rust"));
+    assert!(lines[99].ends_with("a.txt:100:rust"));
+    assert!(lines[100].ends_with("b.txt:1:rust"));
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn parallel_search_without_sort_finds_everything() {
+    let dir = fixture_dir("parallel");
+    for index in 0..20 {
+        fs::write(dir.join(format!("{index}.txt")), "rust\nplain\n").unwrap();
+    }
+
+    let output = minigrep(&["-j", "8", "rust", "-r", dir.to_str().unwrap()], "");
+    assert_eq!(output.status.code(), Some(0));
+    let mut lines: Vec<String> = stdout(&output).lines().map(String::from).collect();
+    lines.sort();
+    let mut expected: Vec<String> = (0..20)
+        .map(|index| format!("{}:rust", dir.join(format!("{index}.txt")).display()))
+        .collect();
+    expected.sort();
+    assert_eq!(lines, expected);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_schema_snapshot() {
+    let output = minigrep(
+        &["--json", "--label=in", "rust", "-"],
+        "rust and rust\nnothing\n\"quoted\" rust\t\n",
+    );
+    assert_eq!(
+        stdout(&output),
+        concat!(
+            r#"{"type":"begin","path":"in"}"#,
+            "\n",
+            r#"{"type":"match","path":"in","line_number":1,"absolute_offset":0,"text":"rust and rust","submatches":[{"start":0,"end":4},{"start":9,"end":13}]}"#,
+            "\n",
+            r#"{"type":"match","path":"in","line_number":3,"absolute_offset":22,"text":"\"quoted\" rust\t","submatches":[{"start":9,"end":13}]}"#,
+            "\n",
+            r#"{"type":"end","path":"in","lines_scanned":3,"matched_lines":2,"bytes_searched":37}"#,
+            "\n",
+            r#"{"type":"summary","files_searched":1,"files_skipped":0,"lines_scanned":3,"matched_lines":2}"#,
+            "\n",
+        )
+    );
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_event_sequence_snapshot() {
+    let dir = fixture_dir("json_events");
+    fs::write(dir.join("a.txt"), "rust\nskip\nmore rust\n").unwrap();
+    fs::write(dir.join("b.txt"), "nothing here\n").unwrap();
+    fs::write(dir.join("c.txt"), "intro\r\nrust, rust\n").unwrap();
+
+    let mut command = command(&["--json", "rust", "a.txt", "b.txt", "c.txt"]);
+    command.current_dir(&dir);
+    let events = stdout(&run(command, ""));
+    assert_eq!(
+        events,
+        concat!(
+            r#"{"type":"begin","path":"a.txt"}"#,
+            "\n",
+            r#"{"type":"match","path":"a.txt","line_number":1,"absolute_offset":0,"text":"rust","submatches":[{"start":0,"end":4}]}"#,
+            "\n",
+            r#"{"type":"match","path":"a.txt","line_number":3,"absolute_offset":10,"text":"more rust","submatches":[{"start":5,"end":9}]}"#,
+            "\n",
+            r#"{"type":"end","path":"a.txt","lines_scanned":3,"matched_lines":2,"bytes_searched":20}"#,
+            "\n",
+            r#"{"type":"begin","path":"c.txt"}"#,
+            "\n",
+            r#"{"type":"match","path":"c.txt","line_number":2,"absolute_offset":7,"text":"rust, rust","submatches":[{"start":0,"end":4},{"start":6,"end":10}]}"#,
+            "\n",
+            r#"{"type":"end","path":"c.txt","lines_scanned":2,"matched_lines":1,"bytes_searched":18}"#,
+            "\n",
+            r#"{"type":"summary","files_searched":3,"files_skipped":0,"lines_scanned":6,"matched_lines":3}"#,
+            "\n",
+        )
+    );
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_reports_errors_as_events() {
+    let dir = fixture_dir("json_errors");
+    fs::create_dir(dir.join("sub")).unwrap();
+    fs::write(dir.join("big.txt"), "rust\n".repeat(20)).unwrap();
+    fs::write(dir.join("sub/bad.txt"), b"rust \xff\n").unwrap();
+
+    let mut search = command(&["--json", "rust", "missing.txt", "sub"]);
+    search.current_dir(&dir);
+    let output = run(search, "");
+    let printed = stdout(&output);
+    let events: Vec<&str> = printed.lines().collect();
+    assert_eq!(
+        events[0],
+        r#"{"type":"error","path":"missing.txt","kind":"not_found","message":"No such file or directory (os error 2)"}"#
+    );
+    assert_eq!(
+        events[1],
+        r#"{"type":"error","path":"sub","kind":"is_directory","message":"is a directory (use -r to search recursively)"}"#
+    );
+    assert!(stderr(&output).contains("minigrep: missing.txt: No such file or directory"));
+
+    let mut search = command(&[
+        "--json",
+        "-r",
+        "--max-filesize=50",
+        "--sort=path",
+        "rust",
+        ".",
+    ]);
+    search.current_dir(&dir);
+    let events = stdout(&run(search, ""));
+    let events: Vec<&str> = events.lines().collect();
+    assert_eq!(
+        events[0],
+        r#"{"type":"error","path":"./big.txt","kind":"too_large","message":"larger than the maximum file size"}"#
+    );
+    assert!(events[1].starts_with(r#"{"type":"begin","path":"./sub/bad.txt"}"#));
+    assert!(events[3].starts_with(r#"{"type":"end","path":"./sub/bad.txt""#));
+    assert_eq!(
+        events[4],
+        r#"{"type":"error","path":"./sub/bad.txt","kind":"invalid_utf8","message":"invalid UTF-8 was replaced with U+FFFD"}"#
+    );
+}
+
+#[cfg(all(unix, feature = "json"))]
+#[test]
+fn json_reports_permission_errors() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = fixture("json_denied.txt", "rust\n");
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o000)).unwrap();
+    // Permissions do not stop a superuser reading, so there is no error.
+    if fs::File::open(&path).is_ok() {
+        return;
+    }
+    let path = path.to_str().unwrap();
+    let output = minigrep(&["--json", "rust", path], "");
+    let events = stdout(&output);
+    assert_eq!(
+        events.lines().next().unwrap(),
+        format!(
+            r#"{{"type":"error","path":"{path}","kind":"permission_denied","message":"Permission denied (os error 13)"}}"#
+        )
+    );
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_reports_binary_files_as_skipped() {
+    let path = byte_fixture("json_binary.bin", b"rust\0\x01\x02\nrust\n");
+    let output = minigrep(&["--json", "rust", &path], "");
+    let events = stdout(&output);
+    let events: Vec<&str> = events.lines().collect();
+    assert_eq!(
+        events[0],
+        format!(
+            r#"{{"type":"error","path":"{path}","kind":"binary_skipped","message":"binary file"}}"#
+        )
+    );
+    assert!(
+        events[1].starts_with(r#"{"type":"summary""#),
+        "{}",
+        events[1]
+    );
+    assert_eq!(stderr(&output), "");
+    assert_eq!(output.status.code(), Some(1));
+
+    let output = minigrep(&["-z", "rust", &path], "");
+    assert_eq!(stdout(&output), "rust\0\x01\x02\nrust\n\0");
+}
+
+#[test]
+fn output_format_prints_quoted_tables() {
+    let input = b"plain rust\n  rust, \"quoted\" \nnothing\nbad \xff\trust\n";
+    let path = byte_fixture("table.txt", input);
+
+    let output = minigrep(&["--output-format=csv", "rust", &path], "");
+    assert_eq!(
+        stdout(&output),
+        format!(
+            "path,line_number,column,text\n\
+             {path},1,7,plain rust\n\
+             {path},2,3,\"  rust, \"\"quoted\"\" \"\n\
+             {path},4,7,bad �\trust\n"
+        )
+    );
+    assert_eq!(output.status.code(), Some(0));
+
+    let output = minigrep(
+        &["--output-format=tsv", "--no-header", "-v", "rust", &path],
+        "",
+    );
+    assert_eq!(stdout(&output), format!("{path}\t3\t\tnothing\n"));
+
+    let output = minigrep(&["--output-format=tsv", "--no-header", "bad", &path], "");
+    assert_eq!(stdout(&output), format!("{path}\t4\t1\tbad �\\trust\n"));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn sarif_document_shape() {
+    let dir = fixture_dir("sarif");
+    fs::create_dir(dir.join("src")).unwrap();
+    fs::write(dir.join("src/a.rs"), "// TODO: one\nlet é = 1; // TODO\n").unwrap();
+    fs::write(dir.join("src/b.rs"), "done\n").unwrap();
+
+    for threads in supported_threads(&["--threads=1", "--threads=2"]) {
+        let mut command = command(&[threads, "--output-format=sarif", "-r", "TODO", "src"]);
+        command.current_dir(&dir);
+        let output = run(command, "");
+        assert_eq!(output.status.code(), Some(0));
+        let document = stdout(&output);
+        assert_eq!(document.lines().count(), 1);
+        assert_eq!(
+            document,
+            concat!(
+                r#"{"$schema":"https://json.schemastore.org/sarif-2.1.0.json","version":"2.1.0","#,
+                r#""runs":[{"tool":{"driver":{"name":"minigrep","version":""#,
+                env!("CARGO_PKG_VERSION"),
+                r#"","rules":[{"id":"pattern-1","shortDescription":{"text":"Matches of \"TODO\""}}]}},"#,
+                r#""columnKind":"unicodeCodePoints","results":["#,
+                r#"{"ruleId":"pattern-1","ruleIndex":0,"level":"warning","message":{"text":"TODO"},"#,
+                r#""locations":[{"physicalLocation":{"artifactLocation":{"uri":"src/a.rs"},"#,
+                r#""region":{"startLine":1,"startColumn":4,"endColumn":8}}}]},"#,
+                r#"{"ruleId":"pattern-1","ruleIndex":0,"level":"warning","message":{"text":"TODO"},"#,
+                r#""locations":[{"physicalLocation":{"artifactLocation":{"uri":"src/a.rs"},"#,
+                r#""region":{"startLine":2,"startColumn":15,"endColumn":19}}}]}"#,
+                "]}]}\n",
+            ),
+            "{threads}"
+        );
+    }
+
+    // Each pattern of -e is a rule of its own, labeling its matches.
+    let mut patterns = command(&[
+        "--output-format=sarif",
+        "-e",
+        "let",
+        "-e",
+        "TODO",
+        "src/a.rs",
+    ]);
+    patterns.current_dir(&dir);
+    let document = stdout(&run(patterns, ""));
+    assert!(
+        document.contains(concat!(
+            r#""rules":[{"id":"pattern-1","shortDescription":{"text":"Matches of \"let\""}},"#,
+            r#"{"id":"pattern-2","shortDescription":{"text":"Matches of \"TODO\""}}]"#,
+        )),
+        "{document}"
+    );
+    let labels: Vec<_> = document
+        .split(r#"{"ruleId":"#)
+        .skip(1)
+        .map(|result| result.split(r#","level""#).next().unwrap())
+        .collect();
+    assert_eq!(
+        labels,
+        [
+            r#""pattern-2","ruleIndex":1"#,
+            r#""pattern-1","ruleIndex":0"#,
+            r#""pattern-2","ruleIndex":1"#,
+        ]
+    );
+
+    let mut command = command(&["--output-format=sarif", "missing", "src/b.rs"]);
+    command.current_dir(&dir);
+    let output = run(command, "");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).contains(r#""results":[]}]}"#));
+}
+
+#[test]
+fn format_templates_render_every_match() {
+    let dir = fixture_dir("format");
+    fs::write(dir.join("notes.md"), "intro\nTODO: a {TODO}\ndone\n").unwrap();
+    let search = |args: &[&str]| {
+        let mut command = command(args);
+        command.current_dir(&dir);
+        run(command, "")
+    };
+
+    let templates = [
+        (
+            "{path}:{line}: {text}",
+            "notes.md:2: TODO: a {TODO}\nnotes.md:2: TODO: a {TODO}\n",
+        ),
+        (
+            "| {path} | {line}:{column} | `{match}` |",
+            "| notes.md | 2:1 | `TODO` |\n| notes.md | 2:10 | `TODO` |\n",
+        ),
+        (
+            "[[file:{path}::{line}][{match}]]",
+            "[[file:notes.md::2][TODO]]\n[[file:notes.md::2][TODO]]\n",
+        ),
+        ("{byte} {{{match}}}", "6 {TODO}\n15 {TODO}\n"),
+    ];
+    for (template, expected) in templates {
+        let format = format!("--format={template}");
+        assert_eq!(stdout(&search(&[&format, "TODO", "notes.md"])), expected);
+    }
+
+    let output = search(&[
+        "--format={line}:{column}:{match}:{byte}",
+        "-v",
+        "TODO",
+        "notes.md",
+    ]);
+    assert_eq!(stdout(&output), "1:::0\n3:::21\n");
+
+    let output = search(&["--format={line", "TODO", "notes.md"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).contains("Invalid template '{line': unclosed '{'"));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_represents_invalid_utf8_lossily() {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("invalid-utf8.txt");
+    fs::write(&path, b"ok rust\nbad \xff rust\r\n").unwrap();
+
+    let output = command(&["--json", "rust", path.to_str().unwrap()])
+        .output()
+        .unwrap();
+    let lines: Vec<String> = stdout(&output).lines().map(String::from).collect();
+    assert_eq!(lines.len(), 6);
+    assert!(!lines[1].contains("lossy"));
+    assert!(lines[4].contains(r#""kind":"invalid_utf8""#));
+    assert!(lines[2].ends_with(
+        r#""line_number":2,"absolute_offset":8,"text":"bad � rust","submatches":[{"start":8,"end":12}],"lossy":true}"#
+    ));
+}
+
+#[cfg(not(feature = "json"))]
+#[test]
+fn json_is_rejected_without_its_feature() {
+    let output = minigrep(&["--json", "rust", "-"], "rust\n");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        stderr(&output)
+            .contains("--json is not available: minigrep was built without json support")
+    );
+    assert_eq!(stdout(&output), "");
+}
+
+#[test]
+fn json_conflicts_with_color() {
+    let output = minigrep(&["--json", "--color=always", "rust", "-"], "rust\n");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).contains("--json cannot be used with --color"));
+}
+
+#[test]
+fn vimgrep_prints_every_match_with_its_character_column() {
+    let output = minigrep(
+        &["--vimgrep", "--label=notes.txt", "rust", "-"],
+        "plain\nrust, née rust, rust\n",
+    );
+    assert_eq!(
+        stdout(&output),
+        "notes.txt:2:1:rust, née rust, rust\n\
+         notes.txt:2:11:rust, née rust, rust\n\
+         notes.txt:2:17:rust, née rust, rust\n"
+    );
+}
+
+#[test]
+fn column_counts_characters_or_bytes_before_the_first_match() {
+    let input = "plain\nnaïve café rust, rust\n日本 rust\n";
+    let output = minigrep(&["--column", "rust", "-"], input);
+    assert_eq!(
+        stdout(&output),
+        "2:12:naïve café rust, rust\n3:4:日本 rust\n"
+    );
+    let output = minigrep(&["--byte-column", "rust", "-"], input);
+    assert_eq!(
+        stdout(&output),
+        "2:14:naïve café rust, rust\n3:8:日本 rust\n"
+    );
+    let output = minigrep(
+        &["--column", "-o", "--label=a.txt", "-H", "rust", "-"],
+        input,
+    );
+    assert_eq!(
+        stdout(&output),
+        "a.txt:2:12:rust\na.txt:2:18:rust\na.txt:3:4:rust\n"
+    );
+    let output = minigrep(&["--vimgrep", "--byte-column", "rust", "-"], "é rust\n");
+    assert_eq!(stdout(&output), "(standard input):1:4:é rust\n");
+}
+
+#[test]
+fn columns_are_colored_like_line_numbers() {
+    let output = minigrep(&["--column", "--color=always", "rust", "-"], "日本 rust\n");
+    assert_eq!(
+        stdout(&output),
+        "\x1b[32m1\x1b[0m:\x1b[32m4\x1b[0m:日本 \x1b[01;31mrust\x1b[0m\n"
+    );
+}
+
+/// Two files for the heading tests, each with two matching lines.
+fn heading_fixtures() -> (String, String) {
+    let dir = fixture_dir("heading");
+    fs::write(dir.join("a.txt"), "rust one\nplain\nrust two\n").unwrap();
+    fs::write(dir.join("b.txt"), "nothing\nrust three\n").unwrap();
+    let a = dir.join("a.txt").to_str().unwrap().to_string();
+    let b = dir.join("b.txt").to_str().unwrap().to_string();
+    (a, b)
+}
+
+#[test]
+fn heading_groups_lines_under_each_file() {
+    let (a, b) = heading_fixtures();
+
+    let output = minigrep(&["--heading", "-n", "rust", &a, &b], "");
+    assert_eq!(
+        stdout(&output),
+        format!("{a}\n1:rust one\n3:rust two\n\n{b}\n2:rust three\n")
+    );
+
+    if cfg!(feature = "parallel") {
+        let output = minigrep(&["--heading", "-j", "2", "--sort=path", "rust", &a, &b], "");
+        assert_eq!(
+            stdout(&output),
+            format!("{a}\nrust one\nrust two\n\n{b}\nrust three\n")
+        );
+    }
+
+    let output = minigrep(&["--heading", "--color=always", "three", &a, &b], "");
+    assert_eq!(
+        stdout(&output),
+        format!("\x1b[35m{b}\x1b[0m\nrust \x1b[01;31mthree\x1b[0m\n")
+    );
+}
+
+#[test]
+fn no_heading_keeps_the_prefix_format() {
+    let (a, b) = heading_fixtures();
+
+    let output = minigrep(&["--no-heading", "-n", "rust", &a, &b], "");
+    assert_eq!(
+        stdout(&output),
+        format!("{a}:1:rust one\n{a}:3:rust two\n{b}:2:rust three\n")
+    );
+}
+
+#[test]
+fn null_data_reads_and_prints_nul_terminated_records() {
+    let input = "./src/main.rs\0./notes\nwith rust\0./rust.txt\0./other\0";
+
+    let output = minigrep(&["-z", "rust", "-"], input);
+    assert_eq!(stdout(&output), "./notes\nwith rust\0./rust.txt\0");
+
+    let output = minigrep(&["-z", "-n", "-v", "rust", "-"], input);
+    assert_eq!(stdout(&output), concat!("1:./src/main.rs\0", "4:./other\0"));
+
+    let output = minigrep(&["-z", "-c", "rust", "-"], input);
+    assert_eq!(stdout(&output), "2\n");
+}
+
+/// Writes `bytes` to a fixture file named `name` and returns its path.
+fn byte_fixture(name: &str, bytes: &[u8]) -> String {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    fs::write(&path, bytes).unwrap();
+    path.to_str().unwrap().to_string()
+}
+
+#[test]
+fn encodings_are_detected_from_the_bom() {
+    let text = "service started\r\nerror: disk full\r\n";
+    let mut le = vec![0xff, 0xfe];
+    le.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+    let mut be = vec![0xfe, 0xff];
+    be.extend(text.encode_utf16().flat_map(u16::to_be_bytes));
+    let mut utf8 = b"\xef\xbb\xbf".to_vec();
+    utf8.extend(text.as_bytes());
+
+    for (name, bytes) in [
+        ("utf16le.log", le),
+        ("utf16be.log", be),
+        ("utf8bom.log", utf8),
+    ] {
+        let path = byte_fixture(name, &bytes);
+        let output = minigrep(&["-n", "error", &path], "");
+        assert_eq!(stdout(&output), "2:error: disk full\n", "{name}");
+    }
+}
+
+#[test]
+fn encoding_flag_reads_files_without_a_bom() {
+    let text = "error: naïve\n";
+    let le: Vec<u8> = text.encode_utf16().flat_map(u16::to_le_bytes).collect();
+    let be: Vec<u8> = text.encode_utf16().flat_map(u16::to_be_bytes).collect();
+    let fixtures = [
+        ("utf-16le", byte_fixture("nobom-utf16le.log", &le)),
+        ("utf-16be", byte_fixture("nobom-utf16be.log", &be)),
+        ("latin-1", byte_fixture("latin1.log", b"error: na\xefve\n")),
+        ("utf-8", byte_fixture("utf8.log", text.as_bytes())),
+    ];
+    for (encoding, path) in &fixtures {
+        let output = minigrep(&[&format!("--encoding={encoding}"), "naïve", path], "");
+        assert_eq!(stdout(&output), "error: naïve\n", "{encoding}");
+    }
+
+    let output = minigrep(&["error", &fixtures[2].1], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "error: na\u{fffd}ve\n");
+    assert!(stderr(&output).contains("invalid UTF-8"));
+}
+
+#[test]
+fn invalid_utf8_does_not_stop_the_rest_of_the_file() {
+    let path = byte_fixture(
+        "mixed-utf8.log",
+        b"ok: start\nbad: \xff\xfe here\nok: middle\nbad \xc3\nok: end\n",
+    );
+
+    let output = minigrep(&["-n", "ok", &path], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "1:ok: start\n3:ok: middle\n5:ok: end\n");
+    let warning = format!("minigrep: {path}: invalid UTF-8 was replaced with U+FFFD\n");
+    assert_eq!(stderr(&output), warning);
+
+    let output = minigrep(&["here", &path], "");
+    assert_eq!(stdout(&output), "bad: \u{fffd}\u{fffd} here\n");
+    let output = minigrep(&["-c", "bad", &path], "");
+    assert_eq!(stdout(&output), "2\n");
+    assert_eq!(stderr(&output).lines().count(), 1);
+}
+
+#[test]
+fn type_filters_choose_files_by_extension() {
+    let dir = fixture_dir("types");
+    fs::create_dir(dir.join("src")).unwrap();
+    fs::write(dir.join("src/lib.rs"), "rust\n").unwrap();
+    fs::write(dir.join("build.py"), "rust\n").unwrap();
+    fs::write(dir.join("README.md"), "rust\n").unwrap();
+    let root = dir.to_str().unwrap();
+
+    let output = minigrep(
+        &["-l", "-r", "--type", "rust", "-t", "py", "rust", root],
+        "",
+    );
+    assert_eq!(
+        stdout(&output),
+        format!("{root}/build.py\n{root}/src/lib.rs\n")
+    );
+
+    let output = minigrep(
+        &["-l", "-r", "-T", "md", "--exclude=*.py", "rust", root],
+        "",
+    );
+    assert_eq!(stdout(&output), format!("{root}/src/lib.rs\n"));
+
+    let output = minigrep(&["-l", "-r", "--type=pyhton", "rust", root], "");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).contains("did you mean 'py'?"));
+}
+
+#[test]
+fn type_list_prints_the_table() {
+    let output = minigrep(&["--type-list"], "");
+    assert_eq!(output.status.code(), Some(0));
+    let table = stdout(&output);
+    assert!(table.starts_with("c: *.c, *.h\ncpp: "));
+    assert!(table.contains("\nrust: *.rs\n"));
+    assert!(table.ends_with("yaml: *.yaml, *.yml\n"));
+}
+
+#[test]
+fn recursion_skips_hidden_entries_unless_hidden_is_given() {
+    let dir = fixture_dir("hidden");
+    fs::create_dir_all(dir.join(".cache")).unwrap();
+    fs::write(dir.join(".cache/entry"), "rust\n").unwrap();
+    fs::write(dir.join(".rc"), "rust\n").unwrap();
+    fs::write(dir.join("visible.txt"), "rust\n").unwrap();
+    let root = dir.to_str().unwrap();
+
+    let output = minigrep(&["-l", "-r", "rust", root], "");
+    assert_eq!(stdout(&output), format!("{root}/visible.txt\n"));
+
+    let output = minigrep(&["-l", "-r", "--hidden", "rust", root], "");
+    assert_eq!(
+        stdout(&output),
+        format!("{root}/.cache/entry\n{root}/.rc\n{root}/visible.txt\n")
+    );
+
+    let rc = format!("{root}/.rc");
+    let output = minigrep(&["-l", "-r", "rust", &rc], "");
+    assert_eq!(stdout(&output), format!("{rc}\n"));
+}
+
+#[cfg(unix)]
+#[test]
+fn follow_traverses_links_and_survives_cycles() {
+    use std::os::unix::fs::symlink;
+
+    let dir = fixture_dir("follow");
+    fs::create_dir(dir.join("content")).unwrap();
+    fs::write(dir.join("content/page.txt"), "rust\n").unwrap();
+    fs::create_dir(dir.join("site")).unwrap();
+    symlink(dir.join("content"), dir.join("site/pages")).unwrap();
+    symlink(dir.join("site"), dir.join("site/pages/up")).unwrap();
+    symlink(dir.join("gone"), dir.join("site/dangling")).unwrap();
+    let site = dir.join("site");
+    let site = site.to_str().unwrap();
+
+    let output = minigrep(&["-r", "rust", site], "");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout(&output), "");
+
+    let output = minigrep(&["-r", "--follow", "rust", site], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), format!("{site}/pages/page.txt:rust\n"));
+    let stderr = stderr(&output);
+    assert!(stderr.contains(&format!("{site}/dangling: broken symbolic link")));
+    assert!(stderr.contains(&format!("{site}/pages/up: symbolic link cycle")));
+}
+
+#[test]
+fn recursion_respects_ignore_files_unless_no_ignore() {
+    let dir = fixture_dir("ignore_files");
+    fs::create_dir_all(dir.join("target/debug")).unwrap();
+    fs::create_dir(dir.join("src")).unwrap();
+    fs::write(dir.join(".gitignore"), "# build output\ntarget/\n*.log\n").unwrap();
+    fs::write(dir.join("src/.gitignore"), "!wanted.log\n").unwrap();
+    fs::write(dir.join("target/debug/out.rs"), "needle\n").unwrap();
+    fs::write(dir.join("build.log"), "needle\n").unwrap();
+    fs::write(dir.join("src/wanted.log"), "needle\n").unwrap();
+    fs::write(dir.join("src/main.rs"), "needle\n").unwrap();
+    let root = dir.to_str().unwrap();
+
+    let output = minigrep(&["-r", "--stats", "needle", root], "");
+    assert_eq!(
+        stdout(&output),
+        format!("{root}/src/main.rs:needle\n{root}/src/wanted.log:needle\n")
+    );
+    assert!(stderr(&output).contains("2 entries ignored\n"));
+
+    let output = minigrep(&["-r", "--no-ignore", "-l", "needle", root], "");
+    assert_eq!(stdout(&output).lines().count(), 4);
+}
+
+#[test]
+fn minigrepignore_applies_without_git_ignores() {
+    let dir = fixture_dir("minigrepignore");
+    fs::create_dir(dir.join("data")).unwrap();
+    fs::write(dir.join(".gitignore"), "*.log\n").unwrap();
+    fs::write(dir.join(".minigrepignore"), "data/\n").unwrap();
+    fs::write(dir.join("data/rows.csv"), "needle\n").unwrap();
+    fs::write(dir.join("run.log"), "needle\n").unwrap();
+    fs::write(dir.join("notes.txt"), "needle\n").unwrap();
+    let root = dir.to_str().unwrap();
+    let files = |args: &[&str]| {
+        let mut all = vec!["-r", "-l"];
+        all.extend(args);
+        all.extend(["needle", root]);
+        stdout(&minigrep(&all, "")).replace(&format!("{root}/"), "")
+    };
+
+    assert_eq!(files(&[]), "notes.txt\n");
+    assert_eq!(files(&["--no-ignore"]), "notes.txt\nrun.log\n");
+    assert_eq!(
+        files(&["--no-ignore-files"]),
+        "data/rows.csv\nnotes.txt\nrun.log\n"
+    );
+}
+
+#[test]
+fn absolute_path_joins_relative_names_to_the_current_directory() {
+    let dir = fixture_dir("absolute_path");
+    fs::create_dir(dir.join("src")).unwrap();
+    fs::write(dir.join("src/lib.rs"), "fn rust() {}\n").unwrap();
+    let absolute = dir.join("src/lib.rs");
+    let absolute = absolute.to_str().unwrap();
+    let search = |args: &[&str]| {
+        let mut command = command(args);
+        command.current_dir(&dir);
+        stdout(&run(command, ""))
+    };
+
+    let modes = [&["-H"][..], &["-l"], &["--vimgrep"], &["--json"]];
+    for mode in modes
+        .into_iter()
+        .filter(|mode| cfg!(feature = "json") || mode[0] != "--json")
+    {
+        let relative_args = [mode, &["rust", "src/lib.rs"]].concat();
+        let absolute_args = [mode, &["--absolute-path", "rust", "src/lib.rs"]].concat();
+        let relative = search(&relative_args);
+        let json_absolute = absolute.replace('\\', "\\\\");
+        assert!(relative.contains("src/lib.rs"), "{mode:?}");
+        assert_eq!(
+            search(&absolute_args),
+            relative.replace("src/lib.rs", &json_absolute),
+            "{mode:?}"
+        );
+    }
+
+    assert_eq!(
+        search(&["-r", "-l", "--absolute-path", "rust", "."]),
+        format!("{absolute}\n")
+    );
+    assert_eq!(
+        search(&["-l", "--absolute-path", "rust", absolute]),
+        format!("{absolute}\n")
+    );
+}
+
+#[test]
+fn piped_input_is_searched_without_a_file_path() {
+    let input = "rust is fast\nslow\ntrust\n";
+    let output = minigrep(&["rust"], input);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "rust is fast\ntrust\n");
+
+    let output = minigrep(&["-H", "-n", "rust", "-"], input);
+    assert_eq!(
+        stdout(&output),
+        "(standard input):1:rust is fast\n(standard input):3:trust\n"
+    );
+    let output = minigrep(&["-H", "--label=pipe", "slow"], input);
+    assert_eq!(stdout(&output), "pipe:slow\n");
+}
+
+#[test]
+fn standard_input_is_searched_as_it_arrives() {
+    use std::io::{BufRead, BufReader};
+
+    let mut child = command(&["--line-buffered", "needle"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+
+    // The first match is printed while the input is still open.
+    stdin.write_all(b"hay\nneedle one\n").unwrap();
+    let mut line = String::new();
+    stdout.read_line(&mut line).unwrap();
+    assert_eq!(line, "needle one\n");
+
+    stdin.write_all(b"needle two\n").unwrap();
+    drop(stdin);
+    line.clear();
+    stdout.read_line(&mut line).unwrap();
+    assert_eq!(line, "needle two\n");
+    assert!(child.wait().unwrap().success());
+}
+
+#[test]
+fn head_stops_reading_before_the_end_of_the_input() {
+    // The input is never closed, so the search only ends if it stops
+    // reading after the first lines.
+    let mut child = command(&["-n", "--skip-lines=1", "--head=3", "error"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    stdin
+        .write_all(
+            b"error header
+error 1
+ok
+error 2
+error 3
+",
+        )
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    drop(stdin);
+    assert_eq!(stdout(&output), "2:error 1\n");
+    assert!(output.status.success());
+}
+
+/// Peak memory should follow the longest line, not the size of the input:
+/// 64 MiB of lines are piped through, and the peak resident size is read
+/// while the process is still waiting for more.
+#[cfg(target_os = "linux")]
+#[test]
+fn peak_memory_stays_proportional_to_the_longest_line() {
+    use std::io::{BufRead, BufReader};
+
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("large.log");
+    let line = format!("{}\n", "x".repeat(1023));
+    let mut file = std::io::BufWriter::new(fs::File::create(&path).unwrap());
+    for _ in 0..64 * 1024 {
+        file.write_all(line.as_bytes()).unwrap();
+    }
+    file.write_all(b"needle\n").unwrap();
+    drop(file);
+
+    let mut child = command(&["--line-buffered", "needle"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    std::io::copy(&mut fs::File::open(&path).unwrap(), &mut stdin).unwrap();
+    let mut found = String::new();
+    BufReader::new(child.stdout.take().unwrap())
+        .read_line(&mut found)
+        .unwrap();
+    assert_eq!(found, "needle\n");
+
+    let status = fs::read_to_string(format!("/proc/{}/status", child.id())).unwrap();
+    let peak_kib: u64 = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmHWM:"))
+        .and_then(|value| value.trim().strip_suffix(" kB"))
+        .unwrap()
+        .parse()
+        .unwrap();
+    drop(stdin);
+    assert!(child.wait().unwrap().success());
+    assert!(peak_kib < 16 * 1024, "peak resident size {peak_kib} KiB");
+}
+
+#[test]
+fn last_line_without_a_newline_is_still_searched() {
+    let path = fixture("no-trailing-newline.txt", "first rust\nlast rust");
+    let output = minigrep(&["-n", "rust", path.to_str().unwrap()], "");
+    assert_eq!(stdout(&output), "1:first rust\n2:last rust\n");
+    let output = minigrep(&["-c", "rust", path.to_str().unwrap()], "");
+    assert_eq!(stdout(&output), "2\n");
+}
+
+#[test]
+fn directories_need_recursion() {
+    let dir = fixture_dir("directory_argument");
+    fs::write(dir.join("notes.txt"), "rust\n").unwrap();
+    let root = dir.to_str().unwrap();
+
+    let output = minigrep(&["rust", root], "");
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(stdout(&output), "");
+    assert!(
+        stderr(&output).contains(&format!(
+            "{root}: is a directory (use -r to search recursively)"
+        )),
+        "{}",
+        stderr(&output)
+    );
+    assert!(!stderr(&output).contains("os error"));
+
+    let output = minigrep(&["-r", "rust", root], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), format!("{root}/notes.txt:rust\n"));
+}
+
+#[test]
+fn unreadable_files_are_skipped_with_a_warning() {
+    let dir = fixture_dir("unreadable");
+    fs::write(dir.join("a.txt"), "rust\n").unwrap();
+    let a = dir.join("a.txt");
+    let missing = dir.join("missing.txt");
+    let (a, missing) = (a.to_str().unwrap(), missing.to_str().unwrap());
+
+    let output = minigrep(&["rust", missing, a], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), format!("{a}:rust\n"));
+    assert!(stderr(&output).starts_with(&format!("minigrep: {missing}: ")));
+
+    let output = minigrep(&["absent", missing, a], "");
+    assert_eq!(output.status.code(), Some(2));
+    let output = minigrep(&["absent", a], "");
+    assert_eq!(output.status.code(), Some(1));
+
+    let threads = if cfg!(feature = "parallel") { "2" } else { "1" };
+    let output = minigrep(&["-s", "-j", threads, "rust", missing, a, missing], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), format!("{a}:rust\n"));
+    assert_eq!(stderr(&output), "");
+}
+
+#[cfg(unix)]
+#[test]
+fn recursion_continues_past_files_without_permission() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = fixture_dir("no_permission");
+    fs::write(dir.join("a.txt"), "rust\n").unwrap();
+    fs::write(dir.join("b.txt"), "rust\n").unwrap();
+    fs::write(dir.join("c.txt"), "rust\n").unwrap();
+    fs::set_permissions(dir.join("b.txt"), fs::Permissions::from_mode(0o000)).unwrap();
+    if fs::read(dir.join("b.txt")).is_ok() {
+        // Running as root, where permissions are not enforced.
+        return;
+    }
+    let root = dir.to_str().unwrap();
+
+    let output = minigrep(&["-r", "--stats", "rust", root], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        stdout(&output),
+        format!("{root}/a.txt:rust\n{root}/c.txt:rust\n")
+    );
+    let stderr = stderr(&output);
+    assert!(stderr.contains(&format!("minigrep: {root}/b.txt: Permission denied")));
+    assert!(stderr.contains("1 files skipped (0 too large, 0 filtered out, 1 unreadable)"));
+}
+
+#[test]
+fn closed_output_ends_the_search_quietly() {
+    let path = fixture("many-matches.log", &"rust line\n".repeat(200_000));
+    let mut child = command(&["rust", path.to_str().unwrap()])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut first = [0; 10];
+    std::io::Read::read_exact(child.stdout.as_mut().unwrap(), &mut first).unwrap();
+    assert_eq!(&first, b"rust line\n");
+    drop(child.stdout.take());
+
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(output.status.code(), Some(141));
+    assert_eq!(stderr(&output), "");
+}
+
+#[cfg(unix)]
+#[test]
+fn grep_finds_what_the_command_line_prints() {
+    let dir = fixture_dir("grep_api");
+    fs::create_dir_all(dir.join("sub")).unwrap();
+    fs::create_dir_all(dir.join(".hidden")).unwrap();
+    fs::write(dir.join("a.txt"), "TODO: one\nnothing\ntodo: two: three\n").unwrap();
+    fs::write(dir.join("sub/b.txt"), "done\nstill todo\n").unwrap();
+    fs::write(dir.join(".hidden/c.txt"), "TODO hidden\n").unwrap();
+    fs::write(dir.join(".minigrepignore"), "skipped.txt\n").unwrap();
+    fs::write(dir.join("skipped.txt"), "TODO ignored\n").unwrap();
+    let dir = dir.to_str().unwrap();
+    let missing = format!("{dir}/missing.txt");
+
+    let output = minigrep(&["-r", "-n", "TODO", dir, &missing, "/i"], "");
+    let mut printed: Vec<(String, usize, String)> = stdout(&output)
+        .lines()
+        .map(|line| {
+            let mut fields = line.splitn(3, ':');
+            let path = fields.next().unwrap().to_string();
+            let line_number = fields.next().unwrap().parse().unwrap();
+            (path, line_number, fields.next().unwrap().to_string())
+        })
+        .collect();
+    printed.sort();
+
+    let options = Options::default().recursive(true).ignore_case(true);
+    let errors = grep(&[dir, &missing], "TODO", options.clone()).unwrap_err();
+    let mut found: Vec<(String, usize, String)> = grep(&[dir], "TODO", options)
+        .unwrap()
+        .iter()
+        .flat_map(|file| {
+            file.lines
+                .iter()
+                .map(|line| (file.path.clone(), line.line_number, line.text.clone()))
+        })
+        .collect();
+    found.sort();
+
+    assert_eq!(found, printed);
+    assert_eq!(found.len(), 3);
+    let warnings: Vec<String> = errors
+        .iter()
+        .map(|error| format!("minigrep: {error}"))
+        .collect();
+    assert_eq!(stderr(&output).lines().collect::<Vec<_>>(), warnings);
+    assert!(warnings[0].starts_with(&format!("minigrep: {missing}: ")));
+    assert!(matches!(
+        errors[..],
+        [error::MinigrepError::NotFound { .. }]
+    ));
+
+    let found = grep(&[dir], "todo", Options::default().recursive(true)).unwrap();
+    let paths: Vec<&str> = found.iter().map(|file| file.path.as_str()).collect();
+    assert_eq!(
+        stdout(&minigrep(&["-r", "-l", "todo", dir, "/s"], "")),
+        paths.join("\n") + "\n"
+    );
+}
+
+#[test]
+fn search_in_file_returns_the_lines_or_why_it_failed() {
+    let path = fixture("search_in_file.txt", "TODO: one\nnothing\ntodo: two\n");
+    let options = Options::default().ignore_case(true);
+    let found = search_in_file("todo", &path, &options).unwrap();
+    assert_eq!(found.path, path.to_str().unwrap());
+    assert_eq!(found.searched_lines, 3);
+    let lines: Vec<(usize, &str)> = found
+        .lines
+        .iter()
+        .map(|line| (line.line_number, line.text.as_str()))
+        .collect();
+    assert_eq!(lines, vec![(1, "TODO: one"), (3, "todo: two")]);
+    assert_eq!(found.lines[1].ranges, vec![0..4]);
+
+    let found = search_in_file("absent", &path, &options).unwrap();
+    assert_eq!((found.lines.len(), found.searched_lines), (0, 3));
+
+    let kind = |path: &PathBuf, options: &Options| {
+        search_in_file("todo", path, options).unwrap_err().kind()
+    };
+    let missing = path.with_extension("missing");
+    assert_eq!(kind(&missing, &options), error::ErrorKind::NotFound);
+    let dir = fixture_dir("search_in_file_dir");
+    assert_eq!(
+        kind(&dir, &options.clone().recursive(true)),
+        error::ErrorKind::IsDirectory
+    );
+    assert_eq!(
+        kind(&path, &options.clone().max_filesize(4)),
+        error::ErrorKind::TooLarge
+    );
+    let binary = PathBuf::from(byte_fixture("search_in_file.bin", b"todo\0\n"));
+    assert_eq!(kind(&binary, &options), error::ErrorKind::BinarySkipped);
+    #[cfg(unix)]
+    assert_eq!(
+        search_in_file("todo", "/dev/null", &options)
+            .unwrap_err()
+            .to_string(),
+        "/dev/null: device, FIFO, or socket"
+    );
+    let error = search_in_file("todo", &missing, &options).unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .starts_with(&format!("{}: ", missing.display()))
+    );
+}
+
+#[test]
+fn files_with_non_unicode_names_are_searched() {
+    use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+    let dir = fixture_dir("non_unicode_names");
+    let path = dir.join(OsStr::from_bytes(b"caf\xe9.txt"));
+    fs::write(&path, "rust\n").unwrap();
+    let shown = format!("{}/caf\u{fffd}.txt", dir.display());
+
+    let output = command(&["-H", "rust"]).arg(&path).output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), format!("{shown}:rust\n"));
+
+    let output = minigrep(&["-r", "-l", "rust", dir.to_str().unwrap()], "");
+    assert_eq!(stdout(&output), format!("{shown}\n"));
+}
+
+/// The CRC-32 of `data`, as gzip and zip files store it.
+#[cfg(any(feature = "gzip", feature = "zip"))]
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                0xedb8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// Compresses `data` into a gzip file of stored blocks of at most five
+/// bytes each, so that no longer text shows through uncompressed.
+#[cfg(feature = "gzip")]
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut compressed = vec![0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 3];
+    let mut chunks = data.chunks(5).peekable();
+    while let Some(chunk) = chunks.next() {
+        let length = chunk.len() as u16;
+        compressed.push(u8::from(chunks.peek().is_none()));
+        compressed.extend_from_slice(&length.to_le_bytes());
+        compressed.extend_from_slice(&(!length).to_le_bytes());
+        compressed.extend_from_slice(chunk);
+    }
+    compressed.extend_from_slice(&crc32(data).to_le_bytes());
+    compressed.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    compressed
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn gzip_files_are_searched_decompressed() {
+    let text = b"service started\nerror: disk full\nservice stopped\nerror: again\n";
+    let named = byte_fixture("app.log.1.gz", &gzip(text));
+    let sniffed = byte_fixture("app.log.2", &gzip(text));
+
+    let output = minigrep(&["-n", "error", &named], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "2:error: disk full\n4:error: again\n");
+    let output = minigrep(&["-c", "service", &sniffed], "");
+    assert_eq!(stdout(&output), "2\n");
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn recursion_decompresses_only_with_search_zip() {
+    let dir = fixture_dir("search_zip");
+    fs::write(dir.join("old.log.gz"), gzip(b"error: disk full\n")).unwrap();
+    fs::write(dir.join("new.log"), "disk ok\n").unwrap();
+    let root = dir.to_str().unwrap();
+
+    let output = minigrep(&["-r", "disk", root], "");
+    assert_eq!(stdout(&output), format!("{root}/new.log:disk ok\n"));
+
+    let output = minigrep(&["-r", "--search-zip", "disk", root], "");
+    assert_eq!(
+        stdout(&output),
+        format!("{root}/new.log:disk ok\n{root}/old.log.gz:error: disk full\n")
+    );
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn corrupt_gzip_files_are_skipped_with_a_warning() {
+    let mut compressed = gzip(b"error: disk full\nerror: again\n");
+    let length = compressed.len();
+    compressed[length - 8] ^= 0xff;
+    let corrupt = byte_fixture("corrupt.log.gz", &compressed);
+    let truncated = byte_fixture("truncated.log.gz", &compressed[..20]);
+    let plain = fixture("plain-neighbour.log", "error: fine\n");
+    let plain = plain.to_str().unwrap();
+
+    let output = minigrep(&["-c", "error", &corrupt, &truncated, plain], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout(&output).ends_with(&format!("{plain}:1\n")));
+    let stderr = stderr(&output);
+    let warnings: Vec<&str> = stderr.lines().collect();
+    assert_eq!(warnings.len(), 2, "{stderr}");
+    assert!(warnings[0].starts_with(&format!("minigrep: {corrupt}: corrupt gzip data: ")));
+    assert!(warnings[0].contains("checksum"), "{stderr}");
+    assert_eq!(
+        warnings[1],
+        format!("minigrep: {truncated}: unexpected end of compressed data")
+    );
+}
+
+/// "service started\nerror: disk full\nservice stopped\n" as `bzip2` and
+/// `xz` compress it.
+#[cfg(feature = "bzip2")]
+const BZIP2_LOG: &[u8] = &[
+    0x42, 0x5a, 0x68, 0x39, 0x31, 0x41, 0x59, 0x26, 0x53, 0x59, 0x4b, 0x60, 0xfc, 0x0b, 0x00, 0x00,
+    0x13, 0x59, 0x80, 0x00, 0x10, 0x40, 0x00, 0x00, 0x10, 0x2f, 0x2c, 0xdf, 0x00, 0x20, 0x00, 0x21,
+    0x2a, 0x06, 0x81, 0x88, 0x68, 0x53, 0x4c, 0x8c, 0x4c, 0x4c, 0x44, 0x37, 0x6d, 0xe4, 0x7e, 0xb1,
+    0x53, 0x4a, 0x5b, 0x8b, 0xd1, 0x20, 0x7a, 0xbc, 0x65, 0xb5, 0x05, 0x1b, 0xd3, 0x18, 0xe3, 0x8b,
+    0x79, 0xc5, 0x0f, 0x8b, 0xb9, 0x22, 0x9c, 0x28, 0x48, 0x25, 0xb0, 0x7e, 0x05, 0x80,
+];
+#[cfg(feature = "xz")]
+const XZ_LOG: &[u8] = &[
+    0xfd, 0x37, 0x7a, 0x58, 0x5a, 0x00, 0x00, 0x04, 0xe6, 0xd6, 0xb4, 0x46, 0x02, 0x00, 0x21, 0x01,
+    0x16, 0x00, 0x00, 0x00, 0x74, 0x2f, 0xe5, 0xa3, 0xe0, 0x00, 0x30, 0x00, 0x2b, 0x5d, 0x00, 0x39,
+    0x99, 0x4a, 0xac, 0x49, 0xd2, 0x3b, 0xd1, 0xc1, 0x69, 0xfc, 0x29, 0x82, 0xfe, 0xbc, 0xdc, 0x09,
+    0x4b, 0x78, 0x57, 0xfe, 0x56, 0xb5, 0x0f, 0x84, 0x49, 0x37, 0x62, 0xa5, 0x01, 0x5a, 0x16, 0xc3,
+    0x1c, 0xa1, 0x23, 0x01, 0xc6, 0x2a, 0x25, 0xce, 0x47, 0x00, 0x00, 0x00, 0xe6, 0x64, 0xf7, 0xc6,
+    0xd1, 0x0a, 0x1c, 0x01, 0x00, 0x01, 0x47, 0x31, 0xd3, 0x6c, 0x60, 0xce, 0x1f, 0xb6, 0xf3, 0x7d,
+    0x01, 0x00, 0x00, 0x00, 0x00, 0x04, 0x59, 0x5a,
+];
+
+#[cfg(feature = "bzip2")]
+#[test]
+fn bzip2_files_are_searched_decompressed() {
+    let named = byte_fixture("app.log.1.bz2", BZIP2_LOG);
+    let sniffed = byte_fixture("app.log.bz", BZIP2_LOG);
+
+    let output = minigrep(&["-n", "error", &named], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "2:error: disk full\n");
+    let output = minigrep(&["-c", "service", &sniffed], "");
+    assert_eq!(stdout(&output), "2\n");
+}
+
+#[cfg(feature = "xz")]
+#[test]
+fn xz_files_are_searched_decompressed() {
+    let named = byte_fixture("app.log.1.xz", XZ_LOG);
+    let sniffed = byte_fixture("app.log.lzma2", XZ_LOG);
+
+    let output = minigrep(&["-n", "error", &named], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "2:error: disk full\n");
+    let output = minigrep(&["-c", "service", &sniffed], "");
+    assert_eq!(stdout(&output), "2\n");
+}
+
+#[test]
+fn unknown_compressed_extensions_are_read_plainly() {
+    let path = fixture("app.log.zst", "error: not really zstd\n");
+
+    let output = command(&["error"]).arg(&path).output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "error: not really zstd\n");
+}
+
+#[cfg(all(feature = "bzip2", feature = "xz"))]
+#[test]
+fn corrupt_archives_are_skipped_with_a_warning() {
+    let mut compressed = BZIP2_LOG.to_vec();
+    compressed[20] ^= 0xff;
+    let bzip2 = byte_fixture("corrupt.log.bz2", &compressed);
+    let xz = byte_fixture("truncated.log.xz", &XZ_LOG[..40]);
+    let plain = fixture("archive-neighbour.log", "error: fine\n");
+    let plain = plain.to_str().unwrap();
+
+    let output = minigrep(&["-c", "error", &bzip2, &xz, plain], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout(&output).ends_with(&format!("{plain}:1\n")));
+    let stderr = stderr(&output);
+    assert!(stderr.starts_with(&format!("minigrep: {bzip2}: corrupt bzip2 data: ")));
+    assert!(stderr.ends_with(&format!(
+        "minigrep: {xz}: unexpected end of compressed data\n"
+    )));
+}
+
+/// Builds a zip archive of stored members from `(name, flags, data)`,
+/// where a flags value of 1 marks a member as encrypted.
+#[cfg(feature = "zip")]
+fn zip(members: &[(&[u8], u16, &[u8])]) -> Vec<u8> {
+    let mut archive = Vec::new();
+    let mut directory = Vec::new();
+    for &(name, flags, data) in members {
+        let offset = archive.len() as u32;
+        let mut fields = vec![20, 0];
+        fields.extend_from_slice(&flags.to_le_bytes());
+        fields.extend_from_slice(&[0; 6]);
+        fields.extend_from_slice(&crc32(data).to_le_bytes());
+        fields.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        fields.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        fields.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        fields.extend_from_slice(&[0; 2]);
+
+        archive.extend_from_slice(b"PK\x03\x04");
+        archive.extend_from_slice(&fields);
+        archive.extend_from_slice(name);
+        archive.extend_from_slice(data);
+
+        directory.extend_from_slice(b"PK\x01\x02\x14\x03");
+        directory.extend_from_slice(&fields);
+        directory.extend_from_slice(&[0; 10]);
+        directory.extend_from_slice(&offset.to_le_bytes());
+        directory.extend_from_slice(name);
+    }
+    let offset = archive.len() as u32;
+    let count = members.len() as u16;
+    archive.extend_from_slice(&directory);
+    archive.extend_from_slice(b"PK\x05\x06\0\0\0\0");
+    archive.extend_from_slice(&count.to_le_bytes());
+    archive.extend_from_slice(&count.to_le_bytes());
+    archive.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+    archive.extend_from_slice(&offset.to_le_bytes());
+    archive.extend_from_slice(&[0; 2]);
+    archive
+}
+
+#[cfg(feature = "zip")]
+#[test]
+fn zip_members_are_searched_with_search_zip() {
+    let bundle = byte_fixture(
+        "bundle.zip",
+        &zip(&[
+            (b"logs/", 0, b""),
+            (b"logs/app.log", 0, b"service started\nerror: disk full\n"),
+            (b"logs/caf\xe9.log", 0, b"error: lossy name\n"),
+            (b"logs/secret.log", 1, b"error: hidden\n"),
+            (b"logs/older.zip", 0, b"PK\x03\x04error: nested\n"),
+            (b"notes.txt", 0, b"no problems\n"),
+        ]),
+    );
+
+    let output = minigrep(&["--search-zip", "-n", "error", &bundle], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        stdout(&output),
+        format!(
+            "{bundle}!logs/app.log:2:error: disk full\n\
+             {bundle}!logs/caf\u{fffd}.log:1:error: lossy name\n"
+        )
+    );
+    assert_eq!(
+        stderr(&output),
+        format!(
+            "minigrep: {bundle}!logs/secret.log: encrypted zip members are not supported\n\
+             minigrep: {bundle}!logs/older.zip: skipping nested archive\n"
+        )
+    );
+
+    let output = minigrep(&["--search-zip", "-l", "problems", &bundle], "");
+    assert_eq!(stdout(&output), format!("{bundle}!notes.txt\n"));
+}
+
+#[cfg(feature = "zip")]
+#[test]
+fn corrupt_zip_archives_are_skipped_with_a_warning() {
+    let corrupt = byte_fixture("corrupt.zip", b"PK\x03\x04 but no directory");
+    let plain = fixture("zip-neighbour.log", "error: fine\n");
+    let plain = plain.to_str().unwrap();
+
+    let output = minigrep(&["--search-zip", "error", &corrupt, plain], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), format!("{plain}:error: fine\n"));
+    assert_eq!(
+        stderr(&output),
+        format!("minigrep: {corrupt}: corrupt zip data: no central directory\n")
+    );
+}
+
+/// Builds a tar archive from `(name, kind, data)` members, where `kind` is
+/// the type flag of the member's header: `b'0'` for a file, `b'5'` for a
+/// directory, and so on.
+#[cfg(feature = "tar")]
+fn tar(members: &[(&str, u8, &[u8])]) -> Vec<u8> {
+    let mut archive = Vec::new();
+    for &(name, kind, data) in members {
+        let mut header = [0; 512];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        header[124..135].copy_from_slice(format!("{:011o}", data.len()).as_bytes());
+        header[156] = kind;
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[148..156].fill(b' ');
+        let sum: u32 = header.iter().map(|&byte| u32::from(byte)).sum();
+        header[148..155].copy_from_slice(format!("{sum:06o}\0").as_bytes());
+        archive.extend_from_slice(&header);
+        archive.extend_from_slice(data);
+        archive.resize(archive.len().next_multiple_of(512), 0);
+    }
+    archive.extend_from_slice(&[0; 1024]);
+    archive
+}
+
+#[cfg(feature = "tar")]
+#[test]
+fn tar_members_are_searched_with_search_zip() {
+    let members = tar(&[
+        ("logs/", b'5', b""),
+        ("logs/app.log", b'0', b"service started\nerror: disk full\n"),
+        ("logs/latest.log", b'2', b""),
+        ("logs/copy.log", b'1', b""),
+        ("blob.bin", b'0', b"\x7fELF\xff\xfe error: binary\n"),
+    ]);
+    let bundle = byte_fixture("bundle.tar", &members);
+
+    let output = minigrep(&["--search-zip", "-n", "error", &bundle], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        stdout(&output),
+        format!(
+            "{bundle}!logs/app.log:2:error: disk full\n\
+             {bundle}!blob.bin:1:\x7fELF\u{fffd}\u{fffd} error: binary\n"
+        )
+    );
+    assert_eq!(
+        stderr(&output),
+        format!("minigrep: {bundle}!blob.bin: invalid UTF-8 was replaced with U+FFFD\n")
+    );
+
+    let truncated = byte_fixture("truncated.tar", &members[..1040]);
+    let output = minigrep(&["--search-zip", "-c", "error", &truncated], "");
+    assert_eq!(
+        stderr(&output),
+        format!("minigrep: {truncated}!logs/app.log: unexpected end of tar data\n")
+    );
+}
+
+/// A `.tar.gz` of "logs/small.log", which is "error: small\n", and
+/// "logs/big.log", which is "error: big\n" 200 times over.
+#[cfg(all(feature = "tar", feature = "gzip"))]
+const TAR_GZ: &[u8] = &[
+    0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xed, 0xd6, 0xb1, 0x0a, 0x83, 0x30,
+    0x14, 0x46, 0xe1, 0x3b, 0xfb, 0x14, 0x3e, 0x41, 0x7b, 0x63, 0x93, 0x0e, 0xbe, 0x8d, 0x42, 0x09,
+    0x85, 0x5b, 0x02, 0x49, 0xfb, 0xfe, 0x46, 0x17, 0xc1, 0xa5, 0x50, 0xd0, 0x0e, 0x9e, 0x6f, 0xc9,
+    0xc1, 0xc5, 0x25, 0x3f, 0xc4, 0x52, 0x2c, 0xd7, 0xf2, 0x1a, 0xcc, 0x2e, 0x96, 0xa2, 0xec, 0x42,
+    0xab, 0xbb, 0xf7, 0xcb, 0x59, 0x6d, 0x4f, 0x55, 0x17, 0xd6, 0x9e, 0xbf, 0x3b, 0x0d, 0x9d, 0x4a,
+    0xab, 0x72, 0x80, 0x4f, 0x79, 0x0f, 0xb9, 0xfe, 0x5e, 0xce, 0xe9, 0x91, 0x73, 0xca, 0x7d, 0xbb,
+    0xdc, 0x80, 0x46, 0x70, 0x36, 0x36, 0xef, 0x7f, 0x7c, 0xc6, 0xfd, 0xd6, 0xff, 0x75, 0xff, 0xbe,
+    0xbb, 0xe9, 0x76, 0xff, 0x2e, 0x78, 0xf6, 0x7f, 0xe0, 0xfe, 0xeb, 0x0d, 0x68, 0x48, 0x92, 0x24,
+    0x49, 0x92, 0x24, 0xff, 0x9a, 0xbc, 0xcc, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x7e, 0x33, 0x01, 0xd9, 0xc3, 0x92, 0xf7, 0x00, 0x28, 0x00, 0x00,
+];
+
+#[cfg(all(feature = "tar", feature = "gzip"))]
+#[test]
+fn compressed_tar_members_respect_max_filesize() {
+    let archive = byte_fixture("logs.tar.gz", TAR_GZ);
+
+    let output = minigrep(&["--search-zip", "-c", "error", &archive], "");
+    assert_eq!(
+        stdout(&output),
+        format!("{archive}!logs/small.log:1\n{archive}!logs/big.log:200\n")
+    );
+
+    let output = minigrep(
+        &[
+            "--search-zip",
+            "--max-filesize",
+            "1K",
+            "-c",
+            "error",
+            &archive,
+        ],
+        "",
+    );
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), format!("{archive}!logs/small.log:1\n"));
+    assert_eq!(stderr(&output), "");
+}
+
+#[cfg(unix)]
+#[test]
+fn watch_searches_appended_lines_until_interrupted() {
+    use std::io::{BufRead, BufReader};
+
+    let dir = fixture_dir("watch");
+    let path = dir.join("app.log");
+    fs::write(&path, "error: before\ninfo: ok\n").unwrap();
+    let mut child = command(&[
+        "--watch",
+        "--watch-interval=10",
+        "-n",
+        "error",
+        path.to_str().unwrap(),
+    ])
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn()
+    .unwrap();
+    let mut lines = BufReader::new(child.stdout.take().unwrap()).lines();
+    assert_eq!(lines.next().unwrap().unwrap(), "1:error: before");
+
+    let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+    file.write_all(b"info: still ok\nerror: after\n").unwrap();
+    assert_eq!(lines.next().unwrap().unwrap(), "4:error: after");
+
+    fs::write(&path, "error: truncated\n").unwrap();
+    assert_eq!(lines.next().unwrap().unwrap(), "1:error: truncated");
+
+    let killed = Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(killed.success());
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert!(lines.next().is_none());
+    assert_eq!(
+        stderr(&output),
+        format!(
+            "minigrep: {}: file truncated, searching it again\n",
+            path.display()
+        )
+    );
+}
+
+/// Writes an executable shell script named `name` that preprocesses the
+/// file it is given by upper-casing it, or fails on names ending in `.bad`.
+#[cfg(unix)]
+fn preprocessor(name: &str) -> String {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = fixture(
+        name,
+        "#!/bin/sh\ncase \"$1\" in\n*.bad) echo \"cannot convert $1\" >&2; exit 3 ;;\nesac\ntr a-z A-Z < \"$1\"\n",
+    );
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    path.to_str().unwrap().to_string()
+}
+
+#[cfg(unix)]
+#[test]
+fn pre_searches_the_output_of_the_preprocessor() {
+    let pre = preprocessor("upper.sh");
+    let dir = fixture_dir("pre");
+    fs::write(dir.join("a.pdf"), "error: in a pdf\n").unwrap();
+    fs::write(dir.join("b.txt"), "error: plain text\nERROR: plain text\n").unwrap();
+    fs::write(dir.join("c.bad"), "error: unconvertible\n").unwrap();
+    let dir = dir.to_str().unwrap();
+
+    let output = minigrep(
+        &[
+            &format!("--pre={pre}"),
+            "--pre-glob=*.pdf",
+            "--pre-glob=*.bad",
+            "--sort=path",
+            "-r",
+            "ERROR",
+            dir,
+        ],
+        "",
+    );
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        stdout(&output),
+        format!("{dir}/a.pdf:ERROR: IN A PDF\n{dir}/b.txt:ERROR: plain text\n")
+    );
+    assert_eq!(
+        stderr(&output),
+        format!(
+            "cannot convert {dir}/c.bad\n\
+             minigrep: {dir}/c.bad: preprocessor {pre} failed with exit status: 3\n"
+        )
+    );
+
+    let file = format!("{dir}/b.txt");
+    let output = minigrep(&["--pre=minigrep-no-such-preprocessor", "error", &file], "");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).starts_with(&format!(
+        "minigrep: {file}: cannot run preprocessor minigrep-no-such-preprocessor: "
+    )));
+}
+
+#[cfg(unix)]
+#[test]
+fn files_reached_through_links_are_searched_once() {
+    let dir = fixture_dir("dedupe");
+    fs::write(dir.join("a.log"), "error: once\n").unwrap();
+    fs::hard_link(dir.join("a.log"), dir.join("b.log")).unwrap();
+    std::os::unix::fs::symlink(dir.join("a.log"), dir.join("c.log")).unwrap();
+    let root = dir.to_str().unwrap();
+
+    let output = minigrep(&["-r", "--follow", "error", root], "");
+    assert_eq!(stdout(&output), format!("{root}/a.log:error: once\n"));
+    assert_eq!(stderr(&output), "");
+
+    let output = minigrep(&["-r", "--follow", "--no-dedupe", "-c", "error", root], "");
+    assert_eq!(
+        stdout(&output),
+        format!("{root}/a.log:1\n{root}/b.log:1\n{root}/c.log:1\n")
+    );
+}
+
+#[test]
+fn files_from_lists_the_paths_to_search() {
+    let dir = fixture_dir("files-from");
+    fs::write(dir.join("a.log"), "error: a\n").unwrap();
+    fs::write(dir.join("b.log"), "info: b\n").unwrap();
+    fs::write(dir.join("c.log"), "error: c\n").unwrap();
+    let (a, b, c) = (
+        dir.join("a.log").display().to_string(),
+        dir.join("b.log").display().to_string(),
+        dir.join("c.log").display().to_string(),
+    );
+
+    let output = minigrep(
+        &["--files-from", "-", "error"],
+        &format!("{a}\n\n{b}\r\n{c}\n"),
+    );
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), format!("{a}:error: a\n{c}:error: c\n"));
+
+    let list = byte_fixture("files0-from.bin", format!("{c}\0{a}\0").as_bytes());
+    let output = minigrep(&["--files0-from", &list, "-c", "error", &b], "");
+    assert_eq!(stdout(&output), format!("{b}:0\n{c}:1\n{a}:1\n"));
+
+    let missing = dir.join("missing.log").display().to_string();
+    let output = minigrep(&["--files-from=-", "error"], &format!("{missing}\n{a}\n"));
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), format!("{a}:error: a\n"));
+    assert!(stderr(&output).starts_with(&format!("minigrep: {missing}: ")));
+}
+
+#[test]
+fn unreadable_file_lists_are_fatal() {
+    let output = minigrep(&["--files-from=no-such-list.txt", "error"], "");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).contains("no-such-list.txt: "));
+    assert_eq!(stdout(&output), "");
+
+    let output = minigrep(&["--files-from=-", "error", "-"], "a.log\n");
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn modification_time_and_minimum_size_filter_recursion() {
+    let dir = fixture_dir("mtime");
+    fs::write(dir.join("empty.log"), "").unwrap();
+    fs::write(dir.join("old.log"), "error: last week\n").unwrap();
+    fs::write(dir.join("new.log"), "error: just now\n").unwrap();
+    let week_ago = std::time::SystemTime::now() - std::time::Duration::from_secs(7 * 86_400);
+    fs::File::options()
+        .write(true)
+        .open(dir.join("old.log"))
+        .unwrap()
+        .set_modified(week_ago)
+        .unwrap();
+    let root = dir.to_str().unwrap();
+
+    let output = minigrep(
+        &[
+            "-r",
+            "--stats",
+            "--newer-than=2d",
+            "--min-filesize=1",
+            "error",
+            root,
+        ],
+        "",
+    );
+    assert_eq!(stdout(&output), format!("{root}/new.log:error: just now\n"));
+    assert!(
+        stderr(&output).contains("2 files skipped (0 too large, 2 filtered out, 0 unreadable)")
+    );
+
+    let output = minigrep(&["-r", "--older-than=1d", "error", root], "");
+    assert_eq!(
+        stdout(&output),
+        format!("{root}/old.log:error: last week\n")
+    );
+
+    let output = minigrep(&["-r", "--newer-than=2w", "error", root], "");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).contains("Invalid duration '2w'"));
+}
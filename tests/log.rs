@@ -0,0 +1,114 @@
+//! Tests of the messages logged through the `log` module, in a test binary
+//! of their own since the logger is installed for the whole process.
+
+use minigrep_cli_tool::{
+    Config,
+    log::{self, Level, Log, Record},
+    run_with_writer,
+};
+use std::{ffi::OsString, fs, path::PathBuf, sync::Mutex};
+
+/// A logger keeping every message, with its level and target.
+struct Capture(Mutex<Vec<(Level, &'static str, String)>>);
+
+impl Log for Capture {
+    fn enabled(&self, _level: Level) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        let message = record.args.to_string();
+        self.0
+            .lock()
+            .unwrap()
+            .push((record.level, record.target, message));
+    }
+}
+
+static CAPTURE: Capture = Capture(Mutex::new(Vec::new()));
+
+/// Installs the capturing logger, runs a search with the command-line
+/// arguments `args`, and returns the messages logged about `dir`.
+fn logged(dir: &str, args: &[&str]) -> Vec<(Level, &'static str, String)> {
+    let _ = log::set_logger(&CAPTURE);
+    let args = std::iter::once("minigrep").chain(args.iter().copied());
+    let config = Config::build(args.map(OsString::from), true, |_| None).unwrap();
+    run_with_writer(&config, &mut Vec::new(), &mut Vec::new()).unwrap();
+    let messages = CAPTURE.0.lock().unwrap();
+    messages
+        .iter()
+        .filter(|(_, _, message)| message.contains(dir))
+        .cloned()
+        .collect()
+}
+
+#[test]
+fn skips_are_logged_with_their_reasons() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("log_skips");
+    fs::create_dir_all(dir.join("target")).unwrap();
+    fs::write(dir.join(".gitignore"), "target\n").unwrap();
+    fs::write(dir.join("a.txt"), "rust\n").unwrap();
+    fs::write(dir.join("big.txt"), "rust\n".repeat(100)).unwrap();
+    fs::write(dir.join("target/out.txt"), "rust\n").unwrap();
+    let root = dir.to_str().unwrap();
+    let missing = dir.join("missing.txt");
+
+    let args = ["-r", "--max-filesize=100", "rust", root];
+    let messages = logged(root, &[&args[..], &[missing.to_str().unwrap()]].concat());
+    let skipped = |name: &str, reason: &str| {
+        let message = format!("skipping {}: {reason}", dir.join(name).display());
+        (Level::Debug, "walk", message)
+    };
+    for expected in [
+        skipped(".gitignore", "hidden"),
+        skipped("big.txt", "larger than the maximum file size"),
+        skipped("target", "listed in an ignore file"),
+    ] {
+        assert!(
+            messages.contains(&expected),
+            "{expected:?} in {messages:#?}"
+        );
+    }
+    assert!(
+        messages.iter().any(|(level, target, message)| {
+            *level == Level::Debug
+                && *target == "search"
+                && message.starts_with(&format!("skipping {}: ", missing.display()))
+        }),
+        "{messages:#?}"
+    );
+
+    let opened = format!("opening {}", dir.join("a.txt").display());
+    assert!(messages.contains(&(Level::Trace, "search", opened)));
+    assert!(
+        !messages
+            .iter()
+            .any(|(level, _, message)| *level == Level::Trace && message.contains("big.txt"))
+    );
+}
+
+#[test]
+fn the_setup_and_the_lines_selected_in_each_input_are_logged() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("log_setup");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("a.txt"), "rust\ngo\nrust again\n").unwrap();
+    fs::write(dir.join("b.txt"), "go\n").unwrap();
+    let root = dir.to_str().unwrap();
+
+    let messages = logged(root, &["-r", "rust", root]);
+    let inputs = format!("inputs: {:?}", root);
+    assert!(messages.contains(&(Level::Debug, "config", inputs)));
+    let selected = |name: &str, line: &str| {
+        let message = format!("{}: {line}", dir.join(name).display());
+        (Level::Debug, "search", message)
+    };
+    for expected in [
+        selected("a.txt", "2 of 3 lines selected"),
+        selected("b.txt", "0 of 1 lines selected"),
+    ] {
+        assert!(
+            messages.contains(&expected),
+            "{expected:?} in {messages:#?}"
+        );
+    }
+}
@@ -0,0 +1,60 @@
+//! Tests of the messages passed on to the `log` crate with the `log`
+//! feature, in a test binary of their own since the logger is installed for
+//! the whole process.
+
+#![cfg(feature = "log")]
+
+use minigrep_cli_tool::{Config, run_with_writer};
+use std::{ffi::OsString, fs, path::PathBuf, sync::Mutex};
+
+/// A logger of the `log` crate keeping every message, with its level and
+/// target.
+struct Capture(Mutex<Vec<(log::Level, String, String)>>);
+
+impl log::Log for Capture {
+    fn enabled(&self, _metadata: &log::Metadata<'_>) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record<'_>) {
+        let entry = (
+            record.level(),
+            record.target().to_string(),
+            record.args().to_string(),
+        );
+        self.0.lock().unwrap().push(entry);
+    }
+
+    fn flush(&self) {}
+}
+
+static CAPTURE: Capture = Capture(Mutex::new(Vec::new()));
+
+#[test]
+fn messages_reach_a_logger_of_the_log_crate() {
+    log::set_logger(&CAPTURE).unwrap();
+    log::set_max_level(log::LevelFilter::Trace);
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("log_crate");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join(".hidden"), "rust\n").unwrap();
+    fs::write(dir.join("a.txt"), "rust\n").unwrap();
+    let root = dir.to_str().unwrap();
+
+    let args = ["minigrep", "-r", "rust", root].map(OsString::from);
+    let config = Config::build(args.into_iter(), true, |_| None).unwrap();
+    run_with_writer(&config, &mut Vec::new(), &mut Vec::new()).unwrap();
+
+    let messages = CAPTURE.0.lock().unwrap();
+    let skipped = format!("skipping {}: hidden", dir.join(".hidden").display());
+    let walk = "minigrep_cli_tool::walk".to_string();
+    assert!(
+        messages.contains(&(log::Level::Debug, walk, skipped)),
+        "{messages:#?}"
+    );
+    let opened = format!("opening {}", dir.join("a.txt").display());
+    let search = "minigrep_cli_tool::search".to_string();
+    assert!(
+        messages.contains(&(log::Level::Trace, search, opened)),
+        "{messages:#?}"
+    );
+}
@@ -0,0 +1,1001 @@
+//! Tests of searches run in process through `run_with_writer`, which are
+//! much faster than spawning the binary.
+
+use minigrep_cli_tool::{Config, RunSummary, metrics::Metrics, run_with_writer};
+use std::{ffi::OsString, fs, io::Write, path::PathBuf, sync::Arc, thread};
+
+/// Writes `contents` to a fixture file named `name` and returns its path.
+fn fixture(name: &str, contents: &str) -> String {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    fs::write(&path, contents).unwrap();
+    path.to_str().unwrap().to_string()
+}
+
+/// Runs a search with the command-line arguments `args`, returning what it
+/// printed as output, what it printed as warnings, and its summary.
+fn search(args: &[&str]) -> (String, String, RunSummary) {
+    let args = std::iter::once("minigrep").chain(args.iter().copied());
+    let config = Config::build(args.map(OsString::from), true, |_| None).unwrap();
+    let (mut out, mut err) = (Vec::new(), Vec::new());
+    let summary = run_with_writer(&config, &mut out, &mut err).unwrap();
+    (
+        String::from_utf8(out).unwrap(),
+        String::from_utf8(err).unwrap(),
+        summary,
+    )
+}
+
+/// Returns only what a search with `args` printed as output.
+fn stdout(args: &[&str]) -> String {
+    search(args).0
+}
+
+#[test]
+fn case_flag_chooses_how_lines_are_compared() {
+    let path = fixture("run_case.txt", "Rust\nrust\nRUST and go\ngo\n");
+
+    let (out, _, summary) = search(&["rust", &path, "/s"]);
+    assert_eq!(out, "rust\n");
+    assert_eq!(summary.matched_lines, 1);
+
+    let (out, _, summary) = search(&["rust", &path, "/i"]);
+    assert_eq!(out, "Rust\nrust\nRUST and go\n");
+    assert_eq!(summary.matched_lines, 3);
+}
+
+#[test]
+fn patterns_set_their_own_case_over_the_case_flag() {
+    let path = fixture(
+        "run_cased.txt",
+        "MaxRetries = 3\nmaxretries = 4\nERROR here\nerror there\nWarn once\nWARN twice\n",
+    );
+    let patterns = fixture("run_cased_patterns.txt", "(?-i)MaxRetries\n(?i)error\n");
+
+    for case in ["/s", "/i"] {
+        assert_eq!(
+            stdout(&["-f", &patterns, &path, case]),
+            "MaxRetries = 3\nERROR here\nerror there\n",
+            "{case}"
+        );
+    }
+    assert_eq!(
+        stdout(&["-f", &patterns, "-e", "Warn", &path, "/s"]),
+        "MaxRetries = 3\nERROR here\nerror there\nWarn once\n"
+    );
+    assert_eq!(
+        stdout(&["-f", &patterns, "-e", "Warn", &path, "/i"]),
+        "MaxRetries = 3\nERROR here\nerror there\nWarn once\nWARN twice\n"
+    );
+    assert_eq!(
+        stdout(&["-e", "(?i)warn", "-o", &path, "/s"]),
+        "Warn\nWARN\n"
+    );
+}
+
+#[test]
+fn invert_count_and_only_matching() {
+    let path = fixture("run_invert.txt", "a rust rust\nb\nc rust\n");
+
+    assert_eq!(stdout(&["-v", "rust", &path]), "b\n");
+    assert_eq!(stdout(&["-c", "rust", &path]), "2\n");
+    assert_eq!(stdout(&["-c", "-v", "rust", &path]), "1\n");
+    assert_eq!(
+        stdout(&["-o", "-n", "RUST", &path, "/i"]),
+        "1:rust\n1:rust\n3:rust\n"
+    );
+}
+
+#[test]
+fn count_matches_counts_occurrences_not_lines() {
+    let path = fixture(
+        "run_count_matches.txt",
+        "error error error\nok\nerrorerror\n",
+    );
+    assert_eq!(stdout(&["-c", "error", &path]), "2\n");
+    assert_eq!(stdout(&["--count-matches", "error", &path]), "5\n");
+
+    let path = fixture("run_overlaps.txt", "aaaaa\n");
+    assert_eq!(stdout(&["--count-matches", "aa", &path]), "2\n");
+}
+
+#[test]
+fn summary_counts_matches_and_failures() {
+    let path = fixture("run_summary.txt", "rust\ngo\nrust\n");
+
+    let (_, err, summary) = search(&["rust", &path]);
+    assert_eq!(
+        summary,
+        RunSummary {
+            found: true,
+            matched_lines: 2,
+            failed_inputs: 0,
+        }
+    );
+    assert_eq!(err, "");
+
+    let (_, _, summary) = search(&["-v", "rust", &path]);
+    assert_eq!((summary.found, summary.matched_lines), (true, 1));
+    let (_, _, summary) = search(&["java", &path]);
+    assert!(!summary.found);
+
+    let missing = format!("{path}.missing");
+    let (out, err, summary) = search(&["rust", &path, &missing]);
+    assert_eq!(out, format!("{path}:rust\n{path}:rust\n"));
+    assert!(err.starts_with(&format!("minigrep: {missing}: ")), "{err}");
+    assert_eq!(summary.failed_inputs, 1);
+    assert!(summary.found);
+}
+
+#[test]
+fn config_can_be_built_field_by_field() {
+    let path = fixture("run_new.txt", "one rust\ntwo\nthree rust\n");
+    let mut config = Config::new("rust", vec![path.into()]);
+    config.line_number = true;
+    config.invert = true;
+    let (mut out, mut err) = (Vec::new(), Vec::new());
+    let summary = run_with_writer(&config, &mut out, &mut err).unwrap();
+    assert_eq!(out, b"2:two\n");
+    assert_eq!(summary.matched_lines, 1);
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn sorted_parallel_output_is_identical_on_every_run() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("run_deterministic");
+    for index in 0..64 {
+        let sub = dir.join(format!("{}", index % 4));
+        fs::create_dir_all(&sub).unwrap();
+        // Uneven sizes, so that the threads finish out of order.
+        let lines = (index * 37) % 50 + 1;
+        let contents = (0..lines)
+            .map(|line| format!("rust {index} {line}\nplain\n"))
+            .collect::<String>();
+        fs::write(sub.join(format!("{index}.txt")), contents).unwrap();
+    }
+    let root = dir.to_str().unwrap();
+
+    for mode in [&["-n"][..], &["-c"], &["--heading", "-n"], &["-o", "-b"]] {
+        let args = |threads| [mode, &["-j", threads, "--sort=path", "-r", "rust", root]].concat();
+        let expected = stdout(&args("1"));
+        assert!(expected.lines().count() >= 64, "{mode:?}");
+        for _ in 0..30 {
+            assert_eq!(stdout(&args("8")), expected, "{mode:?}");
+        }
+    }
+}
+
+#[test]
+fn metrics_add_up_across_searches_and_threads() {
+    let path = fixture("run_metrics.txt", "rust\ngo\nrust again\n");
+    let missing = fixture("run_metrics_missing.txt", "") + ".gone";
+    let metrics = Arc::new(Metrics::default());
+
+    thread::scope(|scope| {
+        for query in ["rust", "go", "java", "rust"] {
+            let metrics = &metrics;
+            let (path, missing) = (&path, &missing);
+            scope.spawn(move || {
+                let args = ["minigrep", query, path, missing];
+                let mut config =
+                    Config::build(args.into_iter().map(OsString::from), true, |_| None).unwrap();
+                config.metrics = Some(metrics.clone());
+                run_with_writer(&config, &mut Vec::new(), &mut Vec::new()).unwrap();
+            });
+        }
+    });
+    assert_eq!(metrics.searches(), 4);
+    assert_eq!(metrics.lines_scanned(), 12);
+    assert_eq!(metrics.matched_lines(), 5);
+    assert_eq!(metrics.failed_inputs(), 4);
+
+    // A search without metrics leaves them as they were.
+    search(&["rust", &path]);
+    assert_eq!(metrics.searches(), 4);
+}
+
+#[test]
+fn escaped_newlines_only_match_records() {
+    let path = fixture("run_escapes.txt", "a\tb\nend\n");
+
+    assert_eq!(stdout(&["--escapes", r"\x61\tb", &path]), "a\tb\n");
+    let (out, err, _) = search(&["--escapes", r"b\nend", &path]);
+    assert_eq!(out, "");
+    assert!(err.contains("the query contains a newline"), "{err}");
+    // Records ending with NUL make a file that would be binary otherwise.
+    let path = fixture("run_escapes_records.txt", "end\0next\nline\0");
+    let (out, err, _) = search(&["-z", "--escapes", r"next\nline", &path]);
+    assert_eq!(out, "next\nline\0");
+    assert_eq!(err, "");
+}
+
+#[test]
+fn hex_patterns_report_byte_offsets() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+    let path = dir.join("run_hex.bin");
+    let mut bytes = vec![0u8; 0x1_0000];
+    bytes[2..6].copy_from_slice(&[0xde, 0xad, 0xbe, 0xef]);
+    bytes[0xabcd..0xabd1].copy_from_slice(&[0xde, 0xad, 0xbe, 0xef]);
+    bytes[0xfffe..].copy_from_slice(&[0xde, 0xad]);
+    fs::write(&path, bytes).unwrap();
+    let path = path.to_str().unwrap();
+
+    let (out, _, summary) = search(&["--hex", "DE AD BE EF", path]);
+    assert_eq!(out, format!("{path}:0x00000002\n{path}:0x0000ABCD\n"));
+    assert_eq!(summary.matched_lines, 2);
+    assert_eq!(
+        stdout(&["--hex", "--hex-context=3", "dead", path]),
+        format!(
+            "{path}:0x00000002: 00 00 [DE AD] BE EF 00\n\
+             {path}:0x0000ABCD: 00 00 00 [DE AD] BE EF 00\n\
+             {path}:0x0000FFFE: 00 00 00 [DE AD]\n"
+        )
+    );
+    assert!(!search(&["--hex", "DE AD DE AD", path]).2.found);
+}
+
+#[test]
+fn strings_searches_the_printable_runs_of_binaries() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+    let path = dir.join("run_strings.bin");
+    let mut bytes = vec![0u8; 0x1000];
+    bytes[..4].copy_from_slice(b"\x7fELF");
+    bytes[0x40..0x4d].copy_from_slice(b"libssl.so.3\x01\x02");
+    bytes[0x80..0x83].copy_from_slice(b"ssl");
+    bytes[0x200..0x212].copy_from_slice("error: caf\u{e9} ssl!\n".as_bytes());
+    fs::write(&path, bytes).unwrap();
+    let path = path.to_str().unwrap();
+
+    let (out, _, summary) = search(&["--strings", "ssl", path]);
+    assert_eq!(out, "64:libssl.so.3\n512:error: caf\u{e9} ssl!\n");
+    assert_eq!(summary.matched_lines, 2);
+    assert_eq!(stdout(&["--strings=3", "-c", "ssl", path]), "3\n");
+    assert_eq!(stdout(&["--strings=3", "-v", "ssl", path]), "1:ELF\n");
+    assert_eq!(
+        stdout(&["--strings", "-o", "ssl", path]),
+        "67:ssl\n525:ssl\n"
+    );
+    assert_eq!(
+        stdout(&["--strings", "-H", "--count-matches", "s", path]),
+        format!("{path}:5\n")
+    );
+}
+
+#[test]
+fn expressions_select_lines_by_their_literals() {
+    let path = fixture(
+        "run_expr.txt",
+        "request timeout\n\
+         Timed out, retry scheduled\n\
+         timed out waiting\n\
+         timeout & retry scheduled\n\
+         all good\n",
+    );
+    let expr = r#"("timeout" | "timed out") & !"retry scheduled""#;
+
+    assert_eq!(
+        stdout(&["--expr", expr, &path]),
+        "request timeout\ntimed out waiting\n"
+    );
+    assert_eq!(
+        stdout(&["-n", "/i", "--expr", expr, &path]),
+        "1:request timeout\n3:timed out waiting\n"
+    );
+    assert_eq!(
+        stdout(&["-v", "--expr", expr, &path]),
+        "Timed out, retry scheduled\ntimeout & retry scheduled\nall good\n"
+    );
+    assert_eq!(
+        stdout(&["--expr", r#""& retry" | "good""#, &path]),
+        "timeout & retry scheduled\nall good\n"
+    );
+    assert_eq!(
+        stdout(&["-o", "--expr", expr, &path]),
+        "timeout\ntimed out\n"
+    );
+}
+
+#[test]
+fn and_not_patterns_exclude_lines_and_invert_with_them() {
+    let path = fixture(
+        "run_and_not.txt",
+        "timeout\nTIMEOUT, retry scheduled\ntimeout, gave up\nall good\n",
+    );
+
+    assert_eq!(
+        stdout(&["--and-not=retry", "timeout", &path, "/s"]),
+        "timeout\ntimeout, gave up\n"
+    );
+    assert_eq!(
+        stdout(&["--and-not=RETRY", "--and-not=gave", "timeout", &path, "/i"]),
+        "timeout\n"
+    );
+    // The whole of "timeout and not retry" is inverted, not only its parts.
+    assert_eq!(
+        stdout(&["-v", "--and-not=retry", "timeout", &path, "/i"]),
+        "TIMEOUT, retry scheduled\nall good\n"
+    );
+    assert_eq!(stdout(&["-c", "--and-not=good", "", &path]), "3\n");
+}
+
+#[test]
+fn skipped_and_unread_lines_are_not_searched_but_still_numbered() {
+    let path = fixture(
+        "run_window.csv",
+        "name,error\nparser,error 1\nlexer,error 2\nwalker,error 3\n",
+    );
+
+    assert_eq!(
+        stdout(&["-n", "--skip-lines=1", "error", &path]),
+        "2:parser,error 1\n3:lexer,error 2\n4:walker,error 3\n"
+    );
+    // The first line searched after three skipped ones is line 4.
+    assert_eq!(
+        stdout(&["-n", "--skip-lines=3", "error", &path]),
+        "4:walker,error 3\n"
+    );
+    assert_eq!(stdout(&["-c", "--head=2", "error", &path]), "2\n");
+    // `--head` counts from the start of the file, skipped lines included.
+    assert_eq!(
+        stdout(&["-n", "--skip-lines=1", "--head=3", "error", &path]),
+        "2:parser,error 1\n3:lexer,error 2\n"
+    );
+    assert_eq!(stdout(&["-c", "--skip-lines=9", "error", &path]), "0\n");
+    assert_eq!(stdout(&["-c", "--head=0", "error", &path]), "0\n");
+}
+
+#[test]
+fn time_windows_select_the_lines_stamped_inside_them() {
+    let path = fixture(
+        "run_window.log",
+        "2024-01-02T13:59:59Z error: early\n\
+         2024-01-02T14:00:00Z error: disk full\n\
+         \x20 at disk.rs:3\n\
+         [2024-01-02 14:05:00] info: retry\n\
+         2024-01-02T15:09:00+01:00 error: still full\n\
+         2024-01-02T14:10:00Z error: late\n",
+    );
+    let window = ["--since=2024-01-02 14:00", "--until=2024-01-02T14:10"];
+
+    assert_eq!(
+        stdout(&[&window[..], &["-n", "error", &path]].concat()),
+        "2:2024-01-02T14:00:00Z error: disk full\n5:2024-01-02T15:09:00+01:00 error: still full\n"
+    );
+    assert_eq!(stdout(&[&window[..], &["-c", "at", &path]].concat()), "0\n");
+    assert_eq!(
+        stdout(&[&window[..], &["--no-timestamp=keep", "-c", "at", &path]].concat()),
+        "1\n"
+    );
+    // -v flips the query, but lines outside the window stay out.
+    assert_eq!(
+        stdout(&[&window[..], &["-v", "error", &path]].concat()),
+        "[2024-01-02 14:05:00] info: retry\n"
+    );
+    assert_eq!(
+        stdout(&["--since=2024-01-02T14:06", "-c", "error", &path]),
+        "2\n"
+    );
+
+    let syslog = fixture(
+        "run_window_syslog.log",
+        "Jan  2 13:59:00 host app: error a\nJan  2 14:00:00 host app: error b\n\
+         Jan 12 09:00:00 host app: error c\n",
+    );
+    assert_eq!(
+        stdout(&[
+            "--since",
+            "Jan  2 14:00:00",
+            "--until=Jan 3 00:00:00",
+            "error",
+            &syslog
+        ]),
+        "Jan  2 14:00:00 host app: error b\n"
+    );
+}
+
+#[test]
+fn between_selects_every_range_of_lines() {
+    let path = fixture(
+        "run_between.conf",
+        "# app\nBEGIN CONFIG\nport = 80\nEND CONFIG\nBEGIN CONFIG\nhost = a\nEND CONFIG\n\
+         other\nBEGIN CONFIG\nBEGIN CONFIG\nuser = b\n",
+    );
+    let between = ["--between", "BEGIN CONFIG", "END CONFIG"];
+
+    assert_eq!(
+        stdout(&[&between[..], &["-n", &path]].concat()),
+        "2:BEGIN CONFIG\n3:port = 80\n4:END CONFIG\n5:BEGIN CONFIG\n6:host = a\n\
+         7:END CONFIG\n9:BEGIN CONFIG\n10:BEGIN CONFIG\n11:user = b\n"
+    );
+    assert_eq!(stdout(&[&between[..], &["-c", &path]].concat()), "9\n");
+    assert_eq!(
+        stdout(&[&between[..], &["-v", &path]].concat()),
+        "# app\nother\n"
+    );
+    assert_eq!(
+        stdout(&["--between=begin", "end", "-c", &path, "/i"]),
+        "9\n"
+    );
+    // Ranges do not carry over from one file to the next.
+    let second = fixture("run_between_second.conf", "user = c\nEND CONFIG\n");
+    assert_eq!(
+        stdout(&[&between[..], &["-c", &path, &second]].concat()),
+        format!("{path}:9\n{second}:0\n")
+    );
+}
+
+#[test]
+fn context_until_follows_each_record_to_the_next() {
+    let path = fixture(
+        "run_context_until.log",
+        "2024-01-01 ok\n2024-01-02 error: disk\n  at io.rs:10\n  at main.rs:3\n\
+         2024-01-03 ok\n  at main.rs:9\n2024-01-04 error: net\n2024-01-05 error: dns\n  at net.rs:7\n",
+    );
+
+    assert_eq!(
+        stdout(&["-n", "--after-context-until=2024-", "error", &path]),
+        "2:2024-01-02 error: disk\n3-  at io.rs:10\n4-  at main.rs:3\n--\n\
+         7:2024-01-04 error: net\n8:2024-01-05 error: dns\n9-  at net.rs:7\n"
+    );
+    assert_eq!(
+        stdout(&[
+            "--after-context-until=2024-",
+            "--include-stop",
+            "disk",
+            &path
+        ]),
+        "2024-01-02 error: disk\n  at io.rs:10\n  at main.rs:3\n2024-01-03 ok\n"
+    );
+    // Combined with -A, whichever window is longer wins.
+    assert_eq!(
+        stdout(&["-A", "3", "--after-context-until=  at io", "disk", &path]),
+        "2024-01-02 error: disk\n  at io.rs:10\n  at main.rs:3\n2024-01-03 ok\n"
+    );
+}
+
+#[test]
+fn trimmed_and_squeezed_lines_are_printed_and_highlighted_as_read() {
+    let path = fixture(
+        "run_normalize.conf",
+        "\tport =  80\nport\t=\t80  \n  host = a\nportal = 80\n",
+    );
+
+    assert_eq!(
+        stdout(&["--squeeze-space", "port = 80", &path]),
+        "\tport =  80\nport\t=\t80  \n"
+    );
+    assert_eq!(
+        stdout(&["--squeeze-space", "-o", "-b", "port  \t= 80", &path]),
+        "1:port =  80\n12:port\t=\t80\n"
+    );
+    assert_eq!(
+        stdout(&["--squeeze-space", "--color=always", "t = 8", &path]),
+        "\tpor\x1b[01;31mt =  8\x1b[0m0\npor\x1b[01;31mt\t=\t8\x1b[0m0  \n"
+    );
+    // Without --trim, a query anchored by its spaces misses padded lines.
+    assert_eq!(stdout(&["-c", "--trim", "80 ", &path]), "0\n");
+    assert_eq!(stdout(&["-c", "80  ", &path]), "1\n");
+    assert_eq!(
+        stdout(&[
+            "-n",
+            "--trim",
+            "--squeeze-space",
+            "--column",
+            "host = a",
+            &path
+        ]),
+        "3:3:  host = a\n"
+    );
+}
+
+/// Runs a search with `args`, returning what it printed and the metrics it
+/// counted.
+fn search_with_metrics(args: &[&str]) -> (String, Arc<Metrics>) {
+    let args = std::iter::once("minigrep").chain(args.iter().copied());
+    let mut config = Config::build(args.map(OsString::from), true, |_| None).unwrap();
+    let metrics = Arc::new(Metrics::default());
+    config.metrics = Some(metrics.clone());
+    let mut out = Vec::new();
+    run_with_writer(&config, &mut out, &mut Vec::new()).unwrap();
+    (String::from_utf8(out).unwrap(), metrics)
+}
+
+#[test]
+fn max_total_stops_the_whole_search_at_the_limit() {
+    let hits = (1..=5).map(|n| format!("hit {n}\n")).collect::<String>();
+    let paths: Vec<String> = ["a", "b", "c"]
+        .iter()
+        .map(|name| fixture(&format!("run_max_total_{name}.txt"), &hits))
+        .collect();
+    let name = |index: usize| paths[index].as_str();
+
+    let (out, metrics) =
+        search_with_metrics(&["--max-total=7", "-n", "hit", name(0), name(1), name(2)]);
+    let expected = (1..=5)
+        .map(|n| format!("{}:{n}:hit {n}\n", name(0)))
+        .chain((1..=2).map(|n| format!("{}:{n}:hit {n}\n", name(1))))
+        .collect::<String>();
+    assert_eq!(out, expected);
+    // The last file is never opened, and the second is left after its
+    // second line.
+    assert_eq!((metrics.lines_scanned(), metrics.matched_lines()), (7, 7));
+
+    // The context of the last line is still printed.
+    let (out, metrics) = search_with_metrics(&["--max-total=2", "-A", "1", "hit", name(0)]);
+    assert_eq!(out, "hit 1\nhit 2\nhit 3\n");
+    assert_eq!(metrics.lines_scanned(), 3);
+
+    assert_eq!(
+        stdout(&["-c", "--max-total=7", "hit", name(0), name(1), name(2)]),
+        format!("{}:5\n{}:2\n", name(0), name(1))
+    );
+    assert_eq!(stdout(&["-c", "--max-total=0", "hit", name(0)]), "");
+}
+
+#[test]
+fn quiet_stops_reading_at_the_first_selected_line() {
+    let hits = (1..=5).map(|n| format!("hit {n}\n")).collect::<String>();
+    let paths: Vec<String> = ["a", "b", "c"]
+        .iter()
+        .map(|name| fixture(&format!("run_quiet_{name}.txt"), &hits))
+        .collect();
+    let with_paths = |args: &[&'static str]| {
+        let mut args = args.to_vec();
+        args.extend(paths.iter().map(String::as_str));
+        args
+    };
+
+    for args in [with_paths(&["-q", "hit"]), with_paths(&["-q", "-c", "hit"])] {
+        let (out, metrics) = search_with_metrics(&args);
+        assert_eq!(out, "", "{args:?}");
+        // Neither the rest of the first file nor the others are read.
+        assert_eq!(
+            (metrics.lines_scanned(), metrics.matched_lines()),
+            (1, 1),
+            "{args:?}"
+        );
+    }
+
+    // Enough lines are read to tell whether as many as required are there.
+    let (_, metrics) = search_with_metrics(&with_paths(&["-q", "--require-count=7", "hit"]));
+    assert_eq!((metrics.lines_scanned(), metrics.matched_lines()), (7, 7));
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn max_total_stops_every_thread() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("run_max_total_parallel");
+    fs::create_dir_all(&dir).unwrap();
+    let hits = (1..=50).map(|n| format!("hit {n}\n")).collect::<String>();
+    for index in 0..64 {
+        fs::write(dir.join(format!("{index}.txt")), &hits).unwrap();
+    }
+    let root = dir.to_str().unwrap();
+
+    for sort in ["--sort=none", "--sort=path"] {
+        let (out, metrics) =
+            search_with_metrics(&["-j", "8", sort, "--max-total=10", "-r", "hit", root]);
+        assert_eq!(out.lines().count(), 10, "{sort}");
+        assert_eq!(metrics.matched_lines(), 10, "{sort}");
+        // Of 3,200 lines, each thread reads at most one past the limit in
+        // each file it is searching when the limit is reached.
+        assert!(
+            metrics.lines_scanned() < 100,
+            "{sort}: {}",
+            metrics.lines_scanned()
+        );
+    }
+}
+
+#[test]
+fn first_prints_the_first_selected_line_and_reads_no_further() {
+    let lines = (1..=20_000)
+        .map(|n| match n % 5_000 {
+            0 => format!("hit {n}\n"),
+            _ => format!("line {n}\n"),
+        })
+        .collect::<String>();
+    let path = fixture("run_first.txt", &lines);
+
+    let (out, metrics) = search_with_metrics(&["--first", "-n", "hit", &path]);
+    assert_eq!(out, "5000:hit 5000\n");
+    assert_eq!(metrics.lines_scanned(), 5_000);
+    let (out, _, summary) = search(&["--first=any", "-A", "1", "hit", &path]);
+    assert_eq!(out, "hit 5000\nline 5001\n");
+    assert!(summary.found);
+    let (out, _, summary) = search(&["--first", "miss", &path]);
+    assert_eq!(out, "");
+    assert!(!summary.found);
+}
+
+#[test]
+fn first_in_order_is_the_same_on_every_run() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("run_first_tree");
+    for index in 0..32 {
+        let sub = dir.join(["x", "y"][index % 2]);
+        fs::create_dir_all(&sub).unwrap();
+        let hit = if index < 10 { "miss" } else { "hit" };
+        let text = "filler\n".repeat((32 - index) * 500) + &format!("{hit} {index}\n");
+        fs::write(sub.join(format!("{index:02}.txt")), text).unwrap();
+    }
+    let root = dir.to_str().unwrap();
+    let threads = if cfg!(feature = "parallel") { "8" } else { "1" };
+
+    // x/ holds the even files and sorts before y/, so x/10.txt is first,
+    // though the files after it are smaller and finish sooner.
+    let expected = format!("{}:hit 10\n", dir.join("x").join("10.txt").display());
+    for _ in 0..10 {
+        assert_eq!(
+            stdout(&["-j", threads, "--first", "-r", "hit", root]),
+            expected
+        );
+    }
+    assert_eq!(stdout(&["-j", "1", "--first", "-r", "hit", root]), expected);
+
+    let (out, metrics) = search_with_metrics(&["-j", threads, "--first=any", "-r", "hit", root]);
+    assert_eq!(out.lines().count(), 1, "{out}");
+    assert!(out.contains(":hit "), "{out}");
+    assert_eq!(metrics.matched_lines(), 1);
+}
+
+#[test]
+fn counts_by_file_are_sorted_and_skip_empty_and_failed_files() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("run_count_by_file");
+    fs::create_dir_all(&dir).unwrap();
+    let file = |name: &str, uses: usize| {
+        let path = dir.join(name);
+        fs::write(&path, "old_api()\nnew_api()\n".repeat(uses) + "other\n").unwrap();
+        path.to_str().unwrap().to_string()
+    };
+    let (b, a, none, c) = (
+        file("b.rs", 3),
+        file("a.rs", 1),
+        file("none.rs", 0),
+        file("c.rs", 3),
+    );
+    let missing = dir.join("gone.rs").to_str().unwrap().to_string();
+    let files = [b.as_str(), a.as_str(), none.as_str(), &missing, c.as_str()];
+    let args = |options: &[&'static str]| [options, &["old_api"], &files].concat();
+
+    let (out, err, summary) = search(&args(&["--count-by-file"]));
+    assert_eq!(out, format!("3\t{b}\n1\t{a}\n3\t{c}\n"));
+    assert!(err.contains("gone.rs"), "{err}");
+    assert_eq!(summary.failed_inputs, 1);
+
+    // Ties go by path, so b.rs and c.rs keep their order by name.
+    assert_eq!(
+        stdout(&args(&["--count-by-file", "--sort-count"])),
+        format!("3\t{b}\n3\t{c}\n1\t{a}\n")
+    );
+    assert_eq!(
+        stdout(&args(&["--count-by-file", "--sort-count", "--top=2"])),
+        format!("3\t{b}\n3\t{c}\n")
+    );
+    assert_eq!(
+        stdout(&args(&["--count-by-file", "-v", "--sort-count"])),
+        format!("4\t{b}\n4\t{c}\n2\t{a}\n1\t{none}\n")
+    );
+    let threads = if cfg!(feature = "parallel") { "4" } else { "1" };
+    assert_eq!(
+        stdout(&args(&["--count-by-file", "--sort-count", "-j", threads])),
+        format!("3\t{b}\n3\t{c}\n1\t{a}\n")
+    );
+}
+
+#[test]
+fn wc_counts_the_selected_lines_as_wc_would() {
+    // Terminators count as bytes, `\r\n` as two and a missing one as none.
+    let one = fixture(
+        "run_wc_one.txt",
+        "error: disk full\r\ninfo\nerror  twice\there\nerror at end",
+    );
+    let two = fixture("run_wc_two.txt", "info\nerror\n");
+    let empty = fixture("run_wc_empty.txt", "");
+
+    assert_eq!(stdout(&["--wc", "error", &one]), " 3  9 48\n");
+    assert_eq!(stdout(&["--wc", "-v", "error", &one]), "1 1 5\n");
+    let (out, _, summary) = search(&["--wc", "error", &one, &two, &empty]);
+    assert_eq!(
+        out,
+        format!(" 3  9 48 {one}\n 1  1  6 {two}\n 0  0  0 {empty}\n 4 10 54 total\n")
+    );
+    assert_eq!(summary.matched_lines, 4);
+    let threads = if cfg!(feature = "parallel") { "4" } else { "1" };
+    assert_eq!(
+        stdout(&["--wc", "-j", threads, "--sort=path", "error", &one, &two]),
+        format!(" 3  9 48 {one}\n 1  1  6 {two}\n 4 10 54 total\n")
+    );
+}
+
+/// Returns the offset a search with `--stats` reported having read `path`
+/// to, in what it printed as warnings.
+fn end_offset(err: &str, path: &str) -> String {
+    let reported = format!("{path} read to byte ");
+    err.lines()
+        .find_map(|line| line.strip_prefix(&reported))
+        .unwrap_or_else(|| panic!("no offset reported for {path} in {err:?}"))
+        .to_string()
+}
+
+#[test]
+fn searches_resumed_from_the_offset_reported_see_every_line_once() {
+    let path = fixture("run_resume.log", "");
+    let append = |text: &str| {
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(text.as_bytes()).unwrap();
+    };
+    let resume = |start: &str| {
+        let (out, err, _) = search(&["--stats", &format!("--start-byte={start}"), "error", &path]);
+        (out, end_offset(&err, &path))
+    };
+
+    let mut start = String::from("0");
+    let mut seen = String::new();
+    for appended in [
+        "error 1\ninfo\nerror 2\n",
+        "error 3\n",
+        "",
+        "info\nerror 4\nerror 5\n",
+    ] {
+        append(appended);
+        let (out, end) = resume(&start);
+        seen.push_str(&out);
+        start = end;
+    }
+    assert_eq!(seen, "error 1\nerror 2\nerror 3\nerror 4\nerror 5\n");
+    assert_eq!(start, "50");
+
+    // A start within a line skips the rest of it, and the lines before
+    // are only numbered if counted.
+    assert_eq!(
+        stdout(&["--start-byte=3", "-n", "--count-from-start", "error", &path]),
+        "3:error 2\n4:error 3\n6:error 4\n7:error 5\n"
+    );
+    assert_eq!(
+        stdout(&["--start-byte=13", "-b", "error", &path]),
+        "13:error 2\n21:error 3\n34:error 4\n42:error 5\n"
+    );
+
+    // A line still being written is only searched once whole, so a line
+    // appended to midway is seen once.
+    append("error 6");
+    assert_eq!(resume("50"), (String::new(), String::from("50")));
+    append(" done\n");
+    assert_eq!(
+        resume("50"),
+        (String::from("error 6 done\n"), String::from("63"))
+    );
+
+    // A file shorter than the start has been rotated, and is read whole.
+    fs::write(&path, "error 7\n").unwrap();
+    let (out, err, _) = search(&["--stats", "--start-byte=63", "error", &path]);
+    assert_eq!(out, "error 7\n");
+    assert!(err.contains("shorter than --start-byte"), "{err}");
+    assert_eq!(end_offset(&err, &path), "8");
+}
+
+#[test]
+fn counts_grouped_by_dir_add_up_each_directory_largest_first() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("run_group_by_dir");
+    let files = [
+        ("a/x.rs", 3),
+        ("a/y.rs", 1),
+        ("b/c/z.rs", 5),
+        ("b/w.rs", 2),
+        ("top.rs", 1),
+        ("b/none.rs", 0),
+    ];
+    for (name, uses) in files {
+        let path = dir.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "old_api()\n".repeat(uses) + "other\n").unwrap();
+    }
+    let root = dir.to_str().unwrap();
+    let group = |options: &[&str]| stdout(&[options, &["-r", "old_api", root]].concat());
+
+    assert_eq!(
+        group(&["--group-by-dir"]),
+        format!(
+            "5\t{root}/b/c\n  5\tz.rs\n\
+             4\t{root}/a\n  3\tx.rs\n  1\ty.rs\n\
+             2\t{root}/b\n  2\tw.rs\n\
+             1\t{root}\n  1\ttop.rs\n"
+        )
+    );
+    assert_eq!(
+        group(&["--group-by-dir", "--group-depth=1", "--top=2"]),
+        format!("7\t{root}/b\n  5\tc/z.rs\n  2\tw.rs\n4\t{root}/a\n  3\tx.rs\n  1\ty.rs\n")
+    );
+    assert_eq!(
+        group(&["--group-by-dir", "--group-depth=0"]),
+        format!("12\t{root}\n  5\tb/c/z.rs\n  3\ta/x.rs\n  2\tb/w.rs\n  1\ta/y.rs\n  1\ttop.rs\n")
+    );
+}
+
+#[test]
+fn no_unicode_prints_the_same_as_the_default_on_ascii_input() {
+    let lines = (1..=300)
+        .map(|n| match n % 7 {
+            0 => format!("{n}: ERROR disk full, error again\n"),
+            3 => format!("{n}: Error in request\n"),
+            _ => format!("{n}: ok\n"),
+        })
+        .collect::<String>();
+    let path = fixture("run_no_unicode.txt", &lines);
+    let flag_sets: [&[&str]; 10] = [
+        &[],
+        &["/i"],
+        &["-n", "/i"],
+        &["-v"],
+        &["-c", "/i"],
+        &["-o", "-b", "/i"],
+        &["-n", "-A", "1", "-B", "2"],
+        &["--column", "-n", "/i"],
+        &["--color=always", "/i"],
+        &["-l"],
+    ];
+    for flags in flag_sets {
+        let (query, rest) = (["error"], [path.as_str()]);
+        let default = search(&[&query[..], &rest, flags].concat());
+        let bytes = search(&[&["--no-unicode"], &query[..], &rest, flags].concat());
+        assert_eq!(bytes.0, default.0, "{flags:?}");
+        assert_eq!(bytes.2.matched_lines, default.2.matched_lines, "{flags:?}");
+    }
+
+    let path = fixture("run_no_unicode_text.txt", "CAFÉ\ncafé\n");
+    assert_eq!(stdout(&["--no-unicode", "café", &path, "/i"]), "café\n");
+    assert_eq!(stdout(&["café", &path, "/i"]), "CAFÉ\ncafé\n");
+    let path = fixture("run_no_unicode_bytes.txt", "");
+    fs::write(&path, b"ok\nerr \xff\n").unwrap();
+    let (out, err, _) = search(&["--no-unicode", "err", &path]);
+    assert_eq!((out.as_str(), err.as_str()), ("err \u{fffd}\n", ""));
+}
+
+#[test]
+fn sorted_parallel_output_streams_each_file_in_path_order() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("run_sort_stream");
+    fs::create_dir_all(&dir).unwrap();
+    // The first file is far larger than the buffer, so the small files
+    // after it finish first and wait, and their batches are held back.
+    for index in 0..12 {
+        let lines = if index == 0 { 20_000 } else { 40 * index };
+        let text = (1..=lines)
+            .map(|n| match n % 3 {
+                0 => format!("match {index}-{n} with a line long enough to fill batches\n"),
+                _ => format!("other {n}\n"),
+            })
+            .collect::<String>();
+        fs::write(dir.join(format!("{index:02}.txt")), text).unwrap();
+    }
+    let root = dir.to_str().unwrap();
+    let threads = if cfg!(feature = "parallel") { "4" } else { "1" };
+    let flag_sets: [&[&str]; 3] = [&["-n"], &["--heading", "-n"], &["-C", "1"]];
+    for flags in flag_sets {
+        let args = |extra: &[&str]| {
+            let base = ["--sort=path", "-r", "match", root];
+            stdout(&[&base[..], flags, extra].concat())
+        };
+        let expected = args(&[]);
+        assert_eq!(
+            args(&["-j", threads, "--sort-buffer=1K"]),
+            expected,
+            "{flags:?}"
+        );
+        assert_eq!(args(&["-j", threads]), expected, "{flags:?}");
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn fifos_are_skipped_unless_named_and_read() {
+    use std::process::Command;
+
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("run_devices");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let fifo = dir.join("pipe");
+    let made = Command::new("mkfifo").arg(&fifo).status().unwrap();
+    assert!(made.success());
+    fs::write(dir.join("app.log"), "error in a file\n").unwrap();
+    let (root, named) = (dir.to_str().unwrap(), fifo.to_str().unwrap());
+
+    // Nothing writes to the FIFO, so reading it would never end.
+    let (out, err, summary) = search(&["error", named]);
+    assert_eq!(out, "");
+    assert_eq!(err, format!("minigrep: {named}: device, FIFO, or socket\n"));
+    assert!(!summary.found);
+    let (_, err, _) = search(&["--stats", "-r", "--devices=read", "error", root]);
+    assert!(err.contains("1 files skipped (0 too large, 0 filtered out, 1 devices, 0 unreadable)"));
+
+    let pipe = fifo.clone();
+    let writer = thread::spawn(move || fs::write(pipe, "error in a pipe\nfine\n").unwrap());
+    let (out, err, summary) = search(&["--devices=read", "error", named]);
+    writer.join().unwrap();
+    assert_eq!((out.as_str(), err.as_str()), ("error in a pipe\n", ""));
+    assert_eq!(summary.matched_lines, 1);
+}
+
+#[test]
+fn every_read_strategy_searches_the_same_bytes() {
+    use minigrep_cli_tool::mmap::MMAP_MIN_LEN;
+
+    let mut contents = String::new();
+    for i in 0.. {
+        if contents.len() as u64 > MMAP_MIN_LEN {
+            break;
+        }
+        let level = ["error", "warning", "info"][i % 3];
+        let end = if i % 5 == 0 { "\r\n" } else { "\n" };
+        contents.push_str(&format!("{level}: request {i} took {}ms{end}", i % 997));
+    }
+    // The last line has no terminator, right at the end of the mapping.
+    contents.push_str("error: unterminated");
+    let large = fixture("read_strategy_large.log", &contents);
+    let small = fixture("read_strategy_small.log", "error: small\nfine\n");
+    let empty = fixture("read_strategy_empty.log", "");
+
+    for flags in [
+        &["-n", "error"][..],
+        &["-c", "error"],
+        &["-o", "-b", "error: request 1"],
+        &["-v", "-n", "error"],
+        &["-C", "1", "--max-total=3", "warning"],
+        &["--wc", "error"],
+    ] {
+        let args = |strategy: &[&str]| {
+            let mut args = strategy.to_vec();
+            args.extend(flags);
+            args.extend([large.as_str(), small.as_str(), empty.as_str()]);
+            stdout(&args)
+        };
+        let expected = args(&[]);
+        assert!(!expected.is_empty(), "{flags:?}");
+        for strategy in ["buffered", "mmap", "auto"] {
+            let flag = format!("--read-strategy={strategy}");
+            assert_eq!(args(&[&flag]), expected, "{strategy} {flags:?}");
+        }
+    }
+
+    let mapped = if cfg!(unix) { "mmap" } else { "buffered" };
+    let (_, err, _) = search(&["--stats", "--read-strategy=auto", "error", &large, &small]);
+    assert!(
+        err.contains(&format!("{large} read with {mapped}\n")),
+        "{err}"
+    );
+    assert!(
+        err.contains(&format!("{small} read with buffered\n")),
+        "{err}"
+    );
+    let (_, err, _) = search(&["--stats", "--read-strategy=mmap", "error", &small]);
+    assert!(
+        err.contains(&format!("{small} read with {mapped}\n")),
+        "{err}"
+    );
+    let (_, err, _) = search(&["--stats", "error", &small]);
+    assert!(!err.contains("read with"), "{err}");
+}
+
+#[test]
+fn colors_come_from_the_environment_the_config_was_built_in() {
+    let path = fixture("run_colors.txt", "an error\n");
+    let colored = |vars: &'static [(&str, &str)]| {
+        let args = ["minigrep", "error", &path].map(OsString::from);
+        let env = |name: &str| {
+            vars.iter()
+                .find(|(var, _)| *var == name)
+                .map(|(_, value)| value.to_string())
+        };
+        let config = Config::build(args.into_iter(), true, env).unwrap();
+        let (mut out, mut err) = (Vec::new(), Vec::new());
+        run_with_writer(&config, &mut out, &mut err).unwrap();
+        String::from_utf8(out).unwrap()
+    };
+    assert_eq!(colored(&[]), "an error\n");
+    assert_eq!(
+        colored(&[("CLICOLOR_FORCE", "1"), ("MINIGREP_COLORS", "ms=35")]),
+        "an \x1b[35merror\x1b[0m\n"
+    );
+    assert_eq!(
+        colored(&[("CLICOLOR_FORCE", "1"), ("NO_COLOR", "1")]),
+        "an error\n"
+    );
+}